@@ -12,6 +12,14 @@ pub struct ManagedApiMetadata {
     /// the contact email for the API (goes into OpenAPI document)
     pub contact_email: Option<&'static str>,
 
+    /// the URL of external documentation for the API (goes into OpenAPI
+    /// document as `externalDocs`)
+    pub external_docs_url: Option<&'static str>,
+
+    /// tags for selecting groups of APIs, e.g. to validate them together (not
+    /// part of the OpenAPI document)
+    pub tags: &'static [&'static str],
+
     /// extra, dynamically-typed metadata for internal use
     pub extra: serde_json::Value,
 }
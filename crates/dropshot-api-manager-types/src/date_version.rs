@@ -0,0 +1,221 @@
+// Copyright 2026 Oxide Computer Company
+
+use anyhow::ensure;
+use std::fmt;
+
+/// A date-encoded API version, such as `2025122300.0.0`.
+///
+/// The major version encodes the date the version was added, as `YYYYMMDD`,
+/// followed by a two-digit serial number (`NN`) that distinguishes versions
+/// added on the same day. The minor and patch versions are always 0. Since
+/// the fields are ordered from most to least significant, date versions order
+/// the same way as the semvers they correspond to.
+///
+/// Date versions can be used with `api_versions!` by writing out the major
+/// version:
+///
+/// ```
+/// use dropshot_api_manager_types::{DateVersion, api_versions};
+///
+/// api_versions!([
+///     (2025122301, ADD_FOOBAR_OPERATION),
+///     (2025122300, INITIAL),
+/// ]);
+///
+/// let version = DateVersion::from_semver(&VERSION_ADD_FOOBAR_OPERATION)
+///     .unwrap();
+/// assert_eq!(version, DateVersion::new(2025, 12, 23, 1));
+/// assert_eq!(version.to_semver(), VERSION_ADD_FOOBAR_OPERATION);
+/// assert!(version > DateVersion::from_semver(&VERSION_INITIAL).unwrap());
+///
+/// // Versions that don't encode a valid date are rejected.
+/// assert!(DateVersion::from_semver(&semver::Version::new(2, 0, 0)).is_err());
+/// assert!(
+///     DateVersion::from_semver(&semver::Version::new(2025023000, 0, 0))
+///         .is_err()
+/// );
+/// ```
+///
+/// To require that every supported version of an API is date-encoded, use
+/// [`VersionScheme::DateEncoded`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DateVersion {
+    year: u16,
+    month: u8,
+    day: u8,
+    serial: u8,
+}
+
+impl DateVersion {
+    /// Creates the `serial`th version added on the given date.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the date is invalid, if the year doesn't have four digits,
+    /// or if `serial` is greater than 99.
+    #[track_caller]
+    pub const fn new(year: u16, month: u8, day: u8, serial: u8) -> DateVersion {
+        assert!(
+            matches!(year, 1000..=9999),
+            "year of a date version must have four digits"
+        );
+        assert!(
+            matches!(month, 1..=12),
+            "month of a date version must be between 1 and 12"
+        );
+        assert!(
+            day != 0 && day <= days_in_month(year, month),
+            "day of a date version must be within its month"
+        );
+        assert!(serial <= 99, "serial of a date version must be at most 99");
+        DateVersion { year, month, day, serial }
+    }
+
+    /// Interprets `version` as a date version.
+    ///
+    /// Returns an error if `version` isn't of the form `YYYYMMDDNN.0.0` for a
+    /// valid date.
+    pub fn from_semver(version: &semver::Version) -> anyhow::Result<Self> {
+        ensure!(
+            version.minor == 0
+                && version.patch == 0
+                && version.pre.is_empty()
+                && version.build.is_empty(),
+            "version {} is not date-encoded: expected minor and patch \
+             versions of 0 and no pre-release or build metadata",
+            version,
+        );
+        let major = version.major;
+        ensure!(
+            (1_000_000_000..10_000_000_000).contains(&major),
+            "version {} is not date-encoded: expected a major version of the \
+             form YYYYMMDDNN",
+            version,
+        );
+        // The casts are lossless given the range check above.
+        let year = (major / 1_000_000) as u16;
+        let month = (major / 10_000 % 100) as u8;
+        let day = (major / 100 % 100) as u8;
+        let serial = (major % 100) as u8;
+        ensure!(
+            (1..=12).contains(&month)
+                && (1..=days_in_month(year, month)).contains(&day),
+            "version {} is not date-encoded: {:04}-{:02}-{:02} is not a \
+             valid date",
+            version,
+            year,
+            month,
+            day,
+        );
+        Ok(DateVersion { year, month, day, serial })
+    }
+
+    /// Returns the semver for this version.
+    pub const fn to_semver(&self) -> semver::Version {
+        let major = self.year as u64 * 1_000_000
+            + self.month as u64 * 10_000
+            + self.day as u64 * 100
+            + self.serial as u64;
+        semver::Version::new(major, 0, 0)
+    }
+
+    /// Returns the year the version was added.
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// Returns the month the version was added, from 1 to 12.
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// Returns the day of the month the version was added, starting at 1.
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// Returns the serial number of the version among those added that day.
+    pub fn serial(&self) -> u8 {
+        self.serial
+    }
+}
+
+impl fmt::Display for DateVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.to_semver().fmt(f)
+    }
+}
+
+impl From<DateVersion> for semver::Version {
+    fn from(version: DateVersion) -> Self {
+        version.to_semver()
+    }
+}
+
+impl TryFrom<&semver::Version> for DateVersion {
+    type Error = anyhow::Error;
+
+    fn try_from(version: &semver::Version) -> anyhow::Result<Self> {
+        Self::from_semver(version)
+    }
+}
+
+const fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The scheme that every supported version of an API must follow.
+///
+/// Mixing schemes within an API makes its versions hard to compare at a
+/// glance, and can let a version sort in an unexpected place. Set an API's
+/// scheme with `ManagedApi::with_version_scheme` in the Dropshot API manager,
+/// which then rejects supported versions that don't follow it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum VersionScheme {
+    /// Versions of the form `N.0.0`, as defined by `api_versions!`, where `N`
+    /// has fewer digits than a date-encoded version.
+    Sequential,
+
+    /// Date-encoded versions of the form `YYYYMMDDNN.0.0`. See
+    /// [`DateVersion`].
+    DateEncoded,
+}
+
+impl VersionScheme {
+    /// Returns an error if `version` doesn't follow this scheme.
+    pub fn check(&self, version: &semver::Version) -> anyhow::Result<()> {
+        match self {
+            VersionScheme::Sequential => {
+                ensure!(
+                    version.minor == 0
+                        && version.patch == 0
+                        && version.pre.is_empty()
+                        && version.build.is_empty()
+                        && version.major < 1_000_000_000,
+                    "version {} is not sequential: expected a version of the \
+                     form N.0.0, with N less than 1000000000",
+                    version,
+                );
+                Ok(())
+            }
+            VersionScheme::DateEncoded => {
+                DateVersion::from_semver(version).map(|_| ())
+            }
+        }
+    }
+}
+
+impl fmt::Display for VersionScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            VersionScheme::Sequential => "sequential",
+            VersionScheme::DateEncoded => "date-encoded",
+        })
+    }
+}
@@ -1,4 +1,4 @@
-// Copyright 2025 Oxide Computer Company
+// Copyright 2026 Oxide Computer Company
 
 //! Shared types for the Dropshot API manager.
 //!
@@ -12,12 +12,16 @@
 //! to depend on this crate for access to the `api_versions!` macro.
 
 mod apis;
+mod date_version;
+mod provider;
 mod validation;
 mod versions;
 
 pub use apis::*;
+pub use date_version::*;
 // Re-export these types for consumers of `api_versions!`.
 pub use paste::paste;
+pub use provider::*;
 pub use semver;
 pub use validation::*;
 pub use versions::*;
@@ -0,0 +1,70 @@
+// Copyright 2026 Oxide Computer Company
+
+use crate::ManagedApiMetadata;
+
+/// Produces OpenAPI documents for an API, independent of the Dropshot version
+/// the API is defined with.
+///
+/// The Dropshot API manager is built against a single version of Dropshot, so
+/// an API crate that depends on a different version can't hand it an
+/// `api_description` function directly. Instead, the API crate implements this
+/// trait using its own version of Dropshot, and the manager only deals with the
+/// resulting JSON. This allows API crates within a workspace to be upgraded to
+/// newer Dropshot versions one at a time.
+///
+/// A typical implementation looks like:
+///
+/// ```ignore
+/// struct MyApiProvider;
+///
+/// impl ApiDescriptionProvider for MyApiProvider {
+///     fn openapi_document(
+///         &self,
+///         info: &ApiDocumentInfo<'_>,
+///     ) -> anyhow::Result<Vec<u8>> {
+///         let description = my_api_mod::stub_api_description()
+///             .map_err(|error| anyhow::anyhow!("{error}"))?;
+///         let mut openapi =
+///             description.openapi(info.title, info.version.clone());
+///         if let Some(description) = info.metadata.description {
+///             openapi.description(description);
+///         }
+///         let mut contents = Vec::new();
+///         openapi.write(&mut contents)?;
+///         Ok(contents)
+///     }
+/// }
+/// ```
+pub trait ApiDescriptionProvider: Send + Sync {
+    /// Returns the OpenAPI document for the version described by `info`,
+    /// serialized as JSON.
+    fn openapi_document(
+        &self,
+        info: &ApiDocumentInfo<'_>,
+    ) -> anyhow::Result<Vec<u8>>;
+}
+
+/// Information passed to [`ApiDescriptionProvider::openapi_document`].
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub struct ApiDocumentInfo<'a> {
+    /// The title of the API (goes into the OpenAPI document).
+    pub title: &'a str,
+
+    /// The version of the API to produce a document for.
+    pub version: &'a semver::Version,
+
+    /// Optional metadata about the API (goes into the OpenAPI document).
+    pub metadata: &'a ManagedApiMetadata,
+}
+
+impl<'a> ApiDocumentInfo<'a> {
+    /// Creates a new `ApiDocumentInfo`.
+    pub fn new(
+        title: &'a str,
+        version: &'a semver::Version,
+        metadata: &'a ManagedApiMetadata,
+    ) -> Self {
+        Self { title, version, metadata }
+    }
+}
@@ -1,7 +1,7 @@
 // Copyright 2026 Oxide Computer Company
 
 use crate::{ManagedApiMetadata, Versions};
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use std::{fmt, ops::Deref};
 
 /// Context for validation of OpenAPI documents.
@@ -82,6 +82,113 @@ impl<'a> ValidationContext<'a> {
     ) {
         self.backend.record_file_contents(path.into(), contents);
     }
+
+    /// Reports a problem that can be fixed automatically.
+    ///
+    /// In check mode, the problem is reported along with the fix's
+    /// description, and the check fails.
+    ///
+    /// In generate mode, the fix is applied.
+    pub fn report_fixable_problem(&mut self, fix: CustomFix) {
+        self.backend.report_fixable_problem(fix);
+    }
+}
+
+/// A fix for a problem found during validation, reported via
+/// [`ValidationContext::report_fixable_problem`].
+///
+/// This is meant for project-specific maintenance that doesn't fit
+/// [`ValidationContext::record_file_contents`], e.g. removing a file that's no
+/// longer needed, or updating several files at once.
+pub struct CustomFix {
+    description: String,
+    is_needed: Box<CustomFixFn<bool>>,
+    apply: Box<CustomFixFn<Vec<String>>>,
+}
+
+type CustomFixFn<T> = dyn Fn(&Utf8Path) -> anyhow::Result<T> + Send + Sync;
+
+impl CustomFix {
+    /// Creates a new fix.
+    ///
+    /// `description` describes what the fix does, phrased as an action (e.g.
+    /// "regenerate docs/index.md"). It is shown in check mode.
+    ///
+    /// Both closures are called with the root of the repository. `is_needed`
+    /// returns true if there's a problem to fix; if it returns false, nothing
+    /// is reported. `apply` is called in generate mode, and returns a
+    /// description of each change made.
+    pub fn new<N, A>(
+        description: impl Into<String>,
+        is_needed: N,
+        apply: A,
+    ) -> Self
+    where
+        N: Fn(&Utf8Path) -> anyhow::Result<bool> + Send + Sync + 'static,
+        A: Fn(&Utf8Path) -> anyhow::Result<Vec<String>> + Send + Sync + 'static,
+    {
+        Self {
+            description: description.into(),
+            is_needed: Box::new(is_needed),
+            apply: Box::new(apply),
+        }
+    }
+
+    /// Creates a fix that removes a file. The fix is needed if the file
+    /// exists.
+    ///
+    /// The path is treated as relative to the root of the repository.
+    pub fn remove_file(path: impl Into<Utf8PathBuf>) -> Self {
+        let path = path.into();
+        let check_path = path.clone();
+        Self::new(
+            format!("remove {path}"),
+            move |repo_root| {
+                let full_path = repo_root.join(&check_path);
+                full_path.try_exists().map_err(|error| {
+                    anyhow::Error::new(error)
+                        .context(format!("failed to check {full_path}"))
+                })
+            },
+            move |repo_root| {
+                let full_path = repo_root.join(&path);
+                std::fs::remove_file(&full_path).map_err(|error| {
+                    anyhow::Error::new(error)
+                        .context(format!("failed to remove {full_path}"))
+                })?;
+                Ok(vec![format!("removed {full_path}")])
+            },
+        )
+    }
+
+    /// Returns the description of this fix.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Returns true if this fix needs to be applied.
+    ///
+    /// Not part of the public API -- only called by the OpenAPI manager.
+    #[doc(hidden)]
+    pub fn is_needed(&self, repo_root: &Utf8Path) -> anyhow::Result<bool> {
+        (self.is_needed)(repo_root)
+    }
+
+    /// Applies this fix.
+    ///
+    /// Not part of the public API -- only called by the OpenAPI manager.
+    #[doc(hidden)]
+    pub fn apply(&self, repo_root: &Utf8Path) -> anyhow::Result<Vec<String>> {
+        (self.apply)(repo_root)
+    }
+}
+
+impl fmt::Debug for CustomFix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomFix")
+            .field("description", &self.description)
+            .finish_non_exhaustive()
+    }
 }
 
 /// The backend for validation.
@@ -98,6 +205,7 @@ pub trait ValidationBackend {
     fn metadata(&self) -> &ManagedApiMetadata;
     fn report_error(&mut self, error: anyhow::Error);
     fn record_file_contents(&mut self, path: Utf8PathBuf, contents: Vec<u8>);
+    fn report_fixable_problem(&mut self, fix: CustomFix);
 }
 
 /// A lockstep API spec filename.
@@ -496,6 +604,24 @@ impl ApiIdent {
         format!("{self}-latest.json")
     }
 
+    /// Given an API identifier, return the basename its "latest" symlink is
+    /// created under before being renamed into place
+    pub fn versioned_api_latest_symlink_temp(&self) -> String {
+        format!("{self}-latest.json.tmp")
+    }
+
+    /// Given an API identifier, return the basename of its "latest" pointer
+    /// file (used instead of the symlink for some APIs)
+    pub fn versioned_api_latest_pointer_file(&self) -> String {
+        format!("{self}-latest.txt")
+    }
+
+    /// Given an API identifier, return the basename of the document combining
+    /// all of its supported versions (maintained for some APIs)
+    pub fn versioned_api_combined_file(&self) -> String {
+        format!("{self}-all.json")
+    }
+
     /// Given an API identifier and a file name, determine if we're looking at
     /// this API's "latest" symlink.
     pub fn versioned_api_is_latest_symlink(&self, base_name: &str) -> bool {
@@ -503,4 +629,15 @@ impl ApiIdent {
             .strip_prefix(self.0.as_str())
             .is_some_and(|rest| rest == "-latest.json")
     }
+
+    /// Given an API identifier and a file name, determine if we're looking at
+    /// a temporary "latest" symlink left behind by an interrupted update.
+    pub fn versioned_api_is_latest_symlink_temp(
+        &self,
+        base_name: &str,
+    ) -> bool {
+        base_name
+            .strip_prefix(self.0.as_str())
+            .is_some_and(|rest| rest == "-latest.json.tmp")
+    }
 }
@@ -245,15 +245,29 @@ impl<'a> DoubleEndedIterator for IterVersionsSemversInner<'a> {
 /// pub const VERSION_INITIAL: semver::Version = semver::Version::new(1, 0, 0);
 /// ```
 ///
-/// It also defines two functions:
+/// It also defines a table of every version along with its label, in the
+/// order listed (newest first), so that servers and tools can look up labels
+/// without parsing strings or keeping a separate list:
+///
+/// ```
+/// # pub const VERSION_ADD_FOOBAR_OPERATION: semver::Version =
+/// #     semver::Version::new(2, 0, 0);
+/// # pub const VERSION_INITIAL: semver::Version = semver::Version::new(1, 0, 0);
+/// pub const VERSION_METADATA: &[(&str, semver::Version)] = &[
+///     ("ADD_FOOBAR_OPERATION", VERSION_ADD_FOOBAR_OPERATION),
+///     ("INITIAL", VERSION_INITIAL),
+/// ];
+/// ```
+///
+/// Finally, it defines two functions:
 ///
 /// * `pub fn supported_versions() -> SupportedVersions` that,
 ///   as the name suggests, returns a [`SupportedVersions`] that describes these
 ///   two supported API versions.
 ///
-/// * `pub fn latest_version() -> semver::Version` that returns the latest
-///   supported API version. The latest supported version is the first version
-///   in the list (hence versions must be in descending order).
+/// * `pub const fn latest_version() -> semver::Version` that returns the
+///   latest supported API version. The latest supported version is the first
+///   version in the list (hence versions must be in descending order).
 // Design constraints:
 // - For each new API version, we need a developer-chosen semver and label that
 //   can be used to construct an identifier.
@@ -333,11 +347,18 @@ macro_rules! api_versions_picky {
                     $crate::semver::Version::new($major, $minor, $patch);
             )*
 
+            pub const VERSION_METADATA: &[(&str, $crate::semver::Version)] = &[
+                (stringify!($latest_name), [<VERSION_ $latest_name>]),
+                $( (stringify!($name), [<VERSION_ $name>]) ),*
+            ];
+
             pub fn supported_versions() -> $crate::SupportedVersions {
-                let mut literal_versions = vec![
-                    $crate::SupportedVersion::new([<VERSION_ $latest_name>], stringify!($latest_name)),
-                    $( $crate::SupportedVersion::new([<VERSION_ $name>], stringify!($name)) ),*
-                ];
+                let mut literal_versions: Vec<_> = VERSION_METADATA
+                    .iter()
+                    .map(|(label, version)| {
+                        $crate::SupportedVersion::new(version.clone(), *label)
+                    })
+                    .collect();
                 literal_versions.reverse();
                 $crate::SupportedVersions::new(literal_versions)
             }
@@ -0,0 +1,242 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Maintains copies of OpenAPI documents under API aliases.
+//!
+//! See [`ManagedApi::with_alias`] for more.
+
+use crate::{
+    apis::ManagedApi,
+    resolved::replace_symlink,
+    shared_schemas,
+    spec_files_local::IgnoreGlobs,
+    validation::{overwrite_file, read_opt},
+};
+use anyhow::{Context, anyhow};
+use camino::{Utf8Path, Utf8PathBuf};
+use dropshot_api_manager_types::ApiIdent;
+use std::{collections::BTreeMap, io};
+
+/// A file within the copy of an API's documents.
+#[derive(Debug, Eq, PartialEq)]
+enum AliasEntry {
+    /// A regular file (a JSON document or a Git stub) with these contents.
+    File(Vec<u8>),
+    /// A symlink (the "latest" link) with this target.
+    Symlink(String),
+}
+
+/// Describes the differences between an alias's copy of an API's documents and
+/// the documents themselves.
+#[derive(Debug, Default)]
+pub(crate) struct AliasDiff {
+    /// Paths (relative to the OpenAPI documents directory) that are missing
+    /// from the copy, or whose contents differ.
+    pub(crate) stale: Vec<Utf8PathBuf>,
+    /// Paths in the copy that don't correspond to any of the API's documents.
+    pub(crate) extra: Vec<Utf8PathBuf>,
+}
+
+impl AliasDiff {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.stale.is_empty() && self.extra.is_empty()
+    }
+
+    /// Returns all of the paths that differ.
+    pub(crate) fn paths(&self) -> impl Iterator<Item = &Utf8PathBuf> + '_ {
+        self.stale.iter().chain(&self.extra)
+    }
+}
+
+/// Compares the copy of `api`'s documents under `alias` with the documents
+/// themselves, in the OpenAPI documents directory `dir`.
+pub(crate) fn diff_alias(
+    dir: &Utf8Path,
+    ignore_globs: &IgnoreGlobs,
+    api: &ManagedApi,
+    alias: &ApiIdent,
+) -> anyhow::Result<AliasDiff> {
+    let expected = expected_entries(dir, ignore_globs, api, alias)?;
+    let actual = actual_entries(dir, ignore_globs, api, alias)?;
+
+    let mut diff = AliasDiff::default();
+    for (path, entry) in &expected {
+        if actual.get(path) != Some(entry) {
+            diff.stale.push(path.clone());
+        }
+    }
+    for path in actual.keys() {
+        if !expected.contains_key(path) {
+            diff.extra.push(path.clone());
+        }
+    }
+    Ok(diff)
+}
+
+/// Updates the copy of `api`'s documents under `alias` to match the documents
+/// themselves, in the OpenAPI documents directory `dir`.
+///
+/// Returns a description of each change made.
+pub(crate) fn sync_alias(
+    dir: &Utf8Path,
+    ignore_globs: &IgnoreGlobs,
+    api: &ManagedApi,
+    alias: &ApiIdent,
+) -> anyhow::Result<Vec<String>> {
+    let expected = expected_entries(dir, ignore_globs, api, alias)?;
+    let actual = actual_entries(dir, ignore_globs, api, alias)?;
+
+    let mut steps = Vec::new();
+    for path in actual.keys() {
+        if !expected.contains_key(path) {
+            let full_path = dir.join(path);
+            fs_err::remove_file(&full_path)?;
+            steps.push(format!("removed {}", full_path));
+        }
+    }
+    for (path, entry) in &expected {
+        if actual.get(path) == Some(entry) {
+            continue;
+        }
+        let full_path = dir.join(path);
+        match entry {
+            AliasEntry::File(contents) => {
+                let _ = overwrite_file(&full_path, contents)?;
+                steps.push(format!("wrote {}", full_path));
+            }
+            AliasEntry::Symlink(target) => {
+                replace_symlink(target, &full_path)?;
+                steps.push(format!("wrote link {} -> {}", full_path, target));
+            }
+        }
+    }
+    Ok(steps)
+}
+
+/// Returns the entries that the copy under `alias` should have, based on the
+/// API's documents.
+fn expected_entries(
+    dir: &Utf8Path,
+    ignore_globs: &IgnoreGlobs,
+    api: &ManagedApi,
+    alias: &ApiIdent,
+) -> anyhow::Result<BTreeMap<Utf8PathBuf, AliasEntry>> {
+    let ident = api.ident();
+    if api.is_lockstep() {
+        let mut entries = BTreeMap::new();
+        if let Some(contents) = read_opt(&dir.join(format!("{}.json", ident)))?
+        {
+            entries.insert(
+                Utf8PathBuf::from(format!("{}.json", alias)),
+                AliasEntry::File(contents),
+            );
+        }
+        return Ok(entries);
+    }
+
+    // Every file in the API's directory is named with the API identifier as a
+    // prefix (including the "latest" symlink and its target), so replace it
+    // with the alias.
+    let prefix = format!("{}-", ident);
+    let alias_prefix = format!("{}-", alias);
+    let rename = |name: &str| {
+        name.strip_prefix(&prefix).map(|rest| format!("{alias_prefix}{rest}"))
+    };
+
+    // Copies don't include shared schemas, so reassemble documents whose
+    // schemas are stored in the shared directory. (Documents that can't be
+    // reassembled are reported when documents are loaded.)
+    let api_dir = dir.join(ident.as_str());
+    let join = |contents: Vec<u8>| {
+        shared_schemas::join(contents, |schema| {
+            Ok(fs_err::read(api_dir.join(schema))?)
+        })
+        .unwrap_or_else(|(_, contents)| contents)
+    };
+
+    let entries = read_dir_entries(dir, ident, ignore_globs)?
+        .into_iter()
+        .filter_map(|(name, entry)| {
+            let name = rename(&name)?;
+            let entry = match entry {
+                AliasEntry::Symlink(target) => {
+                    AliasEntry::Symlink(rename(&target).unwrap_or(target))
+                }
+                AliasEntry::File(contents) => AliasEntry::File(join(contents)),
+            };
+            Some((Utf8PathBuf::from(alias.as_str()).join(name), entry))
+        })
+        .collect();
+    Ok(entries)
+}
+
+/// Returns the entries that the copy under `alias` currently has.
+fn actual_entries(
+    dir: &Utf8Path,
+    ignore_globs: &IgnoreGlobs,
+    api: &ManagedApi,
+    alias: &ApiIdent,
+) -> anyhow::Result<BTreeMap<Utf8PathBuf, AliasEntry>> {
+    if api.is_lockstep() {
+        let path = Utf8PathBuf::from(format!("{}.json", alias));
+        let mut entries = BTreeMap::new();
+        if let Some(contents) = read_opt(&dir.join(&path))? {
+            entries.insert(path, AliasEntry::File(contents));
+        }
+        return Ok(entries);
+    }
+
+    let entries = read_dir_entries(dir, alias, ignore_globs)?
+        .into_iter()
+        .map(|(name, entry)| {
+            (Utf8PathBuf::from(alias.as_str()).join(name), entry)
+        })
+        .collect();
+    Ok(entries)
+}
+
+/// Reads the files in the `subdir` directory of `dir`, keyed by file name.
+/// Files matching `ignore_globs` are skipped, and a missing directory is
+/// treated as empty.
+fn read_dir_entries(
+    dir: &Utf8Path,
+    subdir: &ApiIdent,
+    ignore_globs: &IgnoreGlobs,
+) -> anyhow::Result<BTreeMap<String, AliasEntry>> {
+    let dir = dir.join(subdir.as_str());
+    let read_dir = match dir.read_dir_utf8() {
+        Ok(read_dir) => read_dir,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            return Ok(BTreeMap::new());
+        }
+        Err(error) => {
+            return Err(anyhow!(error).context(format!("readdir {:?}", dir)));
+        }
+    };
+
+    let mut entries = BTreeMap::new();
+    for entry in read_dir {
+        let entry =
+            entry.with_context(|| format!("readdir {:?} entry", dir))?;
+        let path = entry.path();
+        if ignore_globs
+            .is_ignored(&Utf8Path::new(subdir.as_str()).join(entry.file_name()))
+        {
+            continue;
+        }
+        let file_type = entry
+            .file_type()
+            .with_context(|| format!("file type of {:?}", path))?;
+        let alias_entry = if file_type.is_symlink() {
+            let target = path
+                .read_link_utf8()
+                .with_context(|| format!("reading link {:?}", path))?;
+            AliasEntry::Symlink(target.into_string())
+        } else if file_type.is_file() {
+            AliasEntry::File(fs_err::read(path)?)
+        } else {
+            continue;
+        };
+        entries.insert(entry.file_name().to_owned(), alias_entry);
+    }
+    Ok(entries)
+}
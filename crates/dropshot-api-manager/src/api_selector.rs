@@ -0,0 +1,107 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Selecting groups of managed APIs.
+//!
+//! See [`ApiSelector`] for more.
+
+use crate::apis::ManagedApi;
+use anyhow::Context;
+use globset::{Glob, GlobMatcher};
+
+/// Selects a group of managed APIs, e.g. to validate them with
+/// [`ManagedApis::with_validation_for`].
+///
+/// APIs can be selected by a glob pattern on their identifiers, or by one of
+/// the tags in their [`ManagedApiMetadata::tags`]. A variant API generated for
+/// a profile has its own identifier, but the same tags as the API it's derived
+/// from.
+///
+/// [`ManagedApis::with_validation_for`]: crate::ManagedApis::with_validation_for
+/// [`ManagedApiMetadata::tags`]: dropshot_api_manager_types::ManagedApiMetadata::tags
+#[derive(Clone, Debug)]
+pub struct ApiSelector {
+    kind: SelectorKind,
+}
+
+#[derive(Clone, Debug)]
+enum SelectorKind {
+    All,
+    IdentGlob(GlobMatcher),
+    Tag(String),
+}
+
+impl ApiSelector {
+    /// Selects every API.
+    pub fn all() -> Self {
+        Self { kind: SelectorKind::All }
+    }
+
+    /// Selects the APIs whose identifiers match the glob `pattern`, e.g.
+    /// `sled-*`.
+    ///
+    /// Returns an error if `pattern` is not a valid glob.
+    pub fn ident_glob(pattern: impl Into<String>) -> anyhow::Result<Self> {
+        let pattern = pattern.into();
+        let matcher = Glob::new(&pattern)
+            .with_context(|| format!("invalid API pattern {:?}", pattern))?
+            .compile_matcher();
+        Ok(Self { kind: SelectorKind::IdentGlob(matcher) })
+    }
+
+    /// Selects the APIs tagged with `tag` in their metadata.
+    pub fn tag(tag: impl Into<String>) -> Self {
+        Self { kind: SelectorKind::Tag(tag.into()) }
+    }
+
+    /// Returns true if `api` is selected.
+    pub fn matches(&self, api: &ManagedApi) -> bool {
+        match &self.kind {
+            SelectorKind::All => true,
+            SelectorKind::IdentGlob(matcher) => {
+                matcher.is_match(api.ident().as_str())
+            }
+            SelectorKind::Tag(tag) => {
+                api.metadata().tags.iter().any(|t| *t == tag.as_str())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apis::{DocumentSource, ExternalApiConfig};
+    use dropshot_api_manager_types::{ManagedApiMetadata, Versions};
+
+    #[test]
+    fn test_matches() {
+        let api = |ident: &'static str, tags: &'static [&'static str]| {
+            ManagedApi::from(ExternalApiConfig {
+                ident,
+                versions: Versions::Lockstep {
+                    version: semver::Version::new(1, 0, 0),
+                },
+                title: "Test API",
+                metadata: ManagedApiMetadata { tags, ..Default::default() },
+                source: DocumentSource::Static(Vec::new),
+            })
+        };
+        let sled_agent = api("sled-agent", &["internal"]);
+        let nexus = api("nexus", &["external", "internal"]);
+        let other = api("other", &[]);
+
+        let all = ApiSelector::all();
+        assert!([&sled_agent, &nexus, &other].iter().all(|a| all.matches(a)));
+
+        let sled = ApiSelector::ident_glob("sled-*").unwrap();
+        assert!(sled.matches(&sled_agent));
+        assert!(!sled.matches(&nexus));
+
+        let external = ApiSelector::tag("external");
+        assert!(!external.matches(&sled_agent));
+        assert!(external.matches(&nexus));
+        assert!(!external.matches(&other));
+
+        assert!(ApiSelector::ident_glob("sled-[").is_err());
+    }
+}
@@ -1,16 +1,46 @@
 // Copyright 2026 Oxide Computer Company
 
-use crate::validation::DynValidationFn;
+use crate::{
+    api_selector::ApiSelector,
+    approvals::BreakingChangeApprovals,
+    changed_files::SourceGlobs,
+    deprecations::DeprecationSummary,
+    docs_link_lint::DocsLinkLint,
+    environment::{BlessedSource, Environment, ErrorAccumulator},
+    example_lint::ExampleLint,
+    flattened::FlattenedDocument,
+    freeze::DocumentFreeze,
+    json_format::JsonFormat,
+    lockstep_version::LockstepVersionPolicy,
+    naming_lint::NamingLint,
+    next_version::{latest_version_in_use, next_version_after},
+    output::Styles,
+    pins::ConsumerPins,
+    profiles::ApiProfile,
+    shared_schemas::SHARED_SCHEMAS_DIR,
+    signing::DocumentSigning,
+    spec_files_local::IgnoreGlobs,
+    text_lint::TextLint,
+    type_replacements::TypeReplacements,
+    unstable::UnstableOperations,
+    validation::DynValidationFn,
+};
 use anyhow::{Context, bail};
+use camino::Utf8Path;
 use dropshot::{ApiDescription, ApiDescriptionBuildErrors, StubContext};
 use dropshot_api_manager_types::{
-    ApiIdent, IterVersionsSemvers, ManagedApiMetadata, SupportedVersion,
-    ValidationContext, Versions,
+    ApiDescriptionProvider, ApiDocumentInfo, ApiIdent, IterVersionsSemvers,
+    ManagedApiMetadata, SupportedVersion, ValidationContext, VersionScheme,
+    Versions,
 };
-use openapiv3::OpenAPI;
+use openapiv3::{ExternalDocumentation, OpenAPI};
+use regex::Regex;
 use std::{
     collections::{BTreeMap, BTreeSet},
     fmt,
+    ops::{Bound, RangeBounds},
+    process::Command,
+    sync::Arc,
 };
 
 /// Describes an API managed by the Dropshot API manager.
@@ -42,11 +72,159 @@ pub struct ManagedApiConfig {
         fn() -> Result<ApiDescription<StubContext>, ApiDescriptionBuildErrors>,
 }
 
+/// Describes an API managed by the Dropshot API manager whose OpenAPI document
+/// is not produced by the API manager's own version of Dropshot.
+///
+/// This is used for hand-written or externally generated OpenAPI documents, as
+/// well as for APIs defined with a different version of Dropshot.
+/// Such documents are managed with the same blessed/local/check machinery as
+/// Dropshot APIs: the only difference is how the generated document is
+/// obtained. Like [`ManagedApiConfig`], this can be converted into a
+/// [`ManagedApi`] and passed to [`ManagedApis::new`].
+#[derive(Clone, Debug)]
+pub struct ExternalApiConfig {
+    /// The API-specific part of the filename that's used for API descriptions
+    ///
+    /// This string is sometimes used as an identifier for developers.
+    pub ident: &'static str,
+
+    /// how this API is versioned
+    pub versions: Versions,
+
+    /// title of the API
+    ///
+    /// Unlike with [`ManagedApiConfig`], this is only written into the OpenAPI
+    /// document by [`DocumentSource::Provider`]. Other sources are used as-is.
+    pub title: &'static str,
+
+    /// metadata about the API
+    pub metadata: ManagedApiMetadata,
+
+    /// Where the OpenAPI document comes from.
+    pub source: DocumentSource,
+}
+
+/// The source of an OpenAPI document that's not produced by the Dropshot
+/// version the API manager is built against.
+///
+/// The API manager doesn't perform any stub generation for these sources. The
+/// document must be valid OpenAPI, and its `info.version` must match the
+/// version being generated.
+#[derive(Clone)]
+pub enum DocumentSource {
+    /// The document is returned by a function, typically one that returns the
+    /// contents of an `include_bytes!` invocation.
+    ///
+    /// Since there's a single document, this is mostly useful for lockstep
+    /// APIs.
+    Static(fn() -> Vec<u8>),
+
+    /// The document is written to standard output by a command.
+    Command(DocumentCommand),
+
+    /// The document is produced by an API crate, typically using its own
+    /// version of Dropshot.
+    ///
+    /// This allows API crates in a workspace to depend on different versions
+    /// of Dropshot. See [`ApiDescriptionProvider`] for more.
+    Provider(&'static dyn ApiDescriptionProvider),
+}
+
+impl fmt::Debug for DocumentSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocumentSource::Static(_) => f.write_str("Static(...)"),
+            DocumentSource::Command(command) => {
+                f.debug_tuple("Command").field(command).finish()
+            }
+            DocumentSource::Provider(_) => f.write_str("Provider(...)"),
+        }
+    }
+}
+
+/// A command that writes an OpenAPI document to standard output.
+///
+/// The command is run from the current directory. The API identifier and the
+/// version to generate are passed in via the `OPENAPI_MGR_API_IDENT` and
+/// `OPENAPI_MGR_API_VERSION` environment variables, respectively.
+#[derive(Clone, Debug)]
+pub struct DocumentCommand {
+    /// The program to run.
+    pub program: &'static str,
+
+    /// Arguments to pass to the program.
+    pub args: &'static [&'static str],
+}
+
+impl DocumentCommand {
+    fn run(
+        &self,
+        ident: &ApiIdent,
+        version: &semver::Version,
+    ) -> anyhow::Result<Vec<u8>> {
+        let output = Command::new(self.program)
+            .args(self.args)
+            .env("OPENAPI_MGR_API_IDENT", ident.as_str())
+            .env("OPENAPI_MGR_API_VERSION", version.to_string())
+            .output()
+            .with_context(|| format!("failed to run `{}`", self))?;
+        if !output.status.success() {
+            bail!(
+                "`{}` failed ({}): {}",
+                self,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim(),
+            );
+        }
+        Ok(output.stdout)
+    }
+}
+
+impl fmt::Display for DocumentCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.program)?;
+        for arg in self.args {
+            write!(f, " {}", arg)?;
+        }
+        Ok(())
+    }
+}
+
+/// How the generated OpenAPI document for a [`ManagedApi`] is obtained.
+#[derive(Clone)]
+enum ApiSource {
+    Dropshot(
+        fn() -> Result<ApiDescription<StubContext>, ApiDescriptionBuildErrors>,
+    ),
+    Document(DocumentSource),
+}
+
+impl fmt::Debug for ApiSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiSource::Dropshot(_) => f.write_str("Dropshot(...)"),
+            ApiSource::Document(source) => {
+                f.debug_tuple("Document").field(source).finish()
+            }
+        }
+    }
+}
+
+/// An alternate source for the documents of a range of versions, registered
+/// with [`ManagedApi::with_contract`] or
+/// [`ManagedApi::with_external_contract`].
+#[derive(Clone, Debug)]
+struct VersionContract {
+    versions: (Bound<semver::Version>, Bound<semver::Version>),
+    source: ApiSource,
+}
+
 /// Describes an API managed by the Dropshot API manager.
 ///
 /// This type is typically created from a [`ManagedApiConfig`] and can be
 /// further configured using builder methods before being passed to
 /// [`ManagedApis::new`].
+#[derive(Clone)]
 pub struct ManagedApi {
     /// The API-specific part of the filename that's used for API descriptions
     ///
@@ -56,19 +234,27 @@ pub struct ManagedApi {
     /// how this API is versioned
     versions: Versions,
 
+    /// The scheme every supported version must follow, if any.
+    version_scheme: Option<VersionScheme>,
+
+    /// A regular expression every supported version's label must match, if
+    /// any.
+    version_label_pattern: Option<String>,
+
     /// title of the API (goes into OpenAPI document)
     title: &'static str,
 
     /// metadata about the API
     metadata: ManagedApiMetadata,
 
-    /// The API description function, typically a reference to
-    /// `stub_api_description`
-    ///
-    /// This is used to generate the OpenAPI document that matches the current
-    /// server implementation.
-    api_description:
-        fn() -> Result<ApiDescription<StubContext>, ApiDescriptionBuildErrors>,
+    /// Where the generated OpenAPI document comes from: typically a Dropshot
+    /// API description function, but possibly a [`DocumentSource`].
+    source: ApiSource,
+
+    /// Alternate sources for ranges of versions, e.g. older versions whose
+    /// types live in a separate crate. The first contract whose range contains
+    /// a version is used in place of `source`.
+    contracts: Vec<VersionContract>,
 
     /// Extra validation to perform on the OpenAPI document, if any.
     ///
@@ -76,7 +262,7 @@ pub struct ManagedApi {
     /// including blessed ones. You may want to skip performing validation on
     /// blessed versions, though, because they're immutable. To do so, use
     /// [`ValidationContext::is_blessed`].
-    extra_validation: Option<Box<DynValidationFn>>,
+    extra_validation: Option<Arc<DynValidationFn>>,
 
     /// If true, allow trivial changes (doc updates, type renames) for the
     /// latest blessed version without requiring version bumps.
@@ -84,12 +270,107 @@ pub struct ManagedApi {
     /// Default: false (bytewise check is performed for latest version).
     allow_trivial_changes_for_latest: bool,
 
+    /// If true, also check that blessed versions are compatible with the
+    /// generated documents, i.e. that clients built against the current code
+    /// can talk to servers running blessed versions.
+    ///
+    /// Default: false (only the generated document is checked against the
+    /// blessed one).
+    check_reverse_compatibility: bool,
+
     /// Per-API override for Git stub storage.
     ///
     /// - `None`: use the global setting from `ManagedApis`.
     /// - `Some(true)`: enable Git stub storage for this API.
     /// - `Some(false)`: disable Git stub storage for this API.
     use_git_stub_storage: Option<bool>,
+
+    /// Which blessed versions are eligible for Git stub storage, if it's
+    /// enabled.
+    git_stub_policy: GitStubPolicy,
+
+    /// How the latest version of a versioned API is pointed to.
+    ///
+    /// Default: a `<ident>-latest.json` symlink.
+    latest_pointer: LatestPointer,
+
+    /// Whether a document combining all supported versions is maintained.
+    combined_document: bool,
+
+    /// Whether the schemas in versioned documents are stored in a
+    /// content-addressed directory shared between versions.
+    shared_schemas: bool,
+
+    /// How this API's documents are formatted on disk, if not the way
+    /// Dropshot generates them.
+    json_format: Option<JsonFormat>,
+
+    /// Other identifiers under which copies of this API's documents are
+    /// maintained, e.g. while the API is being renamed.
+    aliases: Vec<ApiIdent>,
+
+    /// Files within this API's documents directory that aren't managed by the
+    /// API manager, e.g. a hand-written README.
+    unmanaged_files: IgnoreGlobs,
+
+    /// The files, relative to the repository root, that this API is defined
+    /// by, if known.
+    source_globs: Option<SourceGlobs>,
+
+    /// Schema names to replace with hand-written Rust types in generated
+    /// clients, maintained as a sidecar file.
+    type_replacements: Option<TypeReplacements>,
+
+    /// A copy of the latest document with references inlined, for client
+    /// generators.
+    flattened_document: Option<FlattenedDocument>,
+
+    /// Lints for the summaries and descriptions in the API's documents.
+    text_lint: Option<TextLint>,
+
+    /// Lints for the examples of request and response bodies in the API's
+    /// documents.
+    example_lint: Option<ExampleLint>,
+
+    /// Lints for the names of parameters, properties and operations in the
+    /// API's documents.
+    naming_lint: Option<NamingLint>,
+
+    /// Lints for the links to external documentation in the API's documents.
+    docs_link_lint: Option<DocsLinkLint>,
+
+    /// Operations with this tag are unstable, and are excluded from
+    /// comparisons with blessed documents.
+    unstable_tag: Option<String>,
+
+    /// Operations under these path prefixes are unstable, and are excluded
+    /// from comparisons with blessed documents.
+    unstable_path_prefixes: Vec<String>,
+
+    /// Operation IDs that are allowed to be renamed between consecutive
+    /// versions, as `(old, new)` pairs.
+    renamed_operation_ids: Vec<(String, String)>,
+
+    /// Profiles for which variant documents are maintained.
+    profiles: Vec<ApiProfile>,
+
+    /// Windows during which the latest document must not change.
+    freezes: Vec<DocumentFreeze>,
+
+    /// For a lockstep API, how the document's version is picked, if not from
+    /// the configuration.
+    lockstep_version_policy: Option<LockstepVersionPolicy>,
+
+    /// The percentage by which a new latest document may grow over the
+    /// latest blessed document before a warning is printed.
+    size_growth_threshold: Option<u32>,
+
+    /// Where to write a summary of deprecated operations, if anywhere.
+    deprecation_summary: Option<DeprecationSummary>,
+
+    /// For a variant document, the API it was derived from and the profile
+    /// that selects its operations.
+    derived_from: Option<(ApiIdent, ApiProfile)>,
 }
 
 impl fmt::Debug for ManagedApi {
@@ -97,20 +378,50 @@ impl fmt::Debug for ManagedApi {
         let Self {
             ident,
             versions,
+            version_scheme,
+            version_label_pattern,
             title,
             metadata,
-            api_description: _,
+            source,
+            contracts,
             extra_validation,
             allow_trivial_changes_for_latest,
+            check_reverse_compatibility,
             use_git_stub_storage,
+            git_stub_policy,
+            latest_pointer,
+            combined_document,
+            shared_schemas,
+            json_format,
+            aliases,
+            unmanaged_files,
+            source_globs,
+            type_replacements,
+            flattened_document,
+            text_lint,
+            example_lint,
+            naming_lint,
+            docs_link_lint,
+            unstable_tag,
+            unstable_path_prefixes,
+            renamed_operation_ids,
+            profiles,
+            freezes,
+            lockstep_version_policy,
+            size_growth_threshold,
+            deprecation_summary,
+            derived_from,
         } = self;
 
         f.debug_struct("ManagedApi")
             .field("ident", ident)
             .field("versions", versions)
+            .field("version_scheme", version_scheme)
+            .field("version_label_pattern", version_label_pattern)
             .field("title", title)
             .field("metadata", metadata)
-            .field("api_description", &"...")
+            .field("source", source)
+            .field("contracts", contracts)
             .field(
                 "extra_validation",
                 &extra_validation.as_ref().map(|_| "..."),
@@ -119,7 +430,31 @@ impl fmt::Debug for ManagedApi {
                 "allow_trivial_changes_for_latest",
                 allow_trivial_changes_for_latest,
             )
+            .field("check_reverse_compatibility", check_reverse_compatibility)
             .field("use_git_stub_storage", use_git_stub_storage)
+            .field("git_stub_policy", git_stub_policy)
+            .field("latest_pointer", latest_pointer)
+            .field("combined_document", combined_document)
+            .field("shared_schemas", shared_schemas)
+            .field("json_format", json_format)
+            .field("aliases", aliases)
+            .field("unmanaged_files", unmanaged_files)
+            .field("source_globs", source_globs)
+            .field("type_replacements", type_replacements)
+            .field("flattened_document", flattened_document)
+            .field("text_lint", text_lint)
+            .field("example_lint", example_lint)
+            .field("naming_lint", naming_lint)
+            .field("docs_link_lint", docs_link_lint)
+            .field("unstable_tag", unstable_tag)
+            .field("unstable_path_prefixes", unstable_path_prefixes)
+            .field("renamed_operation_ids", renamed_operation_ids)
+            .field("profiles", profiles)
+            .field("freezes", freezes)
+            .field("lockstep_version_policy", lockstep_version_policy)
+            .field("size_growth_threshold", size_growth_threshold)
+            .field("deprecation_summary", deprecation_summary)
+            .field("derived_from", derived_from)
             .finish()
     }
 }
@@ -136,12 +471,83 @@ impl From<ManagedApiConfig> for ManagedApi {
         ManagedApi {
             ident: ApiIdent::from(ident),
             versions,
+            version_scheme: None,
+            version_label_pattern: None,
             title,
             metadata,
-            api_description,
+            source: ApiSource::Dropshot(api_description),
+            contracts: Vec::new(),
+            extra_validation: None,
+            allow_trivial_changes_for_latest: false,
+            check_reverse_compatibility: false,
+            use_git_stub_storage: None,
+            git_stub_policy: GitStubPolicy::default(),
+            latest_pointer: LatestPointer::default(),
+            combined_document: false,
+            shared_schemas: false,
+            json_format: None,
+            aliases: Vec::new(),
+            unmanaged_files: IgnoreGlobs::empty(),
+            source_globs: None,
+            type_replacements: None,
+            flattened_document: None,
+            text_lint: None,
+            example_lint: None,
+            naming_lint: None,
+            docs_link_lint: None,
+            unstable_tag: None,
+            unstable_path_prefixes: Vec::new(),
+            renamed_operation_ids: Vec::new(),
+            profiles: Vec::new(),
+            freezes: Vec::new(),
+            lockstep_version_policy: None,
+            size_growth_threshold: None,
+            deprecation_summary: None,
+            derived_from: None,
+        }
+    }
+}
+
+impl From<ExternalApiConfig> for ManagedApi {
+    fn from(value: ExternalApiConfig) -> Self {
+        let ExternalApiConfig { ident, versions, title, metadata, source } =
+            value;
+        ManagedApi {
+            ident: ApiIdent::from(ident),
+            versions,
+            version_scheme: None,
+            version_label_pattern: None,
+            title,
+            metadata,
+            source: ApiSource::Document(source),
+            contracts: Vec::new(),
             extra_validation: None,
             allow_trivial_changes_for_latest: false,
+            check_reverse_compatibility: false,
             use_git_stub_storage: None,
+            git_stub_policy: GitStubPolicy::default(),
+            latest_pointer: LatestPointer::default(),
+            combined_document: false,
+            shared_schemas: false,
+            json_format: None,
+            aliases: Vec::new(),
+            unmanaged_files: IgnoreGlobs::empty(),
+            source_globs: None,
+            type_replacements: None,
+            flattened_document: None,
+            text_lint: None,
+            example_lint: None,
+            naming_lint: None,
+            docs_link_lint: None,
+            unstable_tag: None,
+            unstable_path_prefixes: Vec::new(),
+            renamed_operation_ids: Vec::new(),
+            profiles: Vec::new(),
+            freezes: Vec::new(),
+            lockstep_version_policy: None,
+            size_growth_threshold: None,
+            deprecation_summary: None,
+            derived_from: None,
         }
     }
 }
@@ -157,6 +563,92 @@ impl ManagedApi {
         &self.versions
     }
 
+    /// Requires every supported version of this API to follow `scheme`, e.g.
+    /// to be date-encoded.
+    ///
+    /// [`ManagedApis::new`] returns an error if a supported version doesn't
+    /// follow the scheme, so that versions of a different scheme can't be
+    /// added by mistake. This only has an effect for versioned APIs.
+    pub fn with_version_scheme(mut self, scheme: VersionScheme) -> Self {
+        self.version_scheme = Some(scheme);
+        self
+    }
+
+    /// Returns the scheme every supported version of this API must follow,
+    /// if any.
+    pub fn version_scheme(&self) -> Option<VersionScheme> {
+        self.version_scheme
+    }
+
+    /// Requires the label of every supported version of this API (e.g.
+    /// `WITH_METRICS` in `api_versions!`) to match the regular expression
+    /// `pattern`.
+    ///
+    /// The whole label must match. For example, `[A-Z][A-Z0-9]*(_[A-Z0-9]+)*`
+    /// requires labels in SCREAMING_SNAKE_CASE. [`ManagedApis::new`] returns
+    /// an error naming the first label that doesn't match, or if `pattern`
+    /// isn't a valid regular expression. (Labels are always required to be
+    /// unique within an API.) This only has an effect for versioned APIs.
+    pub fn with_version_label_pattern(
+        mut self,
+        pattern: impl Into<String>,
+    ) -> Self {
+        self.version_label_pattern = Some(pattern.into());
+        self
+    }
+
+    /// Returns the regular expression every supported version's label must
+    /// match, if any.
+    pub fn version_label_pattern(&self) -> Option<&str> {
+        self.version_label_pattern.as_deref()
+    }
+
+    /// Checks the supported versions against the configured version scheme
+    /// and label pattern.
+    fn check_supported_versions(&self) -> anyhow::Result<()> {
+        let Some(versions) = self.iter_versioned_versions() else {
+            return Ok(());
+        };
+        let label_pattern = self
+            .version_label_pattern
+            .as_ref()
+            .map(|pattern| {
+                // The whole label must match.
+                let regex = Regex::new(&format!("^(?:{})$", pattern))
+                    .with_context(|| {
+                        format!(
+                            "API {:?}: invalid version label pattern {:?}",
+                            self.ident, pattern,
+                        )
+                    })?;
+                anyhow::Ok((pattern, regex))
+            })
+            .transpose()?;
+        for version in versions {
+            if let Some(scheme) = self.version_scheme {
+                scheme.check(version.semver()).with_context(|| {
+                    format!(
+                        "API {:?} must use {} versions",
+                        self.ident, scheme,
+                    )
+                })?;
+            }
+            if let Some((pattern, regex)) = &label_pattern
+                && !regex.is_match(version.label())
+            {
+                bail!(
+                    "API {:?} version {}: label {:?} doesn't match the \
+                     pattern {:?}",
+                    self.ident,
+                    version.semver(),
+                    version.label(),
+                    pattern,
+                );
+            }
+        }
+        Ok(())
+    }
+
     /// Returns the API title.
     pub fn title(&self) -> &'static str {
         self.title
@@ -194,6 +686,26 @@ impl ManagedApi {
         self.allow_trivial_changes_for_latest
     }
 
+    /// Also checks compatibility in the reverse direction.
+    ///
+    /// By default, the document generated for a blessed version is checked to
+    /// be compatible with the blessed document: the current code must still
+    /// serve clients built against the blessed version. With this setting,
+    /// the blessed document must also be compatible with the generated one,
+    /// so that clients built against the current code can talk to servers
+    /// still running the blessed version, e.g. during a staged rollout.
+    /// Issues found this way are reported separately.
+    pub fn with_reverse_compatibility_check(mut self) -> Self {
+        self.check_reverse_compatibility = true;
+        self
+    }
+
+    /// Returns true if compatibility is also checked in the reverse
+    /// direction.
+    pub fn checks_reverse_compatibility(&self) -> bool {
+        self.check_reverse_compatibility
+    }
+
     /// Enables Git stub storage for this API, overriding the global setting.
     ///
     /// When enabled, non-latest blessed API versions are stored as `.gitstub`
@@ -218,6 +730,566 @@ impl ManagedApi {
         self.use_git_stub_storage
     }
 
+    /// Sets the policy for which blessed versions are eligible for Git stub
+    /// storage.
+    ///
+    /// This only has an effect if Git stub storage is enabled for this API.
+    /// Versions that are not eligible are stored as JSON files.
+    pub fn with_git_stub_policy(mut self, policy: GitStubPolicy) -> Self {
+        self.git_stub_policy = policy;
+        self
+    }
+
+    /// Returns the Git stub eligibility policy for this API.
+    pub fn git_stub_policy(&self) -> &GitStubPolicy {
+        &self.git_stub_policy
+    }
+
+    /// Sets how the latest version of this API is pointed to.
+    ///
+    /// This only has an effect for versioned APIs. See [`LatestPointer`] for
+    /// the options.
+    pub fn with_latest_pointer(
+        mut self,
+        latest_pointer: LatestPointer,
+    ) -> Self {
+        self.latest_pointer = latest_pointer;
+        self
+    }
+
+    /// Returns how the latest version of this API is pointed to.
+    pub fn latest_pointer(&self) -> LatestPointer {
+        self.latest_pointer
+    }
+
+    /// Maintains a document combining the operations of all supported
+    /// versions, `<ident>-all.json`, in this API's documents directory.
+    ///
+    /// This is meant for tooling (such as API gateways) that wants a single
+    /// document for every version. Each version's paths are prefixed with
+    /// `/v<version>`, and its operation IDs and component names with
+    /// `v<version>_` (with dots replaced by underscores), so that they don't
+    /// collide. Each operation also carries an `x-version` extension naming
+    /// its version.
+    ///
+    /// Blessed versions are included as blessed, and other versions as
+    /// generated. `generate` writes the combined document, and `check`
+    /// verifies that it's up-to-date. This only has an effect for versioned
+    /// APIs.
+    pub fn with_combined_document(mut self) -> Self {
+        self.combined_document = true;
+        self
+    }
+
+    /// Returns true if a combined document is maintained for this API.
+    pub fn has_combined_document(&self) -> bool {
+        self.combined_document
+    }
+
+    /// Stores the component schemas of this API's documents in a
+    /// content-addressed directory, `shared-schemas/`, within the API's
+    /// documents directory.
+    ///
+    /// Large APIs repeat mostly the same schemas in every version's document.
+    /// With this option, each document refers to its schemas via `$ref`s to
+    /// files in the shared directory, named by the SHA-256 hash of their
+    /// contents, so that a schema that's unchanged between versions is only
+    /// stored once. `generate` maintains the directory, including removing
+    /// schemas that are no longer referred to, and `check` verifies that it's
+    /// up-to-date.
+    ///
+    /// The API manager reassembles the full documents when loading them, so
+    /// comparisons with blessed and generated documents are unaffected. Tools
+    /// that need self-contained documents can use the `materialize` command,
+    /// and copies of the documents under aliases are always self-contained.
+    /// Turning this option off inlines the schemas again the next time
+    /// `generate` is run.
+    ///
+    /// This only has an effect for versioned APIs whose documents are
+    /// formatted like the ones Dropshot generates.
+    pub fn with_shared_schemas(mut self) -> Self {
+        self.shared_schemas = true;
+        self
+    }
+
+    /// Returns true if this API's schemas are stored in a shared directory.
+    pub fn has_shared_schemas(&self) -> bool {
+        self.shared_schemas
+    }
+
+    /// Formats this API's documents on disk with `format`, rather than the
+    /// way Dropshot generates them.
+    ///
+    /// `generate` writes documents in this format, and `check` reports
+    /// documents that aren't in it. Documents are converted back to Dropshot's
+    /// formatting when they're loaded, so that blessed documents in either
+    /// format can be compared with the generated ones: changing the format
+    /// doesn't require a new version. To go back to Dropshot's formatting, set
+    /// [`JsonFormat::default`] until the blessed documents are in that format
+    /// too.
+    ///
+    /// This can't be combined with [`Self::with_shared_schemas`].
+    pub fn with_json_format(mut self, format: JsonFormat) -> Self {
+        self.json_format = Some(format);
+        self
+    }
+
+    /// Returns the format of this API's documents on disk, if it's been set.
+    pub fn json_format(&self) -> Option<&JsonFormat> {
+        self.json_format.as_ref()
+    }
+
+    /// Maintains a file summarizing the deprecated operations in each supported
+    /// version of this API.
+    ///
+    /// This only has an effect for versioned APIs, and isn't maintained for
+    /// profile variants. See [`DeprecationSummary`] for more.
+    pub fn with_deprecation_summary(
+        mut self,
+        summary: DeprecationSummary,
+    ) -> Self {
+        self.deprecation_summary = Some(summary);
+        self
+    }
+
+    /// Returns the deprecation summary maintained for this API, if any.
+    pub fn deprecation_summary(&self) -> Option<&DeprecationSummary> {
+        self.deprecation_summary.as_ref()
+    }
+
+    /// Maintains a copy of this API's documents under the identifier `alias`.
+    ///
+    /// This is meant for renaming an API: while consumers still read documents
+    /// under the old name, `generate` keeps `<alias>.json` (for lockstep APIs)
+    /// or the `<alias>/` directory (for versioned APIs) byte-identical to this
+    /// API's documents, with file names adjusted for the alias. `check`
+    /// reports a problem if the copy is out of date. Once the alias is removed,
+    /// the copy is no longer maintained, and can be deleted.
+    pub fn with_alias(mut self, alias: impl Into<ApiIdent>) -> Self {
+        self.aliases.push(alias.into());
+        self
+    }
+
+    /// Returns the aliases for this API.
+    pub fn aliases(&self) -> &[ApiIdent] {
+        &self.aliases
+    }
+
+    /// Permits files matching any of the glob `patterns` within this API's
+    /// documents directory, such as a hand-written `README.md`.
+    ///
+    /// Patterns are matched against file names. Matching files are not
+    /// managed by the API manager: they're skipped when loading documents,
+    /// rather than being reported as unexpected files. This only has an effect
+    /// for versioned APIs, since lockstep APIs don't have a directory.
+    ///
+    /// Returns an error if any of the patterns is not a valid glob.
+    pub fn with_unmanaged_files<I, S>(
+        mut self,
+        patterns: I,
+    ) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.unmanaged_files = self.unmanaged_files.with_patterns(patterns)?;
+        Ok(self)
+    }
+
+    /// Returns true if `file_name`, within this API's documents directory, is
+    /// not managed by the API manager.
+    pub(crate) fn is_unmanaged_file(&self, file_name: &str) -> bool {
+        // The pointer file is checked separately from the documents.
+        if self.latest_pointer == LatestPointer::PointerFile
+            && file_name == self.ident.versioned_api_latest_pointer_file()
+        {
+            return true;
+        }
+        // So is the combined document.
+        if self.combined_document
+            && file_name == self.ident.versioned_api_combined_file()
+        {
+            return true;
+        }
+        // And the shared schemas directory, which may be left over from
+        // before the option was turned off.
+        if file_name == SHARED_SCHEMAS_DIR {
+            return true;
+        }
+        self.unmanaged_files.is_ignored(Utf8Path::new(file_name))
+    }
+
+    /// Declares the files this API is defined by, as glob `patterns` relative
+    /// to the repository root, e.g. `"apis/my-api/**"`.
+    ///
+    /// `check --changed-files` uses these to work out which APIs a change can
+    /// affect. Changes to files outside the documents directory can only be
+    /// ruled out if every API has source globs; otherwise, they cause every
+    /// API to be checked. Shared dependencies, such as `Cargo.lock`, should be
+    /// included in the patterns of every API that uses them.
+    ///
+    /// Returns an error if any of the patterns is not a valid glob.
+    pub fn with_source_globs<I, S>(
+        mut self,
+        patterns: I,
+    ) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let existing = self.source_globs.take().unwrap_or_default();
+        self.source_globs = Some(existing.with_patterns(patterns)?);
+        Ok(self)
+    }
+
+    /// Returns the files this API is defined by, if they were declared with
+    /// [`Self::with_source_globs`].
+    pub(crate) fn source_globs(&self) -> Option<&SourceGlobs> {
+        self.source_globs.as_ref()
+    }
+
+    /// Maintains a sidecar file mapping schema names to hand-written Rust
+    /// types, for client generators like Progenitor.
+    ///
+    /// See [`TypeReplacements`] for more.
+    pub fn with_type_replacements(
+        mut self,
+        replacements: TypeReplacements,
+    ) -> Self {
+        self.type_replacements = Some(replacements);
+        self
+    }
+
+    /// Returns the type replacements for this API, if any.
+    pub fn type_replacements(&self) -> Option<&TypeReplacements> {
+        self.type_replacements.as_ref()
+    }
+
+    /// Maintains a copy of this API's latest document with references
+    /// inlined, for client generators that handle references poorly.
+    ///
+    /// See [`FlattenedDocument`] for more.
+    pub fn with_flattened_document(
+        mut self,
+        flattened_document: FlattenedDocument,
+    ) -> Self {
+        self.flattened_document = Some(flattened_document);
+        self
+    }
+
+    /// Returns the flattened document maintained for this API, if any.
+    pub fn flattened_document(&self) -> Option<&FlattenedDocument> {
+        self.flattened_document.as_ref()
+    }
+
+    /// Lints the summaries and descriptions in this API's documents as part
+    /// of validation.
+    ///
+    /// See [`TextLint`] for more.
+    pub fn with_text_lint(mut self, text_lint: TextLint) -> Self {
+        self.text_lint = Some(text_lint);
+        self
+    }
+
+    /// Returns the lint for summaries and descriptions, if any.
+    pub fn text_lint(&self) -> Option<&TextLint> {
+        self.text_lint.as_ref()
+    }
+
+    /// Validates the examples of request and response bodies in this API's
+    /// documents, and optionally requires them for some operations.
+    ///
+    /// See [`ExampleLint`] for more.
+    pub fn with_example_lint(mut self, example_lint: ExampleLint) -> Self {
+        self.example_lint = Some(example_lint);
+        self
+    }
+
+    /// Returns the lint for examples, if any.
+    pub fn example_lint(&self) -> Option<&ExampleLint> {
+        self.example_lint.as_ref()
+    }
+
+    /// Requires the names of parameters, properties and operations in this
+    /// API's documents to follow a naming convention.
+    ///
+    /// See [`NamingLint`] for more.
+    pub fn with_naming_lint(mut self, naming_lint: NamingLint) -> Self {
+        self.naming_lint = Some(naming_lint);
+        self
+    }
+
+    /// Returns the lint for names, if any.
+    pub fn naming_lint(&self) -> Option<&NamingLint> {
+        self.naming_lint.as_ref()
+    }
+
+    /// Requires this API's documents to link to external documentation.
+    ///
+    /// See [`DocsLinkLint`] for more.
+    pub fn with_docs_link_lint(mut self, docs_link_lint: DocsLinkLint) -> Self {
+        self.docs_link_lint = Some(docs_link_lint);
+        self
+    }
+
+    /// Returns the lint for links to external documentation, if any.
+    pub fn docs_link_lint(&self) -> Option<&DocsLinkLint> {
+        self.docs_link_lint.as_ref()
+    }
+
+    /// Treats operations tagged with `tag` as unstable.
+    ///
+    /// Unstable operations are still emitted in generated documents, but
+    /// they're excluded from comparisons with blessed documents: changing or
+    /// removing an unstable operation doesn't break compatibility with a
+    /// blessed version, nor does it require a new version. If a blessed
+    /// version differs from the generated document only in its unstable
+    /// operations, the local document is simply regenerated.
+    ///
+    /// Operations with the `x-unstable: true` vendor extension are always
+    /// unstable, whether or not a tag is set. With Dropshot, the tag can be
+    /// set via `#[endpoint { tags = [...] }]`.
+    pub fn with_unstable_tag(mut self, tag: impl Into<String>) -> Self {
+        self.unstable_tag = Some(tag.into());
+        self
+    }
+
+    /// Returns the tag that marks operations as unstable, if any.
+    pub fn unstable_tag(&self) -> Option<&str> {
+        self.unstable_tag.as_deref()
+    }
+
+    /// Treats operations under the path `prefix` (e.g. `/experimental`) as
+    /// unstable, as with [`Self::with_unstable_tag`].
+    ///
+    /// Prefixes match whole path segments: `/experimental` matches
+    /// `/experimental` and `/experimental/widgets`, but not
+    /// `/experimentally`. Since the exemption applies to every operation under
+    /// the prefix, `check` lists the operations it excludes.
+    pub fn with_unstable_path_prefix(
+        mut self,
+        prefix: impl Into<String>,
+    ) -> Self {
+        self.unstable_path_prefixes.push(prefix.into());
+        self
+    }
+
+    /// Returns the path prefixes under which operations are unstable.
+    pub fn unstable_path_prefixes(&self) -> &[String] {
+        &self.unstable_path_prefixes
+    }
+
+    /// Returns the criteria for which of this API's operations are unstable.
+    pub(crate) fn unstable_operations(&self) -> UnstableOperations<'_> {
+        UnstableOperations {
+            tag: self.unstable_tag.as_deref(),
+            path_prefixes: &self.unstable_path_prefixes,
+        }
+    }
+
+    /// Allows the operation ID `old` to be renamed to `new` in a later version
+    /// of this API.
+    ///
+    /// An operation (i.e., a method and path) that's present in consecutive
+    /// versions of a versioned API must keep the same operation ID: renaming
+    /// it breaks generated clients, even though the change is
+    /// wire-compatible. Use this to mark a rename as intended.
+    pub fn with_renamed_operation_id(
+        mut self,
+        old: impl Into<String>,
+        new: impl Into<String>,
+    ) -> Self {
+        self.renamed_operation_ids.push((old.into(), new.into()));
+        self
+    }
+
+    /// Returns true if renaming the operation ID `old` to `new` is allowed.
+    pub(crate) fn is_renamed_operation_id(&self, old: &str, new: &str) -> bool {
+        self.renamed_operation_ids.iter().any(|(o, n)| o == old && n == new)
+    }
+
+    /// Maintains a variant document containing only the operations selected
+    /// by `profile`, under the identifier `<ident>-<profile name>`.
+    ///
+    /// The variant inherits this API's versions, title, metadata, Git stub
+    /// settings, and unstable tag and path prefixes. Aliases, type
+    /// replacements, and extra validation apply only to the full document. See
+    /// [`ApiProfile`] for more.
+    pub fn with_profile(mut self, profile: ApiProfile) -> Self {
+        self.profiles.push(profile);
+        self
+    }
+
+    /// Returns the profiles for this API.
+    pub fn profiles(&self) -> &[ApiProfile] {
+        &self.profiles
+    }
+
+    /// Freezes the latest document of this API while `freeze` is in effect.
+    ///
+    /// Profile variants of this API are frozen as well. See
+    /// [`DocumentFreeze`] for more.
+    pub fn with_freeze(mut self, freeze: DocumentFreeze) -> Self {
+        self.freezes.push(freeze);
+        self
+    }
+
+    /// Returns the freezes for this API.
+    pub fn freezes(&self) -> &[DocumentFreeze] {
+        &self.freezes
+    }
+
+    /// Picks the version of this API's document with `policy`, bumping it
+    /// whenever the document changes, rather than using the configured
+    /// version.
+    ///
+    /// This only has an effect for lockstep APIs. Profile variants of this API
+    /// are versioned the same way. See [`LockstepVersionPolicy`] for more.
+    pub fn with_lockstep_version_policy(
+        mut self,
+        policy: LockstepVersionPolicy,
+    ) -> Self {
+        self.lockstep_version_policy = Some(policy);
+        self
+    }
+
+    /// Returns the policy for picking this API's document version, if any.
+    pub fn lockstep_version_policy(&self) -> Option<&LockstepVersionPolicy> {
+        self.lockstep_version_policy.as_ref()
+    }
+
+    /// Warns if a new latest document is more than `percent` percent larger
+    /// than the latest blessed document.
+    ///
+    /// A large jump in size within a single change usually means a schema
+    /// exploded by accident, e.g. a recursive type being inlined over and
+    /// over, and every later version would carry the extra bulk. The warning
+    /// is printed as a note by `check` and `generate`, along with both sizes,
+    /// and doesn't cause either command to fail.
+    ///
+    /// This only applies to versioned APIs, and only while the latest version
+    /// isn't blessed yet.
+    pub fn with_size_growth_threshold(mut self, percent: u32) -> Self {
+        self.size_growth_threshold = Some(percent);
+        self
+    }
+
+    /// Returns the size growth threshold for this API, as a percentage, if
+    /// any.
+    pub fn size_growth_threshold(&self) -> Option<u32> {
+        self.size_growth_threshold
+    }
+
+    /// Creates the variant API for `profile`.
+    fn profile_api(&self, profile: &ApiProfile) -> ManagedApi {
+        ManagedApi {
+            ident: ApiIdent::from(format!("{}-{}", self.ident, profile.name())),
+            versions: self.versions.clone(),
+            version_scheme: self.version_scheme,
+            version_label_pattern: self.version_label_pattern.clone(),
+            title: self.title,
+            metadata: self.metadata.clone(),
+            source: self.source.clone(),
+            contracts: self.contracts.clone(),
+            extra_validation: None,
+            allow_trivial_changes_for_latest: self
+                .allow_trivial_changes_for_latest,
+            check_reverse_compatibility: self.check_reverse_compatibility,
+            use_git_stub_storage: self.use_git_stub_storage,
+            git_stub_policy: self.git_stub_policy.clone(),
+            latest_pointer: self.latest_pointer,
+            combined_document: self.combined_document,
+            shared_schemas: self.shared_schemas,
+            json_format: self.json_format,
+            aliases: Vec::new(),
+            unmanaged_files: IgnoreGlobs::empty(),
+            source_globs: self.source_globs.clone(),
+            type_replacements: None,
+            flattened_document: None,
+            text_lint: None,
+            example_lint: None,
+            naming_lint: None,
+            docs_link_lint: None,
+            unstable_tag: self.unstable_tag.clone(),
+            unstable_path_prefixes: self.unstable_path_prefixes.clone(),
+            renamed_operation_ids: self.renamed_operation_ids.clone(),
+            profiles: Vec::new(),
+            freezes: self.freezes.clone(),
+            lockstep_version_policy: self.lockstep_version_policy.clone(),
+            size_growth_threshold: self.size_growth_threshold,
+            deprecation_summary: None,
+            derived_from: Some((self.ident.clone(), profile.clone())),
+        }
+    }
+
+    /// Generates the documents for `versions` from `api_description` rather
+    /// than from the API's own description.
+    ///
+    /// This lets older versions be described by a separate "contract" crate
+    /// (e.g. `my-api-contract-v1`) that keeps the types those versions were
+    /// built with, so that the primary crate doesn't have to retain legacy
+    /// types just to reproduce old documents. The API's title and metadata
+    /// are still applied.
+    ///
+    /// If several contracts cover a version, the one registered first is used.
+    pub fn with_contract(
+        mut self,
+        versions: impl RangeBounds<semver::Version>,
+        api_description: fn() -> Result<
+            ApiDescription<StubContext>,
+            ApiDescriptionBuildErrors,
+        >,
+    ) -> Self {
+        self.contracts.push(VersionContract {
+            versions: (
+                versions.start_bound().cloned(),
+                versions.end_bound().cloned(),
+            ),
+            source: ApiSource::Dropshot(api_description),
+        });
+        self
+    }
+
+    /// Obtains the documents for `versions` from `source` rather than from the
+    /// API's own description.
+    ///
+    /// This is like [`Self::with_contract`], for contract crates that can't be
+    /// linked into the same binary, e.g. because they depend on a different
+    /// version of Dropshot.
+    pub fn with_external_contract(
+        mut self,
+        versions: impl RangeBounds<semver::Version>,
+        source: DocumentSource,
+    ) -> Self {
+        self.contracts.push(VersionContract {
+            versions: (
+                versions.start_bound().cloned(),
+                versions.end_bound().cloned(),
+            ),
+            source: ApiSource::Document(source),
+        });
+        self
+    }
+
+    /// Returns true if the version ranges of this API's endpoints are checked
+    /// against its supported versions.
+    ///
+    /// This is only possible for versioned APIs described by Dropshot. Profile
+    /// variants aren't checked, since they share the full API's description.
+    pub(crate) fn checks_version_ranges(&self) -> bool {
+        self.is_versioned()
+            && self.derived_from.is_none()
+            && matches!(self.source, ApiSource::Dropshot(_))
+    }
+
+    /// Returns the source that the document for `version` is generated from.
+    fn source_for_version(&self, version: &semver::Version) -> &ApiSource {
+        self.contracts
+            .iter()
+            .find(|contract| contract.versions.contains(version))
+            .map_or(&self.source, |contract| &contract.source)
+    }
+
     /// Sets extra validation to perform on the OpenAPI document.
     ///
     /// For versioned APIs, extra validation is performed on *all* versions,
@@ -228,7 +1300,7 @@ impl ManagedApi {
     where
         F: Fn(&OpenAPI, ValidationContext<'_>) + Send + Sync + 'static,
     {
-        self.extra_validation = Some(Box::new(f));
+        self.extra_validation = Some(Arc::new(f));
         self
     }
 
@@ -259,7 +1331,38 @@ impl ManagedApi {
         &self,
         version: &semver::Version,
     ) -> anyhow::Result<Vec<u8>> {
-        let description = (self.api_description)().map_err(|error| {
+        let contents = self.generate_full_spec_bytes(version)?;
+        let contents = match &self.derived_from {
+            None => contents,
+            Some((_, profile)) => {
+                profile.filter_document(&contents).with_context(|| {
+                    format!("applying profile {:?}", profile.name())
+                })?
+            }
+        };
+        // Documents with a custom format are converted to Dropshot's
+        // formatting when they're loaded, so do the same here for documents
+        // that don't come from Dropshot.
+        if self.json_format.is_some() {
+            return JsonFormat::default()
+                .format(&contents)
+                .context("failed to reformat generated document");
+        }
+        Ok(contents)
+    }
+
+    /// Generates the full document, before any profile is applied.
+    fn generate_full_spec_bytes(
+        &self,
+        version: &semver::Version,
+    ) -> anyhow::Result<Vec<u8>> {
+        let api_description = match self.source_for_version(version) {
+            ApiSource::Dropshot(api_description) => api_description,
+            ApiSource::Document(source) => {
+                return self.generate_external_spec_bytes(source, version);
+            }
+        };
+        let description = api_description().map_err(|error| {
             // ApiDescriptionBuildError is actually a list of errors so it
             // doesn't implement std::error::Error itself. Its Display
             // impl formats the errors appropriately.
@@ -281,6 +1384,68 @@ impl ManagedApi {
         // or may not have preserve_order enabled.
         let mut contents = Vec::new();
         openapi_def.write(&mut contents)?;
+
+        // Dropshot can't set the document's external documentation, so add it
+        // here. Only do this if it's configured, so that other documents are
+        // exactly as Dropshot wrote them.
+        if let Some(url) = self.metadata.external_docs_url {
+            let mut openapi: OpenAPI = serde_json::from_slice(&contents)
+                .context("generated document is not valid OpenAPI")?;
+            openapi.external_docs = Some(ExternalDocumentation {
+                description: None,
+                url: url.to_owned(),
+                extensions: Default::default(),
+            });
+            // Match the formatting of documents written by Dropshot.
+            contents = serde_json::to_vec_pretty(&openapi)
+                .context("serializing document")?;
+            contents.push(b'\n');
+        }
+        Ok(contents)
+    }
+
+    fn generate_external_spec_bytes(
+        &self,
+        source: &DocumentSource,
+        version: &semver::Version,
+    ) -> anyhow::Result<Vec<u8>> {
+        let contents = match source {
+            DocumentSource::Static(f) => f(),
+            DocumentSource::Command(command) => {
+                // Variant documents are derived from the full document, so
+                // ask the command for that.
+                let ident = self
+                    .derived_from
+                    .as_ref()
+                    .map_or(&self.ident, |(ident, _)| ident);
+                command.run(ident, version)?
+            }
+            DocumentSource::Provider(provider) => provider.openapi_document(
+                &ApiDocumentInfo::new(self.title, version, &self.metadata),
+            )?,
+        };
+
+        // Check the version here, since otherwise a mismatched document would
+        // be written out under the wrong file name.
+        let openapi: OpenAPI = serde_json::from_slice(&contents)
+            .context("external document is not valid OpenAPI")?;
+        let doc_version =
+            openapi.info.version.parse::<semver::Version>().with_context(
+                || {
+                    format!(
+                        "external document has invalid version {:?}",
+                        openapi.info.version
+                    )
+                },
+            )?;
+        if doc_version != *version {
+            bail!(
+                "external document has version {}, expected {}",
+                doc_version,
+                version
+            );
+        }
+
         Ok(contents)
     }
 
@@ -293,6 +1458,158 @@ impl ManagedApi {
             extra_validation(openapi, validation_context);
         }
     }
+
+    pub(crate) fn type_replacements_validation(
+        &self,
+        openapi: &OpenAPI,
+        validation_context: ValidationContext<'_>,
+    ) {
+        if let Some(type_replacements) = &self.type_replacements {
+            type_replacements.validate(openapi, validation_context);
+        }
+    }
+
+    pub(crate) fn flattened_document_validation(
+        &self,
+        openapi: &OpenAPI,
+        validation_context: ValidationContext<'_>,
+    ) {
+        if let Some(flattened_document) = &self.flattened_document {
+            flattened_document.validate(openapi, validation_context);
+        }
+    }
+
+    pub(crate) fn text_lint_validation(
+        &self,
+        openapi: &OpenAPI,
+        validation_context: ValidationContext<'_>,
+    ) {
+        if let Some(text_lint) = &self.text_lint {
+            text_lint.validate(openapi, validation_context);
+        }
+    }
+
+    pub(crate) fn example_lint_validation(
+        &self,
+        openapi: &OpenAPI,
+        validation_context: ValidationContext<'_>,
+    ) {
+        if let Some(example_lint) = &self.example_lint {
+            example_lint.validate(openapi, validation_context);
+        }
+    }
+
+    pub(crate) fn naming_lint_validation(
+        &self,
+        openapi: &OpenAPI,
+        validation_context: ValidationContext<'_>,
+    ) {
+        if let Some(naming_lint) = &self.naming_lint {
+            naming_lint.validate(openapi, validation_context);
+        }
+    }
+
+    pub(crate) fn docs_link_lint_validation(
+        &self,
+        openapi: &OpenAPI,
+        validation_context: ValidationContext<'_>,
+    ) {
+        if let Some(docs_link_lint) = &self.docs_link_lint {
+            docs_link_lint.validate(openapi, validation_context);
+        }
+    }
+}
+
+/// How the latest version of a versioned API is pointed to, within the API's
+/// documents directory.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LatestPointer {
+    /// No pointer is maintained. An existing `<ident>-latest.json` symlink is
+    /// reported as a problem, and removed by `generate`.
+    None,
+
+    /// A `<ident>-latest.json` symlink to the latest document.
+    #[default]
+    Symlink,
+
+    /// A `<ident>-latest.txt` file containing the file name of the latest
+    /// document, followed by a newline. This is useful on platforms or in
+    /// tools that don't handle symlinks well. An existing
+    /// `<ident>-latest.json` symlink is removed by `generate`.
+    PointerFile,
+}
+
+/// Determines which blessed versions of an API are eligible for Git stub
+/// storage.
+///
+/// By default, every non-latest blessed version is eligible. Small documents
+/// gain little from Git stub indirection while making review harder, so this
+/// policy can restrict Git stubs to older or larger documents. All configured
+/// conditions must hold for a version to be eligible.
+///
+/// The latest version is never stored as a Git stub, regardless of policy.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GitStubPolicy {
+    older_than: Option<semver::Version>,
+    min_document_size: Option<usize>,
+    keep_recent: usize,
+}
+
+impl GitStubPolicy {
+    /// Creates a new policy under which all non-latest blessed versions are
+    /// eligible.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only use Git stubs for versions strictly older than `version`.
+    pub fn only_versions_older_than(
+        mut self,
+        version: semver::Version,
+    ) -> Self {
+        self.older_than = Some(version);
+        self
+    }
+
+    /// Only use Git stubs for documents that are at least `bytes` long.
+    pub fn min_document_size(mut self, bytes: usize) -> Self {
+        self.min_document_size = Some(bytes);
+        self
+    }
+
+    /// Never use Git stubs for the `count` most recent supported versions
+    /// (including the latest version).
+    pub fn keep_recent_versions(mut self, count: usize) -> Self {
+        self.keep_recent = count;
+        self
+    }
+
+    /// Returns true if a blessed version is eligible for Git stub storage.
+    ///
+    /// `recency` is the number of supported versions newer than this one (so
+    /// the latest version has a recency of 0), and `document_size` is the
+    /// size of the blessed document in bytes.
+    pub(crate) fn is_eligible(
+        &self,
+        version: &semver::Version,
+        recency: usize,
+        document_size: usize,
+    ) -> bool {
+        if recency < self.keep_recent {
+            return false;
+        }
+        if let Some(older_than) = &self.older_than
+            && version >= older_than
+        {
+            return false;
+        }
+        if let Some(min_document_size) = self.min_document_size
+            && document_size < min_document_size
+        {
+            return false;
+        }
+        true
+    }
 }
 
 /// Describes the Rust-defined configuration for all of the APIs managed by this
@@ -301,8 +1618,17 @@ impl ManagedApi {
 /// This is repo-specific state that's passed into the OpenAPI manager.
 pub struct ManagedApis {
     apis: BTreeMap<ApiIdent, ManagedApi>,
+    aliases: BTreeSet<ApiIdent>,
     unknown_apis: BTreeSet<ApiIdent>,
-    validation: Option<Box<DynValidationFn>>,
+    removed_apis: BTreeSet<ApiIdent>,
+
+    /// APIs left out of a partial check (see [`ManagedApis::scoped`]).
+    skipped_apis: BTreeSet<ApiIdent>,
+
+    validation: Option<Arc<DynValidationFn>>,
+
+    /// Validation functions for groups of APIs, in the order they were added.
+    selected_validations: Vec<(ApiSelector, Arc<DynValidationFn>)>,
 
     /// If true, store non-latest blessed API versions as Git stubs instead
     /// of full JSON files. This saves disk space but requires VCS access
@@ -310,18 +1636,47 @@ pub struct ManagedApis {
     ///
     /// The default is false.
     use_git_stub_storage: bool,
+
+    /// If set, versioned documents must carry valid detached signatures.
+    document_signing: Option<DocumentSigning>,
+    breaking_change_approvals: Option<BreakingChangeApprovals>,
+    consumer_pins: Option<ConsumerPins>,
 }
 
 impl fmt::Debug for ManagedApis {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let Self { apis, unknown_apis, validation, use_git_stub_storage } =
-            self;
+        let Self {
+            apis,
+            aliases,
+            unknown_apis,
+            removed_apis,
+            skipped_apis,
+            validation,
+            selected_validations,
+            use_git_stub_storage,
+            document_signing,
+            breaking_change_approvals,
+            consumer_pins,
+        } = self;
 
         f.debug_struct("ManagedApis")
             .field("apis", apis)
+            .field("aliases", aliases)
             .field("unknown_apis", unknown_apis)
+            .field("removed_apis", removed_apis)
+            .field("skipped_apis", skipped_apis)
             .field("validation", &validation.as_ref().map(|_| "..."))
+            .field(
+                "selected_validations",
+                &selected_validations
+                    .iter()
+                    .map(|(selector, _)| (selector, "..."))
+                    .collect::<Vec<_>>(),
+            )
             .field("use_git_stub_storage", use_git_stub_storage)
+            .field("document_signing", document_signing)
+            .field("breaking_change_approvals", breaking_change_approvals)
+            .field("consumer_pins", consumer_pins)
             .finish()
     }
 }
@@ -340,17 +1695,58 @@ impl ManagedApis {
     {
         let mut apis = BTreeMap::new();
         for api in api_list {
-            let api = api.into();
-            if let Some(old) = apis.insert(api.ident.clone(), api) {
-                bail!("API is defined twice: {:?}", &old.ident);
+            let api: ManagedApi = api.into();
+            let profile_apis: Vec<_> =
+                api.profiles.iter().map(|p| api.profile_api(p)).collect();
+            for api in std::iter::once(api).chain(profile_apis) {
+                if api.shared_schemas && api.json_format.is_some() {
+                    bail!(
+                        "API {:?} has both shared schemas and a custom JSON \
+                         format, which aren't supported together",
+                        api.ident,
+                    );
+                }
+                api.check_supported_versions()?;
+                if let Some(old) = apis.insert(api.ident.clone(), api) {
+                    bail!("API is defined twice: {:?}", &old.ident);
+                }
+            }
+        }
+
+        let mut aliases = BTreeMap::new();
+        for api in apis.values() {
+            for alias in &api.aliases {
+                if apis.contains_key(alias) {
+                    bail!(
+                        "alias {:?} for API {:?} is also the name of an API",
+                        alias,
+                        api.ident,
+                    );
+                }
+                if let Some(other) = aliases.insert(alias.clone(), &api.ident) {
+                    bail!(
+                        "alias {:?} is defined for both {:?} and {:?}",
+                        alias,
+                        other,
+                        api.ident,
+                    );
+                }
             }
         }
+        let aliases = aliases.into_keys().collect();
 
         Ok(ManagedApis {
             apis,
+            aliases,
             unknown_apis: BTreeSet::new(),
+            removed_apis: BTreeSet::new(),
+            skipped_apis: BTreeSet::new(),
             validation: None,
+            selected_validations: Vec::new(),
             use_git_stub_storage: false,
+            document_signing: None,
+            breaking_change_approvals: None,
+            consumer_pins: None,
         })
     }
 
@@ -369,6 +1765,26 @@ impl ManagedApis {
         self
     }
 
+    /// Records the given API identifiers as APIs that were removed on purpose.
+    ///
+    /// Files left behind for a removed API aren't reported as belonging to an
+    /// unknown API (which might be a typo in an identifier). Instead, they're
+    /// reported as a problem that `generate` fixes by deleting them, whatever
+    /// the environment's [`UnknownApiPolicy`] and even if the API has blessed
+    /// documents.
+    ///
+    /// Identifiers of managed APIs are ignored.
+    ///
+    /// [`UnknownApiPolicy`]: crate::UnknownApiPolicy
+    pub fn with_removed_apis<I, S>(mut self, apis: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<ApiIdent>,
+    {
+        self.removed_apis.extend(apis.into_iter().map(|s| s.into()));
+        self
+    }
+
     /// Sets a validation function to be used for all APIs.
     ///
     /// This function will be called for each API document. The
@@ -378,13 +1794,45 @@ impl ManagedApis {
     where
         F: Fn(&OpenAPI, ValidationContext<'_>) + Send + Sync + 'static,
     {
-        self.validation = Some(Box::new(validation));
+        self.validation = Some(Arc::new(validation));
         self
     }
 
-    /// Returns the validation function for all APIs.
-    pub(crate) fn validation(&self) -> Option<&DynValidationFn> {
-        self.validation.as_deref()
+    /// Adds a validation function for the APIs selected by `selector`.
+    ///
+    /// This applies a policy to a group of APIs without configuring each of
+    /// them with [`ManagedApi::with_extra_validation`]. Each document is
+    /// validated by the function set with [`ManagedApis::with_validation`]
+    /// first, then by the selected functions in the order they were added,
+    /// and finally by the API's own extra validation.
+    pub fn with_validation_for<F>(
+        mut self,
+        selector: ApiSelector,
+        validation: F,
+    ) -> Self
+    where
+        F: Fn(&OpenAPI, ValidationContext<'_>) + Send + Sync + 'static,
+    {
+        self.selected_validations.push((selector, Arc::new(validation)));
+        self
+    }
+
+    /// Returns the validation functions for `api`, in the order they're
+    /// called.
+    pub(crate) fn validations_for(
+        &self,
+        api: &ManagedApi,
+    ) -> Vec<&DynValidationFn> {
+        self.validation
+            .as_deref()
+            .into_iter()
+            .chain(
+                self.selected_validations
+                    .iter()
+                    .filter(|(selector, _)| selector.matches(api))
+                    .map(|(_, validation)| &**validation),
+            )
+            .collect()
     }
 
     /// Enables Git stub storage for older blessed API versions.
@@ -411,6 +1859,52 @@ impl ManagedApis {
         api.uses_git_stub_storage().unwrap_or(self.use_git_stub_storage)
     }
 
+    /// Requires detached signatures for every version of every versioned API.
+    ///
+    /// See [`DocumentSigning`] for more.
+    pub fn with_document_signing(mut self, signing: DocumentSigning) -> Self {
+        self.document_signing = Some(signing);
+        self
+    }
+
+    /// Returns the document signing configuration, if any.
+    pub(crate) fn document_signing(&self) -> Option<&DocumentSigning> {
+        self.document_signing.as_ref()
+    }
+
+    /// Allows specific incompatible changes to blessed versions, as approved
+    /// in a checked-in file.
+    ///
+    /// See [`BreakingChangeApprovals`] for more.
+    pub fn with_breaking_change_approvals(
+        mut self,
+        approvals: BreakingChangeApprovals,
+    ) -> Self {
+        self.breaking_change_approvals = Some(approvals);
+        self
+    }
+
+    /// Returns the breaking change approval configuration, if any.
+    pub(crate) fn breaking_change_approvals(
+        &self,
+    ) -> Option<&BreakingChangeApprovals> {
+        self.breaking_change_approvals.as_ref()
+    }
+
+    /// Checks the versions of APIs that downstream consumers are pinned to,
+    /// as recorded in checked-in `pins.toml` files.
+    ///
+    /// See [`ConsumerPins`] for more.
+    pub fn with_consumer_pins(mut self, pins: ConsumerPins) -> Self {
+        self.consumer_pins = Some(pins);
+        self
+    }
+
+    /// Returns the consumer pin configuration, if any.
+    pub(crate) fn consumer_pins(&self) -> Option<&ConsumerPins> {
+        self.consumer_pins.as_ref()
+    }
+
     /// Returns the number of APIs managed by this instance.
     pub fn len(&self) -> usize {
         self.apis.len()
@@ -431,8 +1925,205 @@ impl ManagedApis {
         self.apis.get(ident)
     }
 
+    /// Returns a copy of this instance that only manages the APIs in `scope`,
+    /// for a partial check.
+    ///
+    /// Documents for the other APIs are skipped when loading, rather than
+    /// being reported as belonging to unknown APIs.
+    pub(crate) fn scoped(&self, scope: &BTreeSet<ApiIdent>) -> ManagedApis {
+        let (apis, skipped): (BTreeMap<_, _>, BTreeMap<_, _>) =
+            self.apis.iter().partition(|(ident, _)| scope.contains(*ident));
+        ManagedApis {
+            apis: apis
+                .into_iter()
+                .map(|(ident, api)| (ident.clone(), api.clone()))
+                .collect(),
+            aliases: self.aliases.clone(),
+            unknown_apis: self.unknown_apis.clone(),
+            removed_apis: self.removed_apis.clone(),
+            skipped_apis: self
+                .skipped_apis
+                .iter()
+                .chain(skipped.into_keys())
+                .cloned()
+                .collect(),
+            validation: self.validation.clone(),
+            selected_validations: self.selected_validations.clone(),
+            use_git_stub_storage: self.use_git_stub_storage,
+            document_signing: self.document_signing.clone(),
+            breaking_change_approvals: self.breaking_change_approvals.clone(),
+            consumer_pins: self.consumer_pins.clone(),
+        }
+    }
+
+    /// Returns true if `ident` was left out of a partial check.
+    pub(crate) fn is_skipped_api(&self, ident: &ApiIdent) -> bool {
+        self.skipped_apis.contains(ident)
+    }
+
+    /// Returns true if `ident` is an alias for one of the managed APIs.
+    ///
+    /// Documents under an alias are copies maintained separately, so they're
+    /// skipped when loading documents.
+    pub(crate) fn is_alias(&self, ident: &ApiIdent) -> bool {
+        self.aliases.contains(ident)
+    }
+
+    /// If the top-level entry `name` in a documents directory belongs to an
+    /// API that isn't managed (and isn't an alias, or left out of a partial
+    /// check), returns that API's identifier.
+    ///
+    /// Directories belong to the versioned API they're named after, and
+    /// `<ident>.json` files to the lockstep API `<ident>`. Other entries don't
+    /// belong to any API.
+    pub(crate) fn unmanaged_api_for_entry(
+        &self,
+        name: &str,
+        is_dir: bool,
+    ) -> Option<ApiIdent> {
+        let ident = if is_dir { name } else { name.strip_suffix(".json")? };
+        let ident = ApiIdent::from(ident.to_owned());
+        (self.api(&ident).is_none()
+            && !self.is_alias(&ident)
+            && !self.is_skipped_api(&ident))
+        .then_some(ident)
+    }
+
     /// Returns the set of unknown APIs.
     pub fn unknown_apis(&self) -> &BTreeSet<ApiIdent> {
         &self.unknown_apis
     }
+
+    /// Returns the set of APIs that were removed on purpose.
+    pub fn removed_apis(&self) -> &BTreeSet<ApiIdent> {
+        &self.removed_apis
+    }
+
+    /// Returns true if `ident` was removed on purpose, and isn't managed.
+    pub(crate) fn is_removed_api(&self, ident: &ApiIdent) -> bool {
+        self.removed_apis.contains(ident) && self.api(ident).is_none()
+    }
+
+    /// Determines which version of an API's OpenAPI document should be served
+    /// to a client that requested version `requested`.
+    ///
+    /// This is intended for servers that serve their own OpenAPI documents
+    /// (e.g. `/openapi.json?version=...`). If `requested` is a supported
+    /// version of the API, that version is returned. Otherwise, the highest
+    /// supported version that satisfies `requested` as a Cargo default (caret)
+    /// requirement is returned: the version must be at least `requested`, and
+    /// have the same major version, or for `0.y.z` versions, the same minor
+    /// version. An older version is never returned, since it may lack
+    /// features the client relies on.
+    ///
+    /// Supported versions are the ones configured in Rust. Once `check`
+    /// passes, these are exactly the versions with documents on disk.
+    pub fn resolve_version(
+        &self,
+        ident: &ApiIdent,
+        requested: &semver::Version,
+    ) -> VersionResolution {
+        let Some(api) = self.api(ident) else {
+            return VersionResolution::UnknownApi;
+        };
+
+        if api.iter_versions_semver().any(|version| version == requested) {
+            return VersionResolution::Exact(requested.clone());
+        }
+
+        api.iter_versions_semver()
+            .filter(|version| {
+                *version >= requested
+                    && is_semver_compatible(version, requested)
+            })
+            .max()
+            .map_or(VersionResolution::NoCompatibleVersion, |version| {
+                VersionResolution::Compatible(version.clone())
+            })
+    }
+
+    /// Returns the next available version number for the versioned API
+    /// `ident`.
+    ///
+    /// This follows every version of the API that's supported, blessed (as
+    /// found in `env`'s default blessed branch), or present in the local
+    /// documents directory: one major version past them, or for APIs with
+    /// date-encoded versions, today's date with the next free serial number.
+    /// Local documents for versions that aren't supported count too: they're
+    /// typically versions added on another branch that's in flight.
+    ///
+    /// This is the number the `new-version` command uses. Warnings found
+    /// while loading documents are returned rather than printed. Returns an
+    /// error if the API is unknown or lockstep, or if documents can't be
+    /// loaded.
+    pub fn next_version(
+        &self,
+        env: &Environment,
+        ident: &ApiIdent,
+    ) -> anyhow::Result<(semver::Version, ErrorAccumulator)> {
+        let env = env.resolve(None)?;
+        let blessed_source = BlessedSource::default_for(&env);
+        let (latest, problems) = latest_version_in_use(
+            self,
+            &env,
+            &blessed_source,
+            ident,
+            &Styles::default(),
+        )?;
+        let errors: Vec<_> =
+            problems.iter_errors().map(|e| format!("{:#}", e)).collect();
+        if !errors.is_empty() {
+            bail!("failed to load documents: {}", errors.join("; "));
+        }
+        let scheme = self.api(ident).and_then(|api| api.version_scheme());
+        let next = next_version_after(
+            &latest,
+            scheme,
+            chrono::Utc::now().date_naive(),
+        )?;
+        Ok((next, problems))
+    }
+}
+
+/// The result of [`ManagedApis::resolve_version`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VersionResolution {
+    /// The requested version is a supported version of the API.
+    Exact(semver::Version),
+
+    /// The requested version is not supported, but this is the highest
+    /// supported newer version that is compatible with it.
+    Compatible(semver::Version),
+
+    /// No supported version is compatible with the requested version.
+    NoCompatibleVersion,
+
+    /// The API is not managed by this instance.
+    UnknownApi,
+}
+
+impl VersionResolution {
+    /// Returns the version to serve, if any.
+    pub fn version(&self) -> Option<&semver::Version> {
+        match self {
+            VersionResolution::Exact(version)
+            | VersionResolution::Compatible(version) => Some(version),
+            VersionResolution::NoCompatibleVersion
+            | VersionResolution::UnknownApi => None,
+        }
+    }
+}
+
+/// Returns true if `a` and `b` are compatible under semver rules: the leftmost
+/// nonzero component of the two versions must be the same.
+fn is_semver_compatible(a: &semver::Version, b: &semver::Version) -> bool {
+    if a.major != b.major {
+        false
+    } else if a.major > 0 {
+        true
+    } else if a.minor != b.minor {
+        false
+    } else {
+        a.minor > 0 || a.patch == b.patch
+    }
 }
@@ -0,0 +1,296 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Approvals for deliberately breaking blessed versions of APIs.
+//!
+//! See [`BreakingChangeApprovals`] for more.
+
+use crate::validation::overwrite_file;
+use anyhow::{Context, anyhow, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use dropshot_api_manager_types::ApiIdent;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// The number of hex digits in the hash identifying a compatibility issue.
+pub(crate) const ISSUE_HASH_LEN: usize = 16;
+
+/// A checked-in file approving specific incompatible changes to blessed
+/// versions of APIs.
+///
+/// Blessed versions are meant to be immutable, so `check` reports an
+/// incompatible change to one as an error. Occasionally, though, a blessed
+/// version must be broken on purpose, e.g. to remove an endpoint with a
+/// security problem. The approval file records each such change along with
+/// who approved it and why, so that the decision is reviewed with the change.
+///
+/// Attach approvals with [`ManagedApis::with_breaking_change_approvals`].
+/// `check` then prints a hash identifying each issue with a blessed version,
+/// and an issue is approved by adding an entry for it:
+///
+/// ```json
+/// {
+///   "approvals": [
+///     {
+///       "api": "my-api",
+///       "version": "1.0.0",
+///       "issue": "0123456789abcdef",
+///       "approver": "security-team",
+///       "justification": "Remove /export, which leaks credentials."
+///     }
+///   ]
+/// }
+/// ```
+///
+/// Approved issues are no longer reported, but other issues with the same
+/// version still are. The blessed document itself is left alone. An approval
+/// that doesn't match any issue, e.g. because the change was reverted or the
+/// version is no longer supported, is reported as stale, and `generate`
+/// removes it. The file doesn't need to exist if there are no approvals.
+///
+/// An issue's hash is derived from its description, so it changes along with
+/// the change it describes.
+///
+/// [`ManagedApis::with_breaking_change_approvals`]: crate::ManagedApis::with_breaking_change_approvals
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BreakingChangeApprovals {
+    path: Utf8PathBuf,
+}
+
+impl BreakingChangeApprovals {
+    /// Creates approvals stored in the file at `path` (relative to the
+    /// repository root).
+    pub fn new(path: impl Into<Utf8PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns the path to the approval file, relative to the repository
+    /// root.
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+
+    /// Loads and validates the approvals in the file.
+    pub(crate) fn load(
+        &self,
+        repo_root: &Utf8Path,
+    ) -> anyhow::Result<Vec<BreakingChangeApproval>> {
+        let Some(value) = self.read(repo_root)? else {
+            return Ok(Vec::new());
+        };
+        let mut approvals: Vec<BreakingChangeApproval> = Vec::new();
+        for (index, entry) in entries(&value)?.iter().enumerate() {
+            let approval = BreakingChangeApproval::parse(entry)
+                .with_context(|| format!("approval {}", index))?;
+            if approvals.iter().any(|a| a.is_same_issue(&approval)) {
+                bail!("approval {}: duplicate approval of {}", index, approval);
+            }
+            approvals.push(approval);
+        }
+        Ok(approvals)
+    }
+
+    /// Removes `approval` from the file.
+    pub(crate) fn remove(
+        &self,
+        repo_root: &Utf8Path,
+        approval: &BreakingChangeApproval,
+    ) -> anyhow::Result<()> {
+        let mut value = self.read(repo_root)?.unwrap_or_default();
+        let Some(Value::Array(entries)) = value.get_mut("approvals") else {
+            bail!("{}: expected an \"approvals\" array", self.path);
+        };
+        entries.retain(|entry| {
+            BreakingChangeApproval::parse(entry)
+                .is_ok_and(|a| !a.is_same_issue(approval))
+        });
+        let mut contents = serde_json::to_vec_pretty(&value)
+            .expect("serializing a JSON value always succeeds");
+        contents.push(b'\n');
+        let _ = overwrite_file(&repo_root.join(&self.path), &contents)?;
+        Ok(())
+    }
+
+    fn read(&self, repo_root: &Utf8Path) -> anyhow::Result<Option<Value>> {
+        let path = repo_root.join(&self.path);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs_err::read(&path)?;
+        let value = serde_json::from_slice(&contents)
+            .with_context(|| format!("{} is not valid JSON", self.path))?;
+        Ok(Some(value))
+    }
+}
+
+fn entries(value: &Value) -> anyhow::Result<&Vec<Value>> {
+    value.get("approvals").and_then(Value::as_array).ok_or_else(|| {
+        anyhow!("expected an object with an \"approvals\" array")
+    })
+}
+
+/// An approval of a single compatibility issue with a blessed version.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct BreakingChangeApproval {
+    api: ApiIdent,
+    version: semver::Version,
+    issue: String,
+    approver: String,
+    justification: String,
+}
+
+impl BreakingChangeApproval {
+    fn parse(entry: &Value) -> anyhow::Result<Self> {
+        let Some(entry) = entry.as_object() else {
+            bail!("expected an object");
+        };
+        for key in entry.keys() {
+            if !["api", "version", "issue", "approver", "justification"]
+                .contains(&key.as_str())
+            {
+                bail!("unknown field {:?}", key);
+            }
+        }
+        let version = field(entry, "version")?;
+        let version = version
+            .parse()
+            .with_context(|| format!("invalid version {:?}", version))?;
+        let issue = field(entry, "issue")?;
+        if issue.len() != ISSUE_HASH_LEN
+            || !issue.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+        {
+            bail!(
+                "invalid issue {:?}: expected {} lowercase hex digits",
+                issue,
+                ISSUE_HASH_LEN,
+            );
+        }
+        Ok(Self {
+            api: ApiIdent::from(field(entry, "api")?.to_owned()),
+            version,
+            issue: issue.to_owned(),
+            approver: field(entry, "approver")?.to_owned(),
+            justification: field(entry, "justification")?.to_owned(),
+        })
+    }
+
+    pub(crate) fn api(&self) -> &ApiIdent {
+        &self.api
+    }
+
+    pub(crate) fn version(&self) -> &semver::Version {
+        &self.version
+    }
+
+    pub(crate) fn issue(&self) -> &str {
+        &self.issue
+    }
+
+    fn is_same_issue(&self, other: &Self) -> bool {
+        self.api == other.api
+            && self.version == other.version
+            && self.issue == other.issue
+    }
+}
+
+impl fmt::Display for BreakingChangeApproval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "approval of issue {} with {} v{} by {} ({:?})",
+            self.issue,
+            self.api,
+            self.version,
+            self.approver,
+            self.justification,
+        )
+    }
+}
+
+/// Returns the value of the required, non-empty string field `key`.
+fn field<'a>(
+    entry: &'a Map<String, Value>,
+    key: &str,
+) -> anyhow::Result<&'a str> {
+    match entry.get(key).and_then(Value::as_str) {
+        Some(value) if !value.trim().is_empty() => Ok(value),
+        Some(_) => bail!("field {:?} is empty", key),
+        None => bail!("missing string field {:?}", key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_load() {
+        let dir = camino_tempfile::tempdir().unwrap();
+        let approvals = BreakingChangeApprovals::new("approvals.json");
+        assert_eq!(approvals.load(dir.path()).unwrap(), []);
+
+        let entry = json!({
+            "api": "my-api",
+            "version": "1.0.0",
+            "issue": "0123456789abcdef",
+            "approver": "security-team",
+            "justification": "Remove an endpoint that leaks credentials.",
+        });
+        let write = |entries: Value| {
+            let value = json!({ "approvals": entries });
+            let _ = overwrite_file(
+                &dir.path().join("approvals.json"),
+                &serde_json::to_vec(&value).unwrap(),
+            )
+            .unwrap();
+        };
+
+        write(json!([entry]));
+        let loaded = approvals.load(dir.path()).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].api().as_str(), "my-api");
+        assert_eq!(loaded[0].issue(), "0123456789abcdef");
+
+        let mut invalid = entry.clone();
+        invalid["issue"] = json!("0123");
+        write(json!([entry, invalid]));
+        let error = approvals.load(dir.path()).unwrap_err();
+        assert_eq!(
+            format!("{:#}", error).split(':').next(),
+            Some("approval 1")
+        );
+
+        let mut empty = entry.clone();
+        empty["justification"] = json!(" ");
+        write(json!([empty]));
+        assert!(approvals.load(dir.path()).is_err());
+
+        write(json!([entry, entry]));
+        assert!(approvals.load(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_remove() {
+        let dir = camino_tempfile::tempdir().unwrap();
+        let approvals = BreakingChangeApprovals::new("approvals.json");
+        let first = json!({
+            "api": "my-api",
+            "version": "1.0.0",
+            "issue": "0123456789abcdef",
+            "approver": "security-team",
+            "justification": "Remove an endpoint that leaks credentials.",
+        });
+        let mut second = first.clone();
+        second["version"] = json!("2.0.0");
+        let _ = overwrite_file(
+            &dir.path().join("approvals.json"),
+            &serde_json::to_vec(&json!({ "approvals": [first, second] }))
+                .unwrap(),
+        )
+        .unwrap();
+
+        let loaded = approvals.load(dir.path()).unwrap();
+        approvals.remove(dir.path(), &loaded[0]).unwrap();
+        assert_eq!(approvals.load(dir.path()).unwrap(), &loaded[1..]);
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Packed baselines of blessed documents.
+//!
+//! `pack-baseline --output FILE` writes every blessed document, as the
+//! configured blessed source resolves them, to a zstd-compressed tarball.
+//! `check --baseline FILE` (and other commands that load blessed documents)
+//! then load blessed documents from that file rather than from VCS. This
+//! bypasses merge-base computation and VCS reads entirely, so checks can run
+//! in hermetic build systems that don't expose the repository's history.
+//!
+//! The tarball contains a manifest, plus each blessed document as a JSON file
+//! laid out the way it would be in the documents directory. Documents stored
+//! as Git stubs are packed as their JSON contents. Entries are written in a
+//! fixed order with fixed metadata, so packing the same blessed documents
+//! always produces the same file.
+
+use crate::spec_files_blessed::BlessedFiles;
+use anyhow::{Context, anyhow, bail};
+use camino::Utf8Path;
+use serde_json::{Value, json};
+use std::io::{Read, Write};
+
+/// The name of the manifest within a baseline.
+const MANIFEST: &str = "baseline-manifest.json";
+
+/// The version of the baseline format, recorded in the manifest.
+const FORMAT_VERSION: u64 = 1;
+
+/// The zstd compression level used for baselines.
+const COMPRESSION_LEVEL: i32 = 19;
+
+/// The contents of a baseline's manifest.
+#[derive(Clone, Debug)]
+pub(crate) struct BaselineManifest {
+    /// Where in VCS history the blessed documents were loaded from when the
+    /// baseline was packed, if they were loaded from VCS.
+    pub(crate) provenance: Option<String>,
+}
+
+/// Returns a baseline containing every document in `blessed`.
+///
+/// Also returns the number of documents packed.
+pub(crate) fn pack_baseline(
+    blessed: &BlessedFiles,
+) -> anyhow::Result<(Vec<u8>, usize)> {
+    let encoder = zstd::Encoder::new(Vec::new(), COMPRESSION_LEVEL)
+        .context("creating zstd encoder")?;
+    let mut builder = tar::Builder::new(encoder);
+
+    let manifest = json!({
+        "format": FORMAT_VERSION,
+        "provenance": blessed.provenance().map(|p| p.to_string()),
+    });
+    let mut manifest = serde_json::to_vec_pretty(&manifest)
+        .context("serializing baseline manifest")?;
+    manifest.push(b'\n');
+    append(&mut builder, MANIFEST, &manifest)?;
+
+    let mut count = 0;
+    for api_files in blessed.values() {
+        for file in api_files.versions().values() {
+            let path = file.spec_file_name().to_json_filename().path();
+            append(&mut builder, path.as_str(), file.contents())?;
+            count += 1;
+        }
+    }
+
+    let encoder = builder.into_inner().context("writing baseline tarball")?;
+    let contents = encoder.finish().context("compressing baseline")?;
+    Ok((contents, count))
+}
+
+/// Appends a regular file to a baseline tarball.
+fn append<W: Write>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    contents: &[u8],
+) -> anyhow::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    // A fixed modification time keeps baselines reproducible.
+    header.set_mtime(0);
+    builder
+        .append_data(&mut header, path, contents)
+        .with_context(|| format!("adding {} to baseline", path))
+}
+
+/// Unpacks the documents in the baseline at `path` into the directory `into`,
+/// returning the baseline's manifest.
+pub(crate) fn unpack_baseline(
+    path: &Utf8Path,
+    into: &Utf8Path,
+) -> anyhow::Result<BaselineManifest> {
+    let file = fs_err::File::open(path)?;
+    let decoder = zstd::Decoder::new(file)
+        .with_context(|| format!("reading baseline {}", path))?;
+    let mut archive = tar::Archive::new(decoder);
+
+    let mut manifest = None;
+    let entries = archive
+        .entries()
+        .with_context(|| format!("reading baseline {}", path))?;
+    for entry in entries {
+        let mut entry =
+            entry.with_context(|| format!("reading baseline {}", path))?;
+        let entry_path = entry.path()?.into_owned();
+        if entry_path.as_os_str() == MANIFEST {
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents)?;
+            manifest = Some(parse_manifest(&contents).with_context(|| {
+                format!("parsing manifest of baseline {}", path)
+            })?);
+            continue;
+        }
+
+        // `unpack_in` refuses to write outside `into`, e.g. for entries
+        // with `..` components.
+        let unpacked = entry.unpack_in(into).with_context(|| {
+            format!("unpacking {:?} from baseline {}", entry_path, path)
+        })?;
+        if !unpacked {
+            bail!(
+                "baseline {} contains {:?}, which is outside the documents \
+                 directory",
+                path,
+                entry_path,
+            );
+        }
+    }
+
+    manifest.ok_or_else(|| {
+        anyhow!(
+            "{} is not a baseline written by `pack-baseline`: it has no \
+             manifest",
+            path
+        )
+    })
+}
+
+fn parse_manifest(contents: &[u8]) -> anyhow::Result<BaselineManifest> {
+    let manifest: Value = serde_json::from_slice(contents)?;
+    let format = manifest.get("format").and_then(Value::as_u64);
+    if format != Some(FORMAT_VERSION) {
+        bail!(
+            "unsupported baseline format {} (expected {}); repack the \
+             baseline with this version of the API manager",
+            manifest.get("format").unwrap_or(&Value::Null),
+            FORMAT_VERSION,
+        );
+    }
+    let provenance =
+        manifest.get("provenance").and_then(Value::as_str).map(String::from);
+    Ok(BaselineManifest { provenance })
+}
@@ -0,0 +1,314 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Limiting `check` to the APIs affected by a set of changed files.
+//!
+//! CI pipelines that run on diffs know which files changed, e.g. from `git
+//! diff --name-only`. With `check --changed-files`, only the APIs those files
+//! can affect are checked, which saves generating and comparing documents for
+//! the rest.
+//!
+//! Files in the documents directory belong to the API they're named after,
+//! and files an API maintains elsewhere (e.g. its flattened document) belong
+//! to that API. Other files belong to the APIs whose source globs (see
+//! [`ManagedApi::with_source_globs`]) they match. If any file can't be
+//! attributed this way, e.g. because it's in the documents directory but
+//! doesn't belong to a managed API, because the API manager maintains it for
+//! every API, or because some APIs don't have source globs, every API is
+//! checked.
+//!
+//! [`ManagedApi::with_source_globs`]: crate::ManagedApi::with_source_globs
+
+use crate::{
+    apis::ManagedApis,
+    environment::ResolvedEnv,
+    output::{Styles, headers::*, plural},
+};
+use anyhow::{Context, bail};
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use dropshot_api_manager_types::ApiIdent;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use owo_colors::OwoColorize;
+use std::{collections::BTreeSet, fmt, io::Read};
+
+/// Glob patterns for the files an API is defined by, relative to the
+/// repository root.
+#[derive(Clone)]
+pub(crate) struct SourceGlobs {
+    patterns: Vec<String>,
+    set: GlobSet,
+}
+
+impl fmt::Debug for SourceGlobs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The compiled glob set is derived from the patterns, so don't print
+        // it.
+        f.debug_tuple("SourceGlobs").field(&self.patterns).finish()
+    }
+}
+
+impl SourceGlobs {
+    /// Returns a new `SourceGlobs` with `patterns` added to the existing ones.
+    pub(crate) fn with_patterns<I, S>(
+        &self,
+        patterns: I,
+    ) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut all = self.patterns.clone();
+        all.extend(patterns.into_iter().map(|p| p.as_ref().to_owned()));
+        Self::new(all)
+    }
+
+    fn new(patterns: Vec<String>) -> anyhow::Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &patterns {
+            let glob = Glob::new(pattern).with_context(|| {
+                format!("invalid source glob {:?}", pattern)
+            })?;
+            builder.add(glob);
+        }
+        let set = builder.build().context("building source globs")?;
+        Ok(Self { patterns, set })
+    }
+
+    /// Returns true if the file at `path` (relative to the repository root)
+    /// matches any of the patterns.
+    pub(crate) fn is_match(&self, path: &Utf8Path) -> bool {
+        self.set.is_match(path)
+    }
+}
+
+impl Default for SourceGlobs {
+    fn default() -> Self {
+        Self::new(Vec::new()).expect("an empty set of globs is valid")
+    }
+}
+
+/// The APIs that changes to a set of files can affect.
+#[derive(Debug)]
+enum ApiScope {
+    /// Only these APIs can be affected.
+    Apis(BTreeSet<ApiIdent>),
+    /// The affected APIs couldn't be worked out, for the given reason.
+    All(String),
+}
+
+/// Returns a copy of `apis` that only manages the APIs affected by the files
+/// listed at `list`, or `None` if every API must be checked.
+///
+/// The scope of the check is printed either way.
+pub(crate) fn scope_to_changed_files(
+    env: &ResolvedEnv,
+    apis: &ManagedApis,
+    list: &Utf8Path,
+    styles: &Styles,
+) -> anyhow::Result<Option<ManagedApis>> {
+    let paths = read_changed_files(env, list)?;
+    match api_scope(env, apis, &paths) {
+        ApiScope::Apis(scope) => {
+            let idents: Vec<_> = scope.iter().map(ApiIdent::as_str).collect();
+            eprintln!(
+                "{:>HEADER_WIDTH$} check to {} of {} APIs, based on {} changed \
+                 {}{}",
+                "Scoping".style(styles.success_header),
+                scope.len(),
+                apis.len(),
+                paths.len(),
+                plural::files(paths.len()),
+                if idents.is_empty() {
+                    String::new()
+                } else {
+                    format!(": {}", idents.join(", "))
+                },
+            );
+            Ok(Some(apis.scoped(&scope)))
+        }
+        ApiScope::All(reason) => {
+            eprintln!(
+                "{:>HEADER_WIDTH$} check to every API: {}",
+                "Scoping".style(styles.warning_header),
+                reason,
+            );
+            Ok(None)
+        }
+    }
+}
+
+/// Reads the list of changed files at `list`, or from standard input if
+/// `list` is `-`.
+///
+/// The list contains one path per line, either absolute or relative to the
+/// repository root, as printed by `git diff --name-only`. Blank lines are
+/// ignored. The paths are returned relative to the repository root.
+fn read_changed_files(
+    env: &ResolvedEnv,
+    list: &Utf8Path,
+) -> anyhow::Result<Vec<Utf8PathBuf>> {
+    let (source, contents) = if list == "-" {
+        let mut contents = String::new();
+        std::io::stdin()
+            .read_to_string(&mut contents)
+            .context("reading changed files from standard input")?;
+        ("<stdin>", contents)
+    } else {
+        (list.as_str(), fs_err::read_to_string(list)?)
+    };
+
+    let mut paths = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut path = Utf8Path::new(line);
+        if path.is_absolute() {
+            let Ok(relative) = path.strip_prefix(&env.repo_root) else {
+                bail!(
+                    "{}:{}: changed file {} is not within the repository {}",
+                    source,
+                    i + 1,
+                    line,
+                    env.repo_root,
+                );
+            };
+            path = relative;
+        }
+        // Paths like `./foo` refer to the same file as `foo`.
+        paths.push(
+            path.components()
+                .filter(|component| *component != Utf8Component::CurDir)
+                .collect::<Utf8PathBuf>(),
+        );
+    }
+    paths.sort();
+    paths.dedup();
+    Ok(paths)
+}
+
+/// Returns the APIs that changes to `paths` (relative to the repository root)
+/// can affect.
+fn api_scope(
+    env: &ResolvedEnv,
+    apis: &ManagedApis,
+    paths: &[Utf8PathBuf],
+) -> ApiScope {
+    let mut scope = BTreeSet::new();
+    for path in paths {
+        match apis_for_path(env, apis, path) {
+            Ok(idents) => scope.extend(idents),
+            Err(reason) => return ApiScope::All(reason),
+        }
+    }
+    ApiScope::Apis(scope)
+}
+
+/// Returns the APIs that a change to `path` can affect, or why they can't be
+/// worked out.
+fn apis_for_path(
+    env: &ResolvedEnv,
+    apis: &ManagedApis,
+    path: &Utf8Path,
+) -> Result<Vec<ApiIdent>, String> {
+    if let Ok(document) = path.strip_prefix(env.openapi_rel_dir()) {
+        if env.settings.ignore_globs.is_ignored(document) {
+            return Ok(Vec::new());
+        }
+        return match api_for_document(apis, document) {
+            Some(ident) => Ok(vec![ident]),
+            None => Err(format!("{} doesn't belong to a managed API", path)),
+        };
+    }
+    if is_shared_config(apis, path) {
+        return Err(format!("{} is configuration shared between APIs", path));
+    }
+    let owners = apis_maintaining(apis, path);
+    if !owners.is_empty() {
+        return Ok(owners);
+    }
+    if is_maintained_for_every_api(env, path) {
+        return Err(format!(
+            "{} is maintained by the API manager for every API",
+            path
+        ));
+    }
+
+    let mut idents = Vec::new();
+    for api in apis.iter_apis() {
+        let Some(globs) = api.source_globs() else {
+            return Err(format!(
+                "{} has no source globs, so {} may affect it",
+                api.ident(),
+                path,
+            ));
+        };
+        if globs.is_match(path) {
+            idents.push(api.ident().clone());
+        }
+    }
+    Ok(idents)
+}
+
+/// Returns the API that the file at `path`, relative to the documents
+/// directory, belongs to.
+///
+/// Each top-level entry in the documents directory is named after the API it
+/// belongs to: `<ident>.json` for lockstep APIs, `<ident>/` for versioned
+/// APIs, and the alias in place of `<ident>` for copies under an alias.
+fn api_for_document(apis: &ManagedApis, path: &Utf8Path) -> Option<ApiIdent> {
+    let mut components = path.components();
+    let first = components.next()?.as_str();
+    let name = if components.next().is_some() {
+        first
+    } else {
+        first.strip_suffix(".json")?
+    };
+    let ident = ApiIdent::from(name.to_owned());
+    if apis.api(&ident).is_some() {
+        return Some(ident);
+    }
+    apis.iter_apis()
+        .find(|api| api.aliases().contains(&ident))
+        .map(|api| api.ident().clone())
+}
+
+/// Returns the APIs that maintain the file at `path` outside the documents
+/// directory, such as a flattened document.
+fn apis_maintaining(apis: &ManagedApis, path: &Utf8Path) -> Vec<ApiIdent> {
+    apis.iter_apis()
+        .filter(|api| {
+            api.flattened_document().is_some_and(|doc| doc.path() == path)
+                || api
+                    .type_replacements()
+                    .is_some_and(|replacements| replacements.path() == path)
+                || api
+                    .deprecation_summary()
+                    .is_some_and(|summary| summary.path() == path)
+        })
+        .map(|api| api.ident().clone())
+        .collect()
+}
+
+/// Returns true if `path` is a file the API manager maintains that doesn't
+/// belong to any one API, such as the generation timings state file.
+fn is_maintained_for_every_api(env: &ResolvedEnv, path: &Utf8Path) -> bool {
+    env.settings
+        .generation_timings
+        .as_ref()
+        .is_some_and(|timings| timings.path() == path)
+}
+
+/// Returns true if `path` is a configuration file that can affect any API,
+/// such as the file listing breaking change approvals.
+fn is_shared_config(apis: &ManagedApis, path: &Utf8Path) -> bool {
+    apis.breaking_change_approvals()
+        .is_some_and(|approvals| approvals.path() == path)
+        || apis
+            .consumer_pins()
+            .is_some_and(|pins| pins.paths().iter().any(|p| p == path))
+        || apis.document_signing().is_some_and(|signing| {
+            path.starts_with(signing.signatures_dir())
+                || path == signing.allowed_signers()
+        })
+}
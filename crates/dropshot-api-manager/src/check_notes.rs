@@ -0,0 +1,141 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Git notes recording the documents verified by `check`.
+//!
+//! With `check --write-note`, a successful check records the SHA-256 digest of
+//! every local document in a note on `HEAD`, under `refs/notes/openapi-check`.
+//! `verify-notes` later confirms that the documents in the working copy match
+//! the note on a commit, so auditors can tie a commit to the API state that CI
+//! verified for it.
+//!
+//! Git doesn't push or fetch notes by default: CI must push
+//! `refs/notes/openapi-check` explicitly, and auditors must fetch it.
+
+use crate::{
+    apis::ManagedApis, environment::ResolvedEnv, provenance::document_digests,
+    spec_files_local::LocalFiles,
+};
+use anyhow::{Context, bail};
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The contents of a check note.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct CheckNote {
+    /// The SHA-256 digest of each document, keyed by its path relative to
+    /// the repository root.
+    documents: BTreeMap<String, String>,
+}
+
+impl CheckNote {
+    /// Returns a note for the documents in `local_files`.
+    pub(crate) fn from_local_files(
+        env: &ResolvedEnv,
+        apis: &ManagedApis,
+        local_files: &LocalFiles,
+    ) -> Self {
+        let documents = document_digests(env, apis, local_files)
+            .into_iter()
+            .map(|(path, digest)| (path.into_string(), digest))
+            .collect();
+        Self { documents }
+    }
+
+    /// Parses the contents of a note.
+    pub(crate) fn parse(contents: &str) -> anyhow::Result<Self> {
+        let value: Value =
+            serde_json::from_str(contents).context("note is not valid JSON")?;
+        let Some(entries) = value.get("documents").and_then(Value::as_object)
+        else {
+            bail!("expected an object with a \"documents\" object");
+        };
+        let mut documents = BTreeMap::new();
+        for (path, digest) in entries {
+            let Some(digest) = digest.as_str() else {
+                bail!("digest of {:?} is not a string", path);
+            };
+            documents.insert(path.clone(), digest.to_owned());
+        }
+        Ok(Self { documents })
+    }
+
+    /// Returns the number of documents in the note.
+    pub(crate) fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Returns the contents of the note.
+    pub(crate) fn to_contents(&self) -> Vec<u8> {
+        let documents: Map<String, Value> = self
+            .documents
+            .iter()
+            .map(|(path, digest)| (path.clone(), Value::from(digest.as_str())))
+            .collect();
+        let note = json!({
+            "dropshot-api-manager": env!("CARGO_PKG_VERSION"),
+            "documents": documents,
+        });
+        let mut contents = serde_json::to_vec_pretty(&note)
+            .expect("serializing a JSON value always succeeds");
+        contents.push(b'\n');
+        contents
+    }
+
+    /// Returns a description of each way in which `current` differs from
+    /// this (recorded) note, in path order.
+    pub(crate) fn differences(&self, current: &CheckNote) -> Vec<String> {
+        let paths: BTreeSet<&String> =
+            self.documents.keys().chain(current.documents.keys()).collect();
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                match (self.documents.get(path), current.documents.get(path)) {
+                    (Some(recorded), Some(current)) if recorded == current => {
+                        None
+                    }
+                    (Some(_), Some(_)) => Some(format!("{}: changed", path)),
+                    (Some(_), None) => Some(format!("{}: missing", path)),
+                    (None, _) => Some(format!("{}: not in note", path)),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_differences() {
+        let recorded = CheckNote {
+            documents: BTreeMap::from([
+                ("openapi/a.json".to_owned(), "aaaa".to_owned()),
+                ("openapi/b.json".to_owned(), "bbbb".to_owned()),
+                ("openapi/c.json".to_owned(), "cccc".to_owned()),
+            ]),
+        };
+        let contents = String::from_utf8(recorded.to_contents()).unwrap();
+        assert_eq!(CheckNote::parse(&contents).unwrap(), recorded);
+        assert!(recorded.differences(&recorded).is_empty());
+
+        let current = CheckNote {
+            documents: BTreeMap::from([
+                ("openapi/a.json".to_owned(), "aaaa".to_owned()),
+                ("openapi/b.json".to_owned(), "b000".to_owned()),
+                ("openapi/d.json".to_owned(), "dddd".to_owned()),
+            ]),
+        };
+        assert_eq!(
+            recorded.differences(&current),
+            [
+                "openapi/b.json: changed",
+                "openapi/c.json: missing",
+                "openapi/d.json: not in note",
+            ],
+        );
+
+        assert!(CheckNote::parse("{}").is_err());
+        assert!(CheckNote::parse(r#"{ "documents": { "a": 1 } }"#).is_err());
+    }
+}
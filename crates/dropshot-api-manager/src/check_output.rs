@@ -0,0 +1,277 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Machine-readable output of `check` runs.
+//!
+//! `check --format json` prints a JSON document describing the result of the
+//! check to standard output, for tools that act on it, e.g. to annotate a pull
+//! request with the problems found. Human-readable output is still printed to
+//! standard error. The document has:
+//!
+//! * `schema_version`: the version of the document's schema (see below);
+//! * `result`: `success`, `needs_update` or `failures`, as for the exit code;
+//! * `problems`: every problem found, with the API and (if any) version it's
+//!   associated with, its kind, a message, and the steps `generate` would take
+//!   to fix it (or null if it can't be fixed automatically); and
+//! * `warnings`: the warnings printed while loading documents.
+//!
+//! If the check fails to run, e.g. because documents can't be loaded, nothing
+//! is printed.
+//!
+//! The document is described by a JSON schema, kept in this crate at
+//! `schemas/check-output.json`, so that consumers can validate it or generate
+//! types from it. The schema is generated, and kept up-to-date, by this
+//! module's tests.
+//!
+//! # Compatibility
+//!
+//! `schema_version` is an integer, incremented whenever the document changes
+//! in a way that could break existing consumers: removing or renaming a
+//! field, changing a field's type or meaning, or adding a `result`. Other
+//! changes keep the version, so consumers must ignore fields they don't know
+//! about, and must accept problem kinds they don't know about (new kinds are
+//! added along with the problems the API manager detects). Consumers should
+//! check `schema_version` and reject versions they don't support.
+
+use crate::{
+    environment::ErrorAccumulator,
+    output::{CheckResult, InlineErrorChain},
+    resolved::{ProblemKind, Resolved},
+};
+use clap::ValueEnum;
+use dropshot_api_manager_types::ApiIdent;
+use serde_json::{Value, json};
+
+/// The version of the schema of the JSON document `check --format json`
+/// prints.
+///
+/// See the module documentation for when this changes.
+const SCHEMA_VERSION: u64 = 1;
+
+/// The format `check` prints its result to standard output in.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub(crate) enum CheckFormat {
+    /// Nothing is printed to standard output: the result is only described by
+    /// the human-readable output on standard error.
+    #[default]
+    Human,
+    /// A JSON document describing the result and every problem found.
+    Json,
+}
+
+/// Renders the JSON document for a `check` run with the given `result`.
+pub(crate) fn render_check_output(
+    resolved: &Resolved<'_>,
+    result: CheckResult,
+    load_problems: &ErrorAccumulator,
+) -> String {
+    let problems = resolved
+        .iter_all_problems()
+        .map(|(ident, version, problem)| {
+            let fix = problem.fix().map(|fix| {
+                fix.to_string()
+                    .trim_end()
+                    .split('\n')
+                    .map(str::to_owned)
+                    .collect()
+            });
+            problem_json(
+                ident,
+                version,
+                problem.kind(),
+                InlineErrorChain::new(problem).to_string(),
+                fix,
+            )
+        })
+        .collect();
+    let warnings = load_problems
+        .iter_warnings()
+        .map(|warning| format!("{:#}", warning))
+        .collect();
+    let mut out =
+        serde_json::to_string_pretty(&output_json(result, problems, warnings))
+            .expect("serializing JSON values succeeds");
+    out.push('\n');
+    out
+}
+
+/// Returns the name of `result` in machine-readable output.
+pub(crate) fn result_name(result: CheckResult) -> &'static str {
+    match result {
+        CheckResult::Success => "success",
+        CheckResult::NeedsUpdate => "needs_update",
+        CheckResult::Failures => "failures",
+    }
+}
+
+fn output_json(
+    result: CheckResult,
+    problems: Vec<Value>,
+    warnings: Vec<String>,
+) -> Value {
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "result": result_name(result),
+        "problems": problems,
+        "warnings": warnings,
+    })
+}
+
+fn problem_json(
+    ident: &ApiIdent,
+    version: Option<&semver::Version>,
+    kind: ProblemKind,
+    message: String,
+    fix: Option<Vec<String>>,
+) -> Value {
+    json!({
+        "api": ident.as_str(),
+        "version": version.map(ToString::to_string),
+        "kind": format!("{kind:?}"),
+        "message": message,
+        "fix": fix,
+    })
+}
+
+/// Returns the JSON schema for the document `check --format json` prints.
+#[cfg(test)]
+fn check_output_schema() -> Value {
+    let results =
+        [CheckResult::Success, CheckResult::NeedsUpdate, CheckResult::Failures]
+            .map(result_name);
+    let version_description = "The version of this schema. It's only \
+        incremented for changes that could break existing consumers.";
+    let result_description = "The result of the check: `success` if \
+        everything is up-to-date, `needs_update` if `generate` would fix \
+        every problem, and `failures` otherwise.";
+    let kind_description = "The kind of problem. Kinds may be added without \
+        changing the schema version, so unknown kinds must be accepted.";
+    let fix_description = "The steps `generate` would take to fix the \
+        problem, or null if it can't be fixed automatically.";
+    let problem = json!({
+        "type": "object",
+        "required": ["api", "version", "kind", "message", "fix"],
+        "properties": {
+            "api": {
+                "description": "The API the problem is associated with.",
+                "type": "string",
+            },
+            "version": {
+                "description": "The API version the problem is associated \
+                    with, or null for problems with the API as a whole.",
+                "type": ["string", "null"],
+            },
+            "kind": { "description": kind_description, "type": "string" },
+            "message": {
+                "description": "A description of the problem.",
+                "type": "string",
+            },
+            "fix": {
+                "description": fix_description,
+                "type": ["array", "null"],
+                "items": { "type": "string" },
+            },
+        },
+    });
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "dropshot-api-manager check output",
+        "description": "The document printed by `check --format json`.",
+        "type": "object",
+        "required": ["schema_version", "result", "problems", "warnings"],
+        "properties": {
+            "schema_version": {
+                "description": version_description,
+                "const": SCHEMA_VERSION,
+            },
+            "result": { "description": result_description, "enum": results },
+            "problems": {
+                "description": "Every problem found.",
+                "type": "array",
+                "items": { "$ref": "#/$defs/problem" },
+            },
+            "warnings": {
+                "description": "The warnings printed while loading documents.",
+                "type": "array",
+                "items": { "type": "string" },
+            },
+        },
+        "$defs": { "problem": problem },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::overwrite_file;
+    use camino::Utf8Path;
+    use std::collections::BTreeSet;
+
+    /// The schema kept in this crate, relative to the crate root.
+    const SCHEMA_PATH: &str = "schemas/check-output.json";
+
+    #[test]
+    fn test_check_output_schema() {
+        let path = Utf8Path::new(env!("CARGO_MANIFEST_DIR")).join(SCHEMA_PATH);
+        let mut expected = serde_json::to_string_pretty(&check_output_schema())
+            .expect("serializing JSON values succeeds");
+        expected.push('\n');
+        if std::env::var("EXPECTORATE").as_deref() == Ok("overwrite") {
+            let _ = overwrite_file(&path, expected.as_bytes()).unwrap();
+        }
+        let actual = fs_err::read_to_string(&path).unwrap();
+        assert_eq!(
+            actual, expected,
+            "{SCHEMA_PATH} is out of date: rerun this test with \
+             EXPECTORATE=overwrite to update it",
+        );
+    }
+
+    /// Returns the names of the properties of `value`, an object.
+    fn keys(value: &Value) -> BTreeSet<&str> {
+        value.as_object().unwrap().keys().map(String::as_str).collect()
+    }
+
+    /// Returns the names of the properties `schema` describes and requires.
+    fn schema_keys(schema: &Value) -> (BTreeSet<&str>, BTreeSet<&str>) {
+        let required = schema["required"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|key| key.as_str().unwrap())
+            .collect();
+        (keys(&schema["properties"]), required)
+    }
+
+    #[test]
+    fn test_output_matches_schema() {
+        let schema = check_output_schema();
+        let problem = problem_json(
+            &ApiIdent::from("api".to_owned()),
+            Some(&semver::Version::new(1, 0, 0)),
+            ProblemKind::LocalVersionStale,
+            "message".to_owned(),
+            Some(vec!["rewrite api-1.0.0.json".to_owned()]),
+        );
+        let output = output_json(
+            CheckResult::NeedsUpdate,
+            vec![problem.clone()],
+            vec!["warning".to_owned()],
+        );
+
+        // Every property is both described and required, so that consumers
+        // can rely on its presence.
+        let (properties, required) = schema_keys(&schema);
+        assert_eq!(keys(&output), properties);
+        assert_eq!(keys(&output), required);
+        let (properties, required) = schema_keys(&schema["$defs"]["problem"]);
+        assert_eq!(keys(&problem), properties);
+        assert_eq!(keys(&problem), required);
+
+        assert_eq!(
+            output["schema_version"],
+            schema["properties"]["schema_version"]["const"],
+        );
+        let results = schema["properties"]["result"]["enum"].as_array();
+        assert!(results.unwrap().contains(&output["result"]));
+    }
+}
@@ -0,0 +1,287 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Reports supported versions of APIs that clients no longer use.
+//!
+//! Observations of the versions clients requested are exported from telemetry
+//! (e.g. access logs) as CSV or JSON. Each supported version of each versioned
+//! API is then cross-referenced against them: versions without traffic in the
+//! last N days are reported as candidates for retirement.
+
+use crate::apis::ManagedApis;
+use anyhow::{Context, anyhow, bail};
+use camino::Utf8Path;
+use chrono::{DateTime, NaiveDate, Utc};
+use dropshot_api_manager_types::ApiIdent;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
+};
+
+/// The most recent time a client was observed using a version of an API.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct Observation {
+    pub(crate) api: ApiIdent,
+    pub(crate) version: semver::Version,
+    pub(crate) last_seen: DateTime<Utc>,
+}
+
+/// Loads observations from the file at `path`.
+///
+/// Files ending in `.json` must contain an array of objects with `api`,
+/// `version` and `last_seen` fields. Other files are read as CSV, with a
+/// header row naming those same columns (in any order). `last_seen` is either
+/// an RFC 3339 timestamp or a date (`YYYY-MM-DD`, taken to be midnight UTC).
+pub(crate) fn load_observations(
+    path: &Utf8Path,
+) -> anyhow::Result<Vec<Observation>> {
+    let contents = fs_err::read_to_string(path)?;
+    let observations = if path.extension() == Some("json") {
+        parse_json(&contents)
+    } else {
+        parse_csv(&contents)
+    };
+    observations.with_context(|| format!("reading observations from {}", path))
+}
+
+fn parse_json(contents: &str) -> anyhow::Result<Vec<Observation>> {
+    let value: Value = serde_json::from_str(contents)?;
+    let Value::Array(records) = value else {
+        bail!("expected an array of observations");
+    };
+    records
+        .iter()
+        .enumerate()
+        .map(|(i, record)| {
+            let field = |name: &str| {
+                record.get(name).and_then(Value::as_str).ok_or_else(|| {
+                    anyhow!(
+                        "observation {}: missing string field {:?}",
+                        i,
+                        name
+                    )
+                })
+            };
+            parse_observation(
+                field("api")?,
+                field("version")?,
+                field("last_seen")?,
+            )
+            .with_context(|| format!("observation {}", i))
+        })
+        .collect()
+}
+
+fn parse_csv(contents: &str) -> anyhow::Result<Vec<Observation>> {
+    let mut lines = contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let Some((_, header)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let columns: Vec<_> = split_csv_row(header);
+    let column = |name: &str| {
+        columns
+            .iter()
+            .position(|c| *c == name)
+            .ok_or_else(|| anyhow!("missing column {:?} in header", name))
+    };
+    let (api, version, last_seen) =
+        (column("api")?, column("version")?, column("last_seen")?);
+
+    lines
+        .map(|(i, line)| {
+            let fields = split_csv_row(line);
+            let field = |index: usize| {
+                fields.get(index).copied().ok_or_else(|| {
+                    anyhow!("line {}: expected {} fields", i + 1, columns.len())
+                })
+            };
+            parse_observation(field(api)?, field(version)?, field(last_seen)?)
+                .with_context(|| format!("line {}", i + 1))
+        })
+        .collect()
+}
+
+/// Splits a CSV row into trimmed fields, removing surrounding quotes.
+///
+/// Telemetry exports of API names, versions and timestamps don't contain
+/// commas, so quoted commas aren't supported.
+fn split_csv_row(line: &str) -> Vec<&str> {
+    line.split(',')
+        .map(|field| {
+            let field = field.trim();
+            field
+                .strip_prefix('"')
+                .and_then(|f| f.strip_suffix('"'))
+                .unwrap_or(field)
+        })
+        .collect()
+}
+
+fn parse_observation(
+    api: &str,
+    version: &str,
+    last_seen: &str,
+) -> anyhow::Result<Observation> {
+    let version = semver::Version::parse(version)
+        .with_context(|| format!("invalid version {:?}", version))?;
+    let last_seen = match DateTime::parse_from_rfc3339(last_seen) {
+        Ok(time) => time.to_utc(),
+        Err(_) => NaiveDate::parse_from_str(last_seen, "%Y-%m-%d")
+            .map(|date| date.and_time(Default::default()).and_utc())
+            .with_context(|| {
+                format!(
+                    "invalid last_seen {:?} (expected an RFC 3339 timestamp \
+                     or YYYY-MM-DD)",
+                    last_seen
+                )
+            })?,
+    };
+    Ok(Observation { api: ApiIdent::from(api.to_owned()), version, last_seen })
+}
+
+/// The usage of one version of an API.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct VersionUsage {
+    pub(crate) api: ApiIdent,
+    pub(crate) version: semver::Version,
+    /// When the version was last observed, if ever.
+    pub(crate) last_seen: Option<DateTime<Utc>>,
+    /// True if this is a supported version of the API, false if it was only
+    /// observed.
+    pub(crate) supported: bool,
+    /// True if this is the latest supported version, which can't be retired.
+    pub(crate) latest: bool,
+    /// True if the version is supported, isn't the latest, and hasn't been
+    /// observed within the window.
+    pub(crate) unused: bool,
+}
+
+/// Cross-references `observations` with the supported versions of every
+/// versioned API, returning the usage of each version.
+///
+/// A version is unused if it hasn't been observed in the `days` days before
+/// `now`. Versions that were observed but aren't supported are included too,
+/// so that stale telemetry (or versions retired too eagerly) stand out.
+pub(crate) fn audit_unused_versions(
+    apis: &ManagedApis,
+    observations: &[Observation],
+    now: DateTime<Utc>,
+    days: u32,
+) -> Vec<VersionUsage> {
+    let cutoff = now - chrono::Duration::days(i64::from(days));
+    let mut last_seen: BTreeMap<(&ApiIdent, &semver::Version), DateTime<Utc>> =
+        BTreeMap::new();
+    for observation in observations {
+        let entry = last_seen
+            .entry((&observation.api, &observation.version))
+            .or_insert(observation.last_seen);
+        *entry = (*entry).max(observation.last_seen);
+    }
+
+    let mut usages = Vec::new();
+    for api in apis.iter_apis().filter(|api| api.is_versioned()) {
+        let ident = api.ident();
+        let latest = api.iter_versions_semver().max();
+        // Newest first, the way versions are usually listed.
+        let mut versions: Vec<_> = api.iter_versions_semver().collect();
+        versions.sort_by(|a, b| b.cmp(a));
+        for version in versions {
+            let seen = last_seen.remove(&(ident, version));
+            let is_latest = Some(version) == latest;
+            usages.push(VersionUsage {
+                api: ident.clone(),
+                version: version.clone(),
+                last_seen: seen,
+                supported: true,
+                latest: is_latest,
+                unused: !is_latest && seen.is_none_or(|seen| seen < cutoff),
+            });
+        }
+        // Observed versions that aren't supported.
+        let unsupported: Vec<_> = last_seen
+            .keys()
+            .filter(|(observed, _)| *observed == ident)
+            .copied()
+            .collect();
+        for key in unsupported.into_iter().rev() {
+            let seen = last_seen.remove(&key);
+            usages.push(VersionUsage {
+                api: ident.clone(),
+                version: key.1.clone(),
+                last_seen: seen,
+                supported: false,
+                latest: false,
+                unused: false,
+            });
+        }
+    }
+    usages
+}
+
+/// Writes a report of `usages` to `out`.
+///
+/// Observations for APIs that aren't managed, or aren't versioned, are
+/// reported to standard error.
+pub(crate) fn audit_unused_versions_impl(
+    apis: &ManagedApis,
+    observations: &[Observation],
+    now: DateTime<Utc>,
+    days: u32,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    for ident in observations.iter().map(|o| &o.api).collect::<BTreeSet<_>>() {
+        match apis.api(ident) {
+            Some(api) if api.is_versioned() => {}
+            Some(_) => eprintln!(
+                "warning: ignoring observations for lockstep API {}",
+                ident
+            ),
+            None => {
+                eprintln!(
+                    "warning: ignoring observations for unknown API {}",
+                    ident
+                )
+            }
+        }
+    }
+
+    let usages = audit_unused_versions(apis, observations, now, days);
+    let mut current_api = None;
+    for usage in &usages {
+        if current_api != Some(&usage.api) {
+            writeln!(out, "{}", usage.api)?;
+            current_api = Some(&usage.api);
+        }
+        let seen = match usage.last_seen {
+            Some(seen) => format!(
+                "last seen {} ({} days ago)",
+                seen.format("%Y-%m-%d"),
+                (now - seen).num_days().max(0),
+            ),
+            None => "never seen".to_owned(),
+        };
+        let verdict = if !usage.supported {
+            "not supported"
+        } else if usage.latest {
+            "latest (can't be retired)"
+        } else if usage.unused {
+            "unused (could be retired)"
+        } else {
+            "in use"
+        };
+        writeln!(out, "  v{}: {}, {}", usage.version, seen, verdict)?;
+    }
+
+    let supported = usages.iter().filter(|u| u.supported).count();
+    let unused = usages.iter().filter(|u| u.unused).count();
+    writeln!(
+        out,
+        "{} of {} supported versions have seen no traffic in {} days and \
+         could be retired",
+        unused, supported, days,
+    )?;
+    Ok(())
+}
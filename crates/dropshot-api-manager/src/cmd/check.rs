@@ -2,61 +2,195 @@
 
 use crate::{
     apis::ManagedApis,
-    environment::{BlessedSource, GeneratedSource, ResolvedEnv},
+    check_output::{CheckFormat, render_check_output},
+    environment::{
+        BlessedSource, ErrorAccumulator, GeneratedSource, ResolvedEnv,
+    },
+    metrics::{CheckMetrics, MetricsFormat},
     output::{
         CheckResult, OutputOpts, display_load_problems, display_resolution,
         headers::*,
     },
+    report::render_report,
     resolved::{ProblemSummary, Resolved},
+    sink::OutputEvent,
+    validation::overwrite_file,
 };
+use anyhow::Context;
+use camino::Utf8Path;
+use owo_colors::OwoColorize;
+use std::{io::Write, time::Instant};
+
+/// Where `check` describes its results, besides its output to standard error.
+#[derive(Debug)]
+pub(crate) struct CheckOutputs<'a> {
+    /// The absolute path to write an HTML report to.
+    pub(crate) report: Option<&'a Utf8Path>,
+    /// The absolute path to write metrics to, and their format.
+    pub(crate) metrics: Option<(&'a Utf8Path, MetricsFormat)>,
+    /// The format to print the result to standard output in.
+    pub(crate) format: CheckFormat,
+}
 
+/// Documents rendered from the results of a check.
+#[derive(Debug, Default)]
+pub(crate) struct CheckRenders<'a> {
+    /// If provided, an HTML report of the check is rendered here.
+    pub(crate) report: Option<&'a mut String>,
+    /// If provided, the JSON document `check --format json` prints is
+    /// rendered here.
+    pub(crate) json: Option<&'a mut String>,
+}
+
+/// Checks the APIs, writing the results to `outputs`.
 pub(crate) fn check_impl(
     apis: &ManagedApis,
     env: &ResolvedEnv,
     blessed_source: &BlessedSource,
     generated_source: &GeneratedSource,
+    outputs: &CheckOutputs<'_>,
     output: &OutputOpts,
 ) -> anyhow::Result<CheckResult> {
-    let (result, _summaries) = check_impl_with_summaries(
+    let styles = output.styles(supports_color::Stream::Stderr);
+    let mut metrics = CheckMetrics::new();
+    let mut report = String::new();
+    let mut json = String::new();
+    let renders = CheckRenders {
+        report: outputs.report.is_some().then_some(&mut report),
+        json: (outputs.format == CheckFormat::Json).then_some(&mut json),
+    };
+    let (result, _summaries, _load_problems) = check_impl_with_summaries(
         apis,
         env,
         blessed_source,
         generated_source,
+        &mut metrics,
+        renders,
         output,
     )?;
+
+    if let Some(report_out) = outputs.report {
+        let _ = overwrite_file(report_out, report.as_bytes())?;
+        eprintln!(
+            "{:>HEADER_WIDTH$} report {}",
+            "Wrote".style(styles.success_header),
+            report_out.style(styles.filename),
+        );
+    }
+    if let Some((metrics_out, format)) = outputs.metrics {
+        let _ = overwrite_file(metrics_out, metrics.render(format).as_bytes())?;
+        eprintln!(
+            "{:>HEADER_WIDTH$} metrics {}",
+            "Wrote".style(styles.success_header),
+            metrics_out.style(styles.filename),
+        );
+    }
+    if outputs.format == CheckFormat::Json {
+        std::io::stdout()
+            .write_all(json.as_bytes())
+            .context("failed to write JSON output")?;
+    }
     Ok(result)
 }
 
+/// Like [`check_impl`], but also returns summaries of the problems found, and
+/// the warnings recorded while loading documents.
+///
+/// Metrics about the run are recorded in `metrics`, and the documents
+/// requested in `renders` are rendered.
 pub(crate) fn check_impl_with_summaries(
     apis: &ManagedApis,
     env: &ResolvedEnv,
     blessed_source: &BlessedSource,
     generated_source: &GeneratedSource,
+    metrics: &mut CheckMetrics,
+    renders: CheckRenders<'_>,
     output: &OutputOpts,
-) -> anyhow::Result<(CheckResult, Vec<ProblemSummary>)> {
+) -> anyhow::Result<(CheckResult, Vec<ProblemSummary>, ErrorAccumulator)> {
+    // Checking never writes to the documents directory, so that it works in
+    // read-only checkouts.
+    let env = &env.to_read_only();
     let styles = output.styles(supports_color::Stream::Stderr);
+    let mut load_problems = ErrorAccumulator::new();
+    let start = Instant::now();
 
     eprintln!("{:>HEADER_WIDTH$}", SEPARATOR);
 
-    let (generated, errors) =
-        generated_source.load(apis, &styles, &env.repo_root, &env.vcs)?;
+    let phase_start = Instant::now();
+    let (mut generated, mut errors) =
+        generated_source.load(apis, &styles, env)?;
+    if let Some(timings) = &env.settings.generation_timings {
+        timings.compare(&env.repo_root, &generated, &mut errors);
+    }
     display_load_problems(&errors, &styles)?;
+    load_problems.extend(errors);
+    metrics.record_phase("load_generated", phase_start.elapsed());
+    metrics.record_documents(
+        "generated",
+        generated.values().map(|files| files.versions().len()).sum(),
+    );
 
-    let (local_files, errors) =
-        env.local_source.load(apis, &styles, &env.repo_root, &env.vcs)?;
+    let phase_start = Instant::now();
+    let (local_files, errors) = env.local_source.load(
+        apis,
+        &styles,
+        &env.repo_root,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
     display_load_problems(&errors, &styles)?;
+    load_problems.extend(errors);
+    generated.apply_lockstep_version_policies(apis, &local_files)?;
+    metrics.record_phase("load_local", phase_start.elapsed());
+    metrics.record_documents(
+        "local",
+        local_files
+            .values()
+            .flat_map(|files| files.versions().values())
+            .map(Vec::len)
+            .sum(),
+    );
 
-    let (blessed, errors) =
-        blessed_source.load(&env.repo_root, apis, &styles, &env.vcs)?;
+    let phase_start = Instant::now();
+    let (blessed, errors) = blessed_source.load(
+        &env.repo_root,
+        apis,
+        &styles,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
     display_load_problems(&errors, &styles)?;
+    load_problems.extend(errors);
+    metrics.record_phase("load_blessed", phase_start.elapsed());
+    metrics.record_documents(
+        "blessed",
+        blessed.values().map(|files| files.versions().len()).sum(),
+    );
 
+    let phase_start = Instant::now();
     let resolved = Resolved::new(env, apis, &blessed, &generated, &local_files);
 
     eprintln!("{:>HEADER_WIDTH$}", SEPARATOR);
-    let result = display_resolution(env, apis, &resolved, &styles)?;
+    let result = display_resolution(
+        env,
+        apis,
+        &resolved,
+        &styles,
+        &mut output.problem_limit(),
+    )?;
+    env.settings.output_sinks.emit_problems(&resolved);
+    if let Some(report) = renders.report {
+        *report = render_report(env, apis, &resolved, result);
+    }
+    if let Some(json) = renders.json {
+        *json = render_check_output(&resolved, result, &load_problems);
+    }
 
     // Extract owned summaries before dropping the borrowed resolved state.
     let summaries = resolved.problem_summaries();
+    metrics.record_phase("resolve", phase_start.elapsed());
+    metrics.record_phase("total", start.elapsed());
+    metrics.record_result(result, &summaries);
 
     // Release borrows held by `resolved`, then drop the source
     // collections in parallel. Each contains many parsed OpenAPI
@@ -68,5 +202,6 @@ pub(crate) fn check_impl_with_summaries(
         s.spawn(|| drop(local_files));
     });
 
-    Ok((result, summaries))
+    env.settings.output_sinks.emit(&OutputEvent::CheckFinished { result });
+    Ok((result, summaries, load_problems))
 }
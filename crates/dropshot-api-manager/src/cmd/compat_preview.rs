@@ -0,0 +1,156 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Compares a candidate document against every blessed version of an API.
+//!
+//! This is meant for sketching a redesign before touching the Rust API trait:
+//! a hand-edited document can be checked for which existing versions it would
+//! break, without generating anything.
+
+use crate::{
+    apis::ManagedApis,
+    compatibility::{ApiCompatIssue, api_compatible},
+    environment::{BlessedSource, ResolvedEnv},
+    output::{
+        CheckResult, OutputOpts, Styles, display_load_problems, headers::*,
+        plural,
+    },
+    unstable::without_unstable_operations,
+};
+use anyhow::{Context, bail};
+use camino::Utf8Path;
+use dropshot_api_manager_types::ApiIdent;
+use openapiv3::OpenAPI;
+use owo_colors::OwoColorize;
+use std::collections::BTreeMap;
+
+/// Returns the compatibility issues between each blessed version of the API
+/// `ident` and the candidate document at `candidate_path`.
+///
+/// Versions the candidate is compatible with map to an empty list.
+pub(crate) fn compat_preview_impl(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    blessed_source: &BlessedSource,
+    ident: &ApiIdent,
+    candidate_path: &Utf8Path,
+    output: &OutputOpts,
+) -> anyhow::Result<BTreeMap<semver::Version, Vec<ApiCompatIssue>>> {
+    let styles = output.styles(supports_color::Stream::Stderr);
+    let Some(api) = apis.api(ident) else {
+        bail!("unknown API {:?}", ident);
+    };
+    if !api.is_versioned() {
+        bail!("{} is a lockstep API, so it has no blessed versions", ident);
+    }
+
+    let contents = fs_err::read(candidate_path)?;
+    let candidate: serde_json::Value = serde_json::from_slice(&contents)
+        .with_context(|| format!("parsing {}", candidate_path))?;
+    serde_json::from_value::<OpenAPI>(candidate.clone()).with_context(
+        || format!("{} is not a valid OpenAPI document", candidate_path),
+    )?;
+
+    let (blessed, errors) = blessed_source.load(
+        &env.repo_root,
+        apis,
+        &styles,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    display_load_problems(&errors, &styles)?;
+
+    // As with `check`, unstable operations are excluded from the comparison.
+    let unstable = api.unstable_operations();
+    let stable_candidate =
+        without_unstable_operations(&candidate, unstable).unwrap_or(candidate);
+
+    let mut results = BTreeMap::new();
+    let Some(api_files) = blessed.get(ident) else {
+        return Ok(results);
+    };
+    for (version, file) in api_files.versions() {
+        let stable_blessed =
+            without_unstable_operations(file.value(), unstable);
+        let stable_blessed = stable_blessed.as_ref().unwrap_or(file.value());
+        let issues = api_compatible(stable_blessed, &stable_candidate)
+            .with_context(|| {
+                format!(
+                    "comparing {} v{} to {}",
+                    ident, version, candidate_path
+                )
+            })?;
+        results.insert(version.clone(), issues);
+    }
+    Ok(results)
+}
+
+/// Prints the results of [`compat_preview_impl`].
+pub(crate) fn display_compat_preview(
+    ident: &ApiIdent,
+    results: &BTreeMap<semver::Version, Vec<ApiCompatIssue>>,
+    styles: &Styles,
+) -> CheckResult {
+    let total = results.len();
+    eprintln!(
+        "{:>HEADER_WIDTH$} candidate against {} blessed {} of {}...",
+        CHECKING.style(styles.success_header),
+        total.style(styles.bold),
+        plural::versions(total),
+        ident.style(styles.filename),
+    );
+
+    let mut num_broken = 0;
+    for (version, issues) in results {
+        if issues.is_empty() {
+            eprintln!(
+                "{:>HEADER_WIDTH$} v{} (compatible)",
+                FRESH.style(styles.success_header),
+                version,
+            );
+            continue;
+        }
+
+        num_broken += 1;
+        eprintln!(
+            "{:>HEADER_WIDTH$} v{} ({} incompatible {})",
+            FAILURE.style(styles.failure_header),
+            version,
+            issues.len().style(styles.bold),
+            plural::changes(issues.len()),
+        );
+        let first_indent =
+            format!("{:>width$}- ", "", width = HEADER_WIDTH + 4);
+        let more_indent = " ".repeat(HEADER_WIDTH + 6);
+        for issue in issues {
+            eprintln!(
+                "{}",
+                textwrap::fill(
+                    &styles.text(&issue.to_string()),
+                    textwrap::Options::with_termwidth()
+                        .initial_indent(&first_indent)
+                        .subsequent_indent(&more_indent)
+                )
+            );
+        }
+    }
+
+    eprintln!("{:>HEADER_WIDTH$}", SEPARATOR);
+    if num_broken > 0 {
+        eprintln!(
+            "{:>HEADER_WIDTH$} the candidate would break {} of {} blessed {}",
+            FAILURE.style(styles.failure_header),
+            num_broken.style(styles.bold),
+            total.style(styles.bold),
+            plural::versions(total),
+        );
+        CheckResult::Failures
+    } else {
+        eprintln!(
+            "{:>HEADER_WIDTH$} the candidate is compatible with {} blessed {}",
+            SUCCESS.style(styles.success_header),
+            total.style(styles.bold),
+            plural::versions(total),
+        );
+        CheckResult::Success
+    }
+}
@@ -23,18 +23,27 @@ pub(crate) fn debug_impl(
 
     // Print information about local files.
 
-    let (local_files, errors) =
-        env.local_source.load(apis, &styles, &env.repo_root, &env.vcs)?;
+    let (local_files, errors) = env.local_source.load(
+        apis,
+        &styles,
+        &env.repo_root,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
     dump_structure(&local_files, &errors);
 
     // Print information about what we found in VCS history.
-    let (blessed, errors) =
-        blessed_source.load(&env.repo_root, apis, &styles, &env.vcs)?;
+    let (blessed, errors) = blessed_source.load(
+        &env.repo_root,
+        apis,
+        &styles,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
     dump_structure(&blessed, &errors);
 
     // Print information about generated files.
-    let (generated, errors) =
-        generated_source.load(apis, &styles, &env.repo_root, &env.vcs)?;
+    let (generated, errors) = generated_source.load(apis, &styles, env)?;
     dump_structure(&generated, &errors);
 
     // Print result of resolving the differences.
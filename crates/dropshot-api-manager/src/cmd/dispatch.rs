@@ -2,18 +2,46 @@
 
 use crate::{
     apis::ManagedApis,
+    changed_files::scope_to_changed_files,
+    check_output::CheckFormat,
     cmd::{
-        check::check_impl, debug::debug_impl, generate::generate_impl,
+        audit_unused_versions::{
+            audit_unused_versions_impl, load_observations,
+        },
+        check::{CheckOutputs, check_impl},
+        compat_preview::{compat_preview_impl, display_compat_preview},
+        debug::debug_impl,
+        drift_report::drift_report_impl,
+        generate::{generate_impl, list_generate_outputs},
         list::list_impl,
+        materialize::materialize_impl,
+        new_version::{NewVersionTargets, new_version_impl},
+        pack_baseline::pack_baseline_impl,
+        patch::{PatchFormat, patch_impl},
+        pin_stubs::pin_stubs_impl,
+        pins::pins_impl,
+        since_release::{
+            display_versions_since_release, versions_since_release,
+        },
+        slim::slim_impl,
+        status::status_impl,
+        verify_notes::{verify_notes_impl, write_check_note_impl},
+        watch::{WatchConfig, watch_impl},
+        why_blessed::why_blessed_impl,
     },
     environment::{BlessedSource, Environment, GeneratedSource, ResolvedEnv},
-    output::OutputOpts,
-    vcs::VcsRevision,
+    hermetic::HermeticInputs,
+    metrics::MetricsFormat,
+    output::{CheckResult, OutputOpts, headers::HEADER_WIDTH},
+    validation::overwrite_file,
+    vcs::{GitTrace, RepoVcsKind, VcsRevision},
 };
-use anyhow::Result;
-use camino::Utf8PathBuf;
+use anyhow::{Context, Result, bail};
+use camino::{Utf8Path, Utf8PathBuf};
 use clap::{Args, Parser, Subcommand};
-use std::process::ExitCode;
+use dropshot_api_manager_types::ApiIdent;
+use owo_colors::OwoColorize;
+use std::{io::Write, process::ExitCode};
 
 /// Manage OpenAPI documents for this repository.
 ///
@@ -30,12 +58,58 @@ pub struct App {
 impl App {
     /// Executes the application under the given environment, and with the
     /// provided list of managed APIs.
+    ///
+    /// To run several commands in the same process, such as `check`, then
+    /// `generate`, then `check` again, attach a [`BlessedCache`] to `env` with
+    /// [`Environment::with_blessed_cache`] so that later commands reuse the
+    /// VCS queries made by earlier ones.
+    ///
+    /// [`BlessedCache`]: crate::BlessedCache
     pub fn exec(self, env: &Environment, apis: &ManagedApis) -> ExitCode {
+        // The trace's summary is written when it's dropped, at the end of the
+        // run.
+        let _trace =
+            match self.output_opts.trace_git.as_deref().map(GitTrace::start) {
+                None => None,
+                Some(Ok(trace)) => Some(trace),
+                Some(Err(error)) => {
+                    eprintln!("failure: {:#}", error);
+                    return ExitCode::FAILURE;
+                }
+            };
         let result = match self.command {
             Command::Debug(args) => args.exec(env, apis, &self.output_opts),
             Command::List(args) => args.exec(apis, &self.output_opts),
             Command::Generate(args) => args.exec(env, apis, &self.output_opts),
             Command::Check(args) => args.exec(env, apis, &self.output_opts),
+            Command::Status(args) => args.exec(env, apis, &self.output_opts),
+            Command::PinStubs(args) => args.exec(env, apis, &self.output_opts),
+            Command::VerifyNotes(args) => {
+                args.exec(env, apis, &self.output_opts)
+            }
+            Command::Materialize(args) => {
+                args.exec(env, apis, &self.output_opts)
+            }
+            Command::Slim(args) => args.exec(env, apis, &self.output_opts),
+            Command::CompatPreview(args) => {
+                args.exec(env, apis, &self.output_opts)
+            }
+            Command::Patch(args) => args.exec(env, apis, &self.output_opts),
+            Command::DriftReport(args) => {
+                args.exec(env, apis, &self.output_opts)
+            }
+            Command::WhyBlessed(args) => {
+                args.exec(env, apis, &self.output_opts)
+            }
+            Command::PackBaseline(args) => {
+                args.exec(env, apis, &self.output_opts)
+            }
+            Command::AuditUnusedVersions(args) => args.exec(apis),
+            Command::Pins(args) => args.exec(env, apis),
+            Command::NewVersion(args) => {
+                args.exec(env, apis, &self.output_opts)
+            }
+            Command::Watch(args) => args.exec(env, &self.output_opts),
         };
 
         match result {
@@ -64,9 +138,122 @@ pub enum Command {
 
     /// Check that OpenAPI documents are up-to-date and valid.
     Check(CheckArgs),
+
+    /// Explain the state of each API's OpenAPI documents in prose.
+    ///
+    /// Unlike `check`, which lists problems, this describes every version of
+    /// every API: whether it's blessed, new locally, or has been removed, and
+    /// what `generate` would do about it.
+    Status(StatusArgs),
+
+    /// Protect commits referenced by Git stubs from garbage collection.
+    ///
+    /// Creates a lightweight ref under `refs/openapi/pins/` for every commit
+    /// referenced by a Git stub in the local OpenAPI documents directory. With
+    /// `--check`, verifies that every referenced commit is present and
+    /// reachable from some ref instead.
+    PinStubs(PinStubsArgs),
+
+    /// Verify that the local OpenAPI documents match the check note recorded
+    /// on a commit.
+    ///
+    /// `check --write-note` records the SHA-256 digest of every local document
+    /// in a Git note under `refs/notes/openapi-check`. This command confirms
+    /// that the documents in the working copy are the ones that were checked
+    /// for that commit.
+    VerifyNotes(VerifyNotesArgs),
+
+    /// Write the JSON contents of all local OpenAPI documents, including those
+    /// stored as Git stubs or with shared schemas, to a separate directory.
+    ///
+    /// This is meant for tools (e.g. documentation generators or external diff
+    /// tools) that can't dereference Git stubs or shared schemas. The target
+    /// directory must not overlap the OpenAPI documents directory.
+    Materialize(MaterializeArgs),
+
+    /// Write a reduced OpenAPI document for one version of an API, containing
+    /// only the operations with the given tags.
+    ///
+    /// Components that the selected operations don't (transitively) refer to
+    /// are removed. This is meant for consumers that only integrate against a
+    /// small part of a large API.
+    Slim(SlimArgs),
+
+    /// Check a candidate OpenAPI document against every blessed version of an
+    /// API, listing the versions it would break.
+    ///
+    /// This is meant for sketching a change to an API before touching its
+    /// Rust definition. Nothing is generated or written.
+    CompatPreview(CompatPreviewArgs),
+
+    /// Write a patch from the latest blessed version of an API to the document
+    /// generated for that version.
+    ///
+    /// If the two aren't bytewise identical, `check` requires a version bump.
+    /// The patch shows exactly which changes (often trivial ones, like
+    /// documentation updates) accumulated.
+    Patch(PatchArgs),
+
+    /// Summarize how far the latest blessed version of each API has drifted
+    /// from the document generated for it.
+    ///
+    /// For each versioned API that allows trivial changes to its latest
+    /// version, prints the number of JSON paths in the generated document that
+    /// differ from the blessed one. These changes accumulate until a new
+    /// version is added, so this helps decide when to add one.
+    DriftReport(DriftReportArgs),
+
+    /// Explain how blessedness was decided for one version of an API.
+    ///
+    /// Prints which revision blessed documents were loaded from and why, the
+    /// merge base used, whether the blessed document is stored as a Git stub,
+    /// and the commit where it was first committed upstream.
+    WhyBlessed(WhyBlessedArgs),
+
+    /// Write all blessed OpenAPI documents to a baseline file.
+    ///
+    /// This is meant to be run in CI on the main branch. Checks of other
+    /// branches can then pass the baseline to `--baseline`, to load blessed
+    /// documents from it without computing a merge base or reading VCS
+    /// history.
+    PackBaseline(PackBaselineArgs),
+
+    /// Report supported versions of APIs that clients haven't used recently,
+    /// and could be retired.
+    ///
+    /// Observed client versions are read from a CSV or JSON file, e.g.
+    /// exported from telemetry. Every supported version of every versioned API
+    /// is cross-referenced against them.
+    AuditUnusedVersions(AuditUnusedVersionsArgs),
+
+    /// List the downstream consumers pinned to each version of each API.
+    ///
+    /// Consumers record the versions they use in `pins.toml` files, which are
+    /// configured with `ConsumerPins`. Versions that are no longer supported
+    /// but are still pinned are listed too.
+    Pins(PinsArgs),
+
+    /// Scaffold a new version of a versioned API.
+    ///
+    /// Computes the next version number, adds it to the `api_versions!` list,
+    /// and creates a module for the version in the versions crate, following
+    /// `guides/new-version.md`. Then lists the operations in the latest
+    /// version, which will need manual work if they change.
+    NewVersion(NewVersionArgs),
+
+    /// Rerun `check` whenever API definitions or documents change.
+    ///
+    /// Watches the OpenAPI documents directory, along with the paths
+    /// configured with `Environment::with_watch_paths` and those passed to
+    /// `--path` (typically the source directories of the API crates). Each
+    /// check runs the OpenAPI manager's command as a subprocess, so that
+    /// changes to API definitions are rebuilt first. Results are summarized in
+    /// a line, and the full output of a check is only printed if it failed
+    /// differently from the last run. Runs until interrupted.
+    Watch(WatchArgs),
 }
 
-#[derive(Debug, Args)]
+#[derive(Debug, Default, Args)]
 pub struct BlessedSourceArgs {
     /// Loads blessed OpenAPI documents from the given VCS REVISION.
     ///
@@ -112,6 +299,61 @@ pub struct BlessedSourceArgs {
         value_name("DIRECTORY")
     )]
     pub blessed_from_dir: Option<Utf8PathBuf>,
+
+    /// Skips loading blessed OpenAPI documents entirely, treating the working
+    /// tree as authoritative.
+    ///
+    /// Every document is then only checked against the one generated from the
+    /// API definitions, the way lockstep APIs are. Versioned APIs are not
+    /// checked for compatibility with earlier versions. This is meant for
+    /// forks and exported source trees where VCS history is unavailable or
+    /// irrelevant.
+    ///
+    /// This takes precedence over the other options for blessed documents.
+    #[clap(long, env("OPENAPI_MGR_NO_BLESSED"))]
+    pub no_blessed: bool,
+
+    /// Also treats documents from the given VCS REVISION as blessed.
+    ///
+    /// This is meant for stacked changes: if this branch is stacked on top of
+    /// another unmerged branch, pass that branch here. Versions added on that
+    /// branch are then treated as blessed rather than as new locally, so they
+    /// aren't regenerated or renumbered here.
+    ///
+    /// As with `--blessed-from-vcs`, the merge-base between the working copy
+    /// and REVISION is used. Versions blessed by the main blessed source take
+    /// precedence.
+    #[clap(
+        long,
+        conflicts_with("no_blessed"),
+        env("OPENAPI_MGR_ALSO_BLESSED_FROM"),
+        value_name("REVISION")
+    )]
+    pub also_blessed_from: Option<String>,
+
+    /// Loads blessed OpenAPI documents from a baseline FILE written by
+    /// `pack-baseline`, instead of from VCS.
+    ///
+    /// This is meant for CI: run `pack-baseline` once on the main branch, and
+    /// pass the baseline to checks of other branches. Blessed documents are
+    /// then loaded without computing a merge base or reading VCS history, so
+    /// checks are faster, and work in build systems that don't expose the
+    /// repository's history.
+    ///
+    /// Unlike with `--blessed-from-vcs`, blessed documents are taken from the
+    /// baseline as-is, so the baseline should be packed from a revision this
+    /// branch is based on.
+    #[clap(
+        long,
+        conflicts_with_all([
+            "blessed_from_vcs",
+            "blessed_from_dir",
+            "also_blessed_from",
+        ]),
+        env("OPENAPI_MGR_BASELINE"),
+        value_name("FILE")
+    )]
+    pub baseline: Option<Utf8PathBuf>,
 }
 
 /// Environment variable for the blessed VCS revision.
@@ -127,11 +369,41 @@ impl BlessedSourceArgs {
     pub(crate) fn to_blessed_source(
         &self,
         env: &ResolvedEnv,
+    ) -> Result<BlessedSource, anyhow::Error> {
+        let source = self.to_main_blessed_source(env)?;
+        let Some(stack_base) = &self.also_blessed_from else {
+            return Ok(source);
+        };
+        if env.vcs.kind() == RepoVcsKind::None {
+            bail!(
+                "cannot also load blessed documents from VCS revision \
+                 {stack_base:?}: the API manager is configured to run \
+                 without version control"
+            );
+        }
+        Ok(BlessedSource::WithStackBase {
+            main: Box::new(source),
+            stack_base: VcsRevision::from(stack_base.clone()),
+            directory: Utf8PathBuf::from(env.openapi_rel_dir()),
+        })
+    }
+
+    fn to_main_blessed_source(
+        &self,
+        env: &ResolvedEnv,
     ) -> Result<BlessedSource, anyhow::Error> {
         assert!(
             self.blessed_from_dir.is_none() || self.blessed_from_vcs.is_none()
         );
 
+        if self.no_blessed {
+            return Ok(BlessedSource::Disabled);
+        }
+
+        if let Some(path) = &self.baseline {
+            return Ok(BlessedSource::Baseline { path: path.clone() });
+        }
+
         if let Some(local_directory) = &self.blessed_from_dir {
             return Ok(BlessedSource::Directory {
                 local_directory: local_directory.clone(),
@@ -140,6 +412,17 @@ impl BlessedSourceArgs {
 
         let resolved =
             resolve_blessed_from_vcs(self.blessed_from_vcs.as_deref());
+        if env.vcs.kind() == RepoVcsKind::None {
+            if let Some(revision) = resolved {
+                bail!(
+                    "cannot load blessed documents from VCS revision \
+                     {revision:?}: the API manager is configured to run \
+                     without version control"
+                );
+            }
+            return Ok(BlessedSource::None);
+        }
+
         let revision_str = match &resolved {
             Some(revision) => revision.as_str(),
             None => env.default_blessed_branch.as_str(),
@@ -153,6 +436,45 @@ impl BlessedSourceArgs {
         };
         Ok(BlessedSource::VcsRevisionMergeBase { revision, directory })
     }
+
+    /// Describes why the blessed source returned by
+    /// [`Self::to_blessed_source`] was chosen.
+    pub(crate) fn blessed_source_reason(&self, env: &ResolvedEnv) -> String {
+        let reason = if self.no_blessed {
+            "--no-blessed (or OPENAPI_MGR_NO_BLESSED) is set".to_owned()
+        } else if let Some(path) = &self.baseline {
+            format!("--baseline (or OPENAPI_MGR_BASELINE) is set to {:?}", path)
+        } else if let Some(dir) = &self.blessed_from_dir {
+            format!(
+                "--blessed-from-dir (or OPENAPI_MGR_BLESSED_FROM_DIR) is set \
+                 to {:?}",
+                dir
+            )
+        } else if env.vcs.kind() == RepoVcsKind::None {
+            "the API manager is configured to run without version control"
+                .to_owned()
+        } else if let Some(revision) = &self.blessed_from_vcs {
+            format!(
+                "--blessed-from-vcs (or {}) is set to {:?}",
+                BLESSED_FROM_VCS_ENV, revision
+            )
+        } else if let Some(revision) = resolve_blessed_from_vcs(None) {
+            format!("{} is set to {:?}", BLESSED_FROM_GIT_ENV, revision)
+        } else {
+            format!(
+                "{:?} is the default blessed branch for this repository",
+                env.default_blessed_branch.as_str()
+            )
+        };
+        match &self.also_blessed_from {
+            Some(stack_base) if !self.no_blessed => format!(
+                "{}, and --also-blessed-from (or \
+                 OPENAPI_MGR_ALSO_BLESSED_FROM) is set to {:?}",
+                reason, stack_base
+            ),
+            _ => reason,
+        }
+    }
 }
 
 /// Resolve the blessed-from-vcs value from the CLI flag or environment
@@ -186,6 +508,14 @@ pub struct GeneratedSourceArgs {
     /// implementation, load OpenAPI documents from this directory.
     #[clap(long, value_name("DIRECTORY"))]
     pub generated_from_dir: Option<Utf8PathBuf>,
+
+    /// Generates each OpenAPI document twice, and fails if the two copies
+    /// aren't byte-for-byte identical.
+    ///
+    /// This catches API definitions that produce documents
+    /// non-deterministically, e.g. by iterating over a `HashMap`.
+    #[clap(long, conflicts_with("generated_from_dir"))]
+    pub verify_deterministic: bool,
 }
 
 impl From<GeneratedSourceArgs> for GeneratedSource {
@@ -194,7 +524,9 @@ impl From<GeneratedSourceArgs> for GeneratedSource {
             Some(local_directory) => {
                 GeneratedSource::Directory { local_directory }
             }
-            None => GeneratedSource::Generated,
+            None => GeneratedSource::Generated {
+                verify_deterministic: value.verify_deterministic,
+            },
         }
     }
 }
@@ -238,85 +570,936 @@ pub struct ListArgs {
     verbose: bool,
 }
 
-impl ListArgs {
+impl ListArgs {
+    fn exec(
+        self,
+        apis: &ManagedApis,
+        output: &OutputOpts,
+    ) -> anyhow::Result<ExitCode> {
+        list_impl(apis, self.verbose, output)?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct GenerateArgs {
+    #[clap(flatten)]
+    local: LocalSourceArgs,
+    #[clap(flatten)]
+    blessed: BlessedSourceArgs,
+    #[clap(flatten)]
+    generated: GeneratedSourceArgs,
+
+    /// After generating documents, write a provenance statement for them to
+    /// PATH (relative to the repository root).
+    ///
+    /// The statement is an in-toto statement with a SLSA provenance
+    /// predicate, recording the digest of every document, the current
+    /// commit, and a digest of this executable.
+    #[clap(long, value_name("PATH"))]
+    provenance: Option<Utf8PathBuf>,
+
+    #[clap(flatten)]
+    inputs: InputsArgs,
+
+    /// With `--inputs`, writes the documents directory to DIR rather than
+    /// updating it in place.
+    ///
+    /// The declared inputs are copied to DIR, and then updated there, so that
+    /// afterwards DIR holds the complete documents directory. Nothing is
+    /// written outside DIR. DIR must be empty or not exist.
+    #[clap(
+        long,
+        requires("inputs"),
+        conflicts_with("provenance"),
+        value_name("DIR")
+    )]
+    output_root: Option<Utf8PathBuf>,
+
+    /// With `--inputs`, prints the paths of the files that `--output-root`
+    /// would write (relative to the output root), one per line, without
+    /// writing anything.
+    #[clap(long, requires("inputs"), conflicts_with("provenance"))]
+    list_outputs: bool,
+
+    /// Allows changes to latest documents that are frozen (see
+    /// `DocumentFreeze`).
+    #[clap(long)]
+    override_freeze: bool,
+
+    /// If another process is updating the documents directory, waits for it
+    /// to finish rather than failing.
+    #[clap(long)]
+    wait: bool,
+
+    /// Proceeds even if the documents directory contains untracked files the
+    /// API manager doesn't recognize (see
+    /// `Environment::with_strict_documents_dir`).
+    #[clap(long)]
+    allow_dirty_docs: bool,
+}
+
+impl GenerateArgs {
+    fn exec(
+        self,
+        env: &Environment,
+        apis: &ManagedApis,
+        output: &OutputOpts,
+    ) -> anyhow::Result<ExitCode> {
+        let mut env = env.resolve(self.local.dir)?;
+        env.override_freeze = self.override_freeze;
+        env.wait_for_lock = self.wait;
+        if self.allow_dirty_docs {
+            env.settings.strict_documents_dir = false;
+        }
+        let blessed_source = self.blessed.to_blessed_source(&env)?;
+        let generated_source = GeneratedSource::from(self.generated);
+        let Some(inputs) = self.inputs.to_hermetic_inputs(&env)? else {
+            return Ok(generate_impl(
+                apis,
+                &env,
+                &blessed_source,
+                &generated_source,
+                self.provenance.as_deref(),
+                output,
+            )?
+            .to_exit_code());
+        };
+
+        inputs.check_apis(apis)?;
+        // Only the declared inputs are read, so other files in the documents
+        // directory don't matter.
+        env.settings.strict_documents_dir = false;
+        if self.list_outputs {
+            let (result, outputs) = list_generate_outputs(
+                apis,
+                &env,
+                &inputs,
+                &blessed_source,
+                &generated_source,
+                output,
+            )?;
+            let mut stdout = std::io::stdout().lock();
+            for path in outputs {
+                writeln!(stdout, "{}", path)?;
+            }
+            return Ok(result.to_exit_code());
+        }
+
+        let Some(output_root) = &self.output_root else {
+            bail!(
+                "with --inputs, either --output-root or --list-outputs is \
+                 required"
+            );
+        };
+        let env = inputs.stage_output_root(&env, output_root)?;
+        Ok(generate_impl(
+            apis,
+            &env,
+            &blessed_source,
+            &generated_source,
+            None,
+            output,
+        )?
+        .to_exit_code())
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CheckArgs {
+    #[clap(flatten)]
+    local: LocalSourceArgs,
+    #[clap(flatten)]
+    blessed: BlessedSourceArgs,
+    #[clap(flatten)]
+    generated: GeneratedSourceArgs,
+
+    /// Checks the merge of HEAD with the given VCS REVISION, the way a merge
+    /// queue would, rather than the working copy.
+    ///
+    /// The merge is created in a temporary Git worktree, so the working copy
+    /// is not modified. Only committed changes are included. Blessed
+    /// documents are loaded from REVISION unless `--blessed-from-vcs` is
+    /// also specified.
+    ///
+    /// Generated documents still come from the API definitions in this
+    /// binary, so changes to API definitions made on REVISION are not
+    /// reflected.
+    #[clap(long, conflicts_with("inputs"), value_name("REVISION"))]
+    base: Option<String>,
+
+    /// Write an HTML report of the check to PATH (relative to the repository
+    /// root).
+    ///
+    /// The report is a self-contained page with a collapsible section per
+    /// API, diffs for stale documents, and tables of compatibility issues.
+    /// It's meant to be attached to CI runs.
+    #[clap(long, value_name("PATH"))]
+    report: Option<Utf8PathBuf>,
+
+    #[clap(flatten)]
+    metrics: MetricsArgs,
+
+    /// The format to print the result of the check to standard output in.
+    ///
+    /// With `json`, a JSON document describing the result and every problem
+    /// found is printed. It has a `schema_version` field, and its schema is
+    /// published in the dropshot-api-manager crate as
+    /// `schemas/check-output.json`. Human-readable output is printed to
+    /// standard error either way.
+    #[clap(
+        long,
+        value_enum,
+        default_value_t,
+        conflicts_with("since_last_release")
+    )]
+    format: CheckFormat,
+
+    #[clap(flatten)]
+    inputs: InputsArgs,
+
+    /// Allows changes to latest documents that are frozen (see
+    /// `DocumentFreeze`).
+    #[clap(long)]
+    override_freeze: bool,
+
+    /// After a successful check, records the SHA-256 digest of every local
+    /// document in a Git note on HEAD, under `refs/notes/openapi-check`.
+    ///
+    /// `verify-notes` can later confirm that a tree matches the recorded
+    /// documents. The documents must not have uncommitted changes. Git
+    /// doesn't push notes by default, so CI must push the notes ref itself.
+    #[clap(long, conflicts_with_all(["base", "inputs"]))]
+    write_note: bool,
+
+    /// After the check, lists the versions of versioned APIs introduced since
+    /// the release at TAG (any VCS revision), with their labels.
+    ///
+    /// The versions are printed to stdout, one per line, as input to release
+    /// notes. The documents at TAG are loaded the way blessed documents are,
+    /// from the merge base of HEAD with TAG. This doesn't affect the result of
+    /// the check.
+    #[clap(long, conflicts_with_all(["base", "inputs"]), value_name("TAG"))]
+    since_last_release: Option<String>,
+
+    /// Checks the OpenAPI documents in DIRECTORY rather than those in the
+    /// working copy.
+    ///
+    /// DIRECTORY takes the place of the documents directory, and may be
+    /// outside the repository, e.g. a copy of the documents exported into a
+    /// container image. Blessed documents are still loaded from VCS, and
+    /// generated documents still come from the API definitions in this
+    /// binary.
+    #[clap(
+        long,
+        conflicts_with_all(["base", "inputs", "write_note"]),
+        value_name("DIRECTORY")
+    )]
+    documents_dir: Option<Utf8PathBuf>,
+
+    /// Only checks the APIs affected by the files listed in PATH, one per
+    /// line, or on standard input if PATH is `-`.
+    ///
+    /// Paths are relative to the repository root, as printed by `git diff
+    /// --name-only`. Files in the documents directory affect the API they
+    /// belong to, and other files the APIs whose source globs (see
+    /// `ManagedApi::with_source_globs`) they match. If that doesn't account
+    /// for every file, every API is checked.
+    #[clap(long, conflicts_with("write_note"), value_name("PATH"))]
+    changed_files: Option<Utf8PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct MetricsArgs {
+    /// Write metrics about the check to PATH (relative to the repository
+    /// root).
+    ///
+    /// The metrics are the result of the check, how long each phase took,
+    /// how many documents were processed, and how many problems were found
+    /// by kind. They're meant to be collected across CI runs.
+    #[clap(long, value_name("PATH"))]
+    metrics_out: Option<Utf8PathBuf>,
+
+    /// The format to write metrics in.
+    #[clap(long, value_enum, default_value_t, requires("metrics_out"))]
+    metrics_format: MetricsFormat,
+}
+
+impl MetricsArgs {
+    fn metrics_out(&self) -> Option<(&Utf8Path, MetricsFormat)> {
+        self.metrics_out.as_deref().map(|path| (path, self.metrics_format))
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct InputsArgs {
+    /// Reads local OpenAPI documents only from the paths listed in FILE, one
+    /// per line, rather than from the whole documents directory.
+    ///
+    /// This is meant for running the API manager as a hermetic build action
+    /// (e.g. under Bazel or Buck), where only declared inputs are available.
+    /// Each path must be within the documents directory, and is either
+    /// absolute or relative to the current directory.
+    #[clap(long, value_name("FILE"))]
+    inputs: Option<Utf8PathBuf>,
+
+    /// With `--inputs`, allows symlinks to be written, e.g. for "latest"
+    /// symlinks.
+    ///
+    /// Without this, APIs that keep a "latest" symlink are rejected, and
+    /// symlinks among the inputs are copied as regular files.
+    #[clap(long, requires("inputs"))]
+    allow_symlinks: bool,
+}
+
+impl InputsArgs {
+    fn to_hermetic_inputs(
+        &self,
+        env: &ResolvedEnv,
+    ) -> Result<Option<HermeticInputs>> {
+        self.inputs
+            .as_deref()
+            .map(|list| HermeticInputs::read(list, env, self.allow_symlinks))
+            .transpose()
+    }
+}
+
+impl CheckArgs {
+    fn exec(
+        self,
+        env: &Environment,
+        apis: &ManagedApis,
+        output: &OutputOpts,
+    ) -> anyhow::Result<ExitCode> {
+        let mut env = match &self.documents_dir {
+            Some(documents_dir) => {
+                env.resolve_with_documents_dir(self.local.dir, documents_dir)?
+            }
+            None => env.resolve(self.local.dir)?,
+        };
+        env.override_freeze = self.override_freeze;
+        let generated_source = GeneratedSource::from(self.generated);
+        // With --inputs, check the declared inputs, staged into a temporary
+        // directory that must outlive the check.
+        let (env, _temp_dir) = match self.inputs.to_hermetic_inputs(&env)? {
+            Some(inputs) => {
+                inputs.check_apis(apis)?;
+                let (temp_dir, env) = inputs.stage_temp(&env)?;
+                (env, Some(temp_dir))
+            }
+            None => (env, None),
+        };
+        let scoped_apis = match &self.changed_files {
+            Some(list) => {
+                let styles = output.styles(supports_color::Stream::Stderr);
+                scope_to_changed_files(&env, apis, list, &styles)?
+            }
+            None => None,
+        };
+        let apis = scoped_apis.as_ref().unwrap_or(apis);
+        // Resolve the report and metrics paths against the working copy,
+        // since with --base the check runs in a temporary worktree.
+        let report = self.report.map(|path| env.repo_root.join(path));
+        let metrics_out = self
+            .metrics
+            .metrics_out()
+            .map(|(path, format)| (env.repo_root.join(path), format));
+        let outputs = CheckOutputs {
+            report: report.as_deref(),
+            metrics: metrics_out
+                .as_ref()
+                .map(|(path, format)| (path.as_path(), *format)),
+            format: self.format,
+        };
+        let Some(base) = self.base else {
+            let blessed_source = self.blessed.to_blessed_source(&env)?;
+            let result = check_impl(
+                apis,
+                &env,
+                &blessed_source,
+                &generated_source,
+                &outputs,
+                output,
+            )?;
+            if self.write_note && result == CheckResult::Success {
+                write_check_note_impl(apis, &env, output)?;
+            }
+            if let Some(tag) = self.since_last_release {
+                let tag = VcsRevision::from(tag);
+                let styles = output.styles(supports_color::Stream::Stderr);
+                let new_versions =
+                    versions_since_release(apis, &env, &tag, &styles)?;
+                display_versions_since_release(
+                    &tag,
+                    &new_versions,
+                    &styles,
+                    &mut std::io::stdout(),
+                )?;
+            }
+            return Ok(result.to_exit_code());
+        };
+
+        let base = VcsRevision::from(base);
+        let worktree = env.vcs.create_merge_worktree(&env.repo_root, &base)?;
+        let styles = output.styles(supports_color::Stream::Stderr);
+        eprintln!(
+            "{:>HEADER_WIDTH$} the merge of HEAD with {} (in temporary \
+             worktree {})",
+            "Merging".style(styles.success_header),
+            base,
+            worktree.path(),
+        );
+        let merge_env = env.for_merge_worktree(worktree.path(), &base);
+        let blessed_source = self.blessed.to_blessed_source(&merge_env)?;
+        Ok(check_impl(
+            apis,
+            &merge_env,
+            &blessed_source,
+            &generated_source,
+            &outputs,
+            output,
+        )?
+        .to_exit_code())
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct StatusArgs {
+    #[clap(flatten)]
+    local: LocalSourceArgs,
+    #[clap(flatten)]
+    blessed: BlessedSourceArgs,
+    #[clap(flatten)]
+    generated: GeneratedSourceArgs,
+}
+
+impl StatusArgs {
+    fn exec(
+        self,
+        env: &Environment,
+        apis: &ManagedApis,
+        output: &OutputOpts,
+    ) -> anyhow::Result<ExitCode> {
+        let env = env.resolve(self.local.dir)?;
+        let blessed_source = self.blessed.to_blessed_source(&env)?;
+        let generated_source = GeneratedSource::from(self.generated);
+        status_impl(
+            apis,
+            &env,
+            &blessed_source,
+            &generated_source,
+            output,
+            &mut std::io::stdout(),
+        )?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct VerifyNotesArgs {
+    #[clap(flatten)]
+    local: LocalSourceArgs,
+
+    /// The VCS revision whose check note to verify against.
+    #[clap(long, value_name("REVISION"), default_value = "HEAD")]
+    commit: String,
+}
+
+impl VerifyNotesArgs {
+    fn exec(
+        self,
+        env: &Environment,
+        apis: &ManagedApis,
+        output: &OutputOpts,
+    ) -> anyhow::Result<ExitCode> {
+        let env = env.resolve(self.local.dir)?;
+        let revision = VcsRevision::from(self.commit);
+        Ok(verify_notes_impl(apis, &env, &revision, output)?.to_exit_code())
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct WatchArgs {
+    /// Also watches PATH (relative to the repository root) for changes.
+    ///
+    /// This may be passed more than once.
+    #[clap(long = "path", value_name("PATH"))]
+    paths: Vec<Utf8PathBuf>,
+
+    /// Arguments passed to each `check`, e.g. `-- --no-blessed`.
+    #[clap(last = true, value_name("CHECK_ARGS"))]
+    check_args: Vec<String>,
+}
+
+impl WatchArgs {
+    fn exec(
+        self,
+        env: &Environment,
+        output: &OutputOpts,
+    ) -> anyhow::Result<ExitCode> {
+        // Parse the arguments the way each check will, both to reject invalid
+        // ones upfront and to follow them below.
+        let app = App::try_parse_from(
+            ["watch", "check"]
+                .into_iter()
+                .chain(self.check_args.iter().map(String::as_str)),
+        )
+        .context("invalid arguments to check")?;
+        let Command::Check(check) = app.command else {
+            unreachable!("parsed a check command");
+        };
+
+        let mut paths = env.settings.watch_paths.clone();
+        paths.extend(self.paths);
+        // Each check runs in the repository root, so resolve a relative
+        // --dir against it rather than the current directory.
+        let env =
+            env.resolve(check.local.dir.map(|dir| env.repo_root.join(dir)))?;
+        // The check writes these files itself, so changes to them mustn't
+        // trigger another check.
+        let outputs = [
+            check.report.as_deref(),
+            check.metrics.metrics_out.as_deref(),
+            env.settings.generation_timings.as_ref().map(|t| t.path()),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|path| env.repo_root.join(path))
+        .collect();
+        let config = WatchConfig {
+            paths,
+            outputs,
+            // --changed-files can't be passed twice, and conflicts with
+            // --write-note.
+            pass_changed_files: check.changed_files.is_none()
+                && !check.write_note,
+        };
+        watch_impl(&env, &config, &self.check_args, output)?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct PinStubsArgs {
+    #[clap(flatten)]
+    local: LocalSourceArgs,
+
+    /// Check that referenced commits are reachable rather than pinning them.
+    #[clap(long)]
+    check: bool,
+}
+
+impl PinStubsArgs {
+    fn exec(
+        self,
+        env: &Environment,
+        apis: &ManagedApis,
+        output: &OutputOpts,
+    ) -> anyhow::Result<ExitCode> {
+        let env = env.resolve(self.local.dir)?;
+        Ok(pin_stubs_impl(apis, &env, self.check, output)?.to_exit_code())
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct MaterializeArgs {
+    #[clap(flatten)]
+    local: LocalSourceArgs,
+
+    /// The directory to write OpenAPI documents to.
+    #[clap(value_name = "TARGET_DIR")]
+    target_dir: Utf8PathBuf,
+}
+
+impl MaterializeArgs {
+    fn exec(
+        self,
+        env: &Environment,
+        apis: &ManagedApis,
+        output: &OutputOpts,
+    ) -> anyhow::Result<ExitCode> {
+        let env = env.resolve(self.local.dir)?;
+        materialize_impl(apis, &env, &self.target_dir, output)?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct SlimArgs {
+    #[clap(flatten)]
+    local: LocalSourceArgs,
+
+    /// The API to reduce.
+    #[clap(value_name = "API")]
+    api: String,
+
+    /// The version of the API to reduce (for lockstep APIs, the version in
+    /// the document).
+    #[clap(value_name = "VERSION")]
+    version: semver::Version,
+
+    /// Keep operations with any of these tags.
+    #[clap(long, required = true, value_delimiter = ',', value_name = "TAGS")]
+    keep_tags: Vec<String>,
+
+    /// Write the document to this file, rather than to standard output.
+    #[clap(long = "output", short, value_name = "FILE")]
+    output_file: Option<Utf8PathBuf>,
+}
+
+impl SlimArgs {
+    fn exec(
+        self,
+        env: &Environment,
+        apis: &ManagedApis,
+        output: &OutputOpts,
+    ) -> anyhow::Result<ExitCode> {
+        let env = env.resolve(self.local.dir)?;
+        let contents = slim_impl(
+            apis,
+            &env,
+            &ApiIdent::from(self.api),
+            &self.version,
+            &self.keep_tags,
+            output,
+        )?;
+        match &self.output_file {
+            Some(path) => {
+                let _ = overwrite_file(path, &contents)?;
+            }
+            None => std::io::stdout().write_all(&contents)?,
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CompatPreviewArgs {
+    #[clap(flatten)]
+    local: LocalSourceArgs,
+    #[clap(flatten)]
+    blessed: BlessedSourceArgs,
+
+    /// The API to check the candidate against.
+    #[clap(value_name = "API")]
+    api: String,
+
+    /// The candidate OpenAPI document.
+    #[clap(long, value_name = "FILE")]
+    against: Utf8PathBuf,
+}
+
+impl CompatPreviewArgs {
+    fn exec(
+        self,
+        env: &Environment,
+        apis: &ManagedApis,
+        output: &OutputOpts,
+    ) -> anyhow::Result<ExitCode> {
+        let env = env.resolve(self.local.dir)?;
+        let blessed_source = self.blessed.to_blessed_source(&env)?;
+        let ident = ApiIdent::from(self.api);
+        let results = compat_preview_impl(
+            apis,
+            &env,
+            &blessed_source,
+            &ident,
+            &self.against,
+            output,
+        )?;
+        let styles = output.styles(supports_color::Stream::Stderr);
+        Ok(display_compat_preview(&ident, &results, &styles).to_exit_code())
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct PatchArgs {
+    #[clap(flatten)]
+    local: LocalSourceArgs,
+    #[clap(flatten)]
+    blessed: BlessedSourceArgs,
+    #[clap(flatten)]
+    generated: GeneratedSourceArgs,
+
+    /// The API to describe changes for.
+    #[clap(value_name = "API")]
+    api: String,
+
+    /// The format of the patch.
+    #[clap(long, value_enum, default_value_t)]
+    format: PatchFormat,
+
+    /// Write the patch to this file, rather than to standard output.
+    #[clap(long = "output", short, value_name = "FILE")]
+    output_file: Option<Utf8PathBuf>,
+}
+
+impl PatchArgs {
+    fn exec(
+        self,
+        env: &Environment,
+        apis: &ManagedApis,
+        output: &OutputOpts,
+    ) -> anyhow::Result<ExitCode> {
+        let env = env.resolve(self.local.dir)?;
+        let blessed_source = self.blessed.to_blessed_source(&env)?;
+        let generated_source = GeneratedSource::from(self.generated);
+        let contents = patch_impl(
+            apis,
+            &env,
+            &blessed_source,
+            &generated_source,
+            &ApiIdent::from(self.api),
+            self.format,
+            output,
+        )?;
+        match &self.output_file {
+            Some(path) => {
+                let _ = overwrite_file(path, &contents)?;
+            }
+            None => std::io::stdout().write_all(&contents)?,
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct DriftReportArgs {
+    #[clap(flatten)]
+    local: LocalSourceArgs,
+    #[clap(flatten)]
+    blessed: BlessedSourceArgs,
+    #[clap(flatten)]
+    generated: GeneratedSourceArgs,
+}
+
+impl DriftReportArgs {
+    fn exec(
+        self,
+        env: &Environment,
+        apis: &ManagedApis,
+        output: &OutputOpts,
+    ) -> anyhow::Result<ExitCode> {
+        let env = env.resolve(self.local.dir)?;
+        let blessed_source = self.blessed.to_blessed_source(&env)?;
+        let generated_source = GeneratedSource::from(self.generated);
+        drift_report_impl(
+            apis,
+            &env,
+            &blessed_source,
+            &generated_source,
+            output,
+            &mut std::io::stdout(),
+        )?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Exit code which indicates that local files are out-of-date.
+///
+/// This is chosen to be 4 so that the exit code is not 0 or 1 (general anyhow
+/// errors).
+pub const NEEDS_UPDATE_EXIT_CODE: u8 = 4;
+
+/// Exit code which indicates that one or more failures occurred.
+///
+/// This exit code is returned for issues like validation errors, or blessed
+/// files being updated in an incompatible way.
+pub const FAILURE_EXIT_CODE: u8 = 100;
+
+#[derive(Debug, Args)]
+pub struct WhyBlessedArgs {
+    #[clap(flatten)]
+    local: LocalSourceArgs,
+    #[clap(flatten)]
+    blessed: BlessedSourceArgs,
+
+    /// The API to explain.
+    #[clap(value_name = "API")]
+    api: String,
+
+    /// The version of the API to explain.
+    #[clap(value_name = "VERSION")]
+    version: semver::Version,
+}
+
+impl WhyBlessedArgs {
+    fn exec(
+        self,
+        env: &Environment,
+        apis: &ManagedApis,
+        output: &OutputOpts,
+    ) -> anyhow::Result<ExitCode> {
+        let env = env.resolve(self.local.dir)?;
+        let blessed_source = self.blessed.to_blessed_source(&env)?;
+        let reason = self.blessed.blessed_source_reason(&env);
+        why_blessed_impl(
+            apis,
+            &env,
+            &blessed_source,
+            &reason,
+            &ApiIdent::from(self.api),
+            &self.version,
+            output,
+            &mut std::io::stdout(),
+        )?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct PackBaselineArgs {
+    #[clap(flatten)]
+    local: LocalSourceArgs,
+    #[clap(flatten)]
+    blessed: BlessedSourceArgs,
+
+    /// Write the baseline to this file (conventionally
+    /// `blessed-baseline.tar.zst`).
+    #[clap(long = "output", short, value_name = "FILE")]
+    output_file: Utf8PathBuf,
+}
+
+impl PackBaselineArgs {
     fn exec(
         self,
+        env: &Environment,
         apis: &ManagedApis,
         output: &OutputOpts,
     ) -> anyhow::Result<ExitCode> {
-        list_impl(apis, self.verbose, output)?;
+        let env = env.resolve(self.local.dir)?;
+        let blessed_source = self.blessed.to_blessed_source(&env)?;
+        let (contents, count) =
+            pack_baseline_impl(apis, &env, &blessed_source, output)?;
+        let _ = overwrite_file(&self.output_file, &contents)?;
+
+        let styles = output.styles(supports_color::Stream::Stderr);
+        eprintln!(
+            "{:>HEADER_WIDTH$} baseline {} ({} documents)",
+            "Wrote".style(styles.success_header),
+            self.output_file.style(styles.filename),
+            count,
+        );
         Ok(ExitCode::SUCCESS)
     }
 }
 
 #[derive(Debug, Args)]
-pub struct GenerateArgs {
+pub struct AuditUnusedVersionsArgs {
+    /// A file of observed client versions.
+    ///
+    /// Files ending in `.json` must contain an array of objects with `api`,
+    /// `version` and `last_seen` fields. Other files are read as CSV, with a
+    /// header row naming those columns. `last_seen` is an RFC 3339 timestamp
+    /// or a date (YYYY-MM-DD).
+    #[clap(long, value_name = "FILE")]
+    observations: Utf8PathBuf,
+
+    /// Versions not observed in this many days are reported as unused.
+    #[clap(long, default_value_t = 90, value_name = "N")]
+    days: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct PinsArgs {
     #[clap(flatten)]
     local: LocalSourceArgs,
-    #[clap(flatten)]
-    blessed: BlessedSourceArgs,
-    #[clap(flatten)]
-    generated: GeneratedSourceArgs,
+
+    /// Only list the consumers of this API.
+    #[clap(long, value_name = "API")]
+    api: Option<String>,
 }
 
-impl GenerateArgs {
+impl PinsArgs {
     fn exec(
         self,
         env: &Environment,
         apis: &ManagedApis,
-        output: &OutputOpts,
     ) -> anyhow::Result<ExitCode> {
         let env = env.resolve(self.local.dir)?;
-        let blessed_source = self.blessed.to_blessed_source(&env)?;
-        let generated_source = GeneratedSource::from(self.generated);
-        Ok(generate_impl(
+        let api = self.api.map(ApiIdent::from);
+        pins_impl(apis, &env, api.as_ref(), &mut std::io::stdout())?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+impl AuditUnusedVersionsArgs {
+    fn exec(self, apis: &ManagedApis) -> anyhow::Result<ExitCode> {
+        let observations = load_observations(&self.observations)?;
+        audit_unused_versions_impl(
             apis,
-            &env,
-            &blessed_source,
-            &generated_source,
-            output,
-        )?
-        .to_exit_code())
+            &observations,
+            chrono::Utc::now(),
+            self.days,
+            &mut std::io::stdout(),
+        )?;
+        Ok(ExitCode::SUCCESS)
     }
 }
 
 #[derive(Debug, Args)]
-pub struct CheckArgs {
+pub struct NewVersionArgs {
     #[clap(flatten)]
     local: LocalSourceArgs,
     #[clap(flatten)]
     blessed: BlessedSourceArgs,
-    #[clap(flatten)]
-    generated: GeneratedSourceArgs,
+
+    /// The API to add a version to.
+    #[clap(value_name = "API")]
+    api: String,
+
+    /// The label of the new version, in SCREAMING_SNAKE_CASE (e.g.
+    /// `ADD_WIDGETS`).
+    #[clap(value_name = "LABEL")]
+    label: String,
+
+    /// The file containing the API's `api_versions!` call (typically
+    /// `{api}-api/src/lib.rs`).
+    #[clap(long, value_name = "FILE")]
+    api_file: Option<Utf8PathBuf>,
+
+    /// The source directory of the API's versions crate, in which to create a
+    /// module for the new version.
+    #[clap(long, value_name = "DIR")]
+    versions_dir: Option<Utf8PathBuf>,
+
+    /// Write changes to `--api-file` and `--versions-dir`, rather than only
+    /// describing them.
+    #[clap(long)]
+    apply: bool,
 }
 
-impl CheckArgs {
+impl NewVersionArgs {
     fn exec(
         self,
         env: &Environment,
         apis: &ManagedApis,
         output: &OutputOpts,
     ) -> anyhow::Result<ExitCode> {
+        if self.apply && self.api_file.is_none() && self.versions_dir.is_none()
+        {
+            bail!("--apply requires --api-file or --versions-dir");
+        }
         let env = env.resolve(self.local.dir)?;
         let blessed_source = self.blessed.to_blessed_source(&env)?;
-        let generated_source = GeneratedSource::from(self.generated);
-        Ok(check_impl(apis, &env, &blessed_source, &generated_source, output)?
-            .to_exit_code())
+        new_version_impl(
+            apis,
+            &env,
+            &blessed_source,
+            &ApiIdent::from(self.api),
+            &self.label,
+            NewVersionTargets {
+                api_file: self.api_file.as_deref(),
+                versions_dir: self.versions_dir.as_deref(),
+                apply: self.apply,
+            },
+            output,
+            &mut std::io::stdout(),
+        )?;
+        Ok(ExitCode::SUCCESS)
     }
 }
 
-/// Exit code which indicates that local files are out-of-date.
-///
-/// This is chosen to be 4 so that the exit code is not 0 or 1 (general anyhow
-/// errors).
-pub const NEEDS_UPDATE_EXIT_CODE: u8 = 4;
-
-/// Exit code which indicates that one or more failures occurred.
-///
-/// This exit code is returned for issues like validation errors, or blessed
-/// files being updated in an incompatible way.
-pub const FAILURE_EXIT_CODE: u8 = 100;
-
 #[cfg(test)]
 mod test {
     use super::*;
@@ -337,23 +1520,72 @@ mod test {
         assert_matches!(
             app.command,
             Command::Check(CheckArgs {
+                format: CheckFormat::Human,
                 local: LocalSourceArgs { dir: None },
                 blessed: BlessedSourceArgs {
                     blessed_from_vcs: None,
                     blessed_from_vcs_path: None,
-                    blessed_from_dir: None
+                    blessed_from_dir: None,
+                    no_blessed: false,
+                    also_blessed_from: None,
+                    baseline: None,
                 },
-                generated: GeneratedSourceArgs { generated_from_dir: None },
+                generated: GeneratedSourceArgs {
+                    generated_from_dir: None,
+                    verify_deterministic: false,
+                },
+                base: None,
+                report: None,
+                metrics: MetricsArgs {
+                    metrics_out: None,
+                    metrics_format: MetricsFormat::Prometheus,
+                },
+                inputs: InputsArgs { inputs: None, allow_symlinks: false },
+                override_freeze: false,
+                write_note: false,
+                since_last_release: None,
+                documents_dir: None,
+                changed_files: None,
             })
         );
 
+        // Print the result as JSON.
+        let app = App::parse_from(["dummy", "check", "--format", "json"]);
+        assert_matches!(
+            app.command,
+            Command::Check(CheckArgs { format: CheckFormat::Json, .. })
+        );
+
+        // Error case: JSON output and --since-last-release both print to
+        // standard output.
+        let error = App::try_parse_from([
+            "dummy",
+            "check",
+            "--format",
+            "json",
+            "--since-last-release",
+            "v1.0.0",
+        ])
+        .unwrap_err();
+        assert_eq!(error.kind(), clap::error::ErrorKind::ArgumentConflict);
+
         // Override local dir
         let app = App::parse_from(["dummy", "check", "--dir", "foo"]);
         assert_matches!(app.command, Command::Check(CheckArgs {
+            format: CheckFormat::Human,
             local: LocalSourceArgs { dir: Some(local_dir) },
             blessed:
-                BlessedSourceArgs { blessed_from_vcs: None, blessed_from_vcs_path: None, blessed_from_dir: None },
-            generated: GeneratedSourceArgs { generated_from_dir: None },
+                BlessedSourceArgs { blessed_from_vcs: None, blessed_from_vcs_path: None, blessed_from_dir: None, no_blessed: false, also_blessed_from: None, baseline: None },
+            generated: GeneratedSourceArgs { generated_from_dir: None, verify_deterministic: false },
+            base: None,
+            report: None,
+            metrics: MetricsArgs { metrics_out: None, .. },
+            inputs: InputsArgs { inputs: None, allow_symlinks: false },
+            override_freeze: false,
+            write_note: false,
+            since_last_release: None,
+            documents_dir: None,
+            changed_files: None,
         }) if local_dir == "foo");
 
         // Override generated dir differently
@@ -366,10 +1598,20 @@ mod test {
             "bar",
         ]);
         assert_matches!(app.command, Command::Check(CheckArgs {
+            format: CheckFormat::Human,
             local: LocalSourceArgs { dir: Some(local_dir) },
             blessed:
-                BlessedSourceArgs { blessed_from_vcs: None, blessed_from_vcs_path: None, blessed_from_dir: None },
-            generated: GeneratedSourceArgs { generated_from_dir: Some(generated_dir) },
+                BlessedSourceArgs { blessed_from_vcs: None, blessed_from_vcs_path: None, blessed_from_dir: None, no_blessed: false, also_blessed_from: None, baseline: None },
+            generated: GeneratedSourceArgs { generated_from_dir: Some(generated_dir), verify_deterministic: false },
+            base: None,
+            report: None,
+            metrics: MetricsArgs { metrics_out: None, .. },
+            inputs: InputsArgs { inputs: None, allow_symlinks: false },
+            override_freeze: false,
+            write_note: false,
+            since_last_release: None,
+            documents_dir: None,
+            changed_files: None,
         }) if local_dir == "foo" && generated_dir == "bar");
 
         // Override blessed with a local directory.
@@ -384,10 +1626,20 @@ mod test {
             "baz",
         ]);
         assert_matches!(app.command, Command::Check(CheckArgs {
+            format: CheckFormat::Human,
             local: LocalSourceArgs { dir: Some(local_dir) },
             blessed:
-                BlessedSourceArgs { blessed_from_vcs: None, blessed_from_vcs_path: None, blessed_from_dir: Some(blessed_dir) },
-            generated: GeneratedSourceArgs { generated_from_dir: Some(generated_dir) },
+                BlessedSourceArgs { blessed_from_vcs: None, blessed_from_vcs_path: None, blessed_from_dir: Some(blessed_dir), no_blessed: false, also_blessed_from: None, baseline: None },
+            generated: GeneratedSourceArgs { generated_from_dir: Some(generated_dir), verify_deterministic: false },
+            base: None,
+            report: None,
+            metrics: MetricsArgs { metrics_out: None, .. },
+            inputs: InputsArgs { inputs: None, allow_symlinks: false },
+            override_freeze: false,
+            write_note: false,
+            since_last_release: None,
+            documents_dir: None,
+            changed_files: None,
         }) if local_dir == "foo" && generated_dir == "bar" && blessed_dir == "baz");
 
         // Override blessed from Git.
@@ -398,12 +1650,269 @@ mod test {
             "some/other/upstream",
         ]);
         assert_matches!(app.command, Command::Check(CheckArgs {
+            format: CheckFormat::Human,
             local: LocalSourceArgs { dir: None },
             blessed:
-                BlessedSourceArgs { blessed_from_vcs: Some(git), blessed_from_vcs_path: None, blessed_from_dir: None },
-            generated: GeneratedSourceArgs { generated_from_dir: None },
+                BlessedSourceArgs { blessed_from_vcs: Some(git), blessed_from_vcs_path: None, blessed_from_dir: None, no_blessed: false, also_blessed_from: None, baseline: None },
+            generated: GeneratedSourceArgs { generated_from_dir: None, verify_deterministic: false },
+            base: None,
+            report: None,
+            metrics: MetricsArgs { metrics_out: None, .. },
+            inputs: InputsArgs { inputs: None, allow_symlinks: false },
+            override_freeze: false,
+            write_note: false,
+            since_last_release: None,
+            documents_dir: None,
+            changed_files: None,
         }) if git == "some/other/upstream");
 
+        // Check the merge with a base revision.
+        let app = App::parse_from(["dummy", "check", "--base", "origin/main"]);
+        assert_matches!(app.command, Command::Check(CheckArgs {
+            format: CheckFormat::Human,
+            local: LocalSourceArgs { dir: None },
+            blessed:
+                BlessedSourceArgs { blessed_from_vcs: None, blessed_from_vcs_path: None, blessed_from_dir: None, no_blessed: false, also_blessed_from: None, baseline: None },
+            generated: GeneratedSourceArgs { generated_from_dir: None, verify_deterministic: false },
+            base: Some(base),
+            report: None,
+            metrics: MetricsArgs { metrics_out: None, .. },
+            inputs: InputsArgs { inputs: None, allow_symlinks: false },
+            override_freeze: false,
+            write_note: false,
+            since_last_release: None,
+            documents_dir: None,
+            changed_files: None,
+        }) if base == "origin/main");
+
+        // Also treat a stack base as blessed.
+        let app = App::parse_from([
+            "dummy",
+            "check",
+            "--also-blessed-from",
+            "stack-base",
+        ]);
+        assert_matches!(app.command, Command::Check(CheckArgs {
+            format: CheckFormat::Human,
+            local: LocalSourceArgs { dir: None },
+            blessed:
+                BlessedSourceArgs { blessed_from_vcs: None, blessed_from_vcs_path: None, blessed_from_dir: None, no_blessed: false, also_blessed_from: Some(stack_base), baseline: None },
+            generated: GeneratedSourceArgs { generated_from_dir: None, verify_deterministic: false },
+            base: None,
+            report: None,
+            metrics: MetricsArgs { metrics_out: None, .. },
+            inputs: InputsArgs { inputs: None, allow_symlinks: false },
+            override_freeze: false,
+            write_note: false,
+            since_last_release: None,
+            documents_dir: None,
+            changed_files: None,
+        }) if stack_base == "stack-base");
+
+        // Skip blessed documents.
+        let app = App::parse_from(["dummy", "check", "--no-blessed"]);
+        assert_matches!(
+            app.command,
+            Command::Check(CheckArgs {
+                format: CheckFormat::Human,
+                local: LocalSourceArgs { dir: None },
+                blessed: BlessedSourceArgs {
+                    blessed_from_vcs: None,
+                    blessed_from_vcs_path: None,
+                    blessed_from_dir: None,
+                    no_blessed: true,
+                    also_blessed_from: None,
+                    baseline: None,
+                },
+                generated: GeneratedSourceArgs {
+                    generated_from_dir: None,
+                    verify_deterministic: false,
+                },
+                base: None,
+                report: None,
+                metrics: MetricsArgs { metrics_out: None, .. },
+                inputs: InputsArgs { inputs: None, allow_symlinks: false },
+                override_freeze: false,
+                write_note: false,
+                since_last_release: None,
+                documents_dir: None,
+                changed_files: None,
+            })
+        );
+
+        // Write metrics.
+        let app = App::parse_from([
+            "dummy",
+            "check",
+            "--metrics-out",
+            "metrics.json",
+            "--metrics-format",
+            "json",
+        ]);
+        assert_matches!(
+            app.command,
+            Command::Check(CheckArgs {
+                metrics: MetricsArgs {
+                    metrics_out: Some(path),
+                    metrics_format: MetricsFormat::Json,
+                },
+                ..
+            }) if path == "metrics.json"
+        );
+
+        // Write an HTML report.
+        let app =
+            App::parse_from(["dummy", "check", "--report", "report.html"]);
+        assert_matches!(
+            app.command,
+            Command::Check(CheckArgs { report: Some(path), .. })
+                if path == "report.html"
+        );
+
+        // Load blessed documents from a baseline.
+        let app = App::parse_from([
+            "dummy",
+            "check",
+            "--baseline",
+            "blessed-baseline.tar.zst",
+        ]);
+        assert_matches!(
+            app.command,
+            Command::Check(CheckArgs {
+                blessed: BlessedSourceArgs {
+                    blessed_from_vcs: None,
+                    blessed_from_dir: None,
+                    also_blessed_from: None,
+                    baseline: Some(path),
+                    ..
+                },
+                ..
+            }) if path == "blessed-baseline.tar.zst"
+        );
+
+        // Error case: specifying both --baseline and --blessed-from-vcs
+        let error = App::try_parse_from([
+            "dummy",
+            "check",
+            "--baseline",
+            "blessed-baseline.tar.zst",
+            "--blessed-from-vcs",
+            "vcs_revision",
+        ])
+        .unwrap_err();
+        assert_eq!(error.kind(), clap::error::ErrorKind::ArgumentConflict);
+
+        // Read declared inputs, and write outputs under an output root.
+        let app = App::parse_from([
+            "dummy",
+            "generate",
+            "--inputs",
+            "inputs.txt",
+            "--output-root",
+            "out",
+        ]);
+        assert_matches!(
+            app.command,
+            Command::Generate(GenerateArgs {
+                inputs: InputsArgs {
+                    inputs: Some(inputs),
+                    allow_symlinks: false,
+                },
+                output_root: Some(output_root),
+                list_outputs: false,
+                ..
+            }) if inputs == "inputs.txt" && output_root == "out"
+        );
+
+        // Wait for other processes updating the documents directory.
+        let app = App::parse_from(["dummy", "generate", "--wait"]);
+        assert_matches!(
+            app.command,
+            Command::Generate(GenerateArgs { wait: true, .. })
+        );
+
+        // Error case: specifying --output-root without --inputs
+        let error =
+            App::try_parse_from(["dummy", "generate", "--output-root", "out"])
+                .unwrap_err();
+        assert_eq!(
+            error.kind(),
+            clap::error::ErrorKind::MissingRequiredArgument
+        );
+
+        // Error case: specifying both --inputs and --base
+        let error = App::try_parse_from([
+            "dummy",
+            "check",
+            "--inputs",
+            "inputs.txt",
+            "--base",
+            "origin/main",
+        ])
+        .unwrap_err();
+        assert_eq!(error.kind(), clap::error::ErrorKind::ArgumentConflict);
+
+        // List versions introduced since a release.
+        let app = App::parse_from([
+            "dummy",
+            "check",
+            "--since-last-release",
+            "v1.0.0",
+        ]);
+        assert_matches!(
+            app.command,
+            Command::Check(CheckArgs {
+                since_last_release: Some(tag),
+                ..
+            }) if tag == "v1.0.0"
+        );
+
+        // Check documents outside the working copy.
+        let app = App::parse_from([
+            "dummy",
+            "check",
+            "--documents-dir",
+            "/export/openapi",
+        ]);
+        assert_matches!(
+            app.command,
+            Command::Check(CheckArgs {
+                documents_dir: Some(dir),
+                ..
+            }) if dir == "/export/openapi"
+        );
+
+        // Error case: specifying both --documents-dir and --write-note
+        let error = App::try_parse_from([
+            "dummy",
+            "check",
+            "--documents-dir",
+            "/export/openapi",
+            "--write-note",
+        ])
+        .unwrap_err();
+        assert_eq!(error.kind(), clap::error::ErrorKind::ArgumentConflict);
+
+        // Error case: specifying both --write-note and --base
+        let error = App::try_parse_from([
+            "dummy",
+            "check",
+            "--write-note",
+            "--base",
+            "origin/main",
+        ])
+        .unwrap_err();
+        assert_eq!(error.kind(), clap::error::ErrorKind::ArgumentConflict);
+
+        // Error case: specifying --metrics-format without --metrics-out
+        let error =
+            App::try_parse_from(["dummy", "check", "--metrics-format", "json"])
+                .unwrap_err();
+        assert_eq!(
+            error.kind(),
+            clap::error::ErrorKind::MissingRequiredArgument
+        );
+
         // Error case: specifying both --blessed-from-vcs and --blessed-from-dir
         let error = App::try_parse_from([
             "dummy",
@@ -475,6 +1984,42 @@ mod test {
                 .expect("resolving environment");
             assert_eq!(env.openapi_abs_dir(), current_dir.join("bar"));
         }
+
+        {
+            let env = Environment::new_for_test(
+                "cargo openapi".to_owned(),
+                Utf8PathBuf::from(ABS_DIR),
+                Utf8PathBuf::from("foo"),
+            )
+            .expect("loading environment")
+            .with_namespace("service-a")
+            .expect("valid namespace");
+            let env = env.resolve(None).expect("resolving environment");
+            assert_eq!(
+                env.openapi_abs_dir(),
+                Utf8Path::new(ABS_DIR).join("foo").join("service-a")
+            );
+            assert_eq!(
+                env.openapi_rel_dir(),
+                Utf8Path::new("foo").join("service-a")
+            );
+        }
+
+        for namespace in ["", ".", "..", "a/b"] {
+            let error = Environment::new_for_test(
+                "cargo openapi".to_owned(),
+                Utf8PathBuf::from(ABS_DIR),
+                Utf8PathBuf::from("foo"),
+            )
+            .expect("loading environment")
+            .with_namespace(namespace)
+            .unwrap_err();
+            assert!(
+                error.to_string().starts_with("namespace must be"),
+                "unexpected error: {}",
+                error,
+            );
+        }
     }
 
     // Test how we convert `GeneratedSourceArgs` into `GeneratedSource`.
@@ -482,11 +2027,25 @@ mod test {
     fn test_generated_args() {
         let source = GeneratedSource::from(GeneratedSourceArgs {
             generated_from_dir: None,
+            verify_deterministic: false,
+        });
+        assert_matches!(
+            source,
+            GeneratedSource::Generated { verify_deterministic: false }
+        );
+
+        let source = GeneratedSource::from(GeneratedSourceArgs {
+            generated_from_dir: None,
+            verify_deterministic: true,
         });
-        assert_matches!(source, GeneratedSource::Generated);
+        assert_matches!(
+            source,
+            GeneratedSource::Generated { verify_deterministic: true }
+        );
 
         let source = GeneratedSource::from(GeneratedSourceArgs {
             generated_from_dir: Some(Utf8PathBuf::from("/tmp")),
+            verify_deterministic: false,
         });
         assert_matches!(
             source,
@@ -523,6 +2082,9 @@ mod test {
             blessed_from_vcs: None,
             blessed_from_vcs_path: None,
             blessed_from_dir: None,
+            no_blessed: false,
+            also_blessed_from: None,
+            baseline: None,
         }
         .to_blessed_source(&env)
         .unwrap();
@@ -537,6 +2099,9 @@ mod test {
             blessed_from_vcs: Some(String::from("my/other/main")),
             blessed_from_vcs_path: None,
             blessed_from_dir: None,
+            no_blessed: false,
+            also_blessed_from: None,
+            baseline: None,
         }
         .to_blessed_source(&env)
         .unwrap();
@@ -551,6 +2116,9 @@ mod test {
             blessed_from_vcs: Some(String::from("my/other/main")),
             blessed_from_vcs_path: Some(Utf8PathBuf::from("other_openapi/bar")),
             blessed_from_dir: None,
+            no_blessed: false,
+            also_blessed_from: None,
+            baseline: None,
         }
         .to_blessed_source(&env)
         .unwrap();
@@ -566,6 +2134,9 @@ mod test {
             blessed_from_vcs: None,
             blessed_from_vcs_path: None,
             blessed_from_dir: Some(Utf8PathBuf::from("/tmp")),
+            no_blessed: false,
+            also_blessed_from: None,
+            baseline: None,
         }
         .to_blessed_source(&env)
         .unwrap();
@@ -574,6 +2145,23 @@ mod test {
             BlessedSource::Directory { local_directory }
                 if local_directory == "/tmp"
         );
+
+        // Load from a baseline.
+        let source = BlessedSourceArgs {
+            blessed_from_vcs: None,
+            blessed_from_vcs_path: None,
+            blessed_from_dir: None,
+            no_blessed: false,
+            also_blessed_from: None,
+            baseline: Some(Utf8PathBuf::from("blessed-baseline.tar.zst")),
+        }
+        .to_blessed_source(&env)
+        .unwrap();
+        assert_matches!(
+            source,
+            BlessedSource::Baseline { path }
+                if path == "blessed-baseline.tar.zst"
+        );
     }
 
     /// Helper: parse CLI args through clap and resolve the blessed
@@ -690,4 +2278,61 @@ mod test {
             },
         );
     }
+
+    #[test]
+    fn test_max_problems_args() {
+        let app = App::parse_from(["dummy", "check"]);
+        assert_eq!(
+            app.output_opts.max_problems,
+            crate::output::DEFAULT_MAX_PROBLEMS
+        );
+
+        // --max-problems is a global option, so it can be passed before or
+        // after the subcommand.
+        let app = App::parse_from(["dummy", "--max-problems", "0", "check"]);
+        assert_eq!(app.output_opts.max_problems, 0);
+
+        let app = App::parse_from(["dummy", "check", "--max-problems", "5"]);
+        assert_eq!(app.output_opts.max_problems, 5);
+    }
+
+    #[test]
+    fn test_trace_git_args() {
+        let app = App::parse_from(["dummy", "check"]);
+        assert_eq!(app.output_opts.trace_git, None);
+
+        // Without a value, the trace goes to standard error. A value must be
+        // passed with `=`, so that the subcommand isn't taken as one.
+        let app = App::parse_from(["dummy", "--trace-git", "check"]);
+        assert_eq!(
+            app.output_opts.trace_git.as_deref(),
+            Some(Utf8Path::new("-"))
+        );
+        assert_matches!(app.command, Command::Check(_));
+
+        let app = App::parse_from(["dummy", "check", "--trace-git=git.log"]);
+        assert_eq!(
+            app.output_opts.trace_git.as_deref(),
+            Some(Utf8Path::new("git.log"))
+        );
+    }
+
+    #[test]
+    fn test_watch_args() {
+        let app = App::parse_from([
+            "dummy",
+            "watch",
+            "--path",
+            "apis/foo",
+            "--path",
+            "apis/bar",
+            "--",
+            "--no-blessed",
+        ]);
+        let Command::Watch(args) = app.command else {
+            panic!("expected watch command");
+        };
+        assert_eq!(args.paths, ["apis/foo", "apis/bar"]);
+        assert_eq!(args.check_args, ["--no-blessed"]);
+    }
 }
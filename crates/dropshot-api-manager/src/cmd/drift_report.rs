@@ -0,0 +1,105 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Summarizes how far the latest version of each API has drifted from its
+//! blessed document.
+//!
+//! APIs configured with [`ManagedApi::allow_trivial_changes_for_latest`] can
+//! accumulate wire-compatible changes (e.g. documentation updates) on their
+//! latest blessed version without a version bump. The blessed document keeps
+//! its original contents, so the drift is invisible until it's flushed by
+//! adding a new version. This report shows how much has accumulated, so that
+//! teams can decide when to do that.
+//!
+//! [`ManagedApi::allow_trivial_changes_for_latest`]: crate::ManagedApi::allow_trivial_changes_for_latest
+
+use crate::{
+    apis::ManagedApis,
+    cmd::patch::json_patch,
+    environment::{BlessedSource, GeneratedSource, ResolvedEnv},
+    output::{OutputOpts, display_load_problems, plural},
+};
+use std::io::Write;
+
+/// Writes a line to `out` for each versioned API that allows trivial changes
+/// to its latest version, describing how many JSON paths in the document
+/// generated for its latest blessed version differ from the blessed document.
+pub(crate) fn drift_report_impl(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    blessed_source: &BlessedSource,
+    generated_source: &GeneratedSource,
+    output: &OutputOpts,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let styles = output.styles(supports_color::Stream::Stderr);
+    let drifting: Vec<_> = apis
+        .iter_apis()
+        .filter(|api| {
+            api.is_versioned() && api.allows_trivial_changes_for_latest()
+        })
+        .collect();
+    if drifting.is_empty() {
+        writeln!(
+            out,
+            "no versioned APIs allow trivial changes to their latest version"
+        )?;
+        return Ok(());
+    }
+
+    let (generated, errors) = generated_source.load(apis, &styles, env)?;
+    display_load_problems(&errors, &styles)?;
+
+    let (blessed, errors) = blessed_source.load(
+        &env.repo_root,
+        apis,
+        &styles,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    display_load_problems(&errors, &styles)?;
+
+    for api in drifting {
+        let ident = api.ident();
+        let Some((version, blessed_file)) = blessed
+            .get(ident)
+            .and_then(|api_files| api_files.versions().last_key_value())
+        else {
+            writeln!(out, "{}: no blessed versions", ident)?;
+            continue;
+        };
+        // If a newer version has been added, it flushes any drift once it's
+        // blessed.
+        let latest = api.iter_versions_semver().max();
+        if let Some(latest) = latest.filter(|latest| *latest > version) {
+            writeln!(out, "{}: v{} is not blessed yet", ident, latest)?;
+            continue;
+        }
+        let Some(generated_file) = generated
+            .get(ident)
+            .and_then(|api_files| api_files.versions().get(version))
+        else {
+            writeln!(
+                out,
+                "{}: the latest blessed version (v{}) is no longer supported",
+                ident, version,
+            )?;
+            continue;
+        };
+
+        let mut ops = Vec::new();
+        json_patch("", blessed_file.value(), generated_file.value(), &mut ops);
+        if ops.is_empty() {
+            writeln!(out, "{} v{}: no drift", ident, version)?;
+        } else {
+            writeln!(
+                out,
+                "{} v{}: {} changed {}",
+                ident,
+                version,
+                ops.len(),
+                plural::paths(ops.len()),
+            )?;
+        }
+    }
+    Ok(())
+}
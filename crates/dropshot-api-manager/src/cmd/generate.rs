@@ -3,18 +3,30 @@
 use crate::{
     FAILURE_EXIT_CODE,
     apis::ManagedApis,
-    environment::{BlessedSource, GeneratedSource, ResolvedEnv},
+    cmd::dispatch::BlessedSourceArgs,
+    environment::{BlessedSource, Environment, GeneratedSource, ResolvedEnv},
+    hermetic::{HermeticInputs, list_outputs},
+    hooks::{DirSnapshot, GenerateHook, GenerateHookContext, GenerateHooks},
+    lock::DocumentsLock,
     output::{
         CheckResult, OutputOpts, Styles, display_api_spec_version,
         display_load_problems, display_resolution, display_resolution_problems,
         headers::{self, *},
         plural,
     },
-    resolved::{Problem, Resolved},
+    provenance::provenance_statement,
+    resolved::{Fix, Problem, Resolved},
+    sink::{OutputEvent, OutputSinks},
+    spec_files_blessed::BlessedFiles,
+    spec_files_generated::GeneratedFiles,
+    spec_files_local::LocalFiles,
+    unrecognized::check_unrecognized_files,
+    validation::overwrite_file,
 };
-use anyhow::{Result, anyhow, bail};
+use anyhow::{Context, Result, anyhow, bail};
+use camino::{Utf8Path, Utf8PathBuf};
 use owo_colors::OwoColorize;
-use std::process::ExitCode;
+use std::{fmt, ops::ControlFlow, process::ExitCode};
 
 #[derive(Clone, Copy, Debug)]
 pub(crate) enum GenerateResult {
@@ -36,23 +48,19 @@ pub(crate) fn generate_impl(
     env: &ResolvedEnv,
     blessed_source: &BlessedSource,
     generated_source: &GeneratedSource,
+    provenance: Option<&Utf8Path>,
     output: &OutputOpts,
 ) -> Result<GenerateResult> {
     let styles = output.styles(supports_color::Stream::Stderr);
+    if env.settings.strict_documents_dir {
+        check_unrecognized_files(env, apis)?;
+    }
 
-    let (generated, errors) =
-        generated_source.load(apis, &styles, &env.repo_root, &env.vcs)?;
-    display_load_problems(&errors, &styles)?;
-
-    let (local_files, errors) =
-        env.local_source.load(apis, &styles, &env.repo_root, &env.vcs)?;
-    display_load_problems(&errors, &styles)?;
-
-    let (blessed, errors) =
-        blessed_source.load(&env.repo_root, apis, &styles, &env.vcs)?;
-    display_load_problems(&errors, &styles)?;
+    let (generated, local_files, blessed) =
+        load_sources(apis, env, blessed_source, generated_source, &styles)?;
 
     let resolved = Resolved::new(env, apis, &blessed, &generated, &local_files);
+    env.settings.output_sinks.emit_problems(&resolved);
     eprintln!("{:>HEADER_WIDTH$}", SEPARATOR);
 
     let total = resolved.nexpected_documents();
@@ -64,14 +72,49 @@ pub(crate) fn generate_impl(
     );
 
     if resolved.has_unfixable_problems() {
-        return match display_resolution(env, apis, &resolved, &styles)? {
-            CheckResult::Failures => Ok(GenerateResult::Failures),
+        return match display_resolution(
+            env,
+            apis,
+            &resolved,
+            &styles,
+            &mut output.problem_limit(),
+        )? {
+            CheckResult::Failures => {
+                env.settings.output_sinks.emit(
+                    &OutputEvent::GenerateFinished {
+                        success: false,
+                        updated: 0,
+                        unchanged: 0,
+                        failed: 0,
+                    },
+                );
+                Ok(GenerateResult::Failures)
+            }
             unexpected => {
                 Err(anyhow!("unexpectedly got {unexpected:?} from summarize()"))
             }
         };
     }
 
+    // Keep other processes from updating the documents directory while fixes
+    // are applied. The lock is released when this function returns.
+    let _lock = lock_documents(env, &styles)?;
+
+    // Post-generate hooks are told which files changed, so take a snapshot
+    // of the documents directory to compare against once fixes are applied.
+    let before = if env.settings.generate_hooks.post.is_empty() {
+        None
+    } else {
+        Some(DirSnapshot::take(env.openapi_abs_dir())?)
+    };
+    run_hooks(
+        env,
+        &env.settings.generate_hooks.pre,
+        "pre-generate",
+        &[],
+        &styles,
+    )?;
+
     let mut num_updated = 0;
     let mut num_unchanged = 0;
     let mut num_errors = 0;
@@ -135,6 +178,24 @@ pub(crate) fn generate_impl(
                 ident.style(styles.filename),
             );
         }
+
+        if let Some(combined_problem) =
+            resolved.combined_document_problem(ident)
+        {
+            eprintln!(
+                "{:>HEADER_WIDTH$} {} combined document",
+                STALE.style(styles.warning_header),
+                ident.style(styles.filename),
+            );
+
+            fix_problems(
+                env,
+                std::iter::once(combined_problem),
+                &styles,
+                &mut num_updated,
+                &mut num_errors,
+            );
+        }
     }
 
     // Fix problems not associated with any supported version, if any.
@@ -147,12 +208,50 @@ pub(crate) fn generate_impl(
         &mut num_errors,
     );
 
+    // Now that the documents themselves are up-to-date, reformat them, update
+    // shared schemas, and update the copies under each alias.
+    for api in apis.iter_apis() {
+        for fix in Fix::post_generate(api) {
+            match fix.execute(env) {
+                Ok(steps) if steps.is_empty() => {}
+                Ok(steps) => {
+                    num_updated += 1;
+                    for s in &steps {
+                        eprintln!(
+                            "{:>HEADER_WIDTH$} {}",
+                            "Fixed".style(styles.success_header),
+                            s,
+                        );
+                    }
+                    env.settings.output_sinks.emit(&OutputEvent::FixApplied {
+                        fix: fix.to_string(),
+                        steps: &steps,
+                    });
+                }
+                Err(error) => {
+                    num_errors += 1;
+                    eprintln!(
+                        "{:>HEADER_WIDTH$} fix {:?}: {:#}",
+                        "FIX FAILED".style(styles.failure_header),
+                        fix.to_string(),
+                        error
+                    );
+                    env.settings.output_sinks.emit(&OutputEvent::FixFailed {
+                        fix: fix.to_string(),
+                        error: &error,
+                    });
+                }
+            }
+        }
+    }
+
     // Done with the first resolution. Release borrows so the source
     // collections can be dropped in parallel later.
     drop(resolved);
 
     if num_errors > 0 {
         print_final_status(
+            env,
             &styles,
             total,
             num_updated,
@@ -165,8 +264,13 @@ pub(crate) fn generate_impl(
     // Finally, check again for any problems. Since we expect this should have
     // fixed everything, be quiet unless we find something amiss.
     let mut nproblems = 0;
-    let (local_files_recheck, errors) =
-        env.local_source.load(apis, &styles, &env.repo_root, &env.vcs)?;
+    let (local_files_recheck, errors) = env.local_source.load(
+        apis,
+        &styles,
+        &env.repo_root,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
     eprintln!(
         "{:>HEADER_WIDTH$} all local files",
         "Rechecking".style(styles.success_header),
@@ -209,6 +313,89 @@ pub(crate) fn generate_impl(
                 &styles,
             );
         }
+
+        if let Some(combined_problem) =
+            resolved.combined_document_problem(ident)
+        {
+            nproblems += 1;
+            eprintln!(
+                "found unexpected problem with API {} combined document \
+                 (this is a bug)",
+                ident
+            );
+            display_resolution_problems(
+                env,
+                std::iter::once(combined_problem),
+                &styles,
+            );
+        }
+
+        if let Some(json_format_problem) = resolved.json_format_problem(ident) {
+            nproblems += 1;
+            eprintln!(
+                "found unexpected problem with API {} JSON format \
+                 (this is a bug)",
+                ident
+            );
+            display_resolution_problems(
+                env,
+                std::iter::once(json_format_problem),
+                &styles,
+            );
+        }
+
+        if let Some(shared_schemas_problem) =
+            resolved.shared_schemas_problem(ident)
+        {
+            nproblems += 1;
+            eprintln!(
+                "found unexpected problem with API {} shared schemas \
+                 (this is a bug)",
+                ident
+            );
+            display_resolution_problems(
+                env,
+                std::iter::once(shared_schemas_problem),
+                &styles,
+            );
+        }
+
+        let alias_problems: Vec<_> = resolved.alias_problems(ident).collect();
+        if !alias_problems.is_empty() {
+            nproblems += alias_problems.len();
+            eprintln!(
+                "found unexpected problem with API {} aliases (this is a bug)",
+                ident
+            );
+            display_resolution_problems(env, alias_problems, &styles);
+        }
+    }
+
+    // Only attest to documents once they're known to be up-to-date.
+    if nproblems == 0
+        && let Some(provenance) = provenance
+    {
+        let path = env.repo_root.join(provenance);
+        let contents = provenance_statement(env, apis, &local_files_recheck)?;
+        let _ = overwrite_file(&path, &contents)?;
+        eprintln!(
+            "{:>HEADER_WIDTH$} provenance statement {}",
+            "Wrote".style(styles.success_header),
+            provenance.style(styles.filename),
+        );
+    }
+
+    if nproblems == 0
+        && let Some(before) = &before
+    {
+        let after = DirSnapshot::take(env.openapi_abs_dir())?;
+        run_hooks(
+            env,
+            &env.settings.generate_hooks.post,
+            "post-generate",
+            &before.changed_paths(&after),
+            &styles,
+        )?;
     }
 
     // Release borrows held by `resolved`, then drop all source
@@ -229,6 +416,7 @@ pub(crate) fn generate_impl(
         );
     } else {
         print_final_status(
+            env,
             &styles,
             total,
             num_updated,
@@ -239,7 +427,79 @@ pub(crate) fn generate_impl(
     }
 }
 
+/// Generates documents from the declared `inputs` in a temporary directory,
+/// and returns the paths of the files that end up there, relative to it.
+///
+/// These are the files `generate --output-root` would write. If generation
+/// fails, no paths are returned.
+pub(crate) fn list_generate_outputs(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    inputs: &HermeticInputs,
+    blessed_source: &BlessedSource,
+    generated_source: &GeneratedSource,
+    output: &OutputOpts,
+) -> Result<(GenerateResult, Vec<Utf8PathBuf>)> {
+    let (temp_dir, mut env) = inputs.stage_temp(env)?;
+    // Nothing is written, so there's nothing for hooks to act on, or to
+    // report to sinks.
+    env.settings.generate_hooks = GenerateHooks::default();
+    env.settings.output_sinks = OutputSinks::default();
+    let result = generate_impl(
+        apis,
+        &env,
+        blessed_source,
+        generated_source,
+        None,
+        output,
+    )?;
+    let outputs = match result {
+        GenerateResult::Success => list_outputs(temp_dir.path())?,
+        GenerateResult::Failures => Vec::new(),
+    };
+    Ok((result, outputs))
+}
+
+/// Locks the documents directory against other processes updating it.
+fn lock_documents(env: &ResolvedEnv, styles: &Styles) -> Result<DocumentsLock> {
+    DocumentsLock::acquire(env.openapi_abs_dir(), env.wait_for_lock, || {
+        eprintln!(
+            "{:>HEADER_WIDTH$} for another process to finish updating {}",
+            "Waiting".style(styles.warning_header),
+            env.openapi_abs_dir(),
+        );
+    })
+}
+
+/// Runs `hooks` in order, stopping at the first one that fails.
+fn run_hooks(
+    env: &ResolvedEnv,
+    hooks: &[GenerateHook],
+    kind: &str,
+    changed_paths: &[Utf8PathBuf],
+    styles: &Styles,
+) -> Result<()> {
+    let cx = GenerateHookContext::new(
+        &env.repo_root,
+        env.openapi_abs_dir(),
+        changed_paths,
+    );
+    for hook in hooks {
+        eprintln!(
+            "{:>HEADER_WIDTH$} {} hook {}",
+            "Running".style(styles.success_header),
+            kind,
+            hook.name().style(styles.bold),
+        );
+        hook.run(&cx)
+            .with_context(|| format!("{} hook {} failed", kind, hook.name()))?;
+    }
+    Ok(())
+}
+
+/// Prints a summary of the run, and reports it to output sinks.
 fn print_final_status(
+    env: &ResolvedEnv,
     styles: &Styles,
     ndocuments: usize,
     num_updated: usize,
@@ -262,6 +522,119 @@ fn print_final_status(
         num_unchanged.style(styles.bold),
         num_errors.style(styles.bold),
     );
+    env.settings.output_sinks.emit(&OutputEvent::GenerateFinished {
+        success: num_errors == 0,
+        updated: num_updated,
+        unchanged: num_unchanged,
+        failed: num_errors,
+    });
+}
+
+/// Loads the generated, local, and blessed documents for `apis`.
+pub(crate) fn load_sources(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    blessed_source: &BlessedSource,
+    generated_source: &GeneratedSource,
+    styles: &Styles,
+) -> Result<(GeneratedFiles, LocalFiles, BlessedFiles)> {
+    let (mut generated, mut errors) =
+        generated_source.load(apis, styles, env)?;
+    if let Some(timings) = &env.settings.generation_timings {
+        timings.record(&env.repo_root, apis, &generated, &mut errors)?;
+    }
+    display_load_problems(&errors, styles)?;
+
+    let (local_files, errors) = env.local_source.load(
+        apis,
+        styles,
+        &env.repo_root,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    display_load_problems(&errors, styles)?;
+    generated.apply_lockstep_version_policies(apis, &local_files)?;
+
+    let (blessed, errors) = blessed_source.load(
+        &env.repo_root,
+        apis,
+        styles,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    display_load_problems(&errors, styles)?;
+
+    Ok((generated, local_files, blessed))
+}
+
+/// A fix applied by [`apply_fixes`].
+///
+/// This displays as a description of the fix, like the ones `generate` prints
+/// when a fix fails.
+pub struct FixOperation<'a> {
+    fix: &'a Fix<'a>,
+}
+
+impl fmt::Display for FixOperation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.fix.to_string().trim_end())
+    }
+}
+
+/// Brings the OpenAPI documents for `apis` up-to-date the way the `generate`
+/// command does, reporting each fix to `on_fix` as it's applied.
+///
+/// This is meant for tools that update documents as part of a larger
+/// workflow, e.g. release automation, so that they don't have to run the
+/// command and parse its output. Documents are loaded from the same default
+/// sources as `generate`.
+///
+/// `on_fix` is passed each fix along with its result: either a description of
+/// each step taken, or the error applying it. It returns
+/// [`ControlFlow::Break`] to stop applying fixes, in which case this returns
+/// `ControlFlow::Break` as well. A failed fix doesn't stop the remaining ones
+/// unless `on_fix` says so.
+///
+/// If there are problems that can't be fixed automatically, this returns an
+/// error without changing anything. As with `generate`, fixes are applied
+/// while holding a lock on the documents directory, and this returns an error
+/// if another process holds it. Unlike `generate`, this doesn't run
+/// generate hooks, write a provenance statement, or recheck the documents
+/// afterward.
+pub fn apply_fixes<F>(
+    env: &Environment,
+    apis: &ManagedApis,
+    mut on_fix: F,
+) -> Result<ControlFlow<()>>
+where
+    F: FnMut(&FixOperation<'_>, &Result<Vec<String>>) -> ControlFlow<()>,
+{
+    let env = env.resolve(None)?;
+    if env.settings.strict_documents_dir {
+        check_unrecognized_files(&env, apis)?;
+    }
+    let styles = Styles::default();
+    let blessed_source =
+        BlessedSourceArgs::default().to_blessed_source(&env)?;
+    let (generated, local_files, blessed) = load_sources(
+        apis,
+        &env,
+        &blessed_source,
+        &GeneratedSource::Generated { verify_deterministic: false },
+        &styles,
+    )?;
+
+    let resolved =
+        Resolved::new(&env, apis, &blessed, &generated, &local_files);
+    if resolved.has_unfixable_problems() {
+        bail!(
+            "found problems that can't be fixed automatically \
+             (run the check command for details)"
+        );
+    }
+    let _lock = lock_documents(&env, &styles)?;
+    Ok(resolved
+        .apply_fixes(&env, |fix, result| on_fix(&FixOperation { fix }, result)))
 }
 
 fn fix_problems<'a, T>(
@@ -280,13 +653,17 @@ fn fix_problems<'a, T>(
         match fix.execute(env) {
             Ok(steps) => {
                 *num_updated += 1;
-                for s in steps {
+                for s in &steps {
                     eprintln!(
                         "{:>HEADER_WIDTH$} {}",
                         "Fixed".style(styles.success_header),
                         s,
                     );
                 }
+                env.settings.output_sinks.emit(&OutputEvent::FixApplied {
+                    fix: fix.to_string(),
+                    steps: &steps,
+                });
             }
             Err(error) => {
                 *num_errors += 1;
@@ -296,6 +673,10 @@ fn fix_problems<'a, T>(
                     fix.to_string(),
                     error
                 );
+                env.settings.output_sinks.emit(&OutputEvent::FixFailed {
+                    fix: fix.to_string(),
+                    error: &error,
+                });
             }
         }
     }
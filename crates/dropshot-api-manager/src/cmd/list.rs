@@ -2,7 +2,10 @@
 
 use crate::{
     apis::ManagedApis,
-    output::{OutputOpts, display_api_spec, display_error, plural},
+    compatibility::describe_differences,
+    output::{
+        InlineErrorChain, OutputOpts, display_api_spec, display_error, plural,
+    },
 };
 use indent_write::io::IndentWriter;
 use openapiv3::OpenAPI;
@@ -97,6 +100,9 @@ pub(crate) fn list_impl(
                 "spec details".style(styles.header),
             )?;
 
+            // The previous version's document, kept around to describe what
+            // each version changed.
+            let mut previous: Option<(&semver::Version, OpenAPI)> = None;
             for v in api.iter_versions_semver() {
                 match api.generate_openapi_doc(v) {
                     Ok(openapi) => {
@@ -113,6 +119,33 @@ pub(crate) fn list_impl(
                             plural::paths(summary.path_count),
                             num_schemas
                         )?;
+                        if let Some((prev_version, prev_openapi)) = &previous {
+                            let changes = match describe_differences(
+                                prev_openapi,
+                                &openapi,
+                            ) {
+                                Ok(delta) => delta.to_string(),
+                                Err(error) => format!(
+                                    "(error comparing documents: {})",
+                                    InlineErrorChain::new(error.as_ref()),
+                                ),
+                            };
+                            write!(
+                                &mut out,
+                                "{continued_indent}     {}: ",
+                                format!("since v{}", prev_version)
+                                    .style(styles.header),
+                            )?;
+                            writeln!(
+                                IndentWriter::new_skip_initial(
+                                    &format!("{continued_indent}         "),
+                                    &mut out,
+                                ),
+                                "{}",
+                                changes,
+                            )?;
+                        }
+                        previous = Some((v, openapi));
                     }
                     Err(error) => {
                         write!(
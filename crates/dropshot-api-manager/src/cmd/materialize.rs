@@ -0,0 +1,116 @@
+// Copyright 2026 Oxide Computer Company
+
+use crate::{
+    apis::ManagedApis,
+    environment::ResolvedEnv,
+    output::{OutputOpts, display_load_problems, headers::*, plural},
+    resolved::symlink_file,
+    spec_files_local::LocalApiSpecFile,
+    validation::overwrite_file,
+};
+use anyhow::{Context, anyhow, bail};
+use camino::Utf8Path;
+use owo_colors::OwoColorize;
+use std::borrow::Cow;
+
+/// Writes the JSON contents of every local OpenAPI document, including those
+/// stored as Git stubs or with shared schemas, to `target_dir`.
+///
+/// The target directory has the same layout as the documents directory, except
+/// that every Git stub is replaced by the JSON file it refers to, and every
+/// document is self-contained, with its shared schemas inlined. This is meant
+/// for tools that can't dereference Git stubs or shared schemas.
+pub(crate) fn materialize_impl(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    target_dir: &Utf8Path,
+    output: &OutputOpts,
+) -> anyhow::Result<()> {
+    let styles = output.styles(supports_color::Stream::Stderr);
+
+    let target_dir = camino::absolute_utf8(target_dir).with_context(|| {
+        format!("error making target directory absolute: {}", target_dir)
+    })?;
+    let openapi_dir = env.openapi_abs_dir();
+    if target_dir.starts_with(openapi_dir)
+        || openapi_dir.starts_with(&target_dir)
+    {
+        bail!(
+            "target directory {} must not overlap the OpenAPI documents \
+             directory {}",
+            target_dir,
+            openapi_dir,
+        );
+    }
+
+    let (local_files, errors) = env.local_source.load(
+        apis,
+        &styles,
+        &env.repo_root,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    display_load_problems(&errors, &styles)?;
+
+    let mut num_written = 0;
+    let mut num_git_stubs = 0;
+    for (ident, api_files) in local_files.iter() {
+        let json_format = apis.api(ident).and_then(|api| api.json_format());
+        for file in api_files.versions().values().flatten() {
+            let (spec, git_stub_commit) = match file {
+                LocalApiSpecFile::Valid { spec, git_stub_commit } => {
+                    (spec, git_stub_commit)
+                }
+                LocalApiSpecFile::Unparseable(unparseable) => {
+                    eprintln!(
+                        "{:>HEADER_WIDTH$} skipping unparseable file {}",
+                        WARNING.style(styles.warning_header),
+                        unparseable.name.path().style(styles.filename),
+                    );
+                    continue;
+                }
+            };
+
+            let name = spec.spec_file_name().to_json_filename();
+            let path = target_dir.join(name.path());
+            // Documents are loaded in Dropshot's formatting, so write them
+            // back out in their configured format.
+            let contents = match json_format {
+                Some(format) => Cow::Owned(format.format(spec.contents())?),
+                None => Cow::Borrowed(spec.contents()),
+            };
+            // Unchanged files are fine: the target may be reused across runs.
+            let _ = overwrite_file(&path, &contents)?;
+            num_written += 1;
+            if git_stub_commit.is_some() {
+                num_git_stubs += 1;
+            }
+        }
+
+        if let Some(link) = api_files.latest_link() {
+            let path = target_dir
+                .join(ident.as_str())
+                .join(ident.versioned_api_latest_symlink());
+            match fs_err::remove_file(&path) {
+                Ok(_) => (),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => {
+                    return Err(anyhow!(err).context("removing old link"));
+                }
+            };
+            symlink_file(&link.json_basename(), &path)?;
+        }
+    }
+
+    eprintln!("{:>HEADER_WIDTH$}", SEPARATOR);
+    eprintln!(
+        "{:>HEADER_WIDTH$} {} OpenAPI {} ({} from Git stubs) to {}",
+        "Materialized".style(styles.success_header),
+        num_written.style(styles.bold),
+        plural::documents(num_written),
+        num_git_stubs.style(styles.bold),
+        target_dir.style(styles.filename),
+    );
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-// Copyright 2025 Oxide Computer Company
+// Copyright 2026 Oxide Computer Company
 
 //! dropshot-api-manager library facilities for implementing the dropshot-api-manager
 //! command-line tool
@@ -7,7 +7,22 @@
 pub mod dispatch;
 
 // subcommands
+pub(crate) mod audit_unused_versions;
 pub(crate) mod check;
+pub(crate) mod compat_preview;
 mod debug;
-mod generate;
+pub(crate) mod drift_report;
+pub(crate) mod generate;
 mod list;
+pub(crate) mod materialize;
+pub(crate) mod new_version;
+pub(crate) mod pack_baseline;
+pub(crate) mod patch;
+pub(crate) mod pin_stubs;
+pub(crate) mod pins;
+pub(crate) mod since_release;
+pub(crate) mod slim;
+pub(crate) mod status;
+pub(crate) mod verify_notes;
+pub(crate) mod watch;
+pub(crate) mod why_blessed;
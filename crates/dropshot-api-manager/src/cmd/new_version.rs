@@ -0,0 +1,276 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Scaffolding for adding a new version of a versioned API.
+//!
+//! This automates the mechanical first steps of the workflow in
+//! `guides/new-version.md`: picking the next version number, adding it to the
+//! `api_versions!` call, and creating a module in the versions crate for the
+//! new or changed types. It then lists the operations that will need manual
+//! work, since changing an endpoint means marking the old one as removed and
+//! adding a replacement.
+//!
+//! The new version can only be generated once the API has been rebuilt, so
+//! this doesn't generate documents itself.
+
+use crate::{
+    apis::ManagedApis,
+    environment::{BlessedSource, ResolvedEnv},
+    next_version::{latest_version_in_use, next_version_after},
+    output::{OutputOpts, display_load_problems},
+    validation::overwrite_file,
+};
+use anyhow::{Context, bail};
+use camino::Utf8Path;
+use dropshot_api_manager_types::ApiIdent;
+use std::io::Write;
+
+/// Where, if anywhere, to apply the scaffolding for a new version.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct NewVersionTargets<'a> {
+    /// The file containing the `api_versions!` call for the API.
+    pub(crate) api_file: Option<&'a Utf8Path>,
+    /// The source directory of the versions crate.
+    pub(crate) versions_dir: Option<&'a Utf8Path>,
+    /// If false, only describe the changes to `api_file` and `versions_dir`.
+    pub(crate) apply: bool,
+}
+
+/// Adds version `label` to the API `ident`, writing a description of the steps
+/// to `out`.
+pub(crate) fn new_version_impl(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    blessed_source: &BlessedSource,
+    ident: &ApiIdent,
+    label: &str,
+    targets: NewVersionTargets<'_>,
+    output: &OutputOpts,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let Some(api) = apis.api(ident) else {
+        bail!("unknown API {:?}", ident);
+    };
+    let Some(versions) = api.iter_versioned_versions() else {
+        bail!("{} is a lockstep API, so it has no versions to add to", ident);
+    };
+    if !is_valid_label(label) {
+        bail!(
+            "invalid version label {:?}: labels are SCREAMING_SNAKE_CASE \
+             identifiers, like ADD_WIDGETS",
+            label
+        );
+    }
+    let versions: Vec<_> = versions.collect();
+    if versions.iter().any(|v| v.label() == label) {
+        bail!("{} already has a version labeled {}", ident, label);
+    }
+    let latest = versions
+        .iter()
+        .max_by_key(|v| v.semver())
+        .expect("versioned APIs have at least one version");
+    let styles = output.styles(supports_color::Stream::Stderr);
+    let (in_use, problems) =
+        latest_version_in_use(apis, env, blessed_source, ident, &styles)?;
+    display_load_problems(&problems, &styles)?;
+    let major = next_version_after(
+        &in_use,
+        api.version_scheme(),
+        chrono::Utc::now().date_naive(),
+    )?
+    .major;
+    let module_dir = label.to_lowercase();
+
+    // Check both targets before writing to either of them.
+    let api_file = match targets.api_file {
+        Some(path) => {
+            let contents = fs_err::read_to_string(path)?;
+            let updated =
+                add_to_api_versions(&contents, major, label, latest.label())
+                    .with_context(|| format!("updating {}", path))?;
+            Some((path, updated))
+        }
+        None => None,
+    };
+    let module_dir_path = targets.versions_dir.map(|dir| dir.join(&module_dir));
+    if let Some(dir) = &module_dir_path
+        && dir.exists()
+    {
+        bail!("{} already exists", dir);
+    }
+
+    writeln!(
+        out,
+        "Adding version {}.0.0 ({}) to {} (latest is {}, {})",
+        major,
+        label,
+        ident,
+        latest.semver(),
+        latest.label(),
+    )?;
+    if in_use > *latest.semver() {
+        writeln!(
+            out,
+            "(Versions up to {} are already used by blessed or local \
+             documents.)",
+            in_use,
+        )?;
+    }
+    writeln!(out)?;
+
+    writeln!(out, "1. Add the version to the top of the api_versions! list:")?;
+    writeln!(out)?;
+    writeln!(out, "       ({}, {}),", major, label)?;
+    writeln!(out)?;
+    if let Some((path, updated)) = api_file {
+        if targets.apply {
+            let _ = overwrite_file(path, updated.as_bytes())?;
+            writeln!(out, "   Updated {}.", path)?;
+        } else {
+            writeln!(out, "   Pass --apply to update {}.", path)?;
+        }
+        writeln!(out)?;
+    }
+
+    writeln!(
+        out,
+        "2. If the version adds or changes types, put them in a new module in \
+         the\n   versions crate, declared in its lib.rs as:"
+    )?;
+    writeln!(out)?;
+    writeln!(out, "       #[path = \"{}/mod.rs\"]", module_dir)?;
+    writeln!(out, "       pub mod v{};", major)?;
+    writeln!(out)?;
+    if let Some(dir) = module_dir_path {
+        let path = dir.join("mod.rs");
+        if targets.apply {
+            let stub = module_stub(label, api.title());
+            let _ = overwrite_file(&path, stub.as_bytes())?;
+            writeln!(out, "   Created {}.", path)?;
+        } else {
+            writeln!(out, "   Pass --apply to create {}.", path)?;
+        }
+        writeln!(out)?;
+    }
+
+    let document =
+        api.generate_openapi_doc(latest.semver()).with_context(|| {
+            format!("generating {} v{}", ident, latest.semver())
+        })?;
+    let mut operations = Vec::new();
+    for (path, item) in document.paths.iter() {
+        let Some(item) = item.as_item() else {
+            continue;
+        };
+        for (method, operation) in item.iter() {
+            operations.push((
+                method.to_uppercase(),
+                path,
+                operation.operation_id.as_deref(),
+            ));
+        }
+    }
+    writeln!(
+        out,
+        "3. Update endpoints. These {} operations are in {}, and will also be \
+         in\n   v{} unless they're marked as removed:",
+        operations.len(),
+        latest.label(),
+        major,
+    )?;
+    writeln!(out)?;
+    for (method, path, operation_id) in &operations {
+        match operation_id {
+            Some(id) => writeln!(out, "       {} {} ({})", method, path, id)?,
+            None => writeln!(out, "       {} {}", method, path)?,
+        }
+    }
+    writeln!(out)?;
+    writeln!(
+        out,
+        "   For each endpoint that changes, mark the existing one with\n   \
+         `versions = ..VERSION_{label}` and add a replacement with\n   \
+         `versions = VERSION_{label}..`, then update the server and\n   \
+         client. New endpoints use `versions = VERSION_{label}..`.",
+    )?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "4. Rebuild, then run `{} generate` to generate the document for v{}.",
+        env.command, major,
+    )?;
+    Ok(())
+}
+
+/// Returns true if `label` is a SCREAMING_SNAKE_CASE identifier.
+fn is_valid_label(label: &str) -> bool {
+    let mut chars = label.chars();
+    chars.next().is_some_and(|c| c.is_ascii_uppercase())
+        && chars
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Adds `(major, label)` to the top of the `api_versions!` list in `contents`.
+///
+/// As a check that this is the right file, the first entry in the list must be
+/// the latest version of the API, `latest_label`.
+fn add_to_api_versions(
+    contents: &str,
+    major: u64,
+    label: &str,
+    latest_label: &str,
+) -> anyhow::Result<String> {
+    let mut calls = contents.match_indices("api_versions!");
+    let (Some((start, _)), None) = (calls.next(), calls.next()) else {
+        bail!("expected exactly one api_versions! call");
+    };
+    let Some(bracket) = contents[start..].find('[').map(|i| start + i + 1)
+    else {
+        bail!("api_versions! call has no list of versions");
+    };
+    let Some(entry) = contents[bracket..].find('(').map(|i| bracket + i) else {
+        bail!("api_versions! call has no versions");
+    };
+    let separator = &contents[bracket..entry];
+    if !separator.trim().is_empty() {
+        bail!(
+            "unexpected {:?} at the start of api_versions!",
+            separator.trim()
+        );
+    }
+    let first = contents[entry + 1..]
+        .split(')')
+        .next()
+        .and_then(|entry| entry.split(',').nth(1))
+        .map(str::trim);
+    if first != Some(latest_label) {
+        bail!(
+            "the first version in api_versions! isn't the latest version of \
+             the API ({})",
+            latest_label
+        );
+    }
+
+    // Match the layout of the existing list: one entry per line with the same
+    // indentation, or all entries on one line.
+    let new_entry = match separator.rfind('\n') {
+        Some(newline) => {
+            format!("({}, {}),\n{}", major, label, &separator[newline + 1..])
+        }
+        None => format!("({}, {}), ", major, label),
+    };
+    let mut updated = contents.to_owned();
+    updated.insert_str(entry, &new_entry);
+    Ok(updated)
+}
+
+/// Returns the initial contents of the version module for `label`, following
+/// the template in `guides/new-version.md`.
+fn module_stub(label: &str, title: &str) -> String {
+    format!(
+        "//! Version `{}` of {}.\n\
+         //!\n\
+         //! TODO: Add a brief summary of what was added or changed in this\n\
+         //! version. Don't refer to future versions here, just past ones.\n",
+        label, title,
+    )
+}
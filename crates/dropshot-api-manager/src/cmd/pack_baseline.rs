@@ -0,0 +1,31 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Packs the blessed documents into a baseline, for checks that can't (or
+//! shouldn't) read VCS history.
+
+use crate::{
+    apis::ManagedApis,
+    baseline::pack_baseline,
+    environment::{BlessedSource, ResolvedEnv},
+    output::{OutputOpts, display_load_problems},
+};
+
+/// Loads the blessed documents from `blessed_source` and returns a baseline
+/// containing them, along with the number of documents packed.
+pub(crate) fn pack_baseline_impl(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    blessed_source: &BlessedSource,
+    output: &OutputOpts,
+) -> anyhow::Result<(Vec<u8>, usize)> {
+    let styles = output.styles(supports_color::Stream::Stderr);
+    let (blessed, errors) = blessed_source.load(
+        &env.repo_root,
+        apis,
+        &styles,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    display_load_problems(&errors, &styles)?;
+    pack_baseline(&blessed)
+}
@@ -0,0 +1,190 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Describes the changes from the latest blessed version of an API to the
+//! document generated for that version.
+//!
+//! If the two aren't bytewise identical, `check` requires a version bump even
+//! if the changes are trivial (e.g. documentation updates). A patch shows
+//! reviewers exactly which changes accumulated.
+
+use crate::{
+    apis::ManagedApis,
+    compatibility::escape_json_pointer,
+    environment::{BlessedSource, GeneratedSource, ResolvedEnv},
+    output::{OutputOpts, display_load_problems},
+};
+use anyhow::{Context, anyhow, bail};
+use clap::ValueEnum;
+use dropshot_api_manager_types::ApiIdent;
+use serde_json::{Map, Value, json};
+
+/// The format of a patch.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub(crate) enum PatchFormat {
+    /// A JSON Patch (RFC 6902).
+    #[default]
+    JsonPatch,
+    /// A JSON Merge Patch (RFC 7396).
+    MergePatch,
+}
+
+/// Returns a patch, in `format`, from the latest blessed version of the API
+/// `ident` to the document generated for that version.
+pub(crate) fn patch_impl(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    blessed_source: &BlessedSource,
+    generated_source: &GeneratedSource,
+    ident: &ApiIdent,
+    format: PatchFormat,
+    output: &OutputOpts,
+) -> anyhow::Result<Vec<u8>> {
+    let styles = output.styles(supports_color::Stream::Stderr);
+    let Some(api) = apis.api(ident) else {
+        bail!("unknown API {:?}", ident);
+    };
+    if !api.is_versioned() {
+        bail!("{} is a lockstep API, so it has no blessed versions", ident);
+    }
+
+    let (generated, errors) = generated_source.load(apis, &styles, env)?;
+    display_load_problems(&errors, &styles)?;
+
+    let (blessed, errors) = blessed_source.load(
+        &env.repo_root,
+        apis,
+        &styles,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    display_load_problems(&errors, &styles)?;
+
+    let (version, blessed_file) = blessed
+        .get(ident)
+        .and_then(|api_files| api_files.versions().last_key_value())
+        .ok_or_else(|| anyhow!("{} has no blessed versions", ident))?;
+    let generated_file = generated
+        .get(ident)
+        .and_then(|api_files| api_files.versions().get(version))
+        .ok_or_else(|| {
+            anyhow!(
+                "the latest blessed version of {} (v{}) is no longer supported",
+                ident,
+                version,
+            )
+        })?;
+
+    let patch = match format {
+        PatchFormat::JsonPatch => {
+            let mut ops = Vec::new();
+            json_patch(
+                "",
+                blessed_file.value(),
+                generated_file.value(),
+                &mut ops,
+            );
+            Value::Array(ops)
+        }
+        PatchFormat::MergePatch => {
+            merge_patch(blessed_file.value(), generated_file.value())
+                .unwrap_or_else(|| Value::Object(Map::new()))
+        }
+    };
+    let mut contents =
+        serde_json::to_vec_pretty(&patch).context("serializing patch")?;
+    contents.push(b'\n');
+    Ok(contents)
+}
+
+/// Appends the JSON Patch operations that transform `from` (at the JSON
+/// pointer `path`) into `to` to `ops`.
+pub(crate) fn json_patch(
+    path: &str,
+    from: &Value,
+    to: &Value,
+    ops: &mut Vec<Value>,
+) {
+    match (from, to) {
+        _ if from == to => {}
+        (Value::Object(from), Value::Object(to)) => {
+            for (key, from_value) in from {
+                let child = format!("{}/{}", path, escape_json_pointer(key));
+                match to.get(key) {
+                    Some(to_value) => {
+                        json_patch(&child, from_value, to_value, ops)
+                    }
+                    None => ops.push(json!({ "op": "remove", "path": child })),
+                }
+            }
+            for (key, to_value) in to {
+                if !from.contains_key(key) {
+                    let child =
+                        format!("{}/{}", path, escape_json_pointer(key));
+                    ops.push(json!({
+                        "op": "add",
+                        "path": child,
+                        "value": to_value,
+                    }));
+                }
+            }
+        }
+        (Value::Array(from), Value::Array(to)) => {
+            let common = from.len().min(to.len());
+            for (i, (from_value, to_value)) in from.iter().zip(to).enumerate() {
+                json_patch(
+                    &format!("{}/{}", path, i),
+                    from_value,
+                    to_value,
+                    ops,
+                );
+            }
+            // Remove trailing elements from the end, so that the indexes of
+            // the ones before them stay valid.
+            for i in (common..from.len()).rev() {
+                ops.push(json!({
+                    "op": "remove",
+                    "path": format!("{}/{}", path, i),
+                }));
+            }
+            for (i, to_value) in to.iter().enumerate().skip(common) {
+                ops.push(json!({
+                    "op": "add",
+                    "path": format!("{}/{}", path, i),
+                    "value": to_value,
+                }));
+            }
+        }
+        _ => ops.push(json!({ "op": "replace", "path": path, "value": to })),
+    }
+}
+
+/// Returns the JSON Merge Patch that transforms `from` into `to`, or `None` if
+/// they're equal.
+///
+/// Merge patches can't express setting a value to `null`, or changing part of
+/// an array; arrays are replaced as a whole.
+fn merge_patch(from: &Value, to: &Value) -> Option<Value> {
+    if from == to {
+        return None;
+    }
+    let (Value::Object(from), Value::Object(to)) = (from, to) else {
+        return Some(to.clone());
+    };
+
+    let mut patch = Map::new();
+    for key in from.keys() {
+        if !to.contains_key(key) {
+            patch.insert(key.clone(), Value::Null);
+        }
+    }
+    for (key, to_value) in to {
+        let value = match from.get(key) {
+            Some(from_value) => merge_patch(from_value, to_value),
+            None => Some(to_value.clone()),
+        };
+        if let Some(value) = value {
+            patch.insert(key.clone(), value);
+        }
+    }
+    Some(Value::Object(patch))
+}
@@ -0,0 +1,212 @@
+// Copyright 2026 Oxide Computer Company
+
+use crate::{
+    apis::ManagedApis,
+    environment::{ErrorAccumulator, ResolvedEnv},
+    output::{
+        CheckResult, OutputOpts, display_load_problems, headers::*, plural,
+    },
+    vcs::{CommitReachability, PIN_REF_PREFIX},
+};
+use anyhow::{Context, anyhow};
+use camino::{Utf8Path, Utf8PathBuf};
+use git_stub::{GitCommitHash, GitStub};
+use owo_colors::OwoColorize;
+use std::collections::BTreeMap;
+
+/// Pins, or checks the reachability of, every commit referenced by a Git stub
+/// in the local OpenAPI documents directory.
+///
+/// If `check` is true, no refs are created. Instead, this reports commits that
+/// are missing (a failure) or present but not reachable from any ref (which
+/// can be fixed by running without `check`).
+pub(crate) fn pin_stubs_impl(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    check: bool,
+    output: &OutputOpts,
+) -> anyhow::Result<CheckResult> {
+    let styles = output.styles(supports_color::Stream::Stderr);
+
+    let mut errors = ErrorAccumulator::new();
+    let commits =
+        collect_git_stub_commits(env.openapi_abs_dir(), apis, &mut errors);
+    display_load_problems(&errors, &styles)?;
+
+    let total = commits.len();
+    eprintln!(
+        "{:>HEADER_WIDTH$} {} {} referenced by Git stubs...",
+        if check { CHECKING } else { "Pinning" }.style(styles.success_header),
+        total.style(styles.bold),
+        plural::commits(total),
+    );
+
+    let mut num_ok = 0;
+    let mut num_unreferenced = 0;
+    let mut num_missing = 0;
+
+    for (commit, paths) in commits.values() {
+        let reachability = env
+            .vcs
+            .commit_reachability(&env.repo_root, *commit)
+            .with_context(|| format!("checking reachability of {commit}"))?;
+        let referenced_by =
+            paths.iter().map(|p| p.as_str()).collect::<Vec<_>>().join(", ");
+
+        match reachability {
+            CommitReachability::Missing => {
+                num_missing += 1;
+                eprintln!(
+                    "{:>HEADER_WIDTH$} {} (missing; referenced by {})",
+                    FAILURE.style(styles.failure_header),
+                    commit.style(styles.bold),
+                    referenced_by.style(styles.filename),
+                );
+            }
+            CommitReachability::Unreferenced if check => {
+                num_unreferenced += 1;
+                eprintln!(
+                    "{:>HEADER_WIDTH$} {} (not reachable from any ref; \
+                     referenced by {})",
+                    STALE.style(styles.warning_header),
+                    commit.style(styles.bold),
+                    referenced_by.style(styles.filename),
+                );
+            }
+            CommitReachability::Reachable if check => {
+                num_ok += 1;
+                eprintln!(
+                    "{:>HEADER_WIDTH$} {}",
+                    FRESH.style(styles.success_header),
+                    commit.style(styles.bold),
+                );
+            }
+            CommitReachability::Unreferenced
+            | CommitReachability::Reachable => {
+                env.vcs.pin_commit(&env.repo_root, *commit)?;
+                num_ok += 1;
+                eprintln!(
+                    "{:>HEADER_WIDTH$} {} as {}{}",
+                    "Pinned".style(styles.success_header),
+                    commit.style(styles.bold),
+                    PIN_REF_PREFIX,
+                    commit,
+                );
+            }
+        }
+    }
+
+    let status_header = if num_missing > 0 {
+        FAILURE.style(styles.failure_header)
+    } else if num_unreferenced > 0 {
+        STALE.style(styles.warning_header)
+    } else {
+        SUCCESS.style(styles.success_header)
+    };
+    eprintln!("{:>HEADER_WIDTH$}", SEPARATOR);
+    eprintln!(
+        "{:>HEADER_WIDTH$} {} {}: {} {}, {} unreferenced, {} missing",
+        status_header,
+        total.style(styles.bold),
+        plural::commits(total),
+        num_ok.style(styles.bold),
+        if check { "reachable" } else { "pinned" },
+        num_unreferenced.style(styles.bold),
+        num_missing.style(styles.bold),
+    );
+
+    if num_missing > 0 {
+        eprintln!(
+            "{:>HEADER_WIDTH$} (missing commits must be fetched from a \
+             remote that has them)",
+            "",
+        );
+        Ok(CheckResult::Failures)
+    } else if num_unreferenced > 0 {
+        eprintln!(
+            "{:>HEADER_WIDTH$} (run {} to pin them)",
+            "",
+            format!("{} pin-stubs", env.command).style(styles.bold),
+        );
+        Ok(CheckResult::NeedsUpdate)
+    } else {
+        Ok(CheckResult::Success)
+    }
+}
+
+/// Reads every Git stub for versioned APIs under `dir`, returning the
+/// referenced commits (keyed by their string form, for a stable order) along
+/// with the stubs that reference them.
+///
+/// Unlike the usual local-file loading, this does not attempt to resolve the
+/// stubs, since the referenced commits may not be available.
+fn collect_git_stub_commits(
+    dir: &Utf8Path,
+    apis: &ManagedApis,
+    errors: &mut ErrorAccumulator,
+) -> BTreeMap<String, (GitCommitHash, Vec<Utf8PathBuf>)> {
+    let mut commits = BTreeMap::new();
+
+    for api in apis.iter_apis().filter(|api| api.is_versioned()) {
+        let api_dir = dir.join(api.ident().as_str());
+        if !api_dir.exists() {
+            continue;
+        }
+
+        let entries = match api_dir.read_dir_utf8() {
+            Ok(entries) => entries,
+            Err(error) => {
+                errors.error(
+                    anyhow!(error).context(format!("readdir {:?}", api_dir)),
+                );
+                continue;
+            }
+        };
+
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    errors.error(
+                        anyhow!(error)
+                            .context(format!("readdir {:?} entry", api_dir)),
+                    );
+                    continue;
+                }
+            };
+            if !entry.file_name().ends_with(".json.gitstub") {
+                continue;
+            }
+
+            let path = entry.path();
+            let git_stub = match fs_err::read_to_string(path)
+                .map_err(anyhow::Error::from)
+                .and_then(|contents| {
+                    contents.parse::<GitStub>().map_err(anyhow::Error::from)
+                }) {
+                Ok(git_stub) => git_stub,
+                Err(error) => {
+                    errors.error(error.context(format!(
+                        "failed to read Git stub {:?}",
+                        path
+                    )));
+                    continue;
+                }
+            };
+
+            let commit = git_stub.commit();
+            let rel_path = path.strip_prefix(dir).unwrap_or(path).to_path_buf();
+            commits
+                .entry(commit.to_string())
+                .or_insert_with(|| (commit, Vec::new()))
+                .1
+                .push(rel_path);
+        }
+    }
+
+    for (_, paths) in commits.values_mut() {
+        paths.sort();
+    }
+
+    commits
+}
@@ -0,0 +1,69 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Lists the consumers pinned to each version of each API.
+//!
+//! Consumers record the versions they use in `pins.toml` files (see
+//! [`ConsumerPins`](crate::ConsumerPins)). This answers questions like "who
+//! still uses v3?" without searching for the files by hand.
+
+use crate::{apis::ManagedApis, environment::ResolvedEnv, pins::ConsumerPin};
+use anyhow::bail;
+use dropshot_api_manager_types::ApiIdent;
+use std::{collections::BTreeMap, io::Write};
+
+/// Writes the consumers pinned to each version of each versioned API (or just
+/// `api`) to `out`, newest version first.
+///
+/// Pinned versions that are no longer supported are listed too.
+pub(crate) fn pins_impl(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    api: Option<&ApiIdent>,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let Some(consumer_pins) = apis.consumer_pins() else {
+        bail!("no consumer pins are configured (see ConsumerPins)");
+    };
+    if let Some(ident) = api {
+        match apis.api(ident) {
+            Some(managed) if managed.is_versioned() => {}
+            Some(_) => bail!("API {} is not versioned", ident),
+            None => bail!("API {} is not managed", ident),
+        }
+    }
+    let pins = consumer_pins.load(&env.repo_root, apis)?;
+
+    for managed in apis.iter_apis().filter(|a| a.is_versioned()) {
+        let ident = managed.ident();
+        if api.is_some_and(|api| api != ident) {
+            continue;
+        }
+        let mut by_version: BTreeMap<&semver::Version, Vec<&ConsumerPin>> =
+            managed.iter_versions_semver().map(|v| (v, Vec::new())).collect();
+        for pin in pins.iter().filter(|pin| &pin.api == ident) {
+            by_version.entry(&pin.version).or_default().push(pin);
+        }
+        let latest = managed.iter_versions_semver().max();
+
+        writeln!(out, "{}", ident)?;
+        for (version, pins) in by_version.iter().rev() {
+            let qualifier = if Some(*version) == latest {
+                " (latest)"
+            } else if !managed.iter_versions_semver().any(|v| v == *version) {
+                " (not supported)"
+            } else {
+                ""
+            };
+            let consumers = if pins.is_empty() {
+                "no pins".to_owned()
+            } else {
+                pins.iter()
+                    .map(|pin| pin.path.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            writeln!(out, "  v{}{}: {}", version, qualifier, consumers)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,91 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Reports the versions of APIs introduced since a release.
+//!
+//! After a check, `check --since-last-release TAG` lists every version of a
+//! versioned API that's supported now but wasn't blessed at TAG, along with its
+//! label. The list is meant as input to release notes.
+
+use crate::{
+    apis::ManagedApis,
+    environment::{BlessedSource, ResolvedEnv},
+    output::{Styles, display_load_problems, headers::*, plural},
+    vcs::VcsRevision,
+};
+use dropshot_api_manager_types::{ApiIdent, SupportedVersion};
+use owo_colors::OwoColorize;
+use std::io::Write;
+
+/// A version of an API that's supported now, but wasn't at a release.
+#[derive(Clone, Debug)]
+pub(crate) struct NewVersion<'a> {
+    pub(crate) ident: &'a ApiIdent,
+    pub(crate) version: &'a SupportedVersion,
+}
+
+/// Returns the versions of versioned APIs that are supported now but weren't
+/// blessed at `tag`, in order of API and version.
+///
+/// The documents at `tag` are loaded the same way blessed documents are, from
+/// the merge base of `HEAD` with `tag`. That's `tag` itself as long as the
+/// release is an ancestor of `HEAD`.
+pub(crate) fn versions_since_release<'a>(
+    apis: &'a ManagedApis,
+    env: &ResolvedEnv,
+    tag: &VcsRevision,
+    styles: &Styles,
+) -> anyhow::Result<Vec<NewVersion<'a>>> {
+    let source = BlessedSource::VcsRevisionMergeBase {
+        revision: tag.clone(),
+        directory: env.openapi_rel_dir().to_owned(),
+    };
+    let (released, errors) = source.load(
+        &env.repo_root,
+        apis,
+        styles,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    display_load_problems(&errors, styles)?;
+
+    let mut new_versions = Vec::new();
+    for api in apis.iter_apis() {
+        let Some(versions) = api.iter_versioned_versions() else {
+            continue;
+        };
+        let released_versions =
+            released.get(api.ident()).map(|files| files.versions());
+        for version in versions {
+            if !released_versions
+                .is_some_and(|released| released.contains_key(version.semver()))
+            {
+                new_versions.push(NewVersion { ident: api.ident(), version });
+            }
+        }
+    }
+    Ok(new_versions)
+}
+
+/// Prints the results of [`versions_since_release`].
+///
+/// A summary goes to stderr, as with other output of `check`. The versions
+/// themselves, one per line, go to `out`, so that they can be collected for
+/// release notes.
+pub(crate) fn display_versions_since_release(
+    tag: &VcsRevision,
+    new_versions: &[NewVersion<'_>],
+    styles: &Styles,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    eprintln!(
+        "{:>HEADER_WIDTH$} {} new {} since {}",
+        "Released".style(styles.success_header),
+        new_versions.len().style(styles.bold),
+        plural::versions(new_versions.len()),
+        tag.style(styles.bold),
+    );
+    for NewVersion { ident, version } in new_versions {
+        writeln!(out, "{} v{} ({})", ident, version.semver(), version.label())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,215 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Produces a reduced OpenAPI document containing a subset of an API's
+//! operations, e.g. for partners that only integrate against a few endpoints.
+
+use crate::{
+    apis::ManagedApis,
+    environment::ResolvedEnv,
+    output::{OutputOpts, display_load_problems},
+    profiles::retain_operations,
+    ref_check::{for_each_ref, from_pointer},
+    spec_files_local::LocalApiSpecFile,
+};
+use anyhow::{Context, anyhow, bail};
+use dropshot_api_manager_types::ApiIdent;
+use openapiv3::OpenAPI;
+use std::collections::BTreeSet;
+
+/// Returns the local document for `version` of the API `ident`, reduced to the
+/// operations carrying any of `keep_tags` and the components they
+/// (transitively) refer to.
+pub(crate) fn slim_impl(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    ident: &ApiIdent,
+    version: &semver::Version,
+    keep_tags: &[String],
+    output: &OutputOpts,
+) -> anyhow::Result<Vec<u8>> {
+    let styles = output.styles(supports_color::Stream::Stderr);
+    if apis.api(ident).is_none() {
+        bail!("unknown API {:?}", ident);
+    }
+
+    let (local_files, errors) = env.local_source.load(
+        apis,
+        &styles,
+        &env.repo_root,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    display_load_problems(&errors, &styles)?;
+
+    let files = local_files
+        .get(ident)
+        .and_then(|api_files| api_files.versions().get(version))
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let specs: Vec<_> = files
+        .iter()
+        .filter_map(|file| match file {
+            LocalApiSpecFile::Valid { spec, .. } => Some(spec),
+            LocalApiSpecFile::Unparseable(_) => None,
+        })
+        .collect();
+    let spec = match specs.as_slice() {
+        [] => bail!("no local document found for {} v{}", ident, version),
+        [spec] => spec,
+        _ => bail!(
+            "found {} local documents for {} v{} (run `{} generate` first)",
+            specs.len(),
+            ident,
+            version,
+            env.command,
+        ),
+    };
+
+    let mut openapi = spec.openapi().clone();
+    retain_operations(&mut openapi, |op| {
+        op.tags.iter().any(|tag| keep_tags.contains(tag))
+    });
+    if openapi.paths.paths.is_empty() {
+        bail!(
+            "no operations in {} v{} have any of the tags: {}",
+            ident,
+            version,
+            keep_tags.join(", "),
+        );
+    }
+    remove_unreferenced_components(&mut openapi)?;
+
+    let used_tags: BTreeSet<_> = openapi
+        .operations()
+        .flat_map(|(_, _, op)| op.tags.iter().cloned())
+        .collect();
+    openapi.tags.retain(|tag| used_tags.contains(&tag.name));
+
+    let mut contents = serde_json::to_vec_pretty(&openapi)
+        .context("serializing reduced document")?;
+    contents.push(b'\n');
+    Ok(contents)
+}
+
+/// Removes components that aren't referenced, directly or transitively, by
+/// any of the document's paths.
+///
+/// Security schemes are referred to by name rather than by `$ref`, so they're
+/// always kept. References to other files, e.g. to shared schemas, can't refer
+/// to this document's components, so they're left alone.
+fn remove_unreferenced_components(openapi: &mut OpenAPI) -> anyhow::Result<()> {
+    let doc = serde_json::to_value(&*openapi)
+        .context("serializing document to find references")?;
+
+    let mut referenced = BTreeSet::new();
+    let mut pending = Vec::new();
+    for_each_ref(&doc["paths"], &mut vec!["paths".to_owned()], &mut |_, r| {
+        pending.push(r)
+    });
+    while let Some(reference) = pending.pop() {
+        let Some(pointer) =
+            reference.strip_prefix('#').filter(|p| p.starts_with('/'))
+        else {
+            continue;
+        };
+        if !referenced.insert(pointer) {
+            continue;
+        }
+        let target = doc
+            .pointer(pointer)
+            .ok_or_else(|| anyhow!("unresolvable reference {:?}", reference))?;
+        for_each_ref(target, &mut from_pointer(pointer), &mut |_, r| {
+            pending.push(r)
+        });
+    }
+
+    // A reference may point inside a component (e.g. to one of a schema's
+    // properties), which keeps the whole component.
+    let referenced_components: BTreeSet<_> = referenced
+        .iter()
+        .filter_map(|pointer| {
+            let mut segments = pointer.split('/').skip(1);
+            match (segments.next(), segments.next(), segments.next()) {
+                (Some("components"), Some(kind), Some(name)) => {
+                    Some((kind, name))
+                }
+                _ => None,
+            }
+        })
+        .collect();
+
+    let Some(components) = openapi.components.as_mut() else {
+        return Ok(());
+    };
+    let is_referenced =
+        |kind: &str, name: &str| referenced_components.contains(&(kind, name));
+    components.schemas.retain(|name, _| is_referenced("schemas", name));
+    components.responses.retain(|name, _| is_referenced("responses", name));
+    components.parameters.retain(|name, _| is_referenced("parameters", name));
+    components.examples.retain(|name, _| is_referenced("examples", name));
+    components
+        .request_bodies
+        .retain(|name, _| is_referenced("requestBodies", name));
+    components.headers.retain(|name, _| is_referenced("headers", name));
+    components.links.retain(|name, _| is_referenced("links", name));
+    components.callbacks.retain(|name, _| is_referenced("callbacks", name));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_remove_unreferenced_components() {
+        let into_name = "#/components/schemas/Name/properties/value";
+        let other_file = "shared.json#/components/schemas/Species";
+        let mut openapi: OpenAPI = serde_json::from_value(json!({
+            "openapi": "3.0.3",
+            "info": { "title": "Trees", "version": "1.0.0" },
+            "paths": {
+                "/trees": {
+                    "get": {
+                        "responses": {
+                            "200": {
+                                "description": "a tree",
+                                "content": {
+                                    "application/json": {
+                                        "schema": {
+                                            "$ref": "#/components/schemas/Tree",
+                                        },
+                                    },
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+            "components": {
+                "schemas": {
+                    "Tree": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "$ref": into_name },
+                            "species": { "$ref": other_file },
+                        },
+                    },
+                    "Name": {
+                        "type": "object",
+                        "properties": { "value": { "type": "string" } },
+                    },
+                    "Unused": { "type": "string" },
+                },
+            },
+        }))
+        .unwrap();
+
+        // References to other files are skipped, and references into a
+        // component keep the whole component.
+        remove_unreferenced_components(&mut openapi).unwrap();
+        let schemas = &openapi.components.as_ref().unwrap().schemas;
+        let names: BTreeSet<_> = schemas.keys().map(String::as_str).collect();
+        assert_eq!(names, BTreeSet::from(["Name", "Tree"]));
+    }
+}
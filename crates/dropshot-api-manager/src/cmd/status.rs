@@ -0,0 +1,344 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Explains the state of each API in prose.
+//!
+//! `check` is problem-centric: it's meant for CI, and lists what's wrong. This
+//! command instead walks through each API version and describes where it
+//! stands in terms of blessed, local, and generated documents.
+
+use crate::{
+    apis::ManagedApis,
+    environment::{BlessedSource, GeneratedSource, ResolvedEnv},
+    merge_guidance::MergeGuidance,
+    output::{InlineErrorChain, OutputOpts, Styles, display_load_problems},
+    resolved::{Note, Problem, Resolution, ResolutionKind, Resolved},
+};
+use owo_colors::OwoColorize;
+use std::io::Write;
+
+pub(crate) fn status_impl(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    blessed_source: &BlessedSource,
+    generated_source: &GeneratedSource,
+    output: &OutputOpts,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    // Loading progress and errors go to stderr, as with other commands. The
+    // narrative goes to `out`.
+    let load_styles = output.styles(supports_color::Stream::Stderr);
+    let (mut generated, errors) =
+        generated_source.load(apis, &load_styles, env)?;
+    display_load_problems(&errors, &load_styles)?;
+
+    let (local_files, errors) = env.local_source.load(
+        apis,
+        &load_styles,
+        &env.repo_root,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    display_load_problems(&errors, &load_styles)?;
+    generated.apply_lockstep_version_policies(apis, &local_files)?;
+
+    let (blessed, errors) = blessed_source.load(
+        &env.repo_root,
+        apis,
+        &load_styles,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    display_load_problems(&errors, &load_styles)?;
+
+    let resolved = Resolved::new(env, apis, &blessed, &generated, &local_files);
+    let styles = output.styles(supports_color::Stream::Stdout);
+    write_status(env, apis, &resolved, &styles, out)?;
+    Ok(())
+}
+
+fn write_status(
+    env: &ResolvedEnv,
+    apis: &ManagedApis,
+    resolved: &Resolved<'_>,
+    styles: &Styles,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let generate = format!("{} generate", env.command);
+    let mut nfixable = 0;
+    let mut nunfixable = 0;
+
+    for (ix, api) in apis.iter_apis().enumerate() {
+        if ix > 0 {
+            writeln!(out)?;
+        }
+        let ident = api.ident();
+        writeln!(
+            out,
+            "{} ({})",
+            ident.style(styles.bold),
+            if api.is_versioned() { "versioned" } else { "lockstep" },
+        )?;
+
+        let mut statuses = Vec::new();
+        for version in api.iter_versions_semver() {
+            let resolution = resolved
+                .resolution_for_api_version(ident, version)
+                .expect("resolution for all supported API versions");
+            let label = if api.is_versioned() {
+                format!("v{version}")
+            } else {
+                String::from("document")
+            };
+            statuses.push((
+                label,
+                describe_version(resolution, &generate),
+                resolution.problems().collect::<Vec<_>>(),
+            ));
+        }
+
+        for note in resolved.notes() {
+            match note {
+                Note::BlessedVersionRemoved { api_ident, version }
+                    if api_ident == ident =>
+                {
+                    statuses.push((
+                        format!("v{version}"),
+                        String::from(
+                            "was blessed, but has been removed; clients that \
+                             still use this version will no longer be \
+                             supported",
+                        ),
+                        Vec::new(),
+                    ));
+                }
+                Note::BlessedVersionRemoved { .. } => {}
+                Note::UnstablePathOperationsExcluded {
+                    api_ident,
+                    operations,
+                    ..
+                } if api_ident == ident => {
+                    statuses.push((
+                        String::from("unstable paths"),
+                        format!(
+                            "excluded from comparisons with blessed \
+                             documents: {operations}"
+                        ),
+                        Vec::new(),
+                    ));
+                }
+                Note::UnstablePathOperationsExcluded { .. } => {}
+                Note::DocumentSizeGrowth {
+                    api_ident,
+                    version,
+                    blessed_version,
+                    generated_size,
+                    blessed_size,
+                    growth_percent,
+                    ..
+                } if api_ident == ident => {
+                    statuses.push((
+                        format!("v{version}"),
+                        format!(
+                            "is {growth_percent}% larger than blessed version \
+                             {blessed_version} ({generated_size} bytes, up \
+                             from {blessed_size}); check for a schema that \
+                             grew by accident"
+                        ),
+                        Vec::new(),
+                    ));
+                }
+                Note::DocumentSizeGrowth { .. } => {}
+                Note::ConsumerPinLagging {
+                    api_ident,
+                    version,
+                    path,
+                    latest,
+                    behind,
+                    ..
+                } if api_ident == ident => {
+                    statuses.push((
+                        format!("v{version}"),
+                        format!(
+                            "is pinned by {path}, {behind} versions behind \
+                             latest version {latest}"
+                        ),
+                        Vec::new(),
+                    ));
+                }
+                Note::ConsumerPinLagging { .. } => {}
+            }
+        }
+
+        if api.is_versioned() {
+            match resolved.symlink_problem(ident) {
+                None => {}
+                Some(problem) => statuses.push((
+                    String::from("\"latest\" symlink"),
+                    describe_problems(&[problem], &generate),
+                    vec![problem],
+                )),
+            }
+        }
+
+        for alias in api.aliases() {
+            let problems: Vec<_> = resolved
+                .alias_problems(ident)
+                .filter(|p| p.alias() == Some(alias))
+                .collect();
+            let description = if problems.is_empty() {
+                String::from("the copy of the documents is up-to-date")
+            } else {
+                describe_problems(&problems, &generate)
+            };
+            statuses.push((format!("alias {alias}"), description, problems));
+        }
+
+        if api.is_versioned() && api.has_combined_document() {
+            match resolved.combined_document_problem(ident) {
+                None => statuses.push((
+                    String::from("combined document"),
+                    String::from("the document is up-to-date"),
+                    Vec::new(),
+                )),
+                Some(problem) => statuses.push((
+                    String::from("combined document"),
+                    describe_problems(&[problem], &generate),
+                    vec![problem],
+                )),
+            }
+        }
+
+        let shared_schemas_problem = resolved.shared_schemas_problem(ident);
+        match shared_schemas_problem {
+            None if api.has_shared_schemas() => statuses.push((
+                String::from("shared schemas"),
+                String::from("the documents and shared schemas are up-to-date"),
+                Vec::new(),
+            )),
+            None => {}
+            Some(problem) => statuses.push((
+                String::from("shared schemas"),
+                describe_problems(&[problem], &generate),
+                vec![problem],
+            )),
+        }
+
+        match resolved.json_format_problem(ident) {
+            None if api.json_format().is_some() => statuses.push((
+                String::from("JSON format"),
+                String::from("the documents are formatted as configured"),
+                Vec::new(),
+            )),
+            None => {}
+            Some(problem) => statuses.push((
+                String::from("JSON format"),
+                describe_problems(&[problem], &generate),
+                vec![problem],
+            )),
+        }
+
+        for (label, description, problems) in statuses {
+            writeln!(
+                out,
+                "  {}: {}",
+                label.style(styles.filename),
+                styles.text(&description),
+            )?;
+            for problem in problems {
+                if problem.is_fixable() {
+                    nfixable += 1;
+                } else {
+                    nunfixable += 1;
+                }
+                writeln!(
+                    out,
+                    "    - {}",
+                    styles.text(&InlineErrorChain::new(problem).to_string()),
+                )?;
+            }
+        }
+    }
+
+    let general: Vec<_> = resolved.general_problems().collect();
+    if !general.is_empty() {
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}: {}",
+            "Other problems".style(styles.bold),
+            describe_problems(&general, &generate),
+        )?;
+        for problem in general {
+            if problem.is_fixable() {
+                nfixable += 1;
+            } else {
+                nunfixable += 1;
+            }
+            writeln!(
+                out,
+                "  - {}",
+                styles.text(&InlineErrorChain::new(problem).to_string()),
+            )?;
+        }
+    }
+
+    if let Some(guidance) = MergeGuidance::new(env, apis, resolved) {
+        writeln!(out)?;
+        write!(out, "{}", guidance)?;
+    }
+
+    writeln!(out)?;
+    let summary = match (nfixable, nunfixable) {
+        (0, 0) => String::from("Everything is up-to-date."),
+        (_, 0) => format!("Run `{generate}` to bring everything up-to-date."),
+        (0, _) => String::from(
+            "Some problems can't be fixed automatically; see above for \
+             details.",
+        ),
+        (_, _) => format!(
+            "Some problems can't be fixed automatically; see above for \
+             details. Run `{generate}` to fix the rest."
+        ),
+    };
+    writeln!(out, "{}", summary.style(styles.bold))?;
+    Ok(())
+}
+
+/// Describes a single version of an API (or a lockstep API's only document).
+fn describe_version(resolution: &Resolution<'_>, generate: &str) -> String {
+    let problems: Vec<_> = resolution.problems().collect();
+    match resolution.kind() {
+        ResolutionKind::Lockstep if problems.is_empty() => {
+            String::from("the local document matches the API definition")
+        }
+        ResolutionKind::Lockstep => describe_problems(&problems, generate),
+        ResolutionKind::Blessed if problems.is_empty() => {
+            String::from("is blessed and unchanged")
+        }
+        ResolutionKind::Blessed => {
+            format!(
+                "is blessed, but {}",
+                describe_problems(&problems, generate)
+            )
+        }
+        ResolutionKind::NewLocally if problems.is_empty() => String::from(
+            "is new locally, and will become blessed once this change is \
+             merged upstream",
+        ),
+        ResolutionKind::NewLocally => {
+            format!(
+                "is new locally, but {}",
+                describe_problems(&problems, generate)
+            )
+        }
+    }
+}
+
+/// Describes what needs to happen to fix a set of problems, as a clause.
+fn describe_problems(problems: &[&Problem<'_>], generate: &str) -> String {
+    if problems.iter().all(|p| p.is_fixable()) {
+        format!("the local files are out of date (`{generate}` will fix this)")
+    } else {
+        String::from("there are problems that need to be fixed by hand")
+    }
+}
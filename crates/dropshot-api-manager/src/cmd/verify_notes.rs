@@ -0,0 +1,118 @@
+// Copyright 2026 Oxide Computer Company
+
+use crate::{
+    apis::ManagedApis,
+    check_notes::CheckNote,
+    environment::ResolvedEnv,
+    output::{
+        CheckResult, OutputOpts, Styles, display_load_problems, headers::*,
+        plural,
+    },
+    vcs::{CHECK_NOTES_REF, VcsRevision},
+};
+use anyhow::{Context, bail};
+use owo_colors::OwoColorize;
+
+/// Records the local documents in a note on `HEAD`, after a successful check.
+///
+/// Fails if any file in the documents directory has uncommitted changes, since
+/// the note would then describe documents that aren't in the commit.
+pub(crate) fn write_check_note_impl(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    output: &OutputOpts,
+) -> anyhow::Result<()> {
+    let styles = output.styles(supports_color::Stream::Stderr);
+    let uncommitted =
+        env.vcs.uncommitted_files(&env.repo_root, env.openapi_rel_dir())?;
+    if let Some(path) = uncommitted.first() {
+        bail!(
+            "not writing check note: {} has uncommitted changes (commit all \
+             documents before running with --write-note)",
+            path,
+        );
+    }
+
+    let commit = env.vcs.head_commit(&env.repo_root)?;
+    let note = current_note(apis, env, &styles)?;
+    env.vcs.add_check_note(&env.repo_root, commit, &note.to_contents())?;
+    eprintln!(
+        "{:>HEADER_WIDTH$} check note for {} {} on {} (under {})",
+        "Recorded".style(styles.success_header),
+        note.len().style(styles.bold),
+        plural::documents(note.len()),
+        commit.style(styles.bold),
+        CHECK_NOTES_REF,
+    );
+    Ok(())
+}
+
+/// Verifies that the local documents match the note recorded on `revision`.
+pub(crate) fn verify_notes_impl(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    revision: &VcsRevision,
+    output: &OutputOpts,
+) -> anyhow::Result<CheckResult> {
+    let styles = output.styles(supports_color::Stream::Stderr);
+    let (commit, contents) = env.vcs.check_note(&env.repo_root, revision)?;
+    let Some(contents) = contents else {
+        eprintln!(
+            "{:>HEADER_WIDTH$} {} has no check note under {}",
+            FAILURE.style(styles.failure_header),
+            commit.style(styles.bold),
+            CHECK_NOTES_REF,
+        );
+        return Ok(CheckResult::Failures);
+    };
+    let recorded = CheckNote::parse(&contents)
+        .with_context(|| format!("invalid check note on {}", commit))?;
+
+    let current = current_note(apis, env, &styles)?;
+    let differences = recorded.differences(&current);
+    for difference in &differences {
+        eprintln!(
+            "{:>HEADER_WIDTH$} {}",
+            FAILURE.style(styles.failure_header),
+            difference,
+        );
+    }
+
+    eprintln!("{:>HEADER_WIDTH$}", SEPARATOR);
+    if differences.is_empty() {
+        eprintln!(
+            "{:>HEADER_WIDTH$} {} {} match the check note on {}",
+            SUCCESS.style(styles.success_header),
+            current.len().style(styles.bold),
+            plural::documents(current.len()),
+            commit.style(styles.bold),
+        );
+        Ok(CheckResult::Success)
+    } else {
+        eprintln!(
+            "{:>HEADER_WIDTH$} {} mismatched {} against the check note on {}",
+            FAILURE.style(styles.failure_header),
+            differences.len().style(styles.bold),
+            plural::paths(differences.len()),
+            commit.style(styles.bold),
+        );
+        Ok(CheckResult::Failures)
+    }
+}
+
+/// Loads the local documents, and returns a note describing them.
+fn current_note(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    styles: &Styles,
+) -> anyhow::Result<CheckNote> {
+    let (local_files, errors) = env.local_source.load(
+        apis,
+        styles,
+        &env.repo_root,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    display_load_problems(&errors, styles)?;
+    Ok(CheckNote::from_local_files(env, apis, &local_files))
+}
@@ -0,0 +1,393 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Rerunning `check` whenever API definitions or documents change.
+//!
+//! API definitions are compiled into the binary running this command, so a
+//! check run in this process would never see edits to them. Instead, each
+//! check runs the OpenAPI manager's command (as passed to
+//! [`Environment::new`]) as a subprocess, which rebuilds it first if it's run
+//! via Cargo.
+//!
+//! [`Environment::new`]: crate::Environment::new
+
+use crate::{
+    FAILURE_EXIT_CODE, NEEDS_UPDATE_EXIT_CODE,
+    environment::ResolvedEnv,
+    lock::LOCK_FILE_NAME,
+    output::{OutputOpts, Styles, headers::*},
+};
+use anyhow::{Context, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use notify::{EventKind, RecursiveMode, Watcher};
+use owo_colors::OwoColorize;
+use std::{
+    collections::BTreeSet,
+    io::Write,
+    path::{Component, Path},
+    process::{Command, Output, Stdio},
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+/// How long to wait for further changes once one is seen, so that saving
+/// several files at once (or a formatter rewriting them) results in a single
+/// check.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// What to watch, and how to run each check.
+#[derive(Debug)]
+pub(crate) struct WatchConfig {
+    /// Paths to watch besides the documents directory, relative to the
+    /// repository root.
+    pub(crate) paths: Vec<Utf8PathBuf>,
+    /// Absolute paths to files each check writes, e.g. its report, changes to
+    /// which are ignored.
+    pub(crate) outputs: Vec<Utf8PathBuf>,
+    /// Whether to pass the files changed since the last check to the next one
+    /// with `--changed-files`, so that only the APIs they affect are checked.
+    pub(crate) pass_changed_files: bool,
+}
+
+/// Watches the documents directory and the paths in `config`, and runs
+/// `check` with `check_args` once at startup and after every change.
+///
+/// This only returns if watching fails.
+pub(crate) fn watch_impl(
+    env: &ResolvedEnv,
+    config: &WatchConfig,
+    check_args: &[String],
+    output: &OutputOpts,
+) -> anyhow::Result<()> {
+    let styles = output.styles(supports_color::Stream::Stderr);
+    let mut check = CheckCommand::new(env, check_args, output)?;
+    let filter =
+        ChangeFilter { repo_root: &env.repo_root, outputs: &config.outputs };
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)
+        .context("failed to start watching for changes")?;
+    // The documents directory may not exist yet, e.g. before the first
+    // documents are generated.
+    fs_err::create_dir_all(env.openapi_abs_dir())?;
+    let watched = std::iter::once(env.openapi_abs_dir().to_owned())
+        .chain(config.paths.iter().map(|path| env.repo_root.join(path)));
+    for path in watched {
+        watcher
+            .watch(path.as_std_path(), RecursiveMode::Recursive)
+            .with_context(|| format!("failed to watch {}", path))?;
+        eprintln!(
+            "{:>HEADER_WIDTH$} {}",
+            "Watching".style(styles.success_header),
+            path,
+        );
+    }
+
+    // The first check is of everything.
+    let mut changed = None;
+    loop {
+        check.run(changed.as_ref(), &styles)?;
+
+        // Wait for a relevant change, then for things to settle down.
+        let mut changes = Some(BTreeSet::new());
+        loop {
+            let event = rx.recv().context("file watcher stopped")??;
+            if filter.record(&event, &mut changes) {
+                break;
+            }
+        }
+        let deadline = Instant::now() + DEBOUNCE;
+        while let Some(timeout) =
+            deadline.checked_duration_since(Instant::now())
+        {
+            match rx.recv_timeout(timeout) {
+                Ok(event) => {
+                    filter.record(&event?, &mut changes);
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    bail!("file watcher stopped")
+                }
+            }
+        }
+        changed = if config.pass_changed_files { changes } else { None };
+    }
+}
+
+/// Decides which changes should trigger a check.
+struct ChangeFilter<'a> {
+    repo_root: &'a Utf8Path,
+    outputs: &'a [Utf8PathBuf],
+}
+
+impl ChangeFilter<'_> {
+    /// Returns true if `event` should trigger a check, and adds the paths it
+    /// changed to `changes`, relative to the repository root.
+    ///
+    /// If the watcher may have missed changes, or a path isn't within the
+    /// repository, `changes` is set to `None`: there's no telling which APIs
+    /// are affected.
+    fn record(
+        &self,
+        event: &notify::Event,
+        changes: &mut Option<BTreeSet<Utf8PathBuf>>,
+    ) -> bool {
+        if event.need_rescan() {
+            *changes = None;
+            return true;
+        }
+        if matches!(event.kind, EventKind::Access(_)) {
+            return false;
+        }
+        let mut relevant = false;
+        for path in &event.paths {
+            if !self.is_relevant_path(path) {
+                continue;
+            }
+            relevant = true;
+            let relative = Utf8Path::from_path(path)
+                .and_then(|path| path.strip_prefix(self.repo_root).ok());
+            match relative {
+                Some(relative) => {
+                    if let Some(changes) = changes.as_mut() {
+                        changes.insert(relative.to_owned());
+                    }
+                }
+                None => *changes = None,
+            }
+        }
+        relevant
+    }
+
+    /// Returns true if a change to `path` should trigger a check.
+    ///
+    /// Build outputs and VCS metadata change as a side effect of running
+    /// checks, so changes to them are ignored, as are changes to the files
+    /// each check writes and to the lock file `generate` holds while updating
+    /// documents. Files are written by way of a temporary file alongside
+    /// them, which is ignored too.
+    fn is_relevant_path(&self, path: &Path) -> bool {
+        let ignored = path.components().any(|component| {
+            matches!(
+                component,
+                Component::Normal(name)
+                    if name == "target" || name == ".git" || name == ".jj"
+                        || name.to_string_lossy().starts_with(".atomicwrite")
+            )
+        });
+        !ignored
+            && path.file_name().is_none_or(|name| name != LOCK_FILE_NAME)
+            && !self.outputs.iter().any(|output| path == output.as_std_path())
+    }
+}
+
+/// The `check` command run on each change, along with the output of the last
+/// run.
+struct CheckCommand<'a> {
+    env: &'a ResolvedEnv,
+    program: &'a str,
+    args: Vec<String>,
+    last_stderr: Option<Vec<u8>>,
+}
+
+impl<'a> CheckCommand<'a> {
+    fn new(
+        env: &'a ResolvedEnv,
+        check_args: &[String],
+        output: &OutputOpts,
+    ) -> anyhow::Result<Self> {
+        // The command is meant to be shown to people, e.g. "cargo openapi",
+        // so split it on whitespace rather than trying to parse it as a
+        // shell would.
+        let mut words = env.command.split_whitespace();
+        let Some(program) = words.next() else {
+            bail!("the command to run the OpenAPI manager is empty");
+        };
+        let mut args: Vec<String> = words.map(str::to_owned).collect();
+        // The output of each check is captured, so pick colors based on where
+        // it's eventually printed.
+        let color = if output.use_color(supports_color::Stream::Stderr) {
+            "always"
+        } else {
+            "never"
+        };
+        args.extend([
+            "--color".to_owned(),
+            color.to_owned(),
+            "--max-problems".to_owned(),
+            output.max_problems.to_string(),
+        ]);
+        if output.ascii {
+            args.push("--ascii".to_owned());
+        }
+        args.push("check".to_owned());
+        args.extend(check_args.iter().cloned());
+        Ok(Self { env, program, args, last_stderr: None })
+    }
+
+    /// Runs the check, and prints a line summarizing the result.
+    ///
+    /// If `changed` is provided, only the APIs affected by those paths
+    /// (relative to the repository root) are checked.
+    ///
+    /// The full output is only printed if the check didn't succeed, and
+    /// differs from that of the last run.
+    fn run(
+        &mut self,
+        changed: Option<&BTreeSet<Utf8PathBuf>>,
+        styles: &Styles,
+    ) -> anyhow::Result<()> {
+        eprintln!(
+            "{:>HEADER_WIDTH$} {} check",
+            "Running".style(styles.success_header),
+            self.env.command,
+        );
+        let start = Instant::now();
+        let mut command = Command::new(self.program);
+        command
+            .args(&self.args)
+            .current_dir(&self.env.repo_root)
+            // Only standard error is summarized. Standard output is reserved
+            // for requested listings, e.g. with `--since-last-release`.
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::piped());
+        if changed.is_some() {
+            command.args(["--changed-files", "-"]).stdin(Stdio::piped());
+        } else {
+            command.stdin(Stdio::null());
+        }
+        let mut child = command
+            .spawn()
+            .with_context(|| format!("failed to run {}", self.program))?;
+        if let Some(changed) = changed {
+            // Dropping standard input once the list is written closes it.
+            let mut stdin = child.stdin.take().expect("stdin is piped");
+            for path in changed {
+                writeln!(stdin, "{}", path)
+                    .context("failed to pass changed files to check")?;
+            }
+        }
+        let Output { status, stderr, .. } = child
+            .wait_with_output()
+            .with_context(|| format!("failed to run {}", self.program))?;
+        let elapsed = start.elapsed();
+
+        let (header, summary) = match status.code() {
+            Some(0) => (
+                SUCCESS.style(styles.success_header),
+                "documents are up-to-date".to_owned(),
+            ),
+            Some(code) if code == i32::from(NEEDS_UPDATE_EXIT_CODE) => (
+                STALE.style(styles.warning_header),
+                format!(
+                    "documents need to be updated: run {} generate",
+                    self.env.command
+                ),
+            ),
+            Some(code) if code == i32::from(FAILURE_EXIT_CODE) => (
+                FAILURE.style(styles.failure_header),
+                "found problems".to_owned(),
+            ),
+            // Most likely, the API crates failed to build.
+            _ => (
+                FAILURE.style(styles.failure_header),
+                format!("check failed to run ({})", status),
+            ),
+        };
+        if !status.success() {
+            if self.last_stderr.as_ref() == Some(&stderr) {
+                eprintln!(
+                    "{:>HEADER_WIDTH$} output is the same as the last run",
+                    "Unchanged".style(styles.unchanged_header),
+                );
+            } else {
+                eprint!("{}", String::from_utf8_lossy(&stderr));
+            }
+        }
+        eprintln!("{:>HEADER_WIDTH$} {} ({:.1?})", header, summary, elapsed);
+        self.last_stderr = Some(stderr);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_relevant_path() {
+        let outputs = [
+            Utf8PathBuf::from("/repo/report.html"),
+            Utf8PathBuf::from("/repo/timings.json"),
+        ];
+        let filter = ChangeFilter {
+            repo_root: Utf8Path::new("/repo"),
+            outputs: &outputs,
+        };
+        assert!(filter.is_relevant_path(Path::new("/repo/apis/src/lib.rs")));
+        assert!(
+            filter.is_relevant_path(Path::new(
+                "/repo/openapi/api/api-1.0.0.json"
+            ))
+        );
+        assert!(
+            !filter.is_relevant_path(Path::new("/repo/target/debug/openapi"))
+        );
+        assert!(!filter.is_relevant_path(Path::new("/repo/.git/index.lock")));
+        assert!(!filter.is_relevant_path(
+            &Path::new("/repo/openapi").join(LOCK_FILE_NAME)
+        ));
+        assert!(!filter.is_relevant_path(Path::new("/repo/report.html")));
+        assert!(!filter.is_relevant_path(Path::new("/repo/timings.json")));
+        assert!(!filter.is_relevant_path(Path::new(
+            "/repo/.atomicwriteAbC123/timings.json"
+        )));
+        // Only the outputs themselves are ignored.
+        assert!(filter.is_relevant_path(Path::new("/repo/report.json")));
+    }
+
+    #[test]
+    fn test_record_changes() {
+        let filter =
+            ChangeFilter { repo_root: Utf8Path::new("/repo"), outputs: &[] };
+        let event = |kind, paths: &[&str]| {
+            paths.iter().fold(notify::Event::new(kind), |event, path| {
+                event.add_path(path.into())
+            })
+        };
+        let modify = EventKind::Modify(notify::event::ModifyKind::Any);
+
+        let mut changes = Some(BTreeSet::new());
+        assert!(filter.record(
+            &event(modify, &["/repo/apis/src/lib.rs", "/repo/target/x"]),
+            &mut changes,
+        ));
+        assert!(
+            !filter.record(&event(modify, &["/repo/target/y"]), &mut changes)
+        );
+        assert!(!filter.record(
+            &event(
+                EventKind::Access(notify::event::AccessKind::Any),
+                &["/repo/openapi/api.json"],
+            ),
+            &mut changes,
+        ));
+        assert_eq!(
+            changes,
+            Some(BTreeSet::from([Utf8PathBuf::from("apis/src/lib.rs")]))
+        );
+
+        // A change outside the repository can't be attributed to any API.
+        assert!(
+            filter
+                .record(&event(modify, &["/elsewhere/api.json"]), &mut changes)
+        );
+        assert_eq!(changes, None);
+
+        // Neither can changes the watcher may have missed.
+        let mut changes = Some(BTreeSet::new());
+        let rescan =
+            event(EventKind::Other, &[]).set_flag(notify::event::Flag::Rescan);
+        assert!(filter.record(&rescan, &mut changes));
+        assert_eq!(changes, None);
+    }
+}
@@ -0,0 +1,173 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Explains how blessedness was decided for one version of an API.
+//!
+//! Whether a version is blessed depends on which revision blessed documents
+//! are loaded from (and why), the merge base of that revision with the working
+//! copy, and whether the blessed document is stored as a Git stub. This
+//! command prints all of that for a single version.
+
+use crate::{
+    apis::ManagedApis,
+    environment::{BlessedSource, ResolvedEnv},
+    output::{InlineErrorChain, OutputOpts, display_load_problems},
+    spec_files_blessed::BlessedGitStub,
+};
+use anyhow::bail;
+use dropshot_api_manager_types::ApiIdent;
+use std::io::Write;
+
+/// Writes an explanation of whether `version` of the API `ident` is blessed
+/// to `out`.
+///
+/// `reason` describes why `blessed_source` was chosen, e.g. which option or
+/// environment variable selected it.
+#[expect(clippy::too_many_arguments)]
+pub(crate) fn why_blessed_impl(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    blessed_source: &BlessedSource,
+    reason: &str,
+    ident: &ApiIdent,
+    version: &semver::Version,
+    output: &OutputOpts,
+    out: &mut dyn Write,
+) -> anyhow::Result<()> {
+    let Some(api) = apis.api(ident) else {
+        bail!("unknown API {:?}", ident);
+    };
+    if !api.is_versioned() {
+        bail!("{} is a lockstep API, so it has no blessed versions", ident);
+    }
+
+    let styles = output.styles(supports_color::Stream::Stderr);
+    let (blessed, errors) = blessed_source.load(
+        &env.repo_root,
+        apis,
+        &styles,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    display_load_problems(&errors, &styles)?;
+
+    writeln!(out, "{} v{}", ident, version)?;
+    if !api.iter_versions_semver().any(|v| v == version) {
+        writeln!(out, "  note: v{} is not a supported version", version)?;
+    }
+
+    writeln!(out, "  blessed source: {}", describe_source(blessed_source))?;
+    writeln!(out, "  chosen because: {}", reason)?;
+    if let Some(provenance) = blessed.provenance() {
+        writeln!(out, "  loaded from: {}", provenance)?;
+    }
+
+    let Some(file) =
+        blessed.get(ident).and_then(|files| files.versions().get(version))
+    else {
+        let verdict = match blessed_source {
+            BlessedSource::None | BlessedSource::Disabled => {
+                "not blessed (no blessed documents are loaded)".to_owned()
+            }
+            _ => format!(
+                "not blessed (v{} doesn't exist in the blessed documents, \
+                 so it's treated as added locally)",
+                version
+            ),
+        };
+        writeln!(out, "  verdict: {}", verdict)?;
+        let others: Vec<_> = blessed
+            .get(ident)
+            .into_iter()
+            .flat_map(|files| files.versions().keys())
+            .map(|v| format!("v{}", v))
+            .collect();
+        if !others.is_empty() {
+            writeln!(out, "  blessed versions: {}", others.join(", "))?;
+        }
+        return Ok(());
+    };
+
+    writeln!(out, "  verdict: blessed")?;
+    writeln!(out, "  blessed document: {}", file.versioned_spec_file_name())?;
+    match blessed.git_stub(ident, version) {
+        Some(BlessedGitStub::Known { commit, path }) => {
+            writeln!(
+                out,
+                "  stored as: a Git stub, referring to {} at commit {}",
+                path, commit,
+            )?;
+        }
+        Some(BlessedGitStub::Lazy { .. }) => {
+            writeln!(out, "  stored as: JSON")?;
+        }
+        None => match blessed_source {
+            BlessedSource::WithStackBase { stack_base, .. } => {
+                writeln!(
+                    out,
+                    "  stored as: JSON, blessed on the stack base {} (not yet \
+                     merged)",
+                    stack_base,
+                )?;
+            }
+            BlessedSource::Baseline { .. } => {
+                writeln!(out, "  stored as: JSON, in the baseline")?
+            }
+            _ => writeln!(out, "  stored as: JSON (not loaded from VCS)")?,
+        },
+    }
+
+    if let Some(git_stub) = blessed.git_stub(ident, version) {
+        match git_stub.to_git_stub(
+            &env.repo_root,
+            blessed.merge_base(),
+            &env.vcs,
+        ) {
+            Ok(git_stub) => writeln!(
+                out,
+                "  first committed upstream: commit {}, path {}",
+                git_stub.commit(),
+                git_stub.path(),
+            )?,
+            Err(error) => writeln!(
+                out,
+                "  first committed upstream: unknown ({})",
+                InlineErrorChain::new(error.as_ref()),
+            )?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Describes where blessed documents are loaded from.
+fn describe_source(blessed_source: &BlessedSource) -> String {
+    match blessed_source {
+        BlessedSource::VcsRevisionMergeBase { revision, directory } => {
+            format!(
+                "the merge base of the working copy and {}, path {:?}",
+                revision, directory
+            )
+        }
+        BlessedSource::Directory { local_directory } => {
+            format!("the local directory {:?}", local_directory)
+        }
+        BlessedSource::Baseline { path } => {
+            format!("the baseline {:?}", path)
+        }
+        BlessedSource::None => {
+            "none (the API manager runs without version control)".to_owned()
+        }
+        BlessedSource::Disabled => {
+            "none (blessed documents are disabled)".to_owned()
+        }
+        BlessedSource::WithStackBase { main, stack_base, directory } => {
+            format!(
+                "{}, plus versions from the merge base of the working copy \
+                 and the stack base {}, path {:?}",
+                describe_source(main),
+                stack_base,
+                directory
+            )
+        }
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Documents combining all supported versions of an API.
+//!
+//! See [`ManagedApi::with_combined_document`] for more.
+//!
+//! [`ManagedApi::with_combined_document`]: crate::ManagedApi::with_combined_document
+
+use crate::compatibility::PATH_ITEM_METHODS;
+use anyhow::{Context, bail};
+use serde_json::{Map, Value};
+
+/// The extension recording which version each operation belongs to.
+const VERSION_EXTENSION: &str = "x-version";
+
+/// Returns the document combining the operations of each of `versions`, which
+/// must be in ascending order.
+///
+/// The document's metadata (info, servers, security requirements and so on)
+/// is taken from the last version.
+pub(crate) fn combined_document<'a>(
+    versions: impl IntoIterator<Item = (&'a semver::Version, &'a Value)>,
+) -> anyhow::Result<Vec<u8>> {
+    let mut combined: Option<Map<String, Value>> = None;
+    let mut paths = Map::new();
+    let mut components: Map<String, Value> = Map::new();
+    let mut tags: Vec<Value> = Vec::new();
+
+    for (version, document) in versions {
+        let mut document = document.clone();
+        let name_prefix =
+            format!("v{}_", version.to_string().replace(['.', '-', '+'], "_"));
+        rename_refs(&mut document, &name_prefix);
+        let Value::Object(mut document) = document else {
+            bail!("document for v{} is not an object", version);
+        };
+
+        if let Some(Value::Object(version_paths)) = document.remove("paths") {
+            for (path, mut item) in version_paths {
+                if let Value::Object(item) = &mut item {
+                    for method in PATH_ITEM_METHODS {
+                        if let Some(Value::Object(operation)) =
+                            item.get_mut(*method)
+                        {
+                            tag_operation(operation, version, &name_prefix);
+                        }
+                    }
+                }
+                let combined_path = format!("/v{}{}", version, path);
+                if paths.insert(combined_path.clone(), item).is_some() {
+                    bail!("duplicate path {:?}", combined_path);
+                }
+            }
+        }
+
+        if let Some(Value::Object(version_components)) =
+            document.remove("components")
+        {
+            for (kind, entries) in version_components {
+                let Value::Object(entries) = entries else {
+                    continue;
+                };
+                let combined_entries = components
+                    .entry(kind.clone())
+                    .or_insert_with(|| Value::Object(Map::new()))
+                    .as_object_mut()
+                    .context("components are not an object")?;
+                for (name, entry) in entries {
+                    // Security schemes are referred to by name rather than by
+                    // `$ref`, so they're shared between versions.
+                    let name = if kind == "securitySchemes" {
+                        name
+                    } else {
+                        format!("{}{}", name_prefix, name)
+                    };
+                    combined_entries.insert(name, entry);
+                }
+            }
+        }
+
+        if let Some(Value::Array(version_tags)) = document.remove("tags") {
+            for tag in version_tags {
+                let existing =
+                    tags.iter_mut().find(|t| t["name"] == tag["name"]);
+                match existing {
+                    Some(existing) => *existing = tag,
+                    None => tags.push(tag),
+                }
+            }
+        }
+
+        combined = Some(document);
+    }
+
+    let Some(mut combined) = combined else {
+        bail!("no versions to combine");
+    };
+    combined.insert("paths".to_owned(), Value::Object(paths));
+    if !components.is_empty() {
+        combined.insert("components".to_owned(), Value::Object(components));
+    }
+    if !tags.is_empty() {
+        combined.insert("tags".to_owned(), Value::Array(tags));
+    }
+
+    let mut contents = serde_json::to_vec_pretty(&combined)
+        .context("serializing combined document")?;
+    contents.push(b'\n');
+    Ok(contents)
+}
+
+/// Prefixes an operation's ID with `name_prefix`, and records its version.
+fn tag_operation(
+    operation: &mut Map<String, Value>,
+    version: &semver::Version,
+    name_prefix: &str,
+) {
+    if let Some(Value::String(operation_id)) = operation.get_mut("operationId")
+    {
+        *operation_id = format!("{}{}", name_prefix, operation_id);
+    }
+    operation.insert(
+        VERSION_EXTENSION.to_owned(),
+        Value::String(version.to_string()),
+    );
+}
+
+/// Prefixes the component name in every `$ref` within `value` with
+/// `name_prefix`.
+fn rename_refs(value: &mut Value, name_prefix: &str) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::String(reference)) = map.get_mut("$ref")
+                && let Some(rest) = reference.strip_prefix("#/components/")
+                && let Some((kind, name)) = rest.split_once('/')
+            {
+                *reference =
+                    format!("#/components/{}/{}{}", kind, name_prefix, name);
+            }
+            for value in map.values_mut() {
+                rename_refs(value, name_prefix);
+            }
+        }
+        Value::Array(values) => {
+            for value in values {
+                rename_refs(value, name_prefix);
+            }
+        }
+        _ => {}
+    }
+}
@@ -2,8 +2,14 @@
 
 //! Determine if one OpenAPI document is a subset of another
 
+use crate::approvals::ISSUE_HASH_LEN;
 use drift::{Change, ChangeClass};
-use std::{collections::BTreeMap, fmt};
+use openapiv3::OpenAPI;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
 
 /// A compatibility error between two OpenAPI documents, indexed by the blessed
 /// and generated paths.
@@ -13,9 +19,21 @@ pub struct ApiCompatIssue {
     // "#/paths/~1thing3/get")
     blessed_pointer: String,
     generated_pointer: String,
+    kind: ApiCompatIssueKind,
     data: CompatIssueData,
 }
 
+/// The category of a compatibility issue.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum ApiCompatIssueKind {
+    /// Changes to paths and schemas, as found by drift.
+    Schema,
+    /// A documented response header was removed.
+    ResponseHeaderRemoved { header: String },
+    /// A content type was removed from a request body or response.
+    ContentTypeRemoved { content_type: String },
+}
+
 impl ApiCompatIssue {
     fn best_pointer(&self) -> ApiCompatPointer<'_> {
         ApiCompatPointer::best_pointer(
@@ -31,6 +49,15 @@ impl ApiCompatIssue {
     pub(crate) fn generated_json(&self) -> String {
         to_json_pretty(self.data.generated_value.as_ref())
     }
+
+    /// Returns the hash identifying this issue in a
+    /// [`BreakingChangeApprovals`] file.
+    ///
+    /// [`BreakingChangeApprovals`]: crate::BreakingChangeApprovals
+    pub(crate) fn approval_hash(&self) -> String {
+        let digest = Sha256::digest(self.to_string());
+        hex::encode(digest)[..ISSUE_HASH_LEN].to_owned()
+    }
 }
 
 impl fmt::Display for ApiCompatIssue {
@@ -51,6 +78,26 @@ impl fmt::Display for ApiCompatIssue {
             }
         }
 
+        match &self.kind {
+            ApiCompatIssueKind::Schema => {}
+            ApiCompatIssueKind::ResponseHeaderRemoved { header } => {
+                return write!(
+                    f,
+                    " backward-incompatible change: response header {:?} \
+                     was removed",
+                    header,
+                );
+            }
+            ApiCompatIssueKind::ContentTypeRemoved { content_type } => {
+                return write!(
+                    f,
+                    " backward-incompatible change: content type {:?} was \
+                     removed",
+                    content_type,
+                );
+            }
+        }
+
         if self.data.changes.len() == 1 {
             let Change {
                 message,
@@ -226,12 +273,18 @@ fn json_pointer_to_jq(pointer: &str) -> String {
     out
 }
 
-fn unescape_pointer_component(component: &str) -> String {
+/// The HTTP methods that may appear as keys of an OpenAPI path item, each
+/// holding an operation.
+pub(crate) const PATH_ITEM_METHODS: &[&str] =
+    &["get", "put", "post", "delete", "options", "head", "patch", "trace"];
+
+/// Unescape a JSON Pointer component (RFC 6901).
+pub(crate) fn unescape_pointer_component(component: &str) -> String {
     component.replace("~1", "/").replace("~0", "~")
 }
 
 /// Escape a string for use as a JSON Pointer component (RFC 6901).
-fn escape_json_pointer(s: &str) -> String {
+pub(crate) fn escape_json_pointer(s: &str) -> String {
     s.replace('~', "~0").replace('/', "~1")
 }
 
@@ -342,9 +395,23 @@ pub fn api_compatible(
     // not a wire-format change.
     normalize_old_websocket_responses(&mut blessed, generated);
 
+    // drift focuses on paths and schemas, so look for removed response headers
+    // and content types separately. drift may also report changes within
+    // them; skip those to avoid reporting the same issue twice.
+    let (extra_issues, covered) =
+        header_and_content_type_issues(&blessed, generated);
+    let is_covered = |pointer: &str| {
+        covered.iter().any(|prefix| {
+            pointer
+                .strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    };
+
     let changes = drift::compare(&blessed, generated)?;
     let changes = changes
         .into_iter()
+        .filter(|change| !change.old_path.iter().any(|p| is_covered(p)))
         .filter_map(|change| match change.class {
             ChangeClass::BackwardIncompatible
             | ChangeClass::ForwardIncompatible
@@ -380,11 +447,293 @@ pub fn api_compatible(
         .map(|((blessed_pointer, generated_pointer), data)| ApiCompatIssue {
             blessed_pointer,
             generated_pointer,
+            kind: ApiCompatIssueKind::Schema,
             data,
         })
+        .chain(extra_issues)
         .collect())
 }
 
+/// Finds response headers and content types (of both request bodies and
+/// responses) that are present in `blessed` but missing from `generated`.
+///
+/// Returns the issues found, along with the blessed pointers they cover.
+fn header_and_content_type_issues(
+    blessed: &serde_json::Value,
+    generated: &serde_json::Value,
+) -> (Vec<ApiCompatIssue>, Vec<String>) {
+    let mut issues = Vec::new();
+    let mut covered = Vec::new();
+    let Some(paths) = blessed.get("paths").and_then(|v| v.as_object()) else {
+        return (issues, covered);
+    };
+
+    for (path, item) in paths {
+        let Some(item) = item.as_object() else { continue };
+        for (method, operation) in item {
+            let Some(responses) =
+                operation.get("responses").and_then(|v| v.as_object())
+            else {
+                continue;
+            };
+            let operation_pointer =
+                format!("#/paths/{}/{}", escape_json_pointer(path), method);
+            if lookup(generated, &operation_pointer).is_none() {
+                // drift reports removed operations.
+                continue;
+            }
+
+            content_type_issues(
+                blessed,
+                generated,
+                &format!("{operation_pointer}/requestBody/content"),
+                &mut issues,
+                &mut covered,
+            );
+            for status in responses.keys() {
+                let response_pointer = format!(
+                    "{operation_pointer}/responses/{}",
+                    escape_json_pointer(status)
+                );
+                content_type_issues(
+                    blessed,
+                    generated,
+                    &format!("{response_pointer}/content"),
+                    &mut issues,
+                    &mut covered,
+                );
+                header_issues(
+                    blessed,
+                    generated,
+                    &response_pointer,
+                    &mut issues,
+                    &mut covered,
+                );
+            }
+        }
+    }
+
+    (issues, covered)
+}
+
+fn content_type_issues(
+    blessed: &serde_json::Value,
+    generated: &serde_json::Value,
+    content_pointer: &str,
+    issues: &mut Vec<ApiCompatIssue>,
+    covered: &mut Vec<String>,
+) {
+    // If either side has no content at all (or refers to a shared component),
+    // drift reports the difference.
+    let (Some(blessed_content), Some(generated_content)) = (
+        lookup(blessed, content_pointer).and_then(|v| v.as_object()),
+        lookup(generated, content_pointer).and_then(|v| v.as_object()),
+    ) else {
+        return;
+    };
+
+    for content_type in blessed_content.keys() {
+        if generated_content.contains_key(content_type) {
+            continue;
+        }
+        covered.push(format!(
+            "{content_pointer}/{}",
+            escape_json_pointer(content_type)
+        ));
+        // Show both sides of the content map, so that a changed content type
+        // is visible in the diff.
+        issues.push(ApiCompatIssue {
+            blessed_pointer: content_pointer.to_owned(),
+            generated_pointer: content_pointer.to_owned(),
+            kind: ApiCompatIssueKind::ContentTypeRemoved {
+                content_type: content_type.clone(),
+            },
+            data: CompatIssueData::new(
+                blessed,
+                content_pointer,
+                generated,
+                content_pointer,
+            ),
+        });
+    }
+}
+
+fn header_issues(
+    blessed: &serde_json::Value,
+    generated: &serde_json::Value,
+    response_pointer: &str,
+    issues: &mut Vec<ApiCompatIssue>,
+    covered: &mut Vec<String>,
+) {
+    let headers_pointer = format!("{response_pointer}/headers");
+    let Some(blessed_headers) =
+        lookup(blessed, &headers_pointer).and_then(|v| v.as_object())
+    else {
+        return;
+    };
+    if lookup(generated, response_pointer).is_none() {
+        // drift reports removed responses.
+        return;
+    }
+    let generated_headers =
+        lookup(generated, &headers_pointer).and_then(|v| v.as_object());
+
+    for header in blessed_headers.keys() {
+        // Header names are case-insensitive.
+        let present = generated_headers.is_some_and(|headers| {
+            headers.keys().any(|h| h.eq_ignore_ascii_case(header))
+        });
+        if present {
+            continue;
+        }
+        let header_pointer =
+            format!("{headers_pointer}/{}", escape_json_pointer(header));
+        covered.push(header_pointer.clone());
+        issues.push(ApiCompatIssue {
+            data: CompatIssueData::new(
+                blessed,
+                &header_pointer,
+                generated,
+                &headers_pointer,
+            ),
+            blessed_pointer: header_pointer,
+            generated_pointer: headers_pointer.clone(),
+            kind: ApiCompatIssueKind::ResponseHeaderRemoved {
+                header: header.clone(),
+            },
+        });
+    }
+}
+
+/// Looks up a `#`-prefixed JSON pointer within `spec`.
+fn lookup<'a>(
+    spec: &'a serde_json::Value,
+    pointer: &str,
+) -> Option<&'a serde_json::Value> {
+    spec.pointer(pointer.trim_start_matches('#'))
+}
+
+/// A concise description of the differences between two versions of an API.
+///
+/// Unlike [`api_compatible`], which only reports incompatible changes, this
+/// describes every change to operations and schemas, including trivial ones.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct VersionDelta {
+    pub(crate) operations_added: BTreeSet<String>,
+    pub(crate) operations_removed: BTreeSet<String>,
+    pub(crate) schemas_added: BTreeSet<String>,
+    pub(crate) schemas_removed: BTreeSet<String>,
+    pub(crate) schemas_changed: BTreeSet<String>,
+}
+
+impl VersionDelta {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.operations_added.is_empty()
+            && self.operations_removed.is_empty()
+            && self.schemas_added.is_empty()
+            && self.schemas_removed.is_empty()
+            && self.schemas_changed.is_empty()
+    }
+}
+
+impl fmt::Display for VersionDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("no changes to operations or schemas");
+        }
+
+        let parts = [
+            ("operations added", &self.operations_added),
+            ("operations removed", &self.operations_removed),
+            ("schemas added", &self.schemas_added),
+            ("schemas removed", &self.schemas_removed),
+            ("schemas changed", &self.schemas_changed),
+        ];
+        let mut first = true;
+        for (label, names) in parts {
+            if names.is_empty() {
+                continue;
+            }
+            if !first {
+                f.write_str("; ")?;
+            }
+            first = false;
+            write!(f, "{}: ", label)?;
+            for (ix, name) in names.iter().enumerate() {
+                if ix > 0 {
+                    f.write_str(", ")?;
+                }
+                f.write_str(name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Describes the differences between `older` and `newer` versions of an API.
+///
+/// Operations are identified by their operation ID, or by method and path if
+/// they don't have one. A schema is considered changed if any change that the
+/// compatibility engine finds passes through it.
+pub(crate) fn describe_differences(
+    older: &OpenAPI,
+    newer: &OpenAPI,
+) -> anyhow::Result<VersionDelta> {
+    let older_ops = operation_names(older);
+    let newer_ops = operation_names(newer);
+    let older_schemas = schema_names(older);
+    let newer_schemas = schema_names(newer);
+
+    let older_value = serde_json::to_value(older)?;
+    let newer_value = serde_json::to_value(newer)?;
+    let mut schemas_changed = BTreeSet::new();
+    for change in drift::compare(&older_value, &newer_value)? {
+        for pointer in change.old_path.iter().chain(change.new_path.iter()) {
+            if let Some(name) = pointer
+                .strip_prefix("#/components/schemas/")
+                .and_then(|rest| rest.split('/').next())
+            {
+                let name = unescape_pointer_component(name);
+                if older_schemas.contains(&name)
+                    && newer_schemas.contains(&name)
+                {
+                    schemas_changed.insert(name);
+                }
+            }
+        }
+    }
+
+    Ok(VersionDelta {
+        operations_added: newer_ops.difference(&older_ops).cloned().collect(),
+        operations_removed: older_ops.difference(&newer_ops).cloned().collect(),
+        schemas_added: newer_schemas
+            .difference(&older_schemas)
+            .cloned()
+            .collect(),
+        schemas_removed: older_schemas
+            .difference(&newer_schemas)
+            .cloned()
+            .collect(),
+        schemas_changed,
+    })
+}
+
+fn operation_names(doc: &OpenAPI) -> BTreeSet<String> {
+    doc.operations()
+        .map(|(path, method, op)| match &op.operation_id {
+            Some(id) => id.clone(),
+            None => format!("{} {}", method.to_uppercase(), path),
+        })
+        .collect()
+}
+
+fn schema_names(doc: &OpenAPI) -> BTreeSet<String> {
+    doc.components
+        .as_ref()
+        .map(|c| c.schemas.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
 pub fn change_class_str(class: &ChangeClass) -> &'static str {
     match class {
         // Add spaces to the end of everything so "unhandled" can return an
@@ -403,6 +752,145 @@ pub fn change_class_str(class: &ChangeClass) -> &'static str {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_header_and_content_type_issues() {
+        let doc = |header: Option<&str>, content_type: &str| {
+            let mut response = serde_json::json!({
+                "description": "ok",
+                "content": {
+                    content_type: { "schema": { "type": "string" } }
+                }
+            });
+            if let Some(header) = header {
+                response["headers"] = serde_json::json!({
+                    header: { "schema": { "type": "string" } }
+                });
+            }
+            serde_json::json!({
+                "openapi": "3.0.3",
+                "info": { "title": "Test", "version": "1.0.0" },
+                "paths": {
+                    "/thing": {
+                        "get": {
+                            "operationId": "get_thing",
+                            "responses": { "200": response }
+                        }
+                    }
+                }
+            })
+        };
+
+        let blessed = doc(Some("X-Request-Id"), "application/json");
+
+        // Header names are compared case-insensitively.
+        let issues = api_compatible(
+            &blessed,
+            &doc(Some("x-request-id"), "application/json"),
+        )
+        .unwrap();
+        assert!(issues.is_empty(), "unexpected issues: {issues:?}");
+
+        let issues =
+            api_compatible(&blessed, &doc(None, "text/plain")).unwrap();
+        let kinds: Vec<_> = issues.iter().map(|issue| &issue.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &ApiCompatIssueKind::ContentTypeRemoved {
+                    content_type: "application/json".to_owned(),
+                },
+                &ApiCompatIssueKind::ResponseHeaderRemoved {
+                    header: "X-Request-Id".to_owned(),
+                },
+            ],
+        );
+        assert_eq!(
+            issues[1].to_string(),
+            "at .paths.\"/thing\".get.responses.200.headers.X-Request-Id: \
+             backward-incompatible change: response header \"X-Request-Id\" \
+             was removed",
+        );
+    }
+
+    #[test]
+    fn test_describe_differences() {
+        let doc = |thing_type: &str, paths: serde_json::Value, extra: bool| {
+            let mut schemas = serde_json::json!({
+                "Thing": {
+                    "type": "object",
+                    "properties": { "x": { "type": thing_type } },
+                    "required": ["x"]
+                }
+            });
+            if extra {
+                schemas["Other"] = serde_json::json!({ "type": "string" });
+            }
+            let doc = serde_json::json!({
+                "openapi": "3.0.3",
+                "info": { "title": "Test", "version": "1.0.0" },
+                "paths": paths,
+                "components": { "schemas": schemas }
+            });
+            serde_json::from_value::<OpenAPI>(doc).unwrap()
+        };
+        let get_thing = serde_json::json!({
+            "operationId": "get_thing",
+            "responses": {
+                "200": {
+                    "description": "ok",
+                    "content": {
+                        "application/json": {
+                            "schema": { "$ref": "#/components/schemas/Thing" }
+                        }
+                    }
+                }
+            }
+        });
+        let no_content = |id: &str| {
+            serde_json::json!({
+                "operationId": id,
+                "responses": { "204": { "description": "done" } }
+            })
+        };
+
+        let older = doc(
+            "string",
+            serde_json::json!({
+                "/thing": { "get": get_thing },
+                "/old": { "delete": no_content("delete_old") },
+            }),
+            false,
+        );
+        let newer = doc(
+            "integer",
+            serde_json::json!({
+                "/thing": { "get": get_thing },
+                "/new": { "post": no_content("create_new") },
+            }),
+            true,
+        );
+
+        let delta = describe_differences(&older, &newer).unwrap();
+        assert_eq!(
+            delta,
+            VersionDelta {
+                operations_added: ["create_new".to_owned()].into(),
+                operations_removed: ["delete_old".to_owned()].into(),
+                schemas_added: ["Other".to_owned()].into(),
+                schemas_removed: BTreeSet::new(),
+                schemas_changed: ["Thing".to_owned()].into(),
+            },
+        );
+        assert_eq!(
+            delta.to_string(),
+            "operations added: create_new; operations removed: delete_old; \
+             schemas added: Other; schemas changed: Thing",
+        );
+
+        let delta = describe_differences(&older, &older).unwrap();
+        assert!(delta.is_empty());
+    }
+
     #[test]
     fn test_best_pointer() {
         let cases = vec![
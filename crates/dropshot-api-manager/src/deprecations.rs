@@ -0,0 +1,143 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Maintains a file summarizing the deprecated operations of a versioned API.
+//!
+//! See [`DeprecationSummary`] for more.
+
+use camino::{Utf8Path, Utf8PathBuf};
+use dropshot_api_manager_types::ApiIdent;
+use openapiv3::{OpenAPI, Operation};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+
+/// A JSON file summarizing, for each supported version of a versioned API,
+/// which operations are deprecated, and in which later version each of them
+/// changes or is removed.
+///
+/// Attach this to an API with [`ManagedApi::with_deprecation_summary`].
+/// `generate` writes the file, and `check` verifies that it's up-to-date, like
+/// any other extra file. Blessed versions are summarized from their blessed
+/// documents. SDK pipelines can read the file to guide clients off deprecated
+/// operations, rather than each deriving this from the documents.
+///
+/// The file has the form:
+///
+/// ```json
+/// {
+///   "api": "my-api",
+///   "versions": [
+///     {
+///       "version": "1.0.0",
+///       "deprecated_operations": [
+///         {
+///           "method": "GET",
+///           "path": "/widgets",
+///           "operation_id": "widget_list",
+///           "changed_in": "2.0.0",
+///           "removed_in": "3.0.0"
+///         }
+///       ]
+///     }
+///   ]
+/// }
+/// ```
+///
+/// An operation is identified by its method and path. `changed_in` is the
+/// first later version in which the operation is different (not counting
+/// changes to the schemas it refers to), and `removed_in` is the first later
+/// version without it. Either is `null` if there's no such version.
+///
+/// [`ManagedApi::with_deprecation_summary`]: crate::ManagedApi::with_deprecation_summary
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeprecationSummary {
+    path: Utf8PathBuf,
+}
+
+impl DeprecationSummary {
+    /// Creates a summary written to `path` (relative to the repository root).
+    pub fn new(path: impl Into<Utf8PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns the path to the summary, relative to the repository root.
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+
+    /// Returns the contents of the summary for the API `ident`, given the
+    /// document for each of its supported versions in ascending order.
+    pub(crate) fn contents(
+        &self,
+        ident: &ApiIdent,
+        documents: &[(&semver::Version, &OpenAPI)],
+    ) -> Vec<u8> {
+        let operations: Vec<_> = documents
+            .iter()
+            .map(|(_, document)| operations(document))
+            .collect();
+
+        let mut versions = Vec::new();
+        for (index, (version, _)) in documents.iter().enumerate() {
+            let mut deprecated = Vec::new();
+            for (&(path, ref method), operation) in &operations[index] {
+                if !operation.deprecated {
+                    continue;
+                }
+                let key = (path, method.clone());
+                let mut changed_in = None;
+                let mut removed_in = None;
+                for (later, (later_version, _)) in
+                    operations[index + 1..].iter().zip(&documents[index + 1..])
+                {
+                    match later.get(&key) {
+                        None => {
+                            removed_in = Some(later_version.to_string());
+                            break;
+                        }
+                        Some(later_operation)
+                            if changed_in.is_none()
+                                && *later_operation != *operation =>
+                        {
+                            changed_in = Some(later_version.to_string());
+                        }
+                        Some(_) => {}
+                    }
+                }
+                deprecated.push(json!({
+                    "method": method,
+                    "path": path,
+                    "operation_id": operation.operation_id,
+                    "changed_in": changed_in,
+                    "removed_in": removed_in,
+                }));
+            }
+            versions.push(json!({
+                "version": version.to_string(),
+                "deprecated_operations": deprecated,
+            }));
+        }
+
+        let summary: Value = json!({
+            "api": ident.as_str(),
+            "versions": versions,
+        });
+        let mut contents = serde_json::to_vec_pretty(&summary)
+            .expect("serializing a JSON value always succeeds");
+        contents.push(b'\n');
+        contents
+    }
+}
+
+/// Returns the operations in `document`, keyed by path and (uppercase) method.
+fn operations(document: &OpenAPI) -> BTreeMap<(&str, String), &Operation> {
+    document
+        .paths
+        .iter()
+        .filter_map(|(path, item)| Some((path, item.as_item()?)))
+        .flat_map(|(path, item)| {
+            item.iter().map(move |(method, operation)| {
+                ((path.as_str(), method.to_uppercase()), operation)
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,184 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Lints the links to external documentation in OpenAPI documents.
+//!
+//! See [`DocsLinkLint`] for more.
+
+use anyhow::{Context, anyhow};
+use dropshot_api_manager_types::ValidationContext;
+use globset::{Glob, GlobMatcher};
+use openapiv3::{ExternalDocumentation, OpenAPI};
+use std::collections::BTreeSet;
+
+/// Requires an API's OpenAPI documents to link to external documentation.
+///
+/// Attach this to an API with [`ManagedApi::with_docs_link_lint`]. The
+/// document must then have an `externalDocs` object whose URL matches the
+/// lint's glob pattern, e.g. `https://docs.example.com/apis/*`, and failing
+/// that, fails validation. (In the pattern, `*` matches any sequence of
+/// characters, including `/`.) For APIs generated by Dropshot, set the URL
+/// with [`ManagedApiMetadata::external_docs_url`].
+///
+/// With [`DocsLinkLint::with_tags_required`], every tag used by an operation
+/// must also be declared with an `externalDocs` URL matching the pattern. For
+/// Dropshot APIs, tags and their links are declared in the API trait's tag
+/// configuration.
+///
+/// Blessed documents can't be changed, so they aren't linted.
+///
+/// [`ManagedApi::with_docs_link_lint`]: crate::ManagedApi::with_docs_link_lint
+/// [`ManagedApiMetadata::external_docs_url`]: dropshot_api_manager_types::ManagedApiMetadata::external_docs_url
+#[derive(Clone, Debug)]
+pub struct DocsLinkLint {
+    pattern: String,
+    matcher: GlobMatcher,
+    require_for_tags: bool,
+}
+
+impl DocsLinkLint {
+    /// Creates a lint requiring a link to external documentation whose URL
+    /// matches the glob `pattern`.
+    ///
+    /// Returns an error if `pattern` is not a valid glob.
+    pub fn new(pattern: impl Into<String>) -> anyhow::Result<Self> {
+        let pattern = pattern.into();
+        let matcher = Glob::new(&pattern)
+            .with_context(|| format!("invalid URL pattern {:?}", pattern))?
+            .compile_matcher();
+        Ok(Self { pattern, matcher, require_for_tags: false })
+    }
+
+    /// Also requires a link for every tag used by an operation.
+    pub fn with_tags_required(mut self) -> Self {
+        self.require_for_tags = true;
+        self
+    }
+
+    /// Returns the glob pattern that URLs must match.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns true if tags also require links.
+    pub fn requires_tags(&self) -> bool {
+        self.require_for_tags
+    }
+
+    /// Lints the document, reporting each issue as a validation error.
+    pub(crate) fn validate(
+        &self,
+        openapi: &OpenAPI,
+        mut cx: ValidationContext<'_>,
+    ) {
+        if cx.is_blessed() == Some(true) {
+            return;
+        }
+        for issue in self.lint_document(openapi) {
+            cx.report_error(anyhow!(issue));
+        }
+    }
+
+    fn lint_document(&self, openapi: &OpenAPI) -> Vec<String> {
+        let mut issues = Vec::new();
+        self.lint_link(
+            "the document",
+            openapi.external_docs.as_ref(),
+            &mut issues,
+        );
+        if !self.require_for_tags {
+            return issues;
+        }
+
+        let used_tags: BTreeSet<&str> = openapi
+            .operations()
+            .flat_map(|(_, _, operation)| &operation.tags)
+            .map(String::as_str)
+            .collect();
+        for name in used_tags {
+            let tag = openapi.tags.iter().find(|tag| tag.name == name);
+            self.lint_link(
+                &format!("tag {:?}", name),
+                tag.and_then(|tag| tag.external_docs.as_ref()),
+                &mut issues,
+            );
+        }
+        issues
+    }
+
+    fn lint_link(
+        &self,
+        what: &str,
+        external_docs: Option<&ExternalDocumentation>,
+        issues: &mut Vec<String>,
+    ) {
+        match external_docs {
+            None => issues.push(format!(
+                "{} has no externalDocs link (expected a URL matching {:?})",
+                what, self.pattern,
+            )),
+            Some(docs) if !self.matcher.is_match(&docs.url) => {
+                issues.push(format!(
+                    "{} has externalDocs URL {:?}, which doesn't match {:?}",
+                    what, docs.url, self.pattern,
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lint_document() {
+        let document = |external_docs: serde_json::Value| -> OpenAPI {
+            serde_json::from_value(json!({
+                "openapi": "3.0.3",
+                "info": { "title": "test", "version": "1.0.0" },
+                "externalDocs": external_docs,
+                "paths": {
+                    "/widgets": {
+                        "get": {
+                            "tags": ["widgets", "gadgets"],
+                            "responses": {},
+                        },
+                    },
+                },
+                "tags": [
+                    {
+                        "name": "widgets",
+                        "externalDocs": {
+                            "url": "https://docs.example.com/apis/widgets",
+                        },
+                    },
+                    {
+                        "name": "gadgets",
+                        "externalDocs": {
+                            "url": "https://example.com/gadgets",
+                        },
+                    },
+                ],
+            }))
+            .unwrap()
+        };
+        let lint =
+            DocsLinkLint::new("https://docs.example.com/apis/*").unwrap();
+
+        let openapi =
+            document(json!({ "url": "https://docs.example.com/apis/test" }));
+        assert_eq!(lint.lint_document(&openapi), Vec::<String>::new());
+        let issues = lint.clone().with_tags_required().lint_document(&openapi);
+        assert_eq!(issues.len(), 1, "issues: {:#?}", issues);
+        assert!(issues[0].starts_with("tag \"gadgets\" has externalDocs URL"));
+
+        let openapi = document(json!({ "url": "https://example.com/test" }));
+        let issues = lint.lint_document(&openapi);
+        assert_eq!(issues.len(), 1, "issues: {:#?}", issues);
+        assert!(issues[0].starts_with("the document has externalDocs URL"));
+
+        assert!(DocsLinkLint::new("https://[").is_err());
+    }
+}
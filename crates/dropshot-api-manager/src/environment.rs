@@ -5,19 +5,28 @@
 
 use crate::{
     apis::ManagedApis,
+    baseline::unpack_baseline,
+    generation_timings::GenerationTimings,
+    hooks::{GenerateHook, GenerateHooks},
+    openapi_version::validate_openapi_version,
     output::{
         Styles,
         headers::{GENERATING, HEADER_WIDTH},
     },
+    sink::{OutputSink, OutputSinks},
     spec_files_blessed::{BlessedApiSpecFile, BlessedFiles},
     spec_files_generated::GeneratedFiles,
-    spec_files_generic::ApiSpecFilesBuilder,
-    spec_files_local::{LocalFiles, walk_local_directory},
-    vcs::{RepoVcs, RepoVcsKind, VcsRevision},
+    spec_files_generic::{ApiSpecFilesBuilder, FileNameHash},
+    spec_files_local::{
+        IgnoreGlobs, LocalFiles, find_unknown_api_entries, walk_local_directory,
+    },
+    vcs::{BlessedCache, GitRunner, RepoVcs, RepoVcsKind, VcsRevision},
 };
 use anyhow::Context;
 use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use camino_tempfile::Utf8TempDir;
 use owo_colors::OwoColorize;
+use std::{collections::BTreeMap, sync::Arc};
 
 /// Default Git branch for the blessed source.
 const DEFAULT_GIT_BRANCH: &str = "origin/main";
@@ -42,6 +51,10 @@ pub struct Environment {
     /// The default OpenAPI directory.
     pub(crate) default_openapi_dir: Utf8PathBuf,
 
+    /// The subdirectory of the OpenAPI directory that this environment owns,
+    /// if any.
+    pub(crate) namespace: Option<String>,
+
     /// The default Git branch for the blessed source (e.g.,
     /// `"origin/main"`).
     pub(crate) default_git_branch: String,
@@ -52,6 +65,70 @@ pub struct Environment {
 
     /// The detected VCS backend.
     pub(crate) vcs: RepoVcs,
+
+    /// Optional settings, carried over to every [`ResolvedEnv`].
+    pub(crate) settings: EnvSettings,
+}
+
+/// Optional settings for an [`Environment`], set with its `with_*` methods.
+///
+/// These are carried over unchanged from an [`Environment`] to each
+/// [`ResolvedEnv`] made from it.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct EnvSettings {
+    /// How the hash in versioned document file names is computed.
+    pub(crate) file_name_hash: FileNameHash,
+
+    /// The `openapi` field that generated documents are given, if any.
+    pub(crate) openapi_version: Option<String>,
+
+    /// Files and directories to skip within the OpenAPI documents directory.
+    pub(crate) ignore_globs: IgnoreGlobs,
+
+    /// What to do about local files for APIs that aren't managed.
+    pub(crate) unknown_api_policy: UnknownApiPolicy,
+
+    /// Hooks run by `generate` before and after updating documents.
+    pub(crate) generate_hooks: GenerateHooks,
+
+    /// Sinks that receive structured events about each run.
+    pub(crate) output_sinks: OutputSinks,
+
+    /// Paths watched by `watch`, in addition to the documents directory,
+    /// relative to the repository root.
+    pub(crate) watch_paths: Vec<Utf8PathBuf>,
+
+    /// If true, `generate` refuses to run while the documents directory
+    /// contains untracked files it doesn't recognize.
+    pub(crate) strict_documents_dir: bool,
+
+    /// Where to record how long each API takes to generate, if anywhere.
+    pub(crate) generation_timings: Option<GenerationTimings>,
+}
+
+/// What to do about files in the OpenAPI documents directory that belong to
+/// APIs that aren't managed, e.g. because the API was removed.
+///
+/// Set with [`Environment::with_unknown_api_policy`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum UnknownApiPolicy {
+    /// Fail to load local documents, unless the API is listed in
+    /// [`ManagedApis::with_unknown_apis`], in which case a lockstep document
+    /// is skipped with a warning.
+    ///
+    /// [`ManagedApis::with_unknown_apis`]: crate::ManagedApis::with_unknown_apis
+    #[default]
+    Error,
+
+    /// Skip the files with a warning.
+    Warn,
+
+    /// Report the files as a problem that `generate` fixes by deleting them.
+    ///
+    /// As a safeguard, this only applies if the API also has no files among
+    /// the blessed documents. Otherwise, the files are reported as a problem
+    /// that must be fixed by hand.
+    Delete,
 }
 
 impl Environment {
@@ -86,18 +163,91 @@ impl Environment {
         Ok(Self {
             repo_root,
             default_openapi_dir,
+            namespace: None,
             default_git_branch: DEFAULT_GIT_BRANCH.to_owned(),
             default_jj_revset: DEFAULT_JJ_REVSET.to_owned(),
             command,
             vcs,
+            settings: EnvSettings::default(),
+        })
+    }
+
+    /// Creates a new environment for OpenAPI documents that aren't under
+    /// version control.
+    ///
+    /// The arguments are the same as for [`Self::new`], except that
+    /// `repo_root` doesn't need to be a Git or Jujutsu repository. Without
+    /// version control, there are no blessed documents, so local documents are
+    /// simply compared with generated ones. This only works for lockstep APIs:
+    /// loading documents fails if any versioned APIs are configured, since
+    /// their compatibility checks depend on blessed documents. (Passing
+    /// `--no-blessed` skips those checks, allowing versioned APIs.)
+    ///
+    /// Returns an error if `repo_root` is not an absolute path or
+    /// `default_openapi_dir` is not a relative path.
+    pub fn new_no_git(
+        command: impl Into<String>,
+        repo_root: impl Into<Utf8PathBuf>,
+        default_openapi_dir: impl Into<Utf8PathBuf>,
+    ) -> anyhow::Result<Self> {
+        let command = command.into();
+        let repo_root = repo_root.into();
+        let default_openapi_dir = default_openapi_dir.into();
+
+        validate_paths(&repo_root, &default_openapi_dir)?;
+
+        Ok(Self {
+            repo_root,
+            default_openapi_dir,
+            namespace: None,
+            default_git_branch: DEFAULT_GIT_BRANCH.to_owned(),
+            default_jj_revset: DEFAULT_JJ_REVSET.to_owned(),
+            command,
+            vcs: RepoVcs::none(),
+            settings: EnvSettings::default(),
         })
     }
 
+    /// Restricts this environment to the `namespace` subdirectory of the
+    /// OpenAPI documents directory.
+    ///
+    /// This is for repositories that hold the documents of several services,
+    /// each managed with its own [`ManagedApis`] by its own tool invocation.
+    /// With a namespace, documents are read from and written to
+    /// `<documents dir>/<namespace>`, including when the documents directory
+    /// is overridden with `--dir`, and blessed documents are loaded from the
+    /// same subdirectory. Files in other namespaces are never read, reported
+    /// or modified.
+    ///
+    /// Returns an error if `namespace` isn't a single, normal path component.
+    pub fn with_namespace(
+        mut self,
+        namespace: impl Into<String>,
+    ) -> anyhow::Result<Self> {
+        let namespace = namespace.into();
+        let mut components = Utf8Path::new(&namespace).components();
+        if !matches!(
+            (components.next(), components.next()),
+            (Some(Utf8Component::Normal(_)), None)
+        ) {
+            return Err(anyhow::anyhow!(
+                "namespace must be a single directory name, found: {:?}",
+                namespace
+            ));
+        }
+        self.namespace = Some(namespace);
+        Ok(self)
+    }
+
     /// Sets the default Git branch used as the blessed source.
     ///
     /// By default, this is `origin/main`. The value should be a valid
     /// Git ref, e.g. `origin/main`, `upstream/dev`, or `main`.
     ///
+    /// In a Jujutsu repository colocated with Git, this branch is also used
+    /// (translated to the equivalent jj bookmark, e.g. `main@origin`) unless
+    /// [`Self::with_default_jj_revset`] is called.
+    ///
     /// For individual commands, the revision can be overridden through
     /// the `--blessed-from-vcs` argument (or
     /// `OPENAPI_MGR_BLESSED_FROM_VCS`), and the path within the
@@ -124,6 +274,185 @@ impl Environment {
         self
     }
 
+    /// Opts into fetching commits referenced by Git stubs from `remote` (e.g.
+    /// `origin`) when they aren't present locally.
+    ///
+    /// This commonly happens right after a fetch that rewrote history. By
+    /// default, a missing commit is reported as an error that includes the
+    /// command to fetch it. Fetching is only supported for Git repositories.
+    pub fn with_fetch_missing_commits_from(
+        mut self,
+        remote: impl Into<String>,
+    ) -> Self {
+        self.vcs.set_fetch_missing_from(remote.into());
+        self
+    }
+
+    /// Caches the VCS queries made to load blessed documents in `cache`, so
+    /// that later commands run in the same process can reuse them.
+    ///
+    /// See [`BlessedCache`] for more.
+    pub fn with_blessed_cache(mut self, cache: BlessedCache) -> Self {
+        self.vcs.set_cache(cache);
+        self
+    }
+
+    /// Runs git commands with `runner` rather than as subprocesses.
+    ///
+    /// See [`GitRunner`] for more.
+    pub fn with_git_runner(mut self, runner: impl GitRunner + 'static) -> Self {
+        self.vcs.set_runner(Arc::new(runner));
+        self
+    }
+
+    /// Sets how the hash in versioned OpenAPI document file names is computed.
+    ///
+    /// By default, file names use the first three bytes of the SHA-256 digest
+    /// of the document. If two distinct documents in flight ever map to the
+    /// same file name, this can be used to switch to a longer hash. Existing
+    /// files keep their names: the hash in a file name is accepted as long as
+    /// it matches the document's contents under any supported algorithm.
+    pub fn with_file_name_hash(mut self, file_name_hash: FileNameHash) -> Self {
+        self.settings.file_name_hash = file_name_hash;
+        self
+    }
+
+    /// Sets the `openapi` field of every generated document to `version`,
+    /// such as `"3.0.3"`.
+    ///
+    /// Different versions of Dropshot write different OpenAPI versions into
+    /// generated documents, so upgrading Dropshot (or building APIs with more
+    /// than one version of it) can change this field across many documents at
+    /// once. Pinning it keeps the field the same in every document the API
+    /// manager generates, whatever wrote them. Local documents with a
+    /// different `openapi` field are then out of date, and `generate` updates
+    /// them. Blessed documents are never changed, though, so pick the version
+    /// the latest blessed documents already use.
+    ///
+    /// Without a pinned version, `check` and `generate` warn if generated
+    /// documents have different `openapi` fields.
+    ///
+    /// Returns an error if `version` isn't an OpenAPI 3.0 version.
+    pub fn with_openapi_version(
+        mut self,
+        version: &str,
+    ) -> anyhow::Result<Self> {
+        validate_openapi_version(version)?;
+        self.settings.openapi_version = Some(version.to_owned());
+        Ok(self)
+    }
+
+    /// Skips files and directories matching any of the glob `patterns` when
+    /// loading documents from the OpenAPI documents directory.
+    ///
+    /// A pattern matches if it matches either an entry's file name or its path
+    /// relative to the documents directory. Files that editors and other tools
+    /// commonly leave behind (such as `.DS_Store`, `*.orig`, and `*.swp`) are
+    /// always ignored.
+    ///
+    /// Returns an error if any of the patterns is not a valid glob.
+    pub fn with_ignore_globs<I, S>(
+        mut self,
+        patterns: I,
+    ) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.settings.ignore_globs =
+            self.settings.ignore_globs.with_patterns(patterns)?;
+        Ok(self)
+    }
+
+    /// Sets what to do about files in the OpenAPI documents directory that
+    /// belong to APIs that aren't managed (by default,
+    /// [`UnknownApiPolicy::Error`]).
+    ///
+    /// Such files are left behind when an API is removed from
+    /// [`ManagedApis`]: a directory named after the API, or an `<ident>.json`
+    /// file.
+    pub fn with_unknown_api_policy(mut self, policy: UnknownApiPolicy) -> Self {
+        self.settings.unknown_api_policy = policy;
+        self
+    }
+
+    /// Adds a hook that `generate` runs just before applying fixes.
+    ///
+    /// This can be called multiple times: hooks run in the order they were
+    /// added. See [`GenerateHook`] for more.
+    pub fn with_pre_generate_hook(mut self, hook: GenerateHook) -> Self {
+        self.settings.generate_hooks.pre.push(hook);
+        self
+    }
+
+    /// Adds a hook that `generate` runs once documents are up-to-date, with
+    /// the list of files it changed in the documents directory.
+    ///
+    /// This can be called multiple times: hooks run in the order they were
+    /// added. See [`GenerateHook`] for more.
+    pub fn with_post_generate_hook(mut self, hook: GenerateHook) -> Self {
+        self.settings.generate_hooks.post.push(hook);
+        self
+    }
+
+    /// Adds a sink that receives structured events as `check` and `generate`
+    /// run, such as each problem found and each fix applied.
+    ///
+    /// This can be called multiple times: each sink receives every event. See
+    /// [`OutputSink`] for more.
+    pub fn with_output_sink(mut self, sink: impl OutputSink + 'static) -> Self {
+        self.settings.output_sinks.push(Arc::new(sink));
+        self
+    }
+
+    /// Sets the paths, relative to the repository root, that the `watch`
+    /// command watches for changes in addition to the documents directory.
+    ///
+    /// These are typically the source directories of the crates that define
+    /// the managed APIs.
+    pub fn with_watch_paths<I, P>(mut self, paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<Utf8PathBuf>,
+    {
+        self.settings.watch_paths = paths.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Makes `generate` refuse to run while the documents directory contains
+    /// untracked files that the API manager doesn't recognize, until they're
+    /// removed or allowed.
+    ///
+    /// By default, `generate` skips over unrecognized files with a warning,
+    /// and deletes files that look like documents of managed APIs but aren't
+    /// expected. In strict mode, an untracked file that isn't a document (or
+    /// another file the API manager maintains) must first be removed, or
+    /// allowed with [`Self::with_ignore_globs`] or
+    /// [`ManagedApi::with_unmanaged_files`]. Passing `--allow-dirty-docs` to
+    /// `generate` skips this check for one run.
+    ///
+    /// Jujutsu tracks new files automatically, so in Jujutsu repositories (and
+    /// outside version control), every file in the documents directory must
+    /// be recognized or allowed.
+    ///
+    /// [`ManagedApi::with_unmanaged_files`]: crate::ManagedApi::with_unmanaged_files
+    pub fn with_strict_documents_dir(mut self, strict: bool) -> Self {
+        self.settings.strict_documents_dir = strict;
+        self
+    }
+
+    /// Records how long generating each API's documents takes, and warns
+    /// when an API takes much longer than it did last time.
+    ///
+    /// See [`GenerationTimings`] for more.
+    pub fn with_generation_timings(
+        mut self,
+        timings: GenerationTimings,
+    ) -> Self {
+        self.settings.generation_timings = Some(timings);
+        self
+    }
+
     /// Creates a new environment without auto-detecting VCS.
     ///
     /// Uses the Git backend by default. This is intended for unit tests that
@@ -146,10 +475,12 @@ impl Environment {
         Ok(Self {
             repo_root,
             default_openapi_dir,
+            namespace: None,
             default_git_branch: DEFAULT_GIT_BRANCH.to_owned(),
             default_jj_revset: DEFAULT_JJ_REVSET.to_owned(),
             command,
             vcs,
+            settings: EnvSettings::default(),
         })
     }
 
@@ -198,24 +529,85 @@ impl Environment {
                 (abs_dir, rel_dir)
             }
         };
+        let (abs_dir, rel_dir) = match &self.namespace {
+            Some(namespace) => {
+                (abs_dir.join(namespace), rel_dir.join(namespace))
+            }
+            None => (abs_dir, rel_dir),
+        };
 
         // Select the appropriate default blessed branch based on the
         // detected VCS backend.
         let default_blessed_branch = match self.vcs.kind() {
             RepoVcsKind::Git => self.default_git_branch.clone(),
+            // In a Jujutsu repository colocated with Git, a custom Git branch
+            // is more likely to be what's intended than the default revset.
+            // (The branch is translated to a revset in merge_base_head.)
+            RepoVcsKind::Jj
+                if self.vcs.is_colocated()
+                    && self.default_jj_revset == DEFAULT_JJ_REVSET
+                    && self.default_git_branch != DEFAULT_GIT_BRANCH =>
+            {
+                self.default_git_branch.clone()
+            }
             RepoVcsKind::Jj => self.default_jj_revset.clone(),
+            // There's no blessed source without version control.
+            RepoVcsKind::None => String::new(),
         };
 
         Ok(ResolvedEnv {
             command: self.command.clone(),
             repo_root: self.repo_root.clone(),
-            local_source: LocalSource::Directory { abs_dir, rel_dir },
+            local_source: LocalSource::Directory {
+                abs_dir,
+                rel_dir,
+                unknown_api_policy: self.settings.unknown_api_policy,
+            },
             default_blessed_branch,
             vcs: self.vcs.clone(),
+            settings: self.settings.clone(),
+            override_freeze: false,
+            wait_for_lock: false,
+            read_only: false,
         })
     }
 }
 
+impl Environment {
+    /// Like [`Self::resolve`], but reads local documents from `documents_dir`,
+    /// which may be outside the repository, e.g. a copy of the documents
+    /// directory exported for packaging.
+    ///
+    /// `documents_dir` takes the place of the documents directory (before any
+    /// namespace is applied). Paths within VCS, e.g. for loading blessed
+    /// documents, still use `openapi_dir` or the default documents directory.
+    pub(crate) fn resolve_with_documents_dir(
+        &self,
+        openapi_dir: Option<Utf8PathBuf>,
+        documents_dir: &Utf8Path,
+    ) -> anyhow::Result<ResolvedEnv> {
+        let env = self.resolve(openapi_dir)?;
+        let abs_dir =
+            camino::absolute_utf8(documents_dir).with_context(|| {
+                format!(
+                    "error making documents directory absolute: {}",
+                    documents_dir
+                )
+            })?;
+        if !abs_dir.is_dir() {
+            return Err(anyhow::anyhow!(
+                "documents directory {} does not exist or is not a directory",
+                abs_dir
+            ));
+        }
+        let abs_dir = match &self.namespace {
+            Some(namespace) => abs_dir.join(namespace),
+            None => abs_dir,
+        };
+        Ok(env.with_documents_dir(&abs_dir))
+    }
+}
+
 /// Validate that `repo_root` is absolute and `default_openapi_dir` is a
 /// normal relative path.
 fn validate_paths(
@@ -254,6 +646,18 @@ pub(crate) struct ResolvedEnv {
     pub(crate) local_source: LocalSource,
     pub(crate) default_blessed_branch: String,
     pub(crate) vcs: RepoVcs,
+    pub(crate) settings: EnvSettings,
+    /// If true, changes to latest documents frozen with a [`DocumentFreeze`]
+    /// are allowed.
+    ///
+    /// [`DocumentFreeze`]: crate::DocumentFreeze
+    pub(crate) override_freeze: bool,
+    /// If true, `generate` waits for other processes updating the documents
+    /// directory to finish, rather than failing.
+    pub(crate) wait_for_lock: bool,
+    /// If true, nothing may be written to the documents directory, and fixes
+    /// fail with an error rather than being applied.
+    pub(crate) read_only: bool,
 }
 
 impl ResolvedEnv {
@@ -268,6 +672,77 @@ impl ResolvedEnv {
             LocalSource::Directory { rel_dir, .. } => rel_dir,
         }
     }
+
+    /// Returns an environment that reads and writes local documents in
+    /// `abs_dir` rather than in the documents directory.
+    ///
+    /// Paths within VCS, e.g. for loading blessed documents, are unchanged.
+    pub(crate) fn with_documents_dir(&self, abs_dir: &Utf8Path) -> ResolvedEnv {
+        let LocalSource::Directory { rel_dir, unknown_api_policy, .. } =
+            &self.local_source;
+        ResolvedEnv {
+            command: self.command.clone(),
+            repo_root: self.repo_root.clone(),
+            local_source: LocalSource::Directory {
+                abs_dir: abs_dir.to_owned(),
+                rel_dir: rel_dir.clone(),
+                unknown_api_policy: *unknown_api_policy,
+            },
+            default_blessed_branch: self.default_blessed_branch.clone(),
+            vcs: self.vcs.clone(),
+            settings: self.settings.clone(),
+            override_freeze: self.override_freeze,
+            wait_for_lock: self.wait_for_lock,
+            read_only: self.read_only,
+        }
+    }
+
+    /// Returns a copy of this environment in which nothing may be written to
+    /// the documents directory, for commands that only read documents.
+    ///
+    /// This way, a read-only checkout produces a precise error, rather than
+    /// failing in some unrelated way, if such a command ever attempts a
+    /// write.
+    pub(crate) fn to_read_only(&self) -> ResolvedEnv {
+        let mut env = self.with_documents_dir(self.openapi_abs_dir());
+        env.read_only = true;
+        env
+    }
+
+    /// Returns an environment for the worktree at `worktree_root`, a checkout
+    /// of this repository that contains the merge of HEAD with `base`.
+    ///
+    /// Local documents are loaded from the worktree, and blessed documents
+    /// default to coming from `base`.
+    pub(crate) fn for_merge_worktree(
+        &self,
+        worktree_root: &Utf8Path,
+        base: &VcsRevision,
+    ) -> ResolvedEnv {
+        let LocalSource::Directory { rel_dir, unknown_api_policy, .. } =
+            &self.local_source;
+        // The generation timings state file isn't under version control, so
+        // it stays in the working copy.
+        let mut settings = self.settings.clone();
+        settings.generation_timings = settings
+            .generation_timings
+            .map(|timings| timings.anchored_at(&self.repo_root));
+        ResolvedEnv {
+            command: self.command.clone(),
+            repo_root: worktree_root.to_owned(),
+            local_source: LocalSource::Directory {
+                abs_dir: worktree_root.join(rel_dir),
+                rel_dir: rel_dir.clone(),
+                unknown_api_policy: *unknown_api_policy,
+            },
+            default_blessed_branch: base.to_string(),
+            vcs: self.vcs.clone(),
+            settings,
+            override_freeze: self.override_freeze,
+            wait_for_lock: self.wait_for_lock,
+            read_only: self.read_only,
+        }
+    }
 }
 
 /// Specifies where to find blessed OpenAPI documents (the ones that are
@@ -283,9 +758,51 @@ pub enum BlessedSource {
     ///
     /// This is basically for testing and debugging this tool.
     Directory { local_directory: Utf8PathBuf },
+
+    /// There are no blessed OpenAPI documents, because the documents aren't
+    /// under version control.
+    None,
+
+    /// Blessed OpenAPI documents are not loaded, and the working tree is
+    /// treated as authoritative.
+    ///
+    /// This is set by `--no-blessed`.
+    Disabled,
+
+    /// Blessed OpenAPI documents come from `main`, plus any additional
+    /// versions from the VCS merge base between the current working state and
+    /// `stack_base`.
+    ///
+    /// This is set by `--also-blessed-from`, for changes stacked on top of
+    /// another unmerged change.
+    WithStackBase {
+        main: Box<BlessedSource>,
+        stack_base: VcsRevision,
+        directory: Utf8PathBuf,
+    },
+
+    /// Blessed OpenAPI documents come from a baseline written by
+    /// `pack-baseline`.
+    ///
+    /// This is set by `--baseline`. No VCS operations are performed to load
+    /// blessed documents.
+    Baseline { path: Utf8PathBuf },
 }
 
 impl BlessedSource {
+    /// Returns the source used when no options choose one: the merge base
+    /// with `env`'s default blessed branch, or no documents if the API
+    /// manager runs without version control.
+    pub(crate) fn default_for(env: &ResolvedEnv) -> BlessedSource {
+        if env.vcs.kind() == RepoVcsKind::None {
+            return BlessedSource::None;
+        }
+        BlessedSource::VcsRevisionMergeBase {
+            revision: VcsRevision::from(env.default_blessed_branch.clone()),
+            directory: Utf8PathBuf::from(env.openapi_rel_dir()),
+        }
+    }
+
     /// Load the blessed OpenAPI documents.
     pub fn load(
         &self,
@@ -293,6 +810,7 @@ impl BlessedSource {
         apis: &ManagedApis,
         styles: &Styles,
         vcs: &RepoVcs,
+        ignore_globs: &IgnoreGlobs,
     ) -> anyhow::Result<(BlessedFiles, ErrorAccumulator)> {
         let mut errors = ErrorAccumulator::new();
         match self {
@@ -309,9 +827,118 @@ impl BlessedSource {
                         &mut errors,
                         repo_root,
                         vcs,
+                        ignore_globs,
+                    )?;
+                let unknown_apis = find_unknown_api_entries(
+                    local_directory,
+                    apis,
+                    ignore_globs,
+                )?
+                .into_keys()
+                .collect();
+                Ok((
+                    BlessedFiles::from(api_files)
+                        .with_unknown_apis(unknown_apis),
+                    errors,
+                ))
+            }
+            BlessedSource::Baseline { path } => {
+                eprintln!(
+                    "{:>HEADER_WIDTH$} blessed OpenAPI documents from \
+                     baseline {:?}",
+                    "Loading".style(styles.success_header),
+                    path,
+                );
+                let temp_dir =
+                    Utf8TempDir::with_prefix("dropshot-api-manager-baseline-")
+                        .context("creating temporary directory for baseline")?;
+                let manifest = unpack_baseline(path, temp_dir.path())
+                    .with_context(|| format!("loading baseline {}", path))?;
+                if let Some(provenance) = &manifest.provenance {
+                    eprintln!(
+                        "{:>HEADER_WIDTH$} blessed OpenAPI documents packed \
+                         from {}",
+                        "Resolved".style(styles.success_header),
+                        provenance,
+                    );
+                }
+                let api_files: ApiSpecFilesBuilder<'_, BlessedApiSpecFile> =
+                    walk_local_directory(
+                        temp_dir.path(),
+                        apis,
+                        &mut errors,
+                        repo_root,
+                        vcs,
+                        ignore_globs,
                     )?;
                 Ok((BlessedFiles::from(api_files), errors))
             }
+            BlessedSource::None => {
+                eprintln!(
+                    "{:>HEADER_WIDTH$} blessed OpenAPI documents (not under \
+                     version control)",
+                    "Skipping".style(styles.success_header),
+                );
+                // Without version control, there are no blessed documents to
+                // check versioned APIs against.
+                let versioned: Vec<_> = apis
+                    .iter_apis()
+                    .filter(|a| a.is_versioned())
+                    .map(|a| a.ident().to_string())
+                    .collect();
+                if !versioned.is_empty() {
+                    errors.error(anyhow::anyhow!(
+                        "versioned APIs require the OpenAPI documents to be \
+                         in a Git or Jujutsu repository, but the API manager \
+                         is configured to run without version control \
+                         (versioned APIs: {}). To only check that local \
+                         documents match generated ones, pass --no-blessed.",
+                        versioned.join(", "),
+                    ));
+                }
+                let api_files: ApiSpecFilesBuilder<'_, BlessedApiSpecFile> =
+                    ApiSpecFilesBuilder::new(apis, &mut errors);
+                Ok((BlessedFiles::from(api_files), errors))
+            }
+            BlessedSource::Disabled => {
+                eprintln!(
+                    "{:>HEADER_WIDTH$} blessed OpenAPI documents \
+                     (--no-blessed was specified)",
+                    "Skipping".style(styles.success_header),
+                );
+                let api_files: ApiSpecFilesBuilder<'_, BlessedApiSpecFile> =
+                    ApiSpecFilesBuilder::new(apis, &mut errors);
+                Ok((BlessedFiles::from(api_files), errors))
+            }
+            BlessedSource::WithStackBase { main, stack_base, directory } => {
+                let (mut blessed, mut errors) =
+                    main.load(repo_root, apis, styles, vcs, ignore_globs)?;
+                eprintln!(
+                    "{:>HEADER_WIDTH$} additional blessed OpenAPI documents \
+                     from stack base {:?} path {:?}",
+                    "Loading".style(styles.success_header),
+                    stack_base,
+                    directory
+                );
+                let stack_blessed = BlessedFiles::load_from_vcs_parent_branch(
+                    repo_root,
+                    stack_base,
+                    directory,
+                    apis,
+                    &mut errors,
+                    vcs,
+                )?;
+                if let Some(provenance) = stack_blessed.provenance() {
+                    eprintln!(
+                        "{:>HEADER_WIDTH$} additional blessed OpenAPI \
+                         documents from {}",
+                        "Resolved".style(styles.success_header),
+                        provenance,
+                    );
+                }
+                blessed.add_from_stack_base(stack_blessed);
+                Ok((blessed, errors))
+            }
             BlessedSource::VcsRevisionMergeBase { revision, directory } => {
                 eprintln!(
                     "{:>HEADER_WIDTH$} blessed OpenAPI documents from VCS \
@@ -320,17 +947,22 @@ impl BlessedSource {
                     revision,
                     directory
                 );
-                Ok((
-                    BlessedFiles::load_from_vcs_parent_branch(
-                        repo_root,
-                        revision,
-                        directory,
-                        apis,
-                        &mut errors,
-                        vcs,
-                    )?,
-                    errors,
-                ))
+                let blessed = BlessedFiles::load_from_vcs_parent_branch(
+                    repo_root,
+                    revision,
+                    directory,
+                    apis,
+                    &mut errors,
+                    vcs,
+                )?;
+                if let Some(provenance) = blessed.provenance() {
+                    eprintln!(
+                        "{:>HEADER_WIDTH$} blessed OpenAPI documents from {}",
+                        "Resolved".style(styles.success_header),
+                        provenance,
+                    );
+                }
+                Ok((blessed, errors))
             }
         }
     }
@@ -340,7 +972,11 @@ impl BlessedSource {
 #[derive(Debug)]
 pub enum GeneratedSource {
     /// Generate OpenAPI documents from the API implementation (default)
-    Generated,
+    Generated {
+        /// Whether to generate each document a second time, and report an
+        /// error if the two copies differ.
+        verify_deterministic: bool,
+    },
 
     /// Load "generated" OpenAPI documents from the specified directory
     ///
@@ -349,23 +985,32 @@ pub enum GeneratedSource {
 }
 
 impl GeneratedSource {
-    /// Load the generated OpenAPI documents (i.e., generating them as needed).
-    pub fn load(
+    /// Load the generated OpenAPI documents (i.e., generating them as needed)
+    /// with the settings in `env`.
+    pub(crate) fn load(
         &self,
         apis: &ManagedApis,
         styles: &Styles,
-        repo_root: &Utf8Path,
-        vcs: &RepoVcs,
+        env: &ResolvedEnv,
     ) -> anyhow::Result<(GeneratedFiles, ErrorAccumulator)> {
         let mut errors = ErrorAccumulator::new();
         match self {
-            GeneratedSource::Generated => {
+            GeneratedSource::Generated { verify_deterministic } => {
                 eprintln!(
                     "{:>HEADER_WIDTH$} OpenAPI documents from API \
                      definitions ... ",
                     GENERATING.style(styles.success_header)
                 );
-                Ok((GeneratedFiles::generate(apis, &mut errors)?, errors))
+                Ok((
+                    GeneratedFiles::generate(
+                        apis,
+                        env.settings.file_name_hash,
+                        env.settings.openapi_version.as_deref(),
+                        *verify_deterministic,
+                        &mut errors,
+                    )?,
+                    errors,
+                ))
             }
             GeneratedSource::Directory { local_directory } => {
                 eprintln!(
@@ -378,8 +1023,9 @@ impl GeneratedSource {
                     local_directory,
                     apis,
                     &mut errors,
-                    repo_root,
-                    vcs,
+                    &env.repo_root,
+                    &env.vcs,
+                    &env.settings.ignore_globs,
                 )?;
                 Ok((GeneratedFiles::from(api_files), errors))
             }
@@ -397,6 +1043,8 @@ pub enum LocalSource {
         /// The directory path relative to the repo root. Used for VCS commands
         /// that read contents of other commits.
         rel_dir: Utf8PathBuf,
+        /// What to do about files for APIs that aren't managed.
+        unknown_api_policy: UnknownApiPolicy,
     },
 }
 
@@ -410,6 +1058,7 @@ impl LocalSource {
         styles: &Styles,
         repo_root: &Utf8Path,
         vcs: &RepoVcs,
+        ignore_globs: &IgnoreGlobs,
     ) -> anyhow::Result<(LocalFiles, ErrorAccumulator)> {
         let mut errors = ErrorAccumulator::new();
 
@@ -428,30 +1077,116 @@ impl LocalSource {
             return Ok((LocalFiles::default(), errors));
         }
 
+        // If the documents directory is excluded by a sparse checkout, every
+        // local file would show up as missing, and the suggested fixes would
+        // be destructive. Report a single error instead.
+        let LocalSource::Directory { rel_dir, .. } = self;
+        if vcs.is_excluded_by_sparse_checkout(repo_root, rel_dir) {
+            let suggestion = match vcs.kind() {
+                RepoVcsKind::Git => {
+                    format!("git sparse-checkout add {rel_dir}")
+                }
+                RepoVcsKind::Jj => format!("jj sparse set --add {rel_dir}"),
+                RepoVcsKind::None => {
+                    unreachable!("sparse checkouts require version control")
+                }
+            };
+            errors.error(anyhow::anyhow!(
+                "the OpenAPI documents directory {rel_dir} is excluded from \
+                 this sparse checkout, so local documents cannot be checked. \
+                 To fix this, add the directory to your sparse set (e.g. \
+                 `{suggestion}`)."
+            ));
+            return Ok((LocalFiles::default(), errors));
+        }
+
         match self {
-            LocalSource::Directory { abs_dir, .. } => {
+            LocalSource::Directory { abs_dir, unknown_api_policy, .. } => {
                 eprintln!(
                     "{:>HEADER_WIDTH$} local OpenAPI documents from \
                      {:?} ... ",
                     "Loading".style(styles.success_header),
                     abs_dir,
                 );
-                Ok((
-                    LocalFiles::load_from_directory(
-                        abs_dir,
-                        apis,
-                        &mut errors,
-                        repo_root,
-                        vcs,
-                    )?,
-                    errors,
-                ))
+
+                // Files for removed APIs, and unless they're errors, files for
+                // other unknown APIs are set aside before loading the rest.
+                let (removed, mut unknown): (BTreeMap<_, _>, BTreeMap<_, _>) =
+                    find_unknown_api_entries(abs_dir, apis, ignore_globs)?
+                        .into_iter()
+                        .partition(|(ident, _)| apis.is_removed_api(ident));
+                if *unknown_api_policy == UnknownApiPolicy::Error {
+                    unknown.clear();
+                }
+                let ignore_globs = ignore_globs.with_patterns(
+                    removed
+                        .values()
+                        .chain(unknown.values())
+                        .flatten()
+                        .map(|path| globset::escape(path.as_str())),
+                )?;
+                let local_files = LocalFiles::load_from_directory(
+                    abs_dir,
+                    apis,
+                    &mut errors,
+                    repo_root,
+                    vcs,
+                    &ignore_globs,
+                )?;
+
+                let mut to_delete = removed;
+                match unknown_api_policy {
+                    UnknownApiPolicy::Error => {}
+                    UnknownApiPolicy::Warn => {
+                        for (ident, paths) in &unknown {
+                            let paths: Vec<_> =
+                                paths.iter().map(|p| p.as_str()).collect();
+                            errors.warning(anyhow::anyhow!(
+                                "skipping files for unknown API {}: {}",
+                                ident,
+                                paths.join(", "),
+                            ));
+                        }
+                    }
+                    UnknownApiPolicy::Delete => to_delete.extend(unknown),
+                }
+                let mut local_files =
+                    local_files.with_unknown_api_files(to_delete);
+
+                if let Some(approvals) = apis.breaking_change_approvals() {
+                    match approvals.load(repo_root) {
+                        Ok(loaded) => {
+                            local_files = local_files
+                                .with_breaking_change_approvals(loaded);
+                        }
+                        Err(error) => errors.error(error.context(format!(
+                            "invalid breaking change approvals in {}",
+                            approvals.path(),
+                        ))),
+                    }
+                }
+                if let Some(pins) = apis.consumer_pins() {
+                    match pins.load(repo_root, apis) {
+                        Ok(loaded) => {
+                            local_files =
+                                local_files.with_consumer_pins(loaded);
+                        }
+                        Err(error) => errors.error(error),
+                    }
+                }
+                Ok((local_files, errors))
             }
         }
     }
 }
 
-/// Stores errors and warnings accumulated during loading
+/// Stores errors and warnings accumulated while loading OpenAPI documents.
+///
+/// Errors make the loaded data incorrect or incomplete, and cause commands to
+/// bail out after they're printed. Warnings (e.g. skipped files, or APIs that
+/// are configured differently than when their documents were blessed) don't,
+/// but are still available here so that callers can inspect them.
+#[derive(Debug, Default)]
 pub struct ErrorAccumulator {
     /// errors that reflect incorrectness or incompleteness of the loaded data
     errors: Vec<anyhow::Error>,
@@ -459,7 +1194,19 @@ pub struct ErrorAccumulator {
     warnings: Vec<anyhow::Error>,
 }
 
+/// Whether a problem recorded in an [`ErrorAccumulator`] is an error or a
+/// warning.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum LoadProblemSeverity {
+    /// The loaded data is incorrect or incomplete.
+    Error,
+    /// The problem doesn't affect the correctness or completeness of the
+    /// loaded data.
+    Warning,
+}
+
 impl ErrorAccumulator {
+    /// Creates an empty accumulator.
     pub fn new() -> ErrorAccumulator {
         ErrorAccumulator { errors: Vec::new(), warnings: Vec::new() }
     }
@@ -474,13 +1221,39 @@ impl ErrorAccumulator {
         self.warnings.push(error);
     }
 
+    /// Moves all problems recorded in `other` into `self`.
+    pub fn extend(&mut self, other: ErrorAccumulator) {
+        self.errors.extend(other.errors);
+        self.warnings.extend(other.warnings);
+    }
+
+    /// Returns true if no errors or warnings were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty() && self.warnings.is_empty()
+    }
+
+    /// Iterates over the recorded errors.
     pub fn iter_errors(&self) -> impl Iterator<Item = &'_ anyhow::Error> + '_ {
         self.errors.iter()
     }
 
+    /// Iterates over the recorded warnings.
     pub fn iter_warnings(
         &self,
     ) -> impl Iterator<Item = &'_ anyhow::Error> + '_ {
         self.warnings.iter()
     }
+
+    /// Iterates over all recorded problems along with their severity, warnings
+    /// first, in the order they're printed.
+    pub fn iter(
+        &self,
+    ) -> impl Iterator<Item = (LoadProblemSeverity, &'_ anyhow::Error)> + '_
+    {
+        let warnings =
+            self.warnings.iter().map(|w| (LoadProblemSeverity::Warning, w));
+        let errors =
+            self.errors.iter().map(|e| (LoadProblemSeverity::Error, e));
+        warnings.chain(errors)
+    }
 }
@@ -0,0 +1,646 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Lints the examples of request and response bodies in OpenAPI documents.
+//!
+//! See [`ExampleLint`] for more.
+
+use crate::compatibility::{PATH_ITEM_METHODS, escape_json_pointer};
+use anyhow::anyhow;
+use dropshot_api_manager_types::ValidationContext;
+use openapiv3::OpenAPI;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+
+/// How deeply schemas (including `$ref`s) may be nested while validating an
+/// example, to guard against cyclic references.
+const MAX_DEPTH: usize = 128;
+
+/// Lints the examples of request and response bodies in an API's OpenAPI
+/// documents.
+///
+/// Attach this to an API with [`ManagedApi::with_example_lint`]. Every example
+/// of a request or response body is then validated against the body's schema
+/// as part of validation. Examples may be given on the media type (`example`
+/// or `examples`), or on the body's schema itself.
+///
+/// Operations can also be required to have examples, by tag
+/// ([`ExampleLint::with_required_for_tag`]) or by path prefix
+/// ([`ExampleLint::with_required_for_path_prefix`]). For such operations,
+/// the request body and every successful (2xx) response body must have at
+/// least one example.
+///
+/// Blessed documents can't be changed, so they aren't linted.
+///
+/// Schemas are interpreted as in OpenAPI 3.0. The `pattern` and `format`
+/// keywords aren't checked.
+///
+/// [`ManagedApi::with_example_lint`]: crate::ManagedApi::with_example_lint
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ExampleLint {
+    required_tags: BTreeSet<String>,
+    required_path_prefixes: Vec<String>,
+}
+
+impl ExampleLint {
+    /// Creates a lint that validates examples, without requiring any.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires examples for operations tagged with `tag`.
+    pub fn with_required_for_tag(mut self, tag: impl Into<String>) -> Self {
+        self.required_tags.insert(tag.into());
+        self
+    }
+
+    /// Requires examples for operations whose path starts with `prefix`.
+    pub fn with_required_for_path_prefix(
+        mut self,
+        prefix: impl Into<String>,
+    ) -> Self {
+        self.required_path_prefixes.push(prefix.into());
+        self
+    }
+
+    /// Returns the tags of operations that require examples.
+    pub fn required_tags(&self) -> &BTreeSet<String> {
+        &self.required_tags
+    }
+
+    /// Returns the path prefixes of operations that require examples.
+    pub fn required_path_prefixes(&self) -> &[String] {
+        &self.required_path_prefixes
+    }
+
+    /// Lints the document, reporting each issue as a validation error.
+    pub(crate) fn validate(
+        &self,
+        openapi: &OpenAPI,
+        mut cx: ValidationContext<'_>,
+    ) {
+        if cx.is_blessed() == Some(true) {
+            return;
+        }
+
+        let document = match serde_json::to_value(openapi) {
+            Ok(document) => document,
+            Err(error) => {
+                cx.report_error(
+                    anyhow!(error).context("serializing document to lint it"),
+                );
+                return;
+            }
+        };
+        for issue in self.lint_document(&document) {
+            cx.report_error(anyhow!(issue));
+        }
+    }
+
+    fn lint_document(&self, document: &Value) -> Vec<String> {
+        let mut issues = Vec::new();
+        let Some(paths) = document.get("paths").and_then(Value::as_object)
+        else {
+            return issues;
+        };
+
+        for (path, item) in paths {
+            let item = resolve(document, item);
+            for method in PATH_ITEM_METHODS {
+                let Some(operation) = item.get(*method) else {
+                    continue;
+                };
+                let operation_name =
+                    format!("{} {}", method.to_uppercase(), path);
+                let required = self.is_required(path, operation);
+
+                if let Some(body) = operation.get("requestBody") {
+                    let body = resolve(document, body);
+                    lint_content(
+                        document,
+                        &format!("{} request body", operation_name),
+                        body,
+                        required,
+                        &mut issues,
+                    );
+                }
+
+                let responses = operation
+                    .get("responses")
+                    .and_then(Value::as_object)
+                    .into_iter()
+                    .flatten();
+                for (status, response) in responses {
+                    let response = resolve(document, response);
+                    // Error responses are usually shared, so examples are
+                    // only required for successful ones.
+                    let is_success = status.starts_with('2');
+                    lint_content(
+                        document,
+                        &format!("{} response {}", operation_name, status),
+                        response,
+                        required && is_success,
+                        &mut issues,
+                    );
+                }
+            }
+        }
+
+        issues
+    }
+
+    /// Returns true if the operation at `path` requires examples.
+    fn is_required(&self, path: &str, operation: &Value) -> bool {
+        let tagged = operation
+            .get("tags")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .any(|tag| self.required_tags.contains(tag));
+        tagged
+            || self
+                .required_path_prefixes
+                .iter()
+                .any(|prefix| path.starts_with(prefix.as_str()))
+    }
+}
+
+/// Lints the examples of each media type of a request or response body.
+fn lint_content(
+    document: &Value,
+    body_name: &str,
+    body: &Value,
+    required: bool,
+    issues: &mut Vec<String>,
+) {
+    let Some(content) = body.get("content").and_then(Value::as_object) else {
+        return;
+    };
+    for (media_type_name, media_type) in content {
+        let schema = media_type.get("schema");
+        let examples = media_type_examples(document, media_type, schema);
+        if required && examples.is_empty() {
+            issues.push(format!(
+                "{} ({}) has no example",
+                body_name, media_type_name
+            ));
+        }
+
+        let Some(schema) = schema else {
+            continue;
+        };
+        for (example_name, example) in examples {
+            let mut errors = Vec::new();
+            check_value(document, schema, example, "", 0, &mut errors);
+            for error in errors {
+                issues.push(format!(
+                    "{} ({}): {} is invalid: {}",
+                    body_name, media_type_name, example_name, error
+                ));
+            }
+        }
+    }
+}
+
+/// Returns the examples of a media type, along with names to report them by.
+///
+/// If the media type has no examples of its own, the example on its schema
+/// (if any) is used.
+fn media_type_examples<'a>(
+    document: &'a Value,
+    media_type: &'a Value,
+    schema: Option<&'a Value>,
+) -> Vec<(String, &'a Value)> {
+    let mut examples = Vec::new();
+    if let Some(example) = media_type.get("example") {
+        examples.push(("example".to_owned(), example));
+    }
+    let named = media_type
+        .get("examples")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flatten();
+    for (name, example) in named {
+        // Examples given by `externalValue` can't be checked.
+        if let Some(value) = resolve(document, example).get("value") {
+            examples.push((format!("example {:?}", name), value));
+        }
+    }
+
+    if examples.is_empty()
+        && let Some(example) =
+            schema.and_then(|schema| resolve(document, schema).get("example"))
+    {
+        examples.push(("schema example".to_owned(), example));
+    }
+    examples
+}
+
+/// Resolves a local `$ref` within `document`, returning `value` itself if it
+/// isn't a reference (or the reference can't be resolved).
+fn resolve<'a>(document: &'a Value, value: &'a Value) -> &'a Value {
+    let mut value = value;
+    for _ in 0..MAX_DEPTH {
+        match value.get("$ref").and_then(Value::as_str) {
+            Some(reference) => match resolve_ref(document, reference) {
+                Some(target) => value = target,
+                None => break,
+            },
+            None => break,
+        }
+    }
+    value
+}
+
+fn resolve_ref<'a>(document: &'a Value, reference: &str) -> Option<&'a Value> {
+    document.pointer(reference.strip_prefix('#')?)
+}
+
+/// Checks `value` (found at the JSON pointer `pointer` within an example)
+/// against `schema`, appending any errors to `errors`.
+fn check_value(
+    document: &Value,
+    schema: &Value,
+    value: &Value,
+    pointer: &str,
+    depth: usize,
+    errors: &mut Vec<String>,
+) {
+    let at = if pointer.is_empty() { "/" } else { pointer };
+    if depth > MAX_DEPTH {
+        errors.push(format!("{}: schema is nested too deeply", at));
+        return;
+    }
+
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        match resolve_ref(document, reference) {
+            Some(target) => {
+                check_value(document, target, value, pointer, depth + 1, errors)
+            }
+            None => {
+                errors.push(format!("{}: unresolved $ref {}", at, reference))
+            }
+        }
+        return;
+    }
+
+    if value.is_null()
+        && schema.get("nullable").and_then(Value::as_bool) == Some(true)
+    {
+        return;
+    }
+
+    let matches = |schema: &Value| {
+        let mut errors = Vec::new();
+        check_value(document, schema, value, pointer, depth + 1, &mut errors);
+        errors
+    };
+    if let Some(Value::Array(schemas)) = schema.get("allOf") {
+        for schema in schemas {
+            errors.extend(matches(schema));
+        }
+    }
+    if let Some(Value::Array(schemas)) = schema.get("anyOf")
+        && !schemas.iter().any(|schema| matches(schema).is_empty())
+    {
+        errors.push(format!("{}: matches none of the anyOf schemas", at));
+    }
+    if let Some(Value::Array(schemas)) = schema.get("oneOf") {
+        let count =
+            schemas.iter().filter(|schema| matches(schema).is_empty()).count();
+        if count != 1 {
+            errors.push(format!(
+                "{}: matches {} of the oneOf schemas (expected exactly 1)",
+                at, count
+            ));
+        }
+    }
+    if let Some(not) = schema.get("not")
+        && matches(not).is_empty()
+    {
+        errors.push(format!("{}: matches the schema under `not`", at));
+    }
+
+    if let Some(Value::Array(variants)) = schema.get("enum")
+        && !variants.contains(value)
+    {
+        errors.push(format!("{}: {} is not one of the enum values", at, value));
+    }
+
+    if let Some(ty) = schema.get("type") {
+        let types: Vec<&str> = match ty {
+            Value::String(ty) => vec![ty.as_str()],
+            Value::Array(types) => {
+                types.iter().filter_map(Value::as_str).collect()
+            }
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|ty| has_type(value, ty)) {
+            errors.push(format!(
+                "{}: expected {}, found {}",
+                at,
+                types.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    match value {
+        Value::String(s) => check_string(schema, s, at, errors),
+        Value::Number(_) => check_number(schema, value, at, errors),
+        Value::Array(values) => {
+            check_array(document, schema, values, pointer, depth, errors)
+        }
+        Value::Object(map) => {
+            check_object(document, schema, map, pointer, depth, errors)
+        }
+        Value::Null | Value::Bool(_) => {}
+    }
+}
+
+fn check_string(
+    schema: &Map<String, Value>,
+    s: &str,
+    at: &str,
+    errors: &mut Vec<String>,
+) {
+    let len = s.chars().count() as u64;
+    if let Some(min) = schema.get("minLength").and_then(Value::as_u64)
+        && len < min
+    {
+        errors.push(format!("{}: shorter than {} characters", at, min));
+    }
+    if let Some(max) = schema.get("maxLength").and_then(Value::as_u64)
+        && len > max
+    {
+        errors.push(format!("{}: longer than {} characters", at, max));
+    }
+}
+
+fn check_number(
+    schema: &Map<String, Value>,
+    value: &Value,
+    at: &str,
+    errors: &mut Vec<String>,
+) {
+    let Some(n) = value.as_f64() else {
+        return;
+    };
+    let exclusive =
+        |key| schema.get(key).and_then(Value::as_bool) == Some(true);
+    if let Some(min) = schema.get("minimum").and_then(Value::as_f64)
+        && (n < min || (exclusive("exclusiveMinimum") && n == min))
+    {
+        errors.push(format!("{}: {} is below the minimum {}", at, value, min));
+    }
+    if let Some(max) = schema.get("maximum").and_then(Value::as_f64)
+        && (n > max || (exclusive("exclusiveMaximum") && n == max))
+    {
+        errors.push(format!("{}: {} is above the maximum {}", at, value, max));
+    }
+}
+
+fn check_array(
+    document: &Value,
+    schema: &Map<String, Value>,
+    values: &[Value],
+    pointer: &str,
+    depth: usize,
+    errors: &mut Vec<String>,
+) {
+    let at = if pointer.is_empty() { "/" } else { pointer };
+    let len = values.len() as u64;
+    if let Some(min) = schema.get("minItems").and_then(Value::as_u64)
+        && len < min
+    {
+        errors.push(format!("{}: fewer than {} items", at, min));
+    }
+    if let Some(max) = schema.get("maxItems").and_then(Value::as_u64)
+        && len > max
+    {
+        errors.push(format!("{}: more than {} items", at, max));
+    }
+    if schema.get("uniqueItems").and_then(Value::as_bool) == Some(true)
+        && values
+            .iter()
+            .enumerate()
+            .any(|(i, value)| values[..i].contains(value))
+    {
+        errors.push(format!("{}: items are not unique", at));
+    }
+    if let Some(items) = schema.get("items") {
+        for (i, value) in values.iter().enumerate() {
+            check_value(
+                document,
+                items,
+                value,
+                &format!("{}/{}", pointer, i),
+                depth + 1,
+                errors,
+            );
+        }
+    }
+}
+
+fn check_object(
+    document: &Value,
+    schema: &Map<String, Value>,
+    map: &Map<String, Value>,
+    pointer: &str,
+    depth: usize,
+    errors: &mut Vec<String>,
+) {
+    let at = if pointer.is_empty() { "/" } else { pointer };
+    let required =
+        schema.get("required").and_then(Value::as_array).into_iter().flatten();
+    for name in required.filter_map(Value::as_str) {
+        if !map.contains_key(name) {
+            errors
+                .push(format!("{}: missing required property {:?}", at, name));
+        }
+    }
+
+    let properties = schema.get("properties").and_then(Value::as_object);
+    let additional = schema.get("additionalProperties");
+    for (name, value) in map {
+        let child = format!("{}/{}", pointer, escape_json_pointer(name));
+        match properties.and_then(|properties| properties.get(name)) {
+            Some(property) => check_value(
+                document,
+                property,
+                value,
+                &child,
+                depth + 1,
+                errors,
+            ),
+            None => match additional {
+                Some(Value::Bool(false)) => errors
+                    .push(format!("{}: unexpected property {:?}", at, name)),
+                Some(additional @ Value::Object(_)) => check_value(
+                    document,
+                    additional,
+                    value,
+                    &child,
+                    depth + 1,
+                    errors,
+                ),
+                _ => {}
+            },
+        }
+    }
+}
+
+/// Returns true if `value` is of the JSON Schema type `ty`.
+fn has_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64()
+                || value.is_u64()
+                || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        // Unknown types aren't checked.
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lint_document() {
+        let thing = json!({ "$ref": "#/components/schemas/Thing" });
+        let doc = json!({
+            "paths": {
+                "/other": {
+                    "get": {
+                        "responses": {
+                            "200": {
+                                "content": {
+                                    "application/json": {
+                                        "schema": {
+                                            "type": "array",
+                                            "items": { "type": "integer" },
+                                        },
+                                        "example": [1, 2.5],
+                                    },
+                                },
+                            },
+                        },
+                    },
+                },
+                "/things": {
+                    "post": {
+                        "tags": ["things"],
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": thing,
+                                    "examples": {
+                                        "good": {
+                                            "value": { "name": "a", "size": 1 },
+                                        },
+                                        "bad": {
+                                            "value": { "size": -1, "x": 1 },
+                                        },
+                                    },
+                                },
+                            },
+                        },
+                        "responses": {
+                            "201": {
+                                "content": {
+                                    "application/json": {
+                                        "schema": thing,
+                                    },
+                                },
+                            },
+                            "4XX": {
+                                "$ref": "#/components/responses/Error",
+                            },
+                        },
+                    },
+                },
+            },
+            "components": {
+                "schemas": {
+                    "Thing": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string", "minLength": 1 },
+                            "size": { "type": "integer", "minimum": 0 },
+                            "note": { "type": "string", "nullable": true },
+                        },
+                        "required": ["name", "size"],
+                        "additionalProperties": false,
+                    },
+                },
+                "responses": {
+                    "Error": {
+                        "content": {
+                            "application/json": {
+                                "schema": { "type": "object" },
+                            },
+                        },
+                    },
+                },
+            },
+        });
+
+        // Examples are validated even if they aren't required.
+        assert_eq!(
+            ExampleLint::new().lint_document(&doc),
+            [
+                "GET /other response 200 (application/json): example is \
+                 invalid: /1: expected integer, found number",
+                "POST /things request body (application/json): example \
+                 \"bad\" is invalid: /: missing required property \"name\"",
+                "POST /things request body (application/json): example \
+                 \"bad\" is invalid: /size: -1 is below the minimum 0",
+                "POST /things request body (application/json): example \
+                 \"bad\" is invalid: /: unexpected property \"x\"",
+            ],
+        );
+
+        // Only successful responses require examples.
+        let issues = ExampleLint::new()
+            .with_required_for_tag("things")
+            .lint_document(&doc);
+        assert_eq!(
+            issues.last().map(String::as_str),
+            Some("POST /things response 201 (application/json) has no example"),
+        );
+        assert_eq!(issues.len(), 5);
+
+        // A schema example counts as an example.
+        let mut doc = doc;
+        doc["components"]["schemas"]["Thing"]["example"] =
+            json!({ "name": "a", "size": 1, "note": null });
+        let issues = ExampleLint::new()
+            .with_required_for_path_prefix("/thing")
+            .lint_document(&doc);
+        assert_eq!(issues.len(), 4);
+    }
+}
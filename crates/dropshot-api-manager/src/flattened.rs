@@ -0,0 +1,308 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Maintains a copy of an API's latest document with references inlined.
+//!
+//! See [`FlattenedDocument`] for more.
+
+use crate::{
+    compatibility::unescape_pointer_component,
+    ref_check::{for_each_ref, from_pointer, to_pointer},
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use dropshot_api_manager_types::ValidationContext;
+use openapiv3::OpenAPI;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+
+/// The prefix of references to components, which are inlined.
+const COMPONENTS_PREFIX: &str = "#/components/";
+
+/// A copy of an API's latest document with every `$ref` to a component
+/// inlined, for client generators that handle references poorly.
+///
+/// Attach this to an API with [`ManagedApi::with_flattened_document`]. The
+/// flattened document is checked and generated like any other extra file, for
+/// the latest version of the API.
+///
+/// A reference that's part of a cycle (e.g. a schema for a tree node that
+/// refers to itself) can't be inlined. Such references are left in place, and
+/// the components they refer to are kept, themselves flattened, under
+/// `components`. All other components that references can point to are
+/// removed, while security schemes (which are referred to by name) are kept.
+///
+/// [`ManagedApi::with_flattened_document`]: crate::ManagedApi::with_flattened_document
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FlattenedDocument {
+    path: Utf8PathBuf,
+}
+
+impl FlattenedDocument {
+    /// Creates a flattened document written to `path` (relative to the
+    /// repository root).
+    pub fn new(path: impl Into<Utf8PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns the path to the flattened document, relative to the repository
+    /// root.
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+
+    /// Records the contents of the flattened document, for the latest
+    /// version.
+    pub(crate) fn validate(
+        &self,
+        openapi: &OpenAPI,
+        mut cx: ValidationContext<'_>,
+    ) {
+        if !cx.is_latest() {
+            return;
+        }
+
+        let document = match serde_json::to_value(openapi) {
+            Ok(document) => document,
+            Err(error) => {
+                cx.report_error(anyhow::Error::new(error).context(format!(
+                    "failed to convert document to JSON for {}",
+                    self.path
+                )));
+                return;
+            }
+        };
+        let mut contents = serde_json::to_vec_pretty(&flatten(&document))
+            .expect("serializing a JSON value always succeeds");
+        contents.push(b'\n');
+        cx.record_file_contents(self.path.clone(), contents);
+    }
+}
+
+/// Returns `document` with every reference to a component inlined, other
+/// than references that are part of a cycle.
+fn flatten(document: &Value) -> Value {
+    let Value::Object(object) = document else {
+        return document.clone();
+    };
+    let mut flattener =
+        Flattener { document, stack: Vec::new(), cyclic: BTreeSet::new() };
+    let mut flattened: Map<String, Value> = object
+        .iter()
+        .filter(|(key, _)| *key != "components")
+        .map(|(key, value)| {
+            (key.clone(), flattener.inline(value, &mut vec![key.clone()]))
+        })
+        .collect();
+
+    // Security schemes are referred to by name, not by `$ref`, so they're
+    // kept as they are. Keep (and flatten) cyclic components, too, which may
+    // turn up more cyclic components.
+    let mut components = Map::new();
+    if let Some(schemes) = document.pointer("/components/securitySchemes") {
+        let mut path = from_pointer("/components/securitySchemes");
+        components.insert(
+            "securitySchemes".to_owned(),
+            flattener.inline(schemes, &mut path),
+        );
+    }
+    let mut kept = BTreeSet::new();
+    while let Some(reference) =
+        flattener.cyclic.iter().find(|r| !kept.contains(*r)).copied()
+    {
+        kept.insert(reference);
+        let Some((kind, name)) = component_name(reference) else {
+            continue;
+        };
+        let target = document
+            .pointer(&reference[1..])
+            .expect("cyclic references resolve");
+        flattener.stack.push(reference);
+        let value =
+            flattener.inline(target, &mut from_pointer(&reference[1..]));
+        flattener.stack.pop();
+        if let Value::Object(section) =
+            components.entry(kind).or_insert_with(|| Value::Object(Map::new()))
+        {
+            section.insert(name, value);
+        }
+    }
+    if !components.is_empty() {
+        flattened.insert("components".to_owned(), Value::Object(components));
+    }
+    Value::Object(flattened)
+}
+
+/// Returns the kind (e.g. `schemas`) and name of the component a reference
+/// points to, unescaping JSON pointer syntax.
+fn component_name(reference: &str) -> Option<(String, String)> {
+    let (kind, name) =
+        reference.strip_prefix(COMPONENTS_PREFIX)?.split_once('/')?;
+    if name.contains('/') {
+        return None;
+    }
+    Some((unescape_pointer_component(kind), unescape_pointer_component(name)))
+}
+
+struct Flattener<'a> {
+    document: &'a Value,
+    /// The references being inlined, outermost first.
+    stack: Vec<&'a str>,
+    /// References that were left in place because they're part of a cycle.
+    cyclic: BTreeSet<&'a str>,
+}
+
+impl<'a> Flattener<'a> {
+    /// Returns `value`, found at `path` within the document, with references
+    /// inlined.
+    fn inline(&mut self, value: &'a Value, path: &mut Vec<String>) -> Value {
+        let base_len = path.len();
+        let mut refs = Vec::new();
+        for_each_ref(value, path, &mut |ref_path, reference| {
+            refs.push((to_pointer(&ref_path[base_len..]), reference));
+        });
+
+        // References aren't nested within each other, so replacing one
+        // doesn't move the others.
+        let mut inlined = value.clone();
+        for (pointer, reference) in refs {
+            if let Some(target) = self.inline_ref(reference) {
+                *inlined
+                    .pointer_mut(&pointer)
+                    .expect("references are found within the value") = target;
+            }
+        }
+        inlined
+    }
+
+    /// Returns the inlined target of `reference`, or `None` if the reference
+    /// is left in place.
+    fn inline_ref(&mut self, reference: &'a str) -> Option<Value> {
+        // References to anything other than a component (e.g. in another
+        // file) are left alone, as are references that don't resolve.
+        component_name(reference)?;
+        let target = self.document.pointer(&reference[1..])?;
+        if self.stack.contains(&reference) {
+            self.cyclic.insert(reference);
+            return None;
+        }
+        self.stack.push(reference);
+        let inlined = self.inline(target, &mut from_pointer(&reference[1..]));
+        self.stack.pop();
+        Some(inlined)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_flatten() {
+        let document = json!({
+            "openapi": "3.0.3",
+            "paths": {
+                "/trees": {
+                    "get": {
+                        "responses": {
+                            "200": { "$ref": "#/components/responses/Tree" },
+                        },
+                    },
+                },
+            },
+            "components": {
+                "responses": {
+                    "Tree": {
+                        "content": {
+                            "application/json": {
+                                "schema": {
+                                    "$ref": "#/components/schemas/Tree",
+                                },
+                            },
+                        },
+                    },
+                },
+                "schemas": {
+                    "Tree": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "$ref": "#/components/schemas/Name" },
+                            "root": { "$ref": "#/components/schemas/Node" },
+                        },
+                    },
+                    "Node": {
+                        "type": "object",
+                        "properties": {
+                            "children": {
+                                "type": "array",
+                                "items": {
+                                    "$ref": "#/components/schemas/Node",
+                                },
+                            },
+                        },
+                    },
+                    "Name": { "type": "string" },
+                    "Unused": { "type": "integer" },
+                },
+                "securitySchemes": {
+                    "token": { "type": "http", "scheme": "bearer" },
+                },
+            },
+        });
+
+        let node = json!({
+            "type": "object",
+            "properties": {
+                "children": {
+                    "type": "array",
+                    "items": { "$ref": "#/components/schemas/Node" },
+                },
+            },
+        });
+        assert_eq!(
+            flatten(&document),
+            json!({
+                "openapi": "3.0.3",
+                "paths": {
+                    "/trees": {
+                        "get": {
+                            "responses": {
+                                "200": {
+                                    "content": {
+                                        "application/json": {
+                                            "schema": {
+                                                "type": "object",
+                                                "properties": {
+                                                    "name": {
+                                                        "type": "string",
+                                                    },
+                                                    "root": node.clone(),
+                                                },
+                                            },
+                                        },
+                                    },
+                                },
+                            },
+                        },
+                    },
+                },
+                "components": {
+                    "schemas": { "Node": node },
+                    "securitySchemes": {
+                        "token": { "type": "http", "scheme": "bearer" },
+                    },
+                },
+            }),
+        );
+    }
+
+    #[test]
+    fn test_component_name() {
+        assert_eq!(
+            component_name("#/components/schemas/a~1b~0c"),
+            Some(("schemas".to_owned(), "a/b~c".to_owned())),
+        );
+        assert_eq!(component_name("#/paths/~1trees"), None);
+        assert_eq!(component_name("other.json#/components/schemas/A"), None);
+        assert_eq!(component_name("#/components/schemas/A/properties"), None);
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Freeze windows for an API's latest document.
+//!
+//! See [`DocumentFreeze`] for more.
+
+use camino::{Utf8Path, Utf8PathBuf};
+use chrono::NaiveDate;
+use std::fmt;
+
+/// A window during which an API's latest document must not change, e.g. while
+/// a release is being stabilized.
+///
+/// Attach a freeze to an API with [`ManagedApi::with_freeze`]. While the
+/// freeze is in effect, `check` and `generate` report an error for any change
+/// to the API's latest document: for versioned APIs, that includes adding a
+/// new version. The error names the freeze and its owner, so that developers
+/// know whom to ask. To make a change anyway, e.g. for a fix approved by the
+/// owner, pass `--override-freeze`.
+///
+/// A freeze is in effect either between two dates, or while a marker file
+/// exists. A marker file lets release managers start and end a freeze by
+/// committing and removing a file, without changing code.
+///
+/// [`ManagedApi::with_freeze`]: crate::ManagedApi::with_freeze
+#[derive(Clone, Debug)]
+pub struct DocumentFreeze {
+    name: String,
+    owner: String,
+    window: FreezeWindow,
+}
+
+#[derive(Clone, Debug)]
+enum FreezeWindow {
+    Dates { start: NaiveDate, end: NaiveDate },
+    MarkerFile(Utf8PathBuf),
+}
+
+impl DocumentFreeze {
+    /// Creates a freeze named `name`, owned by `owner`, that's in effect from
+    /// `start` through `end` (inclusive), in UTC.
+    pub fn between(
+        name: impl Into<String>,
+        owner: impl Into<String>,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            owner: owner.into(),
+            window: FreezeWindow::Dates { start, end },
+        }
+    }
+
+    /// Creates a freeze named `name`, owned by `owner`, that's in effect
+    /// while the file at `path` (relative to the repository root) exists.
+    pub fn while_file_exists(
+        name: impl Into<String>,
+        owner: impl Into<String>,
+        path: impl Into<Utf8PathBuf>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            owner: owner.into(),
+            window: FreezeWindow::MarkerFile(path.into()),
+        }
+    }
+
+    /// Returns the name of the freeze.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the owner of the freeze.
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    /// Returns true if the freeze is in effect on `today`.
+    pub(crate) fn is_active(
+        &self,
+        repo_root: &Utf8Path,
+        today: NaiveDate,
+    ) -> bool {
+        match &self.window {
+            FreezeWindow::Dates { start, end } => {
+                (*start..=*end).contains(&today)
+            }
+            FreezeWindow::MarkerFile(path) => repo_root.join(path).exists(),
+        }
+    }
+}
+
+impl fmt::Display for DocumentFreeze {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "freeze {:?} (owner: {}", self.name, self.owner)?;
+        match &self.window {
+            FreezeWindow::Dates { start, end } => {
+                write!(f, ", {} through {})", start, end)
+            }
+            FreezeWindow::MarkerFile(path) => {
+                write!(f, ", while {} exists)", path)
+            }
+        }
+    }
+}
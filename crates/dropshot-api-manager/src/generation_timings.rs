@@ -0,0 +1,223 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Catching regressions in how long each API's documents take to generate.
+//!
+//! See [`GenerationTimings`] for more.
+
+use crate::{
+    apis::ManagedApis, environment::ErrorAccumulator,
+    spec_files_generated::GeneratedFiles, validation::overwrite_file,
+};
+use anyhow::{Context, anyhow, ensure};
+use camino::{Utf8Path, Utf8PathBuf};
+use dropshot_api_manager_types::ApiIdent;
+use serde_json::{Map, Value, json};
+use std::{collections::BTreeMap, time::Duration};
+
+/// The default factor by which an API's generation time must grow to be
+/// reported.
+const DEFAULT_REGRESSION_FACTOR: f64 = 2.0;
+
+/// The default time below which an API's generation time is never reported.
+const DEFAULT_MIN_DURATION: Duration = Duration::from_millis(100);
+
+/// A state file recording how long generating each API's documents took, so
+/// that regressions can be caught.
+///
+/// Enable it with [`Environment::with_generation_timings`]. After generating
+/// documents, `check` and `generate` compare how long each API took with the
+/// time recorded by the last `generate`, and warn if it has grown by more than
+/// the regression factor (2 by default). A sudden slowdown usually means an
+/// accidental blow-up in the API's schema, e.g. from deeply nested generic
+/// types. `generate` then writes the new times back to the file, while
+/// `check` leaves it alone.
+///
+/// Documents are generated in parallel, so times vary with the load on the
+/// machine. To avoid noise, APIs that take less than a minimum time (100ms by
+/// default) are never reported. The file is best kept out of version control,
+/// e.g. in a CI cache, since times from different machines aren't comparable.
+///
+/// [`Environment::with_generation_timings`]: crate::Environment::with_generation_timings
+#[derive(Clone, Debug)]
+pub struct GenerationTimings {
+    path: Utf8PathBuf,
+    regression_factor: f64,
+    min_duration: Duration,
+}
+
+impl GenerationTimings {
+    /// Records generation times in the file at `path`, relative to the
+    /// repository root.
+    pub fn new(path: impl Into<Utf8PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            regression_factor: DEFAULT_REGRESSION_FACTOR,
+            min_duration: DEFAULT_MIN_DURATION,
+        }
+    }
+
+    /// Warns when an API takes more than `factor` times as long to generate
+    /// as it did last time.
+    ///
+    /// Returns an error if `factor` isn't a finite number greater than 1.
+    pub fn with_regression_factor(
+        mut self,
+        factor: f64,
+    ) -> anyhow::Result<Self> {
+        ensure!(
+            factor.is_finite() && factor > 1.0,
+            "invalid regression factor {}: expected a number greater than 1",
+            factor,
+        );
+        self.regression_factor = factor;
+        Ok(self)
+    }
+
+    /// Never warns about APIs that take less than `min_duration` to generate.
+    pub fn with_min_duration(mut self, min_duration: Duration) -> Self {
+        self.min_duration = min_duration;
+        self
+    }
+
+    /// Returns the path to the state file, relative to the repository root.
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+
+    /// Returns a copy that keeps the state file within `repo_root`, even in
+    /// an environment with another root (e.g. a temporary worktree).
+    pub(crate) fn anchored_at(mut self, repo_root: &Utf8Path) -> Self {
+        self.path = repo_root.join(&self.path);
+        self
+    }
+
+    /// Compares how long generating each API in `generated` took with the
+    /// times in the state file, adding a warning to `errors` for each
+    /// regression.
+    ///
+    /// Returns the times in the state file. This never writes to the file.
+    pub(crate) fn compare(
+        &self,
+        repo_root: &Utf8Path,
+        generated: &GeneratedFiles,
+        errors: &mut ErrorAccumulator,
+    ) -> BTreeMap<ApiIdent, Duration> {
+        let current = generated.generation_durations();
+        // Documents loaded from a directory have no generation times.
+        if current.is_empty() {
+            return BTreeMap::new();
+        }
+
+        let path = repo_root.join(&self.path);
+        let timings = match read_timings(&path) {
+            Ok(timings) => timings,
+            Err(error) => {
+                // A damaged state file shouldn't fail the run: start over.
+                errors.warning(error.context(format!(
+                    "ignoring generation timings in {}",
+                    self.path
+                )));
+                BTreeMap::new()
+            }
+        };
+
+        for (ident, duration) in current {
+            let Some(previous) = timings.get(ident) else {
+                continue;
+            };
+            if *duration >= self.min_duration
+                && duration.as_secs_f64()
+                    > previous.as_secs_f64() * self.regression_factor
+            {
+                errors.warning(anyhow!(
+                    "generating documents for {} took {:.1?}, up from \
+                     {:.1?} last time; this usually means its schema has \
+                     grown unexpectedly, e.g. because of deeply nested \
+                     generic types",
+                    ident,
+                    duration,
+                    previous,
+                ));
+            }
+        }
+        timings
+    }
+
+    /// Like [`Self::compare`], but then records the new times in the state
+    /// file.
+    ///
+    /// Times for APIs that weren't generated this time, e.g. because the run
+    /// was scoped to other APIs, are kept. Times for APIs that are no longer
+    /// managed are dropped.
+    pub(crate) fn record(
+        &self,
+        repo_root: &Utf8Path,
+        apis: &ManagedApis,
+        generated: &GeneratedFiles,
+        errors: &mut ErrorAccumulator,
+    ) -> anyhow::Result<()> {
+        let current = generated.generation_durations();
+        if current.is_empty() {
+            return Ok(());
+        }
+
+        let mut timings = self.compare(repo_root, generated, errors);
+        timings.retain(|ident, _| {
+            apis.api(ident).is_some() || apis.is_skipped_api(ident)
+        });
+        timings.extend(current.iter().map(|(ident, d)| (ident.clone(), *d)));
+        let path = repo_root.join(&self.path);
+        let _ = overwrite_file(&path, &render_timings(&timings))?;
+        Ok(())
+    }
+}
+
+/// Reads the generation times recorded in the state file at `path`.
+///
+/// A missing file has no times recorded.
+fn read_timings(
+    path: &Utf8Path,
+) -> anyhow::Result<BTreeMap<ApiIdent, Duration>> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let contents = fs_err::read(path)?;
+    let value: Value =
+        serde_json::from_slice(&contents).context("parsing JSON")?;
+    let apis = value
+        .get("apis")
+        .and_then(Value::as_object)
+        .context("expected an \"apis\" object")?;
+    apis.iter()
+        .map(|(ident, timing)| {
+            let micros = timing
+                .get("generation_us")
+                .and_then(Value::as_u64)
+                .with_context(|| {
+                    format!(
+                        "expected a \"generation_us\" integer for {}",
+                        ident
+                    )
+                })?;
+            Ok((ApiIdent::from(ident.clone()), Duration::from_micros(micros)))
+        })
+        .collect()
+}
+
+/// Renders the contents of the state file for `timings`.
+fn render_timings(timings: &BTreeMap<ApiIdent, Duration>) -> Vec<u8> {
+    let apis: Map<String, Value> = timings
+        .iter()
+        .map(|(ident, duration)| {
+            // Durations this long don't fit in a u64 of microseconds, and
+            // wouldn't be worth recording anyway.
+            let micros =
+                u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+            (ident.to_string(), json!({ "generation_us": micros }))
+        })
+        .collect();
+    let mut contents = serde_json::to_vec_pretty(&json!({ "apis": apis }))
+        .expect("serializing JSON values succeeds");
+    contents.push(b'\n');
+    contents
+}
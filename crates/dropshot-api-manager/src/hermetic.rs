@@ -0,0 +1,225 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Support for running the API manager as a hermetic build action, e.g. under
+//! Bazel or Buck.
+//!
+//! Such build systems run each action in a sandbox containing only the inputs
+//! it declares, and only collect outputs written to locations it declares. By
+//! default, the API manager reads every file in the documents directory and
+//! updates that directory in place, neither of which fits that model.
+//!
+//! With `--inputs FILE`, local documents are instead read only from the paths
+//! listed in FILE: the listed documents are staged into a separate directory,
+//! which `check` and `generate` then treat as the documents directory. With
+//! `generate --output-root DIR`, that directory is DIR, so every file
+//! `generate` writes ends up under DIR, and DIR afterwards holds the complete
+//! documents directory. `generate --list-outputs` prints the paths of those
+//! files without writing any of them.
+//!
+//! Symlinks are often mishandled by build systems, so hermetic mode refuses to
+//! write them unless `--allow-symlinks` is passed. Versioned APIs can use
+//! [`LatestPointer::PointerFile`] or [`LatestPointer::None`] instead.
+//!
+//! [`LatestPointer::PointerFile`]: crate::LatestPointer::PointerFile
+//! [`LatestPointer::None`]: crate::LatestPointer::None
+
+use crate::{
+    apis::{LatestPointer, ManagedApis},
+    environment::ResolvedEnv,
+    resolved::symlink_file,
+};
+use anyhow::{Context, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use camino_tempfile::Utf8TempDir;
+
+/// The local documents declared as inputs with `--inputs`.
+#[derive(Clone, Debug)]
+pub(crate) struct HermeticInputs {
+    /// The declared documents, relative to the documents directory.
+    documents: Vec<Utf8PathBuf>,
+    allow_symlinks: bool,
+}
+
+impl HermeticInputs {
+    /// Reads the list of inputs at `list`.
+    ///
+    /// The list contains one path per line, either absolute or relative to the
+    /// current directory. Each path must be within the documents directory.
+    /// Blank lines are ignored.
+    pub(crate) fn read(
+        list: &Utf8Path,
+        env: &ResolvedEnv,
+        allow_symlinks: bool,
+    ) -> anyhow::Result<Self> {
+        let contents = fs_err::read_to_string(list)?;
+        let documents_dir = env.openapi_abs_dir();
+        let mut documents = Vec::new();
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let path = camino::absolute_utf8(line).with_context(|| {
+                format!("{}:{}: error making {} absolute", list, i + 1, line)
+            })?;
+            let Ok(relative) = path.strip_prefix(documents_dir) else {
+                bail!(
+                    "{}:{}: input {} is not within the OpenAPI documents \
+                     directory {}",
+                    list,
+                    i + 1,
+                    line,
+                    documents_dir,
+                );
+            };
+            if relative.as_str().is_empty() {
+                bail!(
+                    "{}:{}: input {} is the OpenAPI documents directory \
+                     itself; list the documents within it instead",
+                    list,
+                    i + 1,
+                    line,
+                );
+            }
+            documents.push(relative.to_owned());
+        }
+        documents.sort();
+        documents.dedup();
+        Ok(Self { documents, allow_symlinks })
+    }
+
+    /// Returns an error if any API would need a symlink to be written, and
+    /// symlinks aren't allowed.
+    pub(crate) fn check_apis(&self, apis: &ManagedApis) -> anyhow::Result<()> {
+        if self.allow_symlinks {
+            return Ok(());
+        }
+        let with_symlinks: Vec<_> = apis
+            .iter_apis()
+            .filter(|api| {
+                api.is_versioned()
+                    && api.latest_pointer() == LatestPointer::Symlink
+            })
+            .map(|api| api.ident().to_string())
+            .collect();
+        if !with_symlinks.is_empty() {
+            bail!(
+                "these APIs keep a \"latest\" symlink, which isn't written \
+                 with --inputs unless --allow-symlinks is passed: {} (consider \
+                 using LatestPointer::PointerFile for them instead)",
+                with_symlinks.join(", "),
+            );
+        }
+        Ok(())
+    }
+
+    /// Stages the declared documents into a temporary directory, returning it
+    /// along with an environment that treats it as the documents directory.
+    pub(crate) fn stage_temp(
+        &self,
+        env: &ResolvedEnv,
+    ) -> anyhow::Result<(Utf8TempDir, ResolvedEnv)> {
+        let temp_dir = Utf8TempDir::with_prefix("dropshot-api-manager-inputs-")
+            .context("creating temporary directory for inputs")?;
+        self.stage(env, temp_dir.path())?;
+        let env = env.with_documents_dir(temp_dir.path());
+        Ok((temp_dir, env))
+    }
+
+    /// Stages the declared documents into `output_root`, returning an
+    /// environment that treats it as the documents directory.
+    pub(crate) fn stage_output_root(
+        &self,
+        env: &ResolvedEnv,
+        output_root: &Utf8Path,
+    ) -> anyhow::Result<ResolvedEnv> {
+        let output_root =
+            camino::absolute_utf8(output_root).with_context(|| {
+                format!("error making output root absolute: {}", output_root)
+            })?;
+        let documents_dir = env.openapi_abs_dir();
+        if output_root.starts_with(documents_dir)
+            || documents_dir.starts_with(&output_root)
+        {
+            bail!(
+                "output root {} must not overlap the OpenAPI documents \
+                 directory {}",
+                output_root,
+                documents_dir,
+            );
+        }
+        self.stage(env, &output_root)?;
+        Ok(env.with_documents_dir(&output_root))
+    }
+
+    /// Copies the declared documents from the documents directory into
+    /// `into`, which must be empty or not exist.
+    ///
+    /// Documents are copied as regular files, since build systems commonly
+    /// stage inputs as symlinks. If symlinks are allowed, inputs that are
+    /// symlinks with relative targets (such as "latest" symlinks) are instead
+    /// recreated as symlinks.
+    fn stage(&self, env: &ResolvedEnv, into: &Utf8Path) -> anyhow::Result<()> {
+        if into.exists() && fs_err::read_dir(into)?.next().is_some() {
+            bail!(
+                "output directory {} is not empty (remove its contents, or \
+                 choose another directory)",
+                into
+            );
+        }
+        fs_err::create_dir_all(into)?;
+
+        let documents_dir = env.openapi_abs_dir();
+        for document in &self.documents {
+            let source = documents_dir.join(document);
+            let target = into.join(document);
+            if let Some(parent) = target.parent() {
+                fs_err::create_dir_all(parent)?;
+            }
+
+            let metadata = fs_err::symlink_metadata(&source)
+                .with_context(|| format!("reading input {}", document))?;
+            if metadata.is_symlink() && self.allow_symlinks {
+                let link = fs_err::read_link(&source)?;
+                if link.is_relative() {
+                    let link = Utf8PathBuf::try_from(link)?;
+                    symlink_file(link.as_str(), &target)?;
+                    continue;
+                }
+            }
+            if fs_err::metadata(&source)?.is_dir() {
+                bail!(
+                    "input {} is a directory; list the documents within it \
+                     instead",
+                    document
+                );
+            }
+            fs_err::copy(&source, &target)
+                .with_context(|| format!("staging input {}", document))?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the paths of all files under `root`, relative to it, in sorted
+/// order.
+pub(crate) fn list_outputs(
+    root: &Utf8Path,
+) -> anyhow::Result<Vec<Utf8PathBuf>> {
+    let mut outputs = Vec::new();
+    let mut dirs = vec![root.to_owned()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs_err::read_dir(&dir)? {
+            let entry = entry?;
+            let path = Utf8PathBuf::try_from(entry.path())?;
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+            } else {
+                // unwrap(): every path found here is under `root`.
+                outputs.push(path.strip_prefix(root).unwrap().to_owned());
+            }
+        }
+    }
+    outputs.sort();
+    Ok(outputs)
+}
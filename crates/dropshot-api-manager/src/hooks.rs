@@ -0,0 +1,254 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Hooks run by `generate` before and after it updates documents.
+//!
+//! See [`GenerateHook`] for more.
+
+use anyhow::{Context, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, Write},
+    process::{Command, Stdio},
+    sync::Arc,
+};
+
+/// A hook that `generate` runs before or after it updates documents.
+///
+/// Attach hooks with [`Environment::with_pre_generate_hook`] and
+/// [`Environment::with_post_generate_hook`]. Pre-generate hooks run once
+/// `generate` has determined that every problem can be fixed, just before
+/// fixes are applied. Post-generate hooks run once the documents are
+/// up-to-date, and are told which files in the documents directory changed.
+/// This lets repositories, for example, regenerate clients derived from the
+/// documents, or format other files, within the same invocation.
+///
+/// Hooks run even if there's nothing to fix, in which case post-generate hooks
+/// see no changed paths. If a hook fails, `generate` fails without running any
+/// further hooks.
+///
+/// [`Environment::with_pre_generate_hook`]: crate::Environment::with_pre_generate_hook
+/// [`Environment::with_post_generate_hook`]: crate::Environment::with_post_generate_hook
+#[derive(Clone)]
+pub struct GenerateHook {
+    name: String,
+    kind: HookKind,
+}
+
+type HookFn =
+    dyn Fn(&GenerateHookContext<'_>) -> anyhow::Result<()> + Send + Sync;
+
+#[derive(Clone)]
+enum HookKind {
+    Closure(Arc<HookFn>),
+    Command { program: String, args: Vec<String> },
+}
+
+impl GenerateHook {
+    /// Creates a hook that calls `f`.
+    ///
+    /// `name` is shown in the output of `generate` when the hook runs.
+    pub fn new<F>(name: impl Into<String>, f: F) -> Self
+    where
+        F: Fn(&GenerateHookContext<'_>) -> anyhow::Result<()>
+            + Send
+            + Sync
+            + 'static,
+    {
+        Self { name: name.into(), kind: HookKind::Closure(Arc::new(f)) }
+    }
+
+    /// Creates a hook that runs `program` with `args`.
+    ///
+    /// The command runs in the repository root, with
+    /// `OPENAPI_MGR_DOCUMENTS_DIR` set to the absolute path of the documents
+    /// directory. The absolute paths of changed files are written to its
+    /// standard input, one per line. The hook fails if the command exits
+    /// unsuccessfully.
+    pub fn command<I, S>(program: impl Into<String>, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let program = program.into();
+        let args: Vec<String> = args.into_iter().map(Into::into).collect();
+        let name = std::iter::once(program.as_str())
+            .chain(args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        Self { name, kind: HookKind::Command { program, args } }
+    }
+
+    /// Returns the name of the hook.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn run(
+        &self,
+        cx: &GenerateHookContext<'_>,
+    ) -> anyhow::Result<()> {
+        match &self.kind {
+            HookKind::Closure(f) => f(cx),
+            HookKind::Command { program, args } => {
+                let mut child = Command::new(program)
+                    .args(args)
+                    .current_dir(cx.repo_root)
+                    .env("OPENAPI_MGR_DOCUMENTS_DIR", cx.documents_dir)
+                    .stdin(Stdio::piped())
+                    .spawn()
+                    .with_context(|| format!("failed to run {}", program))?;
+                let mut input = String::new();
+                for path in cx.changed_paths {
+                    input.push_str(path.as_str());
+                    input.push('\n');
+                }
+                // The temporary ChildStdin is dropped at the end of the
+                // statement, closing the command's input.
+                let written = child
+                    .stdin
+                    .take()
+                    .expect("stdin is piped")
+                    .write_all(input.as_bytes());
+                // The command doesn't need to read its input.
+                if let Err(error) = written
+                    && error.kind() != io::ErrorKind::BrokenPipe
+                {
+                    return Err(error).with_context(|| {
+                        format!("writing changed paths to {}", program)
+                    });
+                }
+                let status = child
+                    .wait()
+                    .with_context(|| format!("waiting for {}", program))?;
+                if !status.success() {
+                    bail!("command failed ({})", status);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Debug for GenerateHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GenerateHook")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Information passed to a [`GenerateHook`].
+#[derive(Debug)]
+pub struct GenerateHookContext<'a> {
+    repo_root: &'a Utf8Path,
+    documents_dir: &'a Utf8Path,
+    changed_paths: &'a [Utf8PathBuf],
+}
+
+impl<'a> GenerateHookContext<'a> {
+    pub(crate) fn new(
+        repo_root: &'a Utf8Path,
+        documents_dir: &'a Utf8Path,
+        changed_paths: &'a [Utf8PathBuf],
+    ) -> Self {
+        Self { repo_root, documents_dir, changed_paths }
+    }
+
+    /// Returns the absolute path to the repository root.
+    pub fn repo_root(&self) -> &'a Utf8Path {
+        self.repo_root
+    }
+
+    /// Returns the absolute path to the OpenAPI documents directory.
+    pub fn documents_dir(&self) -> &'a Utf8Path {
+        self.documents_dir
+    }
+
+    /// Returns the absolute paths of the files in the documents directory that
+    /// were created, modified or removed, in sorted order.
+    ///
+    /// This is always empty for pre-generate hooks. Files written outside the
+    /// documents directory (such as detached signatures, or files written by
+    /// extra validation) aren't included.
+    pub fn changed_paths(&self) -> &'a [Utf8PathBuf] {
+        self.changed_paths
+    }
+}
+
+/// The hooks run by `generate`, in the order they were added.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct GenerateHooks {
+    pub(crate) pre: Vec<GenerateHook>,
+    pub(crate) post: Vec<GenerateHook>,
+}
+
+/// A snapshot of the files in a directory, used to find the files that
+/// `generate` changed.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct DirSnapshot {
+    entries: BTreeMap<Utf8PathBuf, SnapshotEntry>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum SnapshotEntry {
+    /// A regular file, with the SHA-256 digest of its contents.
+    File([u8; 32]),
+    /// A symlink, with its target.
+    Symlink(Utf8PathBuf),
+}
+
+impl DirSnapshot {
+    /// Takes a snapshot of the files under `root`, which need not exist.
+    pub(crate) fn take(root: &Utf8Path) -> anyhow::Result<Self> {
+        let mut entries = BTreeMap::new();
+        if !root.exists() {
+            return Ok(Self { entries });
+        }
+        let mut dirs = vec![root.to_owned()];
+        while let Some(dir) = dirs.pop() {
+            for entry in fs_err::read_dir(&dir)? {
+                let entry = entry?;
+                let path = Utf8PathBuf::try_from(entry.path())?;
+                let file_type = entry.file_type()?;
+                let snapshot_entry = if file_type.is_dir() {
+                    dirs.push(path);
+                    continue;
+                } else if file_type.is_symlink() {
+                    let target = fs_err::read_link(&path)?;
+                    SnapshotEntry::Symlink(Utf8PathBuf::try_from(target)?)
+                } else {
+                    let contents = fs_err::read(&path)?;
+                    SnapshotEntry::File(Sha256::digest(&contents).into())
+                };
+                entries.insert(path, snapshot_entry);
+            }
+        }
+        Ok(Self { entries })
+    }
+
+    /// Returns the paths that were created, modified or removed between
+    /// `self` and `after`, in sorted order.
+    pub(crate) fn changed_paths(
+        &self,
+        after: &DirSnapshot,
+    ) -> Vec<Utf8PathBuf> {
+        let mut changed: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|(path, entry)| after.entries.get(*path) != Some(entry))
+            .map(|(path, _)| path.clone())
+            .chain(
+                after
+                    .entries
+                    .keys()
+                    .filter(|path| !self.entries.contains_key(*path))
+                    .cloned(),
+            )
+            .collect();
+        changed.sort();
+        changed
+    }
+}
@@ -0,0 +1,591 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Custom formatting of the JSON in an API's documents.
+//!
+//! See [`JsonFormat`] for more.
+//!
+//! Documents are reformatted textually, token by token, rather than by parsing
+//! them into values and serializing those again. That way, the order of object
+//! keys and the exact text of strings and numbers are preserved.
+
+use crate::{
+    apis::{ManagedApi, ManagedApis},
+    shared_schemas::document_paths,
+    spec_files_local::IgnoreGlobs,
+    validation::{overwrite_file, read_opt},
+};
+use anyhow::{anyhow, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use dropshot_api_manager_types::ApiIdent;
+use std::fmt;
+
+/// How deeply arrays and objects may be nested within a document.
+const MAX_DEPTH: usize = 256;
+
+/// The number of columns a tab is counted as when checking line widths.
+const TAB_WIDTH: usize = 8;
+
+/// How the JSON in an API's documents is formatted on disk.
+///
+/// By default, documents are formatted the way Dropshot generates them:
+/// indented by two spaces, with every non-empty array and object spread over
+/// multiple lines. Attach a different format to an API with
+/// [`ManagedApi::with_json_format`] to suit the tools that consume its
+/// documents.
+///
+/// The format only affects how documents are stored. The API manager converts
+/// them back to Dropshot's formatting when loading them, so comparisons with
+/// blessed and generated documents, and the hashes in file names, are
+/// unaffected. In particular, changing the format of an API doesn't require a
+/// new version. `generate` reformats documents that don't match the
+/// configured format, and `check` reports them.
+///
+/// [`ManagedApi::with_json_format`]: crate::ManagedApi::with_json_format
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct JsonFormat {
+    indent: JsonIndent,
+    max_width: Option<usize>,
+}
+
+/// How each level of nesting is indented in a [`JsonFormat`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum JsonIndent {
+    /// Indent by this many spaces.
+    Spaces(usize),
+    /// Indent by one tab.
+    Tab,
+}
+
+impl Default for JsonIndent {
+    fn default() -> Self {
+        JsonIndent::Spaces(2)
+    }
+}
+
+impl fmt::Display for JsonIndent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonIndent::Spaces(1) => write!(f, "1-space indentation"),
+            JsonIndent::Spaces(n) => write!(f, "{}-space indentation", n),
+            JsonIndent::Tab => write!(f, "tab indentation"),
+        }
+    }
+}
+
+impl JsonFormat {
+    /// Creates a format matching the one Dropshot generates.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how each level of nesting is indented.
+    pub fn with_indent(mut self, indent: JsonIndent) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Puts each array and object on a single line if that line, including
+    /// its indentation, fits within `max_width` columns.
+    pub fn with_max_width(mut self, max_width: usize) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    /// Returns how each level of nesting is indented.
+    pub fn indent(&self) -> JsonIndent {
+        self.indent
+    }
+
+    /// Returns the width within which arrays and objects are put on a single
+    /// line, if any.
+    pub fn max_width(&self) -> Option<usize> {
+        self.max_width
+    }
+
+    /// Reformats the JSON document `contents`.
+    ///
+    /// A trailing newline is kept if there is one.
+    pub(crate) fn format(&self, contents: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let text = std::str::from_utf8(contents)?;
+        let value = parse(text)?;
+        let mut out = String::with_capacity(text.len());
+        self.write_value(&value, 0, 0, &mut out);
+        if text.ends_with('\n') {
+            out.push('\n');
+        }
+        Ok(out.into_bytes())
+    }
+
+    fn write_value(
+        &self,
+        value: &Node<'_>,
+        level: usize,
+        column: usize,
+        out: &mut String,
+    ) {
+        if let Some(max_width) = self.max_width
+            && !matches!(value, Node::Scalar(_))
+        {
+            let mut compact = String::new();
+            write_compact(value, &mut compact);
+            // Leave room for a trailing comma.
+            if column + compact.chars().count() < max_width {
+                out.push_str(&compact);
+                return;
+            }
+        }
+
+        match value {
+            Node::Scalar(text) => out.push_str(text),
+            Node::Array(items) if items.is_empty() => out.push_str("[]"),
+            Node::Object(members) if members.is_empty() => out.push_str("{}"),
+            Node::Array(items) => {
+                out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    out.push_str(if i == 0 { "\n" } else { ",\n" });
+                    let column = self.write_indent(level + 1, out);
+                    self.write_value(item, level + 1, column, out);
+                }
+                out.push('\n');
+                self.write_indent(level, out);
+                out.push(']');
+            }
+            Node::Object(members) => {
+                out.push('{');
+                for (i, (key, member)) in members.iter().enumerate() {
+                    out.push_str(if i == 0 { "\n" } else { ",\n" });
+                    let column = self.write_indent(level + 1, out);
+                    out.push_str(key);
+                    out.push_str(": ");
+                    let column = column + key.chars().count() + 2;
+                    self.write_value(member, level + 1, column, out);
+                }
+                out.push('\n');
+                self.write_indent(level, out);
+                out.push('}');
+            }
+        }
+    }
+
+    /// Writes the indentation for `level`, returning its width in columns.
+    fn write_indent(&self, level: usize, out: &mut String) -> usize {
+        match self.indent {
+            JsonIndent::Spaces(n) => {
+                out.extend(std::iter::repeat_n(' ', n * level));
+                n * level
+            }
+            JsonIndent::Tab => {
+                out.extend(std::iter::repeat_n('\t', level));
+                TAB_WIDTH * level
+            }
+        }
+    }
+}
+
+impl fmt::Display for JsonFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.indent)?;
+        if let Some(max_width) = self.max_width {
+            write!(f, ", up to {} columns per line", max_width)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the document `contents` of the API `ident`, as stored on disk,
+/// formatted the way Dropshot generates documents. Documents of APIs without a
+/// custom format are returned as they are.
+///
+/// On error, the original contents are returned along with the error.
+pub(crate) fn to_dropshot_format(
+    apis: &ManagedApis,
+    ident: &ApiIdent,
+    contents: Vec<u8>,
+) -> Result<Vec<u8>, (anyhow::Error, Vec<u8>)> {
+    if apis.api(ident).and_then(|api| api.json_format()).is_none() {
+        return Ok(contents);
+    }
+    match JsonFormat::default().format(&contents) {
+        Ok(formatted) => Ok(formatted),
+        Err(error) => {
+            Err((error.context("failed to reformat document"), contents))
+        }
+    }
+}
+
+/// Returns true if the JSON documents `a` and `b` differ only in whitespace,
+/// i.e. if they consist of the same tokens in the same order.
+///
+/// Returns false if either document can't be parsed.
+pub(crate) fn same_tokens(a: &[u8], b: &[u8]) -> bool {
+    let compact = |contents: &[u8]| -> anyhow::Result<String> {
+        let value = parse(std::str::from_utf8(contents)?)?;
+        let mut out = String::with_capacity(contents.len());
+        write_compact(&value, &mut out);
+        Ok(out)
+    };
+    match (compact(a), compact(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Describes the indentation of the JSON document `contents`, guessed from its
+/// first indented line.
+fn detect_indent(contents: &[u8]) -> Option<JsonIndent> {
+    let text = std::str::from_utf8(contents).ok()?;
+    let line = text.lines().find(|line| line.starts_with([' ', '\t']))?;
+    if line.starts_with('\t') {
+        Some(JsonIndent::Tab)
+    } else {
+        Some(JsonIndent::Spaces(
+            line.len() - line.trim_start_matches(' ').len(),
+        ))
+    }
+}
+
+/// A document of an API that isn't formatted as configured.
+#[derive(Debug)]
+pub(crate) struct MisformattedDocument {
+    /// The path to the document, relative to the OpenAPI documents directory.
+    pub(crate) path: Utf8PathBuf,
+    /// The indentation the document appears to use, if any.
+    pub(crate) detected: Option<JsonIndent>,
+    /// The document, formatted as configured.
+    contents: Vec<u8>,
+}
+
+impl fmt::Display for MisformattedDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.detected {
+            Some(indent) => write!(f, "{} (uses {})", self.path, indent),
+            None => write!(f, "{}", self.path),
+        }
+    }
+}
+
+/// Returns the documents of `api` in the OpenAPI documents directory `dir`
+/// that aren't formatted as configured.
+///
+/// Documents that can't be parsed are skipped: they're reported when documents
+/// are loaded.
+pub(crate) fn diff_json_format(
+    dir: &Utf8Path,
+    ignore_globs: &IgnoreGlobs,
+    api: &ManagedApi,
+) -> anyhow::Result<Vec<MisformattedDocument>> {
+    let Some(format) = api.json_format() else {
+        return Ok(Vec::new());
+    };
+
+    let paths = if api.is_versioned() {
+        let api_dir = Utf8PathBuf::from(api.ident().as_str());
+        let combined = api.ident().versioned_api_combined_file();
+        document_paths(dir, &api_dir, ignore_globs, api)?
+            .into_iter()
+            .filter(|path| path.file_name() != Some(combined.as_str()))
+            .collect()
+    } else {
+        vec![Utf8PathBuf::from(format!("{}.json", api.ident()))]
+    };
+
+    let mut misformatted = Vec::new();
+    for path in paths {
+        let Some(contents) = read_opt(&dir.join(&path))? else {
+            continue;
+        };
+        let Ok(formatted) = format.format(&contents) else {
+            continue;
+        };
+        if formatted != contents {
+            misformatted.push(MisformattedDocument {
+                path,
+                detected: detect_indent(&contents),
+                contents: formatted,
+            });
+        }
+    }
+    Ok(misformatted)
+}
+
+/// Reformats the documents of `api` in the OpenAPI documents directory `dir`
+/// that aren't formatted as configured.
+///
+/// Returns a description of each change made.
+pub(crate) fn sync_json_format(
+    dir: &Utf8Path,
+    ignore_globs: &IgnoreGlobs,
+    api: &ManagedApi,
+) -> anyhow::Result<Vec<String>> {
+    let mut steps = Vec::new();
+    for document in diff_json_format(dir, ignore_globs, api)? {
+        let full_path = dir.join(&document.path);
+        let _ = overwrite_file(&full_path, &document.contents)?;
+        steps.push(format!("reformatted {}", full_path));
+    }
+    Ok(steps)
+}
+
+/// A JSON value, with scalars (and object keys) kept as their original text.
+enum Node<'a> {
+    Scalar(&'a str),
+    Array(Vec<Node<'a>>),
+    Object(Vec<(&'a str, Node<'a>)>),
+}
+
+/// Parses the JSON document `text`.
+fn parse(text: &str) -> anyhow::Result<Node<'_>> {
+    let mut parser = Parser { text, pos: 0 };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.pos != text.len() {
+        bail!("unexpected trailing characters at offset {}", parser.pos);
+    }
+    Ok(value)
+}
+
+fn write_compact(value: &Node<'_>, out: &mut String) {
+    match value {
+        Node::Scalar(text) => out.push_str(text),
+        Node::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                write_compact(item, out);
+            }
+            out.push(']');
+        }
+        Node::Object(members) => {
+            out.push('{');
+            for (i, (key, member)) in members.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(", ");
+                }
+                out.push_str(key);
+                out.push_str(": ");
+                write_compact(member, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn value(&mut self, depth: usize) -> anyhow::Result<Node<'a>> {
+        if depth > MAX_DEPTH {
+            bail!("document is nested too deeply");
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if !self.consume(b']') {
+                    loop {
+                        items.push(self.value(depth + 1)?);
+                        if self.consume(b']') {
+                            break;
+                        }
+                        self.expect(b',')?;
+                    }
+                }
+                Ok(Node::Array(items))
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut members = Vec::new();
+                if !self.consume(b'}') {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.expect(b':')?;
+                        members.push((key, self.value(depth + 1)?));
+                        if self.consume(b'}') {
+                            break;
+                        }
+                        self.expect(b',')?;
+                    }
+                }
+                Ok(Node::Object(members))
+            }
+            Some(b'"') => Ok(Node::Scalar(self.string()?)),
+            Some(_) => {
+                let start = self.pos;
+                while self.peek().is_some_and(|c| {
+                    c.is_ascii_alphanumeric() || matches!(c, b'+' | b'-' | b'.')
+                }) {
+                    self.pos += 1;
+                }
+                if start == self.pos {
+                    bail!("unexpected character at offset {}", start);
+                }
+                Ok(Node::Scalar(&self.text[start..self.pos]))
+            }
+            None => bail!("unexpected end of document"),
+        }
+    }
+
+    /// Parses a string, returning it with its quotes and escapes.
+    fn string(&mut self) -> anyhow::Result<&'a str> {
+        let start = self.pos;
+        if self.peek() != Some(b'"') {
+            bail!("expected a string at offset {}", start);
+        }
+        self.pos += 1;
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(&self.text[start..self.pos]);
+                }
+                Some(b'\\') => self.pos += 2,
+                Some(_) => self.pos += 1,
+                None => {
+                    return Err(anyhow!(
+                        "unterminated string at offset {}",
+                        start
+                    ));
+                }
+            }
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    /// Consumes `c` (after any whitespace) if it's next.
+    fn consume(&mut self, c: u8) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: u8) -> anyhow::Result<()> {
+        if self.consume(c) {
+            Ok(())
+        } else {
+            bail!("expected {:?} at offset {}", c as char, self.pos)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r#"{
+  "openapi": "3.0.3",
+  "tags": [],
+  "info": {
+    "title": "Test \"API\"",
+    "version": "1.0.0"
+  },
+  "servers": [
+    {
+      "url": "http://localhost"
+    }
+  ],
+  "numbers": [
+    1,
+    2.5e-3
+  ]
+}
+"#;
+
+    #[test]
+    fn test_default_format() {
+        let formatted = JsonFormat::new().format(DOCUMENT.as_bytes()).unwrap();
+        assert_eq!(std::str::from_utf8(&formatted).unwrap(), DOCUMENT);
+
+        // The default format matches serde_json's pretty printing.
+        let value: serde_json::Value = serde_json::from_str(DOCUMENT).unwrap();
+        let pretty = serde_json::to_string_pretty(&value).unwrap() + "\n";
+        let formatted = JsonFormat::new().format(pretty.as_bytes()).unwrap();
+        assert_eq!(std::str::from_utf8(&formatted).unwrap(), pretty);
+    }
+
+    #[test]
+    fn test_custom_format() {
+        let format = JsonFormat::new()
+            .with_indent(JsonIndent::Spaces(4))
+            .with_max_width(50);
+        let formatted = format.format(DOCUMENT.as_bytes()).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&formatted).unwrap(),
+            r#"{
+    "openapi": "3.0.3",
+    "tags": [],
+    "info": {
+        "title": "Test \"API\"",
+        "version": "1.0.0"
+    },
+    "servers": [{"url": "http://localhost"}],
+    "numbers": [1, 2.5e-3]
+}
+"#,
+        );
+        assert_eq!(detect_indent(&formatted), Some(JsonIndent::Spaces(4)));
+
+        // Converting back to the default format restores the original.
+        let restored = JsonFormat::new().format(&formatted).unwrap();
+        assert_eq!(std::str::from_utf8(&restored).unwrap(), DOCUMENT);
+
+        let format = JsonFormat::new().with_indent(JsonIndent::Tab);
+        let formatted = format.format(DOCUMENT.as_bytes()).unwrap();
+        assert!(formatted.starts_with(b"{\n\t\"openapi\""));
+        assert_eq!(detect_indent(&formatted), Some(JsonIndent::Tab));
+    }
+
+    #[test]
+    fn test_same_tokens() {
+        let custom = JsonFormat::new()
+            .with_indent(JsonIndent::Tab)
+            .format(DOCUMENT.as_bytes())
+            .unwrap();
+        assert!(same_tokens(DOCUMENT.as_bytes(), &custom));
+        assert!(same_tokens(DOCUMENT.as_bytes(), DOCUMENT.trim().as_bytes()));
+
+        // Changes to keys, their order, or the text of scalars (including
+        // whitespace within strings) aren't just whitespace.
+        let reordered = DOCUMENT.replace(
+            "\"openapi\": \"3.0.3\",\n  \"tags\": [],",
+            "\"tags\": [],\n  \"openapi\": \"3.0.3\",",
+        );
+        assert!(!same_tokens(DOCUMENT.as_bytes(), reordered.as_bytes()));
+        let renumbered = DOCUMENT.replace("2.5e-3", "0.0025");
+        assert!(!same_tokens(DOCUMENT.as_bytes(), renumbered.as_bytes()));
+        let spaced = DOCUMENT.replace("Test ", "Test  ");
+        assert!(!same_tokens(DOCUMENT.as_bytes(), spaced.as_bytes()));
+
+        assert!(!same_tokens(b"{", b"{"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for invalid in ["", "{", "{\"a\" 1}", "[1,]", "\"abc", "{} {}"] {
+            assert!(
+                JsonFormat::new().format(invalid.as_bytes()).is_err(),
+                "{:?} is invalid",
+                invalid,
+            );
+        }
+    }
+}
@@ -8,24 +8,85 @@
 
 #![warn(missing_docs)]
 
+mod aliases;
+mod api_selector;
 mod apis;
+mod approvals;
+mod baseline;
+mod changed_files;
+mod check_notes;
+mod check_output;
 mod cmd;
+mod combined;
 mod compatibility;
+mod deprecations;
+mod docs_link_lint;
 mod environment;
+mod example_lint;
+mod flattened;
+mod freeze;
+mod generation_timings;
+mod hermetic;
+mod hooks;
 mod iter_only;
+mod json_format;
+mod lock;
+mod lockstep_version;
+mod merge_guidance;
+mod metrics;
+mod naming_lint;
+mod next_version;
+mod openapi_version;
 mod output;
+mod pins;
+mod profiles;
+mod provenance;
+mod ref_check;
+mod report;
 mod resolved;
+mod shared_schemas;
+mod signing;
+mod sink;
 mod spec_files_blessed;
 mod spec_files_generated;
 mod spec_files_generic;
 mod spec_files_local;
 pub mod test_util;
+mod text_lint;
+mod type_replacements;
+mod unrecognized;
+mod unstable;
 mod validation;
 mod vcs;
+mod version_ranges;
 
 #[macro_use]
 extern crate newtype_derive;
 
+pub use api_selector::ApiSelector;
 pub use apis::*;
+pub use approvals::BreakingChangeApprovals;
 pub use cmd::dispatch::{App, FAILURE_EXIT_CODE, NEEDS_UPDATE_EXIT_CODE};
-pub use environment::Environment;
+pub use cmd::generate::{FixOperation, apply_fixes};
+pub use deprecations::DeprecationSummary;
+pub use docs_link_lint::DocsLinkLint;
+pub use environment::{
+    Environment, ErrorAccumulator, LoadProblemSeverity, UnknownApiPolicy,
+};
+pub use example_lint::ExampleLint;
+pub use flattened::FlattenedDocument;
+pub use freeze::DocumentFreeze;
+pub use generation_timings::GenerationTimings;
+pub use hooks::{GenerateHook, GenerateHookContext};
+pub use json_format::{JsonFormat, JsonIndent};
+pub use lockstep_version::LockstepVersionPolicy;
+pub use naming_lint::{NamingConvention, NamingLint};
+pub use pins::ConsumerPins;
+pub use profiles::ApiProfile;
+pub use signing::DocumentSigning;
+pub use sink::{OutputEvent, OutputSink};
+pub use spec_files_generic::{FileNameHash, FileNameHashAlgorithm};
+pub use text_lint::TextLint;
+pub use type_replacements::TypeReplacements;
+pub use unstable::UNSTABLE_EXTENSION;
+pub use vcs::{BlessedCache, GitRunner};
@@ -0,0 +1,156 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Keeps concurrent `generate` runs from updating the same documents
+//! directory at once.
+//!
+//! Two runs applying fixes at the same time (say, one started by an editor and
+//! another from a terminal) can interleave their writes and leave the
+//! documents directory in a state neither would have produced. While applying
+//! fixes, `generate` holds an advisory lock on a file in the documents
+//! directory, and a second run either fails right away or, with `--wait`,
+//! waits for the first to finish.
+
+use anyhow::{Context, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use fs4::fs_std::FileExt;
+
+/// The name of the lock file, within the documents directory.
+///
+/// This is always ignored when loading local documents.
+pub(crate) const LOCK_FILE_NAME: &str = ".dropshot-api-manager.lock";
+
+/// An exclusive lock on a documents directory, released (and its lock file
+/// removed) when dropped.
+#[derive(Debug)]
+pub(crate) struct DocumentsLock {
+    // Held for the lock, which is released when the file is closed.
+    _file: fs_err::File,
+    path: Utf8PathBuf,
+}
+
+impl DocumentsLock {
+    /// Locks the documents directory `dir`.
+    ///
+    /// If another process holds the lock, this returns an error, or with
+    /// `wait`, calls `on_wait` and then blocks until the lock is released.
+    pub(crate) fn acquire(
+        dir: &Utf8Path,
+        wait: bool,
+        on_wait: impl FnOnce(),
+    ) -> anyhow::Result<Self> {
+        // The documents directory may not exist yet, e.g. before the first
+        // documents are generated.
+        fs_err::create_dir_all(dir)?;
+        let path = dir.join(LOCK_FILE_NAME);
+        let mut on_wait = Some(on_wait);
+        loop {
+            let file = fs_err::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&path)?;
+            let locked = file
+                .file()
+                .try_lock_exclusive()
+                .with_context(|| format!("failed to lock {}", path))?;
+            if !locked {
+                if !wait {
+                    bail!(
+                        "another process is updating documents in {} (it \
+                         holds the lock on {}); wait for it to finish, or \
+                         pass --wait to wait for it automatically",
+                        dir,
+                        path,
+                    );
+                }
+                if let Some(on_wait) = on_wait.take() {
+                    on_wait();
+                }
+                file.file()
+                    .lock_exclusive()
+                    .with_context(|| format!("failed to lock {}", path))?;
+            }
+
+            // The previous holder removes the lock file when it's done. If
+            // that happened after we opened the file, the lock we hold is on
+            // a file nobody else will see, so start over.
+            if is_same_file(&file, &path)? {
+                return Ok(Self { _file: file, path });
+            }
+        }
+    }
+}
+
+impl Drop for DocumentsLock {
+    fn drop(&mut self) {
+        // Remove the file while still holding the lock, so that it isn't left
+        // behind in the documents directory. Processes waiting on the removed
+        // file notice and retry (see `acquire`). Errors are ignored: at
+        // worst, the file is left in place for the next run to reuse.
+        //
+        // On Windows, an open file can't be fully removed, and a process
+        // waiting on it would fail to open it again, so the file is kept.
+        if cfg!(unix) {
+            let _ = fs_err::remove_file(&self.path);
+        }
+    }
+}
+
+/// Returns true if `file` is the file currently at `path`.
+#[cfg(unix)]
+fn is_same_file(file: &fs_err::File, path: &Utf8Path) -> anyhow::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let opened = file.metadata()?;
+    match fs_err::metadata(path) {
+        Ok(current) => {
+            Ok(opened.dev() == current.dev() && opened.ino() == current.ino())
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Returns true if `file` is the file currently at `path`.
+///
+/// The lock file is never removed on Windows, so this is always the case.
+#[cfg(windows)]
+fn is_same_file(
+    _file: &fs_err::File,
+    _path: &Utf8Path,
+) -> anyhow::Result<bool> {
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_contended_lock() {
+        let dir = camino_tempfile::tempdir().unwrap();
+        let lock = DocumentsLock::acquire(dir.path(), false, || {}).unwrap();
+
+        let error = DocumentsLock::acquire(dir.path(), false, || {})
+            .expect_err("lock is held");
+        assert!(
+            error.to_string().contains("pass --wait"),
+            "unexpected error: {error:#}"
+        );
+
+        // With `wait`, the lock is acquired once it's released.
+        let (tx, rx) = mpsc::channel();
+        let path = dir.path().to_owned();
+        let waiter = std::thread::spawn(move || {
+            DocumentsLock::acquire(&path, true, || tx.send(()).unwrap())
+        });
+        rx.recv().unwrap();
+        drop(lock);
+        let lock = waiter.join().unwrap().unwrap();
+        drop(lock);
+
+        #[cfg(unix)]
+        assert!(!dir.path().join(LOCK_FILE_NAME).exists());
+    }
+}
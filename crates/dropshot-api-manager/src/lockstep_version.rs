@@ -0,0 +1,188 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Picking the version of a lockstep API's document.
+//!
+//! See [`LockstepVersionPolicy`] for more.
+
+use anyhow::Context;
+use chrono::{Datelike, NaiveDate};
+use openapiv3::OpenAPI;
+
+/// How the `info.version` of a lockstep API's document is maintained.
+///
+/// By default, a lockstep document's version is the one in the API's
+/// configuration, and it only changes when someone remembers to change the
+/// configuration. With a policy attached via
+/// [`ManagedApi::with_lockstep_version_policy`], `generate` instead bumps the
+/// version in the local document whenever the document's contents change,
+/// and keeps it otherwise. `check` reports a document whose version hasn't
+/// been bumped as out of date.
+///
+/// Each run of `generate` that changes the document bumps the version, so
+/// iterating on a change can bump it more than once.
+///
+/// [`ManagedApi::with_lockstep_version_policy`]: crate::ManagedApi::with_lockstep_version_policy
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LockstepVersionPolicy {
+    scheme: Scheme,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Scheme {
+    BumpMajor,
+    BumpMinor,
+    BumpPatch,
+    Date,
+}
+
+impl LockstepVersionPolicy {
+    /// Bumps the major version when the document changes, e.g. from `1.2.3`
+    /// to `2.0.0`.
+    ///
+    /// The first document is given the configured version.
+    pub fn bump_major() -> Self {
+        Self { scheme: Scheme::BumpMajor }
+    }
+
+    /// Bumps the minor version when the document changes, e.g. from `1.2.3`
+    /// to `1.3.0`.
+    ///
+    /// The first document is given the configured version.
+    pub fn bump_minor() -> Self {
+        Self { scheme: Scheme::BumpMinor }
+    }
+
+    /// Bumps the patch version when the document changes, e.g. from `1.2.3`
+    /// to `1.2.4`.
+    ///
+    /// The first document is given the configured version.
+    pub fn bump_patch() -> Self {
+        Self { scheme: Scheme::BumpPatch }
+    }
+
+    /// Uses date-based versions of the form `YYYYMMDD.N.0`, where the major
+    /// version is the date (in UTC) the document last changed, and `N` counts
+    /// the earlier changes made that day.
+    ///
+    /// For example, the first change on January 1, 2026 produces
+    /// `20260101.0.0`, and a second change that day produces `20260101.1.0`.
+    /// The configured version is ignored.
+    pub fn date() -> Self {
+        Self { scheme: Scheme::Date }
+    }
+
+    /// Returns the version for a document that doesn't exist yet.
+    pub(crate) fn initial_version(
+        &self,
+        configured: &semver::Version,
+        today: NaiveDate,
+    ) -> semver::Version {
+        match self.scheme {
+            Scheme::BumpMajor | Scheme::BumpMinor | Scheme::BumpPatch => {
+                configured.clone()
+            }
+            Scheme::Date => semver::Version::new(date_number(today), 0, 0),
+        }
+    }
+
+    /// Returns the version for a document that changed from one at `current`.
+    pub(crate) fn next_version(
+        &self,
+        current: &semver::Version,
+        today: NaiveDate,
+    ) -> semver::Version {
+        let semver::Version { major, minor, patch, .. } = *current;
+        match self.scheme {
+            Scheme::BumpMajor => semver::Version::new(major + 1, 0, 0),
+            Scheme::BumpMinor => semver::Version::new(major, minor + 1, 0),
+            Scheme::BumpPatch => semver::Version::new(major, minor, patch + 1),
+            Scheme::Date => {
+                let today = date_number(today);
+                // If the current version is from today (or, with a skewed
+                // clock, from the future), count another change on that day
+                // so that versions keep increasing.
+                if major >= today {
+                    semver::Version::new(major, minor + 1, 0)
+                } else {
+                    semver::Version::new(today, 0, 0)
+                }
+            }
+        }
+    }
+}
+
+fn date_number(date: NaiveDate) -> u64 {
+    u64::from(date.year().unsigned_abs()) * 10000
+        + u64::from(date.month()) * 100
+        + u64::from(date.day())
+}
+
+/// Returns `contents` with its `info.version` set to `version`.
+pub(crate) fn with_version(
+    contents: &[u8],
+    version: &semver::Version,
+) -> anyhow::Result<Vec<u8>> {
+    let mut openapi: OpenAPI = serde_json::from_slice(contents)
+        .context("generated document is not valid OpenAPI")?;
+    openapi.info.version = version.to_string();
+    // Match the formatting of documents written by Dropshot.
+    let mut contents =
+        serde_json::to_vec_pretty(&openapi).context("serializing document")?;
+    contents.push(b'\n');
+    Ok(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> semver::Version {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_bump() {
+        let today = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        let current = v("1.2.3");
+        assert_eq!(
+            LockstepVersionPolicy::bump_major().next_version(&current, today),
+            v("2.0.0")
+        );
+        assert_eq!(
+            LockstepVersionPolicy::bump_minor().next_version(&current, today),
+            v("1.3.0")
+        );
+        assert_eq!(
+            LockstepVersionPolicy::bump_patch().next_version(&current, today),
+            v("1.2.4")
+        );
+        assert_eq!(
+            LockstepVersionPolicy::bump_minor()
+                .initial_version(&current, today),
+            current
+        );
+    }
+
+    #[test]
+    fn test_date() {
+        let policy = LockstepVersionPolicy::date();
+        let today = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        assert_eq!(
+            policy.initial_version(&v("1.0.0"), today),
+            v("20260101.0.0")
+        );
+        assert_eq!(
+            policy.next_version(&v("20251231.4.0"), today),
+            v("20260101.0.0")
+        );
+        assert_eq!(
+            policy.next_version(&v("20260101.0.0"), today),
+            v("20260101.1.0")
+        );
+        // Versions keep increasing even if the clock goes backwards.
+        assert_eq!(
+            policy.next_version(&v("20260102.0.0"), today),
+            v("20260102.1.0")
+        );
+    }
+}
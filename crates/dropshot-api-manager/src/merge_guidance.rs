@@ -0,0 +1,253 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Step-by-step guidance for resolving conflicting documents during a merge.
+//!
+//! When a merge (or rebase, or cherry-pick) stops with conflicts, the
+//! documents directory often has files with conflict markers, several files
+//! for the same version, or a conflicted "latest" symlink. Each of these is
+//! reported as a separate problem, but they share a cause, and the fix is
+//! almost always the same: renumber the versions added in this branch so they
+//! come after the ones added upstream, then let `generate` rewrite the
+//! documents. This module turns the problems into that plan, following
+//! _Merging with upstream changes to versioned APIs_ in the README.
+
+use crate::{
+    apis::ManagedApis,
+    environment::ResolvedEnv,
+    resolved::{Problem, ResolutionKind, Resolved},
+    vcs::VcsOperation,
+};
+use camino::Utf8PathBuf;
+use dropshot_api_manager_types::ApiIdent;
+use std::fmt;
+
+/// A plan for resolving the problems caused by an in-progress merge.
+#[derive(Debug)]
+pub(crate) struct MergeGuidance {
+    operation: VcsOperation,
+    command: String,
+    /// Versioned APIs whose blessed versions don't match the current code,
+    /// which during a merge usually means that both sides added the same
+    /// version.
+    renumber: Vec<Renumber>,
+    /// Documents that have conflict markers, or are otherwise corrupted.
+    conflicted: Vec<Utf8PathBuf>,
+    /// Documents for versions that were added in this branch, but have since
+    /// been superseded.
+    superseded: Vec<Utf8PathBuf>,
+    /// APIs whose "latest" symlink is stale or conflicted.
+    symlinks: Vec<ApiIdent>,
+}
+
+#[derive(Debug)]
+struct Renumber {
+    ident: ApiIdent,
+    collided: Vec<semver::Version>,
+    next: semver::Version,
+}
+
+impl MergeGuidance {
+    /// Returns guidance for the problems in `resolved`, or `None` if no
+    /// operation is in progress, or none of the problems look like the result
+    /// of one.
+    pub(crate) fn new(
+        env: &ResolvedEnv,
+        apis: &ManagedApis,
+        resolved: &Resolved<'_>,
+    ) -> Option<Self> {
+        let operation = env.vcs.operation_in_progress(&env.repo_root)?;
+        let mut guidance = MergeGuidance {
+            operation,
+            command: env.command.clone(),
+            renumber: Vec::new(),
+            conflicted: Vec::new(),
+            superseded: Vec::new(),
+            symlinks: Vec::new(),
+        };
+
+        for api in apis.iter_apis() {
+            let ident = api.ident();
+            let mut collided = Vec::new();
+            let mut latest_blessed = None;
+            for version in api.iter_versions_semver() {
+                let resolution = resolved
+                    .resolution_for_api_version(ident, version)
+                    .expect("resolution for all supported API versions");
+                if resolution.kind() == ResolutionKind::Blessed {
+                    latest_blessed = Some(version);
+                }
+                for problem in resolution.problems() {
+                    if resolution.kind() == ResolutionKind::Blessed
+                        && is_blessed_mismatch(problem)
+                        && !collided.contains(version)
+                    {
+                        collided.push(version.clone());
+                    }
+                    guidance.add_file_problem(problem);
+                }
+            }
+            if let Some(latest_blessed) = latest_blessed
+                && !collided.is_empty()
+            {
+                guidance.renumber.push(Renumber {
+                    ident: ident.clone(),
+                    collided,
+                    next: semver::Version::new(latest_blessed.major + 1, 0, 0),
+                });
+            }
+
+            if let Some(
+                Problem::LatestLinkStale { .. }
+                | Problem::LatestLinkDangling { .. },
+            ) = resolved.symlink_problem(ident)
+            {
+                guidance.symlinks.push(ident.clone());
+            }
+        }
+        for problem in resolved.general_problems() {
+            guidance.add_file_problem(problem);
+        }
+
+        let is_empty = guidance.renumber.is_empty()
+            && guidance.conflicted.is_empty()
+            && guidance.superseded.is_empty()
+            && guidance.symlinks.is_empty();
+        (!is_empty).then_some(guidance)
+    }
+
+    fn add_file_problem(&mut self, problem: &Problem<'_>) {
+        match problem {
+            Problem::UnparseableLocalFile { unparseable_file } => {
+                self.conflicted.push(unparseable_file.path.clone());
+            }
+            Problem::BlessedVersionCorruptedLocal { local_file, .. } => {
+                self.conflicted.push(local_file.spec_file_name().path());
+            }
+            Problem::BlessedVersionExtraLocalSpec { spec_file_name }
+            | Problem::LocalSpecFileOrphaned { spec_file_name } => {
+                self.superseded.push(spec_file_name.path());
+            }
+            Problem::LocalVersionExtra { spec_file_names } => {
+                self.superseded
+                    .extend(spec_file_names.0.iter().map(|name| name.path()));
+            }
+            Problem::LocalVersionStale { spec_files, .. }
+            | Problem::BlessedLatestVersionReformatted { spec_files, .. } => {
+                self.superseded.extend(
+                    spec_files.iter().map(|file| file.spec_file_name().path()),
+                );
+            }
+            Problem::DuplicateLocalFile { local_file } => {
+                self.superseded.push(local_file.spec_file_name().path());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for MergeGuidance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.operation.name();
+        writeln!(
+            f,
+            "A {name} is in progress, and some OpenAPI documents have \
+             problems caused by it. To resolve them:"
+        )?;
+        let mut step = 0;
+        let mut next_step = || {
+            step += 1;
+            step
+        };
+
+        for renumber in &self.renumber {
+            let collided: Vec<_> =
+                renumber.collided.iter().map(|v| v.to_string()).collect();
+            let (noun, verb, pronoun) = if collided.len() == 1 {
+                ("version", "doesn't", "it")
+            } else {
+                ("versions", "don't", "them")
+            };
+            writeln!(
+                f,
+                "  {}. Upstream's {} {} of {} {} match the code in this \
+                 branch, so this branch likely added {} too. In the \
+                 `api_versions!` call, keep every version from upstream, and \
+                 renumber the versions added in this branch to start at {}, \
+                 keeping the latest version first in the list.",
+                next_step(),
+                noun,
+                collided.join(", "),
+                renumber.ident,
+                verb,
+                pronoun,
+                renumber.next,
+            )?;
+        }
+
+        writeln!(
+            f,
+            "  {}. Resolve any other conflicts in API definitions and server \
+             code by hand. Don't edit conflicted OpenAPI documents or \
+             \"latest\" symlinks: `{} generate` rewrites them.",
+            next_step(),
+            self.command,
+        )?;
+
+        writeln!(
+            f,
+            "  {}. Run `{} generate`. It will:",
+            next_step(),
+            self.command
+        )?;
+        if !self.conflicted.is_empty() {
+            writeln!(
+                f,
+                "       - regenerate documents with conflict markers: {}",
+                join_paths(&self.conflicted),
+            )?;
+        }
+        if !self.superseded.is_empty() {
+            writeln!(
+                f,
+                "       - remove documents for superseded versions: {}",
+                join_paths(&self.superseded),
+            )?;
+        }
+        if !self.symlinks.is_empty() {
+            let idents: Vec<_> =
+                self.symlinks.iter().map(|i| i.as_str()).collect();
+            writeln!(
+                f,
+                "       - regenerate the \"latest\" symlink for {}",
+                idents.join(", "),
+            )?;
+        }
+        writeln!(
+            f,
+            "       - generate documents for the versions added in this branch"
+        )?;
+
+        writeln!(
+            f,
+            "  {}. Run `{} check`, then stage the changes and run `{}`.",
+            next_step(),
+            self.command,
+            self.operation.continue_command(),
+        )
+    }
+}
+
+/// Returns true if `problem` means that a blessed document doesn't match the
+/// current code.
+fn is_blessed_mismatch(problem: &Problem<'_>) -> bool {
+    matches!(
+        problem,
+        Problem::BlessedVersionBroken { .. }
+            | Problem::BlessedVersionReverseBroken { .. }
+            | Problem::BlessedLatestVersionBytewiseMismatch { .. }
+    )
+}
+
+fn join_paths(paths: &[Utf8PathBuf]) -> String {
+    paths.iter().map(|p| p.as_str()).collect::<Vec<_>>().join(", ")
+}
@@ -0,0 +1,181 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Metrics about `check` runs.
+//!
+//! `check --metrics-out PATH` writes metrics about the run, so that the
+//! health and runtime of API checks can be graphed across CI runs. The
+//! metrics are:
+//!
+//! * the result of the check;
+//! * how long each phase of the check took;
+//! * how many documents were processed, by source; and
+//! * how many problems were found, by kind.
+//!
+//! They're written in the [Prometheus text exposition format] (e.g. for the
+//! node exporter's textfile collector), or as JSON.
+//!
+//! [Prometheus text exposition format]: https://prometheus.io/docs/instrumenting/exposition_formats/
+
+use crate::{
+    check_output::result_name,
+    output::CheckResult,
+    resolved::{ProblemKind, ProblemSummary},
+};
+use clap::ValueEnum;
+use serde_json::{Map, Value, json};
+use std::{collections::BTreeMap, fmt::Write, time::Duration};
+
+/// The prefix of every Prometheus metric name.
+const PREFIX: &str = "dropshot_api_manager_check";
+
+/// The format metrics are written in.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub(crate) enum MetricsFormat {
+    /// The Prometheus text exposition format.
+    #[default]
+    Prometheus,
+    /// A JSON object.
+    Json,
+}
+
+/// Metrics recorded during a `check` run.
+#[derive(Debug, Default)]
+pub(crate) struct CheckMetrics {
+    result: Option<CheckResult>,
+    /// The duration of each phase, in the order they ran.
+    phases: Vec<(&'static str, Duration)>,
+    /// The number of documents processed, by source.
+    documents: BTreeMap<&'static str, usize>,
+    problems: BTreeMap<ProblemKind, usize>,
+}
+
+impl CheckMetrics {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that `phase` took `duration`.
+    pub(crate) fn record_phase(
+        &mut self,
+        phase: &'static str,
+        duration: Duration,
+    ) {
+        self.phases.push((phase, duration));
+    }
+
+    /// Records that `count` documents were loaded from `source`.
+    pub(crate) fn record_documents(
+        &mut self,
+        source: &'static str,
+        count: usize,
+    ) {
+        self.documents.insert(source, count);
+    }
+
+    /// Records the result of the check, and the problems it found.
+    pub(crate) fn record_result(
+        &mut self,
+        result: CheckResult,
+        summaries: &[ProblemSummary],
+    ) {
+        self.result = Some(result);
+        self.problems.clear();
+        for summary in summaries {
+            *self.problems.entry(summary.kind).or_default() += 1;
+        }
+    }
+
+    /// Renders the metrics in `format`.
+    pub(crate) fn render(&self, format: MetricsFormat) -> String {
+        match format {
+            MetricsFormat::Prometheus => self.render_prometheus(),
+            MetricsFormat::Json => self.render_json(),
+        }
+    }
+
+    fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String can't fail.
+        let mut metric = |name: &str, help: &str, samples: &[(String, f64)]| {
+            writeln!(out, "# HELP {PREFIX}_{name} {help}").unwrap();
+            writeln!(out, "# TYPE {PREFIX}_{name} gauge").unwrap();
+            for (labels, value) in samples {
+                writeln!(out, "{PREFIX}_{name}{labels} {value}").unwrap();
+            }
+        };
+
+        if let Some(result) = self.result {
+            metric(
+                "result",
+                "The result of the check (1 for the actual result).",
+                &[
+                    CheckResult::Success,
+                    CheckResult::NeedsUpdate,
+                    CheckResult::Failures,
+                ]
+                .map(|r| {
+                    let value = if r == result { 1.0 } else { 0.0 };
+                    (format!("{{result=\"{}\"}}", result_name(r)), value)
+                }),
+            );
+        }
+        let phases: Vec<_> = self
+            .phases
+            .iter()
+            .map(|(phase, duration)| {
+                (format!("{{phase=\"{phase}\"}}"), duration.as_secs_f64())
+            })
+            .collect();
+        metric(
+            "phase_duration_seconds",
+            "How long each phase of the check took.",
+            &phases,
+        );
+        let documents: Vec<_> = self
+            .documents
+            .iter()
+            .map(|(source, count)| {
+                (format!("{{source=\"{source}\"}}"), *count as f64)
+            })
+            .collect();
+        metric(
+            "documents",
+            "The number of documents processed, by source.",
+            &documents,
+        );
+        let problems: Vec<_> = self
+            .problems
+            .iter()
+            .map(|(kind, count)| {
+                (format!("{{kind=\"{kind:?}\"}}"), *count as f64)
+            })
+            .collect();
+        metric("problems", "The number of problems found, by kind.", &problems);
+        out
+    }
+
+    fn render_json(&self) -> String {
+        let phases: Map<String, Value> = self
+            .phases
+            .iter()
+            .map(|(phase, duration)| {
+                ((*phase).to_owned(), json!(duration.as_secs_f64()))
+            })
+            .collect();
+        let problems: Map<String, Value> = self
+            .problems
+            .iter()
+            .map(|(kind, count)| (format!("{kind:?}"), json!(count)))
+            .collect();
+        let value = json!({
+            "result": self.result.map(result_name),
+            "phase_duration_seconds": phases,
+            "documents": self.documents,
+            "problems": problems,
+        });
+        // Serializing a Value can't fail.
+        let mut out = serde_json::to_string_pretty(&value).unwrap();
+        out.push('\n');
+        out
+    }
+}
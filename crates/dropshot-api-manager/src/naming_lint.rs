@@ -0,0 +1,309 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Lints the names of parameters, properties and operations in OpenAPI
+//! documents.
+//!
+//! See [`NamingLint`] for more.
+
+use crate::compatibility::{PATH_ITEM_METHODS, escape_json_pointer};
+use anyhow::anyhow;
+use dropshot_api_manager_types::ValidationContext;
+use openapiv3::OpenAPI;
+use serde_json::Value;
+use std::{collections::BTreeSet, fmt};
+
+/// A convention for the casing of names.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum NamingConvention {
+    /// Lowercase words separated by underscores, e.g. `time_created`.
+    #[default]
+    SnakeCase,
+    /// Lowercase words separated by hyphens, e.g. `time-created`.
+    KebabCase,
+    /// Words joined together, starting with a lowercase letter, e.g.
+    /// `timeCreated`.
+    CamelCase,
+    /// Capitalized words joined together, e.g. `TimeCreated`.
+    PascalCase,
+}
+
+impl NamingConvention {
+    /// Returns true if `name` follows this convention.
+    pub fn matches(self, name: &str) -> bool {
+        let mut chars = name.chars();
+        let Some(first) = chars.next() else {
+            return false;
+        };
+        match self {
+            Self::SnakeCase => is_separated(name, '_'),
+            Self::KebabCase => is_separated(name, '-'),
+            Self::CamelCase => {
+                first.is_ascii_lowercase()
+                    && chars.all(|c| c.is_ascii_alphanumeric())
+            }
+            Self::PascalCase => {
+                first.is_ascii_uppercase()
+                    && chars.all(|c| c.is_ascii_alphanumeric())
+            }
+        }
+    }
+}
+
+impl fmt::Display for NamingConvention {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::SnakeCase => "snake_case",
+            Self::KebabCase => "kebab-case",
+            Self::CamelCase => "camelCase",
+            Self::PascalCase => "PascalCase",
+        })
+    }
+}
+
+/// Returns true if `name` consists of lowercase words (of letters and digits,
+/// starting with a letter) separated by single `separator`s.
+fn is_separated(name: &str, separator: char) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.split(separator).all(|word| {
+            !word.is_empty()
+                && word
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        })
+}
+
+/// Lints the names in an API's OpenAPI documents against a
+/// [`NamingConvention`].
+///
+/// Attach this to an API with [`ManagedApi::with_naming_lint`]. The names of
+/// path and query parameters, of schema properties, and of operations (their
+/// IDs) are then checked as part of validation, and any name that doesn't
+/// follow the convention fails validation. Blessed documents can't be
+/// changed, so they aren't linted.
+///
+/// Exceptions, e.g. for names that must match an external system, can be
+/// allowed with [`NamingLint::with_allowed`].
+///
+/// [`ManagedApi::with_naming_lint`]: crate::ManagedApi::with_naming_lint
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct NamingLint {
+    convention: NamingConvention,
+    allowed: BTreeSet<String>,
+}
+
+impl NamingLint {
+    /// Creates a lint requiring names to be in snake_case.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requires names to follow `convention` rather than snake_case.
+    pub fn with_convention(mut self, convention: NamingConvention) -> Self {
+        self.convention = convention;
+        self
+    }
+
+    /// Allows `name` even if it doesn't follow the convention.
+    pub fn with_allowed(mut self, name: impl Into<String>) -> Self {
+        self.allowed.insert(name.into());
+        self
+    }
+
+    /// Returns the convention names must follow.
+    pub fn convention(&self) -> NamingConvention {
+        self.convention
+    }
+
+    /// Returns the names allowed even if they don't follow the convention.
+    pub fn allowed(&self) -> &BTreeSet<String> {
+        &self.allowed
+    }
+
+    /// Lints the document, reporting each issue as a validation error.
+    pub(crate) fn validate(
+        &self,
+        openapi: &OpenAPI,
+        mut cx: ValidationContext<'_>,
+    ) {
+        if cx.is_blessed() == Some(true) {
+            return;
+        }
+
+        let document = match serde_json::to_value(openapi) {
+            Ok(document) => document,
+            Err(error) => {
+                cx.report_error(
+                    anyhow!(error).context("serializing document to lint it"),
+                );
+                return;
+            }
+        };
+        let mut issues = Vec::new();
+        self.lint_operations(&document, &mut issues);
+        self.lint_properties(&document, "", &mut issues);
+        for issue in issues {
+            cx.report_error(anyhow!(issue));
+        }
+    }
+
+    /// Lints the IDs and path and query parameters of every operation.
+    fn lint_operations(&self, document: &Value, issues: &mut Vec<String>) {
+        let Some(paths) = document.get("paths").and_then(Value::as_object)
+        else {
+            return;
+        };
+        for (path, item) in paths {
+            let item_pointer = format!("/paths/{}", escape_json_pointer(path));
+            self.lint_parameters(document, item, &item_pointer, issues);
+            for method in PATH_ITEM_METHODS {
+                let Some(operation) = item.get(*method) else {
+                    continue;
+                };
+                let pointer = format!("{}/{}", item_pointer, method);
+                if let Some(operation_id) =
+                    operation.get("operationId").and_then(Value::as_str)
+                {
+                    self.check(
+                        "operation ID",
+                        operation_id,
+                        &format!("{}/operationId", pointer),
+                        issues,
+                    );
+                }
+                self.lint_parameters(document, operation, &pointer, issues);
+            }
+        }
+    }
+
+    /// Lints the path and query parameters of a path item or operation.
+    fn lint_parameters(
+        &self,
+        document: &Value,
+        parent: &Value,
+        pointer: &str,
+        issues: &mut Vec<String>,
+    ) {
+        let Some(parameters) =
+            parent.get("parameters").and_then(Value::as_array)
+        else {
+            return;
+        };
+        for (i, parameter) in parameters.iter().enumerate() {
+            // Parameters may be shared as components.
+            let parameter = parameter
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|reference| reference.strip_prefix('#'))
+                .and_then(|reference| document.pointer(reference))
+                .unwrap_or(parameter);
+            let location = parameter.get("in").and_then(Value::as_str);
+            if !matches!(location, Some("path" | "query")) {
+                continue;
+            }
+            if let Some(name) = parameter.get("name").and_then(Value::as_str) {
+                self.check(
+                    "parameter",
+                    name,
+                    &format!("{}/parameters/{}/name", pointer, i),
+                    issues,
+                );
+            }
+        }
+    }
+
+    /// Lints the names of the properties of every schema within `value`,
+    /// found at the JSON pointer `pointer`.
+    fn lint_properties(
+        &self,
+        value: &Value,
+        pointer: &str,
+        issues: &mut Vec<String>,
+    ) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    let child =
+                        format!("{}/{}", pointer, escape_json_pointer(key));
+                    match (key.as_str(), value) {
+                        // Examples and defaults are data, not schemas.
+                        ("example" | "examples" | "default" | "enum", _) => {}
+                        ("properties", Value::Object(properties)) => {
+                            for (name, schema) in properties {
+                                let property = format!(
+                                    "{}/{}",
+                                    child,
+                                    escape_json_pointer(name)
+                                );
+                                self.check("property", name, &property, issues);
+                                self.lint_properties(schema, &property, issues);
+                            }
+                        }
+                        _ => self.lint_properties(value, &child, issues),
+                    }
+                }
+            }
+            Value::Array(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    self.lint_properties(
+                        value,
+                        &format!("{}/{}", pointer, i),
+                        issues,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check(
+        &self,
+        kind: &str,
+        name: &str,
+        pointer: &str,
+        issues: &mut Vec<String>,
+    ) {
+        if !self.convention.matches(name) && !self.allowed.contains(name) {
+            issues.push(format!(
+                "{}: {} {:?} is not in {}",
+                pointer, kind, name, self.convention
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_naming_convention_matches() {
+        let cases = [
+            ("time_created", [true, false, false, false]),
+            ("time-created", [false, true, false, false]),
+            ("timeCreated", [false, false, true, false]),
+            ("TimeCreated", [false, false, false, true]),
+            ("time", [true, true, true, false]),
+            ("ipv4_addr2", [true, false, false, false]),
+            ("time__created", [false, false, false, false]),
+            ("_time", [false, false, false, false]),
+            ("", [false, false, false, false]),
+        ];
+        let conventions = [
+            NamingConvention::SnakeCase,
+            NamingConvention::KebabCase,
+            NamingConvention::CamelCase,
+            NamingConvention::PascalCase,
+        ];
+        for (name, expected) in cases {
+            for (convention, expected) in conventions.iter().zip(expected) {
+                assert_eq!(
+                    convention.matches(name),
+                    expected,
+                    "{:?} in {}",
+                    name,
+                    convention,
+                );
+            }
+        }
+    }
+}
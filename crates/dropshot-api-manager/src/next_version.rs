@@ -0,0 +1,164 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Picking the number of the next version of a versioned API.
+//!
+//! This is shared by the `new-version` command and
+//! [`ManagedApis::next_version`], which differ in how they report problems
+//! loading documents.
+
+use crate::{
+    apis::ManagedApis,
+    environment::{BlessedSource, ErrorAccumulator, ResolvedEnv},
+    output::Styles,
+};
+use anyhow::bail;
+use chrono::{Datelike, NaiveDate};
+use dropshot_api_manager_types::{ApiIdent, DateVersion, VersionScheme};
+
+/// Returns the latest version of the versioned API `ident` that's supported,
+/// blessed, or present locally.
+///
+/// Local documents for versions that aren't supported are included: they're
+/// typically versions added on another branch that's in flight, and reusing
+/// their number would conflict once both are merged.
+///
+/// Also returns the problems found while loading documents, for the caller
+/// to report.
+pub(crate) fn latest_version_in_use(
+    apis: &ManagedApis,
+    env: &ResolvedEnv,
+    blessed_source: &BlessedSource,
+    ident: &ApiIdent,
+    styles: &Styles,
+) -> anyhow::Result<(semver::Version, ErrorAccumulator)> {
+    let Some(api) = apis.api(ident) else {
+        bail!("unknown API {:?}", ident);
+    };
+    let Some(versions) = api.iter_versioned_versions() else {
+        bail!("{} is a lockstep API, so it has no versions to add to", ident);
+    };
+
+    let (local_files, mut problems) = env.local_source.load(
+        apis,
+        styles,
+        &env.repo_root,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    let (blessed, blessed_problems) = blessed_source.load(
+        &env.repo_root,
+        apis,
+        styles,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    problems.extend(blessed_problems);
+
+    let blessed_versions =
+        blessed.get(ident).into_iter().flat_map(|f| f.versions().keys());
+    let local_versions =
+        local_files.get(ident).into_iter().flat_map(|f| f.versions().keys());
+    let latest = versions
+        .map(|v| v.semver())
+        .chain(blessed_versions)
+        .chain(local_versions)
+        .max()
+        .expect("versioned APIs have at least one version");
+    Ok((latest.clone(), problems))
+}
+
+/// Returns the version to add after `latest`, following `scheme`.
+///
+/// Date-encoded versions are for `today`, with a serial number of 00. If
+/// `latest` is already from today (or, with a skewed clock, from the future),
+/// the next version has the next serial number on that day, so that versions
+/// keep increasing. Other versions are one major version past `latest`.
+pub(crate) fn next_version_after(
+    latest: &semver::Version,
+    scheme: Option<VersionScheme>,
+    today: NaiveDate,
+) -> anyhow::Result<semver::Version> {
+    let Some(VersionScheme::DateEncoded) = scheme else {
+        return Ok(semver::Version::new(latest.major + 1, 0, 0));
+    };
+    let latest = DateVersion::from_semver(latest)?;
+    // The casts are lossless for any date a clock reports.
+    let first_today = DateVersion::new(
+        today.year() as u16,
+        today.month() as u8,
+        today.day() as u8,
+        0,
+    );
+    if first_today > latest {
+        return Ok(first_today.to_semver());
+    }
+    if latest.serial() >= 99 {
+        bail!(
+            "no more versions can be added on {:04}-{:02}-{:02}: {} already \
+             uses the last serial number, 99",
+            latest.year(),
+            latest.month(),
+            latest.day(),
+            latest,
+        );
+    }
+    let next = DateVersion::new(
+        latest.year(),
+        latest.month(),
+        latest.day(),
+        latest.serial() + 1,
+    );
+    Ok(next.to_semver())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> semver::Version {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_next_sequential_version() {
+        let today = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        for scheme in [None, Some(VersionScheme::Sequential)] {
+            assert_eq!(
+                next_version_after(&v("3.0.0"), scheme, today).unwrap(),
+                v("4.0.0")
+            );
+        }
+    }
+
+    #[test]
+    fn test_next_date_version() {
+        let scheme = Some(VersionScheme::DateEncoded);
+        let today = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+
+        // The first version on a new day has a serial number of 00.
+        assert_eq!(
+            next_version_after(&v("2025123103.0.0"), scheme, today).unwrap(),
+            v("2026010100.0.0")
+        );
+
+        // Later versions on the same day take the next serial number.
+        assert_eq!(
+            next_version_after(&v("2026010100.0.0"), scheme, today).unwrap(),
+            v("2026010101.0.0")
+        );
+
+        // Versions keep increasing even if the clock goes backwards.
+        assert_eq!(
+            next_version_after(&v("2026010205.0.0"), scheme, today).unwrap(),
+            v("2026010206.0.0")
+        );
+
+        // There are no serial numbers past 99.
+        let error = next_version_after(&v("2026010199.0.0"), scheme, today)
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("no more versions can be added"),
+            "{error:#}"
+        );
+    }
+}
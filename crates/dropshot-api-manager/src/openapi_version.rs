@@ -0,0 +1,93 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Keeping the `openapi` field of generated documents consistent.
+//!
+//! Different versions of Dropshot write different OpenAPI versions (e.g.
+//! `3.0.3` or `3.0.4`) into the `openapi` field of the documents they
+//! generate. Pinning the field with [`Environment::with_openapi_version`]
+//! keeps upgrading Dropshot from changing every document.
+//!
+//! [`Environment::with_openapi_version`]: crate::Environment::with_openapi_version
+
+use crate::spec_files_generated::GeneratedFiles;
+use anyhow::{Context, anyhow, ensure};
+use dropshot_api_manager_types::ApiIdent;
+use openapiv3::OpenAPI;
+use std::collections::BTreeMap;
+
+/// Returns an error if `version` isn't an OpenAPI 3.0 version, such as
+/// `3.0.3`.
+///
+/// Documents are loaded as OpenAPI 3.0, so they can't claim to follow any
+/// other version of the specification.
+pub(crate) fn validate_openapi_version(version: &str) -> anyhow::Result<()> {
+    let parsed: semver::Version = version
+        .parse()
+        .with_context(|| format!("invalid OpenAPI version {:?}", version))?;
+    ensure!(
+        parsed.major == 3
+            && parsed.minor == 0
+            && parsed.pre.is_empty()
+            && parsed.build.is_empty(),
+        "invalid OpenAPI version {:?}: expected a version of the form 3.0.N",
+        version,
+    );
+    Ok(())
+}
+
+/// Returns `contents` with its `openapi` field set to `version`.
+///
+/// Documents that already have that version are returned unchanged.
+pub(crate) fn with_openapi_version(
+    contents: Vec<u8>,
+    version: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let mut openapi: OpenAPI = serde_json::from_slice(&contents)
+        .context("generated document is not valid OpenAPI")?;
+    if openapi.openapi == version {
+        return Ok(contents);
+    }
+    openapi.openapi = version.to_owned();
+    // Match the formatting of documents written by Dropshot.
+    let mut contents =
+        serde_json::to_vec_pretty(&openapi).context("serializing document")?;
+    contents.push(b'\n');
+    Ok(contents)
+}
+
+/// Returns a warning if the generated documents don't all have the same
+/// `openapi` field.
+///
+/// This happens when APIs are built with different versions of Dropshot, or
+/// come from elsewhere.
+pub(crate) fn mixed_openapi_versions(
+    generated: &GeneratedFiles,
+) -> Option<anyhow::Error> {
+    let mut by_version: BTreeMap<&str, Vec<&ApiIdent>> = BTreeMap::new();
+    for (ident, files) in generated.iter() {
+        for file in files.versions().values() {
+            let idents =
+                by_version.entry(file.openapi().openapi.as_str()).or_default();
+            if idents.last() != Some(&ident) {
+                idents.push(ident);
+            }
+        }
+    }
+    if by_version.len() < 2 {
+        return None;
+    }
+
+    let versions: Vec<_> = by_version
+        .iter()
+        .map(|(version, idents)| {
+            let idents: Vec<_> =
+                idents.iter().map(|ident| ident.as_str()).collect();
+            format!("{} ({})", version, idents.join(", "))
+        })
+        .collect();
+    Some(anyhow!(
+        "generated documents use different OpenAPI versions: {}; to make \
+         them consistent, set one with `Environment::with_openapi_version`",
+        versions.join(", "),
+    ))
+}
@@ -1,20 +1,22 @@
-// Copyright 2025 Oxide Computer Company
+// Copyright 2026 Oxide Computer Company
 
 use crate::{
     FAILURE_EXIT_CODE, NEEDS_UPDATE_EXIT_CODE,
     apis::{ManagedApi, ManagedApis},
     environment::{ErrorAccumulator, ResolvedEnv},
+    merge_guidance::MergeGuidance,
     resolved::{Problem, Resolution, ResolutionKind, Resolved},
     validation::CheckStale,
 };
 use anyhow::bail;
-use camino::Utf8Path;
+use camino::{Utf8Path, Utf8PathBuf};
 use clap::{Args, ColorChoice};
 use headers::*;
 use indent_write::fmt::IndentWriter;
 use owo_colors::{OwoColorize, Style};
 use similar::{ChangeTag, DiffableStr, TextDiff};
 use std::{
+    borrow::Cow,
     fmt::{self, Write},
     io,
     process::ExitCode,
@@ -26,6 +28,54 @@ pub struct OutputOpts {
     /// Color output
     #[clap(long, value_enum, global = true, default_value_t)]
     pub(crate) color: ColorChoice,
+
+    /// Maximum number of problems to print in detail (0 for no limit)
+    ///
+    /// Problems beyond this limit are counted, but not printed.
+    #[clap(
+        long,
+        global = true,
+        value_name = "N",
+        default_value_t = DEFAULT_MAX_PROBLEMS
+    )]
+    pub(crate) max_problems: usize,
+
+    /// Only print ASCII characters
+    ///
+    /// This is enabled automatically if the locale (`LC_ALL`, `LC_CTYPE`, or
+    /// `LANG`) is set to a non-UTF-8 value.
+    #[clap(long, global = true)]
+    pub(crate) ascii: bool,
+
+    /// Log every git command run, with its exit status, duration, and the
+    /// number of bytes read from it
+    ///
+    /// The log, followed by a summary, is written to standard error, or with
+    /// `--trace-git=PATH`, appended to PATH. In Jujutsu repositories, jj
+    /// commands are logged too.
+    #[clap(
+        long,
+        global = true,
+        value_name = "PATH",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "-"
+    )]
+    pub(crate) trace_git: Option<Utf8PathBuf>,
+}
+
+/// The default value for `--max-problems`.
+pub(crate) const DEFAULT_MAX_PROBLEMS: usize = 50;
+
+impl Default for OutputOpts {
+    fn default() -> Self {
+        Self {
+            color: ColorChoice::default(),
+            max_problems: DEFAULT_MAX_PROBLEMS,
+            ascii: false,
+            trace_git: None,
+        }
+    }
 }
 
 impl OutputOpts {
@@ -38,6 +88,11 @@ impl OutputOpts {
         }
     }
 
+    /// Returns true if output should be restricted to ASCII.
+    pub(crate) fn use_ascii(&self) -> bool {
+        self.ascii || !locale_is_utf8()
+    }
+
     /// Creates a `Styles` instance, colorized if color is enabled for the
     /// given stream.
     pub(crate) fn styles(&self, stream: supports_color::Stream) -> Styles {
@@ -45,8 +100,60 @@ impl OutputOpts {
         if self.use_color(stream) {
             styles.colorize();
         }
+        styles.ascii = self.use_ascii();
         styles
     }
+
+    /// Returns a new [`ProblemLimit`] based on `--max-problems`.
+    pub(crate) fn problem_limit(&self) -> ProblemLimit {
+        ProblemLimit::new(self.max_problems)
+    }
+}
+
+/// Tracks how many problems have been printed in detail, so that output can be
+/// truncated after `--max-problems`.
+#[derive(Debug)]
+pub(crate) struct ProblemLimit {
+    /// The maximum number of problems to print, or `None` for no limit.
+    max: Option<usize>,
+    shown: usize,
+    suppressed: usize,
+}
+
+impl ProblemLimit {
+    /// Creates a new limit, where `max` of 0 means no limit.
+    pub(crate) fn new(max: usize) -> Self {
+        Self { max: (max > 0).then_some(max), shown: 0, suppressed: 0 }
+    }
+
+    /// Creates a limit under which all problems are printed.
+    pub(crate) fn unlimited() -> Self {
+        Self::new(0)
+    }
+
+    /// Records a problem, returning true if it should be printed.
+    fn admit(&mut self) -> bool {
+        if self.max.is_some_and(|max| self.shown >= max) {
+            self.suppressed += 1;
+            false
+        } else {
+            self.shown += 1;
+            true
+        }
+    }
+
+    /// Prints a trailer describing suppressed problems, if any.
+    pub(crate) fn display_trailer(&self, styles: &Styles) {
+        if self.suppressed > 0 {
+            eprintln!(
+                "{:>HEADER_WIDTH$} and {} more {} (rerun with {} to see all)",
+                "...".style(styles.warning_header),
+                self.suppressed.style(styles.bold),
+                plural::problems(self.suppressed),
+                "--max-problems 0".style(styles.bold),
+            );
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -61,9 +168,18 @@ pub(crate) struct Styles {
     pub(crate) filename: Style,
     pub(crate) diff_before: Style,
     pub(crate) diff_after: Style,
+    /// If true, non-ASCII characters are replaced in text passed through
+    /// [`Styles::text`].
+    pub(crate) ascii: bool,
 }
 
 impl Styles {
+    /// Renders text for output, replacing non-ASCII characters if ASCII output
+    /// is enabled.
+    pub(crate) fn text<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        if self.ascii { to_ascii(text) } else { Cow::Borrowed(text) }
+    }
+
     pub(crate) fn colorize(&mut self) {
         self.bold = Style::new().bold();
         self.header = Style::new().purple();
@@ -78,6 +194,51 @@ impl Styles {
     }
 }
 
+/// Returns true if the locale, as determined by the usual environment
+/// variables, is either unset or uses UTF-8.
+fn locale_is_utf8() -> bool {
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .into_iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty());
+    match locale {
+        // Assume UTF-8 if no locale is set, since that's the norm today.
+        None => true,
+        Some(locale) => {
+            let locale = locale.to_ascii_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        }
+    }
+}
+
+/// Replaces non-ASCII characters in `text` with ASCII equivalents, or with
+/// escape sequences if there's no reasonable equivalent.
+fn to_ascii(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            c if c.is_ascii() => out.push(c),
+            '\u{2018}' | '\u{2019}' | '\u{2032}' => out.push('\''),
+            '\u{201c}' | '\u{201d}' | '\u{2033}' => out.push('"'),
+            '\u{2010}'..='\u{2013}' | '\u{2212}' => out.push('-'),
+            '\u{2014}' | '\u{2015}' => out.push_str("--"),
+            '\u{2026}' => out.push_str("..."),
+            '\u{2192}' | '\u{27f6}' => out.push_str("->"),
+            '\u{2190}' | '\u{27f5}' => out.push_str("<-"),
+            '\u{21d2}' => out.push_str("=>"),
+            '\u{2022}' => out.push('*'),
+            '\u{a0}' => out.push(' '),
+            c => write!(out, "\\u{{{:x}}}", c as u32)
+                .expect("writing to a String is infallible"),
+        }
+    }
+    Cow::Owned(out)
+}
+
 // This is copied from similar's UnifiedDiff::to_writer, except with colorized
 // output.
 pub(crate) fn write_diff<'diff, 'old, 'new, 'bufs, T>(
@@ -115,7 +276,8 @@ where
             };
 
             write!(out, "{}", change.tag().style(style))?;
-            write!(out, "{}", change.value().to_string_lossy().style(style))?;
+            let value = change.value().to_string_lossy();
+            write!(out, "{}", styles.text(&value).style(style))?;
             if !diff.newline_terminated() {
                 writeln!(out)?;
             }
@@ -163,6 +325,14 @@ pub(crate) fn display_api_spec_version(
 ) -> String {
     if api.is_lockstep() {
         assert_eq!(resolution.kind(), ResolutionKind::Lockstep);
+        // With a version policy, the configured version isn't the document's.
+        if api.lockstep_version_policy().is_some() {
+            return format!(
+                "{} (lockstep): {}",
+                api.ident().style(styles.filename),
+                api.title(),
+            );
+        }
         format!(
             "{} (lockstep v{}): {}",
             api.ident().style(styles.filename),
@@ -228,9 +398,9 @@ pub fn display_load_problems(
 ) -> anyhow::Result<()> {
     for w in error_accumulator.iter_warnings() {
         eprintln!(
-            "{:>HEADER_WIDTH$} {:#}",
+            "{:>HEADER_WIDTH$} {}",
             WARNING.style(styles.warning_header),
-            w
+            styles.text(&format!("{:#}", w)),
         );
     }
 
@@ -238,9 +408,9 @@ pub fn display_load_problems(
     for e in error_accumulator.iter_errors() {
         nerrors += 1;
         eprintln!(
-            "{:>HEADER_WIDTH$} {:#}",
+            "{:>HEADER_WIDTH$} {}",
             FAILURE.style(styles.failure_header),
-            e
+            styles.text(&format!("{:#}", e)),
         );
     }
 
@@ -257,11 +427,14 @@ pub fn display_load_problems(
 
 /// Summarize the results of checking all supported API versions, plus other
 /// problems found during resolution
+///
+/// At most `limit` problems are printed in detail.
 pub fn display_resolution(
     env: &ResolvedEnv,
     apis: &ManagedApis,
     resolved: &Resolved,
     styles: &Styles,
+    limit: &mut ProblemLimit,
 ) -> anyhow::Result<CheckResult> {
     let total = resolved.nexpected_documents();
 
@@ -293,7 +466,72 @@ pub fn display_resolution(
             } else {
                 num_fresh += 1;
             }
-            summarize_one(env, api, version, resolution, styles);
+            summarize_one(env, api, version, resolution, styles, limit);
+        }
+
+        for alias in api.aliases() {
+            let problems: Vec<_> = resolved
+                .alias_problems(ident)
+                .filter(|p| p.alias() == Some(alias))
+                .collect();
+            if problems.is_empty() {
+                num_fresh += 1;
+                eprintln!(
+                    "{:>HEADER_WIDTH$} {} alias {}",
+                    FRESH.style(styles.success_header),
+                    ident.style(styles.filename),
+                    alias.style(styles.filename),
+                );
+                continue;
+            }
+
+            let header = if problems.iter().all(|p| p.is_fixable()) {
+                num_general_problems += 1;
+                STALE.style(styles.warning_header)
+            } else {
+                num_failed += 1;
+                FAILURE.style(styles.failure_header)
+            };
+            eprintln!(
+                "{:>HEADER_WIDTH$} {} alias {}",
+                header,
+                ident.style(styles.filename),
+                alias.style(styles.filename),
+            );
+            display_resolution_problems_limited(env, problems, styles, limit);
+        }
+
+        // Show the format of documents for APIs that have one configured.
+        match resolved.json_format_problem(ident) {
+            None if api.json_format().is_some() => {
+                num_fresh += 1;
+                eprintln!(
+                    "{:>HEADER_WIDTH$} {} JSON format",
+                    FRESH.style(styles.success_header),
+                    ident.style(styles.filename),
+                );
+            }
+            None => {}
+            Some(problem) => {
+                let header = if problem.is_fixable() {
+                    num_general_problems += 1;
+                    STALE.style(styles.warning_header)
+                } else {
+                    num_failed += 1;
+                    FAILURE.style(styles.failure_header)
+                };
+                eprintln!(
+                    "{:>HEADER_WIDTH$} {} JSON format",
+                    header,
+                    ident.style(styles.filename),
+                );
+                display_resolution_problems_limited(
+                    env,
+                    std::iter::once(problem),
+                    styles,
+                    limit,
+                );
+            }
         }
 
         if !api.is_versioned() {
@@ -308,10 +546,11 @@ pub fn display_resolution(
                     STALE.style(styles.warning_header),
                     ident.style(styles.filename),
                 );
-                display_resolution_problems(
+                display_resolution_problems_limited(
                     env,
                     std::iter::once(symlink_problem),
                     styles,
+                    limit,
                 );
             } else {
                 num_failed += 1;
@@ -320,10 +559,11 @@ pub fn display_resolution(
                     FAILURE.style(styles.failure_header),
                     ident.style(styles.filename),
                 );
-                display_resolution_problems(
+                display_resolution_problems_limited(
                     env,
                     std::iter::once(symlink_problem),
                     styles,
+                    limit,
                 );
             }
         } else {
@@ -334,6 +574,75 @@ pub fn display_resolution(
                 ident.style(styles.filename),
             );
         }
+
+        // Show shared schemas for APIs that use them, as well as for those
+        // with a shared directory left over.
+        let shared_schemas_problem = resolved.shared_schemas_problem(ident);
+        match shared_schemas_problem {
+            None if api.has_shared_schemas() => {
+                num_fresh += 1;
+                eprintln!(
+                    "{:>HEADER_WIDTH$} {} shared schemas",
+                    FRESH.style(styles.success_header),
+                    ident.style(styles.filename),
+                );
+            }
+            None => {}
+            Some(problem) => {
+                let header = if problem.is_fixable() {
+                    num_general_problems += 1;
+                    STALE.style(styles.warning_header)
+                } else {
+                    num_failed += 1;
+                    FAILURE.style(styles.failure_header)
+                };
+                eprintln!(
+                    "{:>HEADER_WIDTH$} {} shared schemas",
+                    header,
+                    ident.style(styles.filename),
+                );
+                display_resolution_problems_limited(
+                    env,
+                    std::iter::once(problem),
+                    styles,
+                    limit,
+                );
+            }
+        }
+
+        if !api.has_combined_document() {
+            continue;
+        }
+        match resolved.combined_document_problem(ident) {
+            None => {
+                num_fresh += 1;
+                eprintln!(
+                    "{:>HEADER_WIDTH$} {} combined document",
+                    FRESH.style(styles.success_header),
+                    ident.style(styles.filename),
+                );
+            }
+            Some(problem) => {
+                let header = if problem.is_fixable() {
+                    num_general_problems += 1;
+                    STALE.style(styles.warning_header)
+                } else {
+                    num_failed += 1;
+                    FAILURE.style(styles.failure_header)
+                };
+                eprintln!(
+                    "{:>HEADER_WIDTH$} {} combined document",
+                    header,
+                    ident.style(styles.filename),
+                );
+                display_resolution_problems_limited(
+                    env,
+                    std::iter::once(problem),
+                    styles,
+                    limit,
+                );
+            }
+        }
     }
 
     // Print problems not associated with any supported version, if any.
@@ -348,12 +657,19 @@ pub fn display_resolution(
         let (fixable, unfixable): (Vec<&Problem>, Vec<&Problem>) =
             general_problems.iter().partition(|p| p.is_fixable());
         num_failed += unfixable.len();
-        display_resolution_problems(env, general_problems, styles);
+        display_resolution_problems_limited(
+            env,
+            general_problems,
+            styles,
+            limit,
+        );
         fixable.len()
     } else {
         0
     };
 
+    limit.display_trailer(styles);
+
     // Print informational notes, if any.
     for n in resolved.notes() {
         let initial_indent =
@@ -362,7 +678,7 @@ pub fn display_resolution(
         eprintln!(
             "\n{}\n",
             textwrap::fill(
-                &n.to_string(),
+                &styles.text(&n.to_string()),
                 textwrap::Options::with_termwidth()
                     .initial_indent(&initial_indent)
                     .subsequent_indent(&more_indent)
@@ -370,6 +686,24 @@ pub fn display_resolution(
         );
     }
 
+    // If the problems look like they were caused by an in-progress merge,
+    // print a plan for resolving them.
+    if let Some(guidance) = MergeGuidance::new(env, apis, resolved) {
+        let more_indent = " ".repeat(HEADER_WIDTH + " ".len());
+        for (ix, line) in guidance.to_string().lines().enumerate() {
+            if ix == 0 {
+                eprintln!(
+                    "\n{:>HEADER_WIDTH$} {}",
+                    "Merge".style(styles.warning_header),
+                    line,
+                );
+            } else {
+                eprintln!("{}{}", more_indent, line);
+            }
+        }
+        eprintln!();
+    }
+
     // Print a summary line.
     let status_header = if num_failed > 0 {
         FAILURE.style(styles.failure_header)
@@ -442,6 +776,7 @@ fn summarize_one(
     version: &semver::Version,
     resolution: &Resolution<'_>,
     styles: &Styles,
+    limit: &mut ProblemLimit,
 ) {
     let problems: Vec<_> = resolution.problems().collect();
     if problems.is_empty() {
@@ -464,7 +799,7 @@ fn summarize_one(
             display_api_spec_version(api, version, styles, resolution),
         );
 
-        display_resolution_problems(env, problems, styles);
+        display_resolution_problems_limited(env, problems, styles, limit);
     }
 }
 
@@ -475,8 +810,71 @@ pub fn display_resolution_problems<'a, T>(
     styles: &Styles,
 ) where
     T: IntoIterator<Item = &'a Problem<'a>>,
+{
+    display_resolution_problems_limited(
+        env,
+        problems,
+        styles,
+        &mut ProblemLimit::unlimited(),
+    )
+}
+
+/// Returns a diff between the document found on disk and the one expected, for
+/// problems fixed by rewriting a stale file.
+pub(crate) fn stale_diff<'a>(
+    env: &ResolvedEnv,
+    p: &'a Problem<'_>,
+) -> Option<(TextDiff<'a, 'a, 'a, [u8]>, Utf8PathBuf, Utf8PathBuf)> {
+    match p {
+        Problem::LockstepStale { found, generated } => {
+            let diff =
+                TextDiff::from_lines(found.contents(), generated.contents());
+            let path1 =
+                env.openapi_abs_dir().join(found.spec_file_name().path());
+            let path2 =
+                env.openapi_abs_dir().join(generated.spec_file_name().path());
+            Some((diff, path1, path2))
+        }
+        Problem::ExtraFileStale {
+            check_stale: CheckStale::Modified { full_path, actual, expected },
+            ..
+        } => {
+            let diff = TextDiff::from_lines(actual, expected);
+            Some((diff, full_path.clone(), full_path.clone()))
+        }
+        Problem::LocalVersionStale { spec_files, generated }
+        | Problem::BlessedLatestVersionReformatted { spec_files, generated }
+            if spec_files.len() == 1 =>
+        {
+            let diff = TextDiff::from_lines(
+                spec_files[0].contents(),
+                generated.contents(),
+            );
+            let path1 = env
+                .openapi_abs_dir()
+                .join(spec_files[0].spec_file_name().path());
+            let path2 =
+                env.openapi_abs_dir().join(generated.spec_file_name().path());
+            Some((diff, path1, path2))
+        }
+        _ => None,
+    }
+}
+
+/// Print a formatted list of Problems, skipping those beyond `limit`.
+fn display_resolution_problems_limited<'a, T>(
+    env: &ResolvedEnv,
+    problems: T,
+    styles: &Styles,
+    limit: &mut ProblemLimit,
+) where
+    T: IntoIterator<Item = &'a Problem<'a>>,
 {
     for p in problems.into_iter() {
+        if !limit.admit() {
+            continue;
+        }
+
         let subheader_width = HEADER_WIDTH + 4;
         let first_indent = format!(
             "{:>subheader_width$}: ",
@@ -490,16 +888,31 @@ pub fn display_resolution_problems<'a, T>(
         eprintln!(
             "{}",
             textwrap::fill(
-                &InlineErrorChain::new(&p).to_string(),
+                &styles.text(&InlineErrorChain::new(&p).to_string()),
                 textwrap::Options::with_termwidth()
                     .initial_indent(&first_indent)
                     .subsequent_indent(&more_indent)
             )
         );
 
-        // For BlessedVersionBroken, print each item separately, along with a
-        // diff between blessed and generated versions.
-        if let Problem::BlessedVersionBroken { compatibility_issues } = &p {
+        // For BlessedVersionBroken and BlessedVersionReverseBroken, print
+        // each item separately, along with a diff between blessed and
+        // generated versions. The reverse check compares the documents the
+        // other way around, so its issues have the sides swapped. Only
+        // issues found by the forward check can be approved, so only those
+        // are printed with the hash to approve them by.
+        let broken = match &p {
+            Problem::BlessedVersionBroken { compatibility_issues } => {
+                Some((compatibility_issues, "blessed", "generated", true))
+            }
+            Problem::BlessedVersionReverseBroken { compatibility_issues } => {
+                Some((compatibility_issues, "generated", "blessed", false))
+            }
+            _ => None,
+        };
+        if let Some((compatibility_issues, old_label, new_label, approvable)) =
+            broken
+        {
             for issue in compatibility_issues {
                 // Print each compatibility issue on a new line, prefixed with
                 // "- ".
@@ -508,13 +921,21 @@ pub fn display_resolution_problems<'a, T>(
                 eprintln!(
                     "{}",
                     textwrap::fill(
-                        &issue.to_string(),
+                        &styles.text(&issue.to_string()),
                         textwrap::Options::with_termwidth()
                             .initial_indent(&nested_first_indent)
                             .subsequent_indent(&nested_more_indent)
                     )
                 );
 
+                if approvable {
+                    eprintln!(
+                        "{}issue hash: {}",
+                        nested_more_indent,
+                        issue.approval_hash(),
+                    );
+                }
+
                 // Now print a textual diff between the blessed and generated
                 // versions.
                 let blessed_json = issue.blessed_json();
@@ -525,8 +946,8 @@ pub fn display_resolution_problems<'a, T>(
                 // using eprintln! above).
                 let _ = write_diff(
                     &diff,
-                    "blessed".as_ref(),
-                    "generated".as_ref(),
+                    old_label.as_ref(),
+                    new_label.as_ref(),
                     styles,
                     // context_radius: use a large radius to ensure that most of
                     // the schema is printed out.
@@ -578,7 +999,7 @@ pub fn display_resolution_problems<'a, T>(
             "{:>subheader_width$}: ",
             "fix".style(styles.warning_header)
         );
-        let fix_str = fix.to_string();
+        let fix_str = styles.text(&fix.to_string()).into_owned();
         let steps = fix_str.trim_end().split("\n");
         for s in steps {
             eprintln!(
@@ -593,44 +1014,7 @@ pub fn display_resolution_problems<'a, T>(
         }
 
         // When possible, print a useful diff of changes.
-        let do_diff = match p {
-            Problem::LockstepStale { found, generated } => {
-                let diff = TextDiff::from_lines(
-                    found.contents(),
-                    generated.contents(),
-                );
-                let path1 =
-                    env.openapi_abs_dir().join(found.spec_file_name().path());
-                let path2 = env
-                    .openapi_abs_dir()
-                    .join(generated.spec_file_name().path());
-                Some((diff, path1, path2))
-            }
-            Problem::ExtraFileStale {
-                check_stale:
-                    CheckStale::Modified { full_path, actual, expected },
-                ..
-            } => {
-                let diff = TextDiff::from_lines(actual, expected);
-                Some((diff, full_path.clone(), full_path.clone()))
-            }
-            Problem::LocalVersionStale { spec_files, generated }
-                if spec_files.len() == 1 =>
-            {
-                let diff = TextDiff::from_lines(
-                    spec_files[0].contents(),
-                    generated.contents(),
-                );
-                let path1 = env
-                    .openapi_abs_dir()
-                    .join(spec_files[0].spec_file_name().path());
-                let path2 = env
-                    .openapi_abs_dir()
-                    .join(generated.spec_file_name().path());
-                Some((diff, path1, path2))
-            }
-            _ => None,
-        };
+        let do_diff = stale_diff(env, p);
 
         if let Some((diff, path1, path2)) = do_diff {
             let indent = " ".repeat(HEADER_WIDTH + 1);
@@ -703,6 +1087,10 @@ pub(crate) mod plural {
         if count == 1 { "file" } else { "files" }
     }
 
+    pub(crate) fn commits(count: usize) -> &'static str {
+        if count == 1 { "commit" } else { "commits" }
+    }
+
     pub(crate) fn changes(count: usize) -> &'static str {
         if count == 1 { "change" } else { "changes" }
     }
@@ -726,4 +1114,25 @@ pub(crate) mod plural {
     pub(crate) fn schemas(count: usize) -> &'static str {
         if count == 1 { "schema" } else { "schemas" }
     }
+
+    pub(crate) fn versions(count: usize) -> &'static str {
+        if count == 1 { "version" } else { "versions" }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_ascii() {
+        assert!(matches!(to_ascii("plain text"), Cow::Borrowed(_)));
+        assert_eq!(
+            to_ascii(
+                "\u{201c}a\u{201d} \u{2192} \u{2018}b\u{2019} \u{2014} c\u{2026}"
+            ),
+            "\"a\" -> 'b' -- c...",
+        );
+        assert_eq!(to_ascii("caf\u{e9}"), "caf\\u{e9}");
+    }
 }
@@ -0,0 +1,138 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Versions of APIs that downstream consumers are pinned to.
+//!
+//! See [`ConsumerPins`] for more.
+
+use crate::apis::ManagedApis;
+use anyhow::{Context, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use dropshot_api_manager_types::ApiIdent;
+
+/// Checked-in `pins.toml` files in which downstream consumers record the
+/// version of each API they use.
+///
+/// A consumer (e.g. a client crate) records its pins in a file of the form:
+///
+/// ```toml
+/// [pins]
+/// my-api = "3.0.0"
+/// other-api = "1.2.0"
+/// ```
+///
+/// Attach the files to check with [`ManagedApis::with_consumer_pins`]. `check`
+/// then fails if a pinned version is no longer supported, and with
+/// [`ConsumerPins::with_max_lag`], prints a warning for each pin more than N
+/// versions behind the latest version. The `pins` command lists the
+/// consumers pinned to each version of each API.
+///
+/// Each pin must name a versioned API. Consumers are identified by the paths
+/// to their files.
+///
+/// [`ManagedApis::with_consumer_pins`]: crate::ManagedApis::with_consumer_pins
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConsumerPins {
+    paths: Vec<Utf8PathBuf>,
+    max_lag: Option<usize>,
+}
+
+impl ConsumerPins {
+    /// Creates pins read from the files at `paths` (relative to the
+    /// repository root).
+    pub fn new<I>(paths: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Utf8PathBuf>,
+    {
+        Self {
+            paths: paths.into_iter().map(Into::into).collect(),
+            max_lag: None,
+        }
+    }
+
+    /// Prints a warning for each pin more than `max_lag` versions behind the
+    /// latest version of its API.
+    pub fn with_max_lag(mut self, max_lag: usize) -> Self {
+        self.max_lag = Some(max_lag);
+        self
+    }
+
+    /// Returns the paths to the pin files, relative to the repository root.
+    pub fn paths(&self) -> &[Utf8PathBuf] {
+        &self.paths
+    }
+
+    /// Returns the number of versions a pin may lag behind the latest version
+    /// without a warning, if set.
+    pub fn max_lag(&self) -> Option<usize> {
+        self.max_lag
+    }
+
+    /// Loads the pins in every file, in the order of the files.
+    ///
+    /// Returns an error if a file doesn't exist or is invalid, or if a pin
+    /// names an API that isn't a versioned API in `apis`.
+    pub(crate) fn load(
+        &self,
+        repo_root: &Utf8Path,
+        apis: &ManagedApis,
+    ) -> anyhow::Result<Vec<ConsumerPin>> {
+        let mut pins = Vec::new();
+        for path in &self.paths {
+            let contents = fs_err::read_to_string(repo_root.join(path))?;
+            pins.extend(
+                parse_pins(path, &contents, apis)
+                    .with_context(|| format!("invalid pins in {}", path))?,
+            );
+        }
+        Ok(pins)
+    }
+}
+
+/// A consumer's pin of one version of an API.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct ConsumerPin {
+    /// The path to the file the pin is in, relative to the repository root.
+    pub(crate) path: Utf8PathBuf,
+    pub(crate) api: ApiIdent,
+    pub(crate) version: semver::Version,
+}
+
+fn parse_pins(
+    path: &Utf8Path,
+    contents: &str,
+    apis: &ManagedApis,
+) -> anyhow::Result<Vec<ConsumerPin>> {
+    let table: toml::Table = contents.parse()?;
+    for key in table.keys() {
+        if key != "pins" {
+            bail!("unknown key {:?}", key);
+        }
+    }
+    let Some(entries) = table.get("pins") else {
+        return Ok(Vec::new());
+    };
+    let Some(entries) = entries.as_table() else {
+        bail!("expected \"pins\" to be a table");
+    };
+
+    let mut pins = Vec::new();
+    for (api, version) in entries {
+        let Some(version) = version.as_str() else {
+            bail!("pin of {:?}: expected a version string", api);
+        };
+        let version = semver::Version::parse(version).with_context(|| {
+            format!("pin of {:?}: invalid version {:?}", api, version)
+        })?;
+        let api = ApiIdent::from(api.clone());
+        match apis.api(&api) {
+            Some(managed) if managed.is_versioned() => {}
+            Some(_) => bail!("pin of {:?}: API is not versioned", api),
+            // Pins of APIs left out of a partial check are skipped with them.
+            None if apis.is_skipped_api(&api) => continue,
+            None => bail!("pin of {:?}: API is not managed", api),
+        }
+        pins.push(ConsumerPin { path: path.to_owned(), api, version });
+    }
+    Ok(pins)
+}
@@ -0,0 +1,136 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Variant documents containing a subset of an API's operations.
+//!
+//! See [`ApiProfile`] for more.
+
+use anyhow::Context;
+use openapiv3::{OpenAPI, Operation, ReferenceOr};
+use std::{collections::BTreeSet, fmt, sync::Arc};
+
+/// A named subset of an API's operations, managed as a separate document.
+///
+/// Some deployments compile out groups of endpoints. A profile describes such
+/// a deployment: attach it to an API with [`ManagedApi::with_profile`], and
+/// the API manager maintains a variant document named
+/// `<ident>-<profile name>` alongside the full one. The variant is generated
+/// from the full document by removing every operation the profile doesn't
+/// select, along with path items left without operations. Schemas are left in
+/// place.
+///
+/// Variant documents are otherwise managed like any other API: for versioned
+/// APIs, each has its own blessed lineage, and is checked for compatibility
+/// independently of the full document.
+///
+/// [`ManagedApi::with_profile`]: crate::ManagedApi::with_profile
+#[derive(Clone)]
+pub struct ApiProfile {
+    name: String,
+    selector: ProfileSelector,
+}
+
+#[derive(Clone)]
+enum ProfileSelector {
+    Tags(BTreeSet<String>),
+    Filter(Arc<dyn Fn(&Operation) -> bool + Send + Sync>),
+}
+
+impl fmt::Debug for ApiProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("ApiProfile");
+        s.field("name", &self.name);
+        match &self.selector {
+            ProfileSelector::Tags(tags) => s.field("tags", tags),
+            ProfileSelector::Filter(_) => s.field("filter", &"..."),
+        };
+        s.finish()
+    }
+}
+
+impl ApiProfile {
+    /// Creates a profile named `name` that selects operations carrying any of
+    /// `tags`.
+    pub fn with_tags<I, S>(name: impl Into<String>, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            selector: ProfileSelector::Tags(
+                tags.into_iter().map(Into::into).collect(),
+            ),
+        }
+    }
+
+    /// Creates a profile named `name` that selects operations for which
+    /// `filter` returns true.
+    pub fn with_filter<F>(name: impl Into<String>, filter: F) -> Self
+    where
+        F: Fn(&Operation) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            selector: ProfileSelector::Filter(Arc::new(filter)),
+        }
+    }
+
+    /// Returns the name of the profile.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn selects(&self, operation: &Operation) -> bool {
+        match &self.selector {
+            ProfileSelector::Tags(tags) => {
+                operation.tags.iter().any(|tag| tags.contains(tag))
+            }
+            ProfileSelector::Filter(filter) => filter(operation),
+        }
+    }
+
+    /// Produces the variant document for this profile from the full document
+    /// `contents`.
+    pub(crate) fn filter_document(
+        &self,
+        contents: &[u8],
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut openapi: OpenAPI = serde_json::from_slice(contents)
+            .context("generated document is not valid OpenAPI")?;
+        retain_operations(&mut openapi, |op| self.selects(op));
+
+        // Match the formatting of documents written by Dropshot.
+        let mut contents = serde_json::to_vec_pretty(&openapi)
+            .context("serializing profile document")?;
+        contents.push(b'\n');
+        Ok(contents)
+    }
+}
+
+/// Removes every operation in `openapi` for which `keep` returns false, along
+/// with path items left without any operations.
+pub(crate) fn retain_operations(
+    openapi: &mut OpenAPI,
+    keep: impl Fn(&Operation) -> bool,
+) {
+    openapi.paths.paths.retain(|_, item| {
+        let ReferenceOr::Item(item) = item else {
+            return true;
+        };
+        for operation in [
+            &mut item.get,
+            &mut item.put,
+            &mut item.post,
+            &mut item.delete,
+            &mut item.options,
+            &mut item.head,
+            &mut item.patch,
+            &mut item.trace,
+        ] {
+            if operation.as_ref().is_some_and(|op| !keep(op)) {
+                *operation = None;
+            }
+        }
+        item.iter().next().is_some()
+    });
+}
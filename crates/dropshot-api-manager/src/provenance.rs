@@ -0,0 +1,139 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Provenance statements for generated documents.
+//!
+//! `generate --provenance PATH` writes an [in-toto] statement with a [SLSA
+//! provenance] predicate, so that OpenAPI documents can be attested the same
+//! way as other build outputs. The statement records:
+//!
+//! * as subjects, every local document for a managed API, along with its
+//!   SHA-256 digest (Git stubs are recorded under their JSON file name, with
+//!   the digest of the document they refer to);
+//! * the commit the working copy is based on, if any;
+//! * a digest of the executable that generated the documents, which
+//!   fingerprints the crates defining the APIs; and
+//! * the version of the API manager.
+//!
+//! Because the statement records the current commit, it changes with every
+//! commit and is not checked by `check`.
+//!
+//! [in-toto]: https://github.com/in-toto/attestation/blob/main/spec/v1/statement.md
+//! [SLSA provenance]: https://slsa.dev/spec/v1.0/provenance
+
+use crate::{
+    apis::ManagedApis,
+    environment::ResolvedEnv,
+    spec_files_local::{LocalApiSpecFile, LocalFiles},
+};
+use anyhow::Context;
+use camino::Utf8PathBuf;
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+const PREDICATE_TYPE: &str = "https://slsa.dev/provenance/v1";
+const BUILD_TYPE: &str =
+    "https://github.com/oxidecomputer/dropshot-api-manager/generate/v1";
+const BUILDER_ID: &str =
+    "https://github.com/oxidecomputer/dropshot-api-manager";
+
+/// Returns the provenance statement for the documents in `local_files`.
+pub(crate) fn provenance_statement(
+    env: &ResolvedEnv,
+    apis: &ManagedApis,
+    local_files: &LocalFiles,
+) -> anyhow::Result<Vec<u8>> {
+    let subjects: Vec<_> = document_digests(env, apis, local_files)
+        .into_iter()
+        .map(|(path, digest)| {
+            json!({
+                "name": path.as_str(),
+                "digest": { "sha256": digest },
+            })
+        })
+        .collect();
+
+    let mut dependencies = Vec::new();
+    // Outside of a repository (or in one without commits), there's no source
+    // commit to record.
+    if let Ok(commit) = env.vcs.head_commit(&env.repo_root) {
+        dependencies.push(json!({
+            "name": "source",
+            "digest": { "gitCommit": commit.to_string() },
+        }));
+    }
+    let exe = std::env::current_exe()
+        .context("failed to determine the current executable")?;
+    let exe_contents = fs_err::read(&exe)?;
+    let exe_name = exe
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    dependencies.push(resource(&exe_name, &exe_contents));
+
+    let statement = json!({
+        "_type": STATEMENT_TYPE,
+        "subject": subjects,
+        "predicateType": PREDICATE_TYPE,
+        "predicate": {
+            "buildDefinition": {
+                "buildType": BUILD_TYPE,
+                "externalParameters": {
+                    "command": env.command,
+                    "documentsDir": env.openapi_rel_dir().as_str(),
+                },
+                "resolvedDependencies": dependencies,
+            },
+            "runDetails": {
+                "builder": {
+                    "id": BUILDER_ID,
+                    "version": {
+                        "dropshot-api-manager": env!("CARGO_PKG_VERSION"),
+                    },
+                },
+            },
+        },
+    });
+
+    let mut contents = serde_json::to_vec_pretty(&statement)
+        .context("serializing provenance statement")?;
+    contents.push(b'\n');
+    Ok(contents)
+}
+
+/// Returns the path (relative to the repository root) and hex-encoded SHA-256
+/// digest of every valid local document for a managed API.
+///
+/// Git stubs are returned under their JSON file name, with the digest of the
+/// document they refer to.
+pub(crate) fn document_digests(
+    env: &ResolvedEnv,
+    apis: &ManagedApis,
+    local_files: &LocalFiles,
+) -> Vec<(Utf8PathBuf, String)> {
+    let mut digests = Vec::new();
+    for (ident, api_files) in local_files.iter() {
+        if apis.api(ident).is_none() {
+            continue;
+        }
+        for file in api_files.versions().values().flatten() {
+            let LocalApiSpecFile::Valid { spec, .. } = file else {
+                continue;
+            };
+            let path = env
+                .openapi_rel_dir()
+                .join(spec.spec_file_name().to_json_filename().path());
+            digests.push((path, hex::encode(Sha256::digest(spec.contents()))));
+        }
+    }
+    digests
+}
+
+/// Returns an in-toto resource descriptor for `contents`, with its SHA-256
+/// digest.
+fn resource(name: &str, contents: &[u8]) -> Value {
+    json!({
+        "name": name,
+        "digest": { "sha256": hex::encode(Sha256::digest(contents)) },
+    })
+}
@@ -0,0 +1,262 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Finds the `$ref`s in OpenAPI documents, and checks that they resolve.
+//!
+//! Parsing a document with `openapiv3` doesn't check its references, so a
+//! dangling `$ref` would otherwise only be noticed by downstream code
+//! generators. This check runs on every generated document as part of
+//! validation.
+
+use crate::compatibility::{escape_json_pointer, unescape_pointer_component};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Keys of objects whose keys are names chosen by the API, rather than
+/// keywords. Under these, a key like `default` or `example` is just a name.
+const NAME_MAPS: &[&str] = &[
+    "callbacks",
+    "content",
+    "encoding",
+    "examples",
+    "headers",
+    "links",
+    "parameters",
+    "paths",
+    "properties",
+    "requestBodies",
+    "responses",
+    "schemas",
+    "securitySchemes",
+    "variables",
+];
+
+/// Keywords whose values are arbitrary JSON, which may contain a `$ref` key
+/// that isn't a reference.
+const LITERAL_KEYWORDS: &[&str] = &["const", "default", "enum", "example"];
+
+/// Returns a description of each `$ref` in `document` that doesn't resolve.
+///
+/// References within the document (`#/...`) must point to an existing value.
+/// References to other files are resolved relative to `base_dir`, the
+/// directory that contains the document: the file must exist and contain JSON,
+/// and the fragment after `#`, if any, must point to a value within it.
+/// References to URLs aren't checked.
+pub(crate) fn check_refs(document: &Value, base_dir: &Utf8Path) -> Vec<String> {
+    let mut refs = Vec::new();
+    for_each_ref(document, &mut Vec::new(), &mut |path, reference| {
+        refs.push((to_pointer(path), reference));
+    });
+
+    let mut files = BTreeMap::new();
+    let mut errors = Vec::new();
+    for (pointer, reference) in refs {
+        let at = if pointer.is_empty() { "/".to_owned() } else { pointer };
+        if reference.contains("://") {
+            continue;
+        }
+        let (file, fragment) = match reference.split_once('#') {
+            Some((file, fragment)) => (file, fragment),
+            None => (reference, ""),
+        };
+        let target = if file.is_empty() {
+            Ok(document)
+        } else {
+            let path = base_dir.join(file);
+            files
+                .entry(path.clone())
+                .or_insert_with(|| read_json(&path))
+                .as_ref()
+                .map_err(Clone::clone)
+        };
+        let error = match target {
+            Ok(target) if resolve_fragment(target, fragment).is_some() => {
+                continue;
+            }
+            Ok(_) => "target not found".to_owned(),
+            Err(error) => error,
+        };
+        errors.push(format!(
+            "{}: unresolved $ref {:?}: {}",
+            at, reference, error
+        ));
+    }
+    errors
+}
+
+/// Calls `visit` with the location and value of every `$ref` within `value`.
+/// `path` is the location of `value` within its document.
+///
+/// A `$ref` key among names chosen by the API (e.g. a property named `$ref`),
+/// or within an example, a default value or an extension, isn't a reference.
+/// Other keys next to a `$ref` are ignored, as OpenAPI requires, so the walk
+/// doesn't descend into references.
+pub(crate) fn for_each_ref<'a>(
+    value: &'a Value,
+    path: &mut Vec<String>,
+    visit: &mut impl FnMut(&[String], &'a str),
+) {
+    match value {
+        Value::Object(map) => {
+            let is_name_map = path
+                .last()
+                .is_some_and(|parent| NAME_MAPS.contains(&parent.as_str()));
+            if !is_name_map
+                && let Some(reference) = map.get("$ref").and_then(Value::as_str)
+            {
+                visit(path, reference);
+                return;
+            }
+            for (key, child) in map {
+                if !is_name_map
+                    && (LITERAL_KEYWORDS.contains(&key.as_str())
+                        || key.starts_with("x-")
+                        || (key == "value"
+                            && path
+                                .iter()
+                                .rev()
+                                .nth(1)
+                                .is_some_and(|p| p == "examples")))
+                {
+                    continue;
+                }
+                path.push(key.clone());
+                for_each_ref(child, path, visit);
+                path.pop();
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter().enumerate() {
+                path.push(index.to_string());
+                for_each_ref(child, path, visit);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Formats `path` as a JSON pointer.
+pub(crate) fn to_pointer(path: &[String]) -> String {
+    path.iter()
+        .map(|component| format!("/{}", escape_json_pointer(component)))
+        .collect()
+}
+
+/// Splits the JSON pointer `pointer` into the keys it's made up of.
+pub(crate) fn from_pointer(pointer: &str) -> Vec<String> {
+    pointer.split('/').skip(1).map(unescape_pointer_component).collect()
+}
+
+/// Resolves the JSON pointer `fragment` within `document`. An empty fragment
+/// refers to the whole document.
+fn resolve_fragment<'a>(
+    document: &'a Value,
+    fragment: &str,
+) -> Option<&'a Value> {
+    if fragment.is_empty() {
+        Some(document)
+    } else {
+        document.pointer(fragment)
+    }
+}
+
+fn read_json(path: &Utf8PathBuf) -> Result<Value, String> {
+    let contents = fs_err::read(path).map_err(|error| error.to_string())?;
+    serde_json::from_slice(&contents)
+        .map_err(|error| format!("{} is not valid JSON: {}", path, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::overwrite_file;
+    use serde_json::json;
+
+    #[test]
+    fn test_check_refs() {
+        let doc = json!({
+            "paths": {
+                "/things": {
+                    "get": {
+                        "responses": {
+                            "200": {
+                                "content": {
+                                    "application/json": {
+                                        "schema": {
+                                            "$ref": "#/components/schemas/Thing",
+                                        },
+                                        "example": { "$ref": "not a ref" },
+                                    },
+                                },
+                            },
+                            "default": {
+                                "$ref": "#/components/responses/Error",
+                            },
+                        },
+                    },
+                },
+            },
+            "components": {
+                "schemas": {
+                    "Thing": {
+                        "type": "object",
+                        "properties": {
+                            "$ref": { "type": "string" },
+                            "other": {
+                                "$ref": "#/components/schemas/Other",
+                            },
+                            "items": {
+                                "type": "array",
+                                "items": [
+                                    { "$ref": "#/components/schemas/Thing" },
+                                    { "$ref": "missing.json" },
+                                ],
+                            },
+                        },
+                    },
+                },
+            },
+        });
+
+        let errors = check_refs(&doc, Utf8Path::new("/nonexistent"));
+        assert_eq!(errors.len(), 3, "errors: {:#?}", errors);
+        let has_error =
+            |prefix: &str| errors.iter().any(|e| e.starts_with(prefix));
+        assert!(has_error(
+            "/paths/~1things/get/responses/default: unresolved $ref \
+             \"#/components/responses/Error\": target not found"
+        ));
+        assert!(has_error(
+            "/components/schemas/Thing/properties/other: unresolved $ref"
+        ));
+        assert!(has_error(
+            "/components/schemas/Thing/properties/items/items/1: unresolved \
+             $ref \"missing.json\""
+        ));
+    }
+
+    #[test]
+    fn test_external_refs() {
+        let dir = camino_tempfile::tempdir().unwrap();
+        let _ = overwrite_file(
+            &dir.path().join("thing.json"),
+            br#"{ "Thing": { "type": "string" } }"#,
+        )
+        .unwrap();
+        let doc = json!({
+            "components": {
+                "schemas": {
+                    "A": { "$ref": "thing.json" },
+                    "B": { "$ref": "thing.json#/Thing" },
+                    "C": { "$ref": "thing.json#/Other" },
+                    "D": { "$ref": "https://example.com/schema.json" },
+                },
+            },
+        });
+
+        let errors = check_refs(&doc, dir.path());
+        assert_eq!(errors.len(), 1, "errors: {:#?}", errors);
+        assert!(errors[0].starts_with("/components/schemas/C:"));
+    }
+}
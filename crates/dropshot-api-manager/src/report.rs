@@ -0,0 +1,401 @@
+// Copyright 2026 Oxide Computer Company
+
+//! HTML reports of `check` runs.
+//!
+//! `check --report PATH` writes a self-contained HTML page describing the
+//! results of the check, for attaching to CI runs. The page has a collapsible
+//! section per API (expanded if the API has problems), listing the status of
+//! each document along with its problems. Stale documents are shown with a
+//! diff of the changes `generate` would make, and compatibility issues are
+//! shown as a table of issues alongside a diff of the affected schema.
+//!
+//! The page has no external resources or scripts, so it can be viewed offline
+//! or as a CI artifact.
+
+use crate::{
+    apis::ManagedApis,
+    environment::ResolvedEnv,
+    output::{CheckResult, InlineErrorChain, Styles, stale_diff, write_diff},
+    resolved::{Problem, Resolved},
+};
+use camino::Utf8Path;
+use similar::{DiffableStr, TextDiff};
+use std::fmt::{self, Write};
+
+/// The stylesheet embedded in every report.
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.5em; }
+summary { cursor: pointer; font-weight: bold; padding: 0.25em 0; }
+details { border: 1px solid #ccc; border-radius: 4px; margin: 0.5em 0;
+  padding: 0.25em 0.75em; }
+.item { margin: 0.5em 0 0.5em 1em; }
+.status { display: inline-block; min-width: 4.5em; padding: 0 0.4em;
+  border-radius: 3px; color: #fff; font-size: 0.85em; text-align: center; }
+.fresh { background: #2e7d32; }
+.stale { background: #ed8c00; }
+.failed { background: #c62828; }
+.problem { margin: 0.5em 0 0.5em 1em; padding-left: 0.75em;
+  border-left: 3px solid #ed8c00; }
+.problem.error { border-left-color: #c62828; }
+.fix { font-style: italic; }
+pre { background: #f6f8fa; padding: 0.5em; overflow-x: auto;
+  font-size: 0.85em; }
+pre .add { color: #22863a; background: #e6ffed; }
+pre .del { color: #b31d28; background: #ffeef0; }
+pre .hunk { color: #6f42c1; }
+pre .header { font-weight: bold; }
+table { border-collapse: collapse; margin: 0.5em 0; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.5em; text-align: left;
+  vertical-align: top; }
+";
+
+/// The status of a document, or of another item checked for an API.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Status {
+    Fresh,
+    Stale,
+    Failed,
+}
+
+impl Status {
+    fn of<'a>(problems: impl IntoIterator<Item = &'a Problem<'a>>) -> Self {
+        let mut status = Status::Fresh;
+        for p in problems {
+            if !p.is_fixable() {
+                return Status::Failed;
+            }
+            status = Status::Stale;
+        }
+        status
+    }
+
+    fn class(self) -> &'static str {
+        match self {
+            Status::Fresh => "fresh",
+            Status::Stale => "stale",
+            Status::Failed => "failed",
+        }
+    }
+}
+
+/// One item checked for an API, such as a document or the "latest" symlink.
+struct Item<'r> {
+    label: String,
+    problems: Vec<&'r Problem<'r>>,
+}
+
+/// Renders an HTML report of a `check` run with the given `result`.
+pub(crate) fn render_report(
+    env: &ResolvedEnv,
+    apis: &ManagedApis,
+    resolved: &Resolved<'_>,
+    result: CheckResult,
+) -> String {
+    let mut out = String::new();
+    write_report(&mut out, env, apis, resolved, result)
+        .expect("writing to a String is infallible");
+    out
+}
+
+fn write_report(
+    out: &mut String,
+    env: &ResolvedEnv,
+    apis: &ManagedApis,
+    resolved: &Resolved<'_>,
+    result: CheckResult,
+) -> fmt::Result {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>OpenAPI document check</title>")?;
+    writeln!(out, "<style>\n{STYLE}</style>\n</head>\n<body>")?;
+    writeln!(out, "<h1>OpenAPI document check</h1>")?;
+
+    let (status, summary) = match result {
+        CheckResult::Success => {
+            (Status::Fresh, "All documents are up-to-date.".to_owned())
+        }
+        CheckResult::NeedsUpdate => (
+            Status::Stale,
+            format!(
+                "Some documents are stale: run `{} generate`.",
+                env.command
+            ),
+        ),
+        CheckResult::Failures => (
+            Status::Failed,
+            format!(
+                "Some checks failed: fix the failures, then run `{} \
+                 generate`.",
+                env.command
+            ),
+        ),
+    };
+    writeln!(
+        out,
+        "<p><span class=\"status {}\">{}</span> {}</p>",
+        status.class(),
+        status.class(),
+        escape(&summary),
+    )?;
+
+    for api in apis.iter_apis() {
+        let ident = api.ident();
+        let mut items = Vec::new();
+        for version in api.iter_versions_semver() {
+            let resolution = resolved
+                .resolution_for_api_version(ident, version)
+                .expect("resolution for all supported API versions");
+            items.push(Item {
+                label: format!("v{}", version),
+                problems: resolution.problems().collect(),
+            });
+        }
+        for alias in api.aliases() {
+            items.push(Item {
+                label: format!("alias {}", alias),
+                problems: resolved
+                    .alias_problems(ident)
+                    .filter(|p| p.alias() == Some(alias))
+                    .collect(),
+            });
+        }
+        let json_format_problem = resolved.json_format_problem(ident);
+        if api.json_format().is_some() || json_format_problem.is_some() {
+            items.push(Item {
+                label: "JSON format".to_owned(),
+                problems: json_format_problem.into_iter().collect(),
+            });
+        }
+        if api.is_versioned() {
+            items.push(Item {
+                label: "\"latest\" symlink".to_owned(),
+                problems: resolved.symlink_problem(ident).into_iter().collect(),
+            });
+            if api.has_combined_document() {
+                items.push(Item {
+                    label: "combined document".to_owned(),
+                    problems: resolved
+                        .combined_document_problem(ident)
+                        .into_iter()
+                        .collect(),
+                });
+            }
+            let shared_schemas_problem = resolved.shared_schemas_problem(ident);
+            if api.has_shared_schemas() || shared_schemas_problem.is_some() {
+                items.push(Item {
+                    label: "shared schemas".to_owned(),
+                    problems: shared_schemas_problem.into_iter().collect(),
+                });
+            }
+        }
+
+        let status = Status::of(
+            items.iter().flat_map(|item| item.problems.iter().copied()),
+        );
+        writeln!(
+            out,
+            "<details{}>\n<summary><span class=\"status {}\">{}</span> \
+             {} <small>({})</small></summary>",
+            if status == Status::Fresh { "" } else { " open" },
+            status.class(),
+            status.class(),
+            escape(ident.as_str()),
+            escape(api.title()),
+        )?;
+        for item in &items {
+            let status = Status::of(item.problems.iter().copied());
+            writeln!(
+                out,
+                "<div class=\"item\"><span class=\"status {}\">{}</span> {}",
+                status.class(),
+                status.class(),
+                escape(&item.label),
+            )?;
+            for p in &item.problems {
+                write_problem(out, env, p)?;
+            }
+            writeln!(out, "</div>")?;
+        }
+        writeln!(out, "</details>")?;
+    }
+
+    let general_problems: Vec<_> = resolved.general_problems().collect();
+    if !general_problems.is_empty() {
+        let status = Status::of(general_problems.iter().copied());
+        writeln!(
+            out,
+            "<details open>\n<summary><span class=\"status {}\">{}</span> \
+             Problems not associated with a specific supported API \
+             version</summary>",
+            status.class(),
+            status.class(),
+        )?;
+        for p in general_problems {
+            write_problem(out, env, p)?;
+        }
+        writeln!(out, "</details>")?;
+    }
+
+    let notes: Vec<_> = resolved.notes().collect();
+    if !notes.is_empty() {
+        writeln!(out, "<h2>Notes</h2>\n<ul>")?;
+        for note in notes {
+            writeln!(out, "<li>{}</li>", escape(&note.to_string()))?;
+        }
+        writeln!(out, "</ul>")?;
+    }
+
+    writeln!(out, "</body>\n</html>")
+}
+
+/// Writes a problem, along with its fix and any diffs that explain it.
+fn write_problem(
+    out: &mut String,
+    env: &ResolvedEnv,
+    p: &Problem<'_>,
+) -> fmt::Result {
+    let class = if p.is_fixable() { "problem" } else { "problem error" };
+    writeln!(out, "<div class=\"{}\">", class)?;
+    writeln!(out, "<p>{}</p>", escape(&InlineErrorChain::new(p).to_string()))?;
+
+    // As with console output, the reverse check compares the documents the
+    // other way around, so its issues have the sides swapped.
+    let broken = match p {
+        Problem::BlessedVersionBroken { compatibility_issues } => {
+            Some((compatibility_issues, "blessed", "generated"))
+        }
+        Problem::BlessedVersionReverseBroken { compatibility_issues } => {
+            Some((compatibility_issues, "generated", "blessed"))
+        }
+        _ => None,
+    };
+    if let Some((compatibility_issues, old_label, new_label)) = broken {
+        writeln!(out, "<table>\n<tr><th>Issue</th><th>Diff</th></tr>")?;
+        for issue in compatibility_issues {
+            let blessed_json = issue.blessed_json();
+            let generated_json = issue.generated_json();
+            let diff = TextDiff::from_lines(&blessed_json, &generated_json);
+            write!(out, "<tr><td>{}</td><td>", escape(&issue.to_string()))?;
+            // Use a large context radius so that most of the schema is shown.
+            write_html_diff(
+                out,
+                &diff,
+                old_label.as_ref(),
+                new_label.as_ref(),
+                8,
+            )?;
+            writeln!(out, "</td></tr>")?;
+        }
+        writeln!(out, "</table>")?;
+    }
+
+    if let Problem::BlessedLatestVersionBytewiseMismatch {
+        blessed,
+        generated,
+    } = p
+    {
+        let diff =
+            TextDiff::from_lines(blessed.contents(), generated.contents());
+        write_html_diff(
+            out,
+            &diff,
+            &blessed.spec_file_name().path(),
+            &generated.spec_file_name().path(),
+            3,
+        )?;
+    }
+
+    if let Some(fix) = p.fix() {
+        for step in fix.to_string().trim_end().split('\n') {
+            writeln!(out, "<p class=\"fix\">Fix: will {}</p>", escape(step))?;
+        }
+    }
+
+    if let Some((diff, path1, path2)) = stale_diff(env, p) {
+        let path1 = path1.strip_prefix(&env.repo_root).unwrap_or(&path1);
+        let path2 = path2.strip_prefix(&env.repo_root).unwrap_or(&path2);
+        write_html_diff(out, &diff, path1, path2, 3)?;
+    }
+
+    writeln!(out, "</div>")
+}
+
+/// Writes a unified diff as a `<pre>` block, with each line classed by kind.
+fn write_html_diff<T>(
+    out: &mut String,
+    diff: &TextDiff<'_, '_, '_, T>,
+    path1: &Utf8Path,
+    path2: &Utf8Path,
+    context_radius: usize,
+) -> fmt::Result
+where
+    T: DiffableStr + ?Sized,
+{
+    let mut text = Vec::new();
+    write_diff(
+        diff,
+        path1,
+        path2,
+        &Styles::default(),
+        context_radius,
+        /* missing_newline_hint */ true,
+        &mut text,
+    )
+    .expect("writing to a Vec is infallible");
+
+    writeln!(out, "<pre>")?;
+    for (i, line) in String::from_utf8_lossy(&text).lines().enumerate() {
+        let class = if i < 2 {
+            Some("header")
+        } else if line.starts_with("@@") {
+            Some("hunk")
+        } else if line.starts_with('+') {
+            Some("add")
+        } else if line.starts_with('-') {
+            Some("del")
+        } else {
+            None
+        };
+        match class {
+            Some(class) => writeln!(
+                out,
+                "<span class=\"{}\">{}</span>",
+                class,
+                escape(line)
+            )?,
+            None => writeln!(out, "{}", escape(line))?,
+        }
+    }
+    writeln!(out, "</pre>")
+}
+
+/// Escapes text for inclusion in HTML.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+        assert_eq!(escape("plain"), "plain");
+    }
+}
@@ -3,29 +3,42 @@
 //! Resolve different sources of API information (blessed, local, upstream)
 
 use crate::{
-    apis::{ManagedApi, ManagedApis},
+    aliases::{diff_alias, sync_alias},
+    apis::{LatestPointer, ManagedApi, ManagedApis},
+    approvals::{BreakingChangeApproval, BreakingChangeApprovals},
+    combined::combined_document,
     compatibility::{ApiCompatIssue, api_compatible},
     environment::ResolvedEnv,
+    freeze::DocumentFreeze,
     iter_only::iter_only,
+    json_format::{
+        MisformattedDocument, diff_json_format, same_tokens, sync_json_format,
+    },
     output::{InlineErrorChain, plural},
+    pins::ConsumerPin,
+    shared_schemas::{diff_shared_schemas, sync_shared_schemas},
+    signing::{DocumentSigning, SignatureStatus},
     spec_files_blessed::{BlessedApiSpecFile, BlessedFiles, BlessedGitStub},
     spec_files_generated::{GeneratedApiSpecFile, GeneratedFiles},
     spec_files_generic::{ApiFiles, UnparseableFile},
     spec_files_local::{LocalApiSpecFile, LocalFiles},
+    unstable::{exempt_path_operations, without_unstable_operations},
     validation::{
-        CheckStale, CheckStatus, DynValidationFn, overwrite_file, validate,
+        CheckStale, CheckStatus, DynValidationFn, check_file, overwrite_file,
+        read_opt, validate,
     },
 };
 use anyhow::{Context, anyhow};
 use camino::{Utf8Path, Utf8PathBuf};
 use dropshot_api_manager_types::{
-    ApiIdent, ApiSpecFileName, VersionedApiSpecFileName,
+    ApiIdent, ApiSpecFileName, CustomFix, VersionedApiSpecFileName,
 };
 use git_stub::{GitCommitHash, GitStub};
 use rayon::prelude::*;
 use std::{
     collections::{BTreeMap, BTreeSet, HashSet},
     fmt::{Debug, Display},
+    ops::ControlFlow,
 };
 use thiserror::Error;
 
@@ -67,6 +80,63 @@ pub enum Note {
          possible mismerge."
     )]
     BlessedVersionRemoved { api_ident: ApiIdent, version: semver::Version },
+
+    /// Operations under an API's unstable path prefixes were excluded from
+    /// comparisons with blessed documents.
+    ///
+    /// Every operation under such a prefix is exempt, so these are listed
+    /// to make the exemption visible.
+    #[error(
+        "API {api_ident}: operations under unstable path prefixes \
+         ({prefixes}) are excluded from comparisons with blessed documents: \
+         {operations}"
+    )]
+    UnstablePathOperationsExcluded {
+        api_ident: ApiIdent,
+        prefixes: DisplayableVec<String>,
+        operations: DisplayableVec<String>,
+    },
+
+    /// The document for a new latest version is much larger than the latest
+    /// blessed document.
+    ///
+    /// This isn't an error because APIs do grow, but a large jump within a
+    /// single change usually means a schema exploded by accident.
+    #[error(
+        "API {api_ident} version {version}: generated document is \
+         {generated_size} bytes, {growth_percent}% larger than the \
+         {blessed_size} bytes of latest blessed version {blessed_version} \
+         (threshold: {threshold_percent}%).  This often means that a schema \
+         grew by accident, e.g. a recursive type being inlined repeatedly."
+    )]
+    DocumentSizeGrowth {
+        api_ident: ApiIdent,
+        version: semver::Version,
+        blessed_version: semver::Version,
+        generated_size: usize,
+        blessed_size: usize,
+        growth_percent: u64,
+        threshold_percent: u32,
+    },
+
+    /// A consumer is pinned to a version of an API that's further behind the
+    /// latest version than allowed.
+    ///
+    /// This isn't an error because the pinned version is still supported, but
+    /// it's a sign that the consumer should be updated before the version is
+    /// retired.
+    #[error(
+        "API {api_ident} version {version}: pinned by {path}, {behind} \
+         versions behind latest version {latest} (maximum: {max_lag})"
+    )]
+    ConsumerPinLagging {
+        api_ident: ApiIdent,
+        version: semver::Version,
+        path: Utf8PathBuf,
+        latest: semver::Version,
+        behind: usize,
+        max_lag: usize,
+    },
 }
 
 /// Describes the result of resolving the blessed spec(s), generated spec(s),
@@ -98,6 +168,14 @@ impl<'a> Resolution<'a> {
         self.problems.push(problem);
     }
 
+    /// Sort problems by kind, so that they're reported in a stable order.
+    ///
+    /// The sort is stable, so problems of the same kind stay in the order they
+    /// were found.
+    fn sort_problems(&mut self) {
+        self.problems.sort_by_key(|problem| problem.kind());
+    }
+
     pub fn has_errors(&self) -> bool {
         self.problems().any(|p| !p.is_fixable())
     }
@@ -137,32 +215,62 @@ impl Display for ResolutionKind {
 /// Each variant corresponds 1:1 to a `Problem` variant. The exhaustive
 /// match in `Problem::kind` ensures that adding a new `Problem` variant
 /// without updating this enum causes a compile error.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+///
+/// Problems are reported in the order in which variants are declared here
+/// (after sorting by API and version).
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 #[expect(missing_docs)]
 pub enum ProblemKind {
     LocalSpecFileOrphaned,
     UnparseableLocalFile,
+    UnknownApiFiles,
+    UnknownApiFilesBlessed,
+    RemovedApiFiles,
     BlessedVersionMissingLocal,
     BlessedVersionExtraLocalSpec,
     BlessedVersionCompareError,
     BlessedVersionBroken,
+    BlessedVersionReverseBroken,
     BlessedLatestVersionBytewiseMismatch,
+    BlessedLatestVersionReformatted,
+    BreakingChangeApprovalStale,
+    ConsumerPinUnsupported,
     LockstepMissingLocal,
     LockstepStale,
     LocalVersionMissingLocal,
     LocalVersionExtra,
     LocalVersionStale,
     GeneratedSourceMissing,
+    GenerationPanicked,
     GeneratedValidationError,
+    OperationIdChanged,
+    LatestDocumentFrozen,
     ExtraFileStale,
+    ValidationFixable,
     LatestLinkMissing,
     LatestLinkStale,
+    LatestLinkDangling,
+    LatestLinkUnwanted,
+    LatestLinkTempLeftover,
+    LatestPointerFileStale,
     BlessedVersionShouldBeGitStub,
     GitStubShouldBeJson,
     BlessedVersionCorruptedLocal,
     DuplicateLocalFile,
+    FileNameHashCollision,
     GitStubCommitStale,
     GitStubFirstCommitUnknown,
+    DocumentSignatureMissing,
+    DocumentSignatureInvalid,
+    BlessedSignatureInvalid,
+    AliasStale,
+    AliasError,
+    CombinedDocumentStale,
+    CombinedDocumentError,
+    SharedSchemasStale,
+    SharedSchemasError,
+    JsonFormatStale,
+    JsonFormatError,
 }
 
 /// Owned summary of a `Problem` for test assertions.
@@ -171,7 +279,9 @@ pub enum ProblemKind {
 /// belongs to, which version (if any), and its [`ProblemKind`]. Because all
 /// fields are owned and implement `PartialEq`, summaries can be compared
 /// with `assert_eq!`.
-#[derive(Clone, Debug, Eq, PartialEq)]
+///
+/// Summaries are ordered by API, then version, then kind.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub struct ProblemSummary {
     /// The API this problem is associated with.
     pub api_ident: ApiIdent,
@@ -227,6 +337,30 @@ pub enum Problem<'a> {
     )]
     UnparseableLocalFile { unparseable_file: UnparseableFile },
 
+    #[error(
+        "Found files for API {api_ident}, which is not managed by this tool: \
+         {paths}. This is expected after an API is removed. This tool can \
+         delete them for you."
+    )]
+    UnknownApiFiles { api_ident: ApiIdent, paths: DisplayableVec<Utf8PathBuf> },
+
+    #[error(
+        "Found files for API {api_ident}, which is not managed by this tool: \
+         {paths}. The API also has blessed documents, so these files won't \
+         be deleted automatically. If the API is being removed, delete them \
+         by hand."
+    )]
+    UnknownApiFilesBlessed {
+        api_ident: ApiIdent,
+        paths: DisplayableVec<Utf8PathBuf>,
+    },
+
+    #[error(
+        "API {api_ident} was removed, but its files still exist: {paths}. \
+         This tool can delete them for you."
+    )]
+    RemovedApiFiles { api_ident: ApiIdent, paths: DisplayableVec<Utf8PathBuf> },
+
     // All other problems are associated with specific supported versions of an
     // API.
     #[error(
@@ -265,6 +399,14 @@ pub enum Problem<'a> {
     )]
     BlessedVersionBroken { compatibility_issues: Vec<ApiCompatIssue> },
 
+    #[error(
+        "The blessed document (from upstream) is not compatible with the \
+         OpenAPI document generated from the current code, so clients built \
+         against the current code can't talk to servers running the blessed \
+         version. This API requires compatibility in both directions."
+    )]
+    BlessedVersionReverseBroken { compatibility_issues: Vec<ApiCompatIssue> },
+
     #[error(
         "For the latest blessed version, the OpenAPI document generated from \
          the current code is wire-compatible but not bytewise \
@@ -279,6 +421,41 @@ pub enum Problem<'a> {
         generated: &'a GeneratedApiSpecFile,
     },
 
+    #[error(
+        "For the latest blessed version, the OpenAPI document generated from \
+         the current code differs from the blessed document only in \
+         whitespace, e.g. because of a change to the tools that format it. \
+         This doesn't require a new API version, but the local document \
+         needs to be reformatted."
+    )]
+    // As with `LocalVersionStale`, the hash in the file name changes along
+    // with the contents, so the fix replaces the local files with the
+    // generated one.
+    BlessedLatestVersionReformatted {
+        spec_files: Vec<&'a LocalApiSpecFile>,
+        generated: &'a GeneratedApiSpecFile,
+    },
+
+    #[error(
+        "{approval} in {} doesn't match an incompatible change to a blessed \
+         version, e.g. because the change was reverted or the version is no \
+         longer supported. This tool can remove the stale approval for you.",
+        approvals.path()
+    )]
+    BreakingChangeApprovalStale {
+        approvals: &'a BreakingChangeApprovals,
+        approval: &'a BreakingChangeApproval,
+    },
+
+    #[error(
+        "{} pins version {} of this API, which is no longer supported.  The \
+         consumer must move to a supported version (and update its pin) \
+         before this version can be removed.",
+        pin.path,
+        pin.version
+    )]
+    ConsumerPinUnsupported { pin: &'a ConsumerPin },
+
     #[error(
         "No local OpenAPI document was found for this lockstep API.  This is \
          only expected if you're adding a new lockstep API.  This tool can \
@@ -336,6 +513,12 @@ pub enum Problem<'a> {
     )]
     GeneratedSourceMissing { api_ident: ApiIdent },
 
+    #[error(
+        "Generating the OpenAPI document for API {api_ident:?} panicked: \
+         {message}"
+    )]
+    GenerationPanicked { api_ident: ApiIdent, message: String },
+
     #[error(
         "Generated OpenAPI document for API {api_ident:?} version {version} \
          is not valid"
@@ -347,6 +530,35 @@ pub enum Problem<'a> {
         source: anyhow::Error,
     },
 
+    #[error(
+        "Operation {method} {path} of API {api_ident:?} has operation ID \
+         {old_id:?} in version {old_version}, but {new_id:?} in version \
+         {new_version}. Renaming an operation breaks generated clients, even \
+         if the change is wire-compatible. Keep the operation ID (e.g. with \
+         `operation_id = {old_id:?}` on the endpoint), or if the rename is \
+         intended, mark it with `ManagedApi::with_renamed_operation_id`."
+    )]
+    OperationIdChanged {
+        api_ident: ApiIdent,
+        method: String,
+        path: String,
+        old_version: semver::Version,
+        old_id: String,
+        new_version: semver::Version,
+        new_id: String,
+    },
+
+    #[error(
+        "The latest OpenAPI document for API {api_ident:?} is frozen by \
+         {freeze}, but {change}. Ask the owner of the freeze about the \
+         change, and if it's approved, pass --override-freeze to make it."
+    )]
+    LatestDocumentFrozen {
+        api_ident: ApiIdent,
+        freeze: DocumentFreeze,
+        change: String,
+    },
+
     #[error(
         "Additional validated file associated with API {api_ident:?} is \
          stale: {path}"
@@ -357,6 +569,12 @@ pub enum Problem<'a> {
         check_stale: CheckStale,
     },
 
+    #[error(
+        "Validation of API {api_ident:?} found a fixable problem: {}",
+        fix.description(),
+    )]
+    ValidationFixable { api_ident: ApiIdent, fix: CustomFix },
+
     #[error("\"Latest\" symlink for versioned API {api_ident:?} is missing")]
     LatestLinkMissing {
         api_ident: ApiIdent,
@@ -375,6 +593,48 @@ pub enum Problem<'a> {
         link: &'a VersionedApiSpecFileName,
     },
 
+    #[error(
+        "\"Latest\" symlink for versioned API {api_ident:?} points to {}, \
+         which is missing or could not be parsed. This tool can update the \
+         symlink to point to {}, regenerating it if necessary.",
+        found.json_basename(),
+        link.json_basename(),
+    )]
+    LatestLinkDangling {
+        api_ident: ApiIdent,
+        found: &'a VersionedApiSpecFileName,
+        link: &'a VersionedApiSpecFileName,
+    },
+
+    #[error(
+        "Versioned API {api_ident:?} is configured without a \"latest\" \
+         symlink, but one exists (pointing to {}). This tool can remove it \
+         for you.",
+        found.basename(),
+    )]
+    LatestLinkUnwanted {
+        api_ident: ApiIdent,
+        found: &'a VersionedApiSpecFileName,
+    },
+
+    #[error(
+        "Versioned API {api_ident:?} has a temporary \"latest\" symlink ({}) \
+         left behind by an interrupted update. This tool can remove it for \
+         you.",
+        api_ident.versioned_api_latest_symlink_temp(),
+    )]
+    LatestLinkTempLeftover { api_ident: ApiIdent },
+
+    #[error(
+        "\"Latest\" pointer file for versioned API {api_ident:?} is missing \
+         or stale: it should point to {}",
+        link.json_basename(),
+    )]
+    LatestPointerFileStale {
+        api_ident: ApiIdent,
+        link: &'a VersionedApiSpecFileName,
+    },
+
     #[error(
         "Blessed non-latest version is stored as a full JSON file. This can \
          be converted to a Git stub. This tool can perform the conversion for \
@@ -412,6 +672,18 @@ pub enum Problem<'a> {
     )]
     DuplicateLocalFile { local_file: &'a LocalApiSpecFile },
 
+    #[error(
+        "Two OpenAPI documents with different contents map to the same file \
+         name {spec_file_name}: {first} and {second}. The hash in file names \
+         is too short to tell these documents apart. Configure a longer hash \
+         with `Environment::with_file_name_hash`, then regenerate documents."
+    )]
+    FileNameHashCollision {
+        spec_file_name: VersionedApiSpecFileName,
+        first: String,
+        second: String,
+    },
+
     #[error(
         "Git stub has an outdated commit reference that is no longer \
          an ancestor of the merge base. This can happen after a rebase or \
@@ -431,6 +703,106 @@ pub enum Problem<'a> {
         #[source]
         source: anyhow::Error,
     },
+
+    #[error(
+        "No signature found for {} (expected at {path}). This tool can sign \
+         the document for you, if a private key is configured.",
+        file_name.json_basename(),
+    )]
+    DocumentSignatureMissing {
+        signing: &'a DocumentSigning,
+        api_ident: ApiIdent,
+        file_name: &'a VersionedApiSpecFileName,
+        contents: &'a [u8],
+        path: Utf8PathBuf,
+    },
+
+    #[error(
+        "Signature {path} for {} is invalid: {message}. This tool can sign \
+         the document again for you, if a private key is configured.",
+        file_name.json_basename(),
+    )]
+    DocumentSignatureInvalid {
+        signing: &'a DocumentSigning,
+        api_ident: ApiIdent,
+        file_name: &'a VersionedApiSpecFileName,
+        contents: &'a [u8],
+        path: Utf8PathBuf,
+        message: String,
+    },
+
+    #[error(
+        "Signature {path} for blessed document {} is invalid: {message}. \
+         Either the document or its signature was modified after it was \
+         blessed. This needs to be investigated and fixed by hand.",
+        file_name.json_basename(),
+    )]
+    BlessedSignatureInvalid {
+        file_name: &'a VersionedApiSpecFileName,
+        path: Utf8PathBuf,
+        message: String,
+    },
+
+    #[error(
+        "The copy of this API's documents under alias {alias} is out of date: \
+         {paths}. This tool can update it for you."
+    )]
+    AliasStale {
+        api: &'a ManagedApi,
+        alias: &'a ApiIdent,
+        paths: DisplayableVec<Utf8PathBuf>,
+    },
+
+    #[error(
+        "error comparing the copy of this API's documents under alias \
+         {alias} with the documents themselves: {}",
+        InlineErrorChain::new(error.as_ref())
+    )]
+    AliasError { alias: &'a ApiIdent, error: anyhow::Error },
+
+    #[error(
+        "The document combining all supported versions of this API is \
+         missing or stale. This tool can update it for you."
+    )]
+    CombinedDocumentStale { api_ident: ApiIdent, contents: Vec<u8> },
+
+    #[error(
+        "error building the document combining all supported versions of \
+         this API: {}",
+        InlineErrorChain::new(error.as_ref())
+    )]
+    CombinedDocumentError { error: anyhow::Error },
+
+    #[error(
+        "This API's documents aren't stored as configured for shared schemas: \
+         {paths}. This tool can update them for you."
+    )]
+    SharedSchemasStale {
+        api: &'a ManagedApi,
+        paths: DisplayableVec<Utf8PathBuf>,
+    },
+
+    #[error(
+        "error comparing this API's documents with its shared schemas: {}",
+        InlineErrorChain::new(error.as_ref())
+    )]
+    SharedSchemasError { error: anyhow::Error },
+
+    #[error(
+        "These documents aren't formatted as configured for this API ({}): \
+         {documents}. This tool can reformat them for you.",
+        api.json_format().copied().unwrap_or_default()
+    )]
+    JsonFormatStale {
+        api: &'a ManagedApi,
+        documents: DisplayableVec<MisformattedDocument>,
+    },
+
+    #[error(
+        "error checking the format of this API's documents: {}",
+        InlineErrorChain::new(error.as_ref())
+    )]
+    JsonFormatError { error: anyhow::Error },
 }
 
 impl<'a> Problem<'a> {
@@ -446,6 +818,11 @@ impl<'a> Problem<'a> {
             Problem::UnparseableLocalFile { .. } => {
                 ProblemKind::UnparseableLocalFile
             }
+            Problem::UnknownApiFiles { .. } => ProblemKind::UnknownApiFiles,
+            Problem::UnknownApiFilesBlessed { .. } => {
+                ProblemKind::UnknownApiFilesBlessed
+            }
+            Problem::RemovedApiFiles { .. } => ProblemKind::RemovedApiFiles,
             Problem::BlessedVersionMissingLocal { .. } => {
                 ProblemKind::BlessedVersionMissingLocal
             }
@@ -458,9 +835,21 @@ impl<'a> Problem<'a> {
             Problem::BlessedVersionBroken { .. } => {
                 ProblemKind::BlessedVersionBroken
             }
+            Problem::BlessedVersionReverseBroken { .. } => {
+                ProblemKind::BlessedVersionReverseBroken
+            }
             Problem::BlessedLatestVersionBytewiseMismatch { .. } => {
                 ProblemKind::BlessedLatestVersionBytewiseMismatch
             }
+            Problem::BlessedLatestVersionReformatted { .. } => {
+                ProblemKind::BlessedLatestVersionReformatted
+            }
+            Problem::BreakingChangeApprovalStale { .. } => {
+                ProblemKind::BreakingChangeApprovalStale
+            }
+            Problem::ConsumerPinUnsupported { .. } => {
+                ProblemKind::ConsumerPinUnsupported
+            }
             Problem::LockstepMissingLocal { .. } => {
                 ProblemKind::LockstepMissingLocal
             }
@@ -473,12 +862,34 @@ impl<'a> Problem<'a> {
             Problem::GeneratedSourceMissing { .. } => {
                 ProblemKind::GeneratedSourceMissing
             }
+            Problem::GenerationPanicked { .. } => {
+                ProblemKind::GenerationPanicked
+            }
             Problem::GeneratedValidationError { .. } => {
                 ProblemKind::GeneratedValidationError
             }
+            Problem::OperationIdChanged { .. } => {
+                ProblemKind::OperationIdChanged
+            }
+            Problem::LatestDocumentFrozen { .. } => {
+                ProblemKind::LatestDocumentFrozen
+            }
             Problem::ExtraFileStale { .. } => ProblemKind::ExtraFileStale,
+            Problem::ValidationFixable { .. } => ProblemKind::ValidationFixable,
             Problem::LatestLinkMissing { .. } => ProblemKind::LatestLinkMissing,
             Problem::LatestLinkStale { .. } => ProblemKind::LatestLinkStale,
+            Problem::LatestLinkDangling { .. } => {
+                ProblemKind::LatestLinkDangling
+            }
+            Problem::LatestLinkUnwanted { .. } => {
+                ProblemKind::LatestLinkUnwanted
+            }
+            Problem::LatestLinkTempLeftover { .. } => {
+                ProblemKind::LatestLinkTempLeftover
+            }
+            Problem::LatestPointerFileStale { .. } => {
+                ProblemKind::LatestPointerFileStale
+            }
             Problem::BlessedVersionShouldBeGitStub { .. } => {
                 ProblemKind::BlessedVersionShouldBeGitStub
             }
@@ -491,12 +902,40 @@ impl<'a> Problem<'a> {
             Problem::DuplicateLocalFile { .. } => {
                 ProblemKind::DuplicateLocalFile
             }
+            Problem::FileNameHashCollision { .. } => {
+                ProblemKind::FileNameHashCollision
+            }
             Problem::GitStubCommitStale { .. } => {
                 ProblemKind::GitStubCommitStale
             }
             Problem::GitStubFirstCommitUnknown { .. } => {
                 ProblemKind::GitStubFirstCommitUnknown
             }
+            Problem::DocumentSignatureMissing { .. } => {
+                ProblemKind::DocumentSignatureMissing
+            }
+            Problem::DocumentSignatureInvalid { .. } => {
+                ProblemKind::DocumentSignatureInvalid
+            }
+            Problem::BlessedSignatureInvalid { .. } => {
+                ProblemKind::BlessedSignatureInvalid
+            }
+            Problem::AliasStale { .. } => ProblemKind::AliasStale,
+            Problem::AliasError { .. } => ProblemKind::AliasError,
+            Problem::CombinedDocumentStale { .. } => {
+                ProblemKind::CombinedDocumentStale
+            }
+            Problem::CombinedDocumentError { .. } => {
+                ProblemKind::CombinedDocumentError
+            }
+            Problem::SharedSchemasStale { .. } => {
+                ProblemKind::SharedSchemasStale
+            }
+            Problem::SharedSchemasError { .. } => {
+                ProblemKind::SharedSchemasError
+            }
+            Problem::JsonFormatStale { .. } => ProblemKind::JsonFormatStale,
+            Problem::JsonFormatError { .. } => ProblemKind::JsonFormatError,
         }
     }
 
@@ -504,6 +943,16 @@ impl<'a> Problem<'a> {
         self.fix().is_some()
     }
 
+    /// For problems with the copy of an API's documents under an alias,
+    /// returns the alias.
+    pub(crate) fn alias(&self) -> Option<&'a ApiIdent> {
+        match self {
+            Problem::AliasStale { alias, .. }
+            | Problem::AliasError { alias, .. } => Some(alias),
+            _ => None,
+        }
+    }
+
     pub fn fix(&'a self) -> Option<Fix<'a>> {
         match self {
             Problem::LocalSpecFileOrphaned { spec_file_name } => {
@@ -511,6 +960,11 @@ impl<'a> Problem<'a> {
                     files: DisplayableVec(vec![spec_file_name.clone().into()]),
                 })
             }
+            Problem::UnknownApiFiles { paths, .. }
+            | Problem::RemovedApiFiles { paths, .. } => {
+                Some(Fix::DeleteUnknownApiFiles { paths })
+            }
+            Problem::UnknownApiFilesBlessed { .. } => None,
             Problem::BlessedVersionMissingLocal { blessed, git_stub } => {
                 Some(Fix::RestoreFromBlessed {
                     blessed,
@@ -524,7 +978,12 @@ impl<'a> Problem<'a> {
             }
             Problem::BlessedVersionCompareError { .. } => None,
             Problem::BlessedVersionBroken { .. } => None,
+            Problem::BlessedVersionReverseBroken { .. } => None,
             Problem::BlessedLatestVersionBytewiseMismatch { .. } => None,
+            Problem::BreakingChangeApprovalStale { approvals, approval } => {
+                Some(Fix::RemoveBreakingChangeApproval { approvals, approval })
+            }
+            Problem::ConsumerPinUnsupported { .. } => None,
             Problem::LockstepMissingLocal { generated }
             | Problem::LockstepStale { generated, .. } => {
                 Some(Fix::UpdateLockstepFile { generated })
@@ -547,23 +1006,41 @@ impl<'a> Problem<'a> {
                     ),
                 })
             }
-            Problem::LocalVersionStale { spec_files, generated } => {
-                Some(Fix::UpdateVersionedFiles {
-                    old: DisplayableVec(
-                        spec_files.iter().map(|s| s.spec_file_name()).collect(),
-                    ),
-                    generated,
-                })
-            }
+            Problem::LocalVersionStale { spec_files, generated }
+            | Problem::BlessedLatestVersionReformatted {
+                spec_files,
+                generated,
+            } => Some(Fix::UpdateVersionedFiles {
+                old: DisplayableVec(
+                    spec_files.iter().map(|s| s.spec_file_name()).collect(),
+                ),
+                generated,
+            }),
             Problem::GeneratedSourceMissing { .. } => None,
+            Problem::GenerationPanicked { .. } => None,
             Problem::GeneratedValidationError { .. } => None,
+            Problem::OperationIdChanged { .. } => None,
+            Problem::LatestDocumentFrozen { .. } => None,
             Problem::ExtraFileStale { path, check_stale, .. } => {
                 Some(Fix::UpdateExtraFile { path, check_stale })
             }
+            Problem::ValidationFixable { fix, .. } => Some(Fix::Custom { fix }),
             Problem::LatestLinkStale { api_ident, link, .. }
             | Problem::LatestLinkMissing { api_ident, link } => {
                 Some(Fix::UpdateSymlink { api_ident, link })
             }
+            Problem::LatestLinkDangling { api_ident, link, .. } => {
+                Some(Fix::UpdateSymlink { api_ident, link })
+            }
+            Problem::LatestLinkUnwanted { api_ident, .. } => {
+                Some(Fix::RemoveSymlink { api_ident })
+            }
+            Problem::LatestLinkTempLeftover { api_ident } => {
+                Some(Fix::RemoveSymlinkTemp { api_ident })
+            }
+            Problem::LatestPointerFileStale { api_ident, link } => {
+                Some(Fix::UpdatePointerFile { api_ident, link })
+            }
             Problem::BlessedVersionShouldBeGitStub { local_file, git_stub } => {
                 Some(Fix::ConvertToGitStub { local_file, git_stub })
             }
@@ -586,10 +1063,47 @@ impl<'a> Problem<'a> {
                     ]),
                 })
             }
+            Problem::FileNameHashCollision { .. } => None,
             Problem::GitStubCommitStale { local_file, git_stub } => {
                 Some(Fix::UpdateGitStub { local_file, git_stub })
             }
             Problem::GitStubFirstCommitUnknown { .. } => None,
+            Problem::DocumentSignatureMissing {
+                signing,
+                api_ident,
+                file_name,
+                contents,
+                ..
+            }
+            | Problem::DocumentSignatureInvalid {
+                signing,
+                api_ident,
+                file_name,
+                contents,
+                ..
+            } => Some(Fix::SignDocument {
+                signing,
+                api_ident,
+                file_name,
+                contents,
+            }),
+            Problem::BlessedSignatureInvalid { .. } => None,
+            Problem::AliasStale { api, alias, .. } => {
+                Some(Fix::SyncAlias { api, alias })
+            }
+            Problem::AliasError { .. } => None,
+            Problem::CombinedDocumentStale { api_ident, contents } => {
+                Some(Fix::WriteCombinedDocument { api_ident, contents })
+            }
+            Problem::CombinedDocumentError { .. } => None,
+            Problem::SharedSchemasStale { api, .. } => {
+                Some(Fix::SyncSharedSchemas { api })
+            }
+            Problem::SharedSchemasError { .. } => None,
+            Problem::JsonFormatStale { api, .. } => {
+                Some(Fix::FormatDocuments { api })
+            }
+            Problem::JsonFormatError { .. } => None,
             Problem::UnparseableLocalFile { unparseable_file } => {
                 Some(Fix::DeleteUnparseableFile {
                     path: unparseable_file.path.clone(),
@@ -614,10 +1128,28 @@ pub enum Fix<'a> {
         path: &'a Utf8Path,
         check_stale: &'a CheckStale,
     },
+    /// Apply a fix reported by validation.
+    Custom {
+        fix: &'a CustomFix,
+    },
     UpdateSymlink {
         api_ident: &'a ApiIdent,
         link: &'a VersionedApiSpecFileName,
     },
+    /// Remove the "latest" symlink for an API configured without one.
+    RemoveSymlink {
+        api_ident: &'a ApiIdent,
+    },
+    /// Remove a temporary "latest" symlink left behind by an interrupted
+    /// update.
+    RemoveSymlinkTemp {
+        api_ident: &'a ApiIdent,
+    },
+    /// Write the "latest" pointer file, removing any "latest" symlink.
+    UpdatePointerFile {
+        api_ident: &'a ApiIdent,
+        link: &'a VersionedApiSpecFileName,
+    },
     /// Convert a full JSON file to a Git stub.
     ConvertToGitStub {
         local_file: &'a LocalApiSpecFile,
@@ -656,6 +1188,41 @@ pub enum Fix<'a> {
     DeleteUnparseableFile {
         path: Utf8PathBuf,
     },
+    /// Delete files and directories for an API that isn't managed.
+    DeleteUnknownApiFiles {
+        paths: &'a DisplayableVec<Utf8PathBuf>,
+    },
+    /// Update the copy of an API's documents under an alias.
+    SyncAlias {
+        api: &'a ManagedApi,
+        alias: &'a ApiIdent,
+    },
+    /// Write a detached signature for a document.
+    SignDocument {
+        signing: &'a DocumentSigning,
+        api_ident: &'a ApiIdent,
+        file_name: &'a VersionedApiSpecFileName,
+        contents: &'a [u8],
+    },
+    /// Write the document combining all supported versions of an API.
+    WriteCombinedDocument {
+        api_ident: &'a ApiIdent,
+        contents: &'a [u8],
+    },
+    /// Split schemas out of (or back into) an API's documents, and update its
+    /// shared schemas.
+    SyncSharedSchemas {
+        api: &'a ManagedApi,
+    },
+    /// Reformat an API's documents that aren't in its configured format.
+    FormatDocuments {
+        api: &'a ManagedApi,
+    },
+    /// Remove an approval that no longer matches an incompatible change.
+    RemoveBreakingChangeApproval {
+        approvals: &'a BreakingChangeApprovals,
+        approval: &'a BreakingChangeApproval,
+    },
 }
 
 impl Display for Fix<'_> {
@@ -696,6 +1263,9 @@ impl Display for Fix<'_> {
                 };
                 writeln!(f, "{label} file {path} from generated")?;
             }
+            Fix::Custom { fix } => {
+                writeln!(f, "{}", fix.description())?;
+            }
             Fix::UpdateSymlink { link, .. } => {
                 writeln!(
                     f,
@@ -703,6 +1273,28 @@ impl Display for Fix<'_> {
                     link.json_basename()
                 )?;
             }
+            Fix::RemoveSymlink { api_ident } => {
+                writeln!(
+                    f,
+                    "remove symlink {}",
+                    api_ident.versioned_api_latest_symlink()
+                )?;
+            }
+            Fix::RemoveSymlinkTemp { api_ident } => {
+                writeln!(
+                    f,
+                    "remove temporary symlink {}",
+                    api_ident.versioned_api_latest_symlink_temp()
+                )?;
+            }
+            Fix::UpdatePointerFile { api_ident, link } => {
+                writeln!(
+                    f,
+                    "update {} to point to {}",
+                    api_ident.versioned_api_latest_pointer_file(),
+                    link.json_basename()
+                )?;
+            }
             Fix::ConvertToGitStub { local_file, .. } => {
                 writeln!(
                     f,
@@ -758,12 +1350,57 @@ impl Display for Fix<'_> {
             Fix::DeleteUnparseableFile { path } => {
                 writeln!(f, "delete unparseable file {path}")?;
             }
+            Fix::DeleteUnknownApiFiles { paths } => {
+                writeln!(f, "delete files for unmanaged API: {paths}")?;
+            }
+            Fix::SyncAlias { alias, .. } => {
+                writeln!(f, "update copy of documents under alias {alias}")?;
+            }
+            Fix::SignDocument { file_name, .. } => {
+                writeln!(f, "sign {}", file_name.json_basename())?;
+            }
+            Fix::WriteCombinedDocument { api_ident, .. } => {
+                writeln!(
+                    f,
+                    "write {}",
+                    api_ident.versioned_api_combined_file()
+                )?;
+            }
+            Fix::SyncSharedSchemas { api } => {
+                writeln!(f, "update shared schemas for {}", api.ident())?;
+            }
+            Fix::RemoveBreakingChangeApproval { approvals, approval } => {
+                writeln!(
+                    f,
+                    "remove stale {approval} from {}",
+                    approvals.path()
+                )?;
+            }
+            Fix::FormatDocuments { api } => {
+                writeln!(f, "reformat documents for {}", api.ident())?;
+            }
         };
         Ok(())
     }
 }
 
-impl Fix<'_> {
+impl<'a> Fix<'a> {
+    /// Returns the fixes that `generate` applies to `api` once its documents
+    /// are up-to-date, whether or not any problems were found: reformatting
+    /// the documents, updating shared schemas, and updating the copies under
+    /// each alias, in that order.
+    ///
+    /// These are applied unconditionally since fixing other problems may have
+    /// changed the documents involved. The copies are updated last, so that
+    /// they're made from consistent documents.
+    pub fn post_generate(api: &'a ManagedApi) -> impl Iterator<Item = Fix<'a>> {
+        [Fix::FormatDocuments { api }, Fix::SyncSharedSchemas { api }]
+            .into_iter()
+            .chain(
+                api.aliases().iter().map(|alias| Fix::SyncAlias { api, alias }),
+            )
+    }
+
     /// Adds the paths (relative to the OpenAPI documents directory) that this
     /// fix will write to. Used to determine if an unparseable file will be
     /// overwritten.
@@ -779,7 +1416,18 @@ impl Fix<'_> {
             Fix::UpdateExtraFile { path, .. } => {
                 paths.insert((*path).to_owned());
             }
+            // Custom fixes operate on paths relative to the repository root,
+            // and which paths they write isn't known ahead of time.
+            Fix::Custom { .. } => {}
             Fix::UpdateSymlink { .. } => {}
+            Fix::RemoveSymlink { .. } => {}
+            Fix::RemoveSymlinkTemp { .. } => {}
+            Fix::UpdatePointerFile { api_ident, .. } => {
+                paths.insert(
+                    Utf8PathBuf::from(api_ident.as_str())
+                        .join(api_ident.versioned_api_latest_pointer_file()),
+                );
+            }
             Fix::ConvertToGitStub { local_file, .. } => {
                 // Writes to the .gitstub path, not the JSON path.
                 paths.insert(
@@ -822,6 +1470,24 @@ impl Fix<'_> {
                 paths.insert(local_file.spec_file_name().path().to_owned());
             }
             Fix::DeleteUnparseableFile { .. } => {}
+            Fix::DeleteUnknownApiFiles { .. } => {}
+            // Aliases are outside the API's own documents, so they never
+            // overwrite unparseable files.
+            Fix::SyncAlias { .. } => {}
+            // Signatures are stored outside the documents directory.
+            Fix::SignDocument { .. } => {}
+            // These rewrite documents that were already parsed, along with
+            // shared schemas, which aren't documents themselves.
+            Fix::SyncSharedSchemas { .. } => {}
+            Fix::FormatDocuments { .. } => {}
+            // Approvals are stored outside the documents directory.
+            Fix::RemoveBreakingChangeApproval { .. } => {}
+            Fix::WriteCombinedDocument { api_ident, .. } => {
+                paths.insert(
+                    Utf8PathBuf::from(api_ident.as_str())
+                        .join(api_ident.versioned_api_combined_file()),
+                );
+            }
         }
         // No wildcard match: adding a new Fix variant should cause a compile
         // error here, forcing consideration of what paths it writes.
@@ -829,6 +1495,14 @@ impl Fix<'_> {
 
     pub fn execute(&self, env: &ResolvedEnv) -> anyhow::Result<Vec<String>> {
         let root = env.openapi_abs_dir();
+        if env.read_only {
+            return Err(anyhow!(
+                "refusing to {}: this command must not write to the \
+                 documents directory {} (this is a bug)",
+                self.to_string().trim_end(),
+                root,
+            ));
+        }
         match self {
             Fix::DeleteFiles { files } => {
                 let mut rv = Vec::new();
@@ -877,6 +1551,7 @@ impl Fix<'_> {
                     overwrite_file(&full_path, expected_contents)?
                 )])
             }
+            Fix::Custom { fix } => fix.apply(&env.repo_root),
             Fix::UpdateSymlink { api_ident, link } => {
                 let path = root
                     .join(api_ident.to_string())
@@ -886,15 +1561,48 @@ impl Fix<'_> {
                 // resolved from. If the link target is a gitstub, convert it to
                 // the JSON filename (the symlink should always point to JSON).
                 let target = link.json_basename();
+                replace_symlink(&target, &path)?;
+                Ok(vec![format!("wrote link {} -> {}", path, target)])
+            }
+            Fix::RemoveSymlink { api_ident } => {
+                let path = root
+                    .join(api_ident.as_str())
+                    .join(api_ident.versioned_api_latest_symlink());
+                fs_err::remove_file(&path)?;
+                Ok(vec![format!("removed link {}", path)])
+            }
+            Fix::RemoveSymlinkTemp { api_ident } => {
+                let path = root
+                    .join(api_ident.as_str())
+                    .join(api_ident.versioned_api_latest_symlink_temp());
+                // Updating the "latest" symlink may have removed this already.
                 match fs_err::remove_file(&path) {
-                    Ok(_) => (),
+                    Ok(()) => {}
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(err) => return Err(err.into()),
+                }
+                Ok(vec![format!("removed temporary link {}", path)])
+            }
+            Fix::UpdatePointerFile { api_ident, link } => {
+                let dir = root.join(api_ident.as_str());
+                let mut rv = Vec::new();
+                let link_path =
+                    dir.join(api_ident.versioned_api_latest_symlink());
+                match fs_err::remove_file(&link_path) {
+                    Ok(_) => rv.push(format!("removed link {}", link_path)),
                     Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
                     Err(err) => {
                         return Err(anyhow!(err).context("removing old link"));
                     }
-                };
-                symlink_file(&target, &path)?;
-                Ok(vec![format!("wrote link {} -> {}", path, target)])
+                }
+                let path =
+                    dir.join(api_ident.versioned_api_latest_pointer_file());
+                rv.push(format!(
+                    "wrote {}: {:?}",
+                    path,
+                    overwrite_file(&path, &latest_pointer_file_contents(link))?
+                ));
+                Ok(rv)
             }
             Fix::ConvertToGitStub { local_file, git_stub } => {
                 let json_path = root.join(local_file.spec_file_name().path());
@@ -1033,23 +1741,101 @@ impl Fix<'_> {
                 fs_err::remove_file(&full_path)?;
                 Ok(vec![format!("removed unparseable file {}", full_path)])
             }
+            Fix::DeleteUnknownApiFiles { paths } => {
+                let mut messages = Vec::new();
+                for path in &paths.0 {
+                    let full_path = root.join(path);
+                    if full_path.is_dir() {
+                        fs_err::remove_dir_all(&full_path)?;
+                    } else {
+                        fs_err::remove_file(&full_path)?;
+                    }
+                    messages.push(format!("removed {}", full_path));
+                }
+                Ok(messages)
+            }
+            Fix::SyncAlias { api, alias } => {
+                sync_alias(root, &env.settings.ignore_globs, api, alias)
+            }
+            Fix::SyncSharedSchemas { api } => {
+                sync_shared_schemas(root, &env.settings.ignore_globs, api)
+            }
+            Fix::FormatDocuments { api } => {
+                sync_json_format(root, &env.settings.ignore_globs, api)
+            }
+            Fix::RemoveBreakingChangeApproval { approvals, approval } => {
+                approvals.remove(&env.repo_root, approval)?;
+                Ok(vec![format!(
+                    "removed {} from {}",
+                    approval,
+                    approvals.path()
+                )])
+            }
+            Fix::SignDocument { signing, api_ident, file_name, contents } => {
+                let path = signing.sign(
+                    &env.repo_root,
+                    api_ident,
+                    file_name,
+                    contents,
+                )?;
+                Ok(vec![format!("wrote signature {}", path)])
+            }
+            Fix::WriteCombinedDocument { api_ident, contents } => {
+                let path = root
+                    .join(api_ident.as_str())
+                    .join(api_ident.versioned_api_combined_file());
+                Ok(vec![format!(
+                    "wrote {}: {:?}",
+                    path,
+                    overwrite_file(&path, contents)?
+                )])
+            }
         }
     }
 }
 
+/// Points the symlink at `path` to `target`, replacing any existing link.
+///
+/// The new link is created as `{path}.tmp` and then renamed over `path`, so
+/// that a reader (or a crash partway through) never sees `path` missing. Any
+/// temporary link left behind by an earlier, interrupted update is replaced.
+pub(crate) fn replace_symlink(
+    target: &str,
+    path: &Utf8Path,
+) -> anyhow::Result<()> {
+    let temp_path = Utf8PathBuf::from(format!("{}.tmp", path));
+    match fs_err::remove_file(&temp_path) {
+        Ok(()) => {}
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+        Err(err) => {
+            return Err(anyhow!(err).context("removing old temporary link"));
+        }
+    }
+    symlink_file(target, &temp_path)?;
+    fs_err::rename(&temp_path, path).context("renaming link into place")?;
+    Ok(())
+}
+
 #[cfg(unix)]
-fn symlink_file(target: &str, path: &Utf8Path) -> std::io::Result<()> {
+pub(crate) fn symlink_file(
+    target: &str,
+    path: &Utf8Path,
+) -> std::io::Result<()> {
     fs_err::os::unix::fs::symlink(target, path)
 }
 
 #[cfg(windows)]
-fn symlink_file(target: &str, path: &Utf8Path) -> std::io::Result<()> {
+pub(crate) fn symlink_file(
+    target: &str,
+    path: &Utf8Path,
+) -> std::io::Result<()> {
     fs_err::os::windows::fs::symlink_file(target, path)
 }
 
 /// Resolve differences between blessed spec(s), the generated spec, and any
 /// local spec files for a given API
 pub struct Resolved<'a> {
+    apis: &'a ManagedApis,
     notes: Vec<Note>,
     non_version_problems: Vec<(ApiIdent, Option<semver::Version>, Problem<'a>)>,
     api_results: BTreeMap<ApiIdent, ApiResolved<'a>>,
@@ -1085,7 +1871,7 @@ impl<'a> Resolved<'a> {
 
         // Get one easy case out of the way: if there are any blessed API
         // versions that aren't supported any more, note that.
-        let notes: Vec<Note> = resolve_removed_blessed_versions(
+        let mut notes: Vec<Note> = resolve_removed_blessed_versions(
             &supported_versions_by_api,
             blessed,
         )
@@ -1094,6 +1880,10 @@ impl<'a> Resolved<'a> {
             version: version.clone(),
         })
         .collect();
+        notes
+            .extend(resolve_unstable_path_operations(apis, blessed, generated));
+        notes.extend(resolve_size_growth(apis, blessed, generated));
+        notes.extend(resolve_consumer_pin_lag(apis, local));
 
         // Get the other easy case out of the way: if there are any local spec
         // files for APIs or API versions that aren't supported any more, that's
@@ -1118,7 +1908,7 @@ impl<'a> Resolved<'a> {
 
         // Resolve each of the supported API versions first, so we know what
         // paths will be written. (Do this in parallel across each API version.)
-        let api_results: BTreeMap<ApiIdent, ApiResolved<'_>> = apis
+        let mut api_results: BTreeMap<ApiIdent, ApiResolved<'_>> = apis
             .iter_apis()
             .collect::<Vec<_>>()
             .par_iter()
@@ -1127,9 +1917,11 @@ impl<'a> Resolved<'a> {
                 let api_blessed = blessed.get(&ident);
                 let Some(api_generated) = generated.get(&ident) else {
                     // No generated documents for this API. This can happen
-                    // when --generated-from-dir points to a directory that
-                    // doesn't contain documents for all configured APIs.
-                    // Report an unfixable problem for each version.
+                    // when generating the API panicked, or when
+                    // --generated-from-dir points to a directory that doesn't
+                    // contain documents for all configured APIs. Report an
+                    // unfixable problem for each version.
+                    let panic_message = generated.panic_message(&ident);
                     let by_version = api
                         .iter_versions_semver()
                         .map(|version| {
@@ -1142,16 +1934,34 @@ impl<'a> Resolved<'a> {
                                 version.clone(),
                                 Resolution {
                                     kind,
-                                    problems: vec![
-                                        Problem::GeneratedSourceMissing {
-                                            api_ident: ident.clone(),
-                                        },
-                                    ],
+                                    problems: vec![match panic_message {
+                                        Some(message) => {
+                                            Problem::GenerationPanicked {
+                                                api_ident: ident.clone(),
+                                                message: message.to_owned(),
+                                            }
+                                        }
+                                        None => {
+                                            Problem::GeneratedSourceMissing {
+                                                api_ident: ident.clone(),
+                                            }
+                                        }
+                                    }],
                                 },
                             )
                         })
                         .collect();
-                    return (ident, ApiResolved { by_version, symlink: None });
+                    return (
+                        ident,
+                        ApiResolved {
+                            by_version,
+                            symlink: None,
+                            aliases: Vec::new(),
+                            combined: None,
+                            shared_schemas: None,
+                            json_format: None,
+                        },
+                    );
                 };
                 let api_local = local.get(&ident);
                 (
@@ -1159,7 +1969,7 @@ impl<'a> Resolved<'a> {
                     resolve_api(
                         env,
                         api,
-                        apis.validation(),
+                        &apis.validations_for(api),
                         apis.uses_git_stub_storage(api),
                         blessed,
                         api_blessed,
@@ -1188,6 +1998,15 @@ impl<'a> Resolved<'a> {
         }
 
         for (ident, api_files) in local.iter() {
+            if api_files.has_latest_link_temp() {
+                non_version_problems.push((
+                    ident.clone(),
+                    None,
+                    Problem::LatestLinkTempLeftover {
+                        api_ident: ident.clone(),
+                    },
+                ));
+            }
             for unparseable in api_files.unparseable_files() {
                 // Only report if no fix will overwrite this path.
                 if !paths_written.contains(&unparseable.path) {
@@ -1202,7 +2021,124 @@ impl<'a> Resolved<'a> {
             }
         }
 
+        // Files for APIs that aren't managed are only collected if the API was
+        // removed on purpose, or if the environment's policy is to delete
+        // them. In the latter case, they're only deleted automatically if the
+        // API has no blessed documents, since otherwise the API may have been
+        // removed by mistake.
+        for (ident, paths) in local.unknown_api_files() {
+            let api_ident = ident.clone();
+            let paths = DisplayableVec(paths.clone());
+            let problem = if apis.is_removed_api(ident) {
+                Problem::RemovedApiFiles { api_ident, paths }
+            } else if blessed.has_unknown_api(ident) {
+                Problem::UnknownApiFilesBlessed { api_ident, paths }
+            } else {
+                Problem::UnknownApiFiles { api_ident, paths }
+            };
+            non_version_problems.push((ident.clone(), None, problem));
+        }
+
+        if let Some(approvals) = apis.breaking_change_approvals() {
+            non_version_problems.extend(
+                resolve_breaking_change_approvals(
+                    approvals,
+                    local,
+                    &mut api_results,
+                )
+                .into_iter()
+                // Approvals for APIs left out of a partial check can't be
+                // matched against anything.
+                .filter(|(ident, _, _)| !apis.is_skipped_api(ident)),
+            );
+        }
+        non_version_problems.extend(resolve_consumer_pins(apis, local));
+
+        // Problems are discovered in an order that depends on the map
+        // iteration and resolution internals above. Sort them by (API, version,
+        // kind) so that output can be diffed across runs. (Sorts are stable, so
+        // problems that compare equal retain their discovery order.)
+        non_version_problems.sort_by(
+            |(a_ident, a_version, a), (b_ident, b_version, b)| {
+                (a_ident, a_version, a.kind()).cmp(&(
+                    b_ident,
+                    b_version,
+                    b.kind(),
+                ))
+            },
+        );
+        if let Some(signing) = apis.document_signing() {
+            resolve_signatures(
+                env,
+                apis,
+                signing,
+                blessed,
+                generated,
+                &mut api_results,
+            );
+        }
+
+        for api in apis.iter_apis() {
+            let Some(api_resolved) = api_results.get_mut(api.ident()) else {
+                continue;
+            };
+            for (version, problem) in
+                resolve_operation_ids(api, blessed, generated)
+                    .into_iter()
+                    .chain(resolve_deprecation_summary(
+                        env, api, blessed, generated,
+                    ))
+            {
+                if let Some(resolution) =
+                    api_resolved.by_version.get_mut(&version)
+                {
+                    resolution.problems.push(problem);
+                }
+            }
+        }
+
+        if !env.override_freeze {
+            for api in apis.iter_apis() {
+                let Some(api_resolved) = api_results.get_mut(api.ident())
+                else {
+                    continue;
+                };
+                if let Some((version, problem)) =
+                    resolve_freeze(env, api, blessed, api_resolved)
+                    && let Some(resolution) =
+                        api_resolved.by_version.get_mut(&version)
+                {
+                    resolution.problems.push(problem);
+                }
+            }
+        }
+
+        for api_resolved in api_results.values_mut() {
+            for resolution in api_resolved.by_version.values_mut() {
+                resolution.sort_problems();
+            }
+        }
+
+        // Finally, check the copies of documents under each alias against the
+        // documents on disk, combined documents against the documents they're
+        // built from, and how documents are stored against whether they use
+        // shared schemas and how they should be formatted.
+        for api in apis.iter_apis() {
+            let alias_problems = resolve_aliases(env, api);
+            let combined_problem =
+                resolve_combined_document(env, api, blessed, generated);
+            let shared_schemas_problem = resolve_shared_schemas(env, api);
+            let json_format_problem = resolve_json_format(env, api);
+            if let Some(api_resolved) = api_results.get_mut(api.ident()) {
+                api_resolved.aliases = alias_problems;
+                api_resolved.combined = combined_problem;
+                api_resolved.shared_schemas = shared_schemas_problem;
+                api_resolved.json_format = json_format_problem;
+            }
+        }
+
         Resolved {
+            apis,
             notes,
             non_version_problems,
             api_results,
@@ -1218,6 +2154,8 @@ impl<'a> Resolved<'a> {
         self.notes.iter()
     }
 
+    /// Returns problems not associated with a supported API version, sorted
+    /// by API, version, and kind.
     pub fn general_problems(&self) -> impl Iterator<Item = &Problem<'a>> + '_ {
         self.non_version_problems.iter().map(|(_, _, problem)| problem)
     }
@@ -1234,62 +2172,572 @@ impl<'a> Resolved<'a> {
         self.api_results.get(ident).and_then(|v| v.symlink.as_ref())
     }
 
+    /// Returns problems with the copies of an API's documents under its
+    /// aliases.
+    pub fn alias_problems(
+        &self,
+        ident: &ApiIdent,
+    ) -> impl Iterator<Item = &Problem<'_>> + '_ {
+        self.api_results.get(ident).into_iter().flat_map(|v| &v.aliases)
+    }
+
+    /// Returns the problem with the document combining all supported versions
+    /// of an API, if any.
+    pub fn combined_document_problem(
+        &self,
+        ident: &ApiIdent,
+    ) -> Option<&Problem<'_>> {
+        self.api_results.get(ident).and_then(|v| v.combined.as_ref())
+    }
+
+    /// Returns the problem with how an API's documents are stored with respect
+    /// to shared schemas, if any.
+    pub fn shared_schemas_problem(
+        &self,
+        ident: &ApiIdent,
+    ) -> Option<&Problem<'_>> {
+        self.api_results.get(ident).and_then(|v| v.shared_schemas.as_ref())
+    }
+
+    /// Returns the problem with how an API's documents are formatted, if any.
+    pub fn json_format_problem(
+        &self,
+        ident: &ApiIdent,
+    ) -> Option<&Problem<'_>> {
+        self.api_results.get(ident).and_then(|v| v.json_format.as_ref())
+    }
+
     pub fn has_unfixable_problems(&self) -> bool {
         self.general_problems().any(|p| !p.is_fixable())
             || self.api_results.values().any(|a| a.has_unfixable_problems())
     }
 
-    /// Returns an owned, ordered list of all problems as summaries.
-    ///
-    /// Order: general (non-version-specific) problems first, then per-API
-    /// (sorted by ident), per-version (sorted by semver), then symlink
-    /// problems.
-    pub fn problem_summaries(&self) -> Vec<ProblemSummary> {
-        let mut summaries = Vec::new();
+    /// Applies the fixes for all fixable problems, in the same order as
+    /// `generate`, followed by the fixes in [`Fix::post_generate`] for each
+    /// API.
+    ///
+    /// `on_fix` is called with each fix and its result, and can return
+    /// [`ControlFlow::Break`] to stop applying fixes, in which case this
+    /// returns `ControlFlow::Break` as well. Fixes in `Fix::post_generate` that
+    /// turn out to have nothing to do aren't reported. Unfixable problems are
+    /// skipped, so callers should check [`Self::has_unfixable_problems`] first.
+    pub fn apply_fixes<F>(
+        &self,
+        env: &ResolvedEnv,
+        mut on_fix: F,
+    ) -> ControlFlow<()>
+    where
+        F: FnMut(&Fix<'_>, &anyhow::Result<Vec<String>>) -> ControlFlow<()>,
+    {
+        let mut apply = |problem: &Problem<'_>| match problem.fix() {
+            Some(fix) => on_fix(&fix, &fix.execute(env)),
+            None => ControlFlow::Continue(()),
+        };
+
+        for api in self.apis.iter_apis() {
+            let ident = api.ident();
+            for version in api.iter_versions_semver() {
+                if let Some(resolution) =
+                    self.resolution_for_api_version(ident, version)
+                {
+                    resolution.problems().try_for_each(&mut apply)?;
+                }
+            }
+            self.symlink_problem(ident).into_iter().try_for_each(&mut apply)?;
+            self.combined_document_problem(ident)
+                .into_iter()
+                .try_for_each(&mut apply)?;
+        }
+        self.general_problems().try_for_each(&mut apply)?;
+
+        for api in self.apis.iter_apis() {
+            for fix in Fix::post_generate(api) {
+                match fix.execute(env) {
+                    Ok(steps) if steps.is_empty() => {}
+                    result => on_fix(&fix, &result)?,
+                }
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// Returns an owned, ordered list of all problems as summaries.
+    ///
+    /// Order: general (non-version-specific) problems first (sorted by ident,
+    /// version, and kind), then per-API (sorted by ident), per-version (sorted
+    /// by semver, then kind), then symlink, alias, combined document, shared
+    /// schemas, and JSON format problems. This order
+    /// doesn't depend on the order in which problems were found.
+    pub fn problem_summaries(&self) -> Vec<ProblemSummary> {
+        self.iter_all_problems()
+            .map(|(ident, version, problem)| ProblemSummary {
+                api_ident: ident.clone(),
+                version: version.cloned(),
+                kind: problem.kind(),
+            })
+            .collect()
+    }
+
+    /// Iterates over all problems, along with the API and (if any) the version
+    /// each is associated with, in the order described in
+    /// [`Self::problem_summaries`].
+    pub(crate) fn iter_all_problems(
+        &self,
+    ) -> impl Iterator<Item = ProblemEntry<'_, 'a>> {
+        let general = self.non_version_problems.iter().map(
+            |(ident, version, problem)| (ident, version.as_ref(), problem),
+        );
+        let per_api =
+            self.api_results.iter().flat_map(|(ident, api_resolved)| {
+                let by_version =
+                    api_resolved.by_version.iter().flat_map(move |(v, r)| {
+                        r.problems()
+                            .map(move |problem| (ident, Some(v), problem))
+                    });
+                let others = api_resolved
+                    .symlink
+                    .iter()
+                    .chain(&api_resolved.aliases)
+                    .chain(&api_resolved.combined)
+                    .chain(&api_resolved.shared_schemas)
+                    .chain(&api_resolved.json_format)
+                    .map(move |problem| (ident, None, problem));
+                by_version.chain(others)
+            });
+        general.chain(per_api)
+    }
+}
+
+/// A problem, along with the API and (if any) the version it's associated
+/// with.
+pub(crate) type ProblemEntry<'r, 'a> =
+    (&'r ApiIdent, Option<&'r semver::Version>, &'r Problem<'a>);
+
+struct ApiResolved<'a> {
+    by_version: BTreeMap<semver::Version, Resolution<'a>>,
+    symlink: Option<Problem<'a>>,
+    aliases: Vec<Problem<'a>>,
+    combined: Option<Problem<'a>>,
+    shared_schemas: Option<Problem<'a>>,
+    json_format: Option<Problem<'a>>,
+}
+
+impl ApiResolved<'_> {
+    fn has_unfixable_problems(&self) -> bool {
+        self.symlink.as_ref().is_some_and(|f| !f.is_fixable())
+            || self.by_version.values().any(|r| r.has_errors())
+            || self.aliases.iter().any(|p| !p.is_fixable())
+            || self.combined.as_ref().is_some_and(|p| !p.is_fixable())
+            || self.shared_schemas.as_ref().is_some_and(|p| !p.is_fixable())
+            || self.json_format.as_ref().is_some_and(|p| !p.is_fixable())
+    }
+}
+
+/// Compares the copies of `api`'s documents under each of its aliases with the
+/// documents on disk.
+fn resolve_aliases<'a>(
+    env: &ResolvedEnv,
+    api: &'a ManagedApi,
+) -> Vec<Problem<'a>> {
+    api.aliases()
+        .iter()
+        .filter_map(|alias| {
+            match diff_alias(
+                env.openapi_abs_dir(),
+                &env.settings.ignore_globs,
+                api,
+                alias,
+            ) {
+                Ok(diff) if diff.is_empty() => None,
+                Ok(diff) => Some(Problem::AliasStale {
+                    api,
+                    alias,
+                    paths: DisplayableVec(diff.paths().cloned().collect()),
+                }),
+                Err(error) => Some(Problem::AliasError { alias, error }),
+            }
+        })
+        .collect()
+}
+
+/// Compares how `api`'s documents are stored with whether it uses shared
+/// schemas.
+fn resolve_shared_schemas<'a>(
+    env: &ResolvedEnv,
+    api: &'a ManagedApi,
+) -> Option<Problem<'a>> {
+    match diff_shared_schemas(
+        env.openapi_abs_dir(),
+        &env.settings.ignore_globs,
+        api,
+    ) {
+        Ok(diff) if diff.is_empty() => None,
+        Ok(diff) => Some(Problem::SharedSchemasStale {
+            api,
+            paths: DisplayableVec(diff.paths().cloned().collect()),
+        }),
+        Err(error) => Some(Problem::SharedSchemasError { error }),
+    }
+}
+
+/// Compares how `api`'s documents are formatted with its configured format.
+fn resolve_json_format<'a>(
+    env: &ResolvedEnv,
+    api: &'a ManagedApi,
+) -> Option<Problem<'a>> {
+    match diff_json_format(
+        env.openapi_abs_dir(),
+        &env.settings.ignore_globs,
+        api,
+    ) {
+        Ok(documents) if documents.is_empty() => None,
+        Ok(documents) => Some(Problem::JsonFormatStale {
+            api,
+            documents: DisplayableVec(documents),
+        }),
+        Err(error) => Some(Problem::JsonFormatError { error }),
+    }
+}
+
+/// Compares `api`'s combined document, if it has one, with the one built from
+/// the blessed and generated documents.
+fn resolve_combined_document<'a>(
+    env: &ResolvedEnv,
+    api: &'a ManagedApi,
+    blessed: &'a BlessedFiles,
+    generated: &'a GeneratedFiles,
+) -> Option<Problem<'a>> {
+    if !api.is_versioned() || !api.has_combined_document() {
+        return None;
+    }
+    let ident = api.ident();
+    let api_blessed = blessed.get(ident);
+    let api_generated = generated.get(ident)?;
+
+    // As for the "latest" link, blessed versions are included as blessed, in
+    // case the generated documents differ in wire-compatible ways.
+    let mut documents = Vec::new();
+    for version in api.iter_versions_semver() {
+        let document = match api_blessed.and_then(|b| b.versions().get(version))
+        {
+            Some(blessed) => blessed.value(),
+            // If the generated source doesn't include every version (e.g.
+            // --generated-from-dir), the per-version problems already capture
+            // the missing versions.
+            None => api_generated.versions().get(version)?.value(),
+        };
+        documents.push((version, document));
+    }
+    let contents = match combined_document(documents) {
+        Ok(contents) => contents,
+        Err(error) => return Some(Problem::CombinedDocumentError { error }),
+    };
+
+    let path = env
+        .openapi_abs_dir()
+        .join(ident.as_str())
+        .join(ident.versioned_api_combined_file());
+    // An unreadable combined document is treated as stale, and overwritten.
+    let up_to_date =
+        read_opt(&path).ok().flatten().as_deref() == Some(contents.as_slice());
+    (!up_to_date).then(|| Problem::CombinedDocumentStale {
+        api_ident: ident.clone(),
+        contents,
+    })
+}
+
+/// Lists the operations excluded from comparisons with blessed documents
+/// because they're under an API's unstable path prefixes.
+fn resolve_unstable_path_operations(
+    apis: &ManagedApis,
+    blessed: &BlessedFiles,
+    generated: &GeneratedFiles,
+) -> Vec<Note> {
+    let mut notes = Vec::new();
+    for api in apis.iter_apis() {
+        if !api.is_versioned() || api.unstable_path_prefixes().is_empty() {
+            continue;
+        }
+        let ident = api.ident();
+        let Some(api_blessed) = blessed.get(ident) else {
+            continue;
+        };
+        // Only blessed versions are compared, so only operations in blessed
+        // versions (as blessed or generated) are excluded.
+        let mut operations = BTreeSet::new();
+        for version in api.iter_versions_semver() {
+            let Some(blessed_file) = api_blessed.versions().get(version) else {
+                continue;
+            };
+            operations.extend(exempt_path_operations(
+                blessed_file.value(),
+                api.unstable_operations(),
+            ));
+            if let Some(generated_file) = generated
+                .get(ident)
+                .and_then(|files| files.versions().get(version))
+            {
+                operations.extend(exempt_path_operations(
+                    generated_file.value(),
+                    api.unstable_operations(),
+                ));
+            }
+        }
+        if !operations.is_empty() {
+            notes.push(Note::UnstablePathOperationsExcluded {
+                api_ident: ident.clone(),
+                prefixes: DisplayableVec(api.unstable_path_prefixes().to_vec()),
+                operations: DisplayableVec(operations.into_iter().collect()),
+            });
+        }
+    }
+    notes
+}
+
+/// For each versioned API with a size growth threshold, returns a note if the
+/// latest version is new and its generated document exceeds the latest blessed
+/// document by more than the threshold.
+fn resolve_size_growth(
+    apis: &ManagedApis,
+    blessed: &BlessedFiles,
+    generated: &GeneratedFiles,
+) -> Vec<Note> {
+    let mut notes = Vec::new();
+    for api in apis.iter_apis() {
+        let Some(threshold_percent) = api.size_growth_threshold() else {
+            continue;
+        };
+        if !api.is_versioned() {
+            continue;
+        }
+        let ident = api.ident();
+        let Some(version) = api.iter_versions_semver().max() else {
+            continue;
+        };
+        let Some((blessed_version, blessed_file)) =
+            blessed.get(ident).and_then(|b| b.versions().last_key_value())
+        else {
+            continue;
+        };
+        // Once the latest version is blessed, it's compared against its
+        // blessed document rather than against an older version.
+        if blessed_version >= version {
+            continue;
+        }
+        let Some(generated_file) =
+            generated.get(ident).and_then(|g| g.versions().get(version))
+        else {
+            continue;
+        };
 
-        // General problems.
-        for (ident, version, problem) in &self.non_version_problems {
-            summaries.push(ProblemSummary {
+        let blessed_size = blessed_file.contents().len();
+        let generated_size = generated_file.contents().len();
+        if blessed_size == 0 || generated_size <= blessed_size {
+            continue;
+        }
+        let growth_percent = ((generated_size - blessed_size) as u64) * 100
+            / blessed_size as u64;
+        if growth_percent > u64::from(threshold_percent) {
+            notes.push(Note::DocumentSizeGrowth {
                 api_ident: ident.clone(),
                 version: version.clone(),
-                kind: problem.kind(),
+                blessed_version: blessed_version.clone(),
+                generated_size,
+                blessed_size,
+                growth_percent,
+                threshold_percent,
             });
         }
+    }
+    notes
+}
 
-        // Per-API problems.
-        for (ident, api_resolved) in &self.api_results {
-            for (version, resolution) in &api_resolved.by_version {
-                for problem in resolution.problems() {
-                    summaries.push(ProblemSummary {
-                        api_ident: ident.clone(),
-                        version: Some(version.clone()),
-                        kind: problem.kind(),
-                    });
+/// Checks that operations present in consecutive supported versions of `api`
+/// keep the same operation ID, returning a problem (associated with the later
+/// version) for each one that doesn't.
+fn resolve_operation_ids<'a>(
+    api: &ManagedApi,
+    blessed: &BlessedFiles,
+    generated: &GeneratedFiles,
+) -> Vec<(semver::Version, Problem<'a>)> {
+    let mut problems = Vec::new();
+    if !api.is_versioned() {
+        return problems;
+    }
+    let ident = api.ident();
+    let api_blessed = blessed.get(ident);
+    let Some(api_generated) = generated.get(ident) else {
+        return problems;
+    };
+
+    // As for the combined document, blessed versions are compared as blessed,
+    // since that's what clients were generated from.
+    let mut previous: Option<(&semver::Version, BTreeMap<_, _>)> = None;
+    for version in api.iter_versions_semver() {
+        let document = match api_blessed.and_then(|b| b.versions().get(version))
+        {
+            Some(blessed) => blessed.openapi(),
+            None => match api_generated.versions().get(version) {
+                Some(generated) => generated.openapi(),
+                // The per-version problems already capture missing versions.
+                None => {
+                    previous = None;
+                    continue;
+                }
+            },
+        };
+        let mut operation_ids = BTreeMap::new();
+        for (path, item) in document.paths.iter() {
+            let Some(item) = item.as_item() else {
+                continue;
+            };
+            for (method, operation) in item.iter() {
+                if let Some(operation_id) = &operation.operation_id {
+                    operation_ids.insert((path, method), operation_id);
                 }
-            }
-            if let Some(symlink) = &api_resolved.symlink {
-                summaries.push(ProblemSummary {
-                    api_ident: ident.clone(),
-                    version: None,
-                    kind: symlink.kind(),
-                });
             }
         }
 
-        summaries
+        if let Some((old_version, old_ids)) = &previous {
+            for (&(path, method), &new_id) in &operation_ids {
+                let Some(&old_id) = old_ids.get(&(path, method)) else {
+                    continue;
+                };
+                if old_id != new_id
+                    && !api.is_renamed_operation_id(old_id, new_id)
+                {
+                    problems.push((
+                        version.clone(),
+                        Problem::OperationIdChanged {
+                            api_ident: ident.clone(),
+                            method: method.to_uppercase(),
+                            path: path.clone(),
+                            old_version: (*old_version).clone(),
+                            old_id: old_id.clone(),
+                            new_version: version.clone(),
+                            new_id: new_id.clone(),
+                        },
+                    ));
+                }
+            }
+        }
+        previous = Some((version, operation_ids));
     }
+    problems
 }
 
-struct ApiResolved<'a> {
-    by_version: BTreeMap<semver::Version, Resolution<'a>>,
-    symlink: Option<Problem<'a>>,
+/// Compares `api`'s deprecation summary, if it has one, with the one built
+/// from the blessed and generated documents, returning a problem (associated
+/// with the latest version) if it's stale.
+fn resolve_deprecation_summary<'a>(
+    env: &ResolvedEnv,
+    api: &ManagedApi,
+    blessed: &BlessedFiles,
+    generated: &GeneratedFiles,
+) -> Option<(semver::Version, Problem<'a>)> {
+    let summary = api.deprecation_summary()?;
+    if !api.is_versioned() {
+        return None;
+    }
+    let ident = api.ident();
+    let api_blessed = blessed.get(ident);
+    let api_generated = generated.get(ident)?;
+
+    // As for the combined document, blessed versions are summarized as
+    // blessed.
+    let mut documents = Vec::new();
+    for version in api.iter_versions_semver() {
+        let document = match api_blessed.and_then(|b| b.versions().get(version))
+        {
+            Some(blessed) => blessed.openapi(),
+            // The per-version problems already capture missing versions.
+            None => api_generated.versions().get(version)?.openapi(),
+        };
+        documents.push((version, document));
+    }
+    let (latest, _) = documents.last()?;
+    let latest = (*latest).clone();
+
+    let contents = summary.contents(ident, &documents);
+    let path = summary.path().to_owned();
+    let problem = match check_file(env.repo_root.join(&path), contents) {
+        Ok(CheckStatus::Fresh) => return None,
+        Ok(CheckStatus::Stale(check_stale)) => Problem::ExtraFileStale {
+            api_ident: ident.clone(),
+            path,
+            check_stale,
+        },
+        Err(source) => Problem::GeneratedValidationError {
+            api_ident: ident.clone(),
+            version: latest.clone(),
+            source: source.context(format!("checking {}", path)),
+        },
+    };
+    Some((latest, problem))
 }
 
-impl ApiResolved<'_> {
-    fn has_unfixable_problems(&self) -> bool {
-        self.symlink.as_ref().is_some_and(|f| !f.is_fixable())
-            || self.by_version.values().any(|r| r.has_errors())
-    }
+/// If a freeze on `api` is in effect, and the API's latest document is being
+/// changed, returns a problem (associated with the latest version) naming the
+/// freeze.
+fn resolve_freeze<'a>(
+    env: &ResolvedEnv,
+    api: &ManagedApi,
+    blessed: &BlessedFiles,
+    api_resolved: &ApiResolved<'a>,
+) -> Option<(semver::Version, Problem<'a>)> {
+    let today = chrono::Utc::now().date_naive();
+    let freeze = api
+        .freezes()
+        .iter()
+        .find(|freeze| freeze.is_active(&env.repo_root, today))?;
+    let (latest, resolution) = api_resolved.by_version.last_key_value()?;
+
+    let change = if api.is_lockstep() {
+        // Lockstep documents have no blessed version, so any change is one
+        // that would be written out.
+        resolution
+            .problems()
+            .any(|p| {
+                matches!(
+                    p,
+                    Problem::LockstepMissingLocal { .. }
+                        | Problem::LockstepStale { .. }
+                )
+            })
+            .then(|| "it's being changed".to_owned())?
+    } else {
+        let latest_blessed = blessed
+            .get(api.ident())
+            .and_then(|files| files.versions().keys().next_back());
+        match latest_blessed {
+            Some(blessed) if blessed > latest => {
+                format!("version {} is being removed", blessed)
+            }
+            _ if resolution.kind() == ResolutionKind::NewLocally => {
+                format!("version {} is being added", latest)
+            }
+            _ if resolution.problems().any(|p| {
+                matches!(
+                    p,
+                    Problem::BlessedLatestVersionBytewiseMismatch { .. }
+                )
+            }) =>
+            {
+                format!("version {} is being changed", latest)
+            }
+            _ => return None,
+        }
+    };
+
+    Some((
+        latest.clone(),
+        Problem::LatestDocumentFrozen {
+            api_ident: api.ident().clone(),
+            freeze: freeze.clone(),
+            change,
+        },
+    ))
 }
 
 fn resolve_removed_blessed_versions<'a>(
@@ -1340,7 +2788,7 @@ fn resolve_orphaned_local_specs<'a>(
 fn resolve_api<'a>(
     env: &'a ResolvedEnv,
     api: &'a ManagedApi,
-    validation: Option<&DynValidationFn>,
+    validations: &[&DynValidationFn],
     use_git_stub_storage: bool,
     all_blessed: &'a BlessedFiles,
     api_blessed: Option<&'a ApiFiles<BlessedApiSpecFile>>,
@@ -1352,7 +2800,7 @@ fn resolve_api<'a>(
             resolve_api_lockstep(
                 env,
                 api,
-                validation,
+                validations,
                 api_generated,
                 api_local,
             ),
@@ -1405,12 +2853,24 @@ fn resolve_api<'a>(
         let versions: Vec<_> = api.iter_versions_semver().collect();
         let mut by_version: BTreeMap<_, _> = versions
             .par_iter()
-            .map(|&version| {
+            .enumerate()
+            .map(|(index, &version)| {
                 let is_latest = version == latest_version;
                 let version = version.clone();
                 let blessed =
                     api_blessed.and_then(|b| b.versions().get(&version));
                 let is_blessed = Some(blessed.is_some());
+
+                // Apply the API's Git stub policy to this version.
+                let recency = versions.len() - 1 - index;
+                let use_git_stub_storage = use_git_stub_storage
+                    && blessed.is_none_or(|b| {
+                        api.git_stub_policy().is_eligible(
+                            &version,
+                            recency,
+                            b.contents().len(),
+                        )
+                    });
                 let Some(generated) = api_generated.versions().get(&version)
                 else {
                     // This version is missing from the generated source
@@ -1441,7 +2901,7 @@ fn resolve_api<'a>(
                 let resolution = resolve_api_version(
                     env,
                     api,
-                    validation,
+                    validations,
                     use_git_stub_storage,
                     ApiVersion { version: &version, is_latest, is_blessed },
                     blessed,
@@ -1473,7 +2933,14 @@ fn resolve_api<'a>(
             // --generated-from-dir didn't include the latest version).
             // The per-version problems above already capture the missing
             // versions, so skip the symlink check.
-            return ApiResolved { by_version, symlink: None };
+            return ApiResolved {
+                by_version,
+                symlink: None,
+                aliases: Vec::new(),
+                combined: None,
+                shared_schemas: None,
+                json_format: None,
+            };
         };
         let generated_version = latest_generated.version();
         let resolution =
@@ -1486,6 +2953,90 @@ fn resolve_api<'a>(
             });
 
         let latest_local = api_local.and_then(|l| l.latest_link());
+
+        // As in case 3 below, a blessed version is pointed to by its blessed
+        // file rather than the generated one, in case they differ in
+        // wire-compatible ways. (If they differ only in whitespace, the local
+        // document is reformatted to match the generated one, so that's what's
+        // pointed to.)
+        let expected_link = match resolution.kind() {
+            ResolutionKind::Lockstep => {
+                unreachable!("this is a versioned API");
+            }
+            ResolutionKind::Blessed => {
+                let blessed = api_blessed
+                    .and_then(|b| b.versions().get(generated_version))
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "for {} v{}, Blessed means generated_version \
+                             exists",
+                            api.ident(),
+                            generated_version
+                        )
+                    });
+                let generated = api_generated.versions().get(generated_version);
+                match generated {
+                    Some(generated)
+                        if !api.allows_trivial_changes_for_latest()
+                            && generated.contents() != blessed.contents()
+                            && same_tokens(
+                                generated.contents(),
+                                blessed.contents(),
+                            ) =>
+                    {
+                        latest_generated
+                    }
+                    _ => blessed.versioned_spec_file_name(),
+                }
+            }
+            ResolutionKind::NewLocally => latest_generated,
+        };
+
+        match api.latest_pointer() {
+            LatestPointer::Symlink => {}
+            LatestPointer::None => {
+                let symlink =
+                    latest_local.map(|found| Problem::LatestLinkUnwanted {
+                        api_ident: api.ident().clone(),
+                        found,
+                    });
+                return ApiResolved {
+                    by_version,
+                    symlink,
+                    aliases: Vec::new(),
+                    combined: None,
+                    shared_schemas: None,
+                    json_format: None,
+                };
+            }
+            LatestPointer::PointerFile => {
+                let path = env
+                    .openapi_abs_dir()
+                    .join(api.ident().as_str())
+                    .join(api.ident().versioned_api_latest_pointer_file());
+                // An unreadable pointer file is treated as stale, and
+                // overwritten.
+                let up_to_date = read_opt(&path).ok().flatten().as_deref()
+                    == Some(
+                        latest_pointer_file_contents(expected_link).as_slice(),
+                    );
+                let symlink =
+                    (!up_to_date || latest_local.is_some()).then(|| {
+                        Problem::LatestPointerFileStale {
+                            api_ident: api.ident().clone(),
+                            link: expected_link,
+                        }
+                    });
+                return ApiResolved {
+                    by_version,
+                    symlink,
+                    aliases: Vec::new(),
+                    combined: None,
+                    shared_schemas: None,
+                    json_format: None,
+                };
+            }
+        }
         let symlink = match latest_local {
             Some(latest_local) => {
                 if latest_local == latest_generated {
@@ -1620,16 +3171,261 @@ fn resolve_api<'a>(
             }
         };
 
+        // A symlink to a missing or unparseable document would otherwise look
+        // up to date whenever it has the right name.
+        let symlink = symlink.or_else(|| {
+            let found = latest_local?;
+            api_local.is_some_and(|l| l.latest_link_is_dangling()).then(|| {
+                Problem::LatestLinkDangling {
+                    api_ident: api.ident().clone(),
+                    found,
+                    link: expected_link,
+                }
+            })
+        });
+
         (by_version, symlink)
     };
 
-    ApiResolved { by_version, symlink }
+    ApiResolved {
+        by_version,
+        symlink,
+        aliases: Vec::new(),
+        combined: None,
+        shared_schemas: None,
+        json_format: None,
+    }
+}
+
+/// Checks the signatures of every version of every versioned API, adding
+/// problems for missing and invalid ones.
+///
+/// Blessed versions are checked against their blessed contents, and other
+/// versions against their generated contents.
+/// Removes the compatibility issues with blessed versions that are approved in
+/// `approvals`, and returns a problem for each approval that doesn't match an
+/// issue.
+fn resolve_breaking_change_approvals<'a>(
+    approvals: &'a BreakingChangeApprovals,
+    local: &'a LocalFiles,
+    api_results: &mut BTreeMap<ApiIdent, ApiResolved<'a>>,
+) -> Vec<(ApiIdent, Option<semver::Version>, Problem<'a>)> {
+    let entries = local.breaking_change_approvals();
+    let mut matched = vec![false; entries.len()];
+    for (ident, api_resolved) in api_results.iter_mut() {
+        for (version, resolution) in api_resolved.by_version.iter_mut() {
+            let mut for_version = entries
+                .iter()
+                .zip(matched.iter_mut())
+                .filter(|(a, _)| a.api() == ident && a.version() == version)
+                .collect::<Vec<_>>();
+            for problem in &mut resolution.problems {
+                match problem {
+                    Problem::BlessedVersionBroken { compatibility_issues } => {
+                        compatibility_issues.retain(|issue| {
+                            let hash = issue.approval_hash();
+                            let mut approved = false;
+                            for (approval, matched) in &mut for_version {
+                                if approval.issue() == hash {
+                                    **matched = true;
+                                    approved = true;
+                                }
+                            }
+                            !approved
+                        });
+                    }
+                    // If the documents couldn't be compared, whether an
+                    // approval is still needed isn't known.
+                    Problem::BlessedVersionCompareError { .. }
+                    | Problem::GeneratedSourceMissing { .. }
+                    | Problem::GenerationPanicked { .. } => {
+                        for (_, matched) in &mut for_version {
+                            **matched = true;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            resolution.problems.retain(|problem| {
+                !matches!(
+                    problem,
+                    Problem::BlessedVersionBroken { compatibility_issues }
+                        if compatibility_issues.is_empty()
+                )
+            });
+        }
+    }
+
+    entries
+        .iter()
+        .zip(matched)
+        .filter(|(_, matched)| !matched)
+        .map(|(approval, _)| {
+            (
+                approval.api().clone(),
+                Some(approval.version().clone()),
+                Problem::BreakingChangeApprovalStale { approvals, approval },
+            )
+        })
+        .collect()
+}
+
+/// Returns a problem for each consumer pin of a version that's no longer
+/// supported.
+fn resolve_consumer_pins<'a>(
+    apis: &ManagedApis,
+    local: &'a LocalFiles,
+) -> Vec<(ApiIdent, Option<semver::Version>, Problem<'a>)> {
+    local
+        .consumer_pins()
+        .iter()
+        .filter(|pin| {
+            apis.api(&pin.api).is_some_and(|api| {
+                !api.iter_versions_semver().any(|v| *v == pin.version)
+            })
+        })
+        .map(|pin| {
+            (
+                pin.api.clone(),
+                Some(pin.version.clone()),
+                Problem::ConsumerPinUnsupported { pin },
+            )
+        })
+        .collect()
+}
+
+/// Returns a note for each consumer pin of a supported version that's more
+/// than the configured number of versions behind the latest version.
+fn resolve_consumer_pin_lag(
+    apis: &ManagedApis,
+    local: &LocalFiles,
+) -> Vec<Note> {
+    let Some(max_lag) = apis.consumer_pins().and_then(|pins| pins.max_lag())
+    else {
+        return Vec::new();
+    };
+    let mut notes = Vec::new();
+    for pin in local.consumer_pins() {
+        let Some(api) = apis.api(&pin.api) else {
+            continue;
+        };
+        let versions: Vec<_> = api.iter_versions_semver().collect();
+        let Some(latest) = versions.last() else {
+            continue;
+        };
+        if !versions.contains(&&pin.version) {
+            // Unsupported pins are reported as problems instead.
+            continue;
+        }
+        let behind = versions.iter().filter(|v| ***v > pin.version).count();
+        if behind > max_lag {
+            notes.push(Note::ConsumerPinLagging {
+                api_ident: pin.api.clone(),
+                version: pin.version.clone(),
+                path: pin.path.clone(),
+                latest: (*latest).clone(),
+                behind,
+                max_lag,
+            });
+        }
+    }
+    notes
+}
+
+fn resolve_signatures<'a>(
+    env: &'a ResolvedEnv,
+    apis: &'a ManagedApis,
+    signing: &'a DocumentSigning,
+    blessed: &'a BlessedFiles,
+    generated: &'a GeneratedFiles,
+    api_results: &mut BTreeMap<ApiIdent, ApiResolved<'a>>,
+) {
+    for (ident, api_resolved) in api_results.iter_mut() {
+        if apis.api(ident).is_none_or(|api| api.is_lockstep()) {
+            continue;
+        }
+        let api_blessed = blessed.get(ident);
+        let api_generated = generated.get(ident);
+        for (version, resolution) in api_resolved.by_version.iter_mut() {
+            if let Some(blessed) =
+                api_blessed.and_then(|b| b.versions().get(version))
+            {
+                let file_name = blessed.versioned_spec_file_name();
+                let path = signing.signature_path(ident, file_name);
+                match signing.verify(
+                    &env.repo_root,
+                    ident,
+                    file_name,
+                    blessed.contents(),
+                ) {
+                    SignatureStatus::Valid => {}
+                    SignatureStatus::Missing => resolution.add_problem(
+                        Problem::DocumentSignatureMissing {
+                            signing,
+                            api_ident: ident.clone(),
+                            file_name,
+                            contents: blessed.contents(),
+                            path,
+                        },
+                    ),
+                    SignatureStatus::Invalid(message) => resolution
+                        .add_problem(Problem::BlessedSignatureInvalid {
+                            file_name,
+                            path,
+                            message,
+                        }),
+                }
+                continue;
+            }
+
+            // Versions missing from the generated source are reported
+            // separately.
+            let Some((generated, file_name)) = api_generated
+                .and_then(|g| g.versions().get(version))
+                .and_then(|g| Some((g, g.spec_file_name().as_versioned()?)))
+            else {
+                continue;
+            };
+            let path = signing.signature_path(ident, file_name);
+            let problem = match signing.verify(
+                &env.repo_root,
+                ident,
+                file_name,
+                generated.contents(),
+            ) {
+                SignatureStatus::Valid => continue,
+                SignatureStatus::Missing => Problem::DocumentSignatureMissing {
+                    signing,
+                    api_ident: ident.clone(),
+                    file_name,
+                    contents: generated.contents(),
+                    path,
+                },
+                SignatureStatus::Invalid(message) => {
+                    Problem::DocumentSignatureInvalid {
+                        signing,
+                        api_ident: ident.clone(),
+                        file_name,
+                        contents: generated.contents(),
+                        path,
+                        message,
+                    }
+                }
+            };
+            resolution.add_problem(problem);
+        }
+    }
+}
+
+/// Returns the contents of a "latest" pointer file pointing to `link`.
+fn latest_pointer_file_contents(link: &VersionedApiSpecFileName) -> Vec<u8> {
+    format!("{}\n", link.json_basename()).into_bytes()
 }
 
 fn resolve_api_lockstep<'a>(
     env: &'a ResolvedEnv,
     api: &'a ManagedApi,
-    validation: Option<&DynValidationFn>,
+    validations: &[&DynValidationFn],
     api_generated: &'a ApiFiles<GeneratedApiSpecFile>,
     api_local: Option<&'a ApiFiles<Vec<LocalApiSpecFile>>>,
 ) -> BTreeMap<semver::Version, Resolution<'a>> {
@@ -1642,7 +3438,17 @@ fn resolve_api_lockstep<'a>(
         })
         .unwrap();
 
-    let Some(generated) = api_generated.versions().get(version) else {
+    // With a version policy, the generated document's version was picked
+    // based on the local document (see
+    // `GeneratedFiles::apply_lockstep_version_policies`), so it may differ
+    // from the configured one. Either way, there's a single document.
+    let has_policy = api.lockstep_version_policy().is_some();
+    let generated = if has_policy {
+        api_generated.versions().values().next()
+    } else {
+        api_generated.versions().get(version)
+    };
+    let Some(generated) = generated else {
         // Missing from the generated source (e.g. --generated-from-dir
         // didn't include this API's document).
         return BTreeMap::from([(
@@ -1655,7 +3461,13 @@ fn resolve_api_lockstep<'a>(
 
     // We may or may not have found a local OpenAPI document for this API.
     let local = api_local
-        .and_then(|by_version| by_version.versions().get(version))
+        .and_then(|by_version| {
+            if has_policy {
+                by_version.versions().values().next()
+            } else {
+                by_version.versions().get(version)
+            }
+        })
         .and_then(|list| match &list.as_slice() {
             &[first] => Some(first),
             &[] => None,
@@ -1678,9 +3490,9 @@ fn resolve_api_lockstep<'a>(
     validate_generated(
         env,
         api,
-        validation,
+        validations,
         ApiVersion {
-            version,
+            version: generated.version(),
             is_latest: true, // is_latest is always true for lockstep APIs
             is_blessed: None,
         },
@@ -1709,7 +3521,7 @@ struct ApiVersion<'a> {
 fn resolve_api_version<'a>(
     env: &'_ ResolvedEnv,
     api: &'_ ManagedApi,
-    validation: Option<&DynValidationFn>,
+    validations: &[&DynValidationFn],
     use_git_stub_storage: bool,
     version: ApiVersion<'_>,
     blessed: Option<&'a BlessedApiSpecFile>,
@@ -1723,7 +3535,7 @@ fn resolve_api_version<'a>(
         Some(blessed) => resolve_api_version_blessed(
             env,
             api,
-            validation,
+            validations,
             use_git_stub_storage,
             version,
             blessed,
@@ -1734,7 +3546,12 @@ fn resolve_api_version<'a>(
             merge_base,
         ),
         None => resolve_api_version_local(
-            env, api, validation, version, generated, local,
+            env,
+            api,
+            validations,
+            version,
+            generated,
+            local,
         ),
     }
 }
@@ -1743,7 +3560,7 @@ fn resolve_api_version<'a>(
 fn resolve_api_version_blessed<'a>(
     env: &'_ ResolvedEnv,
     api: &'_ ManagedApi,
-    validation: Option<&DynValidationFn>,
+    validations: &[&DynValidationFn],
     use_git_stub_storage: bool,
     version: ApiVersion<'_>,
     blessed: &'a BlessedApiSpecFile,
@@ -1764,13 +3581,31 @@ fn resolve_api_version_blessed<'a>(
     // the API version is blessed, the user might still want to generate extra
     // files for that version. So we validate unconditionally, but let the user
     // know via `is_blessed`, letting them skip validation where appropriate.
-    validate_generated(env, api, validation, version, generated, &mut problems);
+    validate_generated(
+        env,
+        api,
+        validations,
+        version,
+        generated,
+        &mut problems,
+    );
+
+    // Unstable operations are excluded from comparisons with the blessed
+    // document.
+    let unstable = api.unstable_operations();
+    let stable_blessed = without_unstable_operations(blessed.value(), unstable);
+    let stable_generated =
+        without_unstable_operations(generated.value(), unstable);
+    let any_unstable = stable_blessed.is_some() || stable_generated.is_some();
+    let stable_blessed = stable_blessed.as_ref().unwrap_or(blessed.value());
+    let stable_generated =
+        stable_generated.as_ref().unwrap_or(generated.value());
 
     // First off, the blessed spec must be a subset of the generated one.
     // If not, someone has made an incompatible change to the API
     // *implementation*, such that the implementation no longer faithfully
     // implements this older, supported version.
-    match api_compatible(blessed.value(), generated.value()) {
+    match api_compatible(stable_blessed, stable_generated) {
         Ok(issues) => {
             if !issues.is_empty() {
                 problems.push(Problem::BlessedVersionBroken {
@@ -1783,6 +3618,37 @@ fn resolve_api_version_blessed<'a>(
         }
     };
 
+    // Some APIs also require the reverse: that the generated document is a
+    // subset of the blessed one, so that clients built against the current
+    // code can still talk to servers running the blessed version (e.g. during
+    // staged rollouts).
+    if api.checks_reverse_compatibility() {
+        match api_compatible(stable_generated, stable_blessed) {
+            Ok(issues) => {
+                if !issues.is_empty() {
+                    problems.push(Problem::BlessedVersionReverseBroken {
+                        compatibility_issues: issues,
+                    });
+                }
+            }
+            Err(error) => {
+                problems.push(Problem::BlessedVersionCompareError { error })
+            }
+        }
+    }
+
+    // If the only differences from the blessed document are in unstable
+    // operations, the generated document replaces the blessed one: local
+    // documents are checked against it, as with versions that aren't blessed.
+    if any_unstable
+        && problems.is_empty()
+        && generated.contents() != blessed.contents()
+        && stable_blessed == stable_generated
+    {
+        resolve_local_against_generated(env, generated, local, &mut problems);
+        return Resolution::new_blessed(problems);
+    }
+
     // For the latest version, also require bytewise equality. This ensures that
     // trivial changes don't accumulate invisibly. If the generated spec is
     // semantically equivalent but bytewise different, require a version bump.
@@ -1793,6 +3659,12 @@ fn resolve_api_version_blessed<'a>(
         && problems.is_empty()
         && generated.contents() != blessed.contents()
     {
+        // If the documents differ only in whitespace, the generated document
+        // replaces the blessed one without a version bump, as above.
+        if same_tokens(generated.contents(), blessed.contents()) {
+            resolve_local_reformatted(env, generated, local, &mut problems);
+            return Resolution::new_blessed(problems);
+        }
         problems.push(Problem::BlessedLatestVersionBytewiseMismatch {
             blessed,
             generated,
@@ -1811,6 +3683,17 @@ fn resolve_api_version_blessed<'a>(
         .hash()
         .expect("this should be a versioned file so it should have a hash");
 
+    let mut candidates = vec![CollisionCandidate {
+        description: format!(
+            "blessed document {}",
+            blessed.spec_file_name().path()
+        ),
+        name: blessed.versioned_spec_file_name(),
+        contents: blessed.contents(),
+    }];
+    candidates.extend(local_collision_candidates(env, local));
+    detect_hash_collisions(&candidates, &mut problems);
+
     let mut matching = Vec::new();
     let mut corrupted = Vec::new();
     let mut non_matching = Vec::new();
@@ -1832,15 +3715,10 @@ fn resolve_api_version_blessed<'a>(
                 non_matching.push(local_file);
             }
         } else {
-            // For valid files, verify that hash matching implies content
-            // matching (and vice versa).
-            let contents_match = local_file.contents() == blessed.contents();
-            assert_eq!(
-                hashes_match, contents_match,
-                "hash and contents should match for valid files"
-            );
-
-            if hashes_match {
+            // For valid files, matching hashes should imply matching
+            // contents. (Contents that differ but have the same hash are a
+            // collision, reported by `detect_hash_collisions` above.)
+            if hashes_match && local_file.contents() == blessed.contents() {
                 matching.push(local_file);
             } else {
                 non_matching.push(local_file);
@@ -2066,7 +3944,7 @@ fn resolve_api_version_blessed<'a>(
 fn resolve_api_version_local<'a>(
     env: &'_ ResolvedEnv,
     api: &'_ ManagedApi,
-    validation: Option<&DynValidationFn>,
+    validations: &[&DynValidationFn],
     version: ApiVersion<'_>,
     generated: &'a GeneratedApiSpecFile,
     local: &'a [LocalApiSpecFile],
@@ -2074,12 +3952,42 @@ fn resolve_api_version_local<'a>(
     let mut problems = Vec::new();
 
     // Validate the generated API document.
-    validate_generated(env, api, validation, version, generated, &mut problems);
+    validate_generated(
+        env,
+        api,
+        validations,
+        version,
+        generated,
+        &mut problems,
+    );
+    resolve_local_against_generated(env, generated, local, &mut problems);
+
+    Resolution::new_new_locally(problems)
+}
 
+/// Checks that exactly one of the `local` documents matches the `generated`
+/// one, reporting problems otherwise.
+fn resolve_local_against_generated<'a>(
+    env: &ResolvedEnv,
+    generated: &'a GeneratedApiSpecFile,
+    local: &'a [LocalApiSpecFile],
+    problems: &mut Vec<Problem<'a>>,
+) {
     let (matching, non_matching): (Vec<_>, Vec<_>) = local
         .iter()
         .partition(|local| local.contents() == generated.contents());
 
+    let mut candidates = vec![CollisionCandidate {
+        description: "document generated from the current code".to_owned(),
+        name: generated
+            .spec_file_name()
+            .as_versioned()
+            .expect("generated spec in versioned API is versioned"),
+        contents: generated.contents(),
+    }];
+    candidates.extend(local_collision_candidates(env, local));
+    detect_hash_collisions(&candidates, problems);
+
     if matching.is_empty() {
         // There was no matching spec.
         if non_matching.is_empty() {
@@ -2108,14 +4016,98 @@ fn resolve_api_version_local<'a>(
         );
         problems.push(Problem::LocalVersionExtra { spec_file_names });
     }
+}
 
-    Resolution::new_new_locally(problems)
+/// Checks local documents for the latest version, whose generated document
+/// differs from the blessed one only in whitespace.
+///
+/// Local documents that differ from the generated one only in whitespace, too,
+/// just need to be reformatted. Anything else is checked as for versions that
+/// aren't blessed.
+fn resolve_local_reformatted<'a>(
+    env: &ResolvedEnv,
+    generated: &'a GeneratedApiSpecFile,
+    local: &'a [LocalApiSpecFile],
+    problems: &mut Vec<Problem<'a>>,
+) {
+    let reformatted = !local.is_empty()
+        && local.iter().all(|local_file| {
+            !local_file.is_unparseable()
+                && local_file.contents() != generated.contents()
+                && same_tokens(local_file.contents(), generated.contents())
+        });
+    if reformatted {
+        problems.push(Problem::BlessedLatestVersionReformatted {
+            spec_files: local.iter().collect(),
+            generated,
+        });
+    } else {
+        resolve_local_against_generated(env, generated, local, problems);
+    }
+}
+
+/// A document for a single API version, considered by
+/// `detect_hash_collisions`.
+struct CollisionCandidate<'a> {
+    /// Describes where the document came from, including its path.
+    description: String,
+    name: &'a VersionedApiSpecFileName,
+    contents: &'a [u8],
+}
+
+/// Returns collision candidates for the parseable documents in `local`.
+///
+/// Unparseable documents are skipped, since they're reported (and fixed)
+/// separately.
+fn local_collision_candidates<'a>(
+    env: &ResolvedEnv,
+    local: &'a [LocalApiSpecFile],
+) -> impl Iterator<Item = CollisionCandidate<'a>> {
+    local.iter().filter(|local_file| !local_file.is_unparseable()).map(
+        |local_file| CollisionCandidate {
+            description: format!(
+                "local document {}",
+                env.openapi_rel_dir().join(local_file.spec_file_name().path())
+            ),
+            name: local_file
+                .spec_file_name()
+                .as_versioned()
+                .expect("local specs in versioned API are versioned"),
+            contents: local_file.contents(),
+        },
+    )
+}
+
+/// Reports a `FileNameHashCollision` for each pair of documents that have the
+/// same hash in their file names, but different contents.
+///
+/// All candidates are for the same version. The storage kind is ignored: a
+/// JSON file and a Git stub with the same hash must describe the same
+/// document, so without this check, one of them would be silently treated as
+/// redundant.
+fn detect_hash_collisions(
+    candidates: &[CollisionCandidate<'_>],
+    problems: &mut Vec<Problem<'_>>,
+) {
+    for (i, first) in candidates.iter().enumerate() {
+        for second in &candidates[i + 1..] {
+            if first.name.hash() == second.name.hash()
+                && first.contents != second.contents
+            {
+                problems.push(Problem::FileNameHashCollision {
+                    spec_file_name: first.name.clone(),
+                    first: first.description.clone(),
+                    second: second.description.clone(),
+                });
+            }
+        }
+    }
 }
 
 fn validate_generated(
     env: &ResolvedEnv,
     api: &ManagedApi,
-    validation: Option<&DynValidationFn>,
+    validations: &[&DynValidationFn],
     version: ApiVersion<'_>,
     generated: &GeneratedApiSpecFile,
     problems: &mut Vec<Problem<'_>>,
@@ -2125,7 +4117,7 @@ fn validate_generated(
         api,
         version.is_latest,
         version.is_blessed,
-        validation,
+        validations,
         generated,
     ) {
         Err(source) => {
@@ -2135,8 +4127,8 @@ fn validate_generated(
                 source,
             });
         }
-        Ok(extra_files) => {
-            for (path, status) in extra_files {
+        Ok(outcome) => {
+            for (path, status) in outcome.extra_files {
                 match status {
                     CheckStatus::Fresh => (),
                     CheckStatus::Stale(check_stale) => {
@@ -2148,6 +4140,12 @@ fn validate_generated(
                     }
                 }
             }
+            for fix in outcome.custom_fixes {
+                problems.push(Problem::ValidationFixable {
+                    api_ident: api.ident().clone(),
+                    fix,
+                });
+            }
         }
     }
 }
@@ -2218,6 +4216,7 @@ fn storage_format_for_blessed(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use dropshot_api_manager_types::LockstepApiSpecFileName;
 
     #[test]
     fn test_displayable_vec() {
@@ -2276,6 +4275,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_execute_read_only() {
+        let dir = camino_tempfile::tempdir().unwrap();
+        let env = crate::Environment::new_for_test(
+            "cargo openapi",
+            dir.path(),
+            "openapi",
+        )
+        .unwrap()
+        .resolve(None)
+        .unwrap();
+        let file_name = ApiSpecFileName::from(LockstepApiSpecFileName::new(
+            ApiIdent::from("api".to_owned()),
+        ));
+        let path = env.openapi_abs_dir().join(file_name.path());
+        fs_err::create_dir_all(env.openapi_abs_dir()).unwrap();
+        let _ = overwrite_file(&path, b"{}").unwrap();
+        let fix = Fix::DeleteFiles { files: DisplayableVec(vec![file_name]) };
+
+        // The fix is refused before anything is written, regardless of
+        // whether the documents directory is actually writable.
+        let error = fix.execute(&env.to_read_only()).expect_err("read-only");
+        assert!(
+            error
+                .to_string()
+                .starts_with("refusing to delete 1 file: api.json"),
+            "unexpected error: {error:#}"
+        );
+        assert!(path.exists(), "file was not deleted");
+
+        // The same fix applies in a writable environment.
+        fix.execute(&env).unwrap();
+        assert!(!path.exists(), "file was deleted");
+    }
+
     // Test commit hashes.
     const COMMIT_A: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
     const COMMIT_B: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
@@ -0,0 +1,533 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Stores the schemas of versioned documents in a content-addressed directory
+//! shared between versions.
+//!
+//! See [`ManagedApi::with_shared_schemas`] for more.
+//!
+//! Documents are split and reassembled textually rather than by parsing them,
+//! so that a reassembled document is byte-for-byte identical to the original.
+//! This relies on documents being pretty-printed with two-space indentation,
+//! as Dropshot does: each schema under `components.schemas` is then a run of
+//! lines indented by at least six spaces. Documents formatted differently are
+//! stored as they are.
+//!
+//! [`ManagedApi::with_shared_schemas`]: crate::ManagedApi::with_shared_schemas
+
+use crate::{
+    apis::ManagedApi,
+    spec_files_local::IgnoreGlobs,
+    validation::{overwrite_file, read_opt},
+    vcs::RepoVcs,
+};
+use anyhow::{Context, anyhow, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use git_stub::GitStub;
+use sha2::{Digest, Sha256};
+use std::{borrow::Cow, collections::BTreeMap, io};
+
+/// The name of the directory, within a versioned API's documents directory,
+/// in which shared schemas are stored.
+pub(crate) const SHARED_SCHEMAS_DIR: &str = "shared-schemas";
+
+/// The line starting the `components` object of a document.
+const COMPONENTS_START: &str = "  \"components\": {";
+/// The line starting the `components.schemas` object of a document.
+const SCHEMAS_START: &str = "    \"schemas\": {";
+/// The indentation of each entry of `components.schemas`.
+const ENTRY_INDENT: &str = "      ";
+
+/// A document split into the contents of its file and the schemas that it
+/// refers to.
+#[derive(Debug)]
+pub(crate) struct SplitDocument {
+    /// The document, with each schema replaced by a reference to a file in
+    /// the shared directory.
+    pub(crate) contents: Vec<u8>,
+    /// The schemas referred to, keyed by file name within the shared
+    /// directory.
+    pub(crate) schemas: BTreeMap<String, Vec<u8>>,
+}
+
+/// Splits the schemas out of the document `contents`.
+///
+/// Schemas that are already shared, or that are written on a single line
+/// (like `{}`), are left alone.
+pub(crate) fn split(contents: &[u8]) -> SplitDocument {
+    let mut schemas = BTreeMap::new();
+    let Ok(text) = std::str::from_utf8(contents) else {
+        return SplitDocument { contents: contents.to_vec(), schemas };
+    };
+    let lines: Vec<&str> = text.split('\n').collect();
+
+    let mut out: Vec<Cow<'_, str>> = Vec::with_capacity(lines.len());
+    let mut next = 0;
+    for entry in schema_entries(&lines) {
+        let body = &lines[entry.start + 1..entry.end];
+        if shared_schema_name(body).is_some() {
+            continue;
+        }
+        let Some(schema) = schema_file(body) else {
+            continue;
+        };
+        let name = format!("{}.json", hex::encode(Sha256::digest(&schema)));
+        out.extend(lines[next..=entry.start].iter().copied().map(Cow::from));
+        out.push(Cow::from(reference_line(&name)));
+        next = entry.end;
+        schemas.insert(name, schema);
+    }
+    out.extend(lines[next..].iter().copied().map(Cow::from));
+
+    SplitDocument { contents: out.join("\n").into_bytes(), schemas }
+}
+
+/// Reassembles the document stored as `contents`, reading the shared schemas
+/// it refers to with `read_schema`.
+///
+/// `read_schema` is passed the path to each schema relative to the document's
+/// directory. Documents that don't refer to any shared schemas are returned
+/// as they are. On error, the original contents are returned along with the
+/// error.
+pub(crate) fn join(
+    contents: Vec<u8>,
+    read_schema: impl Fn(&Utf8Path) -> anyhow::Result<Vec<u8>>,
+) -> Result<Vec<u8>, (anyhow::Error, Vec<u8>)> {
+    match join_impl(&contents, read_schema) {
+        Ok(Some(joined)) => Ok(joined),
+        Ok(None) => Ok(contents),
+        Err(error) => Err((error, contents)),
+    }
+}
+
+/// Reassembles the document `contents` referred to by `git_stub`, reading
+/// the shared schemas it refers to as of the Git stub's commit.
+pub(crate) fn join_git_stub_contents(
+    contents: Vec<u8>,
+    git_stub: &GitStub,
+    repo_root: &Utf8Path,
+    vcs: &RepoVcs,
+) -> anyhow::Result<Vec<u8>> {
+    let dir = git_stub.path().parent().unwrap_or(Utf8Path::new(""));
+    join(contents, |schema| {
+        vcs.show_file(repo_root, git_stub.commit(), &dir.join(schema))
+    })
+    .map_err(|(error, _)| error)
+}
+
+fn join_impl(
+    contents: &[u8],
+    read_schema: impl Fn(&Utf8Path) -> anyhow::Result<Vec<u8>>,
+) -> anyhow::Result<Option<Vec<u8>>> {
+    // Most documents don't refer to any shared schemas, so check for that
+    // before doing any more work.
+    let Ok(text) = std::str::from_utf8(contents) else {
+        return Ok(None);
+    };
+    if !text.contains(&format!("\"{}/", SHARED_SCHEMAS_DIR)) {
+        return Ok(None);
+    }
+    let lines: Vec<&str> = text.split('\n').collect();
+
+    let mut out: Vec<Cow<'_, str>> = Vec::with_capacity(lines.len());
+    let mut next = 0;
+    for entry in schema_entries(&lines) {
+        let Some(name) = shared_schema_name(&lines[entry.start + 1..entry.end])
+        else {
+            continue;
+        };
+        let path = Utf8Path::new(SHARED_SCHEMAS_DIR).join(name);
+        let schema = read_schema(&path)
+            .with_context(|| format!("reading shared schema {}", path))?;
+        let hash = hex::encode(Sha256::digest(&schema));
+        if name.strip_suffix(".json") != Some(hash.as_str()) {
+            bail!("shared schema {} doesn't match the hash in its name", path);
+        }
+        let body = std::str::from_utf8(&schema)
+            .ok()
+            .and_then(|schema| {
+                schema.strip_prefix("{\n")?.strip_suffix("\n}\n")
+            })
+            .ok_or_else(|| {
+                anyhow!("shared schema {} isn't formatted as expected", path)
+            })?;
+
+        out.extend(lines[next..=entry.start].iter().copied().map(Cow::from));
+        out.extend(
+            body.split('\n')
+                .map(|line| Cow::from(format!("{}{}", ENTRY_INDENT, line))),
+        );
+        next = entry.end;
+    }
+    out.extend(lines[next..].iter().copied().map(Cow::from));
+
+    Ok(Some(out.join("\n").into_bytes()))
+}
+
+/// An entry of `components.schemas` that spans several lines.
+struct SchemaEntry {
+    /// The index of the line with the schema's name and opening brace.
+    start: usize,
+    /// The index of the line with the schema's closing brace.
+    end: usize,
+}
+
+/// Returns the entries of `components.schemas` in the document `lines` that
+/// span several lines.
+fn schema_entries(lines: &[&str]) -> Vec<SchemaEntry> {
+    let Some(first) = schemas_start(lines) else {
+        return Vec::new();
+    };
+
+    let mut entries = Vec::new();
+    let mut index = first;
+    while let Some(line) = lines.get(index) {
+        if *line == "    }" || *line == "    }," {
+            break;
+        }
+        // Lines within an entry are indented further, so they never look
+        // like the start of an entry.
+        let is_start = line
+            .strip_prefix(ENTRY_INDENT)
+            .is_some_and(|rest| rest.starts_with('"') && rest.ends_with(": {"));
+        if !is_start {
+            index += 1;
+            continue;
+        }
+        let Some(len) = lines[index + 1..].iter().position(|line| {
+            line.strip_prefix(ENTRY_INDENT)
+                .is_some_and(|rest| rest == "}" || rest == "},")
+        }) else {
+            break;
+        };
+        let end = index + 1 + len;
+        entries.push(SchemaEntry { start: index, end });
+        index = end + 1;
+    }
+    entries
+}
+
+/// Returns the index of the first line within `components.schemas` in the
+/// document `lines`, if it has any schemas.
+fn schemas_start(lines: &[&str]) -> Option<usize> {
+    let components = lines.iter().position(|line| *line == COMPONENTS_START)?;
+    for (index, line) in lines.iter().enumerate().skip(components + 1) {
+        if *line == SCHEMAS_START {
+            return Some(index + 1);
+        }
+        if *line == "  }" || *line == "  }," {
+            break;
+        }
+    }
+    None
+}
+
+/// Returns the contents of the file storing the schema whose body (the lines
+/// between its braces) is `body`, or `None` if it's indented unexpectedly.
+fn schema_file(body: &[&str]) -> Option<Vec<u8>> {
+    let mut schema = String::from("{\n");
+    for line in body {
+        schema.push_str(line.strip_prefix(ENTRY_INDENT)?);
+        schema.push('\n');
+    }
+    schema.push_str("}\n");
+    Some(schema.into_bytes())
+}
+
+/// Returns the line referring to the shared schema `name`, within an entry of
+/// `components.schemas`.
+fn reference_line(name: &str) -> String {
+    format!("{}  \"$ref\": \"{}/{}\"", ENTRY_INDENT, SHARED_SCHEMAS_DIR, name)
+}
+
+/// If the schema whose body is `body` refers to a shared schema, returns the
+/// name of its file.
+fn shared_schema_name<'a>(body: &[&'a str]) -> Option<&'a str> {
+    let [line] = body else {
+        return None;
+    };
+    let name = line
+        .strip_prefix(ENTRY_INDENT)?
+        .strip_prefix("  \"$ref\": \"")?
+        .strip_suffix('"')?
+        .strip_prefix(SHARED_SCHEMAS_DIR)?
+        .strip_prefix('/')?;
+    let is_hash = name.strip_suffix(".json").is_some_and(|hash| {
+        hash.len() == 64
+            && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    });
+    is_hash.then_some(name)
+}
+
+/// Describes the differences between how an API's documents are stored and how
+/// they should be, given whether the API uses shared schemas.
+#[derive(Debug, Default)]
+pub(crate) struct SharedSchemasDiff {
+    /// Documents to rewrite, keyed by path relative to the OpenAPI documents
+    /// directory.
+    documents: BTreeMap<Utf8PathBuf, Vec<u8>>,
+    /// Shared schemas that are missing, or whose contents differ.
+    write: BTreeMap<Utf8PathBuf, Vec<u8>>,
+    /// Shared schemas that no document refers to.
+    remove: Vec<Utf8PathBuf>,
+}
+
+impl SharedSchemasDiff {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+            && self.write.is_empty()
+            && self.remove.is_empty()
+    }
+
+    /// Returns all of the paths that differ.
+    pub(crate) fn paths(&self) -> impl Iterator<Item = &Utf8PathBuf> + '_ {
+        self.documents.keys().chain(self.write.keys()).chain(&self.remove)
+    }
+}
+
+/// Compares how `api`'s documents are stored in the OpenAPI documents
+/// directory `dir` with how they should be.
+///
+/// For an API that doesn't use shared schemas, this only does any work if a
+/// shared directory is left over, in which case its schemas are inlined again.
+pub(crate) fn diff_shared_schemas(
+    dir: &Utf8Path,
+    ignore_globs: &IgnoreGlobs,
+    api: &ManagedApi,
+) -> anyhow::Result<SharedSchemasDiff> {
+    let mut diff = SharedSchemasDiff::default();
+    let api_dir = Utf8PathBuf::from(api.ident().as_str());
+    let shared_dir = api_dir.join(SHARED_SCHEMAS_DIR);
+    if !api.is_versioned()
+        || (!api.has_shared_schemas() && !dir.join(&shared_dir).exists())
+    {
+        return Ok(diff);
+    }
+
+    let mut wanted = BTreeMap::new();
+    for path in document_paths(dir, &api_dir, ignore_globs, api)? {
+        let contents = fs_err::read(dir.join(&path))?;
+        // Documents whose shared schemas can't be read are reported when
+        // documents are loaded, and rewritten in full by `generate`.
+        let Ok(document) = join(contents.clone(), |schema| {
+            Ok(fs_err::read(dir.join(&api_dir).join(schema))?)
+        }) else {
+            continue;
+        };
+        let stored = if api.has_shared_schemas() {
+            split(&document)
+        } else {
+            SplitDocument { contents: document, schemas: BTreeMap::new() }
+        };
+        if stored.contents != contents {
+            diff.documents.insert(path, stored.contents);
+        }
+        for (name, schema) in stored.schemas {
+            wanted.insert(shared_dir.join(name), schema);
+        }
+    }
+
+    for path in read_dir_files(dir, &shared_dir, ignore_globs)? {
+        if !wanted.contains_key(&path) {
+            diff.remove.push(path);
+        }
+    }
+    for (path, schema) in wanted {
+        if read_opt(&dir.join(&path))?.as_ref() != Some(&schema) {
+            diff.write.insert(path, schema);
+        }
+    }
+    Ok(diff)
+}
+
+/// Updates how `api`'s documents are stored in the OpenAPI documents directory
+/// `dir` to match how they should be.
+///
+/// Returns a description of each change made.
+pub(crate) fn sync_shared_schemas(
+    dir: &Utf8Path,
+    ignore_globs: &IgnoreGlobs,
+    api: &ManagedApi,
+) -> anyhow::Result<Vec<String>> {
+    let diff = diff_shared_schemas(dir, ignore_globs, api)?;
+
+    // Write schemas before the documents that refer to them, and remove
+    // schemas after the documents that referred to them.
+    let mut steps = Vec::new();
+    for (path, schema) in &diff.write {
+        let full_path = dir.join(path);
+        let _ = overwrite_file(&full_path, schema)?;
+        steps.push(format!("wrote {}", full_path));
+    }
+    for (path, contents) in &diff.documents {
+        let full_path = dir.join(path);
+        let _ = overwrite_file(&full_path, contents)?;
+        steps.push(format!("rewrote {}", full_path));
+    }
+    for path in &diff.remove {
+        let full_path = dir.join(path);
+        fs_err::remove_file(&full_path)?;
+        steps.push(format!("removed {}", full_path));
+    }
+
+    if !api.has_shared_schemas() {
+        let shared_dir =
+            dir.join(api.ident().as_str()).join(SHARED_SCHEMAS_DIR);
+        // This fails if anything else (e.g. an ignored file) is left in the
+        // directory, in which case it's left alone.
+        if fs_err::remove_dir(&shared_dir).is_ok() {
+            steps.push(format!("removed {}", shared_dir));
+        }
+    }
+    Ok(steps)
+}
+
+/// Returns the paths to the JSON documents in the API directory `api_dir`,
+/// relative to the OpenAPI documents directory `dir`.
+pub(crate) fn document_paths(
+    dir: &Utf8Path,
+    api_dir: &Utf8Path,
+    ignore_globs: &IgnoreGlobs,
+    api: &ManagedApi,
+) -> anyhow::Result<Vec<Utf8PathBuf>> {
+    let prefix = format!("{}-", api.ident());
+    let paths = read_dir_files(dir, api_dir, ignore_globs)?
+        .into_iter()
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default();
+            name.starts_with(&prefix)
+                && name.ends_with(".json")
+                && !api.is_unmanaged_file(name)
+        })
+        .collect();
+    Ok(paths)
+}
+
+/// Returns the paths to the regular files (not symlinks) in the `subdir`
+/// directory of `dir`, relative to `dir`. Files matching `ignore_globs` are
+/// skipped, and a missing directory is treated as empty.
+fn read_dir_files(
+    dir: &Utf8Path,
+    subdir: &Utf8Path,
+    ignore_globs: &IgnoreGlobs,
+) -> anyhow::Result<Vec<Utf8PathBuf>> {
+    let full_dir = dir.join(subdir);
+    let read_dir = match full_dir.read_dir_utf8() {
+        Ok(read_dir) => read_dir,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            return Ok(Vec::new());
+        }
+        Err(error) => {
+            return Err(
+                anyhow!(error).context(format!("readdir {:?}", full_dir))
+            );
+        }
+    };
+
+    let mut paths = Vec::new();
+    for entry in read_dir {
+        let entry =
+            entry.with_context(|| format!("readdir {:?} entry", full_dir))?;
+        let path = subdir.join(entry.file_name());
+        if ignore_globs.is_ignored(&path) {
+            continue;
+        }
+        let file_type = entry
+            .file_type()
+            .with_context(|| format!("file type of {:?}", entry.path()))?;
+        if file_type.is_file() {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r##"{
+  "openapi": "3.0.3",
+  "components": {
+    "schemas": {
+      "Empty": {},
+      "Widget": {
+        "type": "object",
+        "properties": {
+          "name": {
+            "type": "string"
+          }
+        }
+      },
+      "WidgetAlias": {
+        "$ref": "#/components/schemas/Widget"
+      }
+    },
+    "responses": {
+      "Error": {
+        "description": "Error"
+      }
+    }
+  }
+}
+"##;
+
+    #[test]
+    fn test_split_and_join() {
+        let document = split(DOCUMENT.as_bytes());
+        assert_eq!(document.schemas.len(), 2);
+        let stored = String::from_utf8(document.contents.clone()).unwrap();
+        assert!(stored.contains("      \"Empty\": {},\n"), "{stored}");
+        assert!(!stored.contains("\"type\": \"object\""), "{stored}");
+        // Responses aren't schemas, so they're left alone.
+        assert!(stored.contains("\"description\": \"Error\""), "{stored}");
+        let widget = document
+            .schemas
+            .values()
+            .find(|schema| schema.starts_with(b"{\n  \"type\""))
+            .expect("Widget schema was split out");
+        assert_eq!(
+            std::str::from_utf8(widget).unwrap(),
+            "{\n  \"type\": \"object\",\n  \"properties\": {\n    \"name\": \
+             {\n      \"type\": \"string\"\n    }\n  }\n}\n",
+        );
+
+        // Splitting a split document doesn't change it.
+        let again = split(&document.contents);
+        assert_eq!(again.contents, document.contents);
+        assert!(again.schemas.is_empty());
+
+        let read_schema = |path: &Utf8Path| {
+            let name = path.file_name().unwrap();
+            document
+                .schemas
+                .get(name)
+                .cloned()
+                .ok_or_else(|| anyhow!("no such schema"))
+        };
+        let joined = join(document.contents.clone(), read_schema).unwrap();
+        assert_eq!(std::str::from_utf8(&joined).unwrap(), DOCUMENT);
+
+        // A schema whose contents don't match its name is rejected.
+        let (error, contents) = join(document.contents.clone(), |_| {
+            Ok(b"{\n  \"type\": \"string\"\n}\n".to_vec())
+        })
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("doesn't match the hash"),
+            "{error:#}"
+        );
+        assert_eq!(contents, document.contents);
+    }
+
+    #[test]
+    fn test_unrecognized_format() {
+        // Documents that aren't pretty-printed are stored as they are.
+        let compact = br#"{"components":{"schemas":{"A":{"type":"string"}}}}"#;
+        let document = split(compact);
+        assert_eq!(document.contents, compact);
+        assert!(document.schemas.is_empty());
+        let joined = join(compact.to_vec(), |_| unreachable!()).unwrap();
+        assert_eq!(joined, compact);
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Detached signatures for OpenAPI documents.
+//!
+//! See [`DocumentSigning`] for more.
+
+use crate::validation::overwrite_file;
+use anyhow::{Context, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use dropshot_api_manager_types::{ApiIdent, VersionedApiSpecFileName};
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// The signature namespace used if none is configured.
+const DEFAULT_NAMESPACE: &str = "openapi-document";
+
+/// Signs versioned OpenAPI documents, and verifies their signatures, with
+/// `ssh-keygen -Y`.
+///
+/// Attach this to a set of APIs with [`ManagedApis::with_document_signing`].
+/// Every version of every versioned API then needs a detached signature, at
+/// `<signatures dir>/<ident>/<document file name>.sig`. Because document file
+/// names include a hash of their contents, each signature is tied to exactly
+/// one document.
+///
+/// `check` verifies signatures against the allowed signers file, which lists
+/// the keys trusted to sign documents (see the `ALLOWED SIGNERS` section of
+/// `ssh-keygen(1)`). `generate` signs documents that don't have a signature
+/// yet, using the configured private key. An invalid signature for a blessed
+/// document is never fixed automatically: it indicates that either the
+/// document or its signature was tampered with.
+///
+/// The `ssh-keygen` binary must be on the `PATH`.
+///
+/// [`ManagedApis::with_document_signing`]: crate::ManagedApis::with_document_signing
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DocumentSigning {
+    signatures_dir: Utf8PathBuf,
+    allowed_signers: Utf8PathBuf,
+    identity: String,
+    namespace: String,
+    private_key: Option<Utf8PathBuf>,
+}
+
+impl DocumentSigning {
+    /// Creates a new signing configuration.
+    ///
+    /// Signatures are stored under `signatures_dir`, and verified against the
+    /// keys listed for `identity` in `allowed_signers`. Both paths are
+    /// relative to the repository root.
+    pub fn new(
+        signatures_dir: impl Into<Utf8PathBuf>,
+        allowed_signers: impl Into<Utf8PathBuf>,
+        identity: impl Into<String>,
+    ) -> Self {
+        Self {
+            signatures_dir: signatures_dir.into(),
+            allowed_signers: allowed_signers.into(),
+            identity: identity.into(),
+            namespace: DEFAULT_NAMESPACE.to_owned(),
+            private_key: None,
+        }
+    }
+
+    /// Sets the signature namespace (default: `openapi-document`).
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    /// Sets the private key used to sign documents during `generate`.
+    ///
+    /// The path is relative to the repository root. Without a private key,
+    /// signatures are still verified, but missing ones can't be created.
+    pub fn with_private_key(mut self, path: impl Into<Utf8PathBuf>) -> Self {
+        self.private_key = Some(path.into());
+        self
+    }
+
+    /// Returns the directory signatures are stored in, relative to the
+    /// repository root.
+    pub fn signatures_dir(&self) -> &Utf8Path {
+        &self.signatures_dir
+    }
+
+    /// Returns the path to the allowed signers file, relative to the
+    /// repository root.
+    pub fn allowed_signers(&self) -> &Utf8Path {
+        &self.allowed_signers
+    }
+
+    /// Returns the identity that documents must be signed by.
+    pub fn identity(&self) -> &str {
+        &self.identity
+    }
+
+    /// Returns the signature namespace.
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    /// Returns the path to the private key, if one is configured.
+    pub fn private_key(&self) -> Option<&Utf8Path> {
+        self.private_key.as_deref()
+    }
+
+    /// Returns the path to the signature for a document, relative to the
+    /// repository root.
+    pub(crate) fn signature_path(
+        &self,
+        api_ident: &ApiIdent,
+        file_name: &VersionedApiSpecFileName,
+    ) -> Utf8PathBuf {
+        self.signatures_dir
+            .join(api_ident.to_string())
+            .join(format!("{}.sig", file_name.json_basename()))
+    }
+
+    /// Verifies the signature for a document with the given contents.
+    pub(crate) fn verify(
+        &self,
+        repo_root: &Utf8Path,
+        api_ident: &ApiIdent,
+        file_name: &VersionedApiSpecFileName,
+        contents: &[u8],
+    ) -> SignatureStatus {
+        let path = repo_root.join(self.signature_path(api_ident, file_name));
+        if !path.exists() {
+            return SignatureStatus::Missing;
+        }
+
+        let mut command = Command::new("ssh-keygen");
+        command
+            .arg("-Y")
+            .arg("verify")
+            .arg("-f")
+            .arg(repo_root.join(&self.allowed_signers))
+            .arg("-I")
+            .arg(&self.identity)
+            .arg("-n")
+            .arg(&self.namespace)
+            .arg("-s")
+            .arg(&path);
+        match run_with_input(command, contents) {
+            Ok(_) => SignatureStatus::Valid,
+            Err(error) => SignatureStatus::Invalid(format!("{error:#}")),
+        }
+    }
+
+    /// Signs a document with the given contents, writing its signature.
+    pub(crate) fn sign(
+        &self,
+        repo_root: &Utf8Path,
+        api_ident: &ApiIdent,
+        file_name: &VersionedApiSpecFileName,
+        contents: &[u8],
+    ) -> anyhow::Result<Utf8PathBuf> {
+        let Some(private_key) = &self.private_key else {
+            bail!(
+                "cannot sign {}: no private key is configured",
+                file_name.json_basename(),
+            );
+        };
+
+        let mut command = Command::new("ssh-keygen");
+        command
+            .arg("-Y")
+            .arg("sign")
+            .arg("-f")
+            .arg(repo_root.join(private_key))
+            .arg("-n")
+            .arg(&self.namespace);
+        let signature =
+            run_with_input(command, contents).with_context(|| {
+                format!("signing {}", file_name.json_basename())
+            })?;
+
+        let path = repo_root.join(self.signature_path(api_ident, file_name));
+        let _ = overwrite_file(&path, &signature)?;
+        Ok(path)
+    }
+}
+
+/// The result of verifying a document's signature.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum SignatureStatus {
+    Valid,
+    Missing,
+    Invalid(String),
+}
+
+/// Runs `command` with `input` on stdin, returning its stdout.
+fn run_with_input(
+    mut command: Command,
+    input: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to run ssh-keygen")?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(input)
+        .context("writing document to ssh-keygen")?;
+    let output = child.wait_with_output().context("waiting for ssh-keygen")?;
+    if !output.status.success() {
+        bail!(
+            "ssh-keygen failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim(),
+        );
+    }
+    Ok(output.stdout)
+}
@@ -0,0 +1,121 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Reporting what a run did to library users, as structured events.
+//!
+//! See [`OutputSink`] for more.
+
+use crate::{output::CheckResult, resolved::Resolved};
+use dropshot_api_manager_types::ApiIdent;
+use std::{fmt, sync::Arc};
+
+/// Receives structured events describing what `check` and `generate` do.
+///
+/// The API manager's own output is text written to standard error, meant for
+/// people. Tools that want to act on the results of a run, e.g. a bot that
+/// posts them to a chat channel, can attach a sink with
+/// [`Environment::with_output_sink`] rather than parsing that text. Sinks
+/// receive events in addition to, not instead of, the regular output.
+///
+/// Events are delivered synchronously, in the order they happen, from the
+/// thread running the command. A sink that does slow work, such as making
+/// network requests, should consider buffering events and acting on them once
+/// the run finishes (signalled by [`OutputEvent::CheckFinished`] or
+/// [`OutputEvent::GenerateFinished`]).
+///
+/// [`Environment::with_output_sink`]: crate::Environment::with_output_sink
+pub trait OutputSink: fmt::Debug + Send + Sync {
+    /// Called for each event.
+    fn event(&self, event: &OutputEvent<'_>);
+}
+
+/// An event reported to an [`OutputSink`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum OutputEvent<'a> {
+    /// `check` or `generate` found a problem.
+    ///
+    /// `generate` reports every problem it finds before it starts applying
+    /// fixes.
+    ProblemFound {
+        /// The API the problem is associated with.
+        api: &'a ApiIdent,
+        /// The version the problem is associated with, or `None` for problems
+        /// that aren't specific to a version, e.g. a stale "latest" symlink.
+        version: Option<&'a semver::Version>,
+        /// A description of the problem.
+        message: String,
+        /// Whether `generate` can fix the problem.
+        fixable: bool,
+    },
+
+    /// `generate` applied a fix.
+    FixApplied {
+        /// A description of the fix.
+        fix: String,
+        /// A description of each step taken.
+        steps: &'a [String],
+    },
+
+    /// `generate` failed to apply a fix.
+    FixFailed {
+        /// A description of the fix.
+        fix: String,
+        /// The error applying the fix.
+        error: &'a anyhow::Error,
+    },
+
+    /// `check` finished.
+    CheckFinished {
+        /// The result of the check.
+        result: CheckResult,
+    },
+
+    /// `generate` finished.
+    ///
+    /// This isn't reported if `generate` fails with an error, e.g. because a
+    /// document couldn't be generated.
+    GenerateFinished {
+        /// True if every document is now up-to-date.
+        success: bool,
+        /// The number of fixes applied.
+        updated: usize,
+        /// The number of documents that were already up-to-date.
+        unchanged: usize,
+        /// The number of fixes that failed.
+        failed: usize,
+    },
+}
+
+/// The sinks attached to an environment, in the order they were added.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct OutputSinks {
+    sinks: Vec<Arc<dyn OutputSink>>,
+}
+
+impl OutputSinks {
+    pub(crate) fn push(&mut self, sink: Arc<dyn OutputSink>) {
+        self.sinks.push(sink);
+    }
+
+    /// Reports `event` to each sink.
+    pub(crate) fn emit(&self, event: &OutputEvent<'_>) {
+        for sink in &self.sinks {
+            sink.event(event);
+        }
+    }
+
+    /// Reports each of the problems in `resolved`.
+    pub(crate) fn emit_problems(&self, resolved: &Resolved<'_>) {
+        if self.sinks.is_empty() {
+            return;
+        }
+        for (api, version, problem) in resolved.iter_all_problems() {
+            self.emit(&OutputEvent::ProblemFound {
+                api,
+                version,
+                message: problem.to_string(),
+                fixable: problem.is_fixable(),
+            });
+        }
+    }
+}
@@ -6,6 +6,8 @@
 use crate::{
     apis::ManagedApis,
     environment::ErrorAccumulator,
+    json_format::to_dropshot_format,
+    shared_schemas::{self, SHARED_SCHEMAS_DIR, join_git_stub_contents},
     spec_files_generic::{
         ApiFiles, ApiLoad, ApiSpecFile, ApiSpecFilesBuilder, AsRawFiles,
         GitStubKey, SpecFileInfo, parse_versioned_file_name,
@@ -20,7 +22,11 @@ use dropshot_api_manager_types::{
 };
 use git_stub::{GitCommitHash, GitStub};
 use rayon::prelude::*;
-use std::{collections::BTreeMap, ops::Deref};
+use std::{
+    collections::{BTreeMap, BTreeSet, btree_map::Entry},
+    fmt,
+    ops::Deref,
+};
 
 /// Newtype wrapper around [`ApiSpecFile`] to describe OpenAPI documents from
 /// the "blessed" source.
@@ -184,19 +190,32 @@ impl BlessedGitStub {
                     // Check that the stored commit is still an ancestor
                     // of the merge base. If not, the commit is stale
                     // (e.g., after a rebase) and needs to be recomputed.
-                    if !vcs.is_ancestor(repo_root, *commit, merge_base)? {
-                        let commit = vcs.first_commit_for_file(
+                    let is_ancestor =
+                        match vcs.is_ancestor(repo_root, *commit, merge_base) {
+                            Ok(is_ancestor) => is_ancestor,
+                            // The commit may be missing locally.
+                            Err(error) => {
+                                if !vcs.recover_missing_commit(
+                                    repo_root, *commit,
+                                )? {
+                                    return Err(error);
+                                }
+                                vcs.is_ancestor(repo_root, *commit, merge_base)?
+                            }
+                        };
+                    if !is_ancestor {
+                        let (commit, path) = vcs.first_commit_for_file(
                             repo_root, merge_base, path,
                         )?;
-                        return Ok(GitStub::new(commit, path.clone())?);
+                        return Ok(GitStub::new(commit, path)?);
                     }
                 }
                 Ok(GitStub::new(*commit, path.clone())?)
             }
             BlessedGitStub::Lazy { commit, path } => {
-                let commit =
+                let (commit, path) =
                     vcs.first_commit_for_file(repo_root, *commit, path)?;
-                Ok(GitStub::new(commit, path.clone())?)
+                Ok(GitStub::new(commit, path)?)
             }
         }
     }
@@ -218,6 +237,11 @@ enum BlessedPathKind<'a> {
     /// Two-component path (e.g., "api/api-1.2.3-hash.json"). Could be a
     /// versioned file or latest symlink - requires API validation.
     VersionedFile { api_dir: &'a str, basename: &'a str },
+
+    /// Three-component path within an API's shared schemas directory. These
+    /// are read along with the documents that refer to them, so they're
+    /// skipped.
+    SharedSchema,
 }
 
 /// Path structure we don't understand (empty, >2 components, etc.).
@@ -232,9 +256,18 @@ impl<'a> BlessedPathKind<'a> {
             // Single-component path: lockstep file.
             return Ok(BlessedPathKind::Lockstep);
         };
-        if iter.next().is_some() {
-            // Three or more components: not recognized.
-            return Err(UnrecognizedPath);
+        if let Some(third) = iter.next() {
+            // Three components within a shared schemas directory are
+            // expected. Other paths with three or more components aren't
+            // recognized.
+            return if second == SHARED_SCHEMAS_DIR
+                && third.ends_with(".json")
+                && iter.next().is_none()
+            {
+                Ok(BlessedPathKind::SharedSchema)
+            } else {
+                Err(UnrecognizedPath)
+            };
         }
         // Two-component path: versioned file or Git stub.
         if second.ends_with(".json.gitstub") {
@@ -264,12 +297,54 @@ pub struct BlessedFiles {
     files: BTreeMap<ApiIdent, ApiFiles<BlessedApiSpecFile>>,
     /// Git stubs for each blessed file, keyed by (ident, version).
     git_stubs: BTreeMap<GitStubKey, BlessedGitStub>,
-    /// The merge base used when loading blessed files from VCS history.
+    /// Where the blessed files were loaded from in VCS history.
     ///
     /// This is `Some` when loaded via [`load_from_vcs_parent_branch`] or
     /// [`load_from_vcs_revision`], and `None` when loaded from a
     /// directory.
-    merge_base: Option<GitCommitHash>,
+    provenance: Option<BlessedProvenance>,
+    /// APIs that aren't managed, but have files among the blessed documents.
+    unknown_apis: BTreeSet<ApiIdent>,
+}
+
+/// Describes where blessed files were loaded from in VCS history.
+///
+/// When `check` picks an unexpected baseline, this is the first thing to look
+/// at, so it's printed after blessed files are loaded.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlessedProvenance {
+    /// The revision (typically a branch like `origin/main`) that blessed files
+    /// were requested from, if any.
+    ///
+    /// This is `None` if blessed files were loaded from a specific commit.
+    pub revision: Option<VcsRevision>,
+    /// The commit that blessed files were loaded from.
+    ///
+    /// If `revision` is `Some`, this is the merge base between the working
+    /// copy and `revision`.
+    pub merge_base: GitCommitHash,
+    /// The OpenAPI documents directory at `merge_base`.
+    ///
+    /// This differs from the current documents directory if the directory was
+    /// renamed since `merge_base`.
+    pub directory: Utf8PathBuf,
+}
+
+impl fmt::Display for BlessedProvenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.revision {
+            Some(revision) => write!(
+                f,
+                "merge base {} of the working copy and {}, path {:?}",
+                self.merge_base, revision, self.directory,
+            ),
+            None => write!(
+                f,
+                "commit {}, path {:?}",
+                self.merge_base, self.directory
+            ),
+        }
+    }
 }
 
 impl Deref for BlessedFiles {
@@ -299,7 +374,51 @@ impl BlessedFiles {
     /// This is `Some` when loaded from git, and `None` when loaded from a
     /// directory.
     pub fn merge_base(&self) -> Option<GitCommitHash> {
-        self.merge_base
+        self.provenance.as_ref().map(|p| p.merge_base)
+    }
+
+    /// Adds versions blessed in `stack_base` that aren't blessed here.
+    ///
+    /// `stack_base` is loaded from an unmerged change that the current change
+    /// is stacked on top of. Git stubs aren't carried over, since they would
+    /// refer to commits that may be rewritten before that change merges:
+    /// versions only blessed in `stack_base` are kept as JSON.
+    pub(crate) fn add_from_stack_base(&mut self, stack_base: BlessedFiles) {
+        self.unknown_apis.extend(stack_base.unknown_apis);
+        for (ident, api_files) in stack_base.files {
+            match self.files.entry(ident) {
+                Entry::Vacant(entry) => {
+                    entry.insert(api_files);
+                }
+                Entry::Occupied(mut entry) => {
+                    entry.get_mut().add_missing_versions(api_files);
+                }
+            }
+        }
+    }
+
+    /// Returns where the blessed files were loaded from in VCS history.
+    ///
+    /// This is `Some` when loaded from VCS, and `None` when loaded from a
+    /// directory.
+    pub fn provenance(&self) -> Option<&BlessedProvenance> {
+        self.provenance.as_ref()
+    }
+
+    /// Records APIs that aren't managed, but have files among the blessed
+    /// documents.
+    pub(crate) fn with_unknown_apis(
+        mut self,
+        unknown_apis: BTreeSet<ApiIdent>,
+    ) -> Self {
+        self.unknown_apis = unknown_apis;
+        self
+    }
+
+    /// Returns true if `ident` isn't managed, but has files among the blessed
+    /// documents.
+    pub(crate) fn has_unknown_api(&self, ident: &ApiIdent) -> bool {
+        self.unknown_apis.contains(ident)
     }
 }
 
@@ -337,16 +456,68 @@ enum BlessedFileResult {
 
 // ---- Phase 1: parallel read + filename parse + deserialization ----
 
+/// Reads blessed files, from the working copy if they're unchanged there and
+/// from VCS history otherwise.
+///
+/// Most blessed documents are unchanged in the working copy. Asking the VCS
+/// which files differ is a single query that compares hashes, while reading
+/// each file out of history is a query per file, so this avoids most of the
+/// queries on large repositories.
+struct BlessedReader<'a> {
+    repo_root: &'a Utf8Path,
+    commit: GitCommitHash,
+    vcs: &'a RepoVcs,
+    /// The files that differ in the working copy, relative to the repository
+    /// root. If `None`, every file is read from history.
+    changed: Option<BTreeSet<Utf8PathBuf>>,
+}
+
+impl<'a> BlessedReader<'a> {
+    fn new(
+        repo_root: &'a Utf8Path,
+        commit: GitCommitHash,
+        directory: &Utf8Path,
+        vcs: &'a RepoVcs,
+    ) -> Self {
+        // This is only an optimization, so if the query fails, fall back to
+        // reading every file from history (which will report any real
+        // problems with the repository).
+        let changed = vcs
+            .changed_in_working_copy(repo_root, commit, directory)
+            .ok()
+            .flatten();
+        Self { repo_root, commit, vcs, changed }
+    }
+
+    /// Returns the contents of `path`, relative to the repository root, at
+    /// the blessed commit.
+    fn read(&self, path: &Utf8Path) -> anyhow::Result<Vec<u8>> {
+        if let Some(changed) = &self.changed
+            && !changed.contains(path)
+        {
+            // The file may still be missing from the working copy, e.g. in a
+            // sparse checkout. Also, with line ending conversion, a file Git
+            // considers unchanged can have different bytes on disk; this tool
+            // only writes `\n` line endings, so fall back to history for files
+            // with carriage returns.
+            if let Ok(contents) = fs_err::read(self.repo_root.join(path))
+                && !contents.contains(&b'\r')
+            {
+                return Ok(contents);
+            }
+        }
+        self.vcs.show_file(self.repo_root, self.commit, path)
+    }
+}
+
 /// Process a single path from a VCS.
 ///
 /// This is called in parallel.
 fn process_blessed_entry(
     f: &Utf8Path,
-    repo_root: &Utf8Path,
-    commit: GitCommitHash,
+    reader: &BlessedReader<'_>,
     directory: &Utf8Path,
     apis: &ManagedApis,
-    vcs: &RepoVcs,
 ) -> BlessedFileResult {
     let kind = match BlessedPathKind::parse(f) {
         Ok(kind) => kind,
@@ -356,7 +527,9 @@ fn process_blessed_entry(
     };
 
     match kind {
-        BlessedPathKind::Lockstep => BlessedFileResult::Skip,
+        BlessedPathKind::Lockstep | BlessedPathKind::SharedSchema => {
+            BlessedFileResult::Skip
+        }
 
         BlessedPathKind::VersionedFile { api_dir, basename } => {
             // Skip the latest symlink.
@@ -377,21 +550,31 @@ fn process_blessed_entry(
             };
 
             let git_path = format!("{directory}/{f}");
-            let contents =
-                match vcs.show_file(repo_root, commit, git_path.as_ref()) {
-                    Ok(c) => c,
-                    Err(err) => {
-                        return BlessedFileResult::Error(err);
-                    }
-                };
+            let contents = match reader.read(git_path.as_ref()) {
+                Ok(c) => c,
+                Err(err) => {
+                    return BlessedFileResult::Error(err);
+                }
+            };
 
-            // Deserialize.
-            let result = ApiSpecFile::for_contents(spec_file_name, contents)
-                .map_err(|(err, _bytes)| {
-                    // BlessedApiSpecFile doesn't track unparseable
-                    // files, so drop the raw bytes (as _bytes).
-                    err
-                });
+            // Reassemble the document if its schemas are stored in the shared
+            // directory, convert it to Dropshot's formatting if it has a
+            // custom format, then deserialize.
+            let dir = Utf8Path::new(&git_path).parent().unwrap_or(directory);
+            let result = shared_schemas::join(contents, |schema| {
+                reader.read(&dir.join(schema))
+            })
+            .and_then(|contents| {
+                to_dropshot_format(apis, spec_file_name.ident(), contents)
+            })
+            .and_then(|contents| {
+                ApiSpecFile::for_contents(spec_file_name, contents)
+            })
+            .map_err(|(err, _bytes)| {
+                // BlessedApiSpecFile doesn't track unparseable files, so drop
+                // the raw bytes (as _bytes).
+                err
+            });
 
             BlessedFileResult::VersionedDeserialized { result, git_path }
         }
@@ -409,13 +592,12 @@ fn process_blessed_entry(
             };
 
             let git_path = format!("{directory}/{f}");
-            let contents =
-                match vcs.show_file(repo_root, commit, git_path.as_ref()) {
-                    Ok(c) => c,
-                    Err(err) => {
-                        return BlessedFileResult::Error(err);
-                    }
-                };
+            let contents = match reader.read(git_path.as_ref()) {
+                Ok(c) => c,
+                Err(err) => {
+                    return BlessedFileResult::Error(err);
+                }
+            };
 
             // Read and parse the Git stub contents.
             let git_stub_str = match String::from_utf8(contents) {
@@ -436,21 +618,35 @@ fn process_blessed_entry(
                     }
                 };
 
-            // Read the actual JSON contents.
-            let json_contents =
-                match vcs.resolve_stub_contents(&git_stub, repo_root) {
-                    Ok(c) => c,
-                    Err(err) => {
-                        return BlessedFileResult::Error(err.context(format!(
-                            "reading content for Git stub {:?}",
-                            git_path
-                        )));
-                    }
-                };
+            // Read the actual JSON contents, reassembling the document from
+            // the shared schemas at the same commit if needed.
+            let json_contents = reader
+                .vcs
+                .resolve_stub_contents(&git_stub, reader.repo_root)
+                .and_then(|contents| {
+                    join_git_stub_contents(
+                        contents,
+                        &git_stub,
+                        reader.repo_root,
+                        reader.vcs,
+                    )
+                });
+            let json_contents = match json_contents {
+                Ok(c) => c,
+                Err(err) => {
+                    return BlessedFileResult::Error(err.context(format!(
+                        "reading content for Git stub {:?}",
+                        git_path
+                    )));
+                }
+            };
 
             // Deserialize.
             let result =
-                ApiSpecFile::for_contents(spec_file_name, json_contents)
+                to_dropshot_format(apis, spec_file_name.ident(), json_contents)
+                    .and_then(|contents| {
+                        ApiSpecFile::for_contents(spec_file_name, contents)
+                    })
                     .map_err(|(err, _bytes)| err);
 
             BlessedFileResult::GitStubDeserialized { result, git_stub }
@@ -485,14 +681,21 @@ impl BlessedFiles {
         vcs: &RepoVcs,
     ) -> anyhow::Result<BlessedFiles> {
         let revision = vcs.merge_base_head(repo_root, branch)?;
-        Self::load_from_vcs_revision(
+        // The directory may have had a different name at the merge base.
+        let directory =
+            vcs.directory_at_revision(repo_root, revision, directory)?;
+        let mut blessed = Self::load_from_vcs_revision(
             repo_root,
             revision,
-            directory,
+            &directory,
             apis,
             error_accumulator,
             vcs,
-        )
+        )?;
+        if let Some(provenance) = &mut blessed.provenance {
+            provenance.revision = Some(branch.clone());
+        }
+        Ok(blessed)
     }
 
     /// Load OpenAPI documents from the given VCS revision and directory.
@@ -508,15 +711,20 @@ impl BlessedFiles {
         vcs: &RepoVcs,
     ) -> anyhow::Result<BlessedFiles> {
         let files_found = vcs.list_files(repo_root, commit, directory)?;
+        let unknown_apis = files_found
+            .iter()
+            .filter_map(|f| {
+                let mut components = f.iter();
+                let first = components.next()?;
+                apis.unmanaged_api_for_entry(first, components.next().is_some())
+            })
+            .collect();
 
         // Phase 1 (map): parallel read + deserialize.
+        let reader = BlessedReader::new(repo_root, commit, directory, vcs);
         let results: Vec<BlessedFileResult> = files_found
             .par_iter()
-            .map(|f| {
-                process_blessed_entry(
-                    f, repo_root, commit, directory, apis, vcs,
-                )
-            })
+            .map(|f| process_blessed_entry(f, &reader, directory, apis))
             .collect();
 
         // Phase 2 (reduce): build up the internal builder state.
@@ -608,7 +816,17 @@ impl BlessedFiles {
         }
 
         let files = api_files.into_map();
-        Ok(BlessedFiles { files, git_stubs, merge_base: Some(commit) })
+        let provenance = BlessedProvenance {
+            revision: None,
+            merge_base: commit,
+            directory: directory.to_owned(),
+        };
+        Ok(BlessedFiles {
+            files,
+            git_stubs,
+            provenance: Some(provenance),
+            unknown_apis,
+        })
     }
 }
 
@@ -619,7 +837,8 @@ impl<'a> From<ApiSpecFilesBuilder<'a, BlessedApiSpecFile>> for BlessedFiles {
         BlessedFiles {
             files: api_files.into_map(),
             git_stubs: BTreeMap::new(),
-            merge_base: None,
+            provenance: None,
+            unknown_apis: BTreeSet::new(),
         }
     }
 }
@@ -6,18 +6,60 @@
 use crate::{
     apis::{ManagedApi, ManagedApis},
     environment::ErrorAccumulator,
+    lockstep_version::with_version,
+    openapi_version::{mixed_openapi_versions, with_openapi_version},
     spec_files_generic::{
         ApiFiles, ApiLoad, ApiSpecFile, ApiSpecFilesBuilder, AsRawFiles,
-        SpecFileInfo, hash_contents,
+        FileNameHash, SpecFileInfo,
     },
+    spec_files_local::LocalFiles,
+    version_ranges::check_version_ranges,
 };
-use anyhow::{anyhow, bail};
+use anyhow::{Context, anyhow, bail};
 use dropshot_api_manager_types::{
     ApiIdent, ApiSpecFileName, LockstepApiSpecFileName,
     VersionedApiSpecFileName,
 };
 use rayon::prelude::*;
-use std::{collections::BTreeMap, ops::Deref};
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    ops::Deref,
+    panic::{AssertUnwindSafe, catch_unwind},
+    time::{Duration, Instant},
+};
+
+/// Returns an error if the two copies of a generated document differ.
+fn nondeterminism_error(
+    api: &ManagedApi,
+    version: &semver::Version,
+    first: &[u8],
+    second: &[u8],
+) -> Option<anyhow::Error> {
+    if first == second {
+        return None;
+    }
+    let line = first
+        .iter()
+        .zip(second)
+        .take_while(|(a, b)| a == b)
+        .filter(|(a, _)| **a == b'\n')
+        .count()
+        + 1;
+    let what = if api.is_versioned() {
+        format!("versioned API {:?} version {}", api.ident(), version)
+    } else {
+        format!("lockstep API {:?}", api.ident())
+    };
+    Some(anyhow!(
+        "OpenAPI document for {} is not deterministic: generating it twice \
+         produced different output, starting at line {} (look for \
+         iteration over a HashMap or HashSet in the API's types, schemas, or \
+         extensions)",
+        what,
+        line,
+    ))
+}
 
 /// Newtype wrapper around [`ApiSpecFile`] to describe OpenAPI documents
 /// generated from API definitions
@@ -84,11 +126,26 @@ impl AsRawFiles for GeneratedApiSpecFile {
 ///
 /// For more on what's been validated at this point, see
 /// [`ApiSpecFilesBuilder`].
-pub struct GeneratedFiles(BTreeMap<ApiIdent, ApiFiles<GeneratedApiSpecFile>>);
-NewtypeDeref! {
-    () pub struct GeneratedFiles(
-        BTreeMap<ApiIdent, ApiFiles<GeneratedApiSpecFile>>
-    );
+pub struct GeneratedFiles {
+    files: BTreeMap<ApiIdent, ApiFiles<GeneratedApiSpecFile>>,
+
+    /// APIs for which generation panicked, along with the panic message.
+    ///
+    /// These APIs have no entry in `files`.
+    panics: BTreeMap<ApiIdent, String>,
+
+    /// How long generating each API's documents took.
+    ///
+    /// This is empty if the documents were loaded from a directory.
+    durations: BTreeMap<ApiIdent, Duration>,
+}
+
+impl Deref for GeneratedFiles {
+    type Target = BTreeMap<ApiIdent, ApiFiles<GeneratedApiSpecFile>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.files
+    }
 }
 
 /// Intermediate result from generating all versions for a single API.
@@ -104,18 +161,46 @@ enum GeneratedApiResult {
         ident: ApiIdent,
         versions: Vec<Result<ApiSpecFile, anyhow::Error>>,
         latest: Option<VersionedApiSpecFileName>,
+        /// Warnings about endpoints whose version ranges don't line up with
+        /// the supported versions.
+        warnings: Vec<anyhow::Error>,
+    },
+    /// Generating documents for this API panicked.
+    Panicked {
+        ident: ApiIdent,
+        message: String,
     },
 }
 
+/// Generates the document for one version of an API, with its `openapi` field
+/// set to `openapi_version` if that's specified.
+fn generate_spec_bytes(
+    api: &ManagedApi,
+    version: &semver::Version,
+    openapi_version: Option<&str>,
+) -> anyhow::Result<Vec<u8>> {
+    let contents = api.generate_spec_bytes(version)?;
+    match openapi_version {
+        Some(openapi_version) => {
+            with_openapi_version(contents, openapi_version)
+        }
+        None => Ok(contents),
+    }
+}
+
 /// Generate and deserialize all versions for a single API.
 ///
 /// This is called in parallel.
-fn generate_api(api: &ManagedApi) -> GeneratedApiResult {
+fn generate_api(
+    api: &ManagedApi,
+    file_name_hash: FileNameHash,
+    openapi_version: Option<&str>,
+) -> GeneratedApiResult {
     if api.is_lockstep() {
         let versions = api
             .iter_versions_semver()
             .map(|version| {
-                api.generate_spec_bytes(version)
+                generate_spec_bytes(api, version, openapi_version)
                     .and_then(|contents| {
                         let file_name =
                             LockstepApiSpecFileName::new(api.ident().clone());
@@ -144,12 +229,12 @@ fn generate_api(api: &ManagedApi) -> GeneratedApiResult {
             .par_iter()
             .map(|supported_version| {
                 let version = supported_version.semver();
-                api.generate_spec_bytes(version)
+                generate_spec_bytes(api, version, openapi_version)
                     .and_then(|contents| {
                         let file_name = VersionedApiSpecFileName::new(
                             api.ident().clone(),
                             version.clone(),
-                            hash_contents(&contents),
+                            file_name_hash.hash(&contents),
                         );
                         ApiSpecFile::for_contents(file_name.into(), contents)
                             .map_err(|(e, _buf)| e)
@@ -176,10 +261,25 @@ fn generate_api(api: &ManagedApi) -> GeneratedApiResult {
                 }
             })
         });
+        // Ranges are only checked once every supported version has been
+        // generated.
+        let warnings = if api.checks_version_ranges() {
+            versions
+                .iter()
+                .map(|r| {
+                    r.as_ref().ok().map(|f| (f.version().clone(), f.openapi()))
+                })
+                .collect::<Option<BTreeMap<_, _>>>()
+                .map(|supported| check_version_ranges(api, &supported))
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
         GeneratedApiResult::Versioned {
             ident: api.ident().clone(),
             versions,
             latest,
+            warnings,
         }
     }
 }
@@ -188,30 +288,69 @@ impl GeneratedFiles {
     /// Generate OpenAPI documents for all supported versions of all managed
     /// APIs.
     ///
+    /// Versioned documents are named using `file_name_hash`. If
+    /// `openapi_version` is specified, each document's `openapi` field is set
+    /// to it; otherwise, a warning is reported if the documents don't all have
+    /// the same `openapi` field. If `verify_deterministic` is set, each
+    /// document is generated a second time, and an error is reported if the
+    /// two copies differ.
+    ///
     /// This function loads all APIs in parallel.
     pub fn generate(
         apis: &ManagedApis,
+        file_name_hash: FileNameHash,
+        openapi_version: Option<&str>,
+        verify_deterministic: bool,
         error_accumulator: &mut ErrorAccumulator,
     ) -> anyhow::Result<GeneratedFiles> {
         // Map: generate and deserialize in parallel.
-        let results: Vec<GeneratedApiResult> = apis
-            .iter_apis()
-            .collect::<Vec<_>>()
-            .par_iter()
-            .map(|api| generate_api(api))
-            .collect();
+        //
+        // A panic while generating one API (e.g. within its
+        // `stub_api_description`) is reported as a problem for that API, so
+        // that the other APIs can still be checked.
+        let results: Vec<_> =
+            apis.iter_apis()
+                .collect::<Vec<_>>()
+                .par_iter()
+                .map(|api| {
+                    let start = Instant::now();
+                    let result = catch_unwind(AssertUnwindSafe(|| {
+                        generate_api(api, file_name_hash, openapi_version)
+                    }))
+                    .unwrap_or_else(|payload| GeneratedApiResult::Panicked {
+                        ident: api.ident().clone(),
+                        message: panic_message(&*payload),
+                    });
+                    (api.ident().clone(), result, start.elapsed())
+                })
+                .collect();
 
         // Reduce: feed results into the builder sequentially.
         let mut api_files: ApiSpecFilesBuilder<GeneratedApiSpecFile> =
             ApiSpecFilesBuilder::new(apis, error_accumulator);
+        let mut panics = BTreeMap::new();
+        let mut durations = BTreeMap::new();
 
-        for result in results {
+        for (api_ident, result, elapsed) in results {
             let (versions, latest_info) = match result {
                 GeneratedApiResult::Lockstep { versions } => (versions, None),
-                GeneratedApiResult::Versioned { ident, versions, latest } => {
+                GeneratedApiResult::Versioned {
+                    ident,
+                    versions,
+                    latest,
+                    warnings,
+                } => {
+                    for warning in warnings {
+                        api_files.load_warning(warning);
+                    }
                     (versions, Some((ident, latest)))
                 }
+                GeneratedApiResult::Panicked { ident, message } => {
+                    panics.insert(ident, message);
+                    continue;
+                }
             };
+            durations.insert(api_ident, elapsed);
 
             for version_result in versions {
                 match version_result {
@@ -232,7 +371,133 @@ impl GeneratedFiles {
             }
         }
 
-        Ok(Self::from(api_files))
+        let generated =
+            GeneratedFiles { files: api_files.into_map(), panics, durations };
+        if openapi_version.is_none()
+            && let Some(warning) = mixed_openapi_versions(&generated)
+        {
+            error_accumulator.warning(warning);
+        }
+        if verify_deterministic {
+            for error in generated.verify_deterministic(apis, openapi_version) {
+                error_accumulator.error(error);
+            }
+        }
+        Ok(generated)
+    }
+
+    /// Generates each document again, returning an error for each one that
+    /// differs from the first copy.
+    fn verify_deterministic(
+        &self,
+        apis: &ManagedApis,
+        openapi_version: Option<&str>,
+    ) -> Vec<anyhow::Error> {
+        apis.iter_apis()
+            .collect::<Vec<_>>()
+            .par_iter()
+            .flat_map_iter(|api| {
+                let files = self.files.get(api.ident());
+                api.iter_versions_semver()
+                    .filter_map(|version| {
+                        let first = files?.versions().get(version)?;
+                        let second =
+                            generate_spec_bytes(api, version, openapi_version)
+                                .ok()?;
+                        nondeterminism_error(
+                            api,
+                            version,
+                            first.contents(),
+                            &second,
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Sets the version of the document for each lockstep API with a
+    /// [`LockstepVersionPolicy`], based on the API's local document.
+    ///
+    /// If the document is unchanged apart from its version, the local version
+    /// is kept. Otherwise, the policy picks the next version.
+    ///
+    /// [`LockstepVersionPolicy`]: crate::LockstepVersionPolicy
+    pub(crate) fn apply_lockstep_version_policies(
+        &mut self,
+        apis: &ManagedApis,
+        local_files: &LocalFiles,
+    ) -> anyhow::Result<()> {
+        let today = chrono::Utc::now().date_naive();
+        for api in apis.iter_apis() {
+            let Some(policy) = api.lockstep_version_policy() else {
+                continue;
+            };
+            if !api.is_lockstep() {
+                continue;
+            }
+            let Some(files) = self.files.get_mut(api.ident()) else {
+                continue;
+            };
+            let Some((generated_version, generated)) =
+                files.versions().first_key_value()
+            else {
+                continue;
+            };
+            // A lockstep API has a single local document, whatever its
+            // version.
+            let local = local_files
+                .get(api.ident())
+                .and_then(|files| files.versions().values().next())
+                .and_then(|list| list.first());
+
+            let version = match local {
+                None => policy.initial_version(generated_version, today),
+                Some(local) => {
+                    let unchanged = if local.version() == generated_version {
+                        local.contents() == generated.contents()
+                    } else {
+                        with_version(generated.contents(), local.version())?
+                            == local.contents()
+                    };
+                    if unchanged {
+                        local.version().clone()
+                    } else {
+                        policy.next_version(local.version(), today)
+                    }
+                }
+            };
+            if version == *generated_version {
+                continue;
+            }
+
+            let contents = with_version(generated.contents(), &version)?;
+            let file = ApiSpecFile::for_contents(
+                generated.spec_file_name().clone(),
+                contents,
+            )
+            .map_err(|(error, _buf)| error)
+            .with_context(|| {
+                format!("setting version of lockstep API {:?}", api.ident())
+            })?;
+            let generated_version = generated_version.clone();
+            files.replace_version(&generated_version, version, file.into());
+        }
+        Ok(())
+    }
+
+    /// Returns the panic message if generating documents for this API
+    /// panicked.
+    pub fn panic_message(&self, ident: &ApiIdent) -> Option<&str> {
+        self.panics.get(ident).map(|message| message.as_str())
+    }
+
+    /// Returns how long generating each API's documents took.
+    ///
+    /// APIs whose generation panicked, and documents loaded from a directory,
+    /// have no entry.
+    pub(crate) fn generation_durations(&self) -> &BTreeMap<ApiIdent, Duration> {
+        &self.durations
     }
 }
 
@@ -240,6 +505,21 @@ impl<'a> From<ApiSpecFilesBuilder<'a, GeneratedApiSpecFile>>
     for GeneratedFiles
 {
     fn from(api_files: ApiSpecFilesBuilder<'a, GeneratedApiSpecFile>) -> Self {
-        GeneratedFiles(api_files.into_map())
+        GeneratedFiles {
+            files: api_files.into_map(),
+            panics: BTreeMap::new(),
+            durations: BTreeMap::new(),
+        }
+    }
+}
+
+/// Extracts a human-readable message from a panic payload.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_owned()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "(non-string panic payload)".to_owned()
     }
 }
@@ -4,7 +4,7 @@
 //! repository
 
 use crate::{apis::ManagedApis, environment::ErrorAccumulator};
-use anyhow::anyhow;
+use anyhow::{anyhow, bail};
 use camino::{Utf8Path, Utf8PathBuf};
 use debug_ignore::DebugIgnore;
 use dropshot_api_manager_types::{
@@ -13,7 +13,7 @@ use dropshot_api_manager_types::{
 };
 use git_stub::GitCommitHash;
 use openapiv3::OpenAPI;
-use sha2::{Digest, Sha256};
+use sha2::{Digest, Sha256, Sha512};
 use std::{
     collections::{BTreeMap, btree_map::Entry},
     fmt::Debug,
@@ -59,6 +59,10 @@ pub(crate) fn parse_versioned_file_name(
         return Err(BadVersionedFileName::NotVersioned);
     }
 
+    if api.is_unmanaged_file(basename) {
+        return Err(BadVersionedFileName::Unmanaged);
+    }
+
     let expected_prefix = format!("{}-", &ident);
     let suffix = basename.strip_prefix(&expected_prefix).ok_or_else(|| {
         BadVersionedFileName::UnexpectedName {
@@ -116,6 +120,17 @@ pub(crate) fn parse_versioned_file_name(
         });
     }
 
+    // The hash may have any length, so that the hash configuration can be
+    // changed without invalidating existing file names.
+    if hash.is_empty()
+        || !hash.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+    {
+        return Err(BadVersionedFileName::UnexpectedName {
+            ident,
+            source: anyhow!("hash is not a lowercase hex string: {:?}", hash),
+        });
+    }
+
     Ok(VersionedApiSpecFileName::new(ident, version, hash.to_string()))
 }
 
@@ -128,6 +143,13 @@ pub(crate) fn parse_versioned_git_stub_file_name(
     ident: &str,
     basename: &str,
 ) -> Result<VersionedApiSpecFileName, BadVersionedFileName> {
+    if apis
+        .api(&ApiIdent::from(ident.to_string()))
+        .is_some_and(|api| api.is_unmanaged_file(basename))
+    {
+        return Err(BadVersionedFileName::Unmanaged);
+    }
+
     // The file name must end with .json.gitstub.
     let json_basename = basename.strip_suffix(".gitstub").ok_or_else(|| {
         BadVersionedFileName::UnexpectedName {
@@ -188,6 +210,8 @@ pub(crate) enum BadVersionedFileName {
     NoSuchApi,
     #[error("this API is not a versioned API")]
     NotVersioned,
+    #[error("file is not managed by the API manager")]
+    Unmanaged,
     #[error(
         "expected a versioned API document filename for API {ident:?} to look \
          like \"{ident:?}-SEMVER-HASH.json\""
@@ -307,18 +331,28 @@ impl ApiSpecFile {
 
                 // Only check hash for JSON files. Git stubs use the Git
                 // stub itself as the source of truth.
-                if v.kind() == VersionedApiSpecKind::Json {
-                    let expected_hash = hash_contents(&contents_buf);
-                    if expected_hash != v.hash() {
-                        return Err((
-                            ApiSpecFileParseError::HashMismatch {
-                                path: spec_file_name.path(),
-                                expected: expected_hash,
-                                actual: v.hash().to_owned(),
-                            },
-                            contents_buf,
-                        ));
-                    }
+                if v.kind() == VersionedApiSpecKind::Json
+                    && !hash_matches(&contents_buf, v.hash())
+                {
+                    // Report the hash under the default algorithm, truncated
+                    // to the length of the one in the file name (or the
+                    // nearest valid length, if that's invalid).
+                    let mut expected = FileNameHashAlgorithm::default()
+                        .hex_digest(&contents_buf);
+                    expected.truncate(
+                        v.hash()
+                            .len()
+                            .max(FileNameHash::MIN_LEN_BYTES * 2)
+                            .next_multiple_of(2),
+                    );
+                    return Err((
+                        ApiSpecFileParseError::HashMismatch {
+                            path: spec_file_name.path(),
+                            expected,
+                            actual: v.hash().to_owned(),
+                        },
+                        contents_buf,
+                    ));
                 }
             }
             ApiSpecFileName::Lockstep(_) => {}
@@ -445,6 +479,24 @@ impl<'a, T: ApiLoad + AsRawFiles> ApiSpecFilesBuilder<'a, T> {
         basename: &str,
     ) -> Option<ApiSpecFileName> {
         match parse_lockstep_file_name(self.apis, basename) {
+            // Copies of documents under an alias are maintained separately.
+            Err(BadLockstepFileName::NoSuchApi { ident })
+                if self.apis.is_alias(&ident) =>
+            {
+                None
+            }
+            // Documents for removed APIs are expected until they're deleted.
+            Err(BadLockstepFileName::NoSuchApi { ident })
+                if self.apis.is_removed_api(&ident) =>
+            {
+                None
+            }
+            // Documents for APIs left out of a partial check aren't loaded.
+            Err(BadLockstepFileName::NoSuchApi { ident })
+                if self.apis.is_skipped_api(&ident) =>
+            {
+                None
+            }
             // When we're looking at the blessed files, the caller provides
             // `misconfigurations_okay: true` and we treat these as
             // warnings because the configuration for an API may have
@@ -528,6 +580,12 @@ impl<'a, T: ApiLoad + AsRawFiles> ApiSpecFilesBuilder<'a, T> {
                 }
                 None
             }
+            // Copies of documents under an alias are maintained separately.
+            None if self.apis.is_alias(&ident) => None,
+            // Documents for removed APIs are expected until they're deleted.
+            None if self.apis.is_removed_api(&ident) => None,
+            // Documents for APIs left out of a partial check aren't loaded.
+            None if self.apis.is_skipped_api(&ident) => None,
             None => {
                 let error = anyhow!(
                     "skipping directory for unknown API: {:?}",
@@ -610,6 +668,8 @@ impl<'a, T: ApiLoad + AsRawFiles> ApiSpecFilesBuilder<'a, T> {
     ) -> Option<VersionedApiSpecFileName> {
         match result {
             Ok(file_name) => Some(file_name),
+            // Unmanaged files are expected, so they're skipped silently.
+            Err(BadVersionedFileName::Unmanaged) => None,
             Err(
                 warning @ (BadVersionedFileName::NoSuchApi
                 | BadVersionedFileName::NotVersioned),
@@ -811,6 +871,15 @@ impl<'a, T: ApiLoad + AsRawFiles> ApiSpecFilesBuilder<'a, T> {
         }
     }
 
+    /// Record that a temporary "latest" symlink, left behind by an interrupted
+    /// update, exists for a versioned API
+    pub fn load_latest_link_temp(&mut self, ident: &ApiIdent) {
+        self.spec_files
+            .entry(ident.clone())
+            .or_insert_with(ApiFiles::new)
+            .latest_link_temp = true;
+    }
+
     /// Set the Git stub commit on the most recently loaded item at (ident,
     /// version).
     ///
@@ -859,6 +928,9 @@ impl<'a, T: ApiLoad + AsRawFiles> ApiSpecFilesBuilder<'a, T> {
 pub struct ApiFiles<T> {
     spec_files: BTreeMap<semver::Version, T>,
     latest_link: Option<VersionedApiSpecFileName>,
+    /// Whether a temporary "latest" symlink was left behind by an interrupted
+    /// update.
+    latest_link_temp: bool,
     /// Files that exist on disk but couldn't be parsed. These are tracked so
     /// that generate can delete them and create correct files in their place.
     unparseable_files: Vec<UnparseableFile>,
@@ -869,6 +941,7 @@ impl<T: AsRawFiles> ApiFiles<T> {
         ApiFiles {
             spec_files: BTreeMap::new(),
             latest_link: None,
+            latest_link_temp: false,
             unparseable_files: Vec::new(),
         }
     }
@@ -881,10 +954,42 @@ impl<T: AsRawFiles> ApiFiles<T> {
         self.latest_link.as_ref()
     }
 
+    /// Returns true if a temporary "latest" symlink was left behind by an
+    /// interrupted update.
+    pub fn has_latest_link_temp(&self) -> bool {
+        self.latest_link_temp
+    }
+
     /// Returns files that couldn't be parsed but should be tracked for cleanup.
     pub fn unparseable_files(&self) -> &[UnparseableFile] {
         &self.unparseable_files
     }
+
+    /// Moves the file for `old` to `new`, replacing it with `file`.
+    pub(crate) fn replace_version(
+        &mut self,
+        old: &semver::Version,
+        new: semver::Version,
+        file: T,
+    ) {
+        self.spec_files.remove(old);
+        self.spec_files.insert(new, file);
+    }
+
+    /// Adds versions from `other` that aren't present here.
+    ///
+    /// If this adds a version newer than any here, the "latest" link is taken
+    /// from `other` as well. Unparseable files in `other` are ignored.
+    pub(crate) fn add_missing_versions(&mut self, other: ApiFiles<T>) {
+        let newer = other.spec_files.keys().next_back()
+            > self.spec_files.keys().next_back();
+        if newer {
+            self.latest_link = other.latest_link;
+        }
+        for (version, file) in other.spec_files {
+            self.spec_files.entry(version).or_insert(file);
+        }
+    }
 }
 
 /// Trait for types that provide spec file metadata.
@@ -998,7 +1103,49 @@ pub trait ApiLoad {
     fn set_git_stub_commit(&mut self, _commit: GitCommitHash) {}
 }
 
-/// Return the hash of an OpenAPI document file for the purposes of this tool
+/// The algorithm used to compute the hash in versioned OpenAPI document file
+/// names.
+///
+/// See [`FileNameHash`] for more about this hash.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum FileNameHashAlgorithm {
+    /// SHA-256. This is the default.
+    #[default]
+    Sha256,
+
+    /// SHA-512.
+    Sha512,
+}
+
+impl FileNameHashAlgorithm {
+    /// All supported algorithms.
+    const ALL: [FileNameHashAlgorithm; 2] =
+        [FileNameHashAlgorithm::Sha256, FileNameHashAlgorithm::Sha512];
+
+    /// Returns the length of the (untruncated) digest, in bytes.
+    pub fn digest_len(self) -> usize {
+        match self {
+            FileNameHashAlgorithm::Sha256 => 32,
+            FileNameHashAlgorithm::Sha512 => 64,
+        }
+    }
+
+    /// Returns the full digest of `contents`, hex-encoded.
+    fn hex_digest(self, contents: &[u8]) -> String {
+        match self {
+            FileNameHashAlgorithm::Sha256 => {
+                hex::encode(Sha256::digest(contents))
+            }
+            FileNameHashAlgorithm::Sha512 => {
+                hex::encode(Sha512::digest(contents))
+            }
+        }
+    }
+}
+
+/// Describes how the hash in versioned OpenAPI document file names is
+/// computed.
 ///
 /// The purpose of this hash is to isolate distinct versions of a given API
 /// version, as might happen if two people both try to create the the same
@@ -1008,19 +1155,100 @@ pub trait ApiLoad {
 /// conflicts in one file.  This tool can then fix things up.
 ///
 /// The upshot is: this hash is not required for security or even data
-/// integrity.  We use SHA-256 and truncate it to just the first four bytes
-/// to avoid the annoyance of super long filenames.
-pub(crate) fn hash_contents(contents: &[u8]) -> String {
-    let mut hasher = Sha256::new();
-    hasher.update(contents);
-    let computed_hash = hasher.finalize();
-    hex::encode(&computed_hash.as_slice()[0..3])
+/// integrity.  By default, we use SHA-256 and truncate it to just the first
+/// three bytes to avoid the annoyance of super long filenames. If two
+/// documents in flight ever collide, a longer hash (or a different algorithm)
+/// can be configured via [`Environment::with_file_name_hash`].
+///
+/// Changing this configuration only affects newly-generated file names.
+/// Existing files are accepted as long as the hash in the file name is a
+/// prefix of the contents' digest under any supported algorithm, made up of
+/// whole bytes and at least [`FileNameHash::MIN_LEN_BYTES`] long.
+///
+/// [`Environment::with_file_name_hash`]: crate::Environment::with_file_name_hash
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct FileNameHash {
+    algorithm: FileNameHashAlgorithm,
+    len_bytes: usize,
+}
+
+impl FileNameHash {
+    /// The default number of bytes of the digest to use in file names.
+    pub const DEFAULT_LEN_BYTES: usize = 3;
+
+    /// The minimum number of bytes of the digest in file names.
+    ///
+    /// Shorter hashes would make it too likely that a file's hash matches
+    /// unrelated contents by chance.
+    pub const MIN_LEN_BYTES: usize = 3;
+
+    /// Creates a new `FileNameHash` using the first `len_bytes` bytes of the
+    /// digest computed by `algorithm`.
+    ///
+    /// Returns an error if `len_bytes` is less than [`Self::MIN_LEN_BYTES`]
+    /// or longer than the digest.
+    pub fn new(
+        algorithm: FileNameHashAlgorithm,
+        len_bytes: usize,
+    ) -> anyhow::Result<Self> {
+        if !(Self::MIN_LEN_BYTES..=algorithm.digest_len()).contains(&len_bytes)
+        {
+            bail!(
+                "file name hash length must be between {} and {} bytes for \
+                 {:?}, but got {}",
+                Self::MIN_LEN_BYTES,
+                algorithm.digest_len(),
+                algorithm,
+                len_bytes,
+            );
+        }
+        Ok(Self { algorithm, len_bytes })
+    }
+
+    /// Returns the algorithm used to compute the hash.
+    pub fn algorithm(&self) -> FileNameHashAlgorithm {
+        self.algorithm
+    }
+
+    /// Returns the number of bytes of the digest used in file names.
+    pub fn len_bytes(&self) -> usize {
+        self.len_bytes
+    }
+
+    /// Returns the hash of `contents` for use in a file name.
+    pub(crate) fn hash(&self, contents: &[u8]) -> String {
+        let mut hash = self.algorithm.hex_digest(contents);
+        hash.truncate(self.len_bytes * 2);
+        hash
+    }
+}
+
+impl Default for FileNameHash {
+    fn default() -> Self {
+        Self {
+            algorithm: FileNameHashAlgorithm::default(),
+            len_bytes: Self::DEFAULT_LEN_BYTES,
+        }
+    }
+}
+
+/// Returns true if `hash` (taken from a file name) matches `contents` under
+/// any supported algorithm and length.
+///
+/// The hash must be made up of whole bytes, and be at least
+/// [`FileNameHash::MIN_LEN_BYTES`] long.
+fn hash_matches(contents: &[u8], hash: &str) -> bool {
+    hash.len().is_multiple_of(2)
+        && hash.len() >= FileNameHash::MIN_LEN_BYTES * 2
+        && FileNameHashAlgorithm::ALL
+            .iter()
+            .any(|algorithm| algorithm.hex_digest(contents).starts_with(hash))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::ManagedApiConfig;
+    use crate::{ManagedApi, ManagedApiConfig};
     use anyhow::Context;
     use assert_matches::assert_matches;
     use dropshot::{ApiDescription, ApiDescriptionBuildErrors, StubContext};
@@ -1129,6 +1357,85 @@ mod test {
         )
         .unwrap_err();
         assert_matches!(error, BadVersionedFileName::UnexpectedName { .. });
+
+        let error = parse_versioned_file_name(
+            &apis,
+            "versioned",
+            "versioned-1.2.3-FEEDFACE.json",
+        )
+        .unwrap_err();
+        assert_matches!(error, BadVersionedFileName::UnexpectedName { .. });
+
+        let error = parse_versioned_file_name(
+            &apis,
+            "versioned",
+            "versioned-1.2.3-.json",
+        )
+        .unwrap_err();
+        assert_matches!(error, BadVersionedFileName::UnexpectedName { .. });
+    }
+
+    #[test]
+    fn test_parse_name_versioned_unmanaged() {
+        let apis = all_apis().unwrap();
+        let error = parse_versioned_file_name(&apis, "versioned", "README.md")
+            .unwrap_err();
+        assert_matches!(error, BadVersionedFileName::Unmanaged);
+
+        // The full name is checked for Git stubs too, before the suffix is
+        // removed.
+        let error = parse_versioned_git_stub_file_name(
+            &apis,
+            "versioned",
+            "CHANGELOG.md",
+        )
+        .unwrap_err();
+        assert_matches!(error, BadVersionedFileName::Unmanaged);
+    }
+
+    #[test]
+    fn test_file_name_hash() {
+        let contents = b"{}";
+
+        // The default is the first three bytes of the SHA-256 digest.
+        let default_hash = FileNameHash::default().hash(contents);
+        assert_eq!(default_hash, "44136f");
+
+        let long = FileNameHash::new(FileNameHashAlgorithm::Sha512, 8)
+            .unwrap()
+            .hash(contents);
+        assert_eq!(long.len(), 16);
+
+        // Hashes of any supported algorithm and length are accepted.
+        assert!(hash_matches(contents, &default_hash));
+        assert!(hash_matches(contents, &long));
+        assert!(hash_matches(contents, "44136fa3"));
+        assert!(!hash_matches(contents, "feedface"));
+
+        // Hashes that are too short, or that don't consist of whole bytes,
+        // are rejected even if they're a prefix of the digest.
+        assert!(!hash_matches(contents, ""));
+        assert!(!hash_matches(contents, "4"));
+        assert!(!hash_matches(contents, "4413"));
+        assert!(!hash_matches(contents, "44136"));
+        assert!(!hash_matches(contents, "44136fa"));
+
+        FileNameHash::new(FileNameHashAlgorithm::Sha256, 0).unwrap_err();
+        FileNameHash::new(FileNameHashAlgorithm::Sha256, 2).unwrap_err();
+        FileNameHash::new(FileNameHashAlgorithm::Sha256, 33).unwrap_err();
+        FileNameHash::new(FileNameHashAlgorithm::Sha512, 64).unwrap();
+    }
+
+    #[test]
+    fn test_parse_name_versioned_long_hash() {
+        let apis = all_apis().unwrap();
+        let name = parse_versioned_file_name(
+            &apis,
+            "versioned",
+            "versioned-1.2.3-0123456789abcdef.json",
+        )
+        .unwrap();
+        assert_eq!(name.hash(), "0123456789abcdef");
     }
 
     #[test]
@@ -1193,7 +1500,7 @@ mod test {
 
     fn all_apis() -> anyhow::Result<ManagedApis> {
         let apis = vec![
-            ManagedApiConfig {
+            ManagedApi::from(ManagedApiConfig {
                 ident: "lockstep",
                 versions: Versions::Lockstep {
                     version: "1.0.0".parse().unwrap(),
@@ -1204,8 +1511,8 @@ mod test {
                     ..ManagedApiMetadata::default()
                 },
                 api_description: unimplemented_fn,
-            },
-            ManagedApiConfig {
+            }),
+            ManagedApi::from(ManagedApiConfig {
                 ident: "versioned",
                 versions: Versions::Versioned {
                     supported_versions: SupportedVersions::new(vec![
@@ -1218,7 +1525,8 @@ mod test {
                     ..ManagedApiMetadata::default()
                 },
                 api_description: unimplemented_fn,
-            },
+            })
+            .with_unmanaged_files(["*.md"])?,
         ];
 
         let apis =
@@ -5,7 +5,11 @@
 
 use crate::{
     apis::ManagedApis,
+    approvals::BreakingChangeApproval,
     environment::ErrorAccumulator,
+    json_format::to_dropshot_format,
+    pins::ConsumerPin,
+    shared_schemas::{self, join_git_stub_contents},
     spec_files_generic::{
         ApiFiles, ApiLoad, ApiSpecFile, ApiSpecFilesBuilder, AsRawFiles,
         SpecFileInfo, parse_lockstep_file_name, parse_versioned_file_name,
@@ -17,8 +21,94 @@ use anyhow::{Context, anyhow};
 use camino::{Utf8Path, Utf8PathBuf};
 use dropshot_api_manager_types::{ApiIdent, ApiSpecFileName};
 use git_stub::{GitCommitHash, GitStub};
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use rayon::prelude::*;
-use std::{collections::BTreeMap, ops::Deref};
+use std::{collections::BTreeMap, fmt, ops::Deref};
+
+/// Glob patterns for files that editors and other tools commonly leave behind,
+/// and which are always ignored within the OpenAPI documents directory.
+const DEFAULT_IGNORE_GLOBS: &[&str] = &[
+    ".DS_Store",
+    "Thumbs.db",
+    "*.orig",
+    "*.rej",
+    "*.swp",
+    "*.swo",
+    "*~",
+    ".#*",
+    // Held by `generate` while it applies fixes.
+    crate::lock::LOCK_FILE_NAME,
+];
+
+/// Glob patterns for files and directories to skip when walking a local
+/// OpenAPI documents directory.
+///
+/// A pattern matches an entry if it matches either the entry's file name or its
+/// path relative to the documents directory. For example, `*.orig` matches
+/// `foo/bar.json.orig`, and `scratch/*.json` matches only JSON files in the
+/// `scratch` directory.
+#[derive(Clone)]
+pub(crate) struct IgnoreGlobs {
+    patterns: Vec<String>,
+    set: GlobSet,
+}
+
+impl fmt::Debug for IgnoreGlobs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The compiled glob set is derived from the patterns, so don't print
+        // it.
+        f.debug_tuple("IgnoreGlobs").field(&self.patterns).finish()
+    }
+}
+
+impl IgnoreGlobs {
+    /// Returns an `IgnoreGlobs` that doesn't match anything.
+    pub(crate) fn empty() -> Self {
+        Self::new(Vec::new()).expect("an empty set of globs is valid")
+    }
+
+    /// Returns a new `IgnoreGlobs` with `patterns` added to the existing ones.
+    pub(crate) fn with_patterns<I, S>(
+        &self,
+        patterns: I,
+    ) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut all = self.patterns.clone();
+        all.extend(patterns.into_iter().map(|p| p.as_ref().to_owned()));
+        Self::new(all)
+    }
+
+    fn new(patterns: Vec<String>) -> anyhow::Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &patterns {
+            let glob = Glob::new(pattern).with_context(|| {
+                format!("invalid ignore glob {:?}", pattern)
+            })?;
+            builder.add(glob);
+        }
+        let set = builder.build().context("building ignore globs")?;
+        Ok(Self { patterns, set })
+    }
+
+    /// Returns true if the entry at `rel_path` (relative to the documents
+    /// directory) should be skipped.
+    pub(crate) fn is_ignored(&self, rel_path: &Utf8Path) -> bool {
+        self.set.is_match(rel_path)
+            || rel_path.file_name().is_some_and(|name| self.set.is_match(name))
+    }
+}
+
+impl Default for IgnoreGlobs {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_IGNORE_GLOBS.iter().map(|p| (*p).to_owned()).collect(),
+        )
+        .expect("default ignore globs are valid")
+    }
+}
 
 /// A local file that exists but couldn't be parsed.
 ///
@@ -161,6 +251,29 @@ impl AsRawFiles for Vec<LocalApiSpecFile> {
     }
 }
 
+impl ApiFiles<Vec<LocalApiSpecFile>> {
+    /// Returns true if the "latest" symlink points to a document that doesn't
+    /// exist, or that couldn't be parsed.
+    ///
+    /// Returns false if there's no "latest" symlink.
+    pub fn latest_link_is_dangling(&self) -> bool {
+        let Some(link) = self.latest_link() else {
+            return false;
+        };
+        let target = link.json_basename();
+        let found = self.versions().get(link.version()).is_some_and(|files| {
+            files.iter().any(|file| match file {
+                LocalApiSpecFile::Valid { spec, .. } => {
+                    let name = spec.spec_file_name();
+                    !name.is_git_stub() && name.json_basename() == target
+                }
+                LocalApiSpecFile::Unparseable(_) => false,
+            })
+        });
+        !found
+    }
+}
+
 /// Container for OpenAPI documents found in the local working tree.
 ///
 /// **Be sure to check for load errors and warnings before using this
@@ -172,6 +285,13 @@ impl AsRawFiles for Vec<LocalApiSpecFile> {
 pub struct LocalFiles {
     /// The loaded local files.
     files: BTreeMap<ApiIdent, ApiFiles<Vec<LocalApiSpecFile>>>,
+    /// Files and directories for APIs that aren't managed, to be deleted,
+    /// relative to the documents directory.
+    unknown_api_files: BTreeMap<ApiIdent, Vec<Utf8PathBuf>>,
+    /// Approved incompatible changes to blessed versions.
+    breaking_change_approvals: Vec<BreakingChangeApproval>,
+    /// Versions of APIs that downstream consumers are pinned to.
+    consumer_pins: Vec<ConsumerPin>,
 }
 
 impl Deref for LocalFiles {
@@ -198,19 +318,118 @@ impl LocalFiles {
         error_accumulator: &mut ErrorAccumulator,
         repo_root: &Utf8Path,
         vcs: &RepoVcs,
+        ignore_globs: &IgnoreGlobs,
     ) -> anyhow::Result<LocalFiles> {
-        let api_files =
-            walk_local_directory(dir, apis, error_accumulator, repo_root, vcs)?;
-        Ok(LocalFiles { files: api_files.into_map() })
+        let api_files = walk_local_directory(
+            dir,
+            apis,
+            error_accumulator,
+            repo_root,
+            vcs,
+            ignore_globs,
+        )?;
+        Ok(LocalFiles {
+            files: api_files.into_map(),
+            unknown_api_files: BTreeMap::new(),
+            breaking_change_approvals: Vec::new(),
+            consumer_pins: Vec::new(),
+        })
+    }
+
+    /// Records files and directories for APIs that aren't managed, which
+    /// should be deleted.
+    pub(crate) fn with_unknown_api_files(
+        mut self,
+        unknown_api_files: BTreeMap<ApiIdent, Vec<Utf8PathBuf>>,
+    ) -> Self {
+        self.unknown_api_files = unknown_api_files;
+        self
+    }
+
+    /// Returns the files and directories for APIs that aren't managed, which
+    /// should be deleted, relative to the documents directory.
+    pub(crate) fn unknown_api_files(
+        &self,
+    ) -> &BTreeMap<ApiIdent, Vec<Utf8PathBuf>> {
+        &self.unknown_api_files
+    }
+
+    /// Records the approved incompatible changes to blessed versions.
+    pub(crate) fn with_breaking_change_approvals(
+        mut self,
+        approvals: Vec<BreakingChangeApproval>,
+    ) -> Self {
+        self.breaking_change_approvals = approvals;
+        self
+    }
+
+    /// Returns the approved incompatible changes to blessed versions.
+    pub(crate) fn breaking_change_approvals(
+        &self,
+    ) -> &[BreakingChangeApproval] {
+        &self.breaking_change_approvals
+    }
+
+    /// Records the versions of APIs that downstream consumers are pinned to.
+    pub(crate) fn with_consumer_pins(mut self, pins: Vec<ConsumerPin>) -> Self {
+        self.consumer_pins = pins;
+        self
+    }
+
+    /// Returns the versions of APIs that downstream consumers are pinned to.
+    pub(crate) fn consumer_pins(&self) -> &[ConsumerPin] {
+        &self.consumer_pins
     }
 }
 
 impl From<ApiSpecFilesBuilder<'_, Vec<LocalApiSpecFile>>> for LocalFiles {
     fn from(api_files: ApiSpecFilesBuilder<Vec<LocalApiSpecFile>>) -> Self {
-        LocalFiles { files: api_files.into_map() }
+        LocalFiles {
+            files: api_files.into_map(),
+            unknown_api_files: BTreeMap::new(),
+            breaking_change_approvals: Vec::new(),
+            consumer_pins: Vec::new(),
+        }
     }
 }
 
+/// Returns the top-level entries of the documents directory `dir` that belong
+/// to APIs that aren't managed, keyed by API identifier.
+///
+/// Paths are relative to `dir`. Entries matching `ignore_globs` are skipped.
+pub(crate) fn find_unknown_api_entries(
+    dir: &Utf8Path,
+    apis: &ManagedApis,
+    ignore_globs: &IgnoreGlobs,
+) -> anyhow::Result<BTreeMap<ApiIdent, Vec<Utf8PathBuf>>> {
+    let mut entries: BTreeMap<ApiIdent, Vec<Utf8PathBuf>> = BTreeMap::new();
+    for entry in
+        dir.read_dir_utf8().with_context(|| format!("readdir {:?}", dir))?
+    {
+        let entry =
+            entry.with_context(|| format!("readdir {:?} entry", dir))?;
+        let file_name = entry.file_name();
+        if ignore_globs.is_ignored(Utf8Path::new(file_name)) {
+            continue;
+        }
+        let file_type = entry
+            .file_type()
+            .with_context(|| format!("file type of {:?}", entry.path()))?;
+        if !file_type.is_dir() && !file_type.is_file() {
+            continue;
+        }
+        if let Some(ident) =
+            apis.unmanaged_api_for_entry(file_name, file_type.is_dir())
+        {
+            entries.entry(ident).or_default().push(file_name.into());
+        }
+    }
+    for paths in entries.values_mut() {
+        paths.sort();
+    }
+    Ok(entries)
+}
+
 /// Entry discovered during the directory walk (Phase 1).
 ///
 /// No file content is read here; only metadata from `readdir` and
@@ -228,6 +447,9 @@ enum LocalDiscoveredEntry {
     /// A file matching the latest symlink pattern but not actually a
     /// symlink (e.g., corrupted by a merge conflict).
     LatestNotSymlink { path: Utf8PathBuf },
+    /// A file matching the `{ident}-latest.json.tmp` pattern, left behind by
+    /// an interrupted update of the "latest" symlink.
+    LatestSymlinkTemp { dir_basename: String },
     /// A non-fatal issue discovered during the walk.
     Warning(anyhow::Error),
     /// A fatal issue discovered during the walk.
@@ -280,6 +502,9 @@ enum LocalFileResult {
     LatestNotSymlink {
         path: Utf8PathBuf,
     },
+    LatestSymlinkTemp {
+        dir_basename: String,
+    },
 
     // --- Errors and warnings ---
     Warning(anyhow::Error),
@@ -289,11 +514,12 @@ enum LocalFileResult {
 // ---- Phase 1: sequential directory walk ----
 
 /// Walk the two-level directory structure, collecting entries without
-/// reading file contents.
+/// reading file contents. Entries matching `ignore_globs` are skipped.
 ///
 /// Returns `Err` only if the top-level `readdir` fails.
 fn discover_local_entries(
     dir: &Utf8Path,
+    ignore_globs: &IgnoreGlobs,
 ) -> anyhow::Result<Vec<LocalDiscoveredEntry>> {
     let mut entries = Vec::new();
     let top_iter =
@@ -317,6 +543,9 @@ fn discover_local_entries(
     for entry in top_entries {
         let path = entry.path().to_owned();
         let file_name = entry.file_name().to_owned();
+        if ignore_globs.is_ignored(Utf8Path::new(&file_name)) {
+            continue;
+        }
         let file_type = match entry.file_type() {
             Ok(ft) => ft,
             Err(error) => {
@@ -331,7 +560,12 @@ fn discover_local_entries(
             entries
                 .push(LocalDiscoveredEntry::TopLevelFile { file_name, path });
         } else if file_type.is_dir() {
-            discover_versioned_directory(&mut entries, &path, &file_name);
+            discover_versioned_directory(
+                &mut entries,
+                &path,
+                &file_name,
+                ignore_globs,
+            );
         } else {
             entries.push(LocalDiscoveredEntry::Warning(anyhow!(
                 "ignored (not a file or directory): {:?}",
@@ -349,6 +583,7 @@ fn discover_versioned_directory(
     out: &mut Vec<LocalDiscoveredEntry>,
     path: &Utf8Path,
     dir_basename: &str,
+    ignore_globs: &IgnoreGlobs,
 ) {
     let mut sub_entries = match path
         .read_dir_utf8()
@@ -375,6 +610,11 @@ fn discover_versioned_directory(
     for entry in sub_entries {
         let file_name = entry.file_name().to_owned();
         let entry_path = entry.path().to_owned();
+        if ignore_globs
+            .is_ignored(&Utf8Path::new(dir_basename).join(&file_name))
+        {
+            continue;
+        }
 
         if ident.versioned_api_is_latest_symlink(&file_name) {
             // Check whether it's actually a symlink.
@@ -418,6 +658,13 @@ fn discover_versioned_directory(
             continue;
         }
 
+        if ident.versioned_api_is_latest_symlink_temp(&file_name) {
+            out.push(LocalDiscoveredEntry::LatestSymlinkTemp {
+                dir_basename: dir_basename.to_owned(),
+            });
+            continue;
+        }
+
         if file_name.ends_with(".json.gitstub") {
             out.push(LocalDiscoveredEntry::GitStub {
                 dir_basename: dir_basename.to_owned(),
@@ -467,7 +714,13 @@ fn process_local_entry(
             };
 
             let result =
-                ApiSpecFile::for_contents(spec_file_name.clone(), contents);
+                to_dropshot_format(apis, spec_file_name.ident(), contents)
+                    .and_then(|contents| {
+                        ApiSpecFile::for_contents(
+                            spec_file_name.clone(),
+                            contents,
+                        )
+                    });
             LocalFileResult::LockstepDeserialized {
                 file_name: spec_file_name,
                 result,
@@ -497,8 +750,20 @@ fn process_local_entry(
                 }
             };
 
-            let result =
-                ApiSpecFile::for_contents(spec_file_name.clone(), contents);
+            // If the document's schemas are stored in the shared directory,
+            // reassemble it, and if it has a custom format, convert it back to
+            // Dropshot's. If that fails, the document is treated as
+            // unparseable.
+            let dir = path.parent().unwrap_or(Utf8Path::new(""));
+            let result = shared_schemas::join(contents, |schema| {
+                Ok(fs_err::read(dir.join(schema))?)
+            })
+            .and_then(|contents| {
+                to_dropshot_format(apis, spec_file_name.ident(), contents)
+            })
+            .and_then(|contents| {
+                ApiSpecFile::for_contents(spec_file_name.clone(), contents)
+            });
             LocalFileResult::VersionedDeserialized {
                 file_name: spec_file_name,
                 result,
@@ -557,9 +822,14 @@ fn process_local_entry(
                 };
             }
 
-            // Resolve the git stub to actual file contents.
-            let contents = match vcs.resolve_stub_contents(&git_stub, repo_root)
-            {
+            // Resolve the git stub to actual file contents, reassembling the
+            // document from the shared schemas at the same commit if needed.
+            let contents = vcs
+                .resolve_stub_contents(&git_stub, repo_root)
+                .and_then(|contents| {
+                    join_git_stub_contents(contents, &git_stub, repo_root, vcs)
+                });
+            let contents = match contents {
                 Ok(c) => c,
                 Err(error) => {
                     return LocalFileResult::GitStubUnresolvable {
@@ -576,7 +846,13 @@ fn process_local_entry(
             // Deserialize the resolved contents.
             let commit = git_stub.commit();
             let result =
-                ApiSpecFile::for_contents(spec_file_name.clone(), contents);
+                to_dropshot_format(apis, spec_file_name.ident(), contents)
+                    .and_then(|contents| {
+                        ApiSpecFile::for_contents(
+                            spec_file_name.clone(),
+                            contents,
+                        )
+                    });
             LocalFileResult::GitStubDeserialized {
                 file_name: spec_file_name,
                 result,
@@ -592,6 +868,10 @@ fn process_local_entry(
             LocalFileResult::LatestNotSymlink { path }
         }
 
+        LocalDiscoveredEntry::LatestSymlinkTemp { dir_basename } => {
+            LocalFileResult::LatestSymlinkTemp { dir_basename }
+        }
+
         LocalDiscoveredEntry::Warning(err) => LocalFileResult::Warning(err),
         LocalDiscoveredEntry::Error(err) => LocalFileResult::Error(err),
     }
@@ -632,9 +912,10 @@ pub fn walk_local_directory<'a, T: ApiLoad + AsRawFiles>(
     error_accumulator: &'a mut ErrorAccumulator,
     repo_root: &Utf8Path,
     vcs: &RepoVcs,
+    ignore_globs: &IgnoreGlobs,
 ) -> anyhow::Result<ApiSpecFilesBuilder<'a, T>> {
     // Phase 1: discover entries (sequential, fast).
-    let entries = discover_local_entries(dir)?;
+    let entries = discover_local_entries(dir, ignore_globs)?;
 
     // Phase 2: I/O + filename parse + deserialization (parallel).
     let file_results: Vec<LocalFileResult> = entries
@@ -721,6 +1002,13 @@ pub fn walk_local_directory<'a, T: ApiLoad + AsRawFiles>(
                     path
                 ));
             }
+            LocalFileResult::LatestSymlinkTemp { dir_basename } => {
+                let ident = api_files
+                    .lookup_versioned_dir(&mut seen_dirs, &dir_basename);
+                if let Some(ident) = ident {
+                    api_files.load_latest_link_temp(&ident);
+                }
+            }
             LocalFileResult::Warning(err) => {
                 api_files.load_warning(err);
             }
@@ -1,21 +1,63 @@
 // Copyright 2026 Oxide Computer Company
 
 //! Test utilities for the Dropshot API manager.
+//!
+//! With the `test-environment` feature, this also provides
+//! [`TestEnvironment`], a temporary repository for end-to-end tests.
+
+#[cfg(feature = "test-environment")]
+mod environment;
 
 pub use crate::output::CheckResult;
 #[doc(hidden)]
 pub use crate::resolved::{ProblemKind, ProblemSummary};
+#[doc(hidden)]
+pub use crate::spec_files_blessed::BlessedProvenance;
 use crate::{
     apis::ManagedApis,
     cmd::{
-        check::check_impl_with_summaries,
+        audit_unused_versions::{
+            audit_unused_versions_impl, load_observations,
+        },
+        check::{CheckRenders, check_impl_with_summaries},
+        compat_preview::compat_preview_impl,
         dispatch::{BlessedSourceArgs, GeneratedSourceArgs},
+        drift_report::drift_report_impl,
+        generate::{GenerateResult, list_generate_outputs, load_sources},
+        materialize::materialize_impl,
+        new_version::{NewVersionTargets, new_version_impl},
+        pack_baseline::pack_baseline_impl,
+        patch::{PatchFormat, patch_impl},
+        pin_stubs::pin_stubs_impl,
+        pins::pins_impl,
+        since_release::{
+            display_versions_since_release, versions_since_release,
+        },
+        slim::slim_impl,
+        status::status_impl,
+        why_blessed::why_blessed_impl,
     },
-    environment::{Environment, GeneratedSource},
-    output::OutputOpts,
-    resolved,
+    environment::{Environment, ErrorAccumulator, GeneratedSource},
+    hermetic::HermeticInputs,
+    metrics::{CheckMetrics, MetricsFormat},
+    output::{OutputOpts, Styles},
+    resolved::{self, Problem, Resolved},
+    spec_files_generic::FileNameHash,
+    validation::{ValidationContextImpl, overwrite_file},
+    vcs::VcsRevision,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use dropshot_api_manager_types::{
+    ApiIdent, ApiSpecFileName, CustomFix, LockstepApiSpecFileName,
+    ManagedApiMetadata, SupportedVersion, SupportedVersions, ValidationContext,
+    VersionedApiSpecFileName, Versions,
+};
+#[cfg(feature = "test-environment")]
+pub use environment::{
+    JjMergeResult, JjRebaseResult, MergeResult, RebaseResult, TestEnvironment,
+    VcsMode, check_jj_available, rel_path_forward_slashes,
 };
-use camino::Utf8PathBuf;
+use openapiv3::OpenAPI;
 
 /// Check that a set of APIs is up-to-date.
 ///
@@ -28,6 +70,119 @@ pub fn check_apis_up_to_date(
     Ok(result)
 }
 
+/// Like [`check_apis_up_to_date`], but also returns the warnings recorded
+/// while loading documents.
+pub fn check_apis_with_load_problems(
+    env: &Environment,
+    apis: &ManagedApis,
+) -> Result<(CheckResult, ErrorAccumulator), anyhow::Error> {
+    let env = resolve_env(env)?;
+    let (blessed_source, generated_source, output) =
+        default_sources(&env, None)?;
+    let (result, _summaries, load_problems) = check_impl_with_summaries(
+        apis,
+        &env,
+        &blessed_source,
+        &generated_source,
+        &mut CheckMetrics::new(),
+        CheckRenders::default(),
+        &output,
+    )?;
+    Ok((result, load_problems))
+}
+
+/// Like [`check_apis_up_to_date`], but also returns the metrics that
+/// `check --metrics-out` would write, in JSON format.
+#[doc(hidden)]
+pub fn check_apis_with_metrics(
+    env: &Environment,
+    apis: &ManagedApis,
+) -> Result<(CheckResult, serde_json::Value), anyhow::Error> {
+    let env = resolve_env(env)?;
+    let (blessed_source, generated_source, output) =
+        default_sources(&env, None)?;
+    let mut metrics = CheckMetrics::new();
+    let (result, _summaries, _load_problems) = check_impl_with_summaries(
+        apis,
+        &env,
+        &blessed_source,
+        &generated_source,
+        &mut metrics,
+        CheckRenders::default(),
+        &output,
+    )?;
+    let metrics = serde_json::from_str(&metrics.render(MetricsFormat::Json))?;
+    Ok((result, metrics))
+}
+
+/// Like [`check_apis_up_to_date`], but also returns the HTML report that
+/// `check --report` would write.
+#[doc(hidden)]
+pub fn check_apis_with_report(
+    env: &Environment,
+    apis: &ManagedApis,
+) -> Result<(CheckResult, String), anyhow::Error> {
+    let env = resolve_env(env)?;
+    let (blessed_source, generated_source, output) =
+        default_sources(&env, None)?;
+    let mut report = String::new();
+    let (result, _summaries, _load_problems) = check_impl_with_summaries(
+        apis,
+        &env,
+        &blessed_source,
+        &generated_source,
+        &mut CheckMetrics::new(),
+        CheckRenders { report: Some(&mut report), ..Default::default() },
+        &output,
+    )?;
+    Ok((result, report))
+}
+
+/// Pack the blessed documents into a baseline at `path`, the way the
+/// `pack-baseline` command would, returning the number of documents packed.
+#[doc(hidden)]
+pub fn pack_blessed_baseline(
+    env: &Environment,
+    apis: &ManagedApis,
+    path: &Utf8Path,
+) -> Result<usize, anyhow::Error> {
+    let env = resolve_env(env)?;
+    let (blessed_source, _generated_source, output) =
+        default_sources(&env, None)?;
+    let (contents, count) =
+        pack_baseline_impl(apis, &env, &blessed_source, &output)?;
+    let _ = overwrite_file(path, &contents)?;
+    Ok(count)
+}
+
+/// Like [`check_apis_with_summaries`], but loads blessed documents from the
+/// baseline at `path` rather than from VCS.
+#[doc(hidden)]
+pub fn check_apis_with_baseline(
+    env: &Environment,
+    apis: &ManagedApis,
+    path: &Utf8Path,
+) -> Result<(CheckResult, Vec<resolved::ProblemSummary>), anyhow::Error> {
+    let env = resolve_env(env)?;
+    let (_blessed_source, generated_source, output) =
+        default_sources(&env, None)?;
+    let blessed_source = BlessedSourceArgs {
+        baseline: Some(path.to_owned()),
+        ..default_blessed_args()
+    }
+    .to_blessed_source(&env)?;
+    let (result, summaries, _load_problems) = check_impl_with_summaries(
+        apis,
+        &env,
+        &blessed_source,
+        &generated_source,
+        &mut CheckMetrics::new(),
+        CheckRenders::default(),
+        &output,
+    )?;
+    Ok((result, summaries))
+}
+
 /// Check that a set of APIs is up-to-date, loading generated documents from
 /// the given directory instead of generating them from the API definitions.
 pub fn check_apis_with_generated_from_dir(
@@ -54,13 +209,16 @@ pub fn check_apis_with_summaries(
     let env = resolve_env(env)?;
     let (blessed_source, generated_source, output) =
         default_sources(&env, None)?;
-    check_impl_with_summaries(
+    let (result, summaries, _load_problems) = check_impl_with_summaries(
         apis,
         &env,
         &blessed_source,
         &generated_source,
+        &mut CheckMetrics::new(),
+        CheckRenders::default(),
         &output,
-    )
+    )?;
+    Ok((result, summaries))
 }
 
 /// Like [`check_apis_with_generated_from_dir`], but also returns the list
@@ -74,13 +232,534 @@ pub fn check_apis_with_generated_from_dir_and_summaries(
     let env = resolve_env(env)?;
     let (blessed_source, generated_source, output) =
         default_sources(&env, Some(generated_from_dir))?;
-    check_impl_with_summaries(
+    let (result, summaries, _load_problems) = check_impl_with_summaries(
+        apis,
+        &env,
+        &blessed_source,
+        &generated_source,
+        &mut CheckMetrics::new(),
+        CheckRenders::default(),
+        &output,
+    )?;
+    Ok((result, summaries))
+}
+
+/// Like [`check_apis_up_to_date`], but also returns the JSON document that
+/// `check --format json` would print.
+#[doc(hidden)]
+pub fn check_apis_with_json_output(
+    env: &Environment,
+    apis: &ManagedApis,
+) -> Result<(CheckResult, serde_json::Value), anyhow::Error> {
+    let env = resolve_env(env)?;
+    let (blessed_source, generated_source, output) =
+        default_sources(&env, None)?;
+    let mut json = String::new();
+    let (result, _summaries, _load_problems) = check_impl_with_summaries(
+        apis,
+        &env,
+        &blessed_source,
+        &generated_source,
+        &mut CheckMetrics::new(),
+        CheckRenders { json: Some(&mut json), ..Default::default() },
+        &output,
+    )?;
+    Ok((result, serde_json::from_str(&json)?))
+}
+
+/// Pin every commit referenced by a Git stub, or with `check`, verify that
+/// they are all reachable.
+#[doc(hidden)]
+pub fn pin_git_stub_commits(
+    env: &Environment,
+    apis: &ManagedApis,
+    check: bool,
+) -> Result<CheckResult, anyhow::Error> {
+    let env = resolve_env(env)?;
+    let output = OutputOpts::default();
+    pin_stubs_impl(apis, &env, check, &output)
+}
+
+/// Write the JSON contents of all local documents, including those stored as
+/// Git stubs, to `target_dir`.
+#[doc(hidden)]
+pub fn materialize_documents(
+    env: &Environment,
+    apis: &ManagedApis,
+    target_dir: &Utf8Path,
+) -> Result<(), anyhow::Error> {
+    let env = resolve_env(env)?;
+    let output = OutputOpts::default();
+    materialize_impl(apis, &env, target_dir, &output)
+}
+
+/// Load blessed documents the way `check` would, and return where in VCS
+/// history they were loaded from.
+#[doc(hidden)]
+pub fn blessed_provenance(
+    env: &Environment,
+    apis: &ManagedApis,
+) -> Result<Option<BlessedProvenance>, anyhow::Error> {
+    let env = resolve_env(env)?;
+    let (blessed_source, _generated_source, output) =
+        default_sources(&env, None)?;
+    let styles = output.styles(supports_color::Stream::Stderr);
+    let (blessed, _errors) = blessed_source.load(
+        &env.repo_root,
+        apis,
+        &styles,
+        &env.vcs,
+        &env.settings.ignore_globs,
+    )?;
+    Ok(blessed.provenance().cloned())
+}
+
+/// Return the paths that `generate --inputs INPUTS --list-outputs` would
+/// print.
+#[doc(hidden)]
+pub fn list_generate_outputs_with_inputs(
+    env: &Environment,
+    apis: &ManagedApis,
+    inputs: &Utf8Path,
+) -> Result<Vec<Utf8PathBuf>, anyhow::Error> {
+    let env = resolve_env(env)?;
+    let (blessed_source, generated_source, output) =
+        default_sources(&env, None)?;
+    let inputs = HermeticInputs::read(inputs, &env, false)?;
+    inputs.check_apis(apis)?;
+    let (result, outputs) = list_generate_outputs(
+        apis,
+        &env,
+        &inputs,
+        &blessed_source,
+        &generated_source,
+        &output,
+    )?;
+    match result {
+        GenerateResult::Success => Ok(outputs),
+        GenerateResult::Failures => Err(anyhow::anyhow!("generate failed")),
+    }
+}
+
+/// Return the output of the `audit-unused-versions` command as a string.
+#[doc(hidden)]
+pub fn audit_unused_versions_text(
+    apis: &ManagedApis,
+    observations: &Utf8Path,
+    days: u32,
+) -> Result<String, anyhow::Error> {
+    let observations = load_observations(observations)?;
+    let mut out = Vec::new();
+    audit_unused_versions_impl(
+        apis,
+        &observations,
+        chrono::Utc::now(),
+        days,
+        &mut out,
+    )?;
+    Ok(String::from_utf8(out)?)
+}
+
+/// Run the `pins` command and return its output as a string.
+#[doc(hidden)]
+pub fn pins_text(
+    env: &Environment,
+    apis: &ManagedApis,
+    api: Option<&str>,
+) -> Result<String, anyhow::Error> {
+    let env = resolve_env(env)?;
+    let api = api.map(ApiIdent::from);
+    let mut out = Vec::new();
+    pins_impl(apis, &env, api.as_ref(), &mut out)?;
+    Ok(String::from_utf8(out)?)
+}
+
+/// Run the `new-version` command, applying any changes, and return its output
+/// as a string.
+#[doc(hidden)]
+pub fn new_version_text(
+    env: &Environment,
+    apis: &ManagedApis,
+    api: &str,
+    label: &str,
+    api_file: Option<&Utf8Path>,
+    versions_dir: Option<&Utf8Path>,
+) -> Result<String, anyhow::Error> {
+    let env = resolve_env(env)?;
+    let (blessed_source, _generated_source, output) =
+        default_sources(&env, None)?;
+    let mut out = Vec::new();
+    new_version_impl(
+        apis,
+        &env,
+        &blessed_source,
+        &ApiIdent::from(api.to_owned()),
+        label,
+        NewVersionTargets { api_file, versions_dir, apply: true },
+        &output,
+        &mut out,
+    )?;
+    Ok(String::from_utf8(out)?)
+}
+
+/// Return the output of the `status` command as a string.
+#[doc(hidden)]
+pub fn status_text(
+    env: &Environment,
+    apis: &ManagedApis,
+) -> Result<String, anyhow::Error> {
+    let env = resolve_env(env)?;
+    let (blessed_source, generated_source, output) =
+        default_sources(&env, None)?;
+    let mut out = Vec::new();
+    status_impl(
+        apis,
+        &env,
+        &blessed_source,
+        &generated_source,
+        &output,
+        &mut out,
+    )?;
+    Ok(String::from_utf8(out)?)
+}
+
+/// Return the output of the `drift-report` command as a string.
+#[doc(hidden)]
+pub fn drift_report_text(
+    env: &Environment,
+    apis: &ManagedApis,
+) -> Result<String, anyhow::Error> {
+    let env = resolve_env(env)?;
+    let (blessed_source, generated_source, output) =
+        default_sources(&env, None)?;
+    let mut out = Vec::new();
+    drift_report_impl(
+        apis,
+        &env,
+        &blessed_source,
+        &generated_source,
+        &output,
+        &mut out,
+    )?;
+    Ok(String::from_utf8(out)?)
+}
+
+/// Return the versions that `check --since-last-release TAG` lists, as a
+/// string.
+#[doc(hidden)]
+pub fn versions_since_release_text(
+    env: &Environment,
+    apis: &ManagedApis,
+    tag: &str,
+) -> Result<String, anyhow::Error> {
+    let env = resolve_env(env)?;
+    let styles = Styles::default();
+    let tag = VcsRevision::from(tag.to_owned());
+    let new_versions = versions_since_release(apis, &env, &tag, &styles)?;
+    let mut out = Vec::new();
+    display_versions_since_release(&tag, &new_versions, &styles, &mut out)?;
+    Ok(String::from_utf8(out)?)
+}
+
+/// Return the output of the `why-blessed` command as a string.
+#[doc(hidden)]
+pub fn why_blessed_text(
+    env: &Environment,
+    apis: &ManagedApis,
+    ident: &str,
+    version: &semver::Version,
+) -> Result<String, anyhow::Error> {
+    let env = resolve_env(env)?;
+    let (blessed_source, _generated_source, output) =
+        default_sources(&env, None)?;
+    let reason = default_blessed_args().blessed_source_reason(&env);
+    let mut out = Vec::new();
+    why_blessed_impl(
+        apis,
+        &env,
+        &blessed_source,
+        &reason,
+        &ApiIdent::from(ident),
+        version,
+        &output,
+        &mut out,
+    )?;
+    Ok(String::from_utf8(out)?)
+}
+
+/// Return the reduced document that the `slim` command would write.
+#[doc(hidden)]
+pub fn slim_document(
+    env: &Environment,
+    apis: &ManagedApis,
+    ident: &str,
+    version: &semver::Version,
+    keep_tags: &[&str],
+) -> Result<Vec<u8>, anyhow::Error> {
+    let env = resolve_env(env)?;
+    let output = OutputOpts::default();
+    let keep_tags: Vec<_> = keep_tags.iter().map(|t| t.to_string()).collect();
+    slim_impl(apis, &env, &ApiIdent::from(ident), version, &keep_tags, &output)
+}
+
+/// Return the blessed versions of an API that the `compat-preview` command
+/// would report as broken by the candidate document at `candidate_path`.
+#[doc(hidden)]
+pub fn compat_preview_broken_versions(
+    env: &Environment,
+    apis: &ManagedApis,
+    ident: &str,
+    candidate_path: &Utf8Path,
+) -> Result<Vec<semver::Version>, anyhow::Error> {
+    let env = resolve_env(env)?;
+    let (blessed_source, _, output) = default_sources(&env, None)?;
+    let results = compat_preview_impl(
+        apis,
+        &env,
+        &blessed_source,
+        &ApiIdent::from(ident),
+        candidate_path,
+        &output,
+    )?;
+    Ok(results
+        .into_iter()
+        .filter(|(_, issues)| !issues.is_empty())
+        .map(|(version, _)| version)
+        .collect())
+}
+
+/// Return the hashes identifying the compatibility issues between a blessed
+/// version of an API and the document generated for it, as used to approve
+/// them with [`BreakingChangeApprovals`]. Issues that are already approved
+/// aren't included.
+///
+/// [`BreakingChangeApprovals`]: crate::BreakingChangeApprovals
+#[doc(hidden)]
+pub fn blessed_version_issue_hashes(
+    env: &Environment,
+    apis: &ManagedApis,
+    ident: &str,
+    version: &str,
+) -> Result<Vec<String>, anyhow::Error> {
+    let env = resolve_env(env)?;
+    let (blessed_source, generated_source, _) = default_sources(&env, None)?;
+    let (generated, local_files, blessed) = load_sources(
         apis,
         &env,
         &blessed_source,
         &generated_source,
+        &Styles::default(),
+    )?;
+    let resolved =
+        Resolved::new(&env, apis, &blessed, &generated, &local_files);
+    let resolution = resolved
+        .resolution_for_api_version(&ApiIdent::from(ident), &version.parse()?)
+        .ok_or_else(|| anyhow::anyhow!("no such API version"))?;
+    Ok(resolution
+        .problems()
+        .filter_map(|problem| match problem {
+            Problem::BlessedVersionBroken { compatibility_issues } => {
+                Some(compatibility_issues)
+            }
+            _ => None,
+        })
+        .flatten()
+        .map(|issue| issue.approval_hash())
+        .collect())
+}
+
+/// Return the patch from the latest blessed version of an API to the document
+/// generated for that version: a JSON Merge Patch if `merge` is true, and a
+/// JSON Patch otherwise.
+#[doc(hidden)]
+pub fn blessed_latest_patch(
+    env: &Environment,
+    apis: &ManagedApis,
+    ident: &str,
+    merge: bool,
+) -> Result<serde_json::Value, anyhow::Error> {
+    let env = resolve_env(env)?;
+    let (blessed_source, generated_source, output) =
+        default_sources(&env, None)?;
+    let format =
+        if merge { PatchFormat::MergePatch } else { PatchFormat::JsonPatch };
+    let contents = patch_impl(
+        apis,
+        &env,
+        &blessed_source,
+        &generated_source,
+        &ApiIdent::from(ident),
+        format,
         &output,
-    )
+    )?;
+    Ok(serde_json::from_slice(&contents)?)
+}
+
+/// A harness for unit testing validation functions, such as those passed to
+/// [`ManagedApis::with_validation`] or registered as an API's extra
+/// validation.
+///
+/// The harness calls a validation function directly on a document, with a
+/// synthetic [`ValidationContext`], and captures everything the function
+/// reports. There's no need to set up a repository or generate documents.
+///
+/// By default, the context describes the latest (and only) version, 1.0.0, of
+/// a lockstep API. Use [`ValidationHarness::with_version`] to describe a
+/// version of a versioned API instead.
+///
+/// ```
+/// use dropshot_api_manager::test_util::ValidationHarness;
+///
+/// # let document: openapiv3::OpenAPI = serde_json::from_str(
+/// #     r#"{"openapi": "3.0.3", "info": {"title": "t", "version": "1.0.0"},
+/// #        "paths": {}}"#,
+/// # ).unwrap();
+/// let output = ValidationHarness::new("my-api")
+///     .with_version(semver::Version::new(2, 0, 0))
+///     .with_blessed(false)
+///     .run(&document, |_document, mut cx| {
+///         if cx.is_blessed() == Some(false) {
+///             cx.record_file_contents("docs/my-api.md", b"# My API".to_vec());
+///         }
+///     });
+/// assert!(output.errors().is_empty());
+/// assert_eq!(output.files().len(), 1);
+/// ```
+///
+/// [`ManagedApis::with_validation`]: crate::ManagedApis::with_validation
+#[derive(Clone, Debug)]
+pub struct ValidationHarness {
+    ident: ApiIdent,
+    version: Option<semver::Version>,
+    is_latest: bool,
+    is_blessed: bool,
+    title: &'static str,
+    metadata: ManagedApiMetadata,
+}
+
+impl ValidationHarness {
+    /// Creates a harness for the API `ident`.
+    pub fn new(ident: impl Into<String>) -> Self {
+        Self {
+            ident: ApiIdent::from(ident.into()),
+            version: None,
+            is_latest: true,
+            is_blessed: false,
+            title: "Test API",
+            metadata: ManagedApiMetadata::default(),
+        }
+    }
+
+    /// Describes `version` of a versioned API, rather than a lockstep API.
+    ///
+    /// The API supports only this version.
+    pub fn with_version(mut self, version: semver::Version) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Sets whether the document is the latest version of the API. The
+    /// default is true.
+    pub fn with_latest(mut self, is_latest: bool) -> Self {
+        self.is_latest = is_latest;
+        self
+    }
+
+    /// Sets whether the version is blessed. The default is false.
+    ///
+    /// This only applies to versioned APIs: for lockstep APIs,
+    /// [`ValidationContext::is_blessed`] always returns `None`.
+    pub fn with_blessed(mut self, is_blessed: bool) -> Self {
+        self.is_blessed = is_blessed;
+        self
+    }
+
+    /// Sets the title of the API.
+    pub fn with_title(mut self, title: &'static str) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Sets the metadata of the API.
+    pub fn with_metadata(mut self, metadata: ManagedApiMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Calls `validation` on `document`, and returns what it reported.
+    pub fn run<F>(&self, document: &OpenAPI, validation: F) -> ValidationOutput
+    where
+        F: FnOnce(&OpenAPI, ValidationContext<'_>),
+    {
+        let (file_name, versions, is_blessed) = match &self.version {
+            None => (
+                ApiSpecFileName::from(LockstepApiSpecFileName::new(
+                    self.ident.clone(),
+                )),
+                Versions::new_lockstep(semver::Version::new(1, 0, 0)),
+                None,
+            ),
+            Some(version) => {
+                let contents = serde_json::to_vec_pretty(document)
+                    .expect("serializing an OpenAPI document succeeds");
+                (
+                    ApiSpecFileName::from(VersionedApiSpecFileName::new(
+                        self.ident.clone(),
+                        version.clone(),
+                        FileNameHash::default().hash(&contents),
+                    )),
+                    Versions::new_versioned(SupportedVersions::new(vec![
+                        SupportedVersion::new(version.clone(), "TEST"),
+                    ])),
+                    Some(self.is_blessed),
+                )
+            }
+        };
+        let mut backend = ValidationContextImpl {
+            ident: self.ident.clone(),
+            file_name,
+            versions,
+            is_latest: self.is_latest,
+            is_blessed,
+            title: self.title,
+            metadata: self.metadata.clone(),
+            errors: Vec::new(),
+            files: Vec::new(),
+            custom_fixes: Vec::new(),
+        };
+        validation(document, ValidationContext::new(&mut backend));
+        ValidationOutput {
+            errors: backend.errors,
+            files: backend.files,
+            fixes: backend.custom_fixes,
+        }
+    }
+}
+
+/// What a validation function reported, as returned by
+/// [`ValidationHarness::run`].
+#[derive(Debug)]
+pub struct ValidationOutput {
+    errors: Vec<anyhow::Error>,
+    files: Vec<(Utf8PathBuf, Vec<u8>)>,
+    fixes: Vec<CustomFix>,
+}
+
+impl ValidationOutput {
+    /// Returns the errors reported, in order.
+    pub fn errors(&self) -> &[anyhow::Error] {
+        &self.errors
+    }
+
+    /// Returns the files recorded, in order, along with their contents.
+    pub fn files(&self) -> &[(Utf8PathBuf, Vec<u8>)] {
+        &self.files
+    }
+
+    /// Returns the fixable problems reported, in order.
+    pub fn fixes(&self) -> &[CustomFix] {
+        &self.fixes
+    }
 }
 
 fn resolve_env(
@@ -99,14 +778,15 @@ fn default_sources(
     (crate::environment::BlessedSource, GeneratedSource, OutputOpts),
     anyhow::Error,
 > {
-    let blessed_source = BlessedSourceArgs {
-        blessed_from_vcs: None,
-        blessed_from_vcs_path: None,
-        blessed_from_dir: None,
-    }
-    .to_blessed_source(env)?;
-    let generated_source =
-        GeneratedSource::from(GeneratedSourceArgs { generated_from_dir });
-    let output = OutputOpts { color: clap::ColorChoice::Auto };
+    let blessed_source = default_blessed_args().to_blessed_source(env)?;
+    let generated_source = GeneratedSource::from(GeneratedSourceArgs {
+        generated_from_dir,
+        verify_deterministic: false,
+    });
+    let output = OutputOpts::default();
     Ok((blessed_source, generated_source, output))
 }
+
+fn default_blessed_args() -> BlessedSourceArgs {
+    BlessedSourceArgs::default()
+}
@@ -1,13 +1,20 @@
 // Copyright 2026 Oxide Computer Company
 
-//! Test environment infrastructure for integration tests.
-
+//! A temporary workspace for end-to-end tests of the API manager.
+//!
+//! [`TestEnvironment`] sets up a scratch Git or Jujutsu repository with a
+//! documents directory, and provides helpers to commit changes, run the API
+//! manager's commands against it, and inspect the resulting documents. It's
+//! used by this crate's own integration tests, and is available to downstream
+//! repositories (which typically want to test their own wiring of the API
+//! manager) with the `test-environment` feature.
+
+use crate::{Environment, FileNameHash, ManagedApis};
 use anyhow::{Context, Result, anyhow, bail};
 use camino::{Utf8Path, Utf8PathBuf};
 use camino_tempfile::Utf8TempDir;
 use camino_tempfile_ext::{fixture::ChildPath, prelude::*};
 use clap::Parser;
-use dropshot_api_manager::{Environment, ManagedApis};
 use git_stub::GitStub;
 use git_stub_vcs::Vcs;
 use std::{
@@ -87,10 +94,10 @@ pub enum VcsMode {
     Git,
     /// A Jujutsu repository.
     ///
-    /// Currently, tests always create non-colocated repositories, under the
-    /// assumption that if the tool works for Git repositories and for
-    /// non-colocated Jujutsu repositories, it also works for colocated Jujutsu
-    /// repositories.
+    /// Most tests create non-colocated repositories, so that operations are
+    /// forced to go through jj. Colocated repositories (created with
+    /// [`TestEnvironment::new_jj_colocated`]) are tested separately, since Git
+    /// commands also work in them but see different state.
     Jj {
         /// The currently active bookmark. When commits are made, this bookmark
         /// is advanced to the new commit.
@@ -177,6 +184,16 @@ impl TestEnvironment {
     /// The non-colocated nature of the Jujutsu repository means that operations
     /// are forced to go through jj.
     pub fn new_jj() -> Result<Self> {
+        Self::new_jj_impl(false)
+    }
+
+    /// Create a new test environment with a Jujutsu repository colocated with
+    /// Git.
+    pub fn new_jj_colocated() -> Result<Self> {
+        Self::new_jj_impl(true)
+    }
+
+    fn new_jj_impl(colocate: bool) -> Result<Self> {
         let temp_dir = Utf8TempDir::with_prefix("dropshot-api-manager-jj-")
             .context("failed to create temporary directory")?;
 
@@ -185,9 +202,11 @@ impl TestEnvironment {
         let workspace_root = temp_dir.child("workspace");
         let documents_dir = workspace_root.child("documents");
 
+        let colocate_arg =
+            if colocate { "--colocate" } else { "--no-colocate" };
         Self::run_jj_command(
             workspace_root.as_path(),
-            &["git", "init", "--no-colocate"],
+            &["git", "init", colocate_arg],
         )?;
 
         Self::run_jj_command(
@@ -250,6 +269,29 @@ impl TestEnvironment {
         &self.environment
     }
 
+    /// Replace the underlying environment, e.g. to change the default blessed
+    /// branch.
+    pub fn set_environment(
+        &mut self,
+        f: impl FnOnce(Environment) -> Environment,
+    ) {
+        self.environment = f(self.environment.clone());
+    }
+
+    /// Set how the hash in versioned document file names is computed.
+    pub fn set_file_name_hash(&mut self, file_name_hash: FileNameHash) {
+        self.environment =
+            self.environment.clone().with_file_name_hash(file_name_hash);
+    }
+
+    /// Skip files and directories matching `patterns` within the documents
+    /// directory.
+    pub fn set_ignore_globs(&mut self, patterns: &[&str]) -> Result<()> {
+        self.environment =
+            self.environment.clone().with_ignore_globs(patterns)?;
+        Ok(())
+    }
+
     /// Create a file within the workspace.
     pub fn create_file(
         &self,
@@ -541,6 +583,15 @@ impl TestEnvironment {
         Ok(())
     }
 
+    /// Move a file or directory with `git mv`.
+    pub fn git_mv(&self, from: &Utf8Path, to: &Utf8Path) -> Result<()> {
+        Self::run_git_command(
+            &self.workspace_root,
+            &["mv", from.as_str(), to.as_str()],
+        )?;
+        Ok(())
+    }
+
     /// Commit staged changes to git.
     pub fn git_commit(&self, message: &str) -> Result<()> {
         Self::run_git_command(
@@ -550,6 +601,20 @@ impl TestEnvironment {
         Ok(())
     }
 
+    /// Add this repository as a Git remote of itself named `name`, and fetch
+    /// from it. This creates remote-tracking branches like `name/main`.
+    ///
+    /// This uses Git directly, so it's only meaningful for Git repositories and
+    /// Jujutsu repositories colocated with Git.
+    pub fn add_git_remote_to_self(&self, name: &str) -> Result<()> {
+        Self::run_git_command(
+            &self.workspace_root,
+            &["remote", "add", name, self.workspace_root.as_str()],
+        )?;
+        Self::run_git_command(&self.workspace_root, &["fetch", name])?;
+        Ok(())
+    }
+
     /// Commit documents (for blessed document workflow testing).
     ///
     /// In git mode, stages and commits all files in the documents directory. In
@@ -741,6 +806,25 @@ impl TestEnvironment {
         })
     }
 
+    /// Exclude the documents directory from the working tree using a
+    /// (non-cone) Git sparse checkout.
+    pub fn sparse_checkout_exclude_documents(&self) -> Result<()> {
+        Self::run_git_command(
+            &self.workspace_root,
+            &["sparse-checkout", "set", "--no-cone", "/*", "!/documents/"],
+        )?;
+        Ok(())
+    }
+
+    /// List Git refs under the given prefix (e.g. `refs/openapi/pins/`).
+    pub fn list_git_refs(&self, prefix: &str) -> Result<Vec<String>> {
+        let output = Self::run_git_command(
+            &self.workspace_root,
+            &["for-each-ref", "--format=%(refname)", prefix],
+        )?;
+        Ok(output.lines().map(|line| line.to_owned()).collect())
+    }
+
     /// Create a new branch at the current HEAD.
     pub fn create_branch(&self, name: &str) -> Result<()> {
         match self.vcs_mode {
@@ -1032,7 +1116,7 @@ impl TestEnvironment {
     /// Generate documents without committing (useful for lockstep APIs).
     pub fn generate_documents(&self, apis: &ManagedApis) -> Result<()> {
         let args = ["bin", "generate"];
-        let app = dropshot_api_manager::App::try_parse_from(args)?;
+        let app = crate::App::try_parse_from(args)?;
 
         if app.exec(&self.environment, apis) == ExitCode::SUCCESS {
             Ok(())
@@ -1041,6 +1125,47 @@ impl TestEnvironment {
         }
     }
 
+    /// Run the `generate` command with additional arguments.
+    pub fn generate_documents_with_args(
+        &self,
+        apis: &ManagedApis,
+        extra_args: &[&str],
+    ) -> Result<()> {
+        let args = ["bin", "generate"].iter().chain(extra_args);
+        let app = crate::App::try_parse_from(args)?;
+
+        if app.exec(&self.environment, apis) == ExitCode::SUCCESS {
+            Ok(())
+        } else {
+            Err(anyhow!("failed to generate documents"))
+        }
+    }
+
+    /// Run the `check` command with additional arguments, returning its exit
+    /// code.
+    pub fn check_documents_with_args(
+        &self,
+        apis: &ManagedApis,
+        extra_args: &[&str],
+    ) -> Result<ExitCode> {
+        let args = ["bin", "check"].iter().chain(extra_args);
+        let app = crate::App::try_parse_from(args)?;
+        Ok(app.exec(&self.environment, apis))
+    }
+
+    /// List the paths of the Git worktrees attached to this repository.
+    pub fn list_git_worktrees(&self) -> Result<Vec<String>> {
+        let output = Self::run_git_command(
+            &self.workspace_root,
+            &["worktree", "list", "--porcelain"],
+        )?;
+        Ok(output
+            .lines()
+            .filter_map(|line| line.strip_prefix("worktree "))
+            .map(|path| path.to_owned())
+            .collect())
+    }
+
     fn collect_files_recursive(
         &self,
         dir: &Utf8Path,
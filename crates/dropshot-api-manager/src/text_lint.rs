@@ -0,0 +1,225 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Lints the summaries and descriptions in OpenAPI documents.
+//!
+//! See [`TextLint`] for more.
+
+use crate::compatibility::escape_json_pointer;
+use anyhow::anyhow;
+use dropshot_api_manager_types::ValidationContext;
+use openapiv3::OpenAPI;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Common misspellings, and their corrections.
+const COMMON_MISSPELLINGS: &[(&str, &str)] = &[
+    ("accomodate", "accommodate"),
+    ("acheive", "achieve"),
+    ("adress", "address"),
+    ("agian", "again"),
+    ("alot", "a lot"),
+    ("aquire", "acquire"),
+    ("arguement", "argument"),
+    ("begining", "beginning"),
+    ("beleive", "believe"),
+    ("calender", "calendar"),
+    ("definately", "definitely"),
+    ("enviroment", "environment"),
+    ("existant", "existent"),
+    ("occured", "occurred"),
+    ("occurence", "occurrence"),
+    ("paramater", "parameter"),
+    ("recieve", "receive"),
+    ("reponse", "response"),
+    ("retreive", "retrieve"),
+    ("seperate", "separate"),
+    ("succesful", "successful"),
+    ("sucessful", "successful"),
+    ("teh", "the"),
+    ("untill", "until"),
+    ("wich", "which"),
+];
+
+/// Lints the summaries and descriptions in an API's OpenAPI documents.
+///
+/// Attach this to an API with [`ManagedApi::with_text_lint`]. Every summary
+/// and description in a document (of operations, parameters, schemas, tags,
+/// and so on) is then checked as part of validation, and any issue fails
+/// validation. Blessed documents can't be changed, so they aren't linted.
+///
+/// The checks are:
+///
+/// * Spelling: words from a dictionary of common misspellings, which can be
+///   extended with [`TextLint::with_misspelling`], are reported along with
+///   their correction.
+/// * Sentence case, if enabled with [`TextLint::with_sentence_case`]: text
+///   must not start with a lowercase letter.
+/// * Length, if limited with [`TextLint::with_max_summary_length`]: summaries
+///   must be at most this many characters long.
+///
+/// [`ManagedApi::with_text_lint`]: crate::ManagedApi::with_text_lint
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TextLint {
+    misspellings: BTreeMap<String, String>,
+    sentence_case: bool,
+    max_summary_length: Option<usize>,
+}
+
+impl Default for TextLint {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextLint {
+    /// Creates a lint that only checks for common misspellings.
+    pub fn new() -> Self {
+        Self {
+            misspellings: COMMON_MISSPELLINGS
+                .iter()
+                .map(|(wrong, right)| {
+                    ((*wrong).to_owned(), (*right).to_owned())
+                })
+                .collect(),
+            sentence_case: false,
+            max_summary_length: None,
+        }
+    }
+
+    /// Adds `wrong` (matched case-insensitively against whole words) to the
+    /// dictionary of misspellings, with the correction `right`.
+    pub fn with_misspelling(
+        mut self,
+        wrong: impl AsRef<str>,
+        right: impl Into<String>,
+    ) -> Self {
+        self.misspellings.insert(wrong.as_ref().to_lowercase(), right.into());
+        self
+    }
+
+    /// Requires summaries and descriptions to not start with a lowercase
+    /// letter.
+    pub fn with_sentence_case(mut self) -> Self {
+        self.sentence_case = true;
+        self
+    }
+
+    /// Limits summaries to `max` characters.
+    pub fn with_max_summary_length(mut self, max: usize) -> Self {
+        self.max_summary_length = Some(max);
+        self
+    }
+
+    /// Returns the dictionary of misspellings, mapping each (lowercase)
+    /// misspelling to its correction.
+    pub fn misspellings(&self) -> &BTreeMap<String, String> {
+        &self.misspellings
+    }
+
+    /// Returns true if sentence case is required.
+    pub fn requires_sentence_case(&self) -> bool {
+        self.sentence_case
+    }
+
+    /// Returns the maximum length of summaries, if any.
+    pub fn max_summary_length(&self) -> Option<usize> {
+        self.max_summary_length
+    }
+
+    /// Lints the document, reporting each issue as a validation error.
+    pub(crate) fn validate(
+        &self,
+        openapi: &OpenAPI,
+        mut cx: ValidationContext<'_>,
+    ) {
+        if cx.is_blessed() == Some(true) {
+            return;
+        }
+
+        let document = match serde_json::to_value(openapi) {
+            Ok(document) => document,
+            Err(error) => {
+                cx.report_error(
+                    anyhow!(error).context("serializing document to lint it"),
+                );
+                return;
+            }
+        };
+        let mut issues = Vec::new();
+        self.lint_value(&document, "", &mut issues);
+        for issue in issues {
+            cx.report_error(anyhow!(issue));
+        }
+    }
+
+    /// Lints every summary and description within `value`, found at the JSON
+    /// pointer `pointer`.
+    fn lint_value(
+        &self,
+        value: &Value,
+        pointer: &str,
+        issues: &mut Vec<String>,
+    ) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map {
+                    let child =
+                        format!("{}/{}", pointer, escape_json_pointer(key));
+                    match (key.as_str(), value) {
+                        // Examples and defaults are data, not documentation.
+                        ("example" | "examples" | "default" | "enum", _) => {}
+                        ("summary", Value::String(text)) => {
+                            self.lint_text(&child, text, true, issues)
+                        }
+                        ("description", Value::String(text)) => {
+                            self.lint_text(&child, text, false, issues)
+                        }
+                        _ => self.lint_value(value, &child, issues),
+                    }
+                }
+            }
+            Value::Array(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    self.lint_value(value, &format!("{pointer}/{i}"), issues);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn lint_text(
+        &self,
+        pointer: &str,
+        text: &str,
+        is_summary: bool,
+        issues: &mut Vec<String>,
+    ) {
+        for word in text.split(|c: char| !c.is_alphanumeric() && c != '\'') {
+            if let Some(right) = self.misspellings.get(&word.to_lowercase()) {
+                issues.push(format!(
+                    "{pointer}: {word:?} is misspelled (did you mean \
+                     {right:?}?)"
+                ));
+            }
+        }
+
+        if self.sentence_case
+            && text.trim_start().chars().next().is_some_and(char::is_lowercase)
+        {
+            issues.push(format!(
+                "{pointer}: text should start with an uppercase letter"
+            ));
+        }
+
+        if is_summary
+            && let Some(max) = self.max_summary_length
+            && text.chars().count() > max
+        {
+            issues.push(format!(
+                "{pointer}: summary is {} characters long (the maximum is \
+                 {max})",
+                text.chars().count(),
+            ));
+        }
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Maintains a sidecar file mapping schema names to hand-written Rust types.
+//!
+//! See [`TypeReplacements`] for more.
+
+use camino::{Utf8Path, Utf8PathBuf};
+use dropshot_api_manager_types::ValidationContext;
+use openapiv3::OpenAPI;
+use std::collections::BTreeMap;
+
+/// Maps schema names in an API's OpenAPI document to hand-written Rust types,
+/// for client generators like Progenitor (its `replace` setting).
+///
+/// Attach these to an API with [`ManagedApi::with_type_replacements`]. The
+/// mapping is then written to a sidecar file, as a JSON object from schema
+/// name to Rust type path. The sidecar file is checked and generated like any
+/// other extra file, for the latest version of the API. If a schema named here
+/// no longer exists in the latest document, validation fails, so a stale
+/// replacement doesn't silently stop applying.
+///
+/// [`ManagedApi::with_type_replacements`]: crate::ManagedApi::with_type_replacements
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TypeReplacements {
+    path: Utf8PathBuf,
+    replacements: BTreeMap<String, String>,
+}
+
+impl TypeReplacements {
+    /// Creates an empty set of replacements, written to `path` (relative to
+    /// the repository root).
+    pub fn new(path: impl Into<Utf8PathBuf>) -> Self {
+        Self { path: path.into(), replacements: BTreeMap::new() }
+    }
+
+    /// Replaces the schema named `schema` with the Rust type at `rust_type`
+    /// (e.g. `my_types::Name`).
+    pub fn with_replacement(
+        mut self,
+        schema: impl Into<String>,
+        rust_type: impl Into<String>,
+    ) -> Self {
+        self.replacements.insert(schema.into(), rust_type.into());
+        self
+    }
+
+    /// Returns the path to the sidecar file, relative to the repository root.
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+
+    /// Returns the replacements, keyed by schema name.
+    pub fn replacements(&self) -> &BTreeMap<String, String> {
+        &self.replacements
+    }
+
+    /// Checks that every replaced schema exists in the latest document, and
+    /// records the contents of the sidecar file.
+    pub(crate) fn validate(
+        &self,
+        openapi: &OpenAPI,
+        mut cx: ValidationContext<'_>,
+    ) {
+        if !cx.is_latest() {
+            return;
+        }
+
+        let schemas = openapi.components.as_ref().map(|c| &c.schemas);
+        for (schema, rust_type) in &self.replacements {
+            if !schemas.is_some_and(|s| s.contains_key(schema)) {
+                cx.report_error(anyhow::anyhow!(
+                    "type replacement {schema:?} => {rust_type:?} (in {}) \
+                     refers to a schema that doesn't exist in the latest \
+                     document",
+                    self.path,
+                ));
+            }
+        }
+
+        cx.record_file_contents(self.path.clone(), self.file_contents());
+    }
+
+    fn file_contents(&self) -> Vec<u8> {
+        let mut contents = serde_json::to_vec_pretty(&self.replacements)
+            .expect("serializing a map of strings always succeeds");
+        contents.push(b'\n');
+        contents
+    }
+}
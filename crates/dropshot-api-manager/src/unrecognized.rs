@@ -0,0 +1,144 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Finding untracked files in the documents directory that the API manager
+//! doesn't recognize.
+//!
+//! By default, `generate` skips over such files (with a warning), or deletes
+//! them if they look like documents it manages. With
+//! [`Environment::with_strict_documents_dir`], it refuses to run while any
+//! exist instead, so that it never acts on files nobody has looked at.
+//!
+//! [`Environment::with_strict_documents_dir`]: crate::Environment::with_strict_documents_dir
+
+use crate::{
+    apis::ManagedApis,
+    environment::ResolvedEnv,
+    shared_schemas::SHARED_SCHEMAS_DIR,
+    spec_files_generic::{
+        parse_lockstep_file_name, parse_versioned_file_name,
+        parse_versioned_git_stub_file_name,
+    },
+};
+use anyhow::bail;
+use camino::{Utf8Path, Utf8PathBuf};
+use dropshot_api_manager_types::ApiIdent;
+use std::fmt::Write;
+
+/// Fails if the documents directory contains untracked files that aren't
+/// recognized.
+pub(crate) fn check_unrecognized_files(
+    env: &ResolvedEnv,
+    apis: &ManagedApis,
+) -> anyhow::Result<()> {
+    let unrecognized = find_unrecognized_files(env, apis)?;
+    if unrecognized.is_empty() {
+        return Ok(());
+    }
+
+    let mut message = String::from(
+        "refusing to update documents: the documents directory contains \
+         untracked files that the API manager doesn't recognize:\n",
+    );
+    for path in &unrecognized {
+        writeln!(message, "  {}", path).unwrap();
+    }
+    message.push_str(
+        "remove them, or allow them with `Environment::with_ignore_globs` or \
+         `ManagedApi::with_unmanaged_files`; to proceed anyway, pass \
+         --allow-dirty-docs",
+    );
+    bail!(message);
+}
+
+/// Returns the untracked files in the documents directory that aren't
+/// recognized, as paths relative to it.
+///
+/// Files matching the environment's ignore globs are allowed. In Jujutsu
+/// repositories, and outside version control, every file counts as untracked.
+fn find_unrecognized_files(
+    env: &ResolvedEnv,
+    apis: &ManagedApis,
+) -> anyhow::Result<Vec<Utf8PathBuf>> {
+    let documents_dir = env.openapi_abs_dir();
+    let candidates =
+        match env.vcs.untracked_files(&env.repo_root, env.openapi_rel_dir())? {
+            Some(untracked) => untracked
+                .into_iter()
+                .filter_map(|path| {
+                    path.strip_prefix(env.openapi_rel_dir())
+                        .ok()
+                        .map(Utf8Path::to_owned)
+                })
+                .collect(),
+            None => list_files(documents_dir)?,
+        };
+    Ok(candidates
+        .into_iter()
+        .filter(|path| {
+            !env.settings.ignore_globs.is_ignored(path)
+                && !is_recognized(apis, path)
+        })
+        .collect())
+}
+
+/// Returns true if `path`, relative to the documents directory, is a file that
+/// the API manager maintains.
+fn is_recognized(apis: &ManagedApis, path: &Utf8Path) -> bool {
+    let components: Vec<&str> = path.iter().collect();
+    match components[..] {
+        [file_name] => {
+            parse_lockstep_file_name(apis, file_name).is_ok()
+                || file_name
+                    .strip_suffix(".json")
+                    .is_some_and(|ident| is_alias(apis, ident))
+        }
+        [dir, file_name] => {
+            if is_alias(apis, dir) {
+                return true;
+            }
+            let Some(api) = apis.api(&ApiIdent::from(dir)) else {
+                return false;
+            };
+            api.is_versioned()
+                && (parse_versioned_file_name(apis, dir, file_name).is_ok()
+                    || parse_versioned_git_stub_file_name(apis, dir, file_name)
+                        .is_ok()
+                    || file_name == api.ident().versioned_api_latest_symlink()
+                    || file_name
+                        == api.ident().versioned_api_latest_symlink_temp()
+                    || api.is_unmanaged_file(file_name))
+        }
+        [dir, subdir, ..] => {
+            is_alias(apis, dir)
+                || (subdir == SHARED_SCHEMAS_DIR
+                    && apis.api(&ApiIdent::from(dir)).is_some())
+        }
+        [] => false,
+    }
+}
+
+fn is_alias(apis: &ManagedApis, ident: &str) -> bool {
+    apis.is_alias(&ApiIdent::from(ident))
+}
+
+/// Lists the files (and symlinks) under `dir`, relative to it.
+fn list_files(dir: &Utf8Path) -> anyhow::Result<Vec<Utf8PathBuf>> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+    let mut dirs = vec![dir.to_owned()];
+    while let Some(current) = dirs.pop() {
+        for entry in current.read_dir_utf8()? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                dirs.push(entry.into_path());
+            } else {
+                // unwrap(): the entry is within `dir`.
+                files.push(entry.path().strip_prefix(dir).unwrap().to_owned());
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
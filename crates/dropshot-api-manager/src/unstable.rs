@@ -0,0 +1,238 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Support for unstable operations, which are excluded from comparisons with
+//! blessed documents.
+//!
+//! An operation is unstable if it has the `x-unstable: true` vendor extension,
+//! if it carries the API's unstable tag (see
+//! [`ManagedApi::with_unstable_tag`]), or if its path is under one of the API's
+//! unstable path prefixes (see [`ManagedApi::with_unstable_path_prefix`]).
+//!
+//! [`ManagedApi::with_unstable_tag`]: crate::ManagedApi::with_unstable_tag
+//! [`ManagedApi::with_unstable_path_prefix`]: crate::ManagedApi::with_unstable_path_prefix
+
+use crate::compatibility::PATH_ITEM_METHODS;
+use serde_json::Value;
+
+/// The vendor extension that marks an operation as unstable.
+pub const UNSTABLE_EXTENSION: &str = "x-unstable";
+
+/// The criteria for which of an API's operations are unstable, beyond the
+/// `x-unstable` extension.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct UnstableOperations<'a> {
+    pub(crate) tag: Option<&'a str>,
+    pub(crate) path_prefixes: &'a [String],
+}
+
+impl UnstableOperations<'_> {
+    fn is_unstable(&self, path: &str, operation: &Value) -> bool {
+        if operation.get(UNSTABLE_EXTENSION) == Some(&Value::Bool(true)) {
+            return true;
+        }
+        if self.is_exempt_path(path) {
+            return true;
+        }
+        let Some(unstable_tag) = self.tag else {
+            return false;
+        };
+        operation
+            .get("tags")
+            .and_then(Value::as_array)
+            .is_some_and(|tags| tags.iter().any(|t| t == unstable_tag))
+    }
+
+    /// Returns true if `path` is under one of the unstable path prefixes.
+    fn is_exempt_path(&self, path: &str) -> bool {
+        self.path_prefixes.iter().any(|prefix| {
+            let prefix = prefix.trim_end_matches('/');
+            path.strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    }
+}
+
+/// Returns a copy of the OpenAPI document `doc` with all unstable operations
+/// removed, or `None` if it has no unstable operations.
+///
+/// Path items left without any operations are removed as well. Schemas that
+/// are only referenced by unstable operations are left in place: they can't
+/// affect the compatibility of the remaining operations.
+pub(crate) fn without_unstable_operations(
+    doc: &Value,
+    unstable: UnstableOperations<'_>,
+) -> Option<Value> {
+    let paths = doc.get("paths")?.as_object()?;
+    let any_unstable = paths.iter().any(|(path, item)| {
+        operations(item).any(|(_, op)| unstable.is_unstable(path, op))
+    });
+    if !any_unstable {
+        return None;
+    }
+
+    let mut doc = doc.clone();
+    let paths = doc
+        .get_mut("paths")
+        .and_then(Value::as_object_mut)
+        .expect("paths was checked above");
+    paths.retain(|path, item| {
+        let Some(item) = item.as_object_mut() else {
+            return true;
+        };
+        item.retain(|key, op| {
+            !(PATH_ITEM_METHODS.contains(&key.as_str())
+                && unstable.is_unstable(path, op))
+        });
+        PATH_ITEM_METHODS.iter().any(|key| item.contains_key(*key))
+    });
+    Some(doc)
+}
+
+/// Returns the operations in `doc` that are exempt from comparisons because
+/// they're under one of the unstable path prefixes, as `METHOD /path`.
+pub(crate) fn exempt_path_operations(
+    doc: &Value,
+    unstable: UnstableOperations<'_>,
+) -> Vec<String> {
+    let Some(paths) = doc.get("paths").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    paths
+        .iter()
+        .filter(|(path, _)| unstable.is_exempt_path(path))
+        .flat_map(|(path, item)| {
+            operations(item).map(move |(method, _)| {
+                format!("{} {}", method.to_uppercase(), path)
+            })
+        })
+        .collect()
+}
+
+fn operations(path_item: &Value) -> impl Iterator<Item = (&str, &Value)> {
+    PATH_ITEM_METHODS
+        .iter()
+        .filter_map(|key| path_item.get(*key).map(|op| (*key, op)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_without_unstable_operations() {
+        let doc = json!({
+            "paths": {
+                "/stable": {
+                    "get": { "operationId": "stable" },
+                },
+                "/mixed": {
+                    "get": { "operationId": "mixed_stable" },
+                    "post": {
+                        "operationId": "mixed_unstable",
+                        "x-unstable": true,
+                    },
+                },
+                "/unstable": {
+                    "parameters": [],
+                    "get": {
+                        "operationId": "unstable",
+                        "tags": ["experimental"],
+                    },
+                },
+            },
+        });
+
+        // Without an unstable tag, only the extension is recognized.
+        let stripped =
+            without_unstable_operations(&doc, UnstableOperations::default())
+                .unwrap();
+        assert_eq!(
+            stripped["paths"],
+            json!({
+                "/stable": {
+                    "get": { "operationId": "stable" },
+                },
+                "/mixed": {
+                    "get": { "operationId": "mixed_stable" },
+                },
+                "/unstable": {
+                    "parameters": [],
+                    "get": {
+                        "operationId": "unstable",
+                        "tags": ["experimental"],
+                    },
+                },
+            }),
+        );
+
+        // With an unstable tag, path items left without operations are
+        // removed.
+        let unstable = UnstableOperations {
+            tag: Some("experimental"),
+            path_prefixes: &[],
+        };
+        let stripped = without_unstable_operations(&doc, unstable).unwrap();
+        assert_eq!(
+            stripped["paths"],
+            json!({
+                "/stable": {
+                    "get": { "operationId": "stable" },
+                },
+                "/mixed": {
+                    "get": { "operationId": "mixed_stable" },
+                },
+            }),
+        );
+
+        // Documents without unstable operations are left alone.
+        let stable = json!({
+            "paths": { "/stable": { "get": { "operationId": "stable" } } },
+        });
+        assert_eq!(
+            without_unstable_operations(&stable, UnstableOperations::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unstable_path_prefixes() {
+        let doc = json!({
+            "paths": {
+                "/experimental": {
+                    "get": { "operationId": "list" },
+                },
+                "/experimental/{id}": {
+                    "get": { "operationId": "get" },
+                    "delete": { "operationId": "delete" },
+                },
+                "/experimentally": {
+                    "get": { "operationId": "not_exempt" },
+                },
+            },
+        });
+        let prefixes = ["/experimental/".to_owned()];
+        let unstable =
+            UnstableOperations { tag: None, path_prefixes: &prefixes };
+
+        // Prefixes match whole path segments, with or without a trailing
+        // slash.
+        let stripped = without_unstable_operations(&doc, unstable).unwrap();
+        assert_eq!(
+            stripped["paths"],
+            json!({
+                "/experimentally": {
+                    "get": { "operationId": "not_exempt" },
+                },
+            }),
+        );
+        assert_eq!(
+            exempt_path_operations(&doc, unstable),
+            [
+                "GET /experimental",
+                "GET /experimental/{id}",
+                "DELETE /experimental/{id}",
+            ],
+        );
+    }
+}
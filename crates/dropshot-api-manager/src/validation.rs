@@ -1,15 +1,15 @@
 // Copyright 2026 Oxide Computer Company
 
 use crate::{
-    apis::ManagedApi, environment::ResolvedEnv,
+    apis::ManagedApi, environment::ResolvedEnv, ref_check::check_refs,
     spec_files_generated::GeneratedApiSpecFile,
 };
 use anyhow::Context;
 use atomicwrites::AtomicFile;
 use camino::{Utf8Path, Utf8PathBuf};
 use dropshot_api_manager_types::{
-    ApiIdent, ApiSpecFileName, ManagedApiMetadata, ValidationBackend,
-    ValidationContext, Versions,
+    ApiIdent, ApiSpecFileName, CustomFix, ManagedApiMetadata,
+    ValidationBackend, ValidationContext, Versions,
 };
 use openapiv3::OpenAPI;
 use std::io::Write;
@@ -23,17 +23,20 @@ pub fn validate(
     api: &ManagedApi,
     is_latest: bool,
     is_blessed: Option<bool>,
-    validation: Option<&DynValidationFn>,
+    validations: &[&DynValidationFn],
     generated: &GeneratedApiSpecFile,
-) -> anyhow::Result<Vec<(Utf8PathBuf, CheckStatus)>> {
+) -> anyhow::Result<ValidationOutcome> {
     let openapi = generated.openapi();
+    let file_name = generated.spec_file_name();
+    let document_path = env.openapi_abs_dir().join(file_name.path());
     let validation_result = validate_generated_openapi_document(
         api,
         openapi,
-        generated.spec_file_name(),
+        file_name,
+        document_path.parent().unwrap_or(&document_path),
         is_latest,
         is_blessed,
-        validation,
+        validations,
     )?;
     let extra_files = validation_result
         .extra_files
@@ -44,16 +47,29 @@ pub fn validate(
             Ok((path, status))
         })
         .collect::<anyhow::Result<_>>()?;
-    Ok(extra_files)
+    let custom_fixes = validation_result
+        .custom_fixes
+        .into_iter()
+        .filter_map(|fix| match fix.is_needed(&env.repo_root) {
+            Ok(true) => Some(Ok(fix)),
+            Ok(false) => None,
+            Err(error) => Some(Err(error.context(format!(
+                "failed to check whether fix is needed: {}",
+                fix.description()
+            )))),
+        })
+        .collect::<anyhow::Result<_>>()?;
+    Ok(ValidationOutcome { extra_files, custom_fixes })
 }
 
 fn validate_generated_openapi_document(
     api: &ManagedApi,
     openapi_doc: &OpenAPI,
     file_name: &ApiSpecFileName,
+    base_dir: &Utf8Path,
     is_latest: bool,
     is_blessed: Option<bool>,
-    validation: Option<&DynValidationFn>,
+    validations: &[&DynValidationFn],
 ) -> anyhow::Result<ValidationResult> {
     let mut validation_context = ValidationContextImpl {
         ident: api.ident().clone(),
@@ -65,9 +81,17 @@ fn validate_generated_openapi_document(
         metadata: api.metadata().clone(),
         errors: Vec::new(),
         files: Vec::new(),
+        custom_fixes: Vec::new(),
     };
 
-    if let Some(validation) = validation {
+    // Check that every `$ref` in the document resolves.
+    let document = serde_json::to_value(openapi_doc)
+        .context("failed to convert OpenAPI document to JSON")?;
+    validation_context.errors.extend(
+        check_refs(&document, base_dir).into_iter().map(anyhow::Error::msg),
+    );
+
+    for validation in validations {
         validation(
             openapi_doc,
             ValidationContext::new(&mut validation_context),
@@ -80,6 +104,42 @@ fn validate_generated_openapi_document(
         ValidationContext::new(&mut validation_context),
     );
 
+    // Check the API's type replacements and record their sidecar file.
+    api.type_replacements_validation(
+        openapi_doc,
+        ValidationContext::new(&mut validation_context),
+    );
+
+    // Record the flattened copy of the document.
+    api.flattened_document_validation(
+        openapi_doc,
+        ValidationContext::new(&mut validation_context),
+    );
+
+    // Lint the document's summaries and descriptions.
+    api.text_lint_validation(
+        openapi_doc,
+        ValidationContext::new(&mut validation_context),
+    );
+
+    // Check the examples of request and response bodies.
+    api.example_lint_validation(
+        openapi_doc,
+        ValidationContext::new(&mut validation_context),
+    );
+
+    // Check the names of parameters, properties and operations.
+    api.naming_lint_validation(
+        openapi_doc,
+        ValidationContext::new(&mut validation_context),
+    );
+
+    // Check the links to external documentation.
+    api.docs_link_lint_validation(
+        openapi_doc,
+        ValidationContext::new(&mut validation_context),
+    );
+
     if !validation_context.errors.is_empty() {
         return Err(anyhow::anyhow!(
             "OpenAPI document validation failed:\n{}",
@@ -92,11 +152,14 @@ fn validate_generated_openapi_document(
         ));
     }
 
-    Ok(ValidationResult { extra_files: validation_context.files })
+    Ok(ValidationResult {
+        extra_files: validation_context.files,
+        custom_fixes: validation_context.custom_fixes,
+    })
 }
 
 /// Check a file against expected contents.
-fn check_file(
+pub(crate) fn check_file(
     full_path: Utf8PathBuf,
     contents: Vec<u8>,
 ) -> anyhow::Result<CheckStatus> {
@@ -188,18 +251,32 @@ pub(crate) enum CheckStale {
 pub struct ValidationResult {
     // Extra files recorded by the validation context.
     extra_files: Vec<(Utf8PathBuf, Vec<u8>)>,
+    // Fixable problems reported by the validation context.
+    custom_fixes: Vec<CustomFix>,
 }
 
-struct ValidationContextImpl {
-    ident: ApiIdent,
-    file_name: ApiSpecFileName,
-    versions: Versions,
-    is_latest: bool,
-    is_blessed: Option<bool>,
-    title: &'static str,
-    metadata: ManagedApiMetadata,
-    errors: Vec<anyhow::Error>,
-    files: Vec<(Utf8PathBuf, Vec<u8>)>,
+/// The result of successfully validating a generated OpenAPI document.
+#[derive(Debug)]
+pub(crate) struct ValidationOutcome {
+    /// The status of each extra file recorded by validation.
+    pub(crate) extra_files: Vec<(Utf8PathBuf, CheckStatus)>,
+    /// Fixable problems reported by validation that need to be fixed.
+    pub(crate) custom_fixes: Vec<CustomFix>,
+}
+
+/// The [`ValidationBackend`] used by the OpenAPI manager, and by
+/// [`ValidationHarness`](crate::test_util::ValidationHarness).
+pub(crate) struct ValidationContextImpl {
+    pub(crate) ident: ApiIdent,
+    pub(crate) file_name: ApiSpecFileName,
+    pub(crate) versions: Versions,
+    pub(crate) is_latest: bool,
+    pub(crate) is_blessed: Option<bool>,
+    pub(crate) title: &'static str,
+    pub(crate) metadata: ManagedApiMetadata,
+    pub(crate) errors: Vec<anyhow::Error>,
+    pub(crate) files: Vec<(Utf8PathBuf, Vec<u8>)>,
+    pub(crate) custom_fixes: Vec<CustomFix>,
 }
 
 impl ValidationBackend for ValidationContextImpl {
@@ -238,4 +315,8 @@ impl ValidationBackend for ValidationContextImpl {
     fn record_file_contents(&mut self, path: Utf8PathBuf, contents: Vec<u8>) {
         self.files.push((path, contents));
     }
+
+    fn report_fixable_problem(&mut self, fix: CustomFix) {
+        self.custom_fixes.push(fix);
+    }
 }
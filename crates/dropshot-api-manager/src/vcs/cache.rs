@@ -0,0 +1,139 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Caching of VCS queries made while loading blessed documents.
+
+use camino::{Utf8Path, Utf8PathBuf};
+use git_stub::GitCommitHash;
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+/// A cache of the VCS queries made when loading blessed documents, for reuse
+/// across commands run in the same process.
+///
+/// A typical `xtask` flow runs `check`, then `generate`, then `check` again.
+/// Each of these computes the same merge base and reads the same blessed
+/// documents out of VCS history, running a subprocess for each. Attaching a
+/// cache to the [`Environment`] with [`Environment::with_blessed_cache`] lets
+/// later commands reuse the results of earlier ones. Clones of a cache share
+/// the same underlying storage.
+///
+/// File listings and contents are keyed by commit, so they never go stale.
+/// Merge bases are keyed by the blessed revision and the commit the working
+/// copy is based on, so the cache assumes that the blessed revision (e.g.
+/// `origin/main`) isn't updated while it's in use. Create a new cache after
+/// fetching.
+///
+/// The cache holds the contents of every blessed document it has read, so it
+/// shouldn't be kept around for longer than necessary.
+///
+/// [`Environment`]: crate::Environment
+/// [`Environment::with_blessed_cache`]: crate::Environment::with_blessed_cache
+#[derive(Clone, Debug, Default)]
+pub struct BlessedCache {
+    inner: Arc<VcsCache>,
+}
+
+impl BlessedCache {
+    /// Creates a new, empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn vcs_cache(&self) -> &VcsCache {
+        &self.inner
+    }
+}
+
+/// The storage behind [`BlessedCache`].
+///
+/// Commit hashes are stored as strings.
+#[derive(Debug, Default)]
+pub(crate) struct VcsCache {
+    /// Merge bases, keyed by (blessed revision, working copy commit).
+    merge_bases: CacheMap<(String, String), GitCommitHash>,
+    /// Directory listings, keyed by (commit, directory).
+    file_lists: CacheMap<(String, Utf8PathBuf), Vec<Utf8PathBuf>>,
+    /// The name a directory had at a commit, keyed by (commit, directory).
+    directories: CacheMap<(String, Utf8PathBuf), Utf8PathBuf>,
+    /// File contents, keyed by (commit, path).
+    contents: CacheMap<(String, Utf8PathBuf), Vec<u8>>,
+}
+
+impl VcsCache {
+    pub(crate) fn merge_base(
+        &self,
+        revision: &str,
+        head: GitCommitHash,
+        compute: impl FnOnce() -> anyhow::Result<GitCommitHash>,
+    ) -> anyhow::Result<GitCommitHash> {
+        self.merge_bases
+            .get_or_try_insert((revision.to_owned(), head.to_string()), compute)
+    }
+
+    pub(crate) fn file_list(
+        &self,
+        commit: GitCommitHash,
+        directory: &Utf8Path,
+        compute: impl FnOnce() -> anyhow::Result<Vec<Utf8PathBuf>>,
+    ) -> anyhow::Result<Vec<Utf8PathBuf>> {
+        self.file_lists.get_or_try_insert(
+            (commit.to_string(), directory.to_owned()),
+            compute,
+        )
+    }
+
+    pub(crate) fn directory(
+        &self,
+        commit: GitCommitHash,
+        directory: &Utf8Path,
+        compute: impl FnOnce() -> anyhow::Result<Utf8PathBuf>,
+    ) -> anyhow::Result<Utf8PathBuf> {
+        self.directories.get_or_try_insert(
+            (commit.to_string(), directory.to_owned()),
+            compute,
+        )
+    }
+
+    pub(crate) fn contents(
+        &self,
+        commit: GitCommitHash,
+        path: &Utf8Path,
+        compute: impl FnOnce() -> anyhow::Result<Vec<u8>>,
+    ) -> anyhow::Result<Vec<u8>> {
+        self.contents
+            .get_or_try_insert((commit.to_string(), path.to_owned()), compute)
+    }
+}
+
+/// A map whose values are computed on demand. Errors aren't cached.
+#[derive(Debug)]
+struct CacheMap<K, V> {
+    map: Mutex<HashMap<K, V>>,
+}
+
+impl<K, V> Default for CacheMap<K, V> {
+    fn default() -> Self {
+        Self { map: Mutex::new(HashMap::new()) }
+    }
+}
+
+impl<K: Eq + Hash, V: Clone> CacheMap<K, V> {
+    fn get_or_try_insert(
+        &self,
+        key: K,
+        compute: impl FnOnce() -> anyhow::Result<V>,
+    ) -> anyhow::Result<V> {
+        if let Some(value) = self.map.lock().unwrap().get(&key) {
+            return Ok(value.clone());
+        }
+        // Don't hold the lock while computing the value: blessed documents
+        // are read in parallel. Two threads may then compute the same value,
+        // but they'll get the same result.
+        let value = compute()?;
+        self.map.lock().unwrap().insert(key, value.clone());
+        Ok(value)
+    }
+}
@@ -2,11 +2,19 @@
 
 //! Helpers for accessing data stored in git
 
-use super::imp::{VcsRevision, cmd_label, do_run, do_run_bytes};
+use super::{
+    imp::{
+        CommitReachability, PIN_REF_PREFIX, VcsOperation, VcsRevision,
+        cmd_label, do_run, do_run_bytes,
+    },
+    jj::revset_quote,
+    runner::{Repo, VcsCommand},
+    trace::output,
+};
 use anyhow::{Context, bail};
 use camino::{Utf8Path, Utf8PathBuf};
 use git_stub::GitCommitHash;
-use std::process::Command;
+use std::{collections::BTreeSet, io::Write};
 
 /// Given a revision, return its merge base with the current working state.
 ///
@@ -24,34 +32,34 @@ use std::process::Command;
 /// In the rare case where the two merge bases are independent (neither is an
 /// ancestor of the other), we fall back to HEAD's merge base.
 pub(super) fn git_merge_base_head(
-    repo_root: &Utf8Path,
+    repo: &Repo<'_>,
     revision: &VcsRevision,
 ) -> anyhow::Result<GitCommitHash> {
-    if git_merge_head_exists(repo_root) {
+    if git_merge_head_exists(repo) {
         // We're in a merge. Compute merge bases for both HEAD and MERGE_HEAD.
-        let mb_head = git_merge_base(repo_root, "HEAD", revision)?;
-        let mb_merge_head = git_merge_base(repo_root, "MERGE_HEAD", revision)?;
+        let mb_head = git_merge_base(repo, "HEAD", revision)?;
+        let mb_merge_head = git_merge_base(repo, "MERGE_HEAD", revision)?;
 
         // Use whichever merge base is the descendant (more recent). If mb_head
         // is an ancestor of mb_merge_head, use mb_merge_head (it's newer).
         // Otherwise, use mb_head (either it's newer, or they're parallel).
-        if git_is_ancestor(repo_root, mb_head, mb_merge_head)? {
+        if git_is_ancestor(repo, mb_head, mb_merge_head)? {
             Ok(mb_merge_head)
         } else {
             Ok(mb_head)
         }
     } else {
-        git_merge_base(repo_root, "HEAD", revision)
+        git_merge_base(repo, "HEAD", revision)
     }
 }
 
 /// Compute the merge base between a reference and a revision.
 fn git_merge_base(
-    repo_root: &Utf8Path,
+    repo: &Repo<'_>,
     base_ref: &str,
     revision: &VcsRevision,
 ) -> anyhow::Result<GitCommitHash> {
-    let mut cmd = git_start(repo_root);
+    let mut cmd = git_start(repo);
     cmd.arg("merge-base").arg("--all").arg(base_ref).arg(revision.as_str());
     let label = cmd_label(&cmd);
     let stdout = do_run(&mut cmd)?;
@@ -70,11 +78,11 @@ fn git_merge_base(
 
 /// Check if `potential_ancestor` is an ancestor of `commit`.
 pub(super) fn git_is_ancestor(
-    repo_root: &Utf8Path,
+    repo: &Repo<'_>,
     potential_ancestor: GitCommitHash,
     commit: GitCommitHash,
 ) -> anyhow::Result<bool> {
-    let mut cmd = git_start(repo_root);
+    let mut cmd = git_start(repo);
     cmd.args([
         "merge-base",
         "--is-ancestor",
@@ -82,7 +90,7 @@ pub(super) fn git_is_ancestor(
         &commit.to_string(),
     ]);
     let output =
-        cmd.output().context("running git merge-base --is-ancestor")?;
+        output(&mut cmd).context("running git merge-base --is-ancestor")?;
     // --is-ancestor returns exit code 0 if true, 1 if false.
     // Other exit codes (e.g. 128 for invalid objects) indicate real errors.
     match output.status.code() {
@@ -111,21 +119,53 @@ pub(super) fn git_is_ancestor(
     }
 }
 
+/// Returns the commit that HEAD points to.
+pub(super) fn git_head_commit(
+    repo: &Repo<'_>,
+) -> anyhow::Result<GitCommitHash> {
+    let mut cmd = git_start(repo);
+    cmd.args(["rev-parse", "--verify", "HEAD^{commit}"]);
+    let stdout = do_run(&mut cmd)?;
+    let stdout = stdout.trim();
+    stdout.parse().with_context(|| {
+        format!("git rev-parse returned invalid commit hash: {:?}", stdout)
+    })
+}
+
 /// Returns true if MERGE_HEAD exists, indicating we're in the middle of a
 /// merge.
-fn git_merge_head_exists(repo_root: &Utf8Path) -> bool {
-    let mut cmd = git_start(repo_root);
-    cmd.args(["rev-parse", "--verify", "--quiet", "MERGE_HEAD"]);
-    matches!(cmd.status(), Ok(status) if status.success())
+fn git_merge_head_exists(repo: &Repo<'_>) -> bool {
+    git_pseudo_ref_exists(repo, "MERGE_HEAD")
+}
+
+/// Returns the operation that's in progress, if any, based on the pseudo-refs
+/// Git writes while a merge, rebase or cherry-pick is stopped.
+pub(super) fn git_operation_in_progress(
+    repo: &Repo<'_>,
+) -> Option<VcsOperation> {
+    [
+        ("MERGE_HEAD", VcsOperation::Merge),
+        ("REBASE_HEAD", VcsOperation::Rebase),
+        ("CHERRY_PICK_HEAD", VcsOperation::CherryPick),
+    ]
+    .into_iter()
+    .find(|(name, _)| git_pseudo_ref_exists(repo, name))
+    .map(|(_, operation)| operation)
+}
+
+fn git_pseudo_ref_exists(repo: &Repo<'_>, name: &str) -> bool {
+    let mut cmd = git_start(repo);
+    cmd.args(["rev-parse", "--verify", "--quiet", name]);
+    matches!(output(&mut cmd), Ok(output) if output.status.success())
 }
 
 /// List files recursively under some path `path` in Git revision `revision`.
 pub(super) fn git_ls_tree(
-    repo_root: &Utf8Path,
+    repo: &Repo<'_>,
     revision: GitCommitHash,
     directory: &Utf8Path,
 ) -> anyhow::Result<Vec<Utf8PathBuf>> {
-    let mut cmd = git_start(repo_root);
+    let mut cmd = git_start(repo);
     cmd.arg("ls-tree")
         .arg("-r")
         .arg("-z")
@@ -158,17 +198,43 @@ pub(super) fn git_ls_tree(
 /// Returns the contents of the file at the given path `path` in Git revision
 /// `revision`.
 pub(super) fn git_show_file(
-    repo_root: &Utf8Path,
+    repo: &Repo<'_>,
     revision: GitCommitHash,
     path: &Utf8Path,
 ) -> anyhow::Result<Vec<u8>> {
-    let mut cmd = git_start(repo_root);
+    let mut cmd = git_start(repo);
     cmd.arg("cat-file").arg("blob").arg(format!("{}:{}", revision, path));
     do_run_bytes(&mut cmd)
 }
 
+/// Returns the files under `directory` whose contents in the working tree
+/// differ from Git revision `revision`, as paths relative to the repository
+/// root.
+///
+/// Git only hashes files whose metadata has changed since they were last
+/// indexed, so this is much cheaper than reading each file out of `revision`.
+pub(super) fn git_diff_working_tree(
+    repo: &Repo<'_>,
+    revision: GitCommitHash,
+    directory: &Utf8Path,
+) -> anyhow::Result<BTreeSet<Utf8PathBuf>> {
+    let mut cmd = git_start(repo);
+    // With rename detection, only the new name of a renamed file would be
+    // listed.
+    cmd.args(["diff", "--no-renames", "--name-only", "-z"])
+        .arg(revision.to_string())
+        .arg("--")
+        .arg(directory);
+    let stdout = do_run(&mut cmd)?;
+    Ok(stdout
+        .split('\0')
+        .filter(|s| !s.is_empty())
+        .map(Utf8PathBuf::from)
+        .collect())
+}
+
 /// Returns the first commit where a file was introduced, searching up to and
-/// including the given revision.
+/// including the given revision, along with the file's path in that commit.
 ///
 /// This is used to find a stable, canonical commit for Git stub storage. Using
 /// the first commit (as opposed to something more readily available like the
@@ -177,8 +243,55 @@ pub(super) fn git_show_file(
 /// previous blessed version into having the same contents for both developers.
 /// This avoids an unnecessary merge conflict in the contents of the `.gitstub`
 /// file.
+///
+/// If the file was introduced by moving it unchanged from another directory
+/// (e.g. because the OpenAPI documents directory was renamed), the search
+/// continues from its previous path, so the result doesn't depend on which
+/// side of the move the search started from.
 pub(super) fn git_first_commit_for_file(
-    repo_root: &Utf8Path,
+    repo: &Repo<'_>,
+    revision: GitCommitHash,
+    path: &Utf8Path,
+) -> anyhow::Result<(GitCommitHash, Utf8PathBuf)> {
+    let mut commit = git_commit_adding_file(repo, revision, path)?;
+    let mut path = path.to_owned();
+    loop {
+        let Some(old_path) =
+            git_exact_renames(repo, commit)?.into_iter().find_map(
+                |(old, new)| is_moved_file(&old, &new, &path).then_some(old),
+            )
+        else {
+            return Ok((commit, path));
+        };
+        // Searching from `commit` itself is fine: the file was removed from
+        // `old_path` in `commit`, not added.
+        let Ok(old_commit) = git_commit_adding_file(repo, commit, &old_path)
+        else {
+            // The history before the move isn't available (e.g. in a shallow
+            // clone), so this is as far back as we can go.
+            return Ok((commit, path));
+        };
+        if old_commit == commit {
+            return Ok((commit, path));
+        }
+        commit = old_commit;
+        path = old_path;
+    }
+}
+
+/// Returns true if the exact rename of `old` to `new` is a move of `path` (the
+/// file name staying the same) from another directory.
+///
+/// Requiring an exact rename that preserves the file name avoids the false
+/// matches that Git's similarity-based rename detection can produce.
+fn is_moved_file(old: &Utf8Path, new: &Utf8Path, path: &Utf8Path) -> bool {
+    new == path && old.file_name() == new.file_name()
+}
+
+/// Returns the most recent commit that added `path`, searching backwards from
+/// `revision`.
+fn git_commit_adding_file(
+    repo: &Repo<'_>,
     revision: GitCommitHash,
     path: &Utf8Path,
 ) -> anyhow::Result<GitCommitHash> {
@@ -187,12 +300,12 @@ pub(super) fn git_first_commit_for_file(
     //
     // We intentionally don't use --follow because Git's rename detection can
     // incorrectly match unrelated files with similar content, causing it to
-    // return the wrong commit.
+    // return the wrong commit. (Exact moves are followed by the caller.)
     //
     // We use -m to split merge commits, so that files added in merge commits
     // are properly detected. Without -m, git log may not show files that were
     // added in merge commits.
-    let mut cmd = git_start(repo_root);
+    let mut cmd = git_start(repo);
     cmd.arg("log")
         .arg("-m")
         .arg("--diff-filter=A")
@@ -225,10 +338,419 @@ pub(super) fn git_first_commit_for_file(
     })
 }
 
+/// Returns the exact (content-preserving) renames made by `commit` relative to
+/// each of its parents, as `(old path, new path)` pairs.
+fn git_exact_renames(
+    repo: &Repo<'_>,
+    commit: GitCommitHash,
+) -> anyhow::Result<Vec<(Utf8PathBuf, Utf8PathBuf)>> {
+    let mut cmd = git_start(repo);
+    cmd.args([
+        "diff-tree",
+        "-r",
+        "-z",
+        "-m",
+        "--no-commit-id",
+        "--name-status",
+        "--find-renames=100%",
+        "--diff-filter=R",
+    ])
+    .arg(commit.to_string());
+    let label = cmd_label(&cmd);
+    let stdout = do_run(&mut cmd)?;
+
+    // With -z, each rename is output as "R100\0old\0new\0".
+    let mut fields = stdout.split('\0').filter(|s| !s.is_empty());
+    let mut renames = Vec::new();
+    while let Some(status) = fields.next() {
+        let (Some(old), Some(new)) = (fields.next(), fields.next()) else {
+            bail!("unexpected output from {label}: truncated rename entry");
+        };
+        if !status.starts_with('R') {
+            bail!("unexpected output from {label}: status {status:?}");
+        }
+        renames.push((Utf8PathBuf::from(old), Utf8PathBuf::from(new)));
+    }
+    Ok(renames)
+}
+
+/// If `directory` doesn't exist in `revision` because it was renamed in the
+/// history between `revision` and HEAD, returns its name in `revision`.
+///
+/// Returns `Ok(None)` if `directory` exists in `revision`, or if no such rename
+/// was found.
+pub(super) fn git_directory_before_rename(
+    repo: &Repo<'_>,
+    revision: GitCommitHash,
+    directory: &Utf8Path,
+) -> anyhow::Result<Option<Utf8PathBuf>> {
+    if git_path_exists(repo, revision, directory) {
+        return Ok(None);
+    }
+
+    // Walk back through renames (the directory may have been renamed more
+    // than once), starting from HEAD.
+    let mut upper = String::from("HEAD");
+    let mut current = directory.to_owned();
+    loop {
+        // Find the oldest commit that added files to the directory.
+        let mut cmd = git_start(repo);
+        cmd.args(["log", "-m", "--reverse", "--diff-filter=A", "--format=%H"])
+            .arg(format!("{revision}..{upper}"))
+            .arg("--")
+            .arg(&current);
+        let stdout = do_run(&mut cmd)?;
+        let Some(commit) = stdout.lines().next() else {
+            return Ok(None);
+        };
+        if commit == upper {
+            return Ok(None);
+        }
+        let commit: GitCommitHash = commit.parse().with_context(|| {
+            format!("git returned invalid commit hash {:?}", commit)
+        })?;
+
+        // Determine where the directory's files were moved from.
+        let previous = git_exact_renames(repo, commit)?.into_iter().find_map(
+            |(old, new)| {
+                let rest = new.strip_prefix(&current).ok()?;
+                let old_dir = old.as_str().strip_suffix(rest.as_str())?;
+                let old_dir = old_dir.strip_suffix('/')?;
+                (!old_dir.is_empty()).then(|| Utf8PathBuf::from(old_dir))
+            },
+        );
+        let Some(previous) = previous else {
+            return Ok(None);
+        };
+        if git_path_exists(repo, revision, &previous) {
+            return Ok(Some(previous));
+        }
+        upper = commit.to_string();
+        current = previous;
+    }
+}
+
+/// Returns true if `path` (a file or directory) exists in `revision`.
+fn git_path_exists(
+    repo: &Repo<'_>,
+    revision: GitCommitHash,
+    path: &Utf8Path,
+) -> bool {
+    let mut cmd = git_start(repo);
+    cmd.args(["cat-file", "-e"]).arg(format!("{revision}:{path}"));
+    matches!(output(&mut cmd), Ok(output) if output.status.success())
+}
+
+/// For a Jujutsu repository colocated with Git, translates a Git ref like
+/// `origin/main` into the equivalent jj revset, like `"main"@"origin"`.
+///
+/// jj imports Git branches as bookmarks and remote-tracking branches as remote
+/// bookmarks, but names the latter differently. Returns `Ok(None)` if
+/// `revision` isn't a Git branch, remote-tracking branch, or tag.
+pub(super) fn git_ref_to_jj_revset(
+    repo: &Repo<'_>,
+    revision: &VcsRevision,
+) -> anyhow::Result<Option<VcsRevision>> {
+    let mut cmd = git_start(repo);
+    cmd.args(["rev-parse", "--verify", "--quiet", "--symbolic-full-name"])
+        .arg(revision.as_str());
+    // A failure here means that the revision isn't a ref.
+    let Ok(stdout) = do_run(&mut cmd) else {
+        return Ok(None);
+    };
+    let full_name = stdout.trim();
+
+    if let Some(rest) = full_name.strip_prefix("refs/remotes/") {
+        // Remote names may themselves contain slashes, so find the remote
+        // that this ref belongs to.
+        let mut cmd = git_start(repo);
+        cmd.arg("remote");
+        let remotes = do_run(&mut cmd)?;
+        let translated = remotes.lines().find_map(|remote| {
+            let branch = rest.strip_prefix(remote)?.strip_prefix('/')?;
+            Some(jj_remote_bookmark(branch, remote))
+        });
+        return Ok(translated.map(VcsRevision::from));
+    }
+
+    let name = full_name
+        .strip_prefix("refs/heads/")
+        .or_else(|| full_name.strip_prefix("refs/tags/"));
+    Ok(name.map(|name| VcsRevision::from(revset_quote(name))))
+}
+
+/// Returns the jj revset for the remote bookmark `branch` of `remote`, like
+/// `"main"@"origin"`.
+fn jj_remote_bookmark(branch: &str, remote: &str) -> String {
+    format!("{}@{}", revset_quote(branch), revset_quote(remote))
+}
+
+/// Returns true if any file tracked under `directory` is excluded from the
+/// working tree by a sparse checkout.
+///
+/// Files excluded by sparse checkout have the skip-worktree bit set, which
+/// `git ls-files -t` reports with the `S` tag. The skip-worktree bit can also
+/// be set by hand with `git update-index --skip-worktree`, in which case the
+/// file is still present on disk; we only count files that are actually
+/// missing.
+pub(super) fn git_is_excluded_by_sparse_checkout(
+    repo: &Repo<'_>,
+    directory: &Utf8Path,
+) -> anyhow::Result<bool> {
+    let mut cmd = git_start(repo);
+    cmd.arg("ls-files").arg("-t").arg("-z").arg("--").arg(directory);
+    let stdout = do_run(&mut cmd)?;
+    Ok(stdout
+        .split('\0')
+        .filter_map(|entry| entry.strip_prefix("S "))
+        .any(|path| !repo.root.join(path).exists()))
+}
+
+/// Determines whether `commit` is present in the object store, and if so,
+/// whether it is reachable from any ref.
+///
+/// Commits that are present but not reachable from a ref are eligible for
+/// pruning by `git gc`.
+pub(super) fn git_commit_reachability(
+    repo: &Repo<'_>,
+    commit: GitCommitHash,
+) -> anyhow::Result<CommitReachability> {
+    let mut cmd = git_start(repo);
+    cmd.arg("cat-file").arg("-e").arg(format!("{commit}^{{commit}}"));
+    // cat-file -e exits with a non-zero status if the object doesn't exist.
+    let output = output(&mut cmd).context("running git cat-file -e")?;
+    if !output.status.success() {
+        return Ok(CommitReachability::Missing);
+    }
+
+    let mut cmd = git_start(repo);
+    cmd.arg("for-each-ref")
+        .arg("--count=1")
+        .arg("--format=%(refname)")
+        .arg("--contains")
+        .arg(commit.to_string());
+    let stdout = do_run(&mut cmd)?;
+    if stdout.trim().is_empty() {
+        Ok(CommitReachability::Unreferenced)
+    } else {
+        Ok(CommitReachability::Reachable)
+    }
+}
+
+/// Creates (or updates) a lightweight ref under [`PIN_REF_PREFIX`] that points
+/// to `commit`, protecting it from `git gc`.
+pub(super) fn git_pin_commit(
+    repo: &Repo<'_>,
+    commit: GitCommitHash,
+) -> anyhow::Result<()> {
+    let mut cmd = git_start(repo);
+    cmd.arg("update-ref")
+        .arg(format!("{PIN_REF_PREFIX}{commit}"))
+        .arg(commit.to_string());
+    do_run(&mut cmd)?;
+    Ok(())
+}
+
+/// Returns the untracked files under `directory`, other than those ignored by
+/// `.gitignore` and the like, as paths relative to the repository root.
+pub(super) fn git_untracked_files(
+    repo: &Repo<'_>,
+    directory: &Utf8Path,
+) -> anyhow::Result<BTreeSet<Utf8PathBuf>> {
+    let mut cmd = git_start(repo);
+    cmd.args([
+        "ls-files",
+        "--others",
+        "--exclude-standard",
+        "--full-name",
+        "-z",
+        "--",
+    ])
+    .arg(directory);
+    let stdout = do_run(&mut cmd)?;
+    Ok(stdout
+        .split('\0')
+        .filter(|path| !path.is_empty())
+        .map(Utf8PathBuf::from)
+        .collect())
+}
+
+/// Returns the files under `directory` that are modified, added, deleted or
+/// untracked in the working tree or index, as paths relative to the
+/// repository root.
+pub(super) fn git_uncommitted_files(
+    repo: &Repo<'_>,
+    directory: &Utf8Path,
+) -> anyhow::Result<BTreeSet<Utf8PathBuf>> {
+    let mut cmd = git_start(repo);
+    cmd.args([
+        "status",
+        "--porcelain",
+        "-z",
+        "--no-renames",
+        "--untracked-files=all",
+        "--",
+    ])
+    .arg(directory);
+    let stdout = do_run(&mut cmd)?;
+    // Each entry is a two-character status, a space, and the path.
+    Ok(stdout
+        .split('\0')
+        .filter_map(|entry| entry.get(3..))
+        .filter(|path| !path.is_empty())
+        .map(Utf8PathBuf::from)
+        .collect())
+}
+
+/// Adds a note with `contents` to `commit` under `notes_ref`, replacing any
+/// existing note.
+pub(super) fn git_add_note(
+    repo: &Repo<'_>,
+    notes_ref: &str,
+    commit: GitCommitHash,
+    contents: &[u8],
+) -> anyhow::Result<()> {
+    // Pass the note through a file rather than `-m`, so that its contents are
+    // recorded exactly.
+    let mut file = camino_tempfile::NamedUtf8TempFile::new()
+        .context("creating temporary file for note")?;
+    file.write_all(contents).context("writing note to temporary file")?;
+    let mut cmd = git_start(repo);
+    cmd.arg("notes")
+        .arg(format!("--ref={notes_ref}"))
+        .args(["add", "--force", "--file"])
+        .arg(file.path())
+        .arg(commit.to_string());
+    do_run(&mut cmd)?;
+    Ok(())
+}
+
+/// Resolves `revision` to a commit, and returns it along with its note under
+/// `notes_ref`, if any.
+pub(super) fn git_show_note(
+    repo: &Repo<'_>,
+    notes_ref: &str,
+    revision: &VcsRevision,
+) -> anyhow::Result<(GitCommitHash, Option<String>)> {
+    let mut cmd = git_start(repo);
+    cmd.args(["rev-parse", "--verify"])
+        .arg(format!("{}^{{commit}}", revision.as_str()));
+    let stdout = do_run(&mut cmd)?;
+    let stdout = stdout.trim();
+    let commit: GitCommitHash = stdout.parse().with_context(|| {
+        format!("git rev-parse returned invalid commit hash: {:?}", stdout)
+    })?;
+
+    // `git notes list` fails if the commit doesn't have a note.
+    let mut cmd = git_start(repo);
+    cmd.arg("notes")
+        .arg(format!("--ref={notes_ref}"))
+        .arg("list")
+        .arg(commit.to_string());
+    let output = output(&mut cmd).context("running git notes list")?;
+    if !output.status.success() {
+        return Ok((commit, None));
+    }
+
+    let mut cmd = git_start(repo);
+    cmd.arg("notes")
+        .arg(format!("--ref={notes_ref}"))
+        .arg("show")
+        .arg(commit.to_string());
+    Ok((commit, Some(do_run(&mut cmd)?)))
+}
+
+/// Fetches a single commit from `remote`.
+pub(super) fn git_fetch_commit(
+    repo: &Repo<'_>,
+    remote: &str,
+    commit: GitCommitHash,
+) -> anyhow::Result<()> {
+    let mut cmd = git_start(repo);
+    cmd.arg("fetch")
+        .arg("--quiet")
+        .arg("--no-tags")
+        .arg(remote)
+        .arg(commit.to_string());
+    do_run(&mut cmd)?;
+    Ok(())
+}
+
+/// Creates a worktree at `worktree_dir` with HEAD checked out.
+pub(super) fn git_worktree_add(
+    repo: &Repo<'_>,
+    worktree_dir: &Utf8Path,
+) -> anyhow::Result<()> {
+    let mut cmd = git_start(repo);
+    cmd.args(["worktree", "add", "--quiet", "--detach"])
+        .arg(worktree_dir)
+        .arg("HEAD");
+    do_run(&mut cmd)?;
+    Ok(())
+}
+
+/// Merges `base` into `worktree`.
+///
+/// This emulates the synthetic merge commit that a merge queue evaluates.
+pub(super) fn git_worktree_merge(
+    worktree: &Repo<'_>,
+    base: &VcsRevision,
+) -> anyhow::Result<()> {
+    // The merge commit is thrown away along with the worktree, so don't
+    // require the user to have an identity configured.
+    let mut cmd = git_start(worktree);
+    cmd.args([
+        "-c",
+        "user.name=dropshot-api-manager",
+        "-c",
+        "user.email=dropshot-api-manager@localhost",
+        "merge",
+        "--quiet",
+        "--no-edit",
+        "--no-ff",
+    ])
+    .arg(base.as_str());
+    do_run(&mut cmd).with_context(|| {
+        format!("merging {base} into HEAD failed (are there conflicts?)")
+    })?;
+    Ok(())
+}
+
+/// Removes a worktree created by [`git_worktree_add`].
+pub(super) fn git_worktree_remove(
+    repo: &Repo<'_>,
+    worktree_dir: &Utf8Path,
+) -> anyhow::Result<()> {
+    let mut cmd = git_start(repo);
+    cmd.args(["worktree", "remove", "--force"]).arg(worktree_dir);
+    do_run(&mut cmd)?;
+    Ok(())
+}
+
 /// Begin assembling an invocation of git(1).
-fn git_start(repo_root: &Utf8Path) -> Command {
+fn git_start<'a>(repo: &Repo<'a>) -> VcsCommand<'a> {
     let git = std::env::var("GIT").ok().unwrap_or_else(|| String::from("git"));
-    let mut command = Command::new(&git);
-    command.current_dir(repo_root);
-    command
+    let mut cmd = repo.command(&git);
+    // Commands like `git status` opportunistically write a refreshed index,
+    // which fails in read-only checkouts. Opt out of these writes: the API
+    // manager never needs them.
+    cmd.env("GIT_OPTIONAL_LOCKS", "0");
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jj_remote_bookmark() {
+        assert_eq!(jj_remote_bookmark("main", "origin"), r#""main"@"origin""#);
+
+        // Git allows quotes in branch names, and non-ASCII characters are
+        // passed through as is rather than as Rust escapes.
+        assert_eq!(
+            jj_remote_bookmark("say-\"cafe\u{301}\"", "up/stream"),
+            "\"say-\\\"cafe\u{301}\\\"\"@\"up/stream\"",
+        );
+    }
 }
@@ -1,10 +1,20 @@
 // Copyright 2026 Oxide Computer Company
 
+use super::{
+    cache::BlessedCache,
+    runner::{GitRunner, Repo, SubprocessRunner, VcsCommand},
+    trace,
+};
 use anyhow::{Context, bail};
 use camino::{Utf8Path, Utf8PathBuf};
+use camino_tempfile::Utf8TempDir;
 use git_stub::{GitCommitHash, GitStub};
 use git_stub_vcs::Vcs;
-use std::process::Command;
+use std::{
+    collections::BTreeSet,
+    process::Command,
+    sync::{Arc, Mutex},
+};
 
 /// Newtype String wrapper identifying a VCS revision.
 ///
@@ -20,12 +30,32 @@ NewtypeDeref! { () pub struct VcsRevision(String); }
 NewtypeDisplay! { () pub struct VcsRevision(String); }
 NewtypeFrom! { () pub struct VcsRevision(String); }
 
+/// The ref namespace used to pin commits referenced by Git stubs.
+pub(crate) const PIN_REF_PREFIX: &str = "refs/openapi/pins/";
+
+/// The notes ref under which `check --write-note` records the documents it
+/// verified.
+pub(crate) const CHECK_NOTES_REF: &str = "refs/notes/openapi-check";
+
+/// Whether a commit referenced by a Git stub is available in the repository.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CommitReachability {
+    /// The commit is reachable from at least one ref.
+    Reachable,
+    /// The commit is present, but not reachable from any ref. It may be
+    /// removed by garbage collection.
+    Unreferenced,
+    /// The commit is not present in the repository.
+    Missing,
+}
+
 /// VCS abstraction for repository operations.
 ///
 /// This wraps the detected VCS backend (Git or Jujutsu) and provides
 /// methods for the operations the API manager needs: merge-base
 /// computation, file listing, file content retrieval, and ancestry
-/// checks.
+/// checks. If the documents aren't under version control at all (see
+/// [`RepoVcs::none`]), these operations return errors.
 ///
 /// The cached [`Vcs`] is used for operations that delegate to
 /// `git_stub_vcs` (shallow clone detection, stub resolution).
@@ -34,15 +64,72 @@ pub(crate) struct RepoVcs {
     // These two are kept in sync by from_git_stub_vcs. (Why store RepoVcsKind
     // at all? Because git_stub_vcs::VcsName is non-exhaustive, and it would be
     // annoying to have to have `unreachable` or some other panic over and
-    // over.)
+    // over.) `stub_vcs` is `None` if and only if `kind` is
+    // `RepoVcsKind::None`.
     kind: RepoVcsKind,
-    stub_vcs: Vcs,
+    stub_vcs: Option<Vcs>,
+    // If set, commits referenced by Git stubs that are missing locally are
+    // fetched from this remote.
+    fetch_missing_from: Option<String>,
+    // True for a Jujutsu repository colocated with Git, i.e. one where Git
+    // commands also work.
+    colocated: bool,
+    // If set, the results of queries made to load blessed documents are
+    // cached here.
+    cache: Option<BlessedCache>,
+    // Runs the git and jj commands.
+    runner: Arc<dyn GitRunner>,
+}
+
+/// Serializes fetches of missing commits. Git stubs are resolved in parallel,
+/// and many stubs typically reference the same commit.
+static FETCH_LOCK: Mutex<()> = Mutex::new(());
+
+/// A multi-step VCS operation that stopped partway through, typically because
+/// of conflicts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum VcsOperation {
+    Merge,
+    Rebase,
+    CherryPick,
+}
+
+impl VcsOperation {
+    /// Returns the name of the operation, as used in prose.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            VcsOperation::Merge => "merge",
+            VcsOperation::Rebase => "rebase",
+            VcsOperation::CherryPick => "cherry-pick",
+        }
+    }
+
+    /// Returns the command that resumes the operation once conflicts are
+    /// resolved.
+    pub(crate) fn continue_command(self) -> &'static str {
+        match self {
+            VcsOperation::Merge => "git merge --continue",
+            VcsOperation::Rebase => "git rebase --continue",
+            VcsOperation::CherryPick => "git cherry-pick --continue",
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) enum RepoVcsKind {
     Git,
     Jj,
+    /// The documents are not under version control.
+    None,
+}
+
+/// Returns the error for an operation that requires version control, in an
+/// environment without it.
+fn no_vcs_error() -> anyhow::Error {
+    anyhow::anyhow!(
+        "this operation requires a Git or Jujutsu repository, but the API \
+         manager is configured to run without version control"
+    )
 }
 
 impl RepoVcs {
@@ -53,7 +140,10 @@ impl RepoVcs {
     pub(crate) fn detect(repo_root: &Utf8Path) -> anyhow::Result<Self> {
         let vcs = Vcs::detect(repo_root)
             .with_context(|| format!("detecting VCS at {repo_root}"))?;
-        Self::from_git_stub_vcs(vcs)
+        let mut repo_vcs = Self::from_git_stub_vcs(vcs)?;
+        repo_vcs.colocated =
+            repo_vcs.kind == RepoVcsKind::Jj && repo_root.join(".git").exists();
+        Ok(repo_vcs)
     }
 
     /// Create a `RepoVcs` for the Git backend.
@@ -70,6 +160,20 @@ impl RepoVcs {
         Self::from_git_stub_vcs(vcs)
     }
 
+    /// Create a `RepoVcs` for documents that aren't under version control.
+    ///
+    /// All operations that need a repository return errors.
+    pub(crate) fn none() -> Self {
+        Self {
+            kind: RepoVcsKind::None,
+            stub_vcs: None,
+            fetch_missing_from: None,
+            colocated: false,
+            cache: None,
+            runner: Arc::new(SubprocessRunner),
+        }
+    }
+
     fn from_git_stub_vcs(vcs: Vcs) -> anyhow::Result<Self> {
         let kind = match vcs.name() {
             git_stub_vcs::VcsName::Git => RepoVcsKind::Git,
@@ -78,7 +182,14 @@ impl RepoVcs {
             // so we notice if a new variant is added.
             other => bail!("unsupported VCS backend: {other:?}"),
         };
-        Ok(Self { kind, stub_vcs: vcs })
+        Ok(Self {
+            kind,
+            stub_vcs: Some(vcs),
+            fetch_missing_from: None,
+            colocated: false,
+            cache: None,
+            runner: Arc::new(SubprocessRunner),
+        })
     }
 
     /// Returns the VCS backend kind.
@@ -86,24 +197,126 @@ impl RepoVcs {
         self.kind
     }
 
+    /// Returns true if this is a Jujutsu repository colocated with Git.
+    pub(crate) fn is_colocated(&self) -> bool {
+        self.colocated
+    }
+
+    /// Sets the remote that missing commits referenced by Git stubs are
+    /// fetched from.
+    pub(crate) fn set_fetch_missing_from(&mut self, remote: String) {
+        self.fetch_missing_from = Some(remote);
+    }
+
+    /// Sets the cache used for queries made to load blessed documents.
+    pub(crate) fn set_cache(&mut self, cache: BlessedCache) {
+        self.cache = Some(cache);
+    }
+
+    /// Sets the runner for git and jj commands.
+    pub(crate) fn set_runner(&mut self, runner: Arc<dyn GitRunner>) {
+        self.runner = runner;
+    }
+
+    /// Returns the repository at `root`, with commands run by our runner.
+    fn repo<'a>(&'a self, root: &'a Utf8Path) -> Repo<'a> {
+        Repo { root, runner: &*self.runner }
+    }
+
     /// Compute the merge base between the current working state and a
     /// revision.
     ///
     /// For Git, this handles in-progress merges by also checking
     /// MERGE_HEAD. For Jujutsu, `@` is the merge commit, so
     /// `heads(::@ & ::REV)` naturally handles all parent histories.
+    ///
+    /// In a Jujutsu repository colocated with Git, `revision` may also be a
+    /// Git ref like `origin/main`. (jj doesn't understand these directly, and
+    /// in a colocated repository, Git's `HEAD` doesn't include changes in the
+    /// working-copy commit, so Git can't be used to compute the merge base.)
     pub(crate) fn merge_base_head(
         &self,
         repo_root: &Utf8Path,
         revision: &VcsRevision,
+    ) -> anyhow::Result<GitCommitHash> {
+        let Some(cache) = &self.cache else {
+            return self.merge_base_head_uncached(repo_root, revision);
+        };
+        let head = self.head_commit(repo_root)?;
+        cache.vcs_cache().merge_base(revision, head, || {
+            self.merge_base_head_uncached(repo_root, revision)
+        })
+    }
+
+    fn merge_base_head_uncached(
+        &self,
+        repo_root: &Utf8Path,
+        revision: &VcsRevision,
     ) -> anyhow::Result<GitCommitHash> {
         match &self.kind {
             RepoVcsKind::Git => {
-                super::git::git_merge_base_head(repo_root, revision)
+                super::git::git_merge_base_head(&self.repo(repo_root), revision)
             }
             RepoVcsKind::Jj => {
-                super::jj::jj_merge_base_head(repo_root, revision)
+                let error = match super::jj::jj_merge_base_head(
+                    &self.repo(repo_root),
+                    revision,
+                ) {
+                    Ok(commit) => return Ok(commit),
+                    Err(error) => error,
+                };
+                if !self.colocated {
+                    return Err(error);
+                }
+                match super::git::git_ref_to_jj_revset(
+                    &self.repo(repo_root),
+                    revision,
+                )? {
+                    Some(translated) => super::jj::jj_merge_base_head(
+                        &self.repo(repo_root),
+                        &translated,
+                    )
+                    .with_context(|| {
+                        format!(
+                            "resolving Git ref {revision} as jj \
+                                     revset {translated}"
+                        )
+                    }),
+                    None => Err(error),
+                }
+            }
+            RepoVcsKind::None => Err(no_vcs_error()),
+        }
+    }
+
+    /// Returns the merge, rebase or cherry-pick that's in progress, if any.
+    ///
+    /// This is always `None` for Jujutsu, which records conflicts in commits
+    /// rather than stopping partway through operations.
+    pub(crate) fn operation_in_progress(
+        &self,
+        repo_root: &Utf8Path,
+    ) -> Option<VcsOperation> {
+        match &self.kind {
+            RepoVcsKind::Git => {
+                super::git::git_operation_in_progress(&self.repo(repo_root))
+            }
+            RepoVcsKind::Jj | RepoVcsKind::None => None,
+        }
+    }
+
+    /// Returns the commit the working copy is based on: `HEAD` for Git, and
+    /// the working-copy commit `@` for Jujutsu.
+    pub(crate) fn head_commit(
+        &self,
+        repo_root: &Utf8Path,
+    ) -> anyhow::Result<GitCommitHash> {
+        match &self.kind {
+            RepoVcsKind::Git => {
+                super::git::git_head_commit(&self.repo(repo_root))
             }
+            RepoVcsKind::Jj => super::jj::jj_head_commit(&self.repo(repo_root)),
+            RepoVcsKind::None => Err(no_vcs_error()),
         }
     }
 
@@ -116,13 +329,16 @@ impl RepoVcs {
     ) -> anyhow::Result<bool> {
         match &self.kind {
             RepoVcsKind::Git => super::git::git_is_ancestor(
-                repo_root,
+                &self.repo(repo_root),
                 potential_ancestor,
                 commit,
             ),
-            RepoVcsKind::Jj => {
-                super::jj::jj_is_ancestor(repo_root, potential_ancestor, commit)
-            }
+            RepoVcsKind::Jj => super::jj::jj_is_ancestor(
+                &self.repo(repo_root),
+                potential_ancestor,
+                commit,
+            ),
+            RepoVcsKind::None => Err(no_vcs_error()),
         }
     }
 
@@ -134,14 +350,33 @@ impl RepoVcs {
         repo_root: &Utf8Path,
         revision: GitCommitHash,
         directory: &Utf8Path,
+    ) -> anyhow::Result<Vec<Utf8PathBuf>> {
+        if let Some(cache) = &self.cache {
+            return cache.vcs_cache().file_list(revision, directory, || {
+                self.list_files_uncached(repo_root, revision, directory)
+            });
+        }
+        self.list_files_uncached(repo_root, revision, directory)
+    }
+
+    fn list_files_uncached(
+        &self,
+        repo_root: &Utf8Path,
+        revision: GitCommitHash,
+        directory: &Utf8Path,
     ) -> anyhow::Result<Vec<Utf8PathBuf>> {
         match &self.kind {
-            RepoVcsKind::Git => {
-                super::git::git_ls_tree(repo_root, revision, directory)
-            }
-            RepoVcsKind::Jj => {
-                super::jj::jj_list_files(repo_root, revision, directory)
-            }
+            RepoVcsKind::Git => super::git::git_ls_tree(
+                &self.repo(repo_root),
+                revision,
+                directory,
+            ),
+            RepoVcsKind::Jj => super::jj::jj_list_files(
+                &self.repo(repo_root),
+                revision,
+                directory,
+            ),
+            RepoVcsKind::None => Err(no_vcs_error()),
         }
     }
 
@@ -151,39 +386,129 @@ impl RepoVcs {
         repo_root: &Utf8Path,
         revision: GitCommitHash,
         path: &Utf8Path,
+    ) -> anyhow::Result<Vec<u8>> {
+        if let Some(cache) = &self.cache {
+            return cache.vcs_cache().contents(revision, path, || {
+                self.show_file_uncached(repo_root, revision, path)
+            });
+        }
+        self.show_file_uncached(repo_root, revision, path)
+    }
+
+    fn show_file_uncached(
+        &self,
+        repo_root: &Utf8Path,
+        revision: GitCommitHash,
+        path: &Utf8Path,
     ) -> anyhow::Result<Vec<u8>> {
         match &self.kind {
             RepoVcsKind::Git => {
-                super::git::git_show_file(repo_root, revision, path)
+                super::git::git_show_file(&self.repo(repo_root), revision, path)
             }
             RepoVcsKind::Jj => {
-                super::jj::jj_show_file(repo_root, revision, path)
+                super::jj::jj_show_file(&self.repo(repo_root), revision, path)
             }
+            RepoVcsKind::None => Err(no_vcs_error()),
         }
     }
 
+    /// Returns the files under `directory` whose contents in the working copy
+    /// differ from `revision`, as paths relative to the repository root.
+    ///
+    /// Returns `None` if this can't be determined without reading every file:
+    /// Jujutsu only compares against its last snapshot of the working copy,
+    /// so this is only supported for Git and colocated Jujutsu repositories.
+    pub(crate) fn changed_in_working_copy(
+        &self,
+        repo_root: &Utf8Path,
+        revision: GitCommitHash,
+        directory: &Utf8Path,
+    ) -> anyhow::Result<Option<BTreeSet<Utf8PathBuf>>> {
+        match &self.kind {
+            RepoVcsKind::Git => {}
+            RepoVcsKind::Jj if self.colocated => {}
+            RepoVcsKind::Jj | RepoVcsKind::None => return Ok(None),
+        }
+        super::git::git_diff_working_tree(
+            &self.repo(repo_root),
+            revision,
+            directory,
+        )
+        .map(Some)
+    }
+
     /// Find the most recent commit that *added* a file, searching
-    /// backwards from the given revision.
+    /// backwards from the given revision. Returns the commit along with the
+    /// file's path in that commit.
     ///
-    /// For Git, this uses `--diff-filter=A`. For Jujutsu, this uses a
-    /// revset to find touching commits and a template filter to select
-    /// only those where the file's diff status is `"added"`.
+    /// For Git, this uses `--diff-filter=A`, and follows exact moves of the
+    /// file from other directories (so the path may differ from `path`). For
+    /// Jujutsu, this uses a revset to find touching commits and a template
+    /// filter to select only those where the file's diff status is
+    /// `"added"`.
     pub(crate) fn first_commit_for_file(
         &self,
         repo_root: &Utf8Path,
         revision: GitCommitHash,
         path: &Utf8Path,
-    ) -> anyhow::Result<GitCommitHash> {
+    ) -> anyhow::Result<(GitCommitHash, Utf8PathBuf)> {
         match &self.kind {
-            RepoVcsKind::Git => {
-                super::git::git_first_commit_for_file(repo_root, revision, path)
-            }
+            RepoVcsKind::Git => super::git::git_first_commit_for_file(
+                &self.repo(repo_root),
+                revision,
+                path,
+            ),
             RepoVcsKind::Jj => {
-                super::jj::jj_first_commit_for_file(repo_root, revision, path)
+                let commit = super::jj::jj_first_commit_for_file(
+                    &self.repo(repo_root),
+                    revision,
+                    path,
+                )?;
+                Ok((commit, path.to_owned()))
             }
+            RepoVcsKind::None => Err(no_vcs_error()),
         }
     }
 
+    /// If `directory` doesn't exist in `revision` because it was renamed
+    /// later on (in the history leading up to the working copy), returns its
+    /// name in `revision`. Otherwise, returns `directory`.
+    ///
+    /// Only supported for Git; for Jujutsu, `directory` is always returned.
+    pub(crate) fn directory_at_revision(
+        &self,
+        repo_root: &Utf8Path,
+        revision: GitCommitHash,
+        directory: &Utf8Path,
+    ) -> anyhow::Result<Utf8PathBuf> {
+        if let Some(cache) = &self.cache {
+            return cache.vcs_cache().directory(revision, directory, || {
+                self.directory_at_revision_uncached(
+                    repo_root, revision, directory,
+                )
+            });
+        }
+        self.directory_at_revision_uncached(repo_root, revision, directory)
+    }
+
+    fn directory_at_revision_uncached(
+        &self,
+        repo_root: &Utf8Path,
+        revision: GitCommitHash,
+        directory: &Utf8Path,
+    ) -> anyhow::Result<Utf8PathBuf> {
+        let previous = match &self.kind {
+            RepoVcsKind::Git => super::git::git_directory_before_rename(
+                &self.repo(repo_root),
+                revision,
+                directory,
+            )?,
+            RepoVcsKind::Jj => None,
+            RepoVcsKind::None => return Err(no_vcs_error()),
+        };
+        Ok(previous.unwrap_or_else(|| directory.to_owned()))
+    }
+
     /// Returns true if the repository is a shallow clone.
     ///
     /// If the check fails (e.g. because the VCS binary is missing or the
@@ -193,7 +518,10 @@ impl RepoVcs {
     /// shallow; the downstream git-stub resolution will surface a
     /// clearer error in that case.
     pub(crate) fn is_shallow_clone(&self, repo_root: &Utf8Path) -> bool {
-        match self.stub_vcs.is_shallow_clone(repo_root) {
+        let Some(stub_vcs) = &self.stub_vcs else {
+            return false;
+        };
+        match stub_vcs.is_shallow_clone(repo_root) {
             Ok(is_shallow) => is_shallow,
             Err(err) => {
                 eprintln!(
@@ -205,13 +533,315 @@ impl RepoVcs {
         }
     }
 
+    /// Returns true if `directory` (relative to `repo_root`) is excluded,
+    /// fully or partially, from the working copy by a sparse checkout.
+    ///
+    /// As with [`Self::is_shallow_clone`], if the check fails, a warning is
+    /// printed and `false` is returned.
+    pub(crate) fn is_excluded_by_sparse_checkout(
+        &self,
+        repo_root: &Utf8Path,
+        directory: &Utf8Path,
+    ) -> bool {
+        let res = match &self.kind {
+            RepoVcsKind::Git => super::git::git_is_excluded_by_sparse_checkout(
+                &self.repo(repo_root),
+                directory,
+            ),
+            RepoVcsKind::Jj => super::jj::jj_is_excluded_by_sparse_checkout(
+                &self.repo(repo_root),
+                directory,
+            ),
+            RepoVcsKind::None => return false,
+        };
+        match res {
+            Ok(excluded) => excluded,
+            Err(err) => {
+                eprintln!(
+                    "warning: failed to check if {directory} is excluded by \
+                     a sparse checkout: {err:#}"
+                );
+                false
+            }
+        }
+    }
+
+    /// Determine whether `commit` is present and reachable from a ref.
+    pub(crate) fn commit_reachability(
+        &self,
+        repo_root: &Utf8Path,
+        commit: GitCommitHash,
+    ) -> anyhow::Result<CommitReachability> {
+        match &self.kind {
+            RepoVcsKind::Git => super::git::git_commit_reachability(
+                &self.repo(repo_root),
+                commit,
+            ),
+            RepoVcsKind::Jj => {
+                super::jj::jj_commit_reachability(&self.repo(repo_root), commit)
+            }
+            RepoVcsKind::None => Err(no_vcs_error()),
+        }
+    }
+
+    /// Create a ref under [`PIN_REF_PREFIX`] pointing to `commit`, so that it
+    /// is not garbage collected.
+    ///
+    /// Only supported for Git: Jujutsu has no equivalent of arbitrary refs.
+    pub(crate) fn pin_commit(
+        &self,
+        repo_root: &Utf8Path,
+        commit: GitCommitHash,
+    ) -> anyhow::Result<()> {
+        match &self.kind {
+            RepoVcsKind::Git => {
+                super::git::git_pin_commit(&self.repo(repo_root), commit)
+            }
+            RepoVcsKind::Jj => bail!(
+                "pinning commits is only supported in Git repositories \
+                 (in a Jujutsu repository, create a bookmark at {commit} \
+                 instead)"
+            ),
+            RepoVcsKind::None => Err(no_vcs_error()),
+        }
+    }
+
+    /// Returns the untracked files under `directory` (other than ignored
+    /// ones), as paths relative to the repository root.
+    ///
+    /// Returns `None` if there's no notion of untracked files: Jujutsu tracks
+    /// new files automatically, and without version control, nothing is
+    /// tracked.
+    pub(crate) fn untracked_files(
+        &self,
+        repo_root: &Utf8Path,
+        directory: &Utf8Path,
+    ) -> anyhow::Result<Option<BTreeSet<Utf8PathBuf>>> {
+        match &self.kind {
+            RepoVcsKind::Git => super::git::git_untracked_files(
+                &self.repo(repo_root),
+                directory,
+            )
+            .map(Some),
+            RepoVcsKind::Jj | RepoVcsKind::None => Ok(None),
+        }
+    }
+
+    /// Returns the files under `directory` with uncommitted changes, including
+    /// untracked files, as paths relative to the repository root.
+    ///
+    /// Only supported for Git, since it's only used alongside notes.
+    pub(crate) fn uncommitted_files(
+        &self,
+        repo_root: &Utf8Path,
+        directory: &Utf8Path,
+    ) -> anyhow::Result<BTreeSet<Utf8PathBuf>> {
+        match &self.kind {
+            RepoVcsKind::Git => super::git::git_uncommitted_files(
+                &self.repo(repo_root),
+                directory,
+            ),
+            RepoVcsKind::Jj => {
+                bail!("notes are only supported in Git repositories")
+            }
+            RepoVcsKind::None => Err(no_vcs_error()),
+        }
+    }
+
+    /// Adds a note with `contents` to `commit` under [`CHECK_NOTES_REF`],
+    /// replacing any existing note.
+    ///
+    /// Only supported for Git: Jujutsu doesn't have notes.
+    pub(crate) fn add_check_note(
+        &self,
+        repo_root: &Utf8Path,
+        commit: GitCommitHash,
+        contents: &[u8],
+    ) -> anyhow::Result<()> {
+        match &self.kind {
+            RepoVcsKind::Git => super::git::git_add_note(
+                &self.repo(repo_root),
+                CHECK_NOTES_REF,
+                commit,
+                contents,
+            ),
+            RepoVcsKind::Jj => {
+                bail!("notes are only supported in Git repositories")
+            }
+            RepoVcsKind::None => Err(no_vcs_error()),
+        }
+    }
+
+    /// Resolves `revision` to a commit, and returns it along with its note
+    /// under [`CHECK_NOTES_REF`], if any.
+    ///
+    /// Only supported for Git: Jujutsu doesn't have notes.
+    pub(crate) fn check_note(
+        &self,
+        repo_root: &Utf8Path,
+        revision: &VcsRevision,
+    ) -> anyhow::Result<(GitCommitHash, Option<String>)> {
+        match &self.kind {
+            RepoVcsKind::Git => super::git::git_show_note(
+                &self.repo(repo_root),
+                CHECK_NOTES_REF,
+                revision,
+            ),
+            RepoVcsKind::Jj => {
+                bail!("notes are only supported in Git repositories")
+            }
+            RepoVcsKind::None => Err(no_vcs_error()),
+        }
+    }
+
     /// Resolve a Git stub to its JSON document contents.
+    ///
+    /// If the referenced commit is missing locally, see
+    /// [`Self::recover_missing_commit`].
     pub(crate) fn resolve_stub_contents(
         &self,
         git_stub: &GitStub,
         repo_root: &Utf8Path,
     ) -> anyhow::Result<Vec<u8>> {
-        Ok(self.stub_vcs.read_git_stub_contents(git_stub, repo_root)?)
+        let Some(stub_vcs) = &self.stub_vcs else {
+            return Err(no_vcs_error());
+        };
+        match stub_vcs.read_git_stub_contents(git_stub, repo_root) {
+            Ok(contents) => Ok(contents),
+            Err(error) => {
+                if self.recover_missing_commit(repo_root, git_stub.commit())? {
+                    Ok(stub_vcs.read_git_stub_contents(git_stub, repo_root)?)
+                } else {
+                    Err(error.into())
+                }
+            }
+        }
+    }
+
+    /// Creates a temporary worktree containing the merge of HEAD with `base`,
+    /// the way a merge queue would evaluate it.
+    ///
+    /// Only committed changes are included. Only supported for Git.
+    pub(crate) fn create_merge_worktree(
+        &self,
+        repo_root: &Utf8Path,
+        base: &VcsRevision,
+    ) -> anyhow::Result<MergeWorktree> {
+        match &self.kind {
+            RepoVcsKind::Git => {}
+            RepoVcsKind::Jj => bail!(
+                "checking the merge with a base revision is only supported \
+                 in Git repositories (in a Jujutsu repository, create the \
+                 merge with `jj new @ {base}` instead)"
+            ),
+            RepoVcsKind::None => return Err(no_vcs_error()),
+        }
+
+        let temp_dir = Utf8TempDir::with_prefix("dropshot-api-manager-")
+            .context("creating temporary directory for worktree")?;
+        let path = temp_dir.path().join("merge");
+        super::git::git_worktree_add(&self.repo(repo_root), &path)
+            .context("creating temporary worktree")?;
+        // From here on, dropping `worktree` removes it.
+        let worktree = MergeWorktree {
+            repo_root: repo_root.to_owned(),
+            path,
+            runner: self.runner.clone(),
+            _temp_dir: temp_dir,
+        };
+        super::git::git_worktree_merge(&self.repo(&worktree.path), base)?;
+        Ok(worktree)
+    }
+
+    /// Called after an operation involving `commit` has failed, to handle the
+    /// case where the commit isn't present locally (common right after a
+    /// rebase-heavy fetch).
+    ///
+    /// * If the commit is present, returns `Ok(false)`: the failure has some
+    ///   other cause.
+    /// * If the commit is missing and fetching missing commits is enabled
+    ///   (Git only), fetches the commit and returns `Ok(true)`, indicating
+    ///   that the operation should be retried.
+    /// * Otherwise, returns an error that includes the command to fetch the
+    ///   commit.
+    pub(crate) fn recover_missing_commit(
+        &self,
+        repo_root: &Utf8Path,
+        commit: GitCommitHash,
+    ) -> anyhow::Result<bool> {
+        if self.commit_reachability(repo_root, commit)?
+            != CommitReachability::Missing
+        {
+            return Ok(false);
+        }
+
+        match (&self.kind, &self.fetch_missing_from) {
+            (RepoVcsKind::Git, Some(remote)) => {
+                let _guard = FETCH_LOCK
+                    .lock()
+                    .unwrap_or_else(|error| error.into_inner());
+                // Another thread may have fetched the commit while we were
+                // waiting for the lock.
+                if self.commit_reachability(repo_root, commit)?
+                    == CommitReachability::Missing
+                {
+                    super::git::git_fetch_commit(
+                        &self.repo(repo_root),
+                        remote,
+                        commit,
+                    )
+                    .with_context(|| {
+                        format!(
+                            "commit {commit} is not present locally, \
+                                 and fetching it from {remote} failed"
+                        )
+                    })?;
+                }
+                Ok(true)
+            }
+            (RepoVcsKind::Git, None) => bail!(
+                "commit {commit} is not present locally (to fetch it, run \
+                 `git fetch origin {commit}`)"
+            ),
+            (RepoVcsKind::Jj, _) => bail!(
+                "commit {commit} is not present locally (to fetch it, run \
+                 `jj git fetch`)"
+            ),
+            // commit_reachability returns an error above.
+            (RepoVcsKind::None, _) => Err(no_vcs_error()),
+        }
+    }
+}
+
+/// A temporary Git worktree containing the merge of HEAD with a base
+/// revision.
+///
+/// The worktree is removed when this is dropped.
+#[derive(Debug)]
+pub(crate) struct MergeWorktree {
+    repo_root: Utf8PathBuf,
+    path: Utf8PathBuf,
+    runner: Arc<dyn GitRunner>,
+    // Held so that the directory is deleted after the worktree is removed.
+    _temp_dir: Utf8TempDir,
+}
+
+impl MergeWorktree {
+    /// Returns the root of the worktree.
+    pub(crate) fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+}
+
+impl Drop for MergeWorktree {
+    fn drop(&mut self) {
+        let repo = Repo { root: &self.repo_root, runner: &*self.runner };
+        if let Err(error) = super::git::git_worktree_remove(&repo, &self.path) {
+            eprintln!(
+                "warning: failed to remove temporary worktree {}: {:#}",
+                self.path, error
+            );
+        }
     }
 }
 
@@ -220,9 +850,12 @@ impl RepoVcs {
 /// Runs a command, returning stdout as raw bytes on success. Unlike
 /// [`do_run`], this does not require the output to be valid UTF-8 and
 /// is suitable for commands that return file contents.
-pub(super) fn do_run_bytes(cmd: &mut Command) -> anyhow::Result<Vec<u8>> {
+pub(super) fn do_run_bytes(
+    cmd: &mut VcsCommand<'_>,
+) -> anyhow::Result<Vec<u8>> {
     let label = cmd_label(cmd);
-    let output = cmd.output().with_context(|| format!("invoking {:?}", cmd))?;
+    let output =
+        trace::output(cmd).with_context(|| format!("invoking {:?}", &**cmd))?;
     if output.status.success() {
         return Ok(output.stdout);
     }
@@ -241,7 +874,7 @@ pub(super) fn do_run_bytes(cmd: &mut Command) -> anyhow::Result<Vec<u8>> {
 
 /// Runs a command, returning stdout on success and an error including
 /// the exit status and stderr contents on failure.
-pub(super) fn do_run(cmd: &mut Command) -> anyhow::Result<String> {
+pub(super) fn do_run(cmd: &mut VcsCommand<'_>) -> anyhow::Result<String> {
     let label = cmd_label(cmd);
     let stdout = do_run_bytes(cmd)?;
     String::from_utf8(stdout).with_context(|| {
@@ -6,11 +6,13 @@
 //! `git.rs`. They are called from `RepoVcs` when the detected backend
 //! is Jujutsu.
 
-use super::imp::{VcsRevision, cmd_label, do_run, do_run_bytes};
+use super::{
+    imp::{CommitReachability, VcsRevision, cmd_label, do_run, do_run_bytes},
+    runner::{Repo, VcsCommand},
+};
 use anyhow::{Context, bail};
 use camino::{Utf8Path, Utf8PathBuf};
 use git_stub::GitCommitHash;
-use std::process::Command;
 
 /// Given a revision, return its merge base with the current working state.
 ///
@@ -19,10 +21,10 @@ use std::process::Command;
 /// `heads(::@ & ::REV)` returns the correct merge base without needing to
 /// special-case in-progress merges.
 pub(super) fn jj_merge_base_head(
-    repo_root: &Utf8Path,
+    repo: &Repo<'_>,
     revision: &VcsRevision,
 ) -> anyhow::Result<GitCommitHash> {
-    let mut cmd = jj_start(repo_root);
+    let mut cmd = jj_start(repo);
     cmd.args([
         "log",
         "--revisions",
@@ -65,15 +67,33 @@ pub(super) fn jj_merge_base_head(
     })
 }
 
+/// Returns the working-copy commit.
+pub(super) fn jj_head_commit(repo: &Repo<'_>) -> anyhow::Result<GitCommitHash> {
+    let mut cmd = jj_start(repo);
+    cmd.args([
+        "log",
+        "--revisions",
+        "@",
+        "--template",
+        "commit_id",
+        "--no-graph",
+    ]);
+    let stdout = do_run(&mut cmd)?;
+    let stdout = stdout.trim();
+    stdout.parse().with_context(|| {
+        format!("jj returned invalid commit hash: {:?}", stdout)
+    })
+}
+
 /// Check if `potential_ancestor` is an ancestor of `commit`.
 ///
 /// The revset used is `potential_ancestor & ::commit`.
 pub(super) fn jj_is_ancestor(
-    repo_root: &Utf8Path,
+    repo: &Repo<'_>,
     potential_ancestor: GitCommitHash,
     commit: GitCommitHash,
 ) -> anyhow::Result<bool> {
-    let mut cmd = jj_start(repo_root);
+    let mut cmd = jj_start(repo);
     cmd.args([
         "log",
         "--revisions",
@@ -102,11 +122,11 @@ pub(super) fn jj_is_ancestor(
 ///
 /// Returns paths relative to `directory`.
 pub(super) fn jj_list_files(
-    repo_root: &Utf8Path,
+    repo: &Repo<'_>,
     revision: GitCommitHash,
     directory: &Utf8Path,
 ) -> anyhow::Result<Vec<Utf8PathBuf>> {
-    let mut cmd = jj_start(repo_root);
+    let mut cmd = jj_start(repo);
     cmd.args([
         "file",
         "list",
@@ -141,11 +161,11 @@ pub(super) fn jj_list_files(
 
 /// Return the contents of a file at the given path in a revision.
 pub(super) fn jj_show_file(
-    repo_root: &Utf8Path,
+    repo: &Repo<'_>,
     revision: GitCommitHash,
     path: &Utf8Path,
 ) -> anyhow::Result<Vec<u8>> {
-    let mut cmd = jj_start(repo_root);
+    let mut cmd = jj_start(repo);
     cmd.args(["file", "show", "--revision", &revision.to_string(), "--"])
         .arg(path);
     do_run_bytes(&mut cmd)
@@ -169,7 +189,7 @@ pub(super) fn jj_show_file(
 /// We take the first (most recent) introducing commit, matching Git's
 /// behavior for files that were removed and re-added.
 pub(super) fn jj_first_commit_for_file(
-    repo_root: &Utf8Path,
+    repo: &Repo<'_>,
     revision: GitCommitHash,
     path: &Utf8Path,
 ) -> anyhow::Result<GitCommitHash> {
@@ -186,7 +206,7 @@ pub(super) fn jj_first_commit_for_file(
          ), commit_id ++ \"\\n\", \"\")",
     );
 
-    let mut cmd = jj_start(repo_root);
+    let mut cmd = jj_start(repo);
     cmd.args([
         "log",
         "--revisions",
@@ -217,12 +237,70 @@ pub(super) fn jj_first_commit_for_file(
     })
 }
 
+/// Determines whether `commit` is present in the repository, and if so,
+/// whether it is an ancestor of any bookmark, remote bookmark, or tag.
+///
+/// `present()` evaluates to the empty set rather than failing if the commit
+/// is unknown.
+pub(super) fn jj_commit_reachability(
+    repo: &Repo<'_>,
+    commit: GitCommitHash,
+) -> anyhow::Result<CommitReachability> {
+    let mut cmd = jj_start(repo);
+    cmd.args([
+        "log",
+        "--revisions",
+        &format!("present({commit})"),
+        "--template",
+        "commit_id",
+        "--no-graph",
+    ]);
+    if do_run(&mut cmd)?.trim().is_empty() {
+        return Ok(CommitReachability::Missing);
+    }
+
+    let mut cmd = jj_start(repo);
+    cmd.args([
+        "log",
+        "--revisions",
+        &format!("{commit} & ::(bookmarks() | remote_bookmarks() | tags())"),
+        "--template",
+        "commit_id",
+        "--no-graph",
+    ]);
+    if do_run(&mut cmd)?.trim().is_empty() {
+        Ok(CommitReachability::Unreferenced)
+    } else {
+        Ok(CommitReachability::Reachable)
+    }
+}
+
+/// Returns true if `directory` is not fully included in the working copy's
+/// sparse patterns.
+///
+/// `jj sparse list` prints one path prefix per line, with `.` meaning the
+/// whole repository.
+pub(super) fn jj_is_excluded_by_sparse_checkout(
+    repo: &Repo<'_>,
+    directory: &Utf8Path,
+) -> anyhow::Result<bool> {
+    let mut cmd = jj_start(repo);
+    cmd.args(["sparse", "list"]);
+    let stdout = do_run(&mut cmd)?;
+    let included = stdout
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .any(|pattern| pattern == "." || directory.starts_with(pattern));
+    Ok(!included)
+}
+
 /// Quote a string for use inside a jj revset expression.
 ///
 /// Uses double quotes with `"` and `\` escaped. File paths might contain
 /// characters with special meaning in the revset grammar, a common one being
 /// `-`.
-fn revset_quote(s: &str) -> String {
+pub(super) fn revset_quote(s: &str) -> String {
     let mut out = String::with_capacity(s.len() + 2);
     out.push('"');
     for c in s.chars() {
@@ -243,10 +321,9 @@ fn revset_quote(s: &str) -> String {
 /// Passes `--no-pager`, `--color=never`, and
 /// `--ignore-working-copy` so that output is deterministic and
 /// parseable regardless of user configuration.
-fn jj_start(repo_root: &Utf8Path) -> Command {
+fn jj_start<'a>(repo: &Repo<'a>) -> VcsCommand<'a> {
     let jj = std::env::var("JJ").ok().unwrap_or_else(|| String::from("jj"));
-    let mut command = Command::new(&jj);
-    command.current_dir(repo_root);
+    let mut command = repo.command(&jj);
     command.args(["--no-pager", "--color", "never", "--ignore-working-copy"]);
     command
 }
@@ -6,9 +6,19 @@
 //! the API manager. It wraps both Git and Jujutsu backends, delegating
 //! to the appropriate implementation based on repository detection.
 
+mod cache;
 mod git;
 mod imp;
 mod jj;
+mod runner;
+mod trace;
 
+pub use cache::BlessedCache;
 pub use imp::VcsRevision;
-pub(crate) use imp::{RepoVcs, RepoVcsKind};
+pub(crate) use imp::{
+    CHECK_NOTES_REF, CommitReachability, MergeWorktree, PIN_REF_PREFIX,
+    RepoVcs, RepoVcsKind, VcsOperation,
+};
+pub use runner::GitRunner;
+pub(crate) use runner::SubprocessRunner;
+pub(crate) use trace::GitTrace;
@@ -0,0 +1,85 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Running the git and jj commands used to access repository data.
+
+use camino::Utf8Path;
+use std::{
+    fmt, io,
+    ops::{Deref, DerefMut},
+    process::{Command, Output},
+};
+
+/// Runs the git commands the API manager uses to read repository history,
+/// such as `git merge-base` and `git cat-file`.
+///
+/// By default, commands are run as subprocesses. Attach a different runner
+/// with [`Environment::with_git_runner`] to run them some other way, e.g.
+/// within a sandbox, or to answer them with canned responses in tests.
+///
+/// In Jujutsu repositories, jj commands are run through the runner as well.
+/// Commands run while resolving Git stubs don't use the runner.
+///
+/// [`Environment::with_git_runner`]: crate::Environment::with_git_runner
+pub trait GitRunner: fmt::Debug + Send + Sync {
+    /// Runs `command` to completion and collects its output, like
+    /// [`Command::output`].
+    ///
+    /// `command`'s standard output and standard error are expected to be
+    /// captured. A non-zero exit status isn't an error at this level: callers
+    /// inspect [`Output::status`] themselves.
+    fn output(&self, command: &mut Command) -> io::Result<Output>;
+}
+
+/// The default [`GitRunner`], which runs commands as subprocesses.
+#[derive(Debug)]
+pub(crate) struct SubprocessRunner;
+
+impl GitRunner for SubprocessRunner {
+    fn output(&self, command: &mut Command) -> io::Result<Output> {
+        command.output()
+    }
+}
+
+/// A repository root, along with the runner used for commands in it.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Repo<'a> {
+    pub(super) root: &'a Utf8Path,
+    pub(super) runner: &'a dyn GitRunner,
+}
+
+impl<'a> Repo<'a> {
+    /// Begins assembling an invocation of `program` in the repository.
+    pub(super) fn command(&self, program: &str) -> VcsCommand<'a> {
+        let mut command = Command::new(program);
+        command.current_dir(self.root);
+        VcsCommand { command, runner: self.runner }
+    }
+}
+
+/// A command being assembled, along with the runner that will run it.
+#[derive(Debug)]
+pub(super) struct VcsCommand<'a> {
+    command: Command,
+    runner: &'a dyn GitRunner,
+}
+
+impl VcsCommand<'_> {
+    /// Runs the command using its runner.
+    pub(super) fn run(&mut self) -> io::Result<Output> {
+        self.runner.output(&mut self.command)
+    }
+}
+
+impl Deref for VcsCommand<'_> {
+    type Target = Command;
+
+    fn deref(&self) -> &Command {
+        &self.command
+    }
+}
+
+impl DerefMut for VcsCommand<'_> {
+    fn deref_mut(&mut self) -> &mut Command {
+        &mut self.command
+    }
+}
@@ -0,0 +1,108 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Logs the VCS commands the API manager runs, for `--trace-git`.
+//!
+//! The trace covers a whole run of the command-line tool, including commands
+//! run in parallel while loading blessed documents, so it's process-wide.
+
+use super::runner::VcsCommand;
+use camino::Utf8Path;
+use std::{
+    io::{self, Write},
+    process::Output,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+static TRACE: Mutex<Option<Trace>> = Mutex::new(None);
+
+struct Trace {
+    out: Box<dyn Write + Send>,
+    commands: usize,
+    elapsed: Duration,
+    bytes_read: usize,
+}
+
+/// Traces commands until dropped, at which point a summary is written.
+#[derive(Debug)]
+pub(crate) struct GitTrace(());
+
+impl GitTrace {
+    /// Starts logging each command to `path`, or to standard error if `path`
+    /// is `-`.
+    ///
+    /// If the file exists, the trace is appended to it.
+    pub(crate) fn start(path: &Utf8Path) -> anyhow::Result<Self> {
+        let out: Box<dyn Write + Send> = if path == "-" {
+            Box::new(io::stderr())
+        } else {
+            Box::new(
+                fs_err::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)?,
+            )
+        };
+        *lock() = Some(Trace {
+            out,
+            commands: 0,
+            elapsed: Duration::ZERO,
+            bytes_read: 0,
+        });
+        Ok(Self(()))
+    }
+}
+
+impl Drop for GitTrace {
+    fn drop(&mut self) {
+        if let Some(mut trace) = lock().take() {
+            let _ = writeln!(
+                trace.out,
+                "git trace: {} {} in {:.1?}, {} bytes read",
+                trace.commands,
+                if trace.commands == 1 { "command" } else { "commands" },
+                trace.elapsed,
+                trace.bytes_read,
+            );
+        }
+    }
+}
+
+/// Runs `cmd`, logging it if tracing is enabled.
+pub(super) fn output(cmd: &mut VcsCommand<'_>) -> io::Result<Output> {
+    let start = Instant::now();
+    let result = cmd.run();
+    let elapsed = start.elapsed();
+
+    let mut guard = lock();
+    let Some(trace) = guard.as_mut() else {
+        return result;
+    };
+    let cwd = cmd
+        .get_current_dir()
+        .map_or_else(|| "(current directory)".into(), |d| d.to_string_lossy());
+    let outcome = match &result {
+        Ok(output) => {
+            let bytes_read = output.stdout.len() + output.stderr.len();
+            trace.bytes_read += bytes_read;
+            format!("{}, {} bytes read", output.status, bytes_read)
+        }
+        Err(error) => format!("failed to start: {}", error),
+    };
+    trace.commands += 1;
+    trace.elapsed += elapsed;
+    let _ = writeln!(
+        trace.out,
+        "git trace: {} (in {}): {}, {:.1?}",
+        super::imp::cmd_label(cmd),
+        cwd,
+        outcome,
+        elapsed,
+    );
+    result
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Option<Trace>> {
+    // A panic while tracing leaves nothing inconsistent.
+    TRACE.lock().unwrap_or_else(|error| error.into_inner())
+}
@@ -0,0 +1,166 @@
+// Copyright 2026 Oxide Computer Company
+
+//! Checks that the version ranges of an API's endpoints line up with its
+//! supported versions.
+//!
+//! Dropshot includes an endpoint in the document for a version only if the
+//! version is within the endpoint's `versions` range. An endpoint whose range
+//! doesn't contain any supported version silently never appears in any
+//! document. Similarly, a range that starts or ends at a version that isn't
+//! supported usually refers to a version that was never added.
+//!
+//! Once older versions are retired, ranges that refer to them become
+//! misleading, so these are reported with suggestions for cleaning them up:
+//! endpoints only in retired versions can be removed, and ranges starting at a
+//! retired version can start at the oldest supported version instead.
+//!
+//! Dropshot doesn't expose the ranges themselves, so they're found by also
+//! generating documents for probe versions: every major version up to one past
+//! the latest supported version, along with a version far in the future. The
+//! versions defined by `api_versions!` are all major versions, so this finds
+//! every range bound that refers to one of them. Bounds between major versions
+//! may be missed.
+
+use crate::apis::ManagedApi;
+use openapiv3::OpenAPI;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Returns warnings about the endpoints of the versioned API `api` whose
+/// version ranges don't line up with its supported versions.
+///
+/// `supported` maps each supported version to its generated document.
+pub(crate) fn check_version_ranges(
+    api: &ManagedApi,
+    supported: &BTreeMap<semver::Version, &OpenAPI>,
+) -> Vec<anyhow::Error> {
+    let (Some((oldest, _)), Some((latest, _))) =
+        (supported.first_key_value(), supported.last_key_value())
+    else {
+        return Vec::new();
+    };
+    let far_future = semver::Version::new(u64::MAX, 0, 0);
+    let probes: Vec<_> = (0..=latest.major + 1)
+        .map(|major| semver::Version::new(major, 0, 0))
+        .chain(std::iter::once(far_future.clone()))
+        .filter(|version| !supported.contains_key(version))
+        .collect();
+
+    // The endpoints in each version, supported or probed. Probe versions that
+    // fail to generate are skipped: errors generating supported versions are
+    // reported elsewhere.
+    let mut endpoints_by_version: BTreeMap<semver::Version, BTreeSet<String>> =
+        supported
+            .iter()
+            .map(|(version, document)| (version.clone(), endpoints(document)))
+            .collect();
+    endpoints_by_version.extend(
+        probes
+            .into_par_iter()
+            .filter_map(|version| {
+                let document = api.generate_openapi_doc(&version).ok()?;
+                Some((version, endpoints(&document)))
+            })
+            .collect::<Vec<_>>(),
+    );
+
+    let all_endpoints: BTreeSet<&String> =
+        endpoints_by_version.values().flatten().collect();
+    let ident = api.ident();
+    let mut warnings = Vec::new();
+    for endpoint in all_endpoints {
+        let present: Vec<_> = endpoints_by_version
+            .iter()
+            .filter(|(_, endpoints)| endpoints.contains(endpoint))
+            .map(|(version, _)| version)
+            .collect();
+        if !present.iter().any(|version| supported.contains_key(*version)) {
+            let last = present.last().expect("endpoint is in some version");
+            if *last < oldest {
+                warnings.push(anyhow::anyhow!(
+                    "versioned API {:?}: endpoint {} is only in retired \
+                     versions (the last is {}, before the oldest supported \
+                     version {}), so it can be removed",
+                    ident,
+                    endpoint,
+                    last,
+                    oldest,
+                ));
+                continue;
+            }
+            let first = present.first().expect("endpoint is in some version");
+            warnings.push(anyhow::anyhow!(
+                "versioned API {:?}: endpoint {} isn't in any supported \
+                 version, so it never appears in a document (it would first \
+                 appear in version {}); check its `versions` range",
+                ident,
+                endpoint,
+                if *first == &far_future {
+                    format!("{}.0.0 or later", latest.major + 2)
+                } else {
+                    first.to_string()
+                },
+            ));
+            continue;
+        }
+
+        // Find where the endpoint is added or removed. Other than additions
+        // in retired versions, bounds at or before the oldest supported
+        // version don't affect any supported version.
+        let mut versions = endpoints_by_version.iter();
+        let (mut previous, first) = versions.next().expect("some version");
+        let mut was_present = first.contains(endpoint);
+        for (version, endpoints) in versions {
+            let is_present = endpoints.contains(endpoint);
+            if is_present && !was_present && version < oldest {
+                warnings.push(anyhow::anyhow!(
+                    "versioned API {:?}: endpoint {} is added in a retired \
+                     version (after {} and no later than {}); its `versions` \
+                     range can start at the oldest supported version {} \
+                     instead",
+                    ident,
+                    endpoint,
+                    previous,
+                    version,
+                    oldest,
+                ));
+            } else if is_present != was_present
+                && version > oldest
+                && !supported.contains_key(version)
+            {
+                let action = if is_present { "added" } else { "removed" };
+                let range = if version == &far_future {
+                    format!("after {}", previous)
+                } else {
+                    format!("after {} and no later than {}", previous, version)
+                };
+                warnings.push(anyhow::anyhow!(
+                    "versioned API {:?}: endpoint {} is {} in a version {}, \
+                     but no such version is supported; check that its \
+                     `versions` range refers to a supported version",
+                    ident,
+                    endpoint,
+                    action,
+                    range,
+                ));
+            }
+            was_present = is_present;
+            previous = version;
+        }
+    }
+    warnings
+}
+
+/// Returns the endpoints in `document`, as `METHOD /path`.
+fn endpoints(document: &OpenAPI) -> BTreeSet<String> {
+    document
+        .paths
+        .iter()
+        .filter_map(|(path, item)| Some((path, item.as_item()?)))
+        .flat_map(|(path, item)| {
+            item.iter().map(move |(method, _)| {
+                format!("{} {}", method.to_uppercase(), path)
+            })
+        })
+        .collect()
+}
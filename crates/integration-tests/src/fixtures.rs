@@ -5,17 +5,29 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use dropshot::{
-    HttpError, HttpResponseOk, Path, Query, RequestContext, TypedBody,
-    WebsocketConnection,
+    ApiDescription, ApiDescriptionBuildErrors, HttpError, HttpResponseOk, Path,
+    Query, RequestContext, StubContext, TypedBody, WebsocketConnection,
+};
+use dropshot_api_manager::{
+    ApiProfile, DeprecationSummary, DocsLinkLint, DocumentFreeze,
+    DocumentSigning, DocumentSource, ExampleLint, ExternalApiConfig,
+    FlattenedDocument, JsonFormat, LatestPointer, ManagedApi, ManagedApiConfig,
+    ManagedApis, NamingLint, TextLint, TypeReplacements,
 };
-use dropshot_api_manager::{ManagedApi, ManagedApiConfig, ManagedApis};
 use dropshot_api_manager_types::{
-    ManagedApiMetadata, ValidationContext, Versions,
+    ApiDescriptionProvider, ApiDocumentInfo, CustomFix, ManagedApiMetadata,
+    ValidationContext, Versions,
 };
 use schemars::JsonSchema;
 use semver::Version;
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
+use std::{
+    ops::RangeBounds,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
 
 /// Storage strategy for blessed API versions.
 #[derive(Clone, Copy, Debug, Default)]
@@ -1276,6 +1288,220 @@ pub mod versioned_health_with_v4_trivial_v3 {
     };
 }
 
+/// Versioned health API with a single version, and an unstable `/metrics`
+/// endpoint (tagged `unstable`).
+pub mod versioned_health_unstable {
+    use super::*;
+    use dropshot_api_manager_types::api_versions;
+
+    api_versions!([(1, INITIAL)]);
+
+    #[dropshot::api_description { module = "api_mod" }]
+    pub trait VersionedHealthApi {
+        type Context;
+
+        /// Check if the service is healthy (all versions).
+        #[endpoint {
+            method = GET,
+            path = "/health",
+            operation_id = "health_check",
+            versions = "1.0.0"..
+        }]
+        async fn health_check(
+            rqctx: RequestContext<Self::Context>,
+        ) -> Result<HttpResponseOk<HealthStatusV1>, HttpError>;
+
+        /// Get service metrics (unstable).
+        #[endpoint {
+            method = GET,
+            path = "/metrics",
+            operation_id = "get_metrics",
+            tags = ["unstable"],
+            versions = "1.0.0"..
+        }]
+        async fn get_metrics(
+            rqctx: RequestContext<Self::Context>,
+        ) -> Result<HttpResponseOk<ServiceMetrics>, HttpError>;
+    }
+
+    // Reuse response types from the main versioned_health module.
+    pub use super::versioned_health::{HealthStatusV1, ServiceMetrics};
+}
+
+/// Identical to `versioned_health_unstable`, except that the unstable
+/// `/metrics` endpoint has an incompatible response type.
+pub mod versioned_health_unstable_changed {
+    use super::*;
+    use dropshot_api_manager_types::api_versions;
+
+    api_versions!([(1, INITIAL)]);
+
+    #[dropshot::api_description { module = "api_mod" }]
+    pub trait VersionedHealthApi {
+        type Context;
+
+        /// Check if the service is healthy (all versions).
+        #[endpoint {
+            method = GET,
+            path = "/health",
+            operation_id = "health_check",
+            versions = "1.0.0"..
+        }]
+        async fn health_check(
+            rqctx: RequestContext<Self::Context>,
+        ) -> Result<HttpResponseOk<HealthStatusV1>, HttpError>;
+
+        /// Get service metrics (unstable).
+        #[endpoint {
+            method = GET,
+            path = "/metrics",
+            operation_id = "get_metrics",
+            tags = ["unstable"],
+            versions = "1.0.0"..
+        }]
+        async fn get_metrics(
+            rqctx: RequestContext<Self::Context>,
+        ) -> Result<HttpResponseOk<CounterValue>, HttpError>;
+    }
+
+    // Reuse response types from the main versioned_health module.
+    pub use super::{CounterValue, versioned_health::HealthStatusV1};
+}
+
+/// Versioned health API whose health check operation is renamed (with the same
+/// method and path) in version 2.
+pub mod versioned_health_renamed_operation {
+    use super::*;
+    use dropshot_api_manager_types::api_versions;
+
+    api_versions!([(2, RENAME_HEALTH_CHECK), (1, INITIAL)]);
+
+    #[dropshot::api_description { module = "api_mod" }]
+    pub trait VersionedHealthApi {
+        type Context;
+
+        /// Check if the service is healthy (v1).
+        #[endpoint {
+            method = GET,
+            path = "/health",
+            operation_id = "health_check",
+            versions = "1.0.0"..VERSION_RENAME_HEALTH_CHECK
+        }]
+        async fn health_check_v1(
+            rqctx: RequestContext<Self::Context>,
+        ) -> Result<HttpResponseOk<HealthStatusV1>, HttpError>;
+
+        /// Check if the service is healthy (v2+).
+        #[endpoint {
+            method = GET,
+            path = "/health",
+            operation_id = "check_health",
+            versions = "2.0.0"..
+        }]
+        async fn check_health(
+            rqctx: RequestContext<Self::Context>,
+        ) -> Result<HttpResponseOk<HealthStatusV1>, HttpError>;
+    }
+
+    // Reuse response types from the main versioned_health module.
+    pub use super::versioned_health::HealthStatusV1;
+}
+
+/// A versioned health API with endpoints whose version ranges don't line up
+/// with the supported versions.
+pub mod versioned_health_bad_ranges {
+    use super::*;
+    use dropshot_api_manager_types::api_versions;
+
+    api_versions!([(2, ADD_DETAILS), (1, INITIAL)]);
+
+    #[dropshot::api_description { module = "api_mod" }]
+    pub trait VersionedHealthApi {
+        type Context;
+
+        /// Check if the service is healthy.
+        #[endpoint {
+            method = GET,
+            path = "/health",
+            versions = "1.0.0"..
+        }]
+        async fn health_check(
+            rqctx: RequestContext<Self::Context>,
+        ) -> Result<HttpResponseOk<HealthStatusV1>, HttpError>;
+
+        /// An endpoint added in a version that doesn't exist yet, so it isn't
+        /// in any supported version.
+        #[endpoint {
+            method = GET,
+            path = "/details",
+            versions = "4.0.0"..
+        }]
+        async fn get_details(
+            rqctx: RequestContext<Self::Context>,
+        ) -> Result<HttpResponseOk<HealthStatusV1>, HttpError>;
+
+        /// An endpoint removed in a version that isn't supported.
+        #[endpoint {
+            method = GET,
+            path = "/legacy",
+            versions = "1.0.0".."3.0.0"
+        }]
+        async fn get_legacy(
+            rqctx: RequestContext<Self::Context>,
+        ) -> Result<HttpResponseOk<HealthStatusV1>, HttpError>;
+    }
+
+    // Reuse response types from the main versioned_health module.
+    pub use super::versioned_health::HealthStatusV1;
+}
+
+/// A versioned health API whose oldest versions have been retired, with
+/// endpoints whose version ranges still refer to them.
+pub mod versioned_health_retired_ranges {
+    use super::*;
+    use dropshot_api_manager_types::api_versions;
+
+    api_versions!([(3, WITH_METRICS), (2, WITH_DETAILED_STATUS)]);
+
+    #[dropshot::api_description { module = "api_mod" }]
+    pub trait VersionedHealthApi {
+        type Context;
+
+        /// Check if the service is healthy.
+        #[endpoint {
+            method = GET,
+            path = "/health",
+            versions = "1.0.0"..
+        }]
+        async fn health_check(
+            rqctx: RequestContext<Self::Context>,
+        ) -> Result<HttpResponseOk<HealthStatusV1>, HttpError>;
+
+        /// An endpoint that was removed before the oldest supported version.
+        #[endpoint {
+            method = GET,
+            path = "/legacy",
+            versions = "1.0.0".."2.0.0"
+        }]
+        async fn get_legacy(
+            rqctx: RequestContext<Self::Context>,
+        ) -> Result<HttpResponseOk<HealthStatusV1>, HttpError>;
+
+        /// An endpoint whose range starts at the oldest supported version.
+        #[endpoint {
+            method = GET,
+            path = "/details",
+            versions = "2.0.0"..
+        }]
+        async fn get_details(
+            rqctx: RequestContext<Self::Context>,
+        ) -> Result<HttpResponseOk<HealthStatusV1>, HttpError>;
+    }
+
+    // Reuse response types from the main versioned_health module.
+    pub use super::versioned_health::HealthStatusV1;
+}
+
 pub fn versioned_health_api() -> ManagedApiConfig {
     ManagedApiConfig {
         ident: "versioned-health",
@@ -1355,6 +1581,18 @@ pub fn lockstep_health_apis() -> Result<ManagedApis> {
         .context("failed to create ManagedApis")
 }
 
+/// Create a health API that links to external documentation at
+/// `external_docs_url` (if any), and requires links according to `lint`.
+pub fn lockstep_health_docs_link_apis(
+    external_docs_url: Option<&'static str>,
+    lint: DocsLinkLint,
+) -> Result<ManagedApis> {
+    let mut config = lockstep_health_api();
+    config.metadata.external_docs_url = external_docs_url;
+    ManagedApis::new(vec![ManagedApi::from(config).with_docs_link_lint(lint)])
+        .context("failed to create ManagedApis")
+}
+
 /// Create a counter test API configuration.
 pub fn lockstep_counter_apis() -> Result<ManagedApis> {
     ManagedApis::new(vec![lockstep_counter_api()])
@@ -1367,6 +1605,182 @@ pub fn lockstep_user_apis() -> Result<ManagedApis> {
         .context("failed to create ManagedApis")
 }
 
+/// A hand-written OpenAPI document, used for APIs not generated by Dropshot.
+pub const STATIC_DOCUMENT: &str = r#"{
+  "openapi": "3.0.3",
+  "info": {
+    "title": "Static API",
+    "version": "1.0.0"
+  },
+  "paths": {}
+}
+"#;
+
+fn static_document() -> Vec<u8> {
+    STATIC_DOCUMENT.as_bytes().to_vec()
+}
+
+/// A lockstep API whose document is hand-written rather than generated by
+/// Dropshot.
+pub fn lockstep_static_api() -> ExternalApiConfig {
+    ExternalApiConfig {
+        ident: "static",
+        versions: Versions::Lockstep { version: Version::new(1, 0, 0) },
+        title: "Static API",
+        metadata: ManagedApiMetadata::default(),
+        source: DocumentSource::Static(static_document),
+    }
+}
+
+/// Create a static (non-Dropshot) test API configuration.
+pub fn lockstep_static_apis() -> Result<ManagedApis> {
+    ManagedApis::new(vec![ManagedApi::from(lockstep_static_api())])
+        .context("failed to create ManagedApis")
+}
+
+/// The number of times [`nondeterministic_document`] has been called.
+static NONDETERMINISTIC_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns a document that's different every time it's generated.
+fn nondeterministic_document() -> Vec<u8> {
+    let calls = NONDETERMINISTIC_CALLS.fetch_add(1, Ordering::SeqCst);
+    format!(
+        r#"{{
+  "openapi": "3.0.3",
+  "info": {{
+    "title": "Nondeterministic API",
+    "description": "Generated {} times",
+    "version": "1.0.0"
+  }},
+  "paths": {{}}
+}}
+"#,
+        calls,
+    )
+    .into_bytes()
+}
+
+/// A lockstep API whose document is different every time it's generated.
+pub fn lockstep_nondeterministic_apis() -> Result<ManagedApis> {
+    ManagedApis::new(vec![ManagedApi::from(ExternalApiConfig {
+        ident: "nondeterministic",
+        versions: Versions::Lockstep { version: Version::new(1, 0, 0) },
+        title: "Nondeterministic API",
+        metadata: ManagedApiMetadata::default(),
+        source: DocumentSource::Static(nondeterministic_document),
+    })])
+    .context("failed to create ManagedApis")
+}
+
+fn dangling_ref_document() -> Vec<u8> {
+    br##"{
+  "openapi": "3.0.3",
+  "info": {
+    "title": "Dangling API",
+    "version": "1.0.0"
+  },
+  "paths": {
+    "/thing": {
+      "get": {
+        "operationId": "thing_get",
+        "responses": {
+          "200": {
+            "description": "successful operation",
+            "content": {
+              "application/json": {
+                "schema": {
+                  "$ref": "#/components/schemas/Thing"
+                }
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+"##
+    .to_vec()
+}
+
+/// A lockstep API whose hand-written document refers to a schema that doesn't
+/// exist.
+pub fn lockstep_dangling_ref_apis() -> Result<ManagedApis> {
+    ManagedApis::new(vec![ManagedApi::from(ExternalApiConfig {
+        ident: "dangling",
+        versions: Versions::Lockstep { version: Version::new(1, 0, 0) },
+        title: "Dangling API",
+        metadata: ManagedApiMetadata::default(),
+        source: DocumentSource::Static(dangling_ref_document),
+    })])
+    .context("failed to create ManagedApis")
+}
+
+fn panicking_api_description()
+-> Result<ApiDescription<StubContext>, ApiDescriptionBuildErrors> {
+    panic!("this API description always panics");
+}
+
+/// A lockstep API whose `api_description` function panics.
+pub fn lockstep_panicking_api() -> ManagedApiConfig {
+    ManagedApiConfig {
+        ident: "panicking",
+        versions: Versions::Lockstep { version: Version::new(1, 0, 0) },
+        title: "Panicking API",
+        metadata: ManagedApiMetadata::default(),
+        api_description: panicking_api_description,
+    }
+}
+
+/// Create a configuration with the health API and an API whose generation
+/// panics.
+pub fn lockstep_health_and_panicking_apis() -> Result<ManagedApis> {
+    ManagedApis::new(vec![lockstep_health_api(), lockstep_panicking_api()])
+        .context("failed to create ManagedApis")
+}
+
+/// Produces the health API's document via [`ApiDescriptionProvider`], the way
+/// an API crate using a different version of Dropshot would.
+struct HealthApiProvider;
+
+impl ApiDescriptionProvider for HealthApiProvider {
+    fn openapi_document(
+        &self,
+        info: &ApiDocumentInfo<'_>,
+    ) -> anyhow::Result<Vec<u8>> {
+        let description = health_api_mod::stub_api_description()
+            .map_err(|error| anyhow::anyhow!("{}", error))?;
+        let mut openapi = description.openapi(info.title, info.version.clone());
+        if let Some(description) = info.metadata.description {
+            openapi.description(description);
+        }
+        let mut contents = Vec::new();
+        openapi.write(&mut contents)?;
+        Ok(contents)
+    }
+}
+
+/// The same API as [`lockstep_health_api`], but produced by an
+/// [`ApiDescriptionProvider`].
+pub fn lockstep_health_provider_api() -> ExternalApiConfig {
+    let ManagedApiConfig { ident, versions, title, metadata, .. } =
+        lockstep_health_api();
+    ExternalApiConfig {
+        ident,
+        versions,
+        title,
+        metadata,
+        source: DocumentSource::Provider(&HealthApiProvider),
+    }
+}
+
+/// Create a health test API configuration backed by an
+/// [`ApiDescriptionProvider`].
+pub fn lockstep_health_provider_apis() -> Result<ManagedApis> {
+    ManagedApis::new(vec![ManagedApi::from(lockstep_health_provider_api())])
+        .context("failed to create ManagedApis")
+}
+
 /// Helper to create multiple test APIs.
 pub fn lockstep_multi_apis() -> Result<ManagedApis> {
     let configs = vec![
@@ -1377,6 +1791,30 @@ pub fn lockstep_multi_apis() -> Result<ManagedApis> {
     ManagedApis::new(configs).context("failed to create ManagedApis")
 }
 
+/// Create multiple test APIs, each defined by the files in its own directory
+/// and by `Cargo.lock`. The counter API also maintains a flattened document
+/// at `clients/counter.json`.
+pub fn lockstep_multi_with_source_globs_apis() -> Result<ManagedApis> {
+    let apis =
+        [lockstep_health_api(), lockstep_counter_api(), lockstep_user_api()]
+            .into_iter()
+            .map(|config| {
+                let dir = format!("apis/{}/**", config.ident);
+                let is_counter = config.ident == "counter";
+                let api = ManagedApi::from(config)
+                    .with_source_globs([dir.as_str(), "Cargo.lock"])?;
+                Ok(if is_counter {
+                    api.with_flattened_document(FlattenedDocument::new(
+                        "clients/counter.json",
+                    ))
+                } else {
+                    api
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+    ManagedApis::new(apis).context("failed to create ManagedApis")
+}
+
 /// Create a versioned health ManagedApi with the specified storage.
 pub fn versioned_health_api_with_storage(storage: Storage) -> ManagedApi {
     let config = versioned_health_api();
@@ -1399,6 +1837,139 @@ pub fn versioned_health_apis() -> Result<ManagedApis> {
     versioned_health_apis_with_storage(Storage::Concrete)
 }
 
+/// Create a versioned health API that also keeps a copy of its documents under
+/// the alias `versioned-health-old`.
+pub fn versioned_health_aliased_apis() -> Result<ManagedApis> {
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api())
+            .with_alias("versioned-health-old"),
+    ])
+    .context("failed to create aliased versioned health ManagedApis")
+}
+
+/// Create a versioned health API that permits hand-written Markdown files in
+/// its documents directory.
+pub fn versioned_health_with_unmanaged_files_apis() -> Result<ManagedApis> {
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api())
+            .with_unmanaged_files(["README.md", "CHANGELOG.md"])?,
+    ])
+    .context("failed to create versioned health ManagedApis")
+}
+
+/// Create a versioned health API whose latest document is frozen by `freeze`.
+pub fn versioned_health_frozen_apis(
+    freeze: DocumentFreeze,
+) -> Result<ManagedApis> {
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api()).with_freeze(freeze),
+    ])
+    .context("failed to create frozen versioned health ManagedApis")
+}
+
+/// Create a versioned health API that maintains a summary of its deprecated
+/// operations at `path`.
+pub fn versioned_health_deprecation_summary_apis(
+    path: &str,
+) -> Result<ManagedApis> {
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api())
+            .with_deprecation_summary(DeprecationSummary::new(path)),
+    ])
+    .context("failed to create versioned health ManagedApis")
+}
+
+/// Create a versioned health API that stores its schemas in a shared schema
+/// store.
+pub fn versioned_health_shared_schemas_apis() -> Result<ManagedApis> {
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api()).with_shared_schemas(),
+    ])
+    .context("failed to create versioned health ManagedApis")
+}
+
+/// Create a versioned health API whose documents are formatted with `format`.
+pub fn versioned_health_json_format_apis(
+    format: JsonFormat,
+) -> Result<ManagedApis> {
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api()).with_json_format(format),
+    ])
+    .context("failed to create versioned health ManagedApis")
+}
+
+/// Create a versioned health API whose latest version is pointed to by
+/// `latest_pointer`.
+pub fn versioned_health_with_latest_pointer_apis(
+    latest_pointer: LatestPointer,
+) -> Result<ManagedApis> {
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api())
+            .with_latest_pointer(latest_pointer),
+    ])
+    .context("failed to create versioned health ManagedApis")
+}
+
+/// Create a versioned health API that maintains a document combining all of
+/// its supported versions.
+pub fn versioned_health_combined_apis() -> Result<ManagedApis> {
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api()).with_combined_document(),
+    ])
+    .context("failed to create versioned health ManagedApis")
+}
+
+/// Create a versioned health API whose summaries and descriptions are linted
+/// with `text_lint`.
+pub fn versioned_health_with_text_lint_apis(
+    text_lint: TextLint,
+) -> Result<ManagedApis> {
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api()).with_text_lint(text_lint),
+    ])
+    .context("failed to create versioned health ManagedApis")
+}
+
+/// Create a versioned health API whose request and response body examples are
+/// linted with `example_lint`.
+pub fn versioned_health_with_example_lint_apis(
+    example_lint: ExampleLint,
+) -> Result<ManagedApis> {
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api())
+            .with_example_lint(example_lint),
+    ])
+    .context("failed to create versioned health ManagedApis")
+}
+
+/// Create a versioned health API whose names are linted with `naming_lint`.
+pub fn versioned_health_with_naming_lint_apis(
+    naming_lint: NamingLint,
+) -> Result<ManagedApis> {
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api()).with_naming_lint(naming_lint),
+    ])
+    .context("failed to create versioned health ManagedApis")
+}
+
+/// Create a versioned health API that warns if its latest document grows by
+/// more than `percent` percent over the latest blessed document.
+pub fn versioned_health_size_growth_apis(percent: u32) -> Result<ManagedApis> {
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api())
+            .with_size_growth_threshold(percent),
+    ])
+    .context("failed to create versioned health ManagedApis")
+}
+
+/// Create a versioned health API whose documents must be signed according to
+/// `signing`.
+pub fn versioned_health_signed_apis(
+    signing: DocumentSigning,
+) -> Result<ManagedApis> {
+    Ok(versioned_health_apis()?.with_document_signing(signing))
+}
+
 /// Create a versioned user API for testing.
 pub fn versioned_user_apis() -> Result<ManagedApis> {
     ManagedApis::new(vec![versioned_user_api()])
@@ -1629,12 +2200,9 @@ pub fn versioned_health_no_v1_apis() -> Result<ManagedApis> {
         .context("failed to create no-v1 versioned health ManagedApis")
 }
 
-/// Create versioned health API with only v1.
-///
-/// Used to test Git stub conversion when multiple versions share the same
-/// first commit as the new latest.
-pub fn versioned_health_v1_only_apis() -> Result<ManagedApis> {
-    let config = ManagedApiConfig {
+/// Versioned health API with only v1.
+pub fn versioned_health_v1_only_api() -> ManagedApiConfig {
+    ManagedApiConfig {
         ident: "versioned-health",
         versions: Versions::Versioned {
             supported_versions: versioned_health_v1_only::supported_versions(),
@@ -1648,12 +2216,120 @@ pub fn versioned_health_v1_only_apis() -> Result<ManagedApis> {
         },
         api_description:
             versioned_health_v1_only::api_mod::stub_api_description,
-    };
+    }
+}
 
-    ManagedApis::new(vec![config])
+/// Create versioned health API with only v1.
+///
+/// Used to test Git stub conversion when multiple versions share the same
+/// first commit as the new latest.
+pub fn versioned_health_v1_only_apis() -> Result<ManagedApis> {
+    ManagedApis::new(vec![versioned_health_v1_only_api()])
         .context("failed to create v1-only versioned health ManagedApis")
 }
 
+/// Create a versioned health API with only v1, like
+/// `versioned_health_v1_only_apis`, but with a `/metrics` endpoint added to v1.
+///
+/// The added endpoint is compatible for clients of the v1-only document, but
+/// clients built against this one can't talk to a v1-only server. If
+/// `check_reverse` is true, the API checks compatibility in that direction.
+pub fn versioned_health_v1_with_metrics_apis(
+    check_reverse: bool,
+) -> Result<ManagedApis> {
+    let config = ManagedApiConfig {
+        api_description:
+            versioned_health_unstable::api_mod::stub_api_description,
+        ..versioned_health_v1_only_api()
+    };
+    let mut api = ManagedApi::from(config);
+    if check_reverse {
+        api = api.with_reverse_compatibility_check();
+    }
+    ManagedApis::new(vec![api]).context(
+        "failed to create v1-with-metrics versioned health ManagedApis",
+    )
+}
+
+/// Create a versioned health API like `versioned_health_v1_with_metrics_apis`,
+/// but with the v1-only description registered as a contract for
+/// `contract_versions`.
+pub fn versioned_health_v1_contract_apis(
+    contract_versions: impl RangeBounds<Version>,
+) -> Result<ManagedApis> {
+    let config = ManagedApiConfig {
+        api_description:
+            versioned_health_unstable::api_mod::stub_api_description,
+        ..versioned_health_v1_only_api()
+    };
+    let api = ManagedApi::from(config).with_contract(
+        contract_versions,
+        versioned_health_v1_only::api_mod::stub_api_description,
+    );
+    ManagedApis::new(vec![api])
+        .context("failed to create contract versioned health ManagedApis")
+}
+
+/// Create a versioned health API with an unstable endpoint.
+///
+/// If `changed` is true, the unstable endpoint has an incompatible response
+/// type. If `unstable_tag` is true, the API treats the `unstable` tag as
+/// marking unstable operations.
+pub fn versioned_health_unstable_apis(
+    changed: bool,
+    unstable_tag: bool,
+) -> Result<ManagedApis> {
+    let config = ManagedApiConfig {
+        ident: "versioned-health",
+        versions: Versions::Versioned {
+            supported_versions: versioned_health_unstable::supported_versions(),
+        },
+        title: "Versioned Health API",
+        metadata: ManagedApiMetadata {
+            description: Some("A versioned health API with unstable endpoints"),
+            ..Default::default()
+        },
+        api_description: if changed {
+            versioned_health_unstable_changed::api_mod::stub_api_description
+        } else {
+            versioned_health_unstable::api_mod::stub_api_description
+        },
+    };
+    let mut api = ManagedApi::from(config);
+    if unstable_tag {
+        api = api.with_unstable_tag("unstable");
+    }
+    ManagedApis::new(vec![api])
+        .context("failed to create unstable versioned health ManagedApis")
+}
+
+/// Create a versioned health API where operations under `/metrics` are
+/// unstable by path prefix, optionally with an incompatible `/metrics`
+/// endpoint.
+pub fn versioned_health_unstable_path_apis(
+    changed: bool,
+) -> Result<ManagedApis> {
+    let config = ManagedApiConfig {
+        ident: "versioned-health",
+        versions: Versions::Versioned {
+            supported_versions: versioned_health_unstable::supported_versions(),
+        },
+        title: "Versioned Health API",
+        metadata: ManagedApiMetadata {
+            description: Some("A versioned health API with unstable endpoints"),
+            ..Default::default()
+        },
+        api_description: if changed {
+            versioned_health_unstable_changed::api_mod::stub_api_description
+        } else {
+            versioned_health_unstable::api_mod::stub_api_description
+        },
+    };
+    let api = ManagedApi::from(config).with_unstable_path_prefix("/metrics");
+    ManagedApis::new(vec![api])
+        .context("failed to create unstable path versioned health ManagedApis")
+}
+
 /// Create a versioned health API with incompatible changes that break backward
 /// compatibility.
 pub fn versioned_health_incompat_apis() -> Result<ManagedApis> {
@@ -1742,6 +2418,19 @@ fn validate_with_extra_file(
     }
 }
 
+/// Path (relative to the repository root) of a file that
+/// [`validate_with_custom_fix`] reports should be removed.
+pub const OBSOLETE_FILE_PATH: &str = "obsolete-index.html";
+
+fn validate_with_custom_fix(
+    _spec: &openapiv3::OpenAPI,
+    mut cx: ValidationContext<'_>,
+) {
+    if cx.is_latest() {
+        cx.report_fixable_problem(CustomFix::remove_file(OBSOLETE_FILE_PATH));
+    }
+}
+
 pub fn versioned_health_with_validation_api() -> ManagedApi {
     ManagedApi::from(ManagedApiConfig {
         ident: "versioned-health",
@@ -1778,12 +2467,60 @@ pub fn versioned_health_with_extra_file_api() -> ManagedApi {
     .with_extra_validation(validate_with_extra_file)
 }
 
+pub fn versioned_health_with_custom_fix_api() -> ManagedApi {
+    ManagedApi::from(versioned_health_api())
+        .with_extra_validation(validate_with_custom_fix)
+}
+
 pub fn versioned_health_with_validation_apis() -> Result<ManagedApis> {
     ManagedApis::new(vec![versioned_health_with_validation_api()]).context(
         "failed to create versioned health with validation ManagedApis",
     )
 }
 
+pub fn versioned_health_with_custom_fix_apis() -> Result<ManagedApis> {
+    ManagedApis::new(vec![versioned_health_with_custom_fix_api()]).context(
+        "failed to create versioned health with custom fix ManagedApis",
+    )
+}
+
+pub fn versioned_health_with_type_replacements_apis(
+    replacements: TypeReplacements,
+) -> Result<ManagedApis> {
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api())
+            .with_type_replacements(replacements),
+    ])
+    .context(
+        "failed to create versioned health with type replacements ManagedApis",
+    )
+}
+
+pub fn versioned_health_with_flattened_document_apis(
+    flattened_document: FlattenedDocument,
+) -> Result<ManagedApis> {
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api())
+            .with_flattened_document(flattened_document),
+    ])
+    .context(
+        "failed to create versioned health with flattened document ManagedApis",
+    )
+}
+
+/// Create a versioned health API with a "minimal" profile that only includes
+/// the basic health check.
+pub fn versioned_health_with_profile_apis() -> Result<ManagedApis> {
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api()).with_profile(
+            ApiProfile::with_filter("minimal", |op| {
+                op.operation_id.as_deref() == Some("health_check")
+            }),
+        ),
+    ])
+    .context("failed to create versioned health with profile ManagedApis")
+}
+
 pub fn versioned_health_with_extra_file_apis() -> Result<ManagedApis> {
     ManagedApis::new(vec![versioned_health_with_extra_file_api()]).context(
         "failed to create versioned health with conditional files ManagedApis",
@@ -1858,6 +2595,78 @@ pub fn versioned_health_with_v4_apis() -> Result<ManagedApis> {
         .context("failed to create versioned health with v4 ManagedApis")
 }
 
+/// Create a versioned health API whose health check operation is renamed in
+/// version 2, optionally marking the rename as intended.
+pub fn versioned_health_renamed_operation_apis(
+    allow_rename: bool,
+) -> Result<ManagedApis> {
+    let mut api = ManagedApi::from(ManagedApiConfig {
+        ident: "versioned-health",
+        versions: Versions::Versioned {
+            supported_versions:
+                versioned_health_renamed_operation::supported_versions(),
+        },
+        title: "Versioned Health API",
+        metadata: ManagedApiMetadata {
+            description: Some(
+                "A versioned health API for testing version evolution",
+            ),
+            ..Default::default()
+        },
+        api_description:
+            versioned_health_renamed_operation::api_mod::stub_api_description,
+    });
+    if allow_rename {
+        api = api.with_renamed_operation_id("health_check", "check_health");
+    }
+    ManagedApis::new(vec![api])
+        .context("failed to create renamed operation ManagedApis")
+}
+
+/// Create a versioned health API with endpoints whose version ranges don't
+/// line up with its supported versions.
+pub fn versioned_health_bad_ranges_apis() -> Result<ManagedApis> {
+    ManagedApis::new(vec![ManagedApi::from(ManagedApiConfig {
+        ident: "versioned-health",
+        versions: Versions::Versioned {
+            supported_versions: versioned_health_bad_ranges::supported_versions(
+            ),
+        },
+        title: "Versioned Health API",
+        metadata: ManagedApiMetadata {
+            description: Some(
+                "A versioned health API for testing version evolution",
+            ),
+            ..Default::default()
+        },
+        api_description:
+            versioned_health_bad_ranges::api_mod::stub_api_description,
+    })])
+    .context("failed to create bad ranges ManagedApis")
+}
+
+/// Create a versioned health API with endpoint version ranges that refer to
+/// retired versions.
+pub fn versioned_health_retired_ranges_apis() -> Result<ManagedApis> {
+    ManagedApis::new(vec![ManagedApi::from(ManagedApiConfig {
+        ident: "versioned-health",
+        versions: Versions::Versioned {
+            supported_versions:
+                versioned_health_retired_ranges::supported_versions(),
+        },
+        title: "Versioned Health API",
+        metadata: ManagedApiMetadata {
+            description: Some(
+                "A versioned health API for testing version evolution",
+            ),
+            ..Default::default()
+        },
+        api_description:
+            versioned_health_retired_ranges::api_mod::stub_api_description,
+    })])
+    .context("failed to create retired ranges ManagedApis")
+}
+
 /// Create versioned health APIs with v4 and a trivially modified v3.
 pub fn versioned_health_with_v4_trivial_v3_apis(
     storage: Storage,
@@ -2,11 +2,10 @@
 
 //! Integration tests for dropshot-api-manager.
 
-mod environment;
 mod fixtures;
 
 use camino::Utf8PathBuf;
-pub use environment::{
+pub use dropshot_api_manager::test_util::{
     JjMergeResult, JjRebaseResult, MergeResult, RebaseResult, TestEnvironment,
     VcsMode, check_jj_available, rel_path_forward_slashes,
 };
@@ -2,23 +2,14 @@
 
 //! Integration tests for the `--generated-from-dir` flag.
 
+use crate::atomic_write;
 use anyhow::Result;
-use atomicwrites::{AtomicFile, OverwriteBehavior};
 use dropshot_api_manager::test_util::{
     CheckResult, ProblemKind, ProblemSummary, check_apis_up_to_date,
     check_apis_with_generated_from_dir,
     check_apis_with_generated_from_dir_and_summaries,
 };
 use integration_tests::*;
-use std::io::Write;
-
-/// Write content to a file atomically, matching this project's convention
-/// of using `atomicwrites` instead of `std::fs::write`.
-fn atomic_write(path: &camino::Utf8Path, content: &str) -> Result<()> {
-    AtomicFile::new(path, OverwriteBehavior::AllowOverwrite)
-        .write(|f| f.write_all(content.as_bytes()))?;
-    Ok(())
-}
 
 /// When `--generated-from-dir` points to an empty directory, the tool should
 /// report a clear error rather than panicking.
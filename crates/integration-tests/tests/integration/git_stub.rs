@@ -9,11 +9,12 @@
 
 use anyhow::{Context, Result};
 use camino::Utf8PathBuf;
+use camino_tempfile::Utf8TempDir;
 use dropshot_api_manager::{
-    ManagedApis,
+    Environment, GitStubPolicy, ManagedApis,
     test_util::{
         CheckResult, ProblemKind, ProblemSummary, check_apis_up_to_date,
-        check_apis_with_summaries,
+        check_apis_with_summaries, materialize_documents, pin_git_stub_commits,
     },
 };
 use integration_tests::{
@@ -641,6 +642,61 @@ fn test_duplicates() -> Result<()> {
     Ok(())
 }
 
+/// Test that a Git stub whose contents differ from the blessed document with
+/// the same file name is reported as a hash collision, rather than having one
+/// of the two documents silently treated as redundant.
+#[test]
+fn test_git_stub_hash_collision() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_git_stub_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    let extended = versioned_health_with_v4_git_stub_apis()?;
+    env.generate_documents(&extended)?;
+    env.commit_documents()?;
+
+    // Commit a different document (the same one, with an extra trailing
+    // newline) at v1's JSON path, then remove it again.
+    let git_stub_path = env
+        .find_versioned_git_stub_path("versioned-health", "1.0.0")?
+        .expect("v1 Git stub should exist");
+    let json_path = git_stub_path.with_extension("");
+    let json_content =
+        env.read_git_stub_content("versioned-health", "1.0.0")?;
+    env.create_file(&json_path, &format!("{}\n", json_content))?;
+    env.git_add(&[&json_path])?;
+    env.git_commit("add a colliding document")?;
+    let colliding_commit = env.get_current_commit_hash()?;
+    std::fs::remove_file(env.workspace_root().join(&json_path))?;
+    env.git_add(&[&json_path])?;
+    env.git_commit("remove the colliding document")?;
+
+    // Point v1's Git stub at the colliding document.
+    let path_part = rel_path_forward_slashes(json_path.as_str());
+    env.create_file(
+        &git_stub_path,
+        &format!("{}:{}\n", colliding_commit, path_part),
+    )?;
+
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &extended)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert!(
+        summaries.contains(&ProblemSummary::new(
+            "versioned-health",
+            "1.0.0",
+            ProblemKind::FileNameHashCollision,
+        )),
+        "expected a hash collision, found: {summaries:?}"
+    );
+
+    // generate refuses to fix anything while the collision is present.
+    env.generate_documents(&extended).unwrap_err();
+
+    Ok(())
+}
+
 /// Test that Git stub points to the most recent addition when a version is
 /// removed and re-added.
 ///
@@ -1015,6 +1071,151 @@ fn test_shallow_clone_without_git_stubs() -> Result<()> {
     Ok(())
 }
 
+/// Test that the Git stub policy restricts which versions are converted.
+#[test]
+fn test_git_stub_policy() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+
+    let v1_v2_v3 = versioned_health_git_stub_apis()?;
+    env.generate_documents(&v1_v2_v3)?;
+    env.commit_documents()?;
+
+    // Keep the three most recent versions (v2, v3 and v4) as JSON.
+    let v4 = ManagedApis::new(vec![
+        versioned_health_with_v4_git_stub_api()
+            .with_git_stub_policy(GitStubPolicy::new().keep_recent_versions(3)),
+    ])?;
+    env.generate_documents(&v4)?;
+
+    assert!(env.versioned_git_stub_exists("versioned-health", "1.0.0")?);
+    for version in ["2.0.0", "3.0.0", "4.0.0"] {
+        assert!(
+            !env.versioned_git_stub_exists("versioned-health", version)?,
+            "v{version} should not be a Git stub"
+        );
+        assert!(
+            env.versioned_local_document_exists("versioned-health", version)?,
+            "v{version} should be a JSON file"
+        );
+    }
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &v4)?,
+        CheckResult::Success
+    );
+
+    // Requiring a large document size converts v1 back to JSON.
+    let v4 = ManagedApis::new(vec![
+        versioned_health_with_v4_git_stub_api().with_git_stub_policy(
+            GitStubPolicy::new().min_document_size(1024 * 1024),
+        ),
+    ])?;
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &v4)?,
+        CheckResult::NeedsUpdate
+    );
+    env.generate_documents(&v4)?;
+    assert!(!env.versioned_git_stub_exists("versioned-health", "1.0.0")?);
+    assert!(env.versioned_local_document_exists("versioned-health", "1.0.0")?);
+
+    Ok(())
+}
+
+/// Test pinning commits referenced by Git stubs, and checking that they are
+/// reachable.
+#[test]
+fn test_pin_git_stub_commits() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+
+    let v1_v2_v3 = versioned_health_git_stub_apis()?;
+    env.generate_documents(&v1_v2_v3)?;
+    env.commit_documents()?;
+
+    let v4 = versioned_health_with_v4_git_stub_apis()?;
+    env.generate_documents(&v4)?;
+    env.commit_documents()?;
+    assert!(env.versioned_git_stub_exists("versioned-health", "1.0.0")?);
+
+    // All referenced commits are on the main branch, so they're reachable.
+    let result = pin_git_stub_commits(env.environment(), &v4, true)?;
+    assert_eq!(result, CheckResult::Success);
+    assert!(env.list_git_refs("refs/openapi/pins/")?.is_empty());
+
+    // Pinning creates a ref for the (single) commit the stubs point to.
+    let result = pin_git_stub_commits(env.environment(), &v4, false)?;
+    assert_eq!(result, CheckResult::Success);
+    let git_stub = env.read_versioned_git_stub("versioned-health", "1.0.0")?;
+    assert_eq!(
+        env.list_git_refs("refs/openapi/pins/")?,
+        [format!("refs/openapi/pins/{}", git_stub.commit())],
+    );
+
+    // Point a stub at a commit that doesn't exist.
+    let path = env
+        .find_versioned_git_stub_path("versioned-health", "1.0.0")?
+        .expect("Git stub should exist");
+    let raw = env.read_file(&path)?;
+    let missing = raw.replacen(
+        &git_stub.commit().to_string(),
+        "0123456789abcdef0123456789abcdef01234567",
+        1,
+    );
+    env.create_file(&path, &missing)?;
+
+    let result = pin_git_stub_commits(env.environment(), &v4, true)?;
+    assert_eq!(result, CheckResult::Failures);
+
+    Ok(())
+}
+
+/// Test that materializing writes the JSON contents of Git stubs to a separate
+/// directory, without touching the documents directory.
+#[test]
+fn test_materialize_git_stubs() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+
+    let v1_v2_v3 = versioned_health_git_stub_apis()?;
+    env.generate_documents(&v1_v2_v3)?;
+    env.commit_documents()?;
+
+    let v4 = versioned_health_with_v4_git_stub_apis()?;
+    env.generate_documents(&v4)?;
+    env.commit_documents()?;
+    assert!(env.versioned_git_stub_exists("versioned-health", "1.0.0")?);
+
+    let target_dir = Utf8TempDir::with_prefix("materialize-")?;
+    materialize_documents(env.environment(), &v4, target_dir.path())?;
+
+    // The Git stub was expanded into the corresponding JSON file.
+    let stub_path = env
+        .find_versioned_git_stub_path("versioned-health", "1.0.0")?
+        .expect("Git stub should exist");
+    let json_rel_path = stub_path
+        .strip_prefix("documents")?
+        .as_str()
+        .strip_suffix(".gitstub")
+        .expect("Git stub path should end with .gitstub");
+    let materialized =
+        std::fs::read_to_string(target_dir.path().join(json_rel_path))?;
+    assert_eq!(
+        materialized,
+        env.read_git_stub_content("versioned-health", "1.0.0")?,
+    );
+
+    // Full JSON files and the latest symlink are copied as well.
+    let latest = std::fs::read_to_string(
+        target_dir.path().join("versioned-health/versioned-health-latest.json"),
+    )?;
+    assert_eq!(latest, env.read_versioned_latest_document("versioned-health")?,);
+
+    // The documents directory still contains the Git stub, and everything is
+    // up-to-date.
+    assert!(env.versioned_git_stub_exists("versioned-health", "1.0.0")?);
+    let result = check_apis_up_to_date(env.environment(), &v4)?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
+
 /// Test that Git stubs don't cause merge conflicts when two branches with
 /// different merge bases both convert the same API version to a Git stub.
 ///
@@ -3067,16 +3268,23 @@ fn test_blessed_version_missing_local_is_fixable_git_stub() -> Result<()> {
         std::fs::remove_file(env.workspace_root().join(&v3_json_path))
             .context("failed to delete blessed v3 file")?;
 
+        // The "latest" symlink now dangles as well.
         let (result, summaries) =
             check_apis_with_summaries(env.environment(), &v3_apis)?;
         assert_eq!(result, CheckResult::NeedsUpdate);
         assert_eq!(
             summaries,
-            [ProblemSummary::new(
-                "versioned-health",
-                "3.0.0",
-                ProblemKind::BlessedVersionMissingLocal,
-            )],
+            [
+                ProblemSummary::new(
+                    "versioned-health",
+                    "3.0.0",
+                    ProblemKind::BlessedVersionMissingLocal,
+                ),
+                ProblemSummary::for_api(
+                    "versioned-health",
+                    ProblemKind::LatestLinkDangling,
+                ),
+            ],
         );
 
         env.generate_documents(&v3_apis)?;
@@ -3416,3 +3624,82 @@ fn test_jj_merge_blessed_version_missing_local_git_stub() -> Result<()> {
 
     blessed_version_missing_local_git_stub_verify(&env)
 }
+
+/// Test that renaming the OpenAPI documents directory doesn't break blessed
+/// lookups at revisions from before the rename, or the discovery of the first
+/// commit for Git stubs.
+#[test]
+fn test_git_stub_documents_dir_renamed() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+
+    let v1_v2_apis = versioned_health_reduced_git_stub_apis()?;
+    env.generate_documents(&v1_v2_apis)?;
+    env.commit_documents()?;
+    let v1_v2_commit = env.get_current_commit_hash()?;
+
+    let v1_v2_v3_apis = versioned_health_git_stub_apis()?;
+    env.generate_documents(&v1_v2_v3_apis)?;
+    env.commit_documents()?;
+    let v3_commit = env.get_current_commit_hash()?;
+
+    // Rename the documents directory.
+    env.git_mv("documents".into(), "openapi-docs".into())?;
+    env.git_commit("rename documents directory")?;
+    let renamed_env = Environment::new(
+        "test-openapi-manager",
+        env.workspace_root(),
+        "openapi-docs",
+    )?
+    .with_default_git_branch("main");
+    env.set_environment(|_| renamed_env);
+    let result = check_apis_up_to_date(env.environment(), &v1_v2_v3_apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Blessed documents at a revision from before the rename are found in the
+    // old directory.
+    let docs_dir = env.workspace_root().join("openapi-docs/versioned-health");
+    let find_file = |prefix: &str, suffix: &str| -> Result<Utf8PathBuf> {
+        for entry in docs_dir.read_dir_utf8()? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if name.starts_with(prefix) && name.ends_with(suffix) {
+                return Ok(entry.path().to_owned());
+            }
+        }
+        anyhow::bail!("no file matching {prefix}*{suffix} in {docs_dir}")
+    };
+    let old_blessed_env =
+        env.environment().clone().with_default_git_branch(&v1_v2_commit);
+    let v1_stub = find_file("versioned-health-1.0.0-", ".json.gitstub")?;
+    std::fs::remove_file(&v1_stub)?;
+    let (_, summaries) =
+        check_apis_with_summaries(&old_blessed_env, &v1_v2_v3_apis)?;
+    assert!(
+        summaries.contains(&ProblemSummary::new(
+            "versioned-health",
+            "1.0.0",
+            ProblemKind::BlessedVersionMissingLocal,
+        )),
+        "blessed v1 should be found in the old directory: {summaries:?}"
+    );
+    env.generate_documents(&v1_v2_v3_apis)?;
+    let result = check_apis_up_to_date(env.environment(), &v1_v2_v3_apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Adding v4 turns v3 into a Git stub. It should point to the commit that
+    // originally added v3, not the commit that moved it.
+    let v4_apis = versioned_health_with_v4_git_stub_apis()?;
+    env.generate_documents(&v4_apis)?;
+    let v3_stub = find_file("versioned-health-3.0.0-", ".json.gitstub")?;
+    let v3_stub_contents = std::fs::read_to_string(&v3_stub)?;
+    assert!(
+        v3_stub_contents
+            .starts_with(&format!("{v3_commit}:documents/versioned-health/")),
+        "Git stub should point to the original commit and path: \
+         {v3_stub_contents}"
+    );
+    let result = check_apis_up_to_date(env.environment(), &v4_apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
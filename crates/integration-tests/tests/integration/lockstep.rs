@@ -6,16 +6,28 @@
 //! generated from the current code. There are no "blessed" documents for
 //! lockstep APIs - they're always fresh from the API trait definition.
 
+use crate::atomic_write;
 use anyhow::Result;
+use camino_tempfile::Utf8TempDir;
+use clap::Parser;
 use dropshot_api_manager::{
-    ManagedApiConfig, ManagedApis,
+    ApiSelector, App, DocsLinkLint, Environment, FAILURE_EXIT_CODE,
+    GenerationTimings, LoadProblemSeverity, LockstepVersionPolicy, ManagedApi,
+    ManagedApiConfig, ManagedApis, NEEDS_UPDATE_EXIT_CODE,
     test_util::{
-        CheckResult, ProblemKind, ProblemSummary, check_apis_up_to_date,
+        CheckResult, ProblemKind, ProblemSummary, ValidationHarness,
+        check_apis_up_to_date, check_apis_with_load_problems,
         check_apis_with_summaries,
     },
 };
+use dropshot_api_manager_types::{CustomFix, ValidationContext};
 use integration_tests::*;
 use openapiv3::OpenAPI;
+use std::{
+    process::ExitCode,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 /// Test basic lockstep API document generation.
 #[test]
@@ -61,6 +73,41 @@ fn lockstep_generate_basic_impl(env: &TestEnvironment) -> Result<()> {
     Ok(())
 }
 
+/// Test managing lockstep APIs in a directory that isn't under version
+/// control.
+#[test]
+fn test_lockstep_no_git() -> Result<()> {
+    let temp_dir = Utf8TempDir::with_prefix("dropshot-api-manager-no-git-")?;
+    std::fs::create_dir(temp_dir.path().join("documents"))?;
+    let env = Environment::new_no_git(
+        "test-openapi-manager",
+        temp_dir.path(),
+        "documents",
+    )?;
+    let apis = lockstep_multi_apis()?;
+
+    let generate = App::try_parse_from(["bin", "generate"])?;
+    assert_eq!(generate.exec(&env, &apis), ExitCode::SUCCESS);
+    let result = check_apis_up_to_date(&env, &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Local changes are still compared against generated documents.
+    let health_path = temp_dir.path().join("documents/health.json");
+    atomic_write(&health_path, "{}")?;
+    let result = check_apis_up_to_date(&env, &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+
+    // Versioned APIs need blessed documents, so they're rejected.
+    let error = check_apis_up_to_date(&env, &versioned_health_apis()?)
+        .expect_err("versioned APIs are rejected without version control");
+    assert!(
+        format!("{error:#}").contains("bailing out"),
+        "unexpected error: {error:#}",
+    );
+
+    Ok(())
+}
+
 /// Test that lockstep APIs always pass the up-to-date check.
 #[test]
 fn test_lockstep_always_up_to_date() -> Result<()> {
@@ -77,6 +124,203 @@ fn test_lockstep_always_up_to_date() -> Result<()> {
     Ok(())
 }
 
+/// Test that a lockstep version policy bumps the document's version when its
+/// contents change, and only then.
+#[test]
+fn test_lockstep_version_policy() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis_with = |description: &'static str| -> Result<ManagedApis> {
+        let mut config = lockstep_health_api();
+        config.metadata.description = Some(description);
+        Ok(ManagedApis::new(vec![
+            ManagedApi::from(config).with_lockstep_version_policy(
+                LockstepVersionPolicy::bump_minor(),
+            ),
+        ])?)
+    };
+    let document_version = || -> Result<String> {
+        let document: OpenAPI =
+            serde_json::from_str(&env.read_lockstep_document("health")?)?;
+        Ok(document.info.version)
+    };
+
+    // The first document gets the configured version.
+    let apis = apis_with("original")?;
+    env.generate_documents(&apis)?;
+    assert_eq!(document_version()?, "1.0.0");
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // A change is reported as stale, and generate bumps the version.
+    let apis = apis_with("changed")?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new("health", "1.0.0", ProblemKind::LockstepStale)],
+    );
+    env.generate_documents(&apis)?;
+    assert_eq!(document_version()?, "1.1.0");
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Without another change, the version is kept.
+    env.generate_documents(&apis)?;
+    assert_eq!(document_version()?, "1.1.0");
+
+    Ok(())
+}
+
+/// Test that a pinned OpenAPI version is written into generated documents, and
+/// that documents with a different one are reported as stale.
+#[test]
+fn test_openapi_version() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+    let apis = lockstep_health_apis()?;
+    let openapi_version = |env: &TestEnvironment| -> Result<String> {
+        let document: OpenAPI =
+            serde_json::from_str(&env.read_lockstep_document("health")?)?;
+        Ok(document.openapi)
+    };
+
+    env.generate_documents(&apis)?;
+    let dropshot_version = openapi_version(&env)?;
+
+    // Pinning the version Dropshot already writes changes nothing.
+    let pinned = dropshot_version.clone();
+    env.set_environment(|e| e.with_openapi_version(&pinned).unwrap());
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Pinning another version makes the document stale, until it's generated
+    // again.
+    let pinned = if dropshot_version == "3.0.3" { "3.0.4" } else { "3.0.3" };
+    env.set_environment(|e| e.with_openapi_version(pinned).unwrap());
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    env.generate_documents(&apis)?;
+    assert_eq!(openapi_version(&env)?, pinned);
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Only OpenAPI 3.0 versions can be pinned.
+    for invalid in ["3.1.0", "3.0", "three"] {
+        env.environment()
+            .clone()
+            .with_openapi_version(invalid)
+            .expect_err("version should be rejected");
+    }
+
+    Ok(())
+}
+
+/// Test that APIs with hand-written documents go through the same generate and
+/// check machinery as Dropshot APIs.
+#[test]
+fn test_lockstep_static_document() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = lockstep_static_apis()?;
+
+    env.generate_documents(&apis)?;
+
+    // The document is written out as-is.
+    assert!(env.lockstep_document_exists("static"));
+    assert_eq!(env.read_lockstep_document("static")?, STATIC_DOCUMENT);
+
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Local edits to the document are detected.
+    env.create_file(
+        "documents/static.json",
+        &STATIC_DOCUMENT.replace("Static API", "Edited API"),
+    )?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+
+    Ok(())
+}
+
+/// Test that a document with a `$ref` that doesn't resolve fails validation.
+#[test]
+fn test_lockstep_dangling_ref() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = lockstep_dangling_ref_apis()?;
+
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert!(summaries.contains(&ProblemSummary::new(
+        "dangling",
+        "1.0.0",
+        ProblemKind::GeneratedValidationError,
+    )));
+
+    Ok(())
+}
+
+/// Test that an API produced by an `ApiDescriptionProvider` is interchangeable
+/// with the same API configured via a Dropshot `api_description` function.
+#[test]
+fn test_lockstep_api_description_provider() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = lockstep_health_apis()?;
+    let provider_apis = lockstep_health_provider_apis()?;
+
+    env.generate_documents(&apis)?;
+    let document = env.read_lockstep_document("health")?;
+
+    let result = check_apis_up_to_date(env.environment(), &provider_apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    env.generate_documents(&provider_apis)?;
+    assert_eq!(env.read_lockstep_document("health")?, document);
+
+    Ok(())
+}
+
+/// Test that a panic while generating one API is reported as a problem for
+/// that API, without preventing other APIs from being checked.
+#[test]
+fn test_lockstep_generation_panic() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    env.generate_documents(&lockstep_health_apis()?)?;
+
+    let apis = lockstep_health_and_panicking_apis()?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "panicking",
+            "1.0.0",
+            ProblemKind::GenerationPanicked,
+        )],
+    );
+
+    // A stale document for the other API is still detected.
+    env.create_file(
+        "documents/health.json",
+        &env.read_lockstep_document("health")?.replace("Health API", "Stale"),
+    )?;
+    let (_, summaries) = check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(
+        summaries,
+        [
+            ProblemSummary::new("health", "1.0.0", ProblemKind::LockstepStale),
+            ProblemSummary::new(
+                "panicking",
+                "1.0.0",
+                ProblemKind::GenerationPanicked,
+            ),
+        ],
+    );
+
+    Ok(())
+}
+
 /// Test generating multiple lockstep APIs.
 #[test]
 fn test_lockstep_multiple_apis() -> Result<()> {
@@ -107,6 +351,405 @@ fn test_lockstep_multiple_apis() -> Result<()> {
     Ok(())
 }
 
+/// Test requiring links to external documentation.
+#[test]
+fn test_docs_link_lint() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let lint = DocsLinkLint::new("https://docs.example.com/apis/*")?;
+
+    // The link is written into the document.
+    let apis = lockstep_health_docs_link_apis(
+        Some("https://docs.example.com/apis/health"),
+        lint.clone(),
+    )?;
+    env.generate_documents(&apis)?;
+    let document: OpenAPI =
+        serde_json::from_str(&env.read_lockstep_document("health")?)?;
+    assert_eq!(
+        document.external_docs.map(|docs| docs.url).as_deref(),
+        Some("https://docs.example.com/apis/health"),
+    );
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &apis)?,
+        CheckResult::Success,
+    );
+
+    // Documents without a link, or with one that doesn't match the pattern,
+    // fail validation.
+    for url in [None, Some("https://example.com/health")] {
+        let apis = lockstep_health_docs_link_apis(url, lint.clone())?;
+        let (result, summaries) =
+            check_apis_with_summaries(env.environment(), &apis)?;
+        assert_eq!(result, CheckResult::Failures);
+        assert!(
+            summaries
+                .iter()
+                .any(|s| s.kind == ProblemKind::GeneratedValidationError),
+            "summaries: {:?}",
+            summaries,
+        );
+    }
+
+    Ok(())
+}
+
+/// Test checking documents in a directory outside the working copy.
+#[test]
+fn test_check_documents_dir() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = lockstep_health_apis()?;
+    env.generate_documents(&apis)?;
+
+    // Export the documents, then make the working copy's document stale. The
+    // check only looks at the exported documents.
+    let export_dir = Utf8TempDir::with_prefix("dropshot-api-manager-export-")?;
+    let document = env.read_lockstep_document("health")?;
+    atomic_write(&export_dir.path().join("health.json"), &document)?;
+    let stale = document.replace("Health API", "Stale Health API");
+    env.create_file("documents/health.json", &stale)?;
+
+    let check = || -> Result<ExitCode> {
+        let app = App::try_parse_from([
+            "bin",
+            "check",
+            "--documents-dir",
+            export_dir.path().as_str(),
+        ])?;
+        Ok(app.exec(env.environment(), &apis))
+    };
+    assert_eq!(check()?, ExitCode::SUCCESS);
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &apis)?,
+        CheckResult::NeedsUpdate
+    );
+
+    // A stale exported document fails the check.
+    atomic_write(&export_dir.path().join("health.json"), &stale)?;
+    assert_eq!(check()?, ExitCode::from(NEEDS_UPDATE_EXIT_CODE));
+
+    // The directory must exist.
+    let app = App::try_parse_from([
+        "bin",
+        "check",
+        "--documents-dir",
+        export_dir.path().join("missing").as_str(),
+    ])?;
+    assert_eq!(
+        app.exec(env.environment(), &apis),
+        ExitCode::from(FAILURE_EXIT_CODE)
+    );
+
+    Ok(())
+}
+
+/// Test limiting a check to the APIs affected by a list of changed files.
+#[test]
+fn test_check_changed_files() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+    env.set_environment(|e| {
+        e.with_generation_timings(GenerationTimings::new("timings.json"))
+    });
+    let apis = lockstep_multi_with_source_globs_apis()?;
+    env.generate_documents(&apis)?;
+
+    // Make one document stale. A check only fails if it includes that API.
+    let document = env.read_lockstep_document("counter")?;
+    env.create_file(
+        "documents/counter.json",
+        &document.replace("Counter Test API", "Stale Counter Test API"),
+    )?;
+
+    let list_dir = Utf8TempDir::with_prefix("dropshot-api-manager-changed-")?;
+    let list = list_dir.path().join("changed.txt");
+    let check = |apis: &ManagedApis, changed: &[&str]| -> Result<ExitCode> {
+        let contents: String =
+            changed.iter().map(|path| format!("{}\n", path)).collect();
+        atomic_write(&list, contents)?;
+        let app = App::try_parse_from([
+            "bin",
+            "check",
+            "--changed-files",
+            list.as_str(),
+        ])?;
+        Ok(app.exec(env.environment(), apis))
+    };
+    let needs_update = ExitCode::from(NEEDS_UPDATE_EXIT_CODE);
+
+    // Documents map to the API they belong to, and other files to the APIs
+    // whose source globs they match.
+    assert_eq!(check(&apis, &["documents/health.json"])?, ExitCode::SUCCESS);
+    assert_eq!(check(&apis, &["./apis/user/src/lib.rs"])?, ExitCode::SUCCESS);
+    assert_eq!(check(&apis, &["documents/counter.json"])?, needs_update);
+    assert_eq!(check(&apis, &["apis/counter/src/lib.rs"])?, needs_update);
+    assert_eq!(check(&apis, &["Cargo.lock"])?, needs_update);
+
+    // So do files an API maintains outside the documents directory, even if
+    // its source globs don't match them.
+    assert_eq!(check(&apis, &["clients/counter.json"])?, needs_update);
+
+    // Files that don't affect any API are skipped.
+    assert_eq!(check(&apis, &["README.md"])?, ExitCode::SUCCESS);
+    assert_eq!(check(&apis, &[])?, ExitCode::SUCCESS);
+
+    // Files that can't be attributed to an API cause every API to be checked.
+    assert_eq!(
+        check(&apis, &["documents/health.json", "documents/other.json"])?,
+        needs_update
+    );
+    assert_eq!(check(&apis, &["timings.json"])?, needs_update);
+    let apis = lockstep_multi_apis()?;
+    assert_eq!(check(&apis, &["apis/health/src/lib.rs"])?, needs_update);
+
+    // Absolute paths must be within the repository.
+    assert_eq!(
+        check(&apis, &["/elsewhere/health.json"])?,
+        ExitCode::from(FAILURE_EXIT_CODE)
+    );
+
+    Ok(())
+}
+
+/// Test recording how long each API takes to generate, and warning when an API
+/// takes much longer than last time.
+#[test]
+fn test_generation_timings() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+    let apis = lockstep_multi_apis()?;
+    env.generate_documents(&apis)?;
+    env.set_environment(|e| {
+        e.with_generation_timings(
+            GenerationTimings::new("generation-timings.json")
+                .with_min_duration(Duration::ZERO),
+        )
+    });
+    let check = |env: &TestEnvironment| -> Result<Vec<String>> {
+        let (result, load_problems) =
+            check_apis_with_load_problems(env.environment(), &apis)?;
+        assert_eq!(result, CheckResult::Success);
+        Ok(load_problems
+            .iter()
+            .map(|(severity, error)| {
+                assert_eq!(*severity, LoadProblemSeverity::Warning);
+                format!("{error:#}")
+            })
+            .collect())
+    };
+    let recorded = |env: &TestEnvironment| -> Result<Vec<String>> {
+        let timings: serde_json::Value =
+            serde_json::from_str(&env.read_file("generation-timings.json")?)?;
+        Ok(timings["apis"].as_object().unwrap().keys().cloned().collect())
+    };
+
+    // Checking without a state file neither warns nor creates one.
+    let warnings = check(&env)?;
+    assert!(warnings.is_empty(), "no warnings: {warnings:?}");
+    assert!(!env.file_exists("generation-timings.json"));
+
+    // Generating records a time for each API.
+    env.generate_documents(&apis)?;
+    assert_eq!(recorded(&env)?, ["counter", "health", "user"]);
+
+    // If an API took much less time last time, checking warns, but leaves
+    // the state file alone.
+    let previous = serde_json::json!({
+        "apis": {
+            "counter": { "generation_us": 1 },
+            "gone": { "generation_us": 1 },
+        },
+    });
+    env.create_file("generation-timings.json", &previous.to_string())?;
+    let warnings = check(&env)?;
+    assert_eq!(warnings.len(), 1, "one warning: {warnings:?}");
+    assert!(
+        warnings[0].contains("generating documents for counter took"),
+        "unexpected warning: {}",
+        warnings[0],
+    );
+    assert_eq!(recorded(&env)?, ["counter", "gone"]);
+
+    // Generating records the new times. Times for APIs that are no longer
+    // managed are dropped.
+    env.generate_documents(&apis)?;
+    assert_eq!(recorded(&env)?, ["counter", "health", "user"]);
+
+    // A damaged state file is reported by checking, and replaced by
+    // generating.
+    env.create_file("generation-timings.json", "not json")?;
+    let warnings = check(&env)?;
+    assert_eq!(warnings.len(), 1, "one warning: {warnings:?}");
+    assert!(
+        warnings[0].contains("ignoring generation timings"),
+        "unexpected warning: {}",
+        warnings[0],
+    );
+    assert_eq!(env.read_file("generation-timings.json")?, "not json");
+    env.generate_documents(&apis)?;
+    assert_eq!(recorded(&env)?, ["counter", "health", "user"]);
+
+    // The regression factor must be greater than 1.
+    for invalid in [1.0, 0.5, f64::NAN] {
+        GenerationTimings::new("generation-timings.json")
+            .with_regression_factor(invalid)
+            .expect_err("factor should be rejected");
+    }
+
+    Ok(())
+}
+
+/// Test unit testing a validation function with the validation harness.
+#[test]
+fn test_validation_harness() -> Result<()> {
+    let document: OpenAPI = serde_json::from_value(serde_json::json!({
+        "openapi": "3.0.3",
+        "info": { "title": "Widgets", "version": "1.0.0" },
+        "paths": {},
+    }))?;
+    let validate = |document: &OpenAPI, mut cx: ValidationContext<'_>| {
+        if document.info.title != cx.title() {
+            cx.report_error(anyhow::anyhow!(
+                "document title {:?} doesn't match {:?}",
+                document.info.title,
+                cx.title(),
+            ));
+        }
+        if cx.is_latest() && cx.is_blessed() != Some(true) {
+            cx.record_file_contents(
+                format!("docs/{}.md", cx.ident()),
+                format!("# {}", cx.title()).into_bytes(),
+            );
+        }
+        if cx.file_name().version().is_some() {
+            cx.report_fixable_problem(CustomFix::remove_file("old-index.md"));
+        }
+    };
+
+    // By default, the context describes the latest version of a lockstep API.
+    let output = ValidationHarness::new("widgets")
+        .with_title("Widgets")
+        .run(&document, validate);
+    assert!(output.errors().is_empty(), "errors: {:?}", output.errors());
+    assert_eq!(output.files().len(), 1);
+    assert_eq!(output.files()[0].0, "docs/widgets.md");
+    assert_eq!(output.files()[0].1, b"# Widgets");
+    assert!(output.fixes().is_empty());
+
+    // A blessed version of a versioned API.
+    let output = ValidationHarness::new("widgets")
+        .with_title("Gadgets")
+        .with_version(semver::Version::new(2, 0, 0))
+        .with_blessed(true)
+        .run(&document, validate);
+    assert_eq!(output.errors().len(), 1, "errors: {:?}", output.errors());
+    assert!(output.files().is_empty());
+    assert_eq!(output.fixes().len(), 1);
+    assert_eq!(output.fixes()[0].description(), "remove old-index.md");
+
+    // An older version that isn't blessed.
+    let output = ValidationHarness::new("widgets")
+        .with_title("Widgets")
+        .with_version(semver::Version::new(1, 0, 0))
+        .with_latest(false)
+        .run(&document, |_, cx| {
+            assert_eq!(cx.is_blessed(), Some(false));
+            assert!(!cx.is_latest());
+            assert!(cx.versions().is_versioned());
+            assert!(
+                cx.file_name().basename().starts_with("widgets-1.0.0-"),
+                "file name: {}",
+                cx.file_name(),
+            );
+        });
+    assert!(output.errors().is_empty());
+
+    Ok(())
+}
+
+/// Test validation functions registered for groups of APIs.
+#[test]
+fn test_validation_for_selected_apis() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    env.generate_documents(&lockstep_multi_apis()?)?;
+
+    // Each function is called, in order, for the APIs it selects.
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let record = |name: &'static str| {
+        let calls = Arc::clone(&calls);
+        move |_: &OpenAPI, cx: ValidationContext<'_>| {
+            calls.lock().unwrap().push(format!("{}: {}", name, cx.ident()));
+        }
+    };
+    let apis = lockstep_multi_apis()?
+        .with_validation(record("global"))
+        .with_validation_for(ApiSelector::ident_glob("*er")?, record("*er"))
+        .with_validation_for(ApiSelector::all(), record("all"));
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &apis)?,
+        CheckResult::Success,
+    );
+    let mut calls = std::mem::take(&mut *calls.lock().unwrap());
+    // APIs are validated in parallel, so only the order of calls for each
+    // API is defined.
+    calls.sort_by_key(|call| call.split(": ").nth(1).map(str::to_owned));
+    assert_eq!(
+        calls,
+        [
+            "global: counter",
+            "*er: counter",
+            "all: counter",
+            "global: health",
+            "all: health",
+            "global: user",
+            "*er: user",
+            "all: user",
+        ],
+    );
+
+    // Errors are only reported for the selected APIs.
+    let apis = lockstep_multi_apis()?.with_validation_for(
+        ApiSelector::ident_glob("count*")?,
+        |_, mut cx| cx.report_error(anyhow::anyhow!("counters are forbidden")),
+    );
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "counter",
+            "1.0.0",
+            ProblemKind::GeneratedValidationError,
+        )],
+    );
+
+    Ok(())
+}
+
+/// Test that `--verify-deterministic` catches documents that differ each
+/// time they're generated.
+#[test]
+fn test_verify_deterministic() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+
+    let apis = lockstep_health_apis()?;
+    env.generate_documents_with_args(&apis, &["--verify-deterministic"])?;
+    assert_eq!(
+        env.check_documents_with_args(&apis, &["--verify-deterministic"])?,
+        ExitCode::SUCCESS,
+    );
+
+    let apis = lockstep_nondeterministic_apis()?;
+    let error = env
+        .generate_documents_with_args(&apis, &["--verify-deterministic"])
+        .unwrap_err();
+    assert_eq!(error.to_string(), "failed to generate documents");
+    assert!(
+        !env.lockstep_document_exists("nondeterministic"),
+        "no document should be written if generation isn't deterministic",
+    );
+
+    Ok(())
+}
+
 /// Test empty API set handling.
 #[test]
 fn test_empty_api_set() -> Result<()> {
@@ -1,6 +1,19 @@
 // Copyright 2026 Oxide Computer Company
 
+use anyhow::Result;
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::Utf8Path;
+use std::io::Write;
+
 mod generated_from_dir;
 mod git_stub;
 mod lockstep;
 mod versioned;
+
+/// Write content to a file atomically, matching this project's convention
+/// of using `atomicwrites` instead of `std::fs::write`.
+fn atomic_write(path: &Utf8Path, content: impl AsRef<[u8]>) -> Result<()> {
+    AtomicFile::new(path, OverwriteBehavior::AllowOverwrite)
+        .write(|f| f.write_all(content.as_ref()))?;
+    Ok(())
+}
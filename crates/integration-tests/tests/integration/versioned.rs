@@ -6,18 +6,44 @@
 //! OpenAPI document. These are "blessed" documents that are checked into git
 //! and must remain stable across changes.
 
+use crate::atomic_write;
 use anyhow::{Context, Result};
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
+use camino_tempfile::Utf8TempDir;
+use chrono::NaiveDate;
+use clap::Parser;
 use dropshot_api_manager::{
-    ManagedApi, ManagedApis,
+    App, BlessedCache, BreakingChangeApprovals, ConsumerPins, DocumentFreeze,
+    DocumentSigning, Environment, ExampleLint, FAILURE_EXIT_CODE, FileNameHash,
+    FileNameHashAlgorithm, FlattenedDocument, GenerateHook, GitRunner,
+    JsonFormat, JsonIndent, LatestPointer, LoadProblemSeverity, ManagedApi,
+    ManagedApiConfig, ManagedApis, NEEDS_UPDATE_EXIT_CODE, NamingConvention,
+    NamingLint, OutputEvent, OutputSink, TextLint, TypeReplacements,
+    UnknownApiPolicy, VersionResolution, apply_fixes,
     test_util::{
-        CheckResult, ProblemKind, ProblemSummary, check_apis_up_to_date,
-        check_apis_with_summaries,
+        CheckResult, ProblemKind, ProblemSummary, audit_unused_versions_text,
+        blessed_latest_patch, blessed_provenance, blessed_version_issue_hashes,
+        check_apis_up_to_date, check_apis_with_baseline,
+        check_apis_with_json_output, check_apis_with_load_problems,
+        check_apis_with_metrics, check_apis_with_report,
+        check_apis_with_summaries, compat_preview_broken_versions,
+        drift_report_text, list_generate_outputs_with_inputs, new_version_text,
+        pack_blessed_baseline, pins_text, slim_document, status_text,
+        versions_since_release_text, why_blessed_text,
     },
 };
+use dropshot_api_manager_types::{
+    ApiIdent, SupportedVersion, SupportedVersions, VersionScheme, Versions,
+};
 use integration_tests::*;
 use openapiv3::OpenAPI;
 use semver::Version;
+use std::{
+    io,
+    ops::ControlFlow,
+    process::{Command, ExitCode, Output},
+    sync::{Arc, Mutex},
+};
 
 /// Test basic versioned API document generation.
 #[test]
@@ -36,12 +62,73 @@ fn test_pure_jj_versioned_generate_basic() -> Result<()> {
     versioned_generate_basic_impl(&env)
 }
 
+/// Test basic versioned API document generation in a Jujutsu repository
+/// colocated with Git.
+#[test]
+fn test_colocated_jj_versioned_generate_basic() -> Result<()> {
+    if !check_jj_available()? {
+        return Ok(());
+    }
+    let env = TestEnvironment::new_jj_colocated()?;
+    versioned_generate_basic_impl(&env)
+}
+
+/// Test that in a Jujutsu repository colocated with Git, a Git remote-tracking
+/// branch can be used as the blessed revision.
+#[test]
+fn test_colocated_jj_blessed_from_git_ref() -> Result<()> {
+    if !check_jj_available()? {
+        return Ok(());
+    }
+    let mut env = TestEnvironment::new_jj_colocated()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    // jj calls this remote bookmark `main@upstream`, but Git calls it
+    // `upstream/main`.
+    env.add_git_remote_to_self("upstream")?;
+    env.set_environment(|e| e.with_default_jj_revset("upstream/main"));
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // The blessed documents really do come from upstream/main.
+    let v1_path = env
+        .find_versioned_document_path("versioned-health", "1.0.0")?
+        .expect("v1 document exists");
+    std::fs::remove_file(env.workspace_root().join(&v1_path))?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "1.0.0",
+            ProblemKind::BlessedVersionMissingLocal,
+        )],
+    );
+
+    Ok(())
+}
+
 fn versioned_generate_basic_impl(env: &TestEnvironment) -> Result<()> {
     let apis = versioned_health_apis()?;
 
     // Check that latest_version exists.
     assert_eq!(versioned_health::latest_version(), Version::new(3, 0, 0),);
 
+    // Check that the version table lists each version with its label, newest
+    // first.
+    assert_eq!(
+        versioned_health::VERSION_METADATA,
+        [
+            ("WITH_METRICS", Version::new(3, 0, 0)),
+            ("WITH_DETAILED_STATUS", Version::new(2, 0, 0)),
+            ("INITIAL", Version::new(1, 0, 0)),
+        ],
+    );
+
     // Initially, no documents should exist.
     assert!(
         !env.versioned_local_document_exists("versioned-health", "1.0.0")
@@ -392,6 +479,40 @@ fn test_blessed_api_trivial_changes_pass_when_allowed() -> Result<()> {
     Ok(())
 }
 
+/// Test that the drift report counts the paths that trivial changes to the
+/// latest blessed version have touched.
+#[test]
+fn test_drift_report() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    // APIs that require bytewise equality for the latest version can't drift.
+    assert_eq!(
+        drift_report_text(env.environment(), &apis)?,
+        "no versioned APIs allow trivial changes to their latest version\n",
+    );
+
+    let allowed_apis = ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api())
+            .allow_trivial_changes_for_latest(),
+    ])?;
+    assert_eq!(
+        drift_report_text(env.environment(), &allowed_apis)?,
+        "versioned-health v3.0.0: no drift\n",
+    );
+
+    // The title and description changed.
+    let modified_apis = versioned_health_trivial_change_allowed_apis()?;
+    assert_eq!(
+        drift_report_text(env.environment(), &modified_apis)?,
+        "versioned-health v3.0.0: 2 changed paths\n",
+    );
+
+    Ok(())
+}
+
 /// Test that trivial changes to older (non-latest) blessed versions pass with
 /// semantic equality only.
 #[test]
@@ -604,6 +725,317 @@ fn test_removing_api_version_fails_check() -> Result<()> {
     Ok(())
 }
 
+/// Test that problems are reported in a stable order: by API, then version,
+/// then kind.
+#[test]
+fn test_problem_order_is_deterministic() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = multi_versioned_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    // Removing the later versions of one API and all local documents for the
+    // other results in problems of several kinds across both APIs.
+    for path in env.list_versioned_documents("versioned-user")? {
+        std::fs::remove_file(env.workspace_root().join(path))?;
+    }
+    let v1_only = ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_v1_only_api()),
+        ManagedApi::from(versioned_user_api()),
+    ])?;
+
+    let (_, summaries) =
+        check_apis_with_summaries(env.environment(), &v1_only)?;
+    assert!(!summaries.is_empty(), "expected problems");
+
+    // General problems come first, and are sorted.
+    let num_general = summaries
+        .iter()
+        .take_while(|s| s.kind == ProblemKind::LocalSpecFileOrphaned)
+        .count();
+    assert!(num_general > 0, "expected orphaned documents");
+    assert!(summaries[..num_general].is_sorted());
+
+    // Per-version problems are sorted by API, version, and kind.
+    let per_version: Vec<_> = summaries[num_general..]
+        .iter()
+        .filter(|s| s.version.is_some())
+        .collect();
+    assert!(per_version.is_sorted());
+
+    // Repeated runs produce identical output.
+    for _ in 0..3 {
+        let (_, again) =
+            check_apis_with_summaries(env.environment(), &v1_only)?;
+        assert_eq!(again, summaries);
+    }
+
+    Ok(())
+}
+
+/// Test that files matching ignore globs within the documents directory are
+/// skipped.
+#[test]
+fn test_ignore_globs() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+
+    // Files left behind by editors and merge tools are ignored by default.
+    env.create_file("documents/.DS_Store", "junk")?;
+    env.create_file(
+        "documents/versioned-health/versioned-health-1.0.0-abcdef.json.orig",
+        "junk",
+    )?;
+    env.create_file("documents/versioned-health/.#notes.json", "junk")?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // A directory that isn't an API is an error unless it's ignored.
+    env.create_file("documents/scratch/notes.json", "{}")?;
+    check_apis_up_to_date(env.environment(), &apis)
+        .expect_err("unknown directory is an error");
+
+    env.set_ignore_globs(&["scratch"])?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Invalid globs are rejected up front.
+    env.set_ignore_globs(&["scratch/[a"])
+        .expect_err("invalid glob is rejected");
+
+    Ok(())
+}
+
+/// Test that unmanaged files in an API's directory are left alone.
+#[test]
+fn test_unmanaged_files() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_with_unmanaged_files_apis()?;
+    env.generate_documents(&apis)?;
+
+    let readme_path = "documents/versioned-health/README.md";
+    let changelog_path = "documents/versioned-health/CHANGELOG.md";
+    env.create_file(readme_path, "# Versioned health API\n")?;
+    env.create_file(changelog_path, "# Changelog\n")?;
+    env.commit_documents()?;
+
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    assert!(summaries.is_empty(), "no problems: {summaries:?}");
+
+    // Generating documents doesn't touch the unmanaged files.
+    env.generate_documents(&apis)?;
+    assert_eq!(env.read_file(readme_path)?, "# Versioned health API\n");
+    assert_eq!(env.read_file(changelog_path)?, "# Changelog\n");
+
+    Ok(())
+}
+
+/// Test that warnings recorded while loading documents are returned to
+/// callers.
+#[test]
+fn test_load_warnings() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    let (result, load_problems) =
+        check_apis_with_load_problems(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    assert!(load_problems.is_empty(), "no load problems: {load_problems:?}");
+
+    // A stray file in the documents directory is skipped with a warning.
+    env.create_file("documents/notes.txt", "some notes\n")?;
+    let (result, load_problems) =
+        check_apis_with_load_problems(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    let problems: Vec<_> = load_problems
+        .iter()
+        .map(|(severity, error)| (severity, format!("{error:#}")))
+        .collect();
+    assert_eq!(problems.len(), 1, "one load problem: {problems:?}");
+    let (severity, message) = &problems[0];
+    assert_eq!(*severity, LoadProblemSeverity::Warning);
+    assert!(
+        message.contains("skipping file \"notes.txt\""),
+        "unexpected warning: {message}",
+    );
+
+    Ok(())
+}
+
+/// Test that changing the file name hash only affects newly-generated
+/// documents, and that existing documents continue to be accepted.
+#[test]
+fn test_file_name_hash_change() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+
+    let reduced_apis = versioned_health_reduced_apis()?;
+    env.generate_documents(&reduced_apis)?;
+    env.commit_documents()?;
+
+    env.set_file_name_hash(FileNameHash::new(
+        FileNameHashAlgorithm::Sha512,
+        8,
+    )?);
+
+    // Existing documents keep their names.
+    let result = check_apis_up_to_date(env.environment(), &reduced_apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // New versions are named using the new hash.
+    let expanded_apis = versioned_health_apis()?;
+    env.generate_documents(&expanded_apis)?;
+    let result = check_apis_up_to_date(env.environment(), &expanded_apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    let hash_lens: Vec<_> = env
+        .list_versioned_documents("versioned-health")?
+        .iter()
+        .filter_map(|path| {
+            let stem = path.file_name()?.strip_suffix(".json")?;
+            let (_, hash) = stem.rsplit_once('-')?;
+            (hash != "latest").then_some(hash.len())
+        })
+        .collect();
+    assert!(hash_lens.contains(&6), "blessed documents use 6-character hashes");
+    assert!(hash_lens.contains(&16), "new documents use 16-character hashes");
+    assert!(hash_lens.iter().all(|len| *len == 6 || *len == 16));
+
+    Ok(())
+}
+
+/// Test resolving the document to serve for a requested client version.
+#[test]
+fn test_resolve_version() -> Result<()> {
+    let apis = versioned_health_apis()?;
+    let ident = ApiIdent::from("versioned-health");
+
+    assert_eq!(
+        apis.resolve_version(&ident, &Version::new(2, 0, 0)),
+        VersionResolution::Exact(Version::new(2, 0, 0)),
+    );
+    assert_eq!(
+        apis.resolve_version(&ident, &Version::new(2, 3, 1)),
+        VersionResolution::NoCompatibleVersion,
+    );
+    assert_eq!(
+        apis.resolve_version(&ident, &Version::new(4, 0, 0)),
+        VersionResolution::NoCompatibleVersion,
+    );
+    assert_eq!(
+        apis.resolve_version(
+            &ApiIdent::from("nonexistent"),
+            &Version::new(1, 0, 0),
+        ),
+        VersionResolution::UnknownApi,
+    );
+
+    // Like Cargo's caret requirements, a request is only satisfied by a
+    // version at least as new as the one requested.
+    let apis = ManagedApis::new(vec![ManagedApi::from(ManagedApiConfig {
+        versions: Versions::new_versioned(SupportedVersions::new(vec![
+            SupportedVersion::new(Version::new(1, 0, 0), "INITIAL"),
+            SupportedVersion::new(Version::new(2, 1, 0), "MINOR"),
+            SupportedVersion::new(Version::new(2, 4, 0), "ANOTHER_MINOR"),
+        ])),
+        ..versioned_health_api()
+    })])?;
+    assert_eq!(
+        apis.resolve_version(&ident, &Version::new(2, 0, 0)),
+        VersionResolution::Compatible(Version::new(2, 4, 0)),
+    );
+    assert_eq!(
+        apis.resolve_version(&ident, &Version::new(2, 2, 0)),
+        VersionResolution::Compatible(Version::new(2, 4, 0)),
+    );
+    assert_eq!(
+        apis.resolve_version(&ident, &Version::new(2, 9, 0)),
+        VersionResolution::NoCompatibleVersion,
+    );
+
+    Ok(())
+}
+
+/// Test that an API alias keeps an up-to-date copy of the API's documents.
+#[test]
+fn test_alias_copy() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_aliased_apis()?;
+
+    env.generate_documents(&apis)?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Every document (and the "latest" symlink) should be copied under the
+    // alias, with the file names renamed to match.
+    for path in env.list_versioned_documents("versioned-health")? {
+        let file_name = path.file_name().unwrap();
+        let alias_name =
+            file_name.replacen("versioned-health-", "versioned-health-old-", 1);
+        let alias_path = format!("documents/versioned-health-old/{alias_name}");
+        assert_eq!(
+            env.read_file(&alias_path)?,
+            env.read_file(&path)?,
+            "{alias_path} matches {path}",
+        );
+    }
+    let latest_target = env
+        .read_link("documents/versioned-health/versioned-health-latest.json")?;
+    let alias_latest_target = env.read_link(
+        "documents/versioned-health-old/versioned-health-old-latest.json",
+    )?;
+    assert_eq!(
+        alias_latest_target.as_str(),
+        latest_target.as_str().replacen(
+            "versioned-health-",
+            "versioned-health-old-",
+            1
+        ),
+    );
+
+    // Modifying a copy is detected and fixed by generate.
+    let v1_path = env
+        .find_versioned_document_path("versioned-health", "1.0.0")?
+        .expect("v1 document exists");
+    let v1_file_name = v1_path.file_name().unwrap().replacen(
+        "versioned-health-",
+        "versioned-health-old-",
+        1,
+    );
+    let alias_v1_path =
+        format!("documents/versioned-health-old/{v1_file_name}");
+    env.create_file(&alias_v1_path, "modified")?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::for_api("versioned-health", ProblemKind::AliasStale)],
+    );
+
+    env.generate_documents(&apis)?;
+    assert_eq!(env.read_file(&alias_v1_path)?, env.read_file(&v1_path)?);
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // So is an extra file under the alias.
+    let extra_path =
+        "documents/versioned-health-old/versioned-health-old-9.0.0-abcdef.json";
+    env.create_file(extra_path, "{}")?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    env.generate_documents(&apis)?;
+    assert!(!env.file_exists(extra_path));
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
+
 /// Test that adding new API versions passes the check.
 #[test]
 fn test_adding_new_api_version_passes_check() -> Result<()> {
@@ -1155,51 +1587,2915 @@ fn test_extra_validation_blessed_vs_non_blessed() -> Result<()> {
     Ok(())
 }
 
+/// Test that fixable problems reported by extra validation are reported by
+/// check and fixed by generate.
 #[test]
-fn test_extra_validation_with_extra_file() -> Result<()> {
+fn test_extra_validation_custom_fix() -> Result<()> {
     let env = TestEnvironment::new_git()?;
-    let apis = versioned_health_with_extra_file_apis()?;
+    let apis = versioned_health_with_custom_fix_apis()?;
 
     env.generate_documents(&apis)?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
 
-    let calls = get_validation_calls();
-
+    env.create_file(OBSOLETE_FILE_PATH, "stale contents")?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
     assert_eq!(
-        calls.len(),
-        6,
-        "validation should be called twice for each of the 3 versions"
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "3.0.0",
+            ProblemKind::ValidationFixable,
+        )],
     );
 
-    let latest_file = env
-        .workspace_root()
-        .join("documents")
-        .join("versioned-health")
-        .join("latest-3.0.0.txt");
+    env.generate_documents(&apis)?;
     assert!(
-        latest_file.exists(),
-        "marker file should be generated for latest version"
+        !env.file_exists(OBSOLETE_FILE_PATH),
+        "obsolete file should have been removed"
     );
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
 
-    let content = std::fs::read_to_string(&latest_file)
-        .context("failed to read marker file")?;
-    assert_eq!(content, "This is the latest version: 3.0.0");
+    Ok(())
+}
 
-    let v1_file = env
-        .workspace_root()
-        .join("documents")
-        .join("versioned-health")
-        .join("latest-1.0.0.txt");
-    let v2_file = env
-        .workspace_root()
-        .join("documents")
-        .join("versioned-health")
-        .join("latest-2.0.0.txt");
-    assert!(!v1_file.exists(), "marker file should not exist for v1.0.0");
-    assert!(!v2_file.exists(), "marker file should not exist for v2.0.0");
+/// Test that type replacements are maintained in a sidecar file, and that
+/// replacing a schema that doesn't exist is an error.
+#[test]
+fn test_type_replacements() -> Result<()> {
+    const SIDECAR_PATH: &str = "clients/versioned-health-replace.json";
 
-    // Commit v3.0.0 to make it blessed (while being the latest version).
-    let v3_doc = env
-        .find_versioned_document_path("versioned-health", "3.0.0")?
+    let env = TestEnvironment::new_git()?;
+    let replacements = TypeReplacements::new(SIDECAR_PATH)
+        .with_replacement("ServiceMetrics", "my_types::ServiceMetrics");
+    let apis =
+        versioned_health_with_type_replacements_apis(replacements.clone())?;
+
+    env.generate_documents(&apis)?;
+    assert_eq!(
+        env.read_file(SIDECAR_PATH)?,
+        "{\n  \"ServiceMetrics\": \"my_types::ServiceMetrics\"\n}\n",
+    );
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // A stale sidecar file is fixable.
+    env.create_file(SIDECAR_PATH, "{}\n")?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "3.0.0",
+            ProblemKind::ExtraFileStale,
+        )],
+    );
+    env.generate_documents(&apis)?;
+
+    // HealthStatusV2 doesn't exist in the latest document.
+    let apis = versioned_health_with_type_replacements_apis(
+        replacements.with_replacement("HealthStatusV2", "my_types::Health"),
+    )?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "3.0.0",
+            ProblemKind::GeneratedValidationError,
+        )],
+    );
+
+    Ok(())
+}
+
+/// Test maintaining a copy of the latest document with references inlined.
+#[test]
+fn test_flattened_document() -> Result<()> {
+    const FLATTENED_PATH: &str = "clients/versioned-health-flattened.json";
+
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_with_flattened_document_apis(
+        FlattenedDocument::new(FLATTENED_PATH),
+    )?;
+
+    env.generate_documents(&apis)?;
+    let latest: serde_json::Value = serde_json::from_str(
+        &env.read_versioned_latest_document("versioned-health")?,
+    )?;
+    assert!(
+        latest.to_string().contains("\"$ref\""),
+        "the latest document should have references to flatten",
+    );
+    let flattened: serde_json::Value =
+        serde_json::from_str(&env.read_file(FLATTENED_PATH)?)?;
+    assert!(
+        !flattened.to_string().contains("\"$ref\""),
+        "all references should be inlined: {:#}",
+        flattened,
+    );
+    assert_eq!(flattened["info"], latest["info"]);
+    let path_names = |document: &serde_json::Value| -> Vec<String> {
+        document["paths"]
+            .as_object()
+            .map(|paths| paths.keys().cloned().collect())
+            .unwrap_or_default()
+    };
+    assert_eq!(path_names(&flattened), path_names(&latest));
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // A stale flattened document is fixable.
+    env.create_file(FLATTENED_PATH, "{}\n")?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "3.0.0",
+            ProblemKind::ExtraFileStale,
+        )],
+    );
+    env.generate_documents(&apis)?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
+
+/// Test that summaries and descriptions are linted, except in blessed
+/// documents.
+#[test]
+fn test_text_lint() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    env.generate_documents(&versioned_health_apis()?)?;
+
+    // The fixture's summaries and descriptions pass the default lint.
+    let apis = versioned_health_with_text_lint_apis(
+        TextLint::new().with_sentence_case(),
+    )?;
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &apis)?,
+        CheckResult::Success,
+    );
+
+    // "metrics" only appears in v3.
+    let apis = versioned_health_with_text_lint_apis(
+        TextLint::new().with_misspelling("Metrics", "measurements"),
+    )?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "3.0.0",
+            ProblemKind::GeneratedValidationError,
+        )],
+    );
+
+    // Blessed documents aren't linted.
+    env.commit_documents()?;
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &apis)?,
+        CheckResult::Success,
+    );
+
+    let apis = versioned_health_with_text_lint_apis(
+        TextLint::new().with_max_summary_length(10),
+    )?;
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &apis)?,
+        CheckResult::Success,
+    );
+
+    Ok(())
+}
+
+/// Test that examples can be required for some operations, except in blessed
+/// documents.
+#[test]
+fn test_example_lint() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    env.generate_documents(&versioned_health_apis()?)?;
+
+    // The fixture has no examples, so there's nothing to validate.
+    let apis = versioned_health_with_example_lint_apis(ExampleLint::new())?;
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &apis)?,
+        CheckResult::Success,
+    );
+
+    // The metrics endpoint only exists in v3.
+    let apis = versioned_health_with_example_lint_apis(
+        ExampleLint::new().with_required_for_path_prefix("/metrics"),
+    )?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "3.0.0",
+            ProblemKind::GeneratedValidationError,
+        )],
+    );
+
+    // Blessed documents aren't linted.
+    env.commit_documents()?;
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &apis)?,
+        CheckResult::Success,
+    );
+
+    Ok(())
+}
+
+/// Test that names must follow a naming convention, with exceptions.
+#[test]
+fn test_naming_lint() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    env.generate_documents(&versioned_health_apis()?)?;
+
+    // The fixture's names are all in snake_case.
+    let apis = versioned_health_with_naming_lint_apis(NamingLint::new())?;
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &apis)?,
+        CheckResult::Success,
+    );
+
+    // Every version has an operation ID that isn't in camelCase.
+    let lint = NamingLint::new().with_convention(NamingConvention::CamelCase);
+    let apis = versioned_health_with_naming_lint_apis(lint.clone())?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        [
+            ProblemSummary::new(
+                "versioned-health",
+                "1.0.0",
+                ProblemKind::GeneratedValidationError,
+            ),
+            ProblemSummary::new(
+                "versioned-health",
+                "2.0.0",
+                ProblemKind::GeneratedValidationError,
+            ),
+            ProblemSummary::new(
+                "versioned-health",
+                "3.0.0",
+                ProblemKind::GeneratedValidationError,
+            ),
+        ],
+    );
+
+    // Allow every name that isn't in camelCase.
+    let lint = lint
+        .with_allowed("health_check")
+        .with_allowed("detailed_health_check")
+        .with_allowed("get_metrics")
+        .with_allowed("uptime_seconds")
+        .with_allowed("response_time_ms")
+        .with_allowed("requests_per_second")
+        .with_allowed("error_rate")
+        .with_allowed("avg_response_time_ms")
+        .with_allowed("active_connections")
+        // These are properties of Dropshot's error schema.
+        .with_allowed("error_code")
+        .with_allowed("request_id");
+    let apis = versioned_health_with_naming_lint_apis(lint)?;
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &apis)?,
+        CheckResult::Success,
+    );
+
+    Ok(())
+}
+
+/// Test the metrics written by `check --metrics-out`.
+#[test]
+fn test_check_metrics() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+
+    let (result, metrics) = check_apis_with_metrics(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(metrics["result"], "needs_update");
+    assert_eq!(
+        metrics["documents"],
+        serde_json::json!({ "generated": 3, "local": 0, "blessed": 0 }),
+    );
+    assert_eq!(
+        metrics["problems"],
+        serde_json::json!({
+            "LocalVersionMissingLocal": 3,
+            "LatestLinkMissing": 1,
+        }),
+    );
+    let phases = metrics["phase_duration_seconds"]
+        .as_object()
+        .expect("phase durations are an object");
+    for phase in
+        ["load_generated", "load_local", "load_blessed", "resolve", "total"]
+    {
+        assert!(phases[phase].is_f64(), "duration of {phase} is recorded");
+    }
+
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+    let (result, metrics) = check_apis_with_metrics(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    assert_eq!(metrics["result"], "success");
+    assert_eq!(
+        metrics["documents"],
+        serde_json::json!({ "generated": 3, "local": 3, "blessed": 3 }),
+    );
+    assert_eq!(metrics["problems"], serde_json::json!({}));
+
+    Ok(())
+}
+
+/// Test the HTML report written by `check --report`.
+#[test]
+fn test_check_report() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+
+    // With no documents, every version is stale, and the API's section is
+    // expanded.
+    let (result, report) = check_apis_with_report(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert!(report.starts_with("<!DOCTYPE html>"), "report is HTML");
+    assert!(report.contains("<details open>"), "section is expanded");
+    assert!(report.contains("versioned-health"), "report names the API");
+    assert!(report.contains("Fix: will "), "report describes fixes");
+
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+    let (result, report) = check_apis_with_report(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    assert!(!report.contains("<details open>"), "sections are collapsed");
+    assert!(report.contains("All documents are up-to-date."));
+
+    // An incompatible change to a blessed version is reported with a table
+    // of compatibility issues.
+    let incompatible_apis = versioned_health_incompat_apis()?;
+    let (result, report) =
+        check_apis_with_report(env.environment(), &incompatible_apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert!(report.contains("<details open>"), "section is expanded");
+    assert!(
+        report.contains("<tr><th>Issue</th><th>Diff</th></tr>"),
+        "report has a compatibility issue table: {report}",
+    );
+    assert!(report.contains("<span class=\"add\">"), "report has a diff");
+
+    Ok(())
+}
+
+/// Test that `why-blessed` explains how blessedness was decided.
+#[test]
+fn test_why_blessed() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    let text = why_blessed_text(
+        env.environment(),
+        &apis,
+        "versioned-health",
+        &Version::new(2, 0, 0),
+    )?;
+    assert!(text.starts_with("versioned-health v2.0.0\n"), "{text}");
+    assert!(text.contains("is the default blessed branch"), "{text}");
+    assert!(text.contains("loaded from: merge base "), "{text}");
+    assert!(text.contains("verdict: blessed\n"), "{text}");
+    assert!(text.contains("stored as: JSON\n"), "{text}");
+    assert!(text.contains("first committed upstream: commit "), "{text}");
+
+    let text = why_blessed_text(
+        env.environment(),
+        &apis,
+        "versioned-health",
+        &Version::new(4, 0, 0),
+    )?;
+    assert!(text.contains("v4.0.0 is not a supported version"), "{text}");
+    assert!(text.contains("verdict: not blessed"), "{text}");
+    assert!(
+        text.contains("blessed versions: v1.0.0, v2.0.0, v3.0.0\n"),
+        "{text}"
+    );
+
+    let error = why_blessed_text(
+        env.environment(),
+        &apis,
+        "unknown",
+        &Version::new(1, 0, 0),
+    )
+    .unwrap_err();
+    assert_eq!(error.to_string(), "unknown API \"unknown\"");
+
+    Ok(())
+}
+
+/// Test packing blessed documents into a baseline, and checking against it.
+#[test]
+fn test_baseline() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    let temp_dir = Utf8TempDir::with_prefix("dropshot-api-manager-baseline-")?;
+
+    // Nothing is blessed yet.
+    let empty_path = temp_dir.path().join("empty.tar.zst");
+    let count = pack_blessed_baseline(env.environment(), &apis, &empty_path)?;
+    assert_eq!(count, 0);
+
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    let path = temp_dir.path().join("blessed-baseline.tar.zst");
+    let count = pack_blessed_baseline(env.environment(), &apis, &path)?;
+    assert_eq!(count, 3);
+
+    // Packing is reproducible.
+    let repacked_path = temp_dir.path().join("repacked.tar.zst");
+    pack_blessed_baseline(env.environment(), &apis, &repacked_path)?;
+    assert_eq!(std::fs::read(&path)?, std::fs::read(&repacked_path)?);
+
+    let (result, summaries) =
+        check_apis_with_baseline(env.environment(), &apis, &path)?;
+    assert_eq!(result, CheckResult::Success);
+    assert_eq!(summaries, []);
+
+    // Blessed documents come from the baseline: against it, incompatible
+    // changes are caught just as they would be with VCS...
+    let incompatible_apis = versioned_health_incompat_apis()?;
+    let (result, summaries) =
+        check_apis_with_baseline(env.environment(), &incompatible_apis, &path)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "3.0.0",
+            ProblemKind::BlessedVersionBroken,
+        )],
+    );
+
+    // ...while against the empty baseline, no version is blessed, so the
+    // changed version only needs to be regenerated.
+    let (result, _summaries) = check_apis_with_baseline(
+        env.environment(),
+        &incompatible_apis,
+        &empty_path,
+    )?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+
+    // Files that aren't baselines are rejected.
+    let bogus_path = temp_dir.path().join("bogus.tar.zst");
+    atomic_write(&bogus_path, "not a baseline")?;
+    let error = check_apis_with_baseline(env.environment(), &apis, &bogus_path)
+        .unwrap_err();
+    assert!(format!("{error:#}").contains("loading baseline"), "{error:#}");
+
+    Ok(())
+}
+
+/// Test reading only declared inputs, and writing outputs under an output
+/// root, as a hermetic build action would.
+#[test]
+fn test_hermetic_inputs_and_outputs() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis =
+        versioned_health_with_latest_pointer_apis(LatestPointer::PointerFile)?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    // The documents, relative to the documents directory.
+    let mut documents: Vec<_> = env
+        .list_document_files()?
+        .into_iter()
+        .map(|path| {
+            env.workspace_root()
+                .join(path)
+                .strip_prefix(env.documents_dir())
+                .unwrap()
+                .to_owned()
+        })
+        .collect();
+    documents.sort();
+
+    let temp_dir = Utf8TempDir::with_prefix("dropshot-api-manager-hermetic-")?;
+
+    // With every document declared, the check passes.
+    let all_inputs = temp_dir.path().join("all.txt");
+    write_input_list(&all_inputs, env.documents_dir(), &documents)?;
+    let exit_code = env
+        .check_documents_with_args(&apis, &["--inputs", all_inputs.as_str()])?;
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+
+    // Documents that aren't declared aren't read.
+    let partial_inputs = temp_dir.path().join("partial.txt");
+    let partial: Vec<_> = documents
+        .iter()
+        .filter(|document| !document.as_str().contains("3.0.0"))
+        .cloned()
+        .collect();
+    write_input_list(&partial_inputs, env.documents_dir(), &partial)?;
+    let exit_code = env.check_documents_with_args(
+        &apis,
+        &["--inputs", partial_inputs.as_str()],
+    )?;
+    assert_eq!(exit_code, ExitCode::from(NEEDS_UPDATE_EXIT_CODE));
+
+    // Generating writes the complete documents directory under the output
+    // root, without touching the documents directory itself.
+    let output_root = temp_dir.path().join("out");
+    env.generate_documents_with_args(
+        &apis,
+        &[
+            "--inputs",
+            partial_inputs.as_str(),
+            "--output-root",
+            output_root.as_str(),
+        ],
+    )?;
+    for document in &documents {
+        assert_eq!(
+            std::fs::read(output_root.join(document))?,
+            std::fs::read(env.documents_dir().join(document))?,
+            "{document}",
+        );
+    }
+
+    // Listing outputs reports exactly those files.
+    let outputs = list_generate_outputs_with_inputs(
+        env.environment(),
+        &apis,
+        &partial_inputs,
+    )?;
+    assert_eq!(outputs, documents);
+
+    // The output root must start out empty.
+    env.generate_documents_with_args(
+        &apis,
+        &[
+            "--inputs",
+            partial_inputs.as_str(),
+            "--output-root",
+            output_root.as_str(),
+        ],
+    )
+    .unwrap_err();
+
+    // APIs with "latest" symlinks are rejected unless symlinks are allowed.
+    let symlink_apis = versioned_health_apis()?;
+    let error = list_generate_outputs_with_inputs(
+        env.environment(),
+        &symlink_apis,
+        &all_inputs,
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("--allow-symlinks"), "{error}");
+
+    Ok(())
+}
+
+/// Writes a list of inputs for `--inputs`, with the absolute paths of
+/// `documents` (relative to `documents_dir`).
+fn write_input_list(
+    path: &Utf8Path,
+    documents_dir: &Utf8Path,
+    documents: &[Utf8PathBuf],
+) -> Result<()> {
+    let contents: String = documents
+        .iter()
+        .map(|document| format!("{}\n", documents_dir.join(document)))
+        .collect();
+    atomic_write(path, contents)
+}
+
+/// Test reporting versions without recent traffic.
+#[test]
+fn test_audit_unused_versions() -> Result<()> {
+    let apis = versioned_health_apis()?;
+    let temp_dir = Utf8TempDir::with_prefix("dropshot-api-manager-audit-")?;
+    let now = chrono::Utc::now();
+    let recent = (now - chrono::Duration::days(3)).to_rfc3339();
+    let old =
+        (now - chrono::Duration::days(200)).format("%Y-%m-%d").to_string();
+
+    let csv_path = temp_dir.path().join("observations.csv");
+    atomic_write(
+        &csv_path,
+        format!(
+            "api,version,last_seen\n\
+             versioned-health,2.0.0,{old}\n\
+             versioned-health,2.0.0,{recent}\n\
+             versioned-health,1.0.0,{old}\n\
+             versioned-health,0.9.0,{old}\n\
+             unknown-api,1.0.0,{recent}\n"
+        ),
+    )?;
+    let text = audit_unused_versions_text(&apis, &csv_path, 90)?;
+    assert_eq!(
+        text,
+        format!(
+            "versioned-health\n  \
+             v3.0.0: never seen, latest (can't be retired)\n  \
+             v2.0.0: last seen {} (3 days ago), in use\n  \
+             v1.0.0: last seen {old} (200 days ago), unused (could be \
+             retired)\n  \
+             v0.9.0: last seen {old} (200 days ago), not supported\n\
+             1 of 3 supported versions have seen no traffic in 90 days and \
+             could be retired\n",
+            &recent[..10],
+        ),
+    );
+
+    // With a longer window, every version is in use.
+    let text = audit_unused_versions_text(&apis, &csv_path, 365)?;
+    assert!(
+        text.ends_with(
+            "0 of 3 supported versions have seen no traffic in 365 \
+             days and could be retired\n"
+        ),
+        "{text}"
+    );
+
+    // JSON observations work the same way.
+    let json_path = temp_dir.path().join("observations.json");
+    let observations = serde_json::json!([
+        { "api": "versioned-health", "version": "2.0.0", "last_seen": recent },
+        { "api": "versioned-health", "version": "1.0.0", "last_seen": old },
+    ]);
+    atomic_write(&json_path, observations.to_string())?;
+    let text = audit_unused_versions_text(&apis, &json_path, 90)?;
+    assert!(
+        text.ends_with(
+            "1 of 3 supported versions have seen no traffic in 90 \
+             days and could be retired\n"
+        ),
+        "{text}"
+    );
+
+    // Malformed observations are rejected.
+    let bad_path = temp_dir.path().join("bad.csv");
+    atomic_write(&bad_path, "api,version\nversioned-health,1.0.0\n")?;
+    let error = audit_unused_versions_text(&apis, &bad_path, 90).unwrap_err();
+    assert!(
+        format!("{error:#}").contains("missing column \"last_seen\""),
+        "{error:#}"
+    );
+
+    Ok(())
+}
+
+/// Test that renaming an operation between consecutive versions is reported,
+/// unless the rename is marked as intended.
+#[test]
+fn test_operation_id_renamed() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_renamed_operation_apis(false)?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert!(
+        summaries.contains(&ProblemSummary::new(
+            "versioned-health",
+            "2.0.0",
+            ProblemKind::OperationIdChanged,
+        )),
+        "{summaries:?}"
+    );
+    // The problem can't be fixed by generating documents.
+    env.generate_documents(&apis).unwrap_err();
+
+    let apis = versioned_health_renamed_operation_apis(true)?;
+    env.generate_documents(&apis)?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
+
+/// Test that endpoints whose version ranges don't line up with the supported
+/// versions produce warnings.
+#[test]
+fn test_version_range_warnings() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_bad_ranges_apis()?;
+    env.generate_documents(&apis)?;
+
+    let (result, load_problems) =
+        check_apis_with_load_problems(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    let warnings: Vec<_> = load_problems
+        .iter()
+        .map(|(severity, error)| {
+            assert_eq!(*severity, LoadProblemSeverity::Warning);
+            format!("{error:#}")
+        })
+        .collect();
+    assert_eq!(warnings.len(), 2, "two warnings: {warnings:?}");
+    assert!(
+        warnings[0]
+            .contains("endpoint GET /details isn't in any supported version")
+            && warnings[0].contains("first appear in version 4.0.0 or later"),
+        "unexpected warning: {}",
+        warnings[0],
+    );
+    assert!(
+        warnings[1].contains(
+            "endpoint GET /legacy is removed in a version after 2.0.0 and no \
+             later than 3.0.0"
+        ),
+        "unexpected warning: {}",
+        warnings[1],
+    );
+
+    Ok(())
+}
+
+/// Test that endpoint version ranges referring to retired versions produce
+/// cleanup suggestions.
+#[test]
+fn test_retired_version_range_warnings() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_retired_ranges_apis()?;
+    env.generate_documents(&apis)?;
+
+    let (result, load_problems) =
+        check_apis_with_load_problems(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    let warnings: Vec<_> = load_problems
+        .iter()
+        .map(|(severity, error)| {
+            assert_eq!(*severity, LoadProblemSeverity::Warning);
+            format!("{error:#}")
+        })
+        .collect();
+    // GET /details starts at the oldest supported version, so it's fine.
+    assert_eq!(warnings.len(), 2, "two warnings: {warnings:?}");
+    assert!(
+        warnings[0].contains(
+            "endpoint GET /health is added in a retired version (after 0.0.0 \
+             and no later than 1.0.0)"
+        ) && warnings[0].contains("can start at the oldest supported version"),
+        "unexpected warning: {}",
+        warnings[0],
+    );
+    assert!(
+        warnings[1].contains(
+            "endpoint GET /legacy is only in retired versions (the last is \
+             1.0.0"
+        ) && warnings[1].contains("so it can be removed"),
+        "unexpected warning: {}",
+        warnings[1],
+    );
+
+    Ok(())
+}
+
+/// Test that problems caused by an in-progress merge come with a plan for
+/// resolving them.
+#[test]
+fn test_merge_guidance() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+    env.generate_documents(&versioned_health_reduced_apis()?)?;
+    env.commit_documents()?;
+    env.create_branch("branch")?;
+
+    // Upstream adds v3.
+    env.generate_documents(&versioned_health_apis()?)?;
+    env.commit_documents()?;
+
+    // This branch adds a different v3.
+    env.checkout_branch("branch")?;
+    let branch_apis = versioned_health_v3_alternate_apis(Storage::Concrete)?;
+    env.generate_documents(&branch_apis)?;
+    env.commit_documents()?;
+
+    // Without a merge in progress, there's no plan.
+    let text = status_text(env.environment(), &branch_apis)?;
+    assert!(!text.contains("is in progress"), "{text}");
+
+    let MergeResult::Conflict(_) = env.try_merge_branch("main")? else {
+        panic!("merge should conflict on the \"latest\" symlink");
+    };
+    let text = status_text(env.environment(), &branch_apis)?;
+    assert!(text.contains("A merge is in progress"), "{text}");
+    assert!(
+        text.contains(
+            "Upstream's version 3.0.0 of versioned-health doesn't match the \
+             code in this branch"
+        ),
+        "{text}"
+    );
+    assert!(
+        text.contains(
+            "renumber the versions added in this branch to start at 4.0.0"
+        ),
+        "{text}"
+    );
+    assert!(text.contains("run `git merge --continue`"), "{text}");
+
+    // Following the plan resolves the merge.
+    let renumbered_apis =
+        versioned_health_v1_v2_v3_v4alt_apis(Storage::Concrete)?;
+    env.generate_documents(&renumbered_apis)?;
+    env.complete_merge()?;
+    let result = check_apis_up_to_date(env.environment(), &renumbered_apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
+
+/// Test computing the next available version number of an API.
+#[test]
+fn test_next_version() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    let ident = ApiIdent::from("versioned-health");
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+    assert_eq!(
+        apis.next_version(env.environment(), &ident)?.0,
+        Version::new(4, 0, 0)
+    );
+
+    // A local document for an unsupported version, as left by another branch
+    // that's in flight, takes its version number.
+    env.generate_documents(&versioned_health_with_v4_apis()?)?;
+    assert_eq!(
+        apis.next_version(env.environment(), &ident)?.0,
+        Version::new(5, 0, 0)
+    );
+
+    // Lockstep and unknown APIs have no next version.
+    let error = lockstep_health_apis()?
+        .next_version(env.environment(), &ApiIdent::from("health"))
+        .unwrap_err();
+    assert!(format!("{error:#}").contains("lockstep API"), "{error:#}");
+    let error = apis
+        .next_version(env.environment(), &ApiIdent::from("nonexistent"))
+        .unwrap_err();
+    assert!(format!("{error:#}").contains("unknown API"), "{error:#}");
+
+    Ok(())
+}
+
+/// Test that a blessed cache shared across commands gives the same results as
+/// running without one, including after a commit moves the merge base.
+#[test]
+fn test_blessed_cache() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+    env.set_environment(|e| e.with_blessed_cache(BlessedCache::new()));
+    let v1_v2_apis = versioned_health_reduced_apis()?;
+    let v1_v2_v3_apis = versioned_health_apis()?;
+
+    env.generate_documents(&v1_v2_apis)?;
+    env.commit_documents()?;
+    let result = check_apis_up_to_date(env.environment(), &v1_v2_apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // v3 is only present locally, so it can be regenerated.
+    let result = check_apis_up_to_date(env.environment(), &v1_v2_v3_apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    env.generate_documents(&v1_v2_v3_apis)?;
+    let result = check_apis_up_to_date(env.environment(), &v1_v2_v3_apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Once v3 is committed, it's blessed. If the cache returned the old merge
+    // base, a different v3 would still be treated as a local change.
+    env.commit_documents()?;
+    let v3_alt_apis = versioned_health_v3_alternate_apis(Storage::Concrete)?;
+    let result = check_apis_up_to_date(env.environment(), &v3_alt_apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    let result = check_apis_up_to_date(env.environment(), &v1_v2_v3_apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
+
+/// Test that blessed documents changed in the working copy are read from
+/// history, even though unchanged ones are read from disk.
+#[test]
+fn test_blessed_changed_in_working_copy() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+    let v1_path = env
+        .find_versioned_document_path("versioned-health", "1.0.0")?
+        .expect("v1 should exist");
+    let v1_contents = env.read_file(&v1_path)?;
+
+    // If the blessed document were read from disk, it would fail to parse.
+    env.create_file(&v1_path, "<<<<<<< HEAD\n=======\n>>>>>>> branch\n")?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "1.0.0",
+            ProblemKind::BlessedVersionCorruptedLocal,
+        )],
+    );
+
+    env.generate_documents(&apis)?;
+    assert_eq!(env.read_file(&v1_path)?, v1_contents);
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
+
+/// Test that changes to a frozen latest document are rejected unless the
+/// freeze is overridden.
+#[test]
+fn test_document_freeze() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    env.generate_documents(&versioned_health_reduced_apis()?)?;
+    env.commit_documents()?;
+
+    // A freeze that isn't in effect doesn't prevent adding a version.
+    let start = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+    let end = NaiveDate::from_ymd_opt(2000, 12, 31).unwrap();
+    let apis = versioned_health_frozen_apis(DocumentFreeze::between(
+        "v2000", "releng", start, end,
+    ))?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+
+    // Once the marker file exists, adding v3 is an error.
+    let apis = versioned_health_frozen_apis(
+        DocumentFreeze::while_file_exists("r42", "releng", "RELEASE_FREEZE"),
+    )?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    env.create_file("RELEASE_FREEZE", "")?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        [
+            ProblemSummary::new(
+                "versioned-health",
+                "3.0.0",
+                ProblemKind::LocalVersionMissingLocal,
+            ),
+            ProblemSummary::new(
+                "versioned-health",
+                "3.0.0",
+                ProblemKind::LatestDocumentFrozen,
+            ),
+            ProblemSummary::for_api(
+                "versioned-health",
+                ProblemKind::LatestLinkStale,
+            ),
+        ],
+    );
+    assert!(env.generate_documents(&apis).is_err());
+
+    // With --override-freeze, the version can be added. After that, the
+    // document is unchanged, so the freeze doesn't apply.
+    env.generate_documents_with_args(&apis, &["--override-freeze"])?;
+    let app = App::try_parse_from(["bin", "check", "--override-freeze"])?;
+    assert_eq!(app.exec(env.environment(), &apis), ExitCode::SUCCESS);
+    env.commit_documents()?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
+
+/// Test maintaining a summary of an API's deprecated operations.
+#[test]
+fn test_deprecation_summary() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let path = "clients/versioned-health-deprecations.json";
+    let apis = versioned_health_deprecation_summary_apis(path)?;
+
+    // The summary is missing until it's generated.
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert!(summaries.contains(&ProblemSummary::new(
+        "versioned-health",
+        "3.0.0",
+        ProblemKind::ExtraFileStale,
+    )));
+    env.generate_documents(&apis)?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // The summary lists every supported version. None of the operations in
+    // the versioned health API are deprecated.
+    let summary: serde_json::Value =
+        serde_json::from_str(&env.read_file(path)?)?;
+    assert_eq!(summary["api"], "versioned-health");
+    let versions: Vec<_> = summary["versions"]
+        .as_array()
+        .context("versions is an array")?
+        .iter()
+        .map(|v| {
+            assert_eq!(v["deprecated_operations"], serde_json::json!([]));
+            v["version"].as_str().unwrap().to_owned()
+        })
+        .collect();
+    assert_eq!(versions, ["1.0.0", "2.0.0", "3.0.0"]);
+
+    // Edits to the summary are detected and undone.
+    env.create_file(path, "{}\n")?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    env.generate_documents(&apis)?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
+
+/// Test storing the schemas of a versioned API in a shared schema store.
+#[test]
+fn test_shared_schemas() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_shared_schemas_apis()?;
+    let store = env.documents_dir().join("versioned-health/shared-schemas");
+
+    env.generate_documents(&apis)?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Schemas are moved out of each document and into the store.
+    let mut fragments: Vec<_> = std::fs::read_dir(&store)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    fragments.sort();
+    assert!(!fragments.is_empty(), "store has schema fragments");
+    for version in ["1.0.0", "2.0.0", "3.0.0"] {
+        let document =
+            env.read_versioned_document("versioned-health", version)?;
+        assert!(
+            document.contains("\"$ref\": \"shared-schemas/"),
+            "v{} refers to the store",
+            version,
+        );
+    }
+
+    // Blessed documents are reassembled from the store as well.
+    env.commit_documents()?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // A missing fragment is detected and restored.
+    std::fs::remove_file(&fragments[0])?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_ne!(result, CheckResult::Success);
+    env.generate_documents(&apis)?;
+    assert!(fragments[0].exists(), "fragment was restored");
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Turning the store off inlines the schemas again.
+    let apis = versioned_health_apis()?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    env.generate_documents(&apis)?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    assert!(!store.exists(), "store was removed");
+    let document = env.read_versioned_document("versioned-health", "1.0.0")?;
+    assert!(!document.contains("shared-schemas/"));
+
+    Ok(())
+}
+
+/// Test formatting an API's documents differently from Dropshot.
+#[test]
+fn test_json_format() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let spaces = JsonFormat::new().with_indent(JsonIndent::Spaces(4));
+    let apis = versioned_health_json_format_apis(spaces)?;
+
+    env.generate_documents(&apis)?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    for version in ["1.0.0", "2.0.0", "3.0.0"] {
+        let document =
+            env.read_versioned_document("versioned-health", version)?;
+        assert!(document.starts_with("{\n    \"openapi\""), "v{}", version);
+    }
+
+    // Blessed documents in the custom format match the generated ones.
+    env.commit_documents()?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Changing the format doesn't require a new version: the documents are
+    // just reformatted.
+    let tabs =
+        JsonFormat::new().with_indent(JsonIndent::Tab).with_max_width(80);
+    let apis = versioned_health_json_format_apis(tabs)?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::for_api(
+            "versioned-health",
+            ProblemKind::JsonFormatStale,
+        )],
+    );
+    env.generate_documents(&apis)?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    let document = env.read_versioned_document("versioned-health", "3.0.0")?;
+    assert!(document.starts_with("{\n\t\"openapi\""));
+
+    // Going back to Dropshot's formatting, the format can be removed once
+    // the blessed documents are in Dropshot's formatting too.
+    let apis = versioned_health_json_format_apis(JsonFormat::default())?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+    let apis = versioned_health_apis()?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    let document = env.read_versioned_document("versioned-health", "3.0.0")?;
+    assert!(document.starts_with("{\n  \"openapi\""));
+
+    Ok(())
+}
+
+/// Test approving incompatible changes to a blessed version.
+#[test]
+fn test_breaking_change_approvals() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let approvals = BreakingChangeApprovals::new("openapi-approvals.json");
+    let apis = versioned_health_apis()?
+        .with_breaking_change_approvals(approvals.clone());
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    // Without approvals, breaking a blessed version is an error.
+    let incompat_apis = versioned_health_incompat_apis()?
+        .with_breaking_change_approvals(approvals.clone());
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &incompat_apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "3.0.0",
+            ProblemKind::BlessedVersionBroken,
+        )],
+    );
+
+    // Approving each issue suppresses the problem.
+    let hashes = blessed_version_issue_hashes(
+        env.environment(),
+        &incompat_apis,
+        "versioned-health",
+        "3.0.0",
+    )?;
+    assert!(!hashes.is_empty());
+    let entries: Vec<_> = hashes
+        .iter()
+        .map(|hash| {
+            serde_json::json!({
+                "api": "versioned-health",
+                "version": "3.0.0",
+                "issue": hash,
+                "approver": "api-owners",
+                "justification": "The new endpoint is needed by all servers.",
+            })
+        })
+        .collect();
+    env.create_file(
+        "openapi-approvals.json",
+        &serde_json::json!({ "approvals": entries }).to_string(),
+    )?;
+    let result = check_apis_up_to_date(env.environment(), &incompat_apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Once the change is reverted, the approvals are stale, and generate
+    // removes them.
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        vec![
+            ProblemSummary::new(
+                "versioned-health",
+                "3.0.0",
+                ProblemKind::BreakingChangeApprovalStale,
+            );
+            hashes.len()
+        ],
+    );
+    env.generate_documents(&apis)?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    let file: serde_json::Value =
+        serde_json::from_str(&env.read_file("openapi-approvals.json")?)?;
+    assert_eq!(file, serde_json::json!({ "approvals": [] }));
+
+    // The approval file is validated.
+    env.create_file(
+        "openapi-approvals.json",
+        r#"{ "approvals": [{ "api": "versioned-health" }] }"#,
+    )?;
+    let error = check_apis_up_to_date(env.environment(), &apis).unwrap_err();
+    assert!(
+        format!("{:#}", error).contains("bailing out"),
+        "error: {:#}",
+        error,
+    );
+
+    Ok(())
+}
+
+/// Test checking and listing the versions that consumers are pinned to.
+#[test]
+fn test_consumer_pins() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = || -> Result<ManagedApis> {
+        Ok(versioned_health_apis()?.with_consumer_pins(
+            ConsumerPins::new(["clients/a/pins.toml", "clients/b/pins.toml"])
+                .with_max_lag(1),
+        ))
+    };
+    env.create_file(
+        "clients/a/pins.toml",
+        "[pins]\nversioned-health = \"1.0.0\"\n",
+    )?;
+    env.create_file(
+        "clients/b/pins.toml",
+        "[pins]\nversioned-health = \"3.0.0\"\n",
+    )?;
+    env.generate_documents(&apis()?)?;
+
+    // A pin that lags behind is reported, but isn't an error.
+    let result = check_apis_up_to_date(env.environment(), &apis()?)?;
+    assert_eq!(result, CheckResult::Success);
+    let text = status_text(env.environment(), &apis()?)?;
+    assert!(
+        text.contains(
+            "is pinned by clients/a/pins.toml, 2 versions behind latest \
+             version 3.0.0"
+        ),
+        "{text}"
+    );
+    assert_eq!(
+        pins_text(env.environment(), &apis()?, Some("versioned-health"))?,
+        "versioned-health\n  \
+           v3.0.0 (latest): clients/b/pins.toml\n  \
+           v2.0.0: no pins\n  \
+           v1.0.0: clients/a/pins.toml\n",
+    );
+
+    // A pin of a version that isn't supported is an error.
+    env.create_file(
+        "clients/a/pins.toml",
+        "[pins]\nversioned-health = \"0.5.0\"\n",
+    )?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis()?)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "0.5.0",
+            ProblemKind::ConsumerPinUnsupported,
+        )],
+    );
+    let text = pins_text(env.environment(), &apis()?, None)?;
+    assert!(
+        text.contains("v0.5.0 (not supported): clients/a/pins.toml"),
+        "{text}"
+    );
+
+    // Pins must name versioned APIs.
+    env.create_file("clients/a/pins.toml", "[pins]\nunknown = \"1.0.0\"\n")?;
+    let error = check_apis_up_to_date(env.environment(), &apis()?).unwrap_err();
+    assert!(
+        format!("{:#}", error).contains("bailing out"),
+        "error: {:#}",
+        error,
+    );
+    assert!(pins_text(env.environment(), &apis()?, None).is_err());
+
+    Ok(())
+}
+
+/// Test listing the versions introduced since a release.
+#[test]
+fn test_versions_since_release() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let v1_v2_apis = versioned_health_reduced_apis()?;
+    let v1_v2_v3_apis = versioned_health_apis()?;
+    env.generate_documents(&v1_v2_apis)?;
+    env.commit_documents()?;
+    env.create_branch("release-1")?;
+
+    assert_eq!(
+        versions_since_release_text(
+            env.environment(),
+            &v1_v2_apis,
+            "release-1"
+        )?,
+        "",
+    );
+
+    // Versions count as new whether or not they've been committed since.
+    env.generate_documents(&v1_v2_v3_apis)?;
+    let expected = "versioned-health v3.0.0 (WITH_METRICS)\n";
+    assert_eq!(
+        versions_since_release_text(
+            env.environment(),
+            &v1_v2_v3_apis,
+            "release-1"
+        )?,
+        expected,
+    );
+    env.commit_documents()?;
+    assert_eq!(
+        versions_since_release_text(
+            env.environment(),
+            &v1_v2_v3_apis,
+            "release-1"
+        )?,
+        expected,
+    );
+
+    // Listing versions doesn't affect the result of the check.
+    assert_eq!(
+        env.check_documents_with_args(
+            &v1_v2_v3_apis,
+            &["--since-last-release", "release-1"],
+        )?,
+        ExitCode::SUCCESS,
+    );
+
+    Ok(())
+}
+
+/// Test that environments with namespaces only see and own their own
+/// subdirectory of a shared documents directory.
+#[test]
+fn test_namespaces() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+    let base = env.environment().clone();
+    let service_a = base.clone().with_namespace("service-a")?;
+    let service_b = base.with_namespace("service-b")?;
+    let versioned_apis = versioned_health_apis()?;
+    let lockstep_apis = lockstep_health_apis()?;
+
+    // A file for an unknown API in another namespace is neither reported nor
+    // touched.
+    env.create_file("documents/service-b/unknown-api.json", "{}")?;
+    env.set_environment(|_| service_a.clone());
+    env.generate_documents(&versioned_apis)?;
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &versioned_apis)?,
+        CheckResult::Success,
+    );
+    assert_eq!(env.read_file("documents/service-b/unknown-api.json")?, "{}");
+    env.commit_documents()?;
+
+    // Each namespace's documents are kept in its own subdirectory. (Within
+    // its own namespace, service B would report the unknown file.)
+    std::fs::remove_file(
+        env.workspace_root().join("documents/service-b/unknown-api.json"),
+    )?;
+    env.set_environment(|_| service_b.clone());
+    env.generate_documents(&lockstep_apis)?;
+    env.commit_documents()?;
+    for file in env.list_document_files()? {
+        let file = file.as_str().replace('\\', "/");
+        assert!(
+            file.starts_with("documents/service-a/versioned-health/")
+                || file == "documents/service-b/health.json",
+            "unexpected file: {}",
+            file,
+        );
+    }
+
+    // Blessed documents are loaded from the namespace too.
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &lockstep_apis)?,
+        CheckResult::Success,
+    );
+    env.set_environment(|_| service_a.clone());
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &versioned_apis)?,
+        CheckResult::Success,
+    );
+
+    Ok(())
+}
+
+/// Test logging git commands with `--trace-git`.
+#[test]
+fn test_trace_git() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    // Blessed documents are loaded from git, so checking runs git commands.
+    let trace_path = env.workspace_root().join("git-trace.log");
+    assert_eq!(
+        env.check_documents_with_args(
+            &apis,
+            &[&format!("--trace-git={}", trace_path)],
+        )?,
+        ExitCode::SUCCESS,
+    );
+
+    let trace = std::fs::read_to_string(&trace_path)?;
+    let lines: Vec<_> = trace.lines().collect();
+    assert!(
+        lines.iter().any(|line| line.starts_with("git trace: ")
+            && line.contains("\"merge-base\"")
+            && line.contains("exit status: 0")),
+        "expected a merge-base command in trace:\n{trace}"
+    );
+    let summary = lines.last().expect("trace isn't empty");
+    assert!(
+        summary.starts_with("git trace: ") && summary.contains(" commands in "),
+        "expected a summary at the end of the trace:\n{trace}"
+    );
+
+    Ok(())
+}
+
+/// A [`GitRunner`] that records the arguments of each command before running
+/// it as usual.
+#[derive(Debug, Default)]
+struct RecordingGitRunner {
+    commands: Arc<Mutex<Vec<String>>>,
+}
+
+impl GitRunner for RecordingGitRunner {
+    fn output(&self, command: &mut Command) -> io::Result<Output> {
+        let args: Vec<_> =
+            command.get_args().map(|a| a.to_string_lossy()).collect();
+        self.commands.lock().unwrap().push(args.join(" "));
+        command.output()
+    }
+}
+
+/// A [`GitRunner`] that fails to run any command, as if git weren't
+/// installed.
+#[derive(Debug)]
+struct MissingGitRunner;
+
+impl GitRunner for MissingGitRunner {
+    fn output(&self, _command: &mut Command) -> io::Result<Output> {
+        Err(io::Error::new(io::ErrorKind::NotFound, "git is not installed"))
+    }
+}
+
+/// Test running git commands through an injected runner.
+#[test]
+fn test_git_runner() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    // Blessed documents are loaded through the runner.
+    let runner = RecordingGitRunner::default();
+    let commands = runner.commands.clone();
+    let recording_env = env.environment().clone().with_git_runner(runner);
+    let result = check_apis_up_to_date(&recording_env, &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    let commands = commands.lock().unwrap();
+    assert!(
+        commands.iter().any(|c| c.starts_with("merge-base ")),
+        "expected a merge-base command, found: {commands:?}"
+    );
+
+    // If the runner can't run git, blessed documents can't be loaded.
+    let missing_env =
+        env.environment().clone().with_git_runner(MissingGitRunner);
+    let result = check_apis_up_to_date(&missing_env, &apis);
+    assert!(
+        !matches!(result, Ok(CheckResult::Success)),
+        "check unexpectedly succeeded without git"
+    );
+
+    Ok(())
+}
+
+/// Test recording checked documents in a Git note, and verifying a tree
+/// against it.
+#[test]
+fn test_check_notes() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+
+    // Documents with uncommitted changes can't be recorded.
+    assert_eq!(
+        env.check_documents_with_args(&apis, &["--write-note"])?,
+        ExitCode::FAILURE,
+    );
+    env.commit_documents()?;
+
+    // Before a note is written, there's nothing to verify against.
+    let verify = || -> Result<ExitCode> {
+        let app = App::try_parse_from(["bin", "verify-notes"])?;
+        Ok(app.exec(env.environment(), &apis))
+    };
+    assert_eq!(verify()?, ExitCode::from(FAILURE_EXIT_CODE));
+
+    assert_eq!(
+        env.check_documents_with_args(&apis, &["--write-note"])?,
+        ExitCode::SUCCESS,
+    );
+    assert_eq!(verify()?, ExitCode::SUCCESS);
+
+    // Changing a document in the working copy makes verification fail.
+    let path = env
+        .find_versioned_document_path("versioned-health", "1.0.0")?
+        .expect("document for 1.0.0 exists");
+    let contents = env.read_file(&path)?;
+    env.create_file(&path, &contents.replacen('{', "{\n", 1))?;
+    assert_eq!(verify()?, ExitCode::from(FAILURE_EXIT_CODE));
+
+    Ok(())
+}
+
+/// Test applying fixes through the library API, including stopping early.
+#[test]
+fn test_apply_fixes() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+
+    // Stop after the first fix: the rest of the documents are left alone.
+    let mut descriptions = Vec::new();
+    let flow = apply_fixes(env.environment(), &apis, |fix, result| {
+        assert!(result.is_ok(), "fix {} failed: {:?}", fix, result);
+        descriptions.push(fix.to_string());
+        ControlFlow::Break(())
+    })?;
+    assert_eq!(flow, ControlFlow::Break(()));
+    assert_eq!(descriptions.len(), 1, "descriptions: {:?}", descriptions);
+    assert!(
+        descriptions[0].starts_with("write new file versioned-health/"),
+        "description: {}",
+        descriptions[0],
+    );
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+
+    // Apply the remaining fixes, observing each step.
+    let mut steps = Vec::new();
+    let flow = apply_fixes(env.environment(), &apis, |fix, result| {
+        match result {
+            Ok(s) => steps.extend(s.iter().cloned()),
+            Err(error) => panic!("fix {} failed: {:#}", fix, error),
+        }
+        ControlFlow::Continue(())
+    })?;
+    assert_eq!(flow, ControlFlow::Continue(()));
+    assert!(!steps.is_empty());
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // With everything up-to-date, there's nothing left to do.
+    let mut nfixes = 0;
+    let flow = apply_fixes(env.environment(), &apis, |_, _| {
+        nfixes += 1;
+        ControlFlow::Continue(())
+    })?;
+    assert_eq!(flow, ControlFlow::Continue(()));
+    assert_eq!(nfixes, 0);
+
+    Ok(())
+}
+
+/// Test scaffolding a new version of an API.
+#[test]
+fn test_new_version() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    let api_file = env.workspace_root().join("api/src/lib.rs");
+    let versions_dir = env.workspace_root().join("versions/src");
+    std::fs::create_dir_all(api_file.parent().unwrap())?;
+    atomic_write(
+        &api_file,
+        "api_versions!([\n    \
+         (3, WITH_METRICS),\n    \
+         (2, WITH_DETAILED_STATUS),\n    \
+         (1, INITIAL),\n\
+         ]);\n",
+    )?;
+
+    let text = new_version_text(
+        env.environment(),
+        &apis,
+        "versioned-health",
+        "ADD_WIDGETS",
+        Some(&api_file),
+        Some(&versions_dir),
+    )?;
+    assert!(
+        text.starts_with(
+            "Adding version 4.0.0 (ADD_WIDGETS) to versioned-health (latest \
+             is 3.0.0, WITH_METRICS)\n"
+        ),
+        "{text}"
+    );
+    assert!(text.contains("pub mod v4;"), "{text}");
+    assert!(text.contains("GET /metrics (get_metrics)"), "{text}");
+
+    // The new version is added to the top of the list, matching its layout.
+    assert_eq!(
+        std::fs::read_to_string(&api_file)?,
+        "api_versions!([\n    \
+         (4, ADD_WIDGETS),\n    \
+         (3, WITH_METRICS),\n    \
+         (2, WITH_DETAILED_STATUS),\n    \
+         (1, INITIAL),\n\
+         ]);\n",
+    );
+    let module =
+        std::fs::read_to_string(versions_dir.join("add_widgets/mod.rs"))?;
+    assert!(module.starts_with("//! Version `ADD_WIDGETS` of "), "{module}");
+
+    // The API's versions come from code, so running the command again picks
+    // the same version, whose module now exists.
+    let error = new_version_text(
+        env.environment(),
+        &apis,
+        "versioned-health",
+        "ADD_WIDGETS",
+        None,
+        Some(&versions_dir),
+    )
+    .unwrap_err();
+    assert!(format!("{error:#}").contains("already exists"), "{error:#}");
+
+    // A list that doesn't start with the latest version is rejected.
+    let error = new_version_text(
+        env.environment(),
+        &apis,
+        "versioned-health",
+        "ADD_GADGETS",
+        Some(&api_file),
+        None,
+    )
+    .unwrap_err();
+    assert!(
+        format!("{error:#}").contains("isn't the latest version"),
+        "{error:#}"
+    );
+
+    // Labels must be SCREAMING_SNAKE_CASE and unused.
+    for label in ["addGadgets", "WITH_METRICS"] {
+        new_version_text(
+            env.environment(),
+            &apis,
+            "versioned-health",
+            label,
+            None,
+            None,
+        )
+        .unwrap_err();
+    }
+
+    Ok(())
+}
+
+/// Test that hooks run before and after `generate` applies fixes, and that
+/// post-generate hooks see the files that changed.
+#[test]
+fn test_generate_hooks() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let pre_events = Arc::clone(&events);
+    let post_events = Arc::clone(&events);
+    env.set_environment(|environment| {
+        environment
+            .with_pre_generate_hook(GenerateHook::new(
+                "record pre",
+                move |cx| {
+                    assert!(cx.changed_paths().is_empty());
+                    pre_events.lock().unwrap().push("pre".to_owned());
+                    Ok(())
+                },
+            ))
+            .with_post_generate_hook(GenerateHook::new(
+                "record post",
+                move |cx| {
+                    for path in cx.changed_paths() {
+                        let path = path.strip_prefix(cx.documents_dir())?;
+                        post_events.lock().unwrap().push(path.to_string());
+                    }
+                    post_events.lock().unwrap().push("post".to_owned());
+                    Ok(())
+                },
+            ))
+    });
+
+    // The first generate creates every document, plus the "latest" symlink.
+    env.generate_documents(&apis)?;
+    let recorded = std::mem::take(&mut *events.lock().unwrap());
+    assert_eq!(recorded.first().map(String::as_str), Some("pre"));
+    assert_eq!(recorded.last().map(String::as_str), Some("post"));
+    let changed = &recorded[1..recorded.len() - 1];
+    assert_eq!(changed.len(), 4, "changed paths: {changed:?}");
+    assert!(
+        changed.contains(
+            &"versioned-health/versioned-health-latest.json".to_owned()
+        ),
+        "changed paths include the \"latest\" symlink: {changed:?}",
+    );
+
+    // With nothing to fix, hooks still run, but nothing changed.
+    env.generate_documents(&apis)?;
+    assert_eq!(*events.lock().unwrap(), ["pre", "post"]);
+
+    // A failing hook fails generate.
+    env.set_environment(|environment| {
+        environment.with_pre_generate_hook(GenerateHook::new("fail", |_| {
+            Err(anyhow::anyhow!("hook failed"))
+        }))
+    });
+    env.generate_documents(&apis).unwrap_err();
+
+    Ok(())
+}
+
+/// Test that output sinks receive the problems found, the fixes applied, and a
+/// summary of each run.
+#[test]
+fn test_output_sink() -> Result<()> {
+    #[derive(Debug)]
+    struct RecordingSink(Arc<Mutex<Vec<String>>>);
+
+    impl OutputSink for RecordingSink {
+        fn event(&self, event: &OutputEvent<'_>) {
+            let recorded = match event {
+                OutputEvent::ProblemFound { api, version, fixable, .. } => {
+                    format!(
+                        "problem {} {} fixable={}",
+                        api,
+                        version.map_or("-".to_owned(), |v| v.to_string()),
+                        fixable,
+                    )
+                }
+                OutputEvent::FixApplied { steps, .. } => {
+                    assert!(!steps.is_empty());
+                    "fixed".to_owned()
+                }
+                OutputEvent::FixFailed { fix, error } => {
+                    format!("fix {fix:?} failed: {error:#}")
+                }
+                OutputEvent::CheckFinished { result } => {
+                    format!("check {result:?}")
+                }
+                OutputEvent::GenerateFinished { success, failed, .. } => {
+                    format!("generate success={success} failed={failed}")
+                }
+                _ => return,
+            };
+            self.0.lock().unwrap().push(recorded);
+        }
+    }
+
+    let mut env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = RecordingSink(Arc::clone(&events));
+    env.set_environment(|environment| environment.with_output_sink(sink));
+    let take = || std::mem::take(&mut *events.lock().unwrap());
+
+    // Every document is missing, which check reports as fixable problems.
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    let recorded = take();
+    assert_eq!(recorded.last().unwrap(), "check NeedsUpdate");
+    for version in ["1.0.0", "2.0.0", "3.0.0"] {
+        let expected =
+            format!("problem versioned-health {version} fixable=true");
+        assert!(recorded.contains(&expected), "recorded: {recorded:?}");
+    }
+
+    // generate reports the same problems, then the fixes it applies.
+    env.generate_documents(&apis)?;
+    let recorded = take();
+    assert_eq!(recorded.last().unwrap(), "generate success=true failed=0");
+    let nproblems =
+        recorded.iter().filter(|e| e.starts_with("problem")).count();
+    let nfixed = recorded.iter().filter(|e| *e == "fixed").count();
+    assert!(nproblems >= 3, "recorded: {recorded:?}");
+    assert!(nfixed >= 3, "recorded: {recorded:?}");
+    assert_eq!(nproblems + nfixed + 1, recorded.len(), "{recorded:?}");
+
+    // Once everything is up-to-date, only the summary is reported.
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    assert_eq!(take(), ["check Success"]);
+
+    Ok(())
+}
+
+/// Test that command hooks are given the changed paths on standard input.
+#[cfg(unix)]
+#[test]
+fn test_generate_hook_command() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.set_environment(|environment| {
+        environment.with_post_generate_hook(GenerateHook::command(
+            "sh",
+            ["-c", "cat > changed-paths.txt"],
+        ))
+    });
+
+    env.generate_documents(&apis)?;
+    let changed = env.read_file("changed-paths.txt")?;
+    let changed: Vec<_> = changed.lines().collect();
+    assert_eq!(changed.len(), 4, "changed paths: {changed:?}");
+    for path in changed {
+        assert!(
+            Utf8Path::new(path).starts_with(env.documents_dir()),
+            "{path} is within the documents directory",
+        );
+    }
+
+    // A command that fails fails generate.
+    env.set_environment(|environment| {
+        environment
+            .with_post_generate_hook(GenerateHook::command("false", [""; 0]))
+    });
+    env.generate_documents(&apis).unwrap_err();
+
+    Ok(())
+}
+
+/// Test that check works in a read-only documents directory, and doesn't
+/// write anything to it.
+///
+/// Permissions don't stop root from writing, so this only shows that check
+/// doesn't write when run as root. That fixes are refused in a read-only
+/// environment is tested in the `resolved` module's unit tests.
+#[cfg(unix)]
+#[test]
+fn test_check_read_only_documents() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Makes `dir` and everything under it (other than symlinks) read-only,
+    /// or writable again.
+    fn set_read_only(dir: &Utf8Path, read_only: bool) -> Result<()> {
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = Utf8PathBuf::try_from(entry.path())?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                set_read_only(&path, read_only)?;
+            } else if file_type.is_file() {
+                let mode = if read_only { 0o444 } else { 0o644 };
+                std::fs::set_permissions(
+                    &path,
+                    PermissionsExt::from_mode(mode),
+                )?;
+            }
+        }
+        let mode = if read_only { 0o555 } else { 0o755 };
+        std::fs::set_permissions(dir, PermissionsExt::from_mode(mode))?;
+        Ok(())
+    }
+
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    // Leave something for check to report, which generate would fix by
+    // writing to the documents directory.
+    env.delete_versioned_latest_symlink("versioned-health")?;
+    let before = env.list_versioned_documents("versioned-health")?;
+
+    let documents_dir = env.documents_dir().to_owned();
+    set_read_only(&documents_dir, true)?;
+    let result = check_apis_up_to_date(env.environment(), &apis);
+    set_read_only(&documents_dir, false)?;
+
+    assert_eq!(result?, CheckResult::NeedsUpdate);
+    assert_eq!(env.list_versioned_documents("versioned-health")?, before);
+
+    Ok(())
+}
+
+/// Test that in strict mode, generate refuses to run while the documents
+/// directory contains untracked files it doesn't recognize.
+#[test]
+fn test_strict_documents_dir() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+    env.set_environment(|e| e.with_strict_documents_dir(true));
+    let apis = versioned_health_apis()?;
+
+    // Newly generated documents are untracked, but recognized.
+    env.generate_documents(&apis)?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    let stray = env.documents_dir().join("versioned-health/notes.txt");
+    atomic_write(&stray, "some notes\n")?;
+    env.generate_documents(&apis)
+        .expect_err("generate should refuse to run with a stray file");
+    assert!(stray.exists(), "stray file should be left alone");
+
+    // The check can be skipped for a single run.
+    env.generate_documents_with_args(&apis, &["--allow-dirty-docs"])?;
+
+    // Allowing the file lets generate proceed.
+    env.set_ignore_globs(&["notes.txt"])?;
+    env.generate_documents(&apis)?;
+    assert!(stray.exists(), "ignored file should be left alone");
+
+    Ok(())
+}
+
+#[test]
+fn test_unstable_operations() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+
+    let apis = versioned_health_unstable_apis(false, true)?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    // Without an unstable tag, changing the unstable operation breaks the
+    // blessed version.
+    let apis = versioned_health_unstable_apis(true, false)?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "1.0.0",
+            ProblemKind::BlessedVersionBroken,
+        )],
+    );
+
+    // With the unstable tag, the change is allowed, and the local document
+    // just needs to be regenerated.
+    let apis = versioned_health_unstable_apis(true, true)?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "1.0.0",
+            ProblemKind::LocalVersionStale,
+        )],
+    );
+
+    env.generate_documents(&apis)?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
+
+/// Test that a latest blessed document that differs from the generated one
+/// only in whitespace is reformatted rather than requiring a new version.
+#[test]
+fn test_latest_version_whitespace_only() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+
+    // Bless a copy of the latest document with different whitespace, as if
+    // it had been written by a tool that formats documents differently.
+    let path = env
+        .find_versioned_document_path("versioned-health", "3.0.0")?
+        .expect("latest document exists");
+    let generated = env.read_file(&path)?;
+    let reformatted =
+        generated.lines().map(str::trim_start).collect::<Vec<_>>().join("\n");
+    env.create_file(&path, &reformatted)?;
+    env.commit_documents()?;
+
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "3.0.0",
+            ProblemKind::BlessedLatestVersionReformatted,
+        )],
+    );
+
+    // Generating reformats the local document, after which it's up-to-date
+    // even though it no longer matches the blessed document bytewise.
+    env.generate_documents(&apis)?;
+    assert_eq!(env.read_file(&path)?, generated);
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &apis)?,
+        CheckResult::Success,
+    );
+
+    Ok(())
+}
+
+/// Test the warning for a new latest document that's much larger than the
+/// latest blessed document.
+#[test]
+fn test_size_growth_threshold() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    env.generate_documents(&versioned_health_reduced_apis()?)?;
+    env.commit_documents()?;
+
+    // v3 adds an endpoint, so its document is larger than v2's. With a
+    // threshold of 0%, any growth is reported, but doesn't fail the check.
+    let apis = versioned_health_size_growth_apis(0)?;
+    env.generate_documents(&apis)?;
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &apis)?,
+        CheckResult::Success,
+    );
+    let text = status_text(env.environment(), &apis)?;
+    assert!(text.contains("larger than blessed version 2.0.0"), "{text}");
+
+    // Within the threshold, nothing is reported.
+    let apis = versioned_health_size_growth_apis(1000)?;
+    let text = status_text(env.environment(), &apis)?;
+    assert!(!text.contains("larger than blessed version"), "{text}");
+
+    // Once the new version is blessed, it's no longer compared.
+    env.commit_documents()?;
+    let apis = versioned_health_size_growth_apis(0)?;
+    let text = status_text(env.environment(), &apis)?;
+    assert!(!text.contains("larger than blessed version"), "{text}");
+
+    Ok(())
+}
+
+/// Test that operations under unstable path prefixes are excluded from
+/// comparisons with blessed documents, and that the exclusions are listed.
+#[test]
+fn test_unstable_path_prefixes() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+
+    let apis = versioned_health_unstable_path_apis(false)?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    // Changing an operation under the prefix doesn't break the blessed
+    // version: the local document just needs to be regenerated.
+    let apis = versioned_health_unstable_path_apis(true)?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "1.0.0",
+            ProblemKind::LocalVersionStale,
+        )],
+    );
+
+    env.generate_documents(&apis)?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // The excluded operations are listed.
+    let text = status_text(env.environment(), &apis)?;
+    assert!(
+        text.contains(
+            "excluded from comparisons with blessed documents: GET /metrics"
+        ),
+        "{text}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_profile_documents() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_with_profile_apis()?;
+
+    env.generate_documents(&apis)?;
+    for version in ["1.0.0", "2.0.0", "3.0.0"] {
+        assert!(
+            env.versioned_local_document_exists("versioned-health", version)?
+        );
+        assert!(env.versioned_local_document_exists(
+            "versioned-health-minimal",
+            version
+        )?);
+    }
+
+    // The variant document only has the selected operation.
+    let full = env.read_versioned_latest_document("versioned-health")?;
+    let minimal =
+        env.read_versioned_latest_document("versioned-health-minimal")?;
+    let full: OpenAPI = serde_json::from_str(&full)?;
+    let minimal: OpenAPI = serde_json::from_str(&minimal)?;
+    assert_eq!(
+        full.paths.paths.keys().collect::<Vec<_>>(),
+        ["/health", "/health/detailed", "/metrics"],
+    );
+    assert_eq!(minimal.paths.paths.keys().collect::<Vec<_>>(), ["/health"]);
+
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Once committed, the variant has its own blessed lineage.
+    env.commit_documents()?;
+    for version in ["1.0.0", "2.0.0", "3.0.0"] {
+        assert!(env.versioned_local_and_blessed_document_exists(
+            "versioned-health-minimal",
+            version
+        )?);
+    }
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
+
+#[test]
+fn test_slim_document() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_unstable_apis(false, false)?;
+    env.generate_documents(&apis)?;
+
+    let version = Version::new(1, 0, 0);
+    let contents = slim_document(
+        env.environment(),
+        &apis,
+        "versioned-health",
+        &version,
+        &["unstable"],
+    )?;
+    let slim: OpenAPI = serde_json::from_slice(&contents)?;
+    assert_eq!(slim.paths.paths.keys().collect::<Vec<_>>(), ["/metrics"]);
+
+    // Only schemas referred to by the remaining operations (including
+    // Dropshot's error response) are kept.
+    let schemas = &slim.components.as_ref().unwrap().schemas;
+    assert_eq!(schemas.keys().collect::<Vec<_>>(), ["Error", "ServiceMetrics"]);
+
+    // Asking for tags that no operations have is an error.
+    let error = slim_document(
+        env.environment(),
+        &apis,
+        "versioned-health",
+        &version,
+        &["nonexistent"],
+    )
+    .unwrap_err();
+    assert!(
+        error.to_string().contains("no operations"),
+        "unexpected error: {error}",
+    );
+
+    // So is asking for a version that doesn't exist locally.
+    let error = slim_document(
+        env.environment(),
+        &apis,
+        "versioned-health",
+        &Version::new(2, 0, 0),
+        &["unstable"],
+    )
+    .unwrap_err();
+    assert!(
+        error.to_string().contains("no local document"),
+        "unexpected error: {error}",
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_reverse_compatibility_check() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    env.generate_documents(&versioned_health_v1_only_apis()?)?;
+    env.commit_documents()?;
+
+    // Adding an endpoint to the blessed version is compatible for existing
+    // clients, so only the bytewise check for the latest version fails.
+    let apis = versioned_health_v1_with_metrics_apis(false)?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "1.0.0",
+            ProblemKind::BlessedLatestVersionBytewiseMismatch,
+        )],
+    );
+
+    // But clients built against the current code would call an endpoint that
+    // servers running the blessed version don't have.
+    let apis = versioned_health_v1_with_metrics_apis(true)?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "1.0.0",
+            ProblemKind::BlessedVersionReverseBroken,
+        )],
+    );
+
+    // Unchanged blessed versions pass the reverse check.
+    let result = check_apis_up_to_date(
+        env.environment(),
+        &ManagedApis::new(vec![
+            ManagedApi::from(versioned_health_v1_only_api())
+                .with_reverse_compatibility_check(),
+        ])?,
+    )?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
+
+#[test]
+fn test_latest_pointer() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    env.generate_documents(&versioned_health_apis()?)?;
+    env.commit_documents()?;
+
+    let symlink_path =
+        "documents/versioned-health/versioned-health-latest.json";
+    let pointer_path = "documents/versioned-health/versioned-health-latest.txt";
+    let latest = env.read_link(symlink_path)?;
+
+    // With no pointer, the existing symlink is unwanted.
+    let apis = versioned_health_with_latest_pointer_apis(LatestPointer::None)?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::for_api(
+            "versioned-health",
+            ProblemKind::LatestLinkUnwanted,
+        )],
+    );
+
+    env.generate_documents(&apis)?;
+    assert!(!env.file_exists(symlink_path), "symlink should be removed");
+    assert!(!env.file_exists(pointer_path));
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // With a pointer file, the file is written in place of the symlink.
+    let apis =
+        versioned_health_with_latest_pointer_apis(LatestPointer::PointerFile)?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::for_api(
+            "versioned-health",
+            ProblemKind::LatestPointerFileStale,
+        )],
+    );
+
+    env.generate_documents(&apis)?;
+    assert_eq!(env.read_file(pointer_path)?, format!("{latest}\n"));
+    assert!(!env.file_exists(symlink_path));
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Switching back to the default restores the symlink. The pointer file
+    // isn't managed in that mode, so remove it by hand.
+    std::fs::remove_file(env.workspace_root().join(pointer_path))?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    assert_eq!(env.read_link(symlink_path)?, latest);
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
+
+#[test]
+fn test_latest_link_dangling() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    // Corrupt the document the "latest" symlink points to. The symlink's name
+    // is still correct, but it no longer leads to a usable document.
+    let v3_path = env
+        .find_versioned_document_path("versioned-health", "3.0.0")?
+        .expect("v3 document should exist");
+    env.create_file(&v3_path, "<<<<<<< HEAD\n")?;
+
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert!(
+        summaries.contains(&ProblemSummary::for_api(
+            "versioned-health",
+            ProblemKind::LatestLinkDangling,
+        )),
+        "expected a dangling symlink, found: {summaries:?}"
+    );
+
+    env.generate_documents(&apis)?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    assert!(env.read_versioned_latest_document("versioned-health").is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn test_latest_link_temp_leftover() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    let original_target = env
+        .read_link("documents/versioned-health/versioned-health-latest.json")?;
+
+    // Simulate a crash while updating the "latest" symlink: the new link was
+    // created under its temporary name, but never renamed into place.
+    let temp_path = env
+        .documents_dir()
+        .join("versioned-health/versioned-health-latest.json.tmp");
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(original_target.as_str(), &temp_path)
+        .context("failed to create temporary symlink")?;
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_file(original_target.as_str(), &temp_path)
+        .context("failed to create temporary symlink")?;
+
+    // The leftover link is reported by itself, rather than as an unexpected
+    // file.
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        vec![ProblemSummary::for_api(
+            "versioned-health",
+            ProblemKind::LatestLinkTempLeftover,
+        )],
+    );
+
+    // Generating removes it, and leaves the "latest" symlink alone.
+    env.generate_documents(&apis)?;
+    assert!(!temp_path.is_symlink());
+    assert_eq!(
+        env.read_link(
+            "documents/versioned-health/versioned-health-latest.json"
+        )?,
+        original_target,
+    );
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
+
+#[test]
+fn test_document_signing() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+
+    // Create a signing key, and trust it to sign documents.
+    std::fs::create_dir_all(env.workspace_root().join("keys"))?;
+    let key_path = env.workspace_root().join("keys/signing-key");
+    let status = std::process::Command::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-C", "test", "-f"])
+        .arg(&key_path)
+        .status()
+        .context("failed to run ssh-keygen")?;
+    assert!(status.success(), "ssh-keygen failed: {status}");
+    let public_key = env.read_file("keys/signing-key.pub")?;
+    env.create_file(
+        "keys/allowed_signers",
+        &format!("docs@example.com {}", public_key),
+    )?;
+
+    let verify_only = DocumentSigning::new(
+        "signatures",
+        "keys/allowed_signers",
+        "docs@example.com",
+    );
+    let signing = verify_only.clone().with_private_key("keys/signing-key");
+
+    // Documents are signed as they're generated.
+    let apis = versioned_health_signed_apis(signing)?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+    let result = check_apis_up_to_date(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Checking signatures doesn't require the private key.
+    let verify_apis = versioned_health_signed_apis(verify_only)?;
+    let result = check_apis_up_to_date(env.environment(), &verify_apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // Swapping signatures around is detected, and can't be fixed
+    // automatically.
+    let signature_path = |version| -> Result<Utf8PathBuf> {
+        let document = env
+            .find_versioned_document_path("versioned-health", version)?
+            .expect("document exists");
+        Ok(Utf8PathBuf::from("signatures/versioned-health").join(format!(
+            "{}.sig",
+            document.file_name().expect("document has a file name"),
+        )))
+    };
+    let v2_signature = env.read_file(signature_path("2.0.0")?)?;
+    env.create_file(signature_path("1.0.0")?, &v2_signature)?;
+
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &verify_apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "1.0.0",
+            ProblemKind::BlessedSignatureInvalid,
+        )],
+    );
+
+    // A missing signature can be recreated.
+    std::fs::remove_file(env.workspace_root().join(signature_path("1.0.0")?))?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "1.0.0",
+            ProblemKind::DocumentSignatureMissing,
+        )],
+    );
+    env.generate_documents(&apis)?;
+    let result = check_apis_up_to_date(env.environment(), &verify_apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    Ok(())
+}
+
+#[test]
+fn test_generate_provenance() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    env.generate_documents_with_args(
+        &apis,
+        &["--provenance", "provenance.intoto.json"],
+    )?;
+    let statement: serde_json::Value =
+        serde_json::from_str(&env.read_file("provenance.intoto.json")?)?;
+    assert_eq!(statement["_type"], "https://in-toto.io/Statement/v1");
+    assert_eq!(statement["predicateType"], "https://slsa.dev/provenance/v1");
+
+    // Every document is a subject.
+    let mut names: Vec<_> = statement["subject"]
+        .as_array()
+        .expect("subjects are an array")
+        .iter()
+        .map(|subject| {
+            let digest = subject["digest"]["sha256"].as_str().unwrap();
+            assert_eq!(digest.len(), 64, "unexpected digest {digest:?}");
+            subject["name"].as_str().unwrap().to_owned()
+        })
+        .collect();
+    names.sort();
+    let mut expected: Vec<_> = env
+        .list_document_files()?
+        .into_iter()
+        .filter(|path| !path.as_str().ends_with("-latest.json"))
+        .map(|path| path.to_string())
+        .collect();
+    expected.sort();
+    assert_eq!(names, expected);
+
+    // The source commit is recorded.
+    let dependencies =
+        &statement["predicate"]["buildDefinition"]["resolvedDependencies"];
+    assert_eq!(
+        dependencies[0]["digest"]["gitCommit"],
+        env.get_current_commit_hash()?,
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_version_contract() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    env.generate_documents(&versioned_health_v1_only_apis()?)?;
+    env.commit_documents()?;
+
+    // The primary description has diverged from the blessed v1 document, but
+    // a contract covering v1 still produces it.
+    let v1 = Version::new(1, 0, 0);
+    let apis = versioned_health_v1_contract_apis(..=v1.clone())?;
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &apis)?,
+        CheckResult::Success,
+    );
+
+    // A contract that doesn't cover v1 isn't used for it.
+    let apis = versioned_health_v1_contract_apis(Version::new(2, 0, 0)..)?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        [ProblemSummary::new(
+            "versioned-health",
+            "1.0.0",
+            ProblemKind::BlessedLatestVersionBytewiseMismatch,
+        )],
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_compat_preview() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    // The latest document is a superset of every earlier version.
+    let v3_path = env
+        .find_versioned_document_path("versioned-health", "3.0.0")?
+        .expect("v3 document exists");
+    let broken = compat_preview_broken_versions(
+        env.environment(),
+        &apis,
+        "versioned-health",
+        &env.workspace_root().join(&v3_path),
+    )?;
+    assert_eq!(broken, Vec::<Version>::new());
+
+    // A candidate without `/metrics` would break v3, but not earlier versions.
+    let v2_path = env
+        .find_versioned_document_path("versioned-health", "2.0.0")?
+        .expect("v2 document exists");
+    env.create_file("candidate.json", &env.read_file(&v2_path)?)?;
+    let broken = compat_preview_broken_versions(
+        env.environment(),
+        &apis,
+        "versioned-health",
+        &env.workspace_root().join("candidate.json"),
+    )?;
+    assert_eq!(broken, [Version::new(3, 0, 0)]);
+
+    // Candidates must be valid OpenAPI documents.
+    env.create_file("candidate.json", "{}")?;
+    let error = compat_preview_broken_versions(
+        env.environment(),
+        &apis,
+        "versioned-health",
+        &env.workspace_root().join("candidate.json"),
+    )
+    .unwrap_err();
+    assert!(
+        error.to_string().contains("not a valid OpenAPI document"),
+        "unexpected error: {error}",
+    );
+
+    Ok(())
+}
+
+/// Test the policies for files belonging to APIs that aren't managed, e.g.
+/// after an API is removed.
+#[test]
+fn test_unknown_api_policy() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+    let health_apis = versioned_health_apis()?;
+    env.generate_documents(&health_apis)?;
+    env.commit_documents()?;
+
+    // Documents for an API that was never blessed.
+    env.generate_documents(&multi_versioned_apis()?)?;
+    let user_dir = env.workspace_root().join("documents/versioned-user");
+    assert!(user_dir.exists(), "versioned-user documents were generated");
+
+    // By default, the unknown API is an error.
+    let error = check_apis_with_summaries(env.environment(), &health_apis)
+        .expect_err("unknown API files are an error by default");
+    assert!(
+        format!("{error:#}").contains("versioned-user"),
+        "error mentions the unknown API: {error:#}",
+    );
+
+    // With the warn policy, the files are skipped with a warning.
+    env.set_environment(|e| e.with_unknown_api_policy(UnknownApiPolicy::Warn));
+    let (result, load_problems) =
+        check_apis_with_load_problems(env.environment(), &health_apis)?;
+    assert_eq!(result, CheckResult::Success);
+    let warnings: Vec<_> = load_problems
+        .iter()
+        .map(|(severity, error)| (severity, format!("{error:#}")))
+        .collect();
+    assert!(
+        warnings.iter().any(|(severity, message)| {
+            *severity == LoadProblemSeverity::Warning
+                && message.contains("unknown API versioned-user")
+        }),
+        "expected a warning for the unknown API: {warnings:?}",
+    );
+
+    // With the delete policy, generate deletes the files.
+    env.set_environment(|e| {
+        e.with_unknown_api_policy(UnknownApiPolicy::Delete)
+    });
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &health_apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        vec![ProblemSummary::for_api(
+            "versioned-user",
+            ProblemKind::UnknownApiFiles,
+        )],
+    );
+    env.generate_documents(&health_apis)?;
+    assert!(!user_dir.exists(), "versioned-user documents were deleted");
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &health_apis)?,
+        CheckResult::Success,
+    );
+
+    // Files for an API that's blessed are never deleted automatically.
+    env.generate_documents(&multi_versioned_apis()?)?;
+    env.commit_documents()?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &health_apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(
+        summaries,
+        vec![ProblemSummary::for_api(
+            "versioned-user",
+            ProblemKind::UnknownApiFilesBlessed,
+        )],
+    );
+    assert!(
+        env.generate_documents(&health_apis).is_err(),
+        "generate fails with an unfixable problem",
+    );
+    assert!(user_dir.exists(), "blessed documents were not deleted");
+
+    Ok(())
+}
+
+/// Test that documents for APIs removed on purpose are deleted, whatever the
+/// unknown API policy.
+#[test]
+fn test_removed_apis() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    env.generate_documents(&multi_versioned_apis()?)?;
+    env.commit_documents()?;
+    let user_dir = env.workspace_root().join("documents/versioned-user");
+
+    let apis = versioned_health_apis()?.with_removed_apis(["versioned-user"]);
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        vec![ProblemSummary::for_api(
+            "versioned-user",
+            ProblemKind::RemovedApiFiles,
+        )],
+    );
+
+    env.generate_documents(&apis)?;
+    assert!(!user_dir.exists(), "versioned-user documents were deleted");
+
+    // The blessed documents for the removed API don't produce warnings.
+    let (result, load_problems) =
+        check_apis_with_load_problems(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    assert!(load_problems.is_empty(), "no load problems: {load_problems:?}");
+
+    Ok(())
+}
+
+/// Test that the document combining all supported versions is written by
+/// generate and verified by check.
+#[test]
+fn test_combined_document() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_combined_apis()?;
+    env.generate_documents(&apis)?;
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &apis)?,
+        CheckResult::Success,
+    );
+
+    let combined_path = "documents/versioned-health/versioned-health-all.json";
+    let combined: serde_json::Value =
+        serde_json::from_str(&env.read_file(combined_path)?)?;
+    let paths = combined["paths"].as_object().context("paths")?;
+    for version in ["1.0.0", "2.0.0", "3.0.0"] {
+        let prefix = format!("/v{version}/");
+        let (_, item) = paths
+            .iter()
+            .find(|(path, _)| path.starts_with(&prefix))
+            .with_context(|| format!("no paths for v{version}"))?;
+        let (_, operation) = item
+            .as_object()
+            .context("path item")?
+            .iter()
+            .find(|(_, op)| op.get("operationId").is_some())
+            .context("no operations")?;
+        assert_eq!(operation["x-version"], version);
+        let operation_id = operation["operationId"].as_str().unwrap();
+        let id_prefix = format!("v{}_", version.replace('.', "_"));
+        assert!(
+            operation_id.starts_with(&id_prefix),
+            "operation ID {operation_id} starts with {id_prefix}",
+        );
+    }
+
+    // A stale combined document is rewritten.
+    env.create_file(combined_path, "{}\n")?;
+    let (result, summaries) =
+        check_apis_with_summaries(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(
+        summaries,
+        vec![ProblemSummary::for_api(
+            "versioned-health",
+            ProblemKind::CombinedDocumentStale,
+        )],
+    );
+    env.generate_documents(&apis)?;
+    assert_eq!(
+        check_apis_up_to_date(env.environment(), &apis)?,
+        CheckResult::Success,
+    );
+
+    Ok(())
+}
+
+/// Test patches from the latest blessed version to the generated document.
+#[test]
+fn test_blessed_latest_patch() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    // Without changes, the patches are empty.
+    assert_eq!(
+        blessed_latest_patch(
+            env.environment(),
+            &apis,
+            "versioned-health",
+            false
+        )?,
+        serde_json::json!([]),
+    );
+    assert_eq!(
+        blessed_latest_patch(
+            env.environment(),
+            &apis,
+            "versioned-health",
+            true
+        )?,
+        serde_json::json!({}),
+    );
+
+    let trivial_apis = versioned_health_trivial_change_apis()?;
+    let patch = blessed_latest_patch(
+        env.environment(),
+        &trivial_apis,
+        "versioned-health",
+        false,
+    )?;
+    let ops = patch.as_array().context("patch is an array")?;
+    assert!(
+        ops.contains(&serde_json::json!({
+            "op": "replace",
+            "path": "/info/title",
+            "value": "Modified Versioned Health API",
+        })),
+        "patch replaces the title: {patch:#}",
+    );
+    assert!(
+        ops.iter().all(|op| op["path"].as_str().unwrap().starts_with("/info/")),
+        "only the info section changed: {patch:#}",
+    );
+
+    let merge_patch = blessed_latest_patch(
+        env.environment(),
+        &trivial_apis,
+        "versioned-health",
+        true,
+    )?;
+    assert_eq!(
+        merge_patch,
+        serde_json::json!({
+            "info": {
+                "title": "Modified Versioned Health API",
+                "description": "A versioned health API with breaking changes",
+            },
+        }),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_extra_validation_with_extra_file() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_with_extra_file_apis()?;
+
+    env.generate_documents(&apis)?;
+
+    let calls = get_validation_calls();
+
+    assert_eq!(
+        calls.len(),
+        6,
+        "validation should be called twice for each of the 3 versions"
+    );
+
+    let latest_file = env
+        .workspace_root()
+        .join("documents")
+        .join("versioned-health")
+        .join("latest-3.0.0.txt");
+    assert!(
+        latest_file.exists(),
+        "marker file should be generated for latest version"
+    );
+
+    let content = std::fs::read_to_string(&latest_file)
+        .context("failed to read marker file")?;
+    assert_eq!(content, "This is the latest version: 3.0.0");
+
+    let v1_file = env
+        .workspace_root()
+        .join("documents")
+        .join("versioned-health")
+        .join("latest-1.0.0.txt");
+    let v2_file = env
+        .workspace_root()
+        .join("documents")
+        .join("versioned-health")
+        .join("latest-2.0.0.txt");
+    assert!(!v1_file.exists(), "marker file should not exist for v1.0.0");
+    assert!(!v2_file.exists(), "marker file should not exist for v2.0.0");
+
+    // Commit v3.0.0 to make it blessed (while being the latest version).
+    let v3_doc = env
+        .find_versioned_document_path("versioned-health", "3.0.0")?
         .expect("v3 document should exist");
     env.git_add(&[&v3_doc])?;
     env.git_commit("Add v3.0.0")?;
@@ -1507,16 +4803,23 @@ fn test_blessed_version_missing_local_is_fixable() -> Result<()> {
         std::fs::remove_file(env.workspace_root().join(&v3_blessed_path))
             .context("failed to delete blessed v3 file")?;
 
+        // The "latest" symlink now dangles as well.
         let (result, summaries) =
             check_apis_with_summaries(env.environment(), &v3_apis)?;
         assert_eq!(result, CheckResult::NeedsUpdate);
         assert_eq!(
             summaries,
-            [ProblemSummary::new(
-                "versioned-health",
-                "3.0.0",
-                ProblemKind::BlessedVersionMissingLocal,
-            )],
+            [
+                ProblemSummary::new(
+                    "versioned-health",
+                    "3.0.0",
+                    ProblemKind::BlessedVersionMissingLocal,
+                ),
+                ProblemSummary::for_api(
+                    "versioned-health",
+                    ProblemKind::LatestLinkDangling,
+                ),
+            ],
         );
 
         env.generate_documents(&v3_apis)?;
@@ -1591,6 +4894,60 @@ fn test_blessed_version_missing_local_is_fixable() -> Result<()> {
     Ok(())
 }
 
+/// Test the JSON document printed by `check --format json`.
+#[test]
+fn test_check_json_output() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+
+    let (result, output) =
+        check_apis_with_json_output(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::NeedsUpdate);
+    assert_eq!(output["schema_version"], 1);
+    assert_eq!(output["result"], "needs_update");
+    assert_eq!(output["warnings"], serde_json::json!([]));
+    let problems = output["problems"].as_array().expect("problems is a list");
+    assert_eq!(problems.len(), 4);
+    for problem in problems {
+        assert_eq!(problem["api"], "versioned-health");
+        assert!(problem["message"].is_string(), "problem has a message");
+        let fix = problem["fix"].as_array().expect("problem is fixable");
+        assert!(!fix.is_empty(), "fix has steps");
+        match problem["kind"].as_str() {
+            Some("LocalVersionMissingLocal") => {
+                assert!(problem["version"].is_string(), "version is recorded")
+            }
+            Some("LatestLinkMissing") => {
+                assert!(problem["version"].is_null(), "not version-specific")
+            }
+            kind => panic!("unexpected problem kind {kind:?}"),
+        }
+    }
+
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+    let (result, output) =
+        check_apis_with_json_output(env.environment(), &apis)?;
+    assert_eq!(result, CheckResult::Success);
+    assert_eq!(output["result"], "success");
+    assert_eq!(output["problems"], serde_json::json!([]));
+
+    // An incompatible change to a blessed version can't be fixed by
+    // generate.
+    let incompatible_apis = versioned_health_incompat_apis()?;
+    let (result, output) =
+        check_apis_with_json_output(env.environment(), &incompatible_apis)?;
+    assert_eq!(result, CheckResult::Failures);
+    assert_eq!(output["result"], "failures");
+    let problems = output["problems"].as_array().expect("problems is a list");
+    assert!(
+        problems.iter().any(|problem| problem["fix"].is_null()),
+        "some problem isn't fixable: {problems:?}",
+    );
+
+    Ok(())
+}
+
 /// Shared setup for the dependent-PR rebase/merge tests.
 ///
 /// Creates this branch structure:
@@ -1931,3 +5288,304 @@ fn test_blessed_ws_old_format_passes_with_trivial_changes_allowed() -> Result<()
 
     Ok(())
 }
+
+/// Test that a sparse checkout excluding the documents directory produces a
+/// single error rather than a wall of missing-file problems.
+#[test]
+fn test_sparse_checkout_excluding_documents() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    env.sparse_checkout_exclude_documents()?;
+    assert!(
+        !env.versioned_local_document_exists("versioned-health", "1.0.0")?,
+        "documents should be excluded from the working tree"
+    );
+
+    // Check should fail early.
+    let result = check_apis_up_to_date(env.environment(), &apis);
+    result.expect_err("check should fail in a sparse checkout");
+
+    Ok(())
+}
+
+/// Test that `check --base` checks the merge of HEAD with the base revision,
+/// catching failures that only show up in a merge queue.
+#[test]
+fn test_check_base_merge() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+    let v1_v2_apis = versioned_health_reduced_apis()?;
+    env.generate_documents(&v1_v2_apis)?;
+    env.commit_documents()?;
+    env.create_branch("feature")?;
+
+    // Meanwhile, main adds v3.
+    let v1_v2_v3_apis = versioned_health_apis()?;
+    env.generate_documents(&v1_v2_v3_apis)?;
+    env.commit_documents()?;
+
+    // The feature branch (which doesn't know about v3) is up-to-date on its
+    // own.
+    env.checkout_branch("feature")?;
+    env.make_unrelated_commit("feature work")?;
+    let result = check_apis_up_to_date(env.environment(), &v1_v2_apis)?;
+    assert_eq!(result, CheckResult::Success);
+
+    // But merging it with main brings in the blessed v3 document, which the
+    // feature branch's API definitions don't include.
+    let exit_code =
+        env.check_documents_with_args(&v1_v2_apis, &["--base", "main"])?;
+    assert_eq!(exit_code, ExitCode::FAILURE);
+
+    // With the API definitions from main, the merge is fine.
+    let exit_code =
+        env.check_documents_with_args(&v1_v2_v3_apis, &["--base", "main"])?;
+    assert_eq!(exit_code, ExitCode::SUCCESS);
+
+    // The working copy wasn't touched, and the temporary worktree was
+    // removed.
+    assert!(
+        env.find_versioned_document_path("versioned-health", "3.0.0")?
+            .is_none(),
+        "v3 document should not be present in the working copy"
+    );
+    assert_eq!(env.list_git_worktrees()?.len(), 1);
+
+    Ok(())
+}
+
+/// Test that `status` describes each version in prose.
+#[test]
+fn test_status_describes_versions() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+
+    // Bless v1 and v2.
+    let reduced_apis = versioned_health_reduced_apis()?;
+    env.generate_documents(&reduced_apis)?;
+    env.commit_documents()?;
+
+    // Add v3, which hasn't been generated yet.
+    let apis = versioned_health_apis()?;
+    let status = status_text(env.environment(), &apis)?;
+    assert!(
+        status.contains("versioned-health (versioned)"),
+        "status output:\n{status}"
+    );
+    assert!(
+        status.contains("v1.0.0: is blessed and unchanged"),
+        "status output:\n{status}"
+    );
+    assert!(
+        status.contains(
+            "v3.0.0: is new locally, but the local files are out of date"
+        ),
+        "status output:\n{status}"
+    );
+    assert!(
+        status.contains("to bring everything up-to-date."),
+        "status output:\n{status}"
+    );
+
+    // After generating, v3 is new locally with no problems.
+    env.generate_documents(&apis)?;
+    let status = status_text(env.environment(), &apis)?;
+    assert!(
+        status.contains(
+            "v3.0.0: is new locally, and will become blessed once this \
+             change is merged upstream"
+        ),
+        "status output:\n{status}"
+    );
+    assert!(
+        status.contains("Everything is up-to-date."),
+        "status output:\n{status}"
+    );
+
+    Ok(())
+}
+
+/// Test that the provenance of blessed documents (the branch and merge base
+/// they were loaded from) is recorded.
+#[test]
+fn test_blessed_provenance() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+    let base_commit = env.get_current_commit_hash()?;
+
+    // Move to a branch with its own commit, so that the merge base differs
+    // from HEAD.
+    env.create_branch("feature")?;
+    env.checkout_branch("feature")?;
+    env.make_unrelated_commit("unrelated change")?;
+    assert_ne!(env.get_current_commit_hash()?, base_commit);
+
+    let provenance = blessed_provenance(env.environment(), &apis)?
+        .expect("blessed documents loaded from VCS have a provenance");
+    assert_eq!(
+        provenance.revision.as_deref().map(|r| r.as_str()),
+        Some("main"),
+    );
+    assert_eq!(provenance.merge_base.to_string(), base_commit);
+    assert_eq!(provenance.directory, "documents");
+
+    Ok(())
+}
+
+/// Test that `--no-blessed` treats the working tree as authoritative,
+/// skipping checks against blessed documents.
+#[test]
+fn test_no_blessed() -> Result<()> {
+    let env = TestEnvironment::new_git()?;
+    let apis = versioned_health_apis()?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+
+    // A trivial change to the latest blessed version normally requires a
+    // version bump.
+    let modified_apis = versioned_health_trivial_change_apis()?;
+    let result = check_apis_up_to_date(env.environment(), &modified_apis)?;
+    assert_eq!(result, CheckResult::Failures);
+
+    // With --no-blessed, the local documents only need to match the generated
+    // ones, so generate and check both succeed.
+    let generate = App::try_parse_from(["bin", "generate", "--no-blessed"])?;
+    assert_eq!(
+        generate.exec(env.environment(), &modified_apis),
+        ExitCode::SUCCESS
+    );
+    assert_eq!(
+        env.check_documents_with_args(&modified_apis, &["--no-blessed"])?,
+        ExitCode::SUCCESS,
+    );
+
+    // Without --no-blessed, the change is still rejected.
+    let result = check_apis_up_to_date(env.environment(), &modified_apis)?;
+    assert_eq!(result, CheckResult::Failures);
+
+    Ok(())
+}
+
+/// Test that `--no-blessed` allows versioned APIs outside version control.
+#[test]
+fn test_no_blessed_no_git() -> Result<()> {
+    let temp_dir = Utf8TempDir::with_prefix("dropshot-api-manager-no-git-")?;
+    std::fs::create_dir(temp_dir.path().join("documents"))?;
+    let env = Environment::new_no_git(
+        "test-openapi-manager",
+        temp_dir.path(),
+        "documents",
+    )?;
+    let apis = versioned_health_apis()?;
+
+    let generate = App::try_parse_from(["bin", "generate", "--no-blessed"])?;
+    assert_eq!(generate.exec(&env, &apis), ExitCode::SUCCESS);
+    let check = App::try_parse_from(["bin", "check", "--no-blessed"])?;
+    assert_eq!(check.exec(&env, &apis), ExitCode::SUCCESS);
+
+    Ok(())
+}
+
+/// Test that `--also-blessed-from` treats versions added on the base of a
+/// stack of changes as blessed.
+#[test]
+fn test_also_blessed_from_stack_base() -> Result<()> {
+    let mut env = TestEnvironment::new_git()?;
+
+    // Bless v1 and v2 on main.
+    env.generate_documents(&versioned_health_reduced_apis()?)?;
+    env.commit_documents()?;
+
+    // Add v3 on an unmerged branch, then stack another branch on top of it.
+    let apis = versioned_health_apis()?;
+    env.create_branch("stack-a")?;
+    env.checkout_branch("stack-a")?;
+    env.generate_documents(&apis)?;
+    env.commit_documents()?;
+    env.create_branch("stack-b")?;
+    env.checkout_branch("stack-b")?;
+
+    let stack_args = ["--also-blessed-from", "stack-a"];
+    assert_eq!(
+        env.check_documents_with_args(&apis, &stack_args)?,
+        ExitCode::SUCCESS,
+    );
+
+    // Without the stack base, a trivial change to v3 is treated as a local
+    // change to a new version, so it's simply regenerated.
+    let modified_apis = versioned_health_trivial_change_apis()?;
+    assert_eq!(
+        env.check_documents_with_args(&modified_apis, &[])?,
+        ExitCode::from(NEEDS_UPDATE_EXIT_CODE),
+    );
+
+    // With the stack base, v3 is blessed, so the same change requires a
+    // version bump.
+    assert_eq!(
+        env.check_documents_with_args(&modified_apis, &stack_args)?,
+        ExitCode::from(FAILURE_EXIT_CODE),
+    );
+
+    Ok(())
+}
+
+/// Test that an API's supported versions must follow its version scheme.
+#[test]
+fn test_version_scheme() -> Result<()> {
+    // The fixture's versions are sequential.
+    ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api())
+            .with_version_scheme(VersionScheme::Sequential),
+    ])?;
+
+    let error = ManagedApis::new(vec![
+        ManagedApi::from(versioned_health_api())
+            .with_version_scheme(VersionScheme::DateEncoded),
+    ])
+    .expect_err("sequential versions aren't date-encoded");
+    let message = format!("{error:#}");
+    assert!(
+        message.contains("must use date-encoded versions")
+            && message.contains("version 1.0.0 is not date-encoded"),
+        "unexpected error: {message}",
+    );
+
+    Ok(())
+}
+
+/// Test that supported version labels must match the configured pattern.
+#[test]
+fn test_version_label_pattern() -> Result<()> {
+    let apis_with = |pattern: &str| {
+        ManagedApis::new(vec![
+            ManagedApi::from(versioned_health_api())
+                .with_version_label_pattern(pattern),
+        ])
+    };
+
+    apis_with("[A-Z][A-Z0-9]*(_[A-Z0-9]+)*")?;
+
+    // The whole label must match, not just part of it.
+    let error = apis_with("[A-Z]+").expect_err("labels have underscores");
+    let message = format!("{error:#}");
+    assert!(
+        message.contains(
+            "version 2.0.0: label \"WITH_DETAILED_STATUS\" doesn't match"
+        ),
+        "unexpected error: {message}",
+    );
+
+    let error = apis_with("(").expect_err("pattern is invalid");
+    let message = format!("{error:#}");
+    assert!(
+        message.contains("invalid version label pattern"),
+        "unexpected error: {message}",
+    );
+
+    Ok(())
+}